@@ -55,12 +55,17 @@
             "Cargo.lock"
             ".cargo"
             "fmo_api_types"
+            "fmo_client"
             "fmo_server"
             "fmo_frontend"
             "tailwind.config.js"
+            "Trunk.toml"
           ];
         };
 
+        # Embedded into server and frontend so the frontend can detect when it is outdated
+        gitRev = self.rev or self.dirtyRev or "unknown";
+
         nativePackages =
           (flakeboxLib.craneMultiBuild { toolchains = { default = toolchains; }; }) (craneLib':
             let
@@ -70,6 +75,7 @@
                 src = rustSrc;
                 cargoExtraArgs = "--package=fmo_server";
                 RUSTFLAGS = "--cfg tokio_unstable";
+                FMO_GIT_REV = gitRev;
               });
             in
             rec {
@@ -80,11 +86,21 @@
               fmo_server = craneLib.buildPackage { };
               fmo_server_image = pkgs.dockerTools.buildLayeredImage {
                 name = "fmo_server";
-                contents = [ fmo_server pkgs.bash pkgs.coreutils ];
+                contents = [ fmo_server pkgs.bash pkgs.coreutils pkgs.curl pkgs.dejavu_fonts ];
                 config = {
                   Cmd = [
                     "${fmo_server}/bin/fmo_server"
                   ];
+                  # Fonts used to render federation share cards
+                  Env = [ "FO_CARD_FONTS_DIR=${pkgs.dejavu_fonts}/share/fonts" ];
+                  # Only checks liveness, so long migrations on startup don't get the container restarted. Load
+                  # balancers should route traffic based on /ready instead.
+                  Healthcheck = {
+                    Test = [ "CMD-SHELL" "curl -fsS http://\${FO_BIND:-127.0.0.1:3000}/live || exit 1" ];
+                    Interval = 30000000000;
+                    Timeout = 5000000000;
+                    Retries = 3;
+                  };
                 };
               };
 
@@ -107,6 +123,7 @@
               # Specify the wasm32 target
               CARGO_BUILD_TARGET = "wasm32-unknown-unknown";
               RUSTFLAGS = "--cfg=web_sys_unstable_apis";
+              FMO_GIT_REV = gitRev;
             };
 
             cargoArtifactsWasm = craneLib.buildDepsOnly (wasmArgs // {
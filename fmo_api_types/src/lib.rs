@@ -1,53 +1,414 @@
+use std::collections::BTreeMap;
+
 use bitcoin::address::NetworkUnchecked;
+use chrono::{DateTime, NaiveDate, Utc};
 use fedimint_core::config::FederationId;
-use fedimint_core::Amount;
+use fedimint_core::invite_code::InviteCode;
+use fedimint_core::secp256k1::PublicKey;
+use fedimint_core::{Amount, PeerId, TransactionId};
 use serde::{Deserialize, Serialize};
 
+pub mod rating;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FedimintTotals {
     pub federations: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
     pub tx_volume: Amount,
     pub tx_count: u64,
+    /// Assets of the federations in `federations_at_risk`
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub assets_at_risk: Amount,
+    /// Federations whose online guardians are currently below the signing
+    /// threshold, so their users can't transact
+    #[serde(default)]
+    pub federations_at_risk: Vec<FederationAtRisk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationAtRisk {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    pub name: Option<String>,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub assets: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FederationSummary {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
     pub id: FederationId,
     pub name: Option<String>,
+    /// From the federation's resolved meta fields, including consensus meta
+    #[serde(default)]
+    pub icon_url: Option<String>,
     pub last_7d_activity: Vec<FederationActivity>,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
     pub deposits: Amount,
     pub invite: String,
+    /// Bitcoin network of the federation's wallet module (e.g. `bitcoin` or
+    /// `signet`), if it has one
+    pub network: Option<String>,
     pub nostr_votes: FederationRating,
     pub health: FederationHealth,
+    /// Estimated time of the federation's first session, `None` if no session
+    /// timestamps are known yet
+    pub created_at: Option<DateTime<Utc>>,
+    /// Final statistics if the federation was marked as shut down
+    pub shutdown: Option<FederationShutdown>,
+    /// Rating index computed by the server, see [`rating::rating_indices`].
+    /// Only comparable between federations of the same response.
+    #[serde(default)]
+    pub rating_index: f64,
+}
+
+/// Federation announced via Nostr with the result of the observer's latest
+/// attempt to fetch its config through the announced invite code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrFederationStatus {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub invite_code: InviteCode,
+    /// Name from the federation's config, known once it was reachable
+    pub name: Option<String>,
+    /// `None` if the federation wasn't probed yet
+    pub online: Option<bool>,
+    pub last_checked: Option<DateTime<Utc>>,
+    /// Last time the federation's config could be fetched
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// Counts of federations announced via Nostr by their latest probe result
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrFederationSummary {
+    pub total: u64,
+    pub online: u64,
+    pub offline: u64,
+    /// Announced, but not probed yet
+    pub unchecked: u64,
+    /// Announced federations this instance observes
+    pub observed: u64,
+}
+
+/// Result of contacting every guardian of a federation individually, starting
+/// from an invite code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InviteCheck {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    /// All guardians of the federation if its config could be downloaded,
+    /// otherwise only the ones listed in the invite code
+    pub guardians: Vec<InviteGuardianCheck>,
+    /// Hex encoded hash of the config returned by a threshold of guardians,
+    /// `None` if they didn't agree on one
+    pub consensus_config_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InviteGuardianCheck {
+    #[cfg_attr(feature = "openapi", schema(value_type = u16))]
+    pub guardian_id: PeerId,
+    pub url: String,
+    /// The guardian returned a config
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    /// Core consensus version supported by the guardian, e.g. `2.0`
+    pub consensus_version: Option<String>,
+    /// Hex encoded hash of the config returned by the guardian
+    pub config_hash: Option<String>,
+    /// The guardian's config differs from the consensus config
+    pub config_mismatch: bool,
+    pub error: Option<String>,
+}
+
+/// Most recent nostr announcement of a federation with its NIP-19 identifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrAnnouncement {
+    /// Hex encoded event id
+    pub event_id: String,
+    /// Hex encoded public key of the announcing party
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    /// Address of the replaceable announcement, stays valid when it's updated
+    pub naddr: String,
+    /// Reference to this specific announcement event
+    pub nevent: String,
+}
+
+/// Raw nostr event the observer stored, either a federation announcement
+/// (kind 38173) or a recommendation (kind 38000)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrArchivedEvent {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    /// When the observer first fetched the event from a relay
+    pub fetch_time: DateTime<Utc>,
+    /// Signed event as published, in NIP-01 JSON format
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub event: serde_json::Value,
+}
+
+/// Author whose recommendations are always (allowed) or never counted in
+/// federation ratings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrModeratedPubkey {
+    /// Hex encoded public key
+    pub pubkey: String,
+    pub allowed: bool,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Recommendation an operator hid from federation ratings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrHiddenVote {
+    /// Hex encoded event id
+    pub event_id: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    /// Hex encoded public key of the recommendation's author
+    pub author: String,
+    pub reason: Option<String>,
+    pub hidden_at: DateTime<Utc>,
+}
+
+/// Filters applied to recommendations before they count towards federation
+/// ratings. Only the latest recommendation of each author is counted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NostrModeration {
+    /// Minimum NIP-13 proof of work in leading zero bits of the event id
+    pub min_pow: u8,
+    /// Once any author is allowed, only allowed authors are counted
+    pub pubkeys: Vec<NostrModeratedPubkey>,
+    pub hidden_votes: Vec<NostrHiddenVote>,
+}
+
+/// Federation matching a search on `/federations/search`, either observed by
+/// this instance or only announced via Nostr
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationSearchResult {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub id: FederationId,
+    pub name: Option<String>,
+    /// `None` if the federation isn't observed by this instance
+    pub summary: Option<FederationSummary>,
+    /// Status of the federation's Nostr announcement, if it was announced
+    pub announcement: Option<NostrFederationStatus>,
+    /// Kinds of the modules in the federation's config, empty if it isn't
+    /// observed
+    pub modules: Vec<String>,
+    pub matched: FederationSearchMatch,
+}
+
+/// How a federation matched a search query
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FederationSearchMatch {
+    /// The query was empty, only filters were applied
+    Any,
+    /// The query was an invite code of the federation
+    Invite,
+    /// The query was a prefix of the federation id
+    IdPrefix,
+    /// The query fuzzily matched the federation name
+    Name,
+}
+
+/// Lifetime statistics of a federation, computed when it was marked as shut
+/// down
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationShutdown {
+    pub shutdown_at: DateTime<Utc>,
+    /// Sum of all transaction inputs over the federation's lifetime
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub total_volume: Amount,
+    /// Highest on-chain deposits the federation ever held
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub peak_assets: Amount,
+    /// Time of the last session containing a transaction
+    pub last_activity: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FederationRating {
     pub count: u64,
     pub avg: Option<f64>,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FederationActivity {
     pub num_transactions: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
     pub amount_transferred: Amount,
 }
 
+/// Metric that can be requested from the time series endpoint, amounts are
+/// in msat
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TimeseriesMetric {
+    /// Sum of transaction inputs
+    Volume,
+    TxCount,
+    /// On-chain assets at the end of the bucket
+    Assets,
+    /// Lightning module inputs, i.e. incoming payments
+    LnIn,
+    /// Lightning module outputs, i.e. outgoing payments
+    LnOut,
+    PegIns,
+    PegOuts,
+}
+
+impl TimeseriesMetric {
+    pub const ALL: [TimeseriesMetric; 7] = [
+        TimeseriesMetric::Volume,
+        TimeseriesMetric::TxCount,
+        TimeseriesMetric::Assets,
+        TimeseriesMetric::LnIn,
+        TimeseriesMetric::LnOut,
+        TimeseriesMetric::PegIns,
+        TimeseriesMetric::PegOuts,
+    ];
+
+    /// Name used in the `metrics` query parameter
+    pub fn name(self) -> &'static str {
+        match self {
+            TimeseriesMetric::Volume => "volume",
+            TimeseriesMetric::TxCount => "tx_count",
+            TimeseriesMetric::Assets => "assets",
+            TimeseriesMetric::LnIn => "ln_in",
+            TimeseriesMetric::LnOut => "ln_out",
+            TimeseriesMetric::PegIns => "peg_ins",
+            TimeseriesMetric::PegOuts => "peg_outs",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TimeseriesMetric> {
+        Self::ALL.into_iter().find(|metric| metric.name() == name)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TimeseriesResolution {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+/// Several metrics of a federation bucketed by the same time periods, each
+/// series has one value per entry of `buckets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Timeseries {
+    pub resolution: TimeseriesResolution,
+    /// First day of each bucket (UTC)
+    pub buckets: Vec<NaiveDate>,
+    pub series: BTreeMap<TimeseriesMetric, Vec<u64>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct FederationUtxo {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
     pub address: bitcoin::Address<NetworkUnchecked>,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
     pub out_point: bitcoin::OutPoint,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
     pub amount: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GuardianHealth {
     pub avg_uptime: f32,
     pub avg_latency: f32,
     pub latest: Option<GuardianHealthLatest>,
+    /// `None` if the endpoint doesn't use TLS or wasn't checked yet
+    pub certificate: Option<GuardianCertificate>,
+    /// `None` if no withdrawal was linked to its on-chain transaction yet
+    pub withdrawal_signing: Option<GuardianSigningLatency>,
+    /// Latest fedimintd version the guardian reported, `None` if it never
+    /// answered a version request
+    pub fedimintd_version: Option<String>,
+}
+
+/// How many guardians of observed federations run a fedimintd version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FedimintdVersionCount {
+    pub version: String,
+    pub guardians: u64,
+    /// Federations with at least one guardian running the version
+    pub federations: u64,
+}
+
+/// How long a guardian takes to sign peg-out transactions, measured from the
+/// session the withdrawal was requested in to the first session containing the
+/// guardian's signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianSigningLatency {
+    pub signed_withdrawals: u64,
+    /// Withdrawals that were paid out without a signature of this guardian
+    pub unsigned_withdrawals: u64,
+    /// `None` if the guardian didn't sign any withdrawal
+    pub avg_session_delay: Option<f64>,
+    pub median_session_delay: Option<u64>,
+    pub max_session_delay: Option<u64>,
+    /// Average delay based on estimated session times, `None` if no times
+    /// could be estimated
+    pub avg_delay_secs: Option<f64>,
+}
+
+/// Result of the latest TLS certificate check of a guardian's API endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianCertificate {
+    pub checked_at: DateTime<Utc>,
+    /// `None` if the certificate couldn't be fetched, see `error`
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The certificate expires within 14 days or already expired
+    pub expiring: bool,
+    /// Why the check failed, e.g. the host name couldn't be resolved
+    pub error: Option<String>,
+}
+
+/// Health checks of a guardian within one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianHealthBucket {
+    pub start: DateTime<Utc>,
+    /// Share of successful health checks in percent
+    pub uptime: f32,
+    /// Average latency of successful health checks
+    pub avg_latency_ms: Option<f32>,
+    pub samples: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GuardianHealthLatest {
     pub block_height: u32,
     pub block_outdated: bool,
@@ -55,10 +416,1194 @@ pub struct GuardianHealthLatest {
     pub session_outdated: bool,
 }
 
+/// How far the observer and each guardian are behind the most advanced
+/// guardian, based on the latest health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionDivergence {
+    /// Time of the latest health check, `None` if there wasn't any yet
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Sessions the observer had committed at the time of the check
+    pub observed_session_count: u64,
+    /// Highest session count reported by any guardian
+    pub federation_session_count: Option<u64>,
+    /// Sessions the observer is behind the federation
+    pub observer_lag: Option<u64>,
+    pub guardians: Vec<GuardianSessionDivergence>,
+    /// Largest lags per hour
+    pub history: Vec<SessionDivergenceBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianSessionDivergence {
+    #[cfg_attr(feature = "openapi", schema(value_type = u16))]
+    pub guardian_id: PeerId,
+    /// `None` if the guardian didn't respond
+    pub reported_session_count: Option<u64>,
+    /// Sessions the guardian is behind the most advanced guardian
+    pub behind_federation: Option<u64>,
+    /// Set if the guardian reports fewer sessions than the observer already
+    /// committed, which means it serves a stale or forked history
+    pub behind_observer: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionDivergenceBucket {
+    pub start: DateTime<Utc>,
+    pub max_observer_lag: u64,
+    /// Largest lag of a responding guardian behind the most advanced one
+    pub max_guardian_lag: u64,
+    /// Largest number of sessions a guardian reported less than the observer
+    /// had committed
+    pub max_stale_sessions: u64,
+}
+
+/// Latest result of a deployment specific health probe of a guardian
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthProbeResult {
+    /// Name of the probe as configured
+    pub probe: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = u16))]
+    pub guardian_id: PeerId,
+    pub checked_at: DateTime<Utc>,
+    pub success: bool,
+    pub latency_ms: Option<u64>,
+    /// Why the probe failed or what it observed
+    pub message: Option<String>,
+}
+
+/// How many consensus items a guardian contributed to the sessions observed so
+/// far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianContribution {
+    pub item_count: u64,
+    pub transaction_count: u64,
+    /// Share of all consensus items proposed by this guardian, between 0 and 1
+    pub item_share: f64,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum FederationHealth {
     Online,
     Degraded,
     Offline,
 }
+
+impl FederationHealth {
+    pub const ALL: [FederationHealth; 3] = [
+        FederationHealth::Online,
+        FederationHealth::Degraded,
+        FederationHealth::Offline,
+    ];
+
+    /// Name used in the API, e.g. for the `health` search filter
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FederationHealth::Online => "online",
+            FederationHealth::Degraded => "degraded",
+            FederationHealth::Offline => "offline",
+        }
+    }
+}
+
+/// Build information of a server or frontend binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VersionInfo {
+    pub version: String,
+    /// Git revision the binary was built from, if known at build time
+    pub git_rev: Option<String>,
+}
+
+/// Statistics on how concentrated peg-outs of a federation are on few
+/// destination addresses, which can hint at custodial sweeps (e.g. to an
+/// exchange). Destinations whose payouts were later spent in the same
+/// transaction are grouped into clusters, since they likely belong to the same
+/// wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawalConcentration {
+    pub withdrawal_count: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub total_amount: Amount,
+    pub destination_count: u64,
+    /// Share of the withdrawn amount that went to the largest destination,
+    /// between 0 and 1
+    pub top_destination_share: f64,
+    /// Herfindahl-Hirschman index of the withdrawn amounts per destination,
+    /// between 0 (perfectly spread out) and 1 (single destination)
+    pub hhi: f64,
+    /// Number of equally sized destinations that would result in the same
+    /// HHI, higher means more decentralized withdrawals
+    pub effective_destinations: f64,
+    pub cluster_count: u64,
+    /// Share of the withdrawn amount that went to the largest cluster, between
+    /// 0 and 1
+    pub top_cluster_share: f64,
+    /// Herfindahl-Hirschman index of the withdrawn amounts per cluster
+    pub cluster_hhi: f64,
+    /// Number of equally sized clusters that would result in the same HHI
+    pub effective_clusters: f64,
+    /// Set if withdrawals are dominated by a single destination cluster
+    pub concentrated: bool,
+    /// Largest destinations by withdrawn amount
+    pub top_destinations: Vec<WithdrawalDestination>,
+    /// Largest clusters by withdrawn amount
+    pub top_clusters: Vec<WithdrawalCluster>,
+}
+
+/// Destination addresses likely belonging to the same wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawalCluster {
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub addresses: Vec<bitcoin::Address<NetworkUnchecked>>,
+    pub withdrawal_count: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub amount: Amount,
+}
+
+/// Concentration of the peg-outs requested within one period, see
+/// [`WithdrawalConcentration`]. Clusters are formed from all spends observed so
+/// far, not just the ones within the period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawalConcentrationPeriod {
+    /// First day of the period (UTC)
+    pub start: NaiveDate,
+    pub withdrawal_count: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub total_amount: Amount,
+    pub destination_count: u64,
+    pub cluster_count: u64,
+    pub top_cluster_share: f64,
+    pub cluster_hhi: f64,
+    pub effective_clusters: f64,
+    pub concentrated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawalDestination {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub address: bitcoin::Address<NetworkUnchecked>,
+    pub withdrawal_count: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub amount: Amount,
+}
+
+/// Deployment specific defaults for the frontend, configured by the operator
+/// of an observer instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct InstanceSettings {
+    /// Number of days shown in activity charts by default, `None` shows the
+    /// full history
+    pub chart_window_days: Option<u32>,
+    pub display_unit: DisplayUnit,
+    /// Weights used to order federations in the overview
+    pub rating_weights: RatingWeights,
+    /// Algorithm turning nostr votes into the rating part of the index
+    pub rating_algorithm: RatingAlgorithm,
+}
+
+impl Default for InstanceSettings {
+    fn default() -> Self {
+        InstanceSettings {
+            chart_window_days: None,
+            display_unit: DisplayUnit::Btc,
+            rating_weights: RatingWeights::default(),
+            rating_algorithm: RatingAlgorithm::default(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayUnit {
+    Btc,
+    Sats,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct RatingWeights {
+    pub nostr_rating: f64,
+    pub activity: f64,
+    pub assets: f64,
+}
+
+impl Default for RatingWeights {
+    fn default() -> Self {
+        RatingWeights {
+            nostr_rating: 1.0,
+            activity: 1.0,
+            assets: 1.0,
+        }
+    }
+}
+
+/// How a federation's nostr votes are scored, see
+/// [`rating::nostr_scores`]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RatingAlgorithm {
+    /// Average star rating, a single five star vote beats many good ones
+    #[default]
+    PlainAvg,
+    /// Average pulled towards the mean of all federations until a federation
+    /// has enough votes of its own
+    Bayesian,
+    /// Lower bound of the Wilson score interval, penalizes few votes
+    Wilson,
+}
+
+impl RatingAlgorithm {
+    /// Name used in the API, e.g. for the `rating` query parameter
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RatingAlgorithm::PlainAvg => "plain_avg",
+            RatingAlgorithm::Bayesian => "bayesian",
+            RatingAlgorithm::Wilson => "wilson",
+        }
+    }
+}
+
+/// Per-federation thresholds of the alerting engine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct AlertSettings {
+    /// Raise a latency regression alert if a guardian's p95 API latency of the
+    /// last week is at least this many times its p95 latency of the week before
+    pub latency_regression_ratio: f64,
+    /// Latency regressions are ignored as long as the p95 latency stays below
+    /// this value, so fast guardians don't cause noise
+    pub latency_regression_min_ms: u32,
+    /// Raise an alert if peg-outs exceed peg-ins by more than this within
+    /// `net_flow_window_hours`, disabled if `None`
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<u64>))]
+    pub net_outflow_threshold: Option<Amount>,
+    /// Raise an alert if peg-ins exceed peg-outs by more than this within
+    /// `net_flow_window_hours`, disabled if `None`
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<u64>))]
+    pub net_inflow_threshold: Option<Amount>,
+    pub net_flow_window_hours: u32,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        AlertSettings {
+            latency_regression_ratio: 2.0,
+            latency_regression_min_ms: 500,
+            net_outflow_threshold: None,
+            net_inflow_threshold: None,
+            net_flow_window_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    LatencyRegression,
+    ObserverQuarantined,
+    /// A guardian stopped answering health checks
+    GuardianOffline,
+    /// A guardian's bitcoin node fell behind the chain tip
+    GuardianBlockLag,
+    /// A guardian fell behind the other guardians' consensus sessions
+    GuardianSessionLag,
+    /// Too few guardians answer health checks to reach consensus
+    FederationOffline,
+    /// A guardian's TLS certificate is about to expire or already expired
+    GuardianCertificateExpiry,
+    /// A guardian's wallet module votes (fee rate, block height) persistently
+    /// deviate from the other guardians'
+    WalletVoteDivergence,
+    /// Peg-outs exceeded peg-ins by more than the configured threshold
+    LargeNetOutflow,
+    /// Peg-ins exceeded peg-outs by more than the configured threshold
+    LargeNetInflow,
+}
+
+/// An alert raised by the alerting engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AlertEvent {
+    pub kind: AlertKind,
+    /// Guardian the alert is about, `None` for federation-wide alerts
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<u16>))]
+    pub guardian_id: Option<PeerId>,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Period in which a guardian failed all health checks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GuardianIncident {
+    #[cfg_attr(feature = "openapi", schema(value_type = u16))]
+    pub guardian_id: PeerId,
+    pub guardian_name: String,
+    pub started_at: DateTime<Utc>,
+    /// First successful health check after the incident, `None` while it's
+    /// ongoing
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Duration up to now for ongoing incidents
+    pub duration_secs: u64,
+}
+
+/// Transition of a federation's health state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthEvent {
+    pub timestamp: DateTime<Utc>,
+    /// `None` for the first state recorded for the federation
+    pub previous_health: Option<FederationHealth>,
+    pub health: FederationHealth,
+}
+
+/// Payload format of an alert webhook
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// Slack incoming webhook
+    Slack,
+    /// Matrix webhook bridge accepting `m.text` message bodies
+    Matrix,
+    /// [`AlertNotification`] as JSON
+    Json,
+}
+
+/// Which alerts to deliver to a webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRuleSettings {
+    /// Only deliver alerts of this federation, all federations if `None`
+    pub federation_id: Option<FederationId>,
+    /// Only deliver alerts of these kinds, all kinds if empty
+    #[serde(default)]
+    pub kinds: Vec<AlertKind>,
+    pub webhook_url: String,
+    pub format: WebhookFormat,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub rule_id: u64,
+    #[serde(flatten)]
+    pub settings: AlertRuleSettings,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertDeliveryStatus {
+    Pending,
+    Delivered,
+    /// Delivery was given up after too many failed attempts
+    Failed,
+}
+
+/// Delivery of an alert to an alert rule's webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertDelivery {
+    pub federation_id: FederationId,
+    pub event: AlertEvent,
+    pub status: AlertDeliveryStatus,
+    pub attempts: u32,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Body of alert webhooks using the [`WebhookFormat::Json`] format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertNotification {
+    pub federation_id: FederationId,
+    pub federation_name: Option<String>,
+    #[serde(flatten)]
+    pub event: AlertEvent,
+}
+
+/// Timestamp of a bitcoin block as used for session time estimation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BlockTime {
+    pub block_height: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// How far the observer's block time table lags behind the bitcoin chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChainSyncStatus {
+    /// Highest block whose timestamp was synced, `None` before the first sync
+    pub synced_height: Option<u32>,
+    pub synced_block_time: Option<DateTime<Utc>>,
+    /// Chain tip as last reported by the block explorer, `None` if it wasn't
+    /// queried successfully since startup
+    pub chain_tip_height: Option<u32>,
+    pub blocks_behind: Option<u32>,
+}
+
+/// Health of the task following a federation's consensus history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ObserverStatus {
+    /// Number of times the observer failed without making progress in between
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_failure: Option<DateTime<Utc>>,
+    /// Set once the observer failed too often, it won't be restarted
+    /// automatically anymore
+    pub quarantined: bool,
+    /// Last session the observer committed, it resumes after it on restart
+    pub last_committed_session: Option<u64>,
+    pub last_commit: Option<DateTime<Utc>>,
+    /// Transaction inputs and outputs of module versions the observer can't
+    /// decode yet, their amounts are missing from all statistics
+    pub quarantined_items: u64,
+    /// Session items the observer failed to process and skipped
+    #[serde(default)]
+    pub processing_errors: u64,
+    /// Set while there are processing errors, statistics of the federation
+    /// may be incomplete
+    #[serde(default)]
+    pub degraded: bool,
+}
+
+/// Progress of a long running job like a federation's initial sync, a session
+/// replay or the backfill of a migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobProgress {
+    pub id: u64,
+    /// `sync`, `replay`, `import` or `migration_v<version>`
+    pub kind: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub federation_id: Option<FederationId>,
+    /// Waiting for a free worker, `started_at` is when it was queued
+    #[serde(default)]
+    pub queued: bool,
+    pub processed: u64,
+    pub total: u64,
+    pub percent_complete: f64,
+    /// Items processed per second, `None` until the first progress update
+    pub rate: Option<f64>,
+    /// Estimated completion time, only set while the job is running
+    pub eta: Option<DateTime<Utc>>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Sync state of a federation's history observer
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpState {
+    /// Behind and waiting for another observer to finish catching up
+    Queued,
+    CatchingUp,
+    /// Following new sessions as they are created
+    Live,
+}
+
+/// Catch-up progress of a federation's history observer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CatchUpProgress {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    pub state: CatchUpState,
+    /// Queued observers with a higher priority catch up first
+    pub priority: u64,
+    pub next_session: u64,
+    /// Session count reported by the federation when the observer started
+    pub session_count: u64,
+    /// When the observer entered its current state
+    pub since: DateTime<Utc>,
+}
+
+/// Session item the observer failed to process and skipped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProcessingError {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    pub session_index: u64,
+    pub item_index: u64,
+    /// Set if the item is a transaction
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub txid: Option<TransactionId>,
+    pub error: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of re-processing the sessions with processing errors or
+/// quarantined items of a federation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProcessingRetry {
+    pub replayed_sessions: u64,
+    /// Sessions whose replay failed, they keep their previous data
+    pub failed_sessions: Vec<u64>,
+    /// Processing errors left after the replay
+    pub remaining_errors: u64,
+}
+
+/// One page of a potentially long list, `total` is the number of items across
+/// all pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::ToSchema),
+    aliases(
+        TransactionIdPage = Page<String>,
+        SessionPage = Page<SessionSummary>,
+        DepositPage = Page<Deposit>,
+        NostrEventPage = Page<NostrArchivedEvent>
+    )
+)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+/// Overview of a session without its consensus items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionSummary {
+    pub session_index: u64,
+    pub item_count: u64,
+    pub transaction_count: u64,
+    /// Derived from the block height votes up to the session, `None` if no
+    /// votes were seen yet
+    pub estimated_time: Option<DateTime<Utc>>,
+}
+
+/// Session including all its consensus items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionDetails {
+    pub summary: SessionSummary,
+    pub items: Vec<SessionItem>,
+}
+
+/// Consensus item as accepted in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionItem {
+    /// Guardian that proposed the item
+    pub peer: u16,
+    /// `transaction`, the module kind for module consensus items or `unknown`
+    pub kind: String,
+    /// Set if the item is a transaction
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub txid: Option<TransactionId>,
+    /// Debug representation of the decoded item
+    pub decoded: String,
+}
+
+/// Where an item matched by a structured item search was found
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ItemSearchSource {
+    Input,
+    Output,
+    /// Module consensus item, e.g. a peg-out signature or block height vote
+    ConsensusItem,
+}
+
+/// Transaction input/output or consensus item whose decoded JSON matched a
+/// structured item search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ItemSearchMatch {
+    pub source: ItemSearchSource,
+    pub session_index: u64,
+    pub item_index: u64,
+    /// Transaction of matched inputs and outputs
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub txid: Option<TransactionId>,
+    /// Index of the input or output within its transaction
+    pub index: Option<u64>,
+    /// Guardian that proposed a matched consensus item
+    pub peer: Option<u16>,
+    /// Module kind
+    pub kind: String,
+    /// The decoded item as it was matched
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub details: serde_json::Value,
+}
+
+/// On-chain peg-out transaction signed by a federation's guardians
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct WithdrawalTransaction {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub on_chain_txid: bitcoin::Txid,
+    /// Federation transaction requesting the withdrawal, if it could be matched
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub federation_txid: Option<TransactionId>,
+    /// Number of guardians that signed the transaction
+    pub signatures: u64,
+    pub confirmation_height: Option<u32>,
+    /// Transaction spending the same inputs that replaced this one, e.g. to
+    /// bump its fee
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub replaced_by: Option<bitcoin::Txid>,
+    /// All replacements in order, the last one is the one that counts
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
+    pub replacement_chain: Vec<bitcoin::Txid>,
+}
+
+/// On-chain deposit claimed by a federation transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Deposit {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub on_chain_txid: bitcoin::Txid,
+    pub on_chain_vout: u32,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub address: bitcoin::Address<NetworkUnchecked>,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub amount: Amount,
+    /// Federation transaction that claimed the deposit
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_txid: TransactionId,
+    pub session_index: u64,
+    pub estimated_time: Option<DateTime<Utc>>,
+}
+
+/// Peg-out requested by a federation transaction, with the progress of the
+/// on-chain transaction paying it out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Withdrawal {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_txid: TransactionId,
+    pub session_index: u64,
+    pub estimated_time: Option<DateTime<Utc>>,
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub address: bitcoin::Address<NetworkUnchecked>,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub amount: Amount,
+    /// Latest on-chain transaction paying out the withdrawal, replaced
+    /// transactions are skipped
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub on_chain_txid: Option<bitcoin::Txid>,
+    /// Number of guardians that signed the on-chain transaction
+    pub signatures: u64,
+    /// Number of signatures needed for the transaction to be valid
+    pub signature_threshold: u64,
+    pub confirmation_height: Option<u32>,
+    pub status: WithdrawalStatus,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalStatus {
+    /// No on-chain transaction was matched to the request yet
+    Requested,
+    /// Guardians are signing the on-chain transaction
+    Signing,
+    /// Enough guardians signed, the transaction awaits confirmation
+    Broadcast,
+    Confirmed,
+}
+
+/// Decoded inputs and outputs of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionDetails {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub txid: TransactionId,
+    pub session_index: u64,
+    pub item_index: u64,
+    pub inputs: Vec<TransactionItem>,
+    pub outputs: Vec<TransactionItem>,
+}
+
+/// Where a transaction's funds came from and went, for rendering the
+/// transaction as a graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionGraph {
+    pub nodes: Vec<TransactionGraphNode>,
+    /// Directed in the direction funds flow
+    pub edges: Vec<TransactionGraphEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionGraphNode {
+    /// Unique within the graph, e.g. `input:0`
+    pub id: String,
+    pub kind: TransactionGraphNodeKind,
+    pub label: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<u64>))]
+    pub amount: Option<Amount>,
+    /// Federation transaction or on-chain txid the node stands for, if any
+    pub txid: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionGraphNodeKind {
+    /// The transaction itself or one it references
+    Transaction,
+    Input,
+    Output,
+    LnContract,
+    /// Peg-in or peg-out transaction on the bitcoin chain
+    OnChainTransaction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionGraphEdge {
+    pub from: String,
+    pub to: String,
+}
+
+/// Input or output of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TransactionItem {
+    /// Kind of the module the item belongs to, e.g. `mint`
+    pub kind: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<u64>))]
+    pub amount: Option<Amount>,
+    pub ln_contract: Option<LnContractReference>,
+    /// Debug representation of the decoded item
+    pub decoded: String,
+}
+
+/// Lightning contract an input or output interacts with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LnContractReference {
+    /// Hex encoded contract id
+    pub contract_id: String,
+    /// `fund`, `cancel` or `offer` for outputs, `None` for inputs
+    pub interaction: Option<String>,
+    /// Transaction that funded the contract, if it isn't this one
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<String>))]
+    pub funding_txid: Option<TransactionId>,
+}
+
+/// Fees collected by a federation's guardians
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationFees {
+    /// Fee parameters of each module as found in the client config, keyed by
+    /// module kind
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub fee_config: BTreeMap<String, serde_json::Value>,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub total_fees: Amount,
+    /// Fees collected per day, only includes sessions with a known timestamp
+    #[cfg_attr(feature = "openapi", schema(value_type = BTreeMap<String, FeeRevenue>))]
+    pub history: BTreeMap<NaiveDate, FeeRevenue>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FeeRevenue {
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub fees: Amount,
+    /// Fees collected up to and including this day
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub cumulative_fees: Amount,
+}
+
+/// Number of e-cash notes of one denomination a federation's mint issued and
+/// redeemed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MintDenomination {
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub denomination: Amount,
+    pub issued_notes: u64,
+    pub redeemed_notes: u64,
+    /// Notes issued but not redeemed yet
+    pub outstanding_notes: u64,
+    /// Value of the outstanding notes
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub outstanding_amount: Amount,
+}
+
+/// A lightning gateway that funded or received contracts of a federation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationGateway {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub gateway_key: PublicKey,
+    /// Number of contracts with the gateway as counterparty
+    pub contracts: u64,
+    /// Total amount of all contracts routed through the gateway
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub routed_volume: Amount,
+    pub first_seen_session: u64,
+    pub last_seen_session: u64,
+}
+
+/// Lightning contract statistics of a federation, split by direction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LightningStats {
+    /// Contracts funded by gateways to pay users
+    pub incoming: LightningContractStats,
+    /// Contracts funded by users to pay through gateways
+    pub outgoing: LightningContractStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LightningContractStats {
+    pub funded: u64,
+    /// Contracts that were spent without being cancelled first
+    pub settled: u64,
+    /// Outgoing contracts the gateway cancelled, always zero for incoming ones
+    pub cancelled: u64,
+    /// Funded contracts that weren't spent yet
+    pub pending: u64,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub total_amount: Amount,
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub avg_amount: Amount,
+    /// Share of settled contracts among settled and cancelled ones, `None` if
+    /// none were resolved yet
+    pub success_rate: Option<f64>,
+}
+
+/// Heuristic estimate of how well a federation's on-chain and e-cash activity
+/// protects user privacy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PrivacyScore {
+    /// Weighted average of the component scores between 0 (worst) and 100
+    /// (best), `None` if there isn't enough activity to judge
+    pub score: Option<f64>,
+    /// How evenly e-cash notes are spread across denominations, larger
+    /// anonymity sets per denomination make notes harder to link
+    pub denomination_entropy: PrivacyScoreComponent,
+    /// How rarely deposit and withdrawal addresses are reused
+    pub address_reuse: PrivacyScoreComponent,
+    /// How often withdrawals are batched into shared on-chain transactions
+    pub withdrawal_batching: PrivacyScoreComponent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PrivacyScoreComponent {
+    /// Between 0 (worst) and 1 (best)
+    pub score: f64,
+    pub weight: f64,
+    /// The raw metric the score is derived from, i.e. the entropy in bits, the
+    /// share of address uses that reuse an address or the share of batched
+    /// withdrawal transactions
+    pub value: f64,
+    /// Number of notes, address uses or withdrawal transactions the metric is
+    /// based on
+    pub samples: u64,
+}
+
+/// Comparison of a federation's on-chain reserves with what it owes its users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationSolvency {
+    /// Sum of the federation's tracked on-chain UTXOs
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub assets: Amount,
+    /// E-cash issued minus e-cash redeemed
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub ecash_liabilities: Amount,
+    /// Funds locked in lightning contracts that weren't claimed or refunded yet
+    #[cfg_attr(feature = "openapi", schema(value_type = u64))]
+    pub ln_contract_liabilities: Amount,
+    /// Assets divided by all liabilities, `None` if there are no liabilities
+    pub ratio: Option<f64>,
+    /// Set if the assets fall noticeably short of the liabilities
+    pub appears_insolvent: bool,
+}
+
+/// How complete the observer's data of a federation is, to judge how far its
+/// statistics can be trusted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationCompleteness {
+    /// Number of stored sessions
+    pub session_count: u64,
+    /// Highest stored session index, `None` if no session was stored yet
+    pub latest_session: Option<u64>,
+    /// Ranges of sessions below the latest one that are missing
+    pub gaps: Vec<SessionGap>,
+    /// Estimated time of the first session with a time estimate
+    pub earliest_time: Option<DateTime<Utc>>,
+    /// Estimated time of the latest session with a time estimate
+    pub latest_time: Option<DateTime<Utc>>,
+    /// Sessions without a time estimate, they are missing from time-based
+    /// statistics
+    pub untimed_sessions: u64,
+    /// Transaction inputs and outputs of modules the observer can't decode,
+    /// their amounts are missing from volume statistics
+    pub undecoded_items: u64,
+    /// Peg-out transactions whose on-chain transaction wasn't fetched yet
+    pub pending_on_chain_lookups: u64,
+}
+
+/// Inclusive range of missing sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SessionGap {
+    pub first: u64,
+    pub last: u64,
+}
+
+/// Statistics of fetching a meta override file, kept since the server started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MetaFetchStats {
+    pub url: String,
+    pub fetches: u64,
+    pub failures: u64,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Duration of the last fetch attempt
+    pub last_duration_ms: Option<u64>,
+    /// Size of the last successfully fetched file
+    pub last_size_bytes: Option<u64>,
+}
+
+/// API usage of this instance since the server started. Unique clients are
+/// only counted within the current period since client identifiers are
+/// rotated regularly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UsageStats {
+    pub since: DateTime<Utc>,
+    /// Start of the period unique clients are counted in
+    pub period_start: DateTime<Utc>,
+    /// Most requested routes first
+    pub routes: Vec<RouteUsage>,
+    /// Most requested federations first
+    pub federations: Vec<FederationUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RouteUsage {
+    pub method: String,
+    /// Route template, e.g. `/federations/:federation_id/health`
+    pub route: String,
+    pub requests: u64,
+    pub unique_clients: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FederationUsage {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    pub requests: u64,
+    pub unique_clients: u64,
+}
+
+/// Named position up to which an API client exported a federation's
+/// transactions, the next export with the cursor starts after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExportCursor {
+    pub name: String,
+    /// Session of the last exported transaction
+    pub session_index: u64,
+    /// Item index of the last exported transaction within its session
+    pub item_index: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Size and maintenance state of a database table. Bloat is estimated from the
+/// share of dead rows, assuming they are as large as live ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DbTableStats {
+    pub table: String,
+    pub live_rows: u64,
+    pub dead_rows: u64,
+    /// Rows modified since the table was last analyzed
+    pub modified_since_analyze: u64,
+    pub table_bytes: u64,
+    /// Combined size of all indexes of the table
+    pub index_bytes: u64,
+    pub estimated_table_bloat_bytes: u64,
+    pub estimated_index_bloat_bytes: u64,
+    /// Last manual or automatic vacuum
+    pub last_vacuum: Option<DateTime<Utc>>,
+    /// Last manual or automatic analyze
+    pub last_analyze: Option<DateTime<Utc>>,
+}
+
+/// Permission granted to an API key. The `FO_ADMIN_AUTH` token has all of them
+/// and is the only one that can manage API keys.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// Read-only operator endpoints, e.g. query plans and usage statistics
+    Query,
+    /// Adding, removing and maintaining observed federations, views and alert
+    /// rules
+    Federations,
+    /// Managing the relays nostr events are synced from and published to, and
+    /// moderating recommendations
+    Nostr,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 3] = [ApiScope::Query, ApiScope::Federations, ApiScope::Nostr];
+
+    /// Name stored in the database and used in requests
+    pub fn name(self) -> &'static str {
+        match self {
+            ApiScope::Query => "query",
+            ApiScope::Federations => "federations",
+            ApiScope::Nostr => "nostr",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ApiScope> {
+        Self::ALL.into_iter().find(|scope| scope.name() == name)
+    }
+}
+
+/// API key without its secret, which is only returned once on creation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiKey {
+    pub id: u64,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Name and scopes of an API key to create or update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+}
+
+/// Newly created API key, `key` is sent as bearer token and can't be
+/// retrieved again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreatedApiKey {
+    pub id: u64,
+    pub key: String,
+}
+
+/// Progress of comparing the sessions of a federation restored from a snapshot
+/// against the federation itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SnapshotVerification {
+    #[cfg_attr(feature = "openapi", schema(value_type = String))]
+    pub federation_id: FederationId,
+    pub restored_at: DateTime<Utc>,
+    /// Number of sessions contained in the snapshot
+    pub session_count: u64,
+    /// Number of sessions, starting from the first one, that matched
+    pub verified_sessions: u64,
+    /// First session that differs from the federation's, verification stops
+    /// there
+    pub mismatch_session: Option<u64>,
+}
+
+/// Meta fields of a federation as observed at one point in time, a new entry
+/// is recorded whenever they change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MetaHistoryEntry {
+    pub recorded_at: DateTime<Utc>,
+    /// Consensus meta fields merged with the ones from the override file
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub meta: BTreeMap<String, serde_json::Value>,
+    /// Fields added, removed or changed since the previous entry, empty for
+    /// the first one
+    pub changed_fields: Vec<String>,
+}
+
+/// Problems found in a federation's meta fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MetaLint {
+    pub issues: Vec<MetaLintIssue>,
+    pub lightning_contacts: Vec<LightningContactVerification>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MetaLintIssue {
+    /// Meta field the issue is about
+    pub field: String,
+    pub severity: MetaLintSeverity,
+    pub message: String,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MetaLintSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LightningContactKind {
+    LightningAddress,
+    Lnurl,
+}
+
+/// Whether a lightning address or LNURL from a federation's meta resolves to
+/// a valid LNURL endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LightningContactVerification {
+    /// Meta field containing the contact
+    pub field: String,
+    pub contact: String,
+    pub kind: LightningContactKind,
+    pub verified: bool,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
@@ -0,0 +1,291 @@
+//! Ranking of federations by their rating index. Lives here so the server, the
+//! frontend and other API consumers order federations identically.
+
+use fedimint_core::Amount;
+
+use crate::{FederationSummary, RatingAlgorithm, RatingWeights};
+
+impl FederationSummary {
+    /// Average number of transactions per day over the last 7 days
+    pub fn avg_daily_transactions(&self) -> f64 {
+        if self.last_7d_activity.is_empty() {
+            return 0.0;
+        }
+
+        self.last_7d_activity
+            .iter()
+            .map(|activity| activity.num_transactions)
+            .sum::<u64>() as f64
+            / self.last_7d_activity.len() as f64
+    }
+
+    /// Average volume transferred per day over the last 7 days
+    pub fn avg_daily_volume(&self) -> Amount {
+        if self.last_7d_activity.is_empty() {
+            return Amount::ZERO;
+        }
+
+        Amount::from_msats(
+            self.last_7d_activity
+                .iter()
+                .map(|activity| activity.amount_transferred.msats)
+                .sum::<u64>()
+                / self.last_7d_activity.len() as u64,
+        )
+    }
+}
+
+/// Number of votes at the mean of all federations a federation's own votes are
+/// mixed with by [`RatingAlgorithm::Bayesian`]
+const BAYESIAN_PRIOR_VOTES: f64 = 5.0;
+/// z-score of the 95% confidence interval used by [`RatingAlgorithm::Wilson`]
+const WILSON_Z: f64 = 1.96;
+
+/// Scores the nostr votes of each federation between 0 and 1 using
+/// `algorithm`. The result has the same order as `federations`.
+pub fn nostr_scores(federations: &[FederationSummary], algorithm: RatingAlgorithm) -> Vec<f64> {
+    let (total_votes, total_stars) = federations
+        .iter()
+        .filter_map(|summary| {
+            let votes = summary.nostr_votes.count as f64;
+            Some((votes, summary.nostr_votes.avg? * votes))
+        })
+        .fold((0.0, 0.0), |(votes, stars), (v, s)| (votes + v, stars + s));
+    let mean = if total_votes > 0.0 {
+        total_stars / total_votes
+    } else {
+        0.0
+    };
+
+    federations
+        .iter()
+        .map(|summary| {
+            let votes = summary.nostr_votes.count as f64;
+            let avg = summary.nostr_votes.avg.unwrap_or_default();
+            match algorithm {
+                RatingAlgorithm::PlainAvg => avg / 5.0,
+                RatingAlgorithm::Bayesian => {
+                    (BAYESIAN_PRIOR_VOTES * mean + votes * avg)
+                        / (BAYESIAN_PRIOR_VOTES + votes)
+                        / 5.0
+                }
+                RatingAlgorithm::Wilson => wilson_lower_bound(avg, votes),
+            }
+        })
+        .collect()
+}
+
+/// Lower bound of the Wilson score interval of the star votes, treating one
+/// star as negative and five stars as positive
+fn wilson_lower_bound(avg: f64, votes: f64) -> f64 {
+    if votes == 0.0 {
+        return 0.0;
+    }
+
+    let positive = ((avg - 1.0) / 4.0).clamp(0.0, 1.0);
+    let z2 = WILSON_Z * WILSON_Z;
+    let center = positive + z2 / (2.0 * votes);
+    let margin =
+        WILSON_Z * (positive * (1.0 - positive) / votes + z2 / (4.0 * votes * votes)).sqrt();
+    ((center - margin) / (1.0 + z2 / votes)).max(0.0)
+}
+
+/// Calculates the rating index of each federation, a weighted combination of
+/// its nostr rating, activity and assets, each normalized to the best
+/// federation in the list. The result has the same order as `federations`.
+pub fn rating_indices(
+    federations: &[FederationSummary],
+    weights: RatingWeights,
+    algorithm: RatingAlgorithm,
+) -> Vec<f64> {
+    let max_avg_txs = federations
+        .iter()
+        .map(FederationSummary::avg_daily_transactions)
+        .fold(0f64, f64::max);
+    let max_deposits = federations
+        .iter()
+        .map(|summary| summary.deposits.msats)
+        .max()
+        .unwrap_or_default();
+
+    let normalize = |value: f64, max: f64| if max > 0.0 { value / max } else { 0.0 };
+    federations
+        .iter()
+        .zip(nostr_scores(federations, algorithm))
+        .map(|(summary, nostr_score)| {
+            weights.nostr_rating * nostr_score
+                + weights.activity * normalize(summary.avg_daily_transactions(), max_avg_txs)
+                + weights.assets * normalize(summary.deposits.msats as f64, max_deposits as f64)
+        })
+        .collect()
+}
+
+/// Sets the rating index of each federation, see [`rating_indices`]
+pub fn assign_rating_indices(
+    federations: &mut [FederationSummary],
+    weights: RatingWeights,
+    algorithm: RatingAlgorithm,
+) {
+    let indices = rating_indices(federations, weights, algorithm);
+    for (summary, index) in federations.iter_mut().zip(indices) {
+        summary.rating_index = index;
+    }
+}
+
+/// Orders federations by descending rating index as set by
+/// [`assign_rating_indices`]
+pub fn sort_by_rating_index(federations: &mut [FederationSummary]) {
+    federations.sort_by(|a, b| b.rating_index.total_cmp(&a.rating_index));
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use fedimint_core::Amount;
+
+    use super::{assign_rating_indices, nostr_scores, rating_indices, sort_by_rating_index};
+    use crate::{
+        FederationActivity, FederationHealth, FederationRating, FederationSummary, RatingAlgorithm,
+        RatingWeights,
+    };
+
+    fn summary(
+        name: &str,
+        daily_txs: &[u64],
+        deposits_sat: u64,
+        rating: Option<f64>,
+    ) -> FederationSummary {
+        rated(
+            name,
+            daily_txs,
+            deposits_sat,
+            rating.map(|rating| (1, rating)),
+        )
+    }
+
+    fn rated(
+        name: &str,
+        daily_txs: &[u64],
+        deposits_sat: u64,
+        votes: Option<(u64, f64)>,
+    ) -> FederationSummary {
+        FederationSummary {
+            id: FederationId::dummy(),
+            name: Some(name.to_owned()),
+            icon_url: None,
+            last_7d_activity: daily_txs
+                .iter()
+                .map(|&num_transactions| FederationActivity {
+                    num_transactions,
+                    amount_transferred: Amount::from_sats(num_transactions * 1_000),
+                })
+                .collect(),
+            deposits: Amount::from_sats(deposits_sat),
+            invite: String::new(),
+            network: None,
+            nostr_votes: FederationRating {
+                count: votes.map_or(0, |(count, _)| count),
+                avg: votes.map(|(_, avg)| avg),
+            },
+            health: FederationHealth::Online,
+            created_at: None,
+            shutdown: None,
+            rating_index: 0.0,
+        }
+    }
+
+    fn names(federations: &[FederationSummary]) -> Vec<&str> {
+        federations
+            .iter()
+            .map(|summary| summary.name.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_daily_averages() {
+        let federation = summary("a", &[1, 2, 3, 6], 0, None);
+        assert_eq!(federation.avg_daily_transactions(), 3.0);
+        assert_eq!(federation.avg_daily_volume(), Amount::from_sats(3_000));
+
+        let inactive = summary("b", &[], 0, None);
+        assert_eq!(inactive.avg_daily_transactions(), 0.0);
+        assert_eq!(inactive.avg_daily_volume(), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_rating_indices() {
+        let federations = vec![
+            summary("busy", &[10, 10], 100, None),
+            summary("rich", &[5, 5], 200, Some(5.0)),
+            summary("empty", &[], 0, None),
+        ];
+
+        let indices = rating_indices(
+            &federations,
+            RatingWeights::default(),
+            RatingAlgorithm::PlainAvg,
+        );
+        assert_eq!(indices, vec![1.0 + 0.5, 1.0 + 0.5 + 1.0, 0.0]);
+
+        let only_assets = RatingWeights {
+            nostr_rating: 0.0,
+            activity: 0.0,
+            assets: 2.0,
+        };
+        assert_eq!(
+            rating_indices(&federations, only_assets, RatingAlgorithm::PlainAvg),
+            vec![1.0, 2.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_rating_index() {
+        let mut federations = vec![
+            summary("empty", &[], 0, None),
+            summary("busy", &[10, 10], 100, None),
+            summary("rich", &[5, 5], 200, Some(5.0)),
+        ];
+
+        assign_rating_indices(
+            &mut federations,
+            RatingWeights::default(),
+            RatingAlgorithm::PlainAvg,
+        );
+        sort_by_rating_index(&mut federations);
+        assert_eq!(names(&federations), vec!["rich", "busy", "empty"]);
+
+        let only_activity = RatingWeights {
+            nostr_rating: 0.0,
+            activity: 1.0,
+            assets: 0.0,
+        };
+        assign_rating_indices(&mut federations, only_activity, RatingAlgorithm::PlainAvg);
+        sort_by_rating_index(&mut federations);
+        assert_eq!(names(&federations), vec!["busy", "rich", "empty"]);
+    }
+
+    #[test]
+    fn test_nostr_scores() {
+        let federations = vec![
+            rated("single", &[], 0, Some((1, 5.0))),
+            rated("many", &[], 0, Some((20, 4.5))),
+            rated("none", &[], 0, None),
+            rated("poor", &[], 0, Some((10, 2.0))),
+        ];
+
+        let plain = nostr_scores(&federations, RatingAlgorithm::PlainAvg);
+        assert_eq!(plain, vec![1.0, 0.9, 0.0, 0.4]);
+
+        // Many good votes beat a single perfect one
+        let bayesian = nostr_scores(&federations, RatingAlgorithm::Bayesian);
+        assert!(bayesian[1] > bayesian[0]);
+        // Unrated federations get the mean of all votes
+        let mean = (5.0 + 20.0 * 4.5 + 10.0 * 2.0) / 31.0 / 5.0;
+        assert!((bayesian[2] - mean).abs() < 1e-9);
+
+        let wilson = nostr_scores(&federations, RatingAlgorithm::Wilson);
+        assert!(wilson[1] > wilson[0]);
+        assert_eq!(wilson[2], 0.0);
+        assert!(wilson.iter().all(|score| (0.0..=1.0).contains(score)));
+    }
+}
@@ -0,0 +1,828 @@
+//! Typed async client for the Fedimint Observer API, usable from native Rust
+//! services and from wasm.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use chrono::NaiveDate;
+use fedimint_core::config::{FederationId, JsonClientConfig};
+use fedimint_core::invite_code::InviteCode;
+use fedimint_core::task::sleep;
+use fedimint_core::{PeerId, TransactionId};
+use fmo_api_types::{
+    AlertEvent, AlertSettings, BlockTime, ChainSyncStatus, Deposit, FederationActivity,
+    FederationCompleteness, FederationFees, FederationGateway, FederationHealth,
+    FederationSearchResult, FederationSolvency, FederationSummary, FederationUtxo, FedimintTotals,
+    FedimintdVersionCount, GuardianContribution, GuardianHealth, GuardianHealthBucket,
+    GuardianIncident, HealthEvent, InstanceSettings, InviteCheck, LightningStats, MetaHistoryEntry,
+    MetaLint, MintDenomination, NostrAnnouncement, NostrArchivedEvent, NostrFederationStatus,
+    NostrFederationSummary, ObserverStatus, Page, PrivacyScore, RatingAlgorithm, SessionDetails,
+    SessionSummary, Timeseries, TimeseriesMetric, TimeseriesResolution, TransactionDetails,
+    TransactionGraph, VersionInfo, Withdrawal, WithdrawalConcentration,
+    WithdrawalConcentrationPeriod, WithdrawalTransaction,
+};
+use nostr_sdk::Event;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use tracing::debug;
+
+const DEFAULT_MAX_RETRIES: usize = 3;
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Filters of [`FmoClient::search_federations`], unset filters match all
+/// federations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FederationSearchFilters {
+    pub network: Option<String>,
+    /// Module kinds that all have to be present
+    pub modules: BTreeSet<String>,
+    pub health: Option<FederationHealth>,
+    /// Rating algorithm ordering equally good matches instead of the
+    /// instance's default
+    pub rating: Option<RatingAlgorithm>,
+}
+
+/// Filters of [`FmoClient::nostr_events`], unset filters match all events
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NostrEventFilters {
+    /// 38173 for announcements or 38000 for recommendations
+    pub kind: Option<u16>,
+    /// Unix timestamp events have to be created at or after
+    pub since: Option<i64>,
+    pub federation_id: Option<FederationId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FmoClient {
+    base_url: String,
+    http: reqwest::Client,
+    admin_token: Option<String>,
+    max_retries: usize,
+}
+
+impl FmoClient {
+    /// Creates a client for the observer API reachable at `base_url`, e.g.
+    /// `https://observer.fedimint.org/api`
+    pub fn new(base_url: impl Into<String>) -> FmoClient {
+        FmoClient {
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            http: reqwest::Client::new(),
+            admin_token: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Sets the admin token used for endpoints that modify the observer
+    pub fn with_admin_token(mut self, admin_token: impl Into<String>) -> FmoClient {
+        self.admin_token = Some(admin_token.into());
+        self
+    }
+
+    /// Sets how often failed requests are retried with exponential backoff.
+    /// Only network errors and server errors (5xx) are retried.
+    pub fn with_max_retries(mut self, max_retries: usize) -> FmoClient {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn version(&self) -> anyhow::Result<VersionInfo> {
+        self.get("/version").await
+    }
+
+    pub async fn instance_settings(&self) -> anyhow::Result<InstanceSettings> {
+        self.get("/instance/settings").await
+    }
+
+    pub async fn federations(&self) -> anyhow::Result<Vec<FederationSummary>> {
+        self.get("/federations").await
+    }
+
+    /// Federations ranked with `algorithm` instead of the instance's default
+    pub async fn federations_rated_by(
+        &self,
+        algorithm: RatingAlgorithm,
+    ) -> anyhow::Result<Vec<FederationSummary>> {
+        self.get_with_query("/federations", &[("rating", algorithm.as_str().to_owned())])
+            .await
+    }
+
+    /// Searches federations by name, federation id prefix or invite code. An
+    /// empty query returns all federations matching the filters.
+    pub async fn search_federations(
+        &self,
+        query: &str,
+        filters: &FederationSearchFilters,
+    ) -> anyhow::Result<Vec<FederationSearchResult>> {
+        let mut params = vec![("q", query.to_owned())];
+        if let Some(network) = &filters.network {
+            params.push(("network", network.clone()));
+        }
+        if !filters.modules.is_empty() {
+            params.push((
+                "modules",
+                filters
+                    .modules
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ));
+        }
+        if let Some(health) = filters.health {
+            params.push(("health", health.as_str().to_owned()));
+        }
+        if let Some(rating) = filters.rating {
+            params.push(("rating", rating.as_str().to_owned()));
+        }
+        self.get_with_query("/federations/search", &params).await
+    }
+
+    pub async fn federation_totals(&self) -> anyhow::Result<FedimintTotals> {
+        self.get("/federations/totals").await
+    }
+
+    pub async fn federation_config(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<JsonClientConfig> {
+        self.get(&format!("/federations/{federation_id}/config"))
+            .await
+    }
+
+    pub async fn federation_meta(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<BTreeMap<String, serde_json::Value>> {
+        self.get(&format!("/federations/{federation_id}/meta"))
+            .await
+    }
+
+    /// Recorded versions of the federation's meta fields, newest first
+    pub async fn federation_meta_history(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<MetaHistoryEntry>> {
+        self.get(&format!("/federations/{federation_id}/meta/history"))
+            .await
+    }
+
+    /// Guardian downtime incidents of the last `days` days, most recent first
+    pub async fn federation_incidents(
+        &self,
+        federation_id: FederationId,
+        days: u32,
+    ) -> anyhow::Result<Vec<GuardianIncident>> {
+        self.get(&format!(
+            "/federations/{federation_id}/incidents?days={days}"
+        ))
+        .await
+    }
+
+    pub async fn federation_meta_lint(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<MetaLint> {
+        self.get(&format!("/federations/{federation_id}/meta/lint"))
+            .await
+    }
+
+    /// URL of the federation's share card image, to be used e.g. in `<img>`
+    /// tags
+    pub fn federation_card_url(&self, federation_id: FederationId) -> String {
+        format!("{}/federations/{federation_id}/card.png", self.base_url)
+    }
+
+    pub async fn federation_health(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<BTreeMap<PeerId, GuardianHealth>> {
+        self.get(&format!("/federations/{federation_id}/health"))
+            .await
+    }
+
+    /// Uptime and latency history of the federation's guardians, optionally
+    /// only of one guardian
+    pub async fn federation_health_history(
+        &self,
+        federation_id: FederationId,
+        guardian: Option<PeerId>,
+        days: u32,
+    ) -> anyhow::Result<BTreeMap<PeerId, Vec<GuardianHealthBucket>>> {
+        let mut query = vec![("days", days.to_string())];
+        if let Some(guardian) = guardian {
+            query.push(("guardian", guardian.to_string()));
+        }
+        self.get_with_query(
+            &format!("/federations/{federation_id}/health/history"),
+            &query,
+        )
+        .await
+    }
+
+    /// Transitions of the federation's health state in the last `days` days,
+    /// most recent first
+    pub async fn federation_health_events(
+        &self,
+        federation_id: FederationId,
+        days: u32,
+    ) -> anyhow::Result<Vec<HealthEvent>> {
+        self.get(&format!(
+            "/federations/{federation_id}/health/events?days={days}"
+        ))
+        .await
+    }
+
+    pub async fn federation_peer_contribution(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<BTreeMap<PeerId, GuardianContribution>> {
+        self.get(&format!("/federations/{federation_id}/peers/contribution"))
+            .await
+    }
+
+    /// Daily transaction activity, merged into at most `max_points` buckets if
+    /// set. Days are bucketed in the IANA time zone `tz`, UTC by default.
+    pub async fn transaction_histogram(
+        &self,
+        federation_id: FederationId,
+        max_points: Option<usize>,
+        tz: Option<&str>,
+    ) -> anyhow::Result<BTreeMap<NaiveDate, FederationActivity>> {
+        let mut query = Vec::new();
+        if let Some(max_points) = max_points {
+            query.push(("max_points", max_points.to_string()));
+        }
+        if let Some(tz) = tz {
+            query.push(("tz", tz.to_owned()));
+        }
+        self.get_with_query(
+            &format!("/federations/{federation_id}/transactions/histogram"),
+            &query,
+        )
+        .await
+    }
+
+    /// Daily transaction activity summed over all observed federations,
+    /// limited to the last `days` days if set. Otherwise like
+    /// [`Self::transaction_histogram`].
+    pub async fn network_activity(
+        &self,
+        days: Option<u32>,
+        max_points: Option<usize>,
+        tz: Option<&str>,
+    ) -> anyhow::Result<BTreeMap<NaiveDate, FederationActivity>> {
+        let mut query = Vec::new();
+        if let Some(days) = days {
+            query.push(("days", days.to_string()));
+        }
+        if let Some(max_points) = max_points {
+            query.push(("max_points", max_points.to_string()));
+        }
+        if let Some(tz) = tz {
+            query.push(("tz", tz.to_owned()));
+        }
+        self.get_with_query("/federations/activity", &query).await
+    }
+
+    /// Ids of the federation's transactions in consensus order, paged by
+    /// `limit` and `offset`
+    pub async fn federation_transactions(
+        &self,
+        federation_id: FederationId,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Page<TransactionId>> {
+        self.get(&format!(
+            "/federations/{federation_id}/transactions?limit={limit}&offset={offset}"
+        ))
+        .await
+    }
+
+    /// Decoded inputs and outputs of a transaction
+    pub async fn transaction_details(
+        &self,
+        federation_id: FederationId,
+        transaction_id: TransactionId,
+    ) -> anyhow::Result<TransactionDetails> {
+        self.get(&format!(
+            "/federations/{federation_id}/transactions/{transaction_id}"
+        ))
+        .await
+    }
+
+    /// Flow of funds through a transaction, from the contracts and on-chain
+    /// transactions it spends to the ones it creates
+    pub async fn transaction_graph(
+        &self,
+        federation_id: FederationId,
+        transaction_id: TransactionId,
+    ) -> anyhow::Result<TransactionGraph> {
+        self.get(&format!(
+            "/federations/{federation_id}/transactions/{transaction_id}/graph"
+        ))
+        .await
+    }
+
+    /// Sessions with their item counts, most recent first
+    pub async fn federation_sessions(
+        &self,
+        federation_id: FederationId,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Page<SessionSummary>> {
+        self.get(&format!(
+            "/federations/{federation_id}/sessions?limit={limit}&offset={offset}"
+        ))
+        .await
+    }
+
+    /// Decoded consensus items of a session
+    pub async fn session_details(
+        &self,
+        federation_id: FederationId,
+        session_index: u64,
+    ) -> anyhow::Result<SessionDetails> {
+        self.get(&format!(
+            "/federations/{federation_id}/sessions/{session_index}"
+        ))
+        .await
+    }
+
+    pub async fn federation_transaction_count(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<u64> {
+        self.get(&format!("/federations/{federation_id}/transactions/count"))
+            .await
+    }
+
+    pub async fn federation_utxos(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<FederationUtxo>> {
+        self.get(&format!("/federations/{federation_id}/utxos"))
+            .await
+    }
+
+    pub async fn federation_fees(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationFees> {
+        self.get(&format!("/federations/{federation_id}/fees"))
+            .await
+    }
+
+    /// E-cash notes issued and redeemed per denomination, smallest first
+    pub async fn mint_denominations(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<MintDenomination>> {
+        self.get(&format!("/federations/{federation_id}/mint/denominations"))
+            .await
+    }
+
+    pub async fn federation_gateways(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<FederationGateway>> {
+        self.get(&format!("/federations/{federation_id}/gateways"))
+            .await
+    }
+
+    pub async fn federation_ln_stats(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<LightningStats> {
+        self.get(&format!("/federations/{federation_id}/ln/stats"))
+            .await
+    }
+
+    pub async fn privacy_score(&self, federation_id: FederationId) -> anyhow::Result<PrivacyScore> {
+        self.get(&format!("/federations/{federation_id}/privacy_score"))
+            .await
+    }
+
+    pub async fn federation_solvency(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationSolvency> {
+        self.get(&format!("/federations/{federation_id}/solvency"))
+            .await
+    }
+
+    /// Missing sessions and data the observer couldn't derive yet
+    /// Latest nostr announcement of the federation, `None` if it wasn't
+    /// announced
+    pub async fn federation_announcement(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<NostrAnnouncement>> {
+        self.get(&format!("/federations/{federation_id}/announcement"))
+            .await
+    }
+
+    pub async fn federation_completeness(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationCompleteness> {
+        self.get(&format!("/federations/{federation_id}/completeness"))
+            .await
+    }
+
+    /// `metrics` of a federation aligned to buckets of the given resolution
+    pub async fn federation_timeseries(
+        &self,
+        federation_id: FederationId,
+        metrics: &[TimeseriesMetric],
+        resolution: TimeseriesResolution,
+    ) -> anyhow::Result<Timeseries> {
+        let metrics = metrics
+            .iter()
+            .map(|metric| metric.name())
+            .collect::<Vec<_>>()
+            .join(",");
+        let resolution = resolution_name(resolution);
+        self.get(&format!(
+            "/federations/{federation_id}/timeseries?metrics={metrics}&resolution={resolution}"
+        ))
+        .await
+    }
+
+    /// On-chain deposits, most recent first, optionally limited to the days
+    /// between `from` and `to` (inclusive, UTC)
+    pub async fn deposits(
+        &self,
+        federation_id: FederationId,
+        limit: u64,
+        offset: u64,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> anyhow::Result<Page<Deposit>> {
+        let mut path =
+            format!("/federations/{federation_id}/deposits?limit={limit}&offset={offset}");
+        if let Some(from) = from {
+            path.push_str(&format!("&from={from}"));
+        }
+        if let Some(to) = to {
+            path.push_str(&format!("&to={to}"));
+        }
+        self.get(&path).await
+    }
+
+    /// Peg-out requests, most recent first
+    pub async fn withdrawals(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<Withdrawal>> {
+        self.get(&format!("/federations/{federation_id}/withdrawals"))
+            .await
+    }
+
+    pub async fn withdrawal_concentration(
+        &self,
+        federation_id: FederationId,
+        days: Option<u32>,
+    ) -> anyhow::Result<WithdrawalConcentration> {
+        let mut path = format!("/federations/{federation_id}/withdrawals/concentration");
+        if let Some(days) = days {
+            path.push_str(&format!("?days={days}"));
+        }
+        self.get(&path).await
+    }
+
+    /// Withdrawal concentration of each period with peg-out requests
+    pub async fn withdrawal_concentration_history(
+        &self,
+        federation_id: FederationId,
+        resolution: TimeseriesResolution,
+    ) -> anyhow::Result<Vec<WithdrawalConcentrationPeriod>> {
+        self.get(&format!(
+            "/federations/{federation_id}/withdrawals/concentration/history?resolution={}",
+            resolution_name(resolution)
+        ))
+        .await
+    }
+
+    /// On-chain peg-out transactions including the ones replaced by fee bumps
+    pub async fn withdrawal_transactions(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<WithdrawalTransaction>> {
+        self.get(&format!(
+            "/federations/{federation_id}/withdrawals/transactions"
+        ))
+        .await
+    }
+
+    pub async fn federation_alerts(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<AlertEvent>> {
+        self.get(&format!("/federations/{federation_id}/alerts"))
+            .await
+    }
+
+    pub async fn alert_settings(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<AlertSettings> {
+        self.get(&format!("/federations/{federation_id}/alerts/settings"))
+            .await
+    }
+
+    pub async fn set_alert_settings(
+        &self,
+        federation_id: FederationId,
+        settings: &AlertSettings,
+    ) -> anyhow::Result<()> {
+        self.send_admin(
+            Method::PUT,
+            &format!("/federations/{federation_id}/alerts/settings"),
+            Some(settings),
+        )
+        .await
+    }
+
+    pub async fn observer_status(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<ObserverStatus> {
+        self.get(&format!("/federations/{federation_id}/observer"))
+            .await
+    }
+
+    pub async fn restart_observer(&self, federation_id: FederationId) -> anyhow::Result<()> {
+        self.send_admin::<()>(
+            Method::POST,
+            &format!("/federations/{federation_id}/observer/restart"),
+            None,
+        )
+        .await
+    }
+
+    /// Starts observing the federation behind `invite_code`
+    pub async fn add_federation(&self, invite_code: &InviteCode) -> anyhow::Result<FederationId> {
+        let response = self
+            .request(Method::PUT, "/federations", true, |request| {
+                request.json(&json!({ "invite": invite_code }))
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Stops observing a federation, if `purge` is set its history is deleted
+    /// too
+    pub async fn remove_federation(
+        &self,
+        federation_id: FederationId,
+        purge: bool,
+    ) -> anyhow::Result<()> {
+        self.send_admin::<()>(
+            Method::DELETE,
+            &format!("/federations/{federation_id}?purge={purge}"),
+            None,
+        )
+        .await
+    }
+
+    pub async fn mark_federation_shutdown(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        self.send_admin::<()>(
+            Method::PUT,
+            &format!("/federations/{federation_id}/shutdown"),
+            None,
+        )
+        .await
+    }
+
+    pub async fn views(&self) -> anyhow::Result<BTreeMap<String, Vec<FederationId>>> {
+        self.get("/views").await
+    }
+
+    pub async fn view_federations(&self, name: &str) -> anyhow::Result<Vec<FederationSummary>> {
+        self.get(&format!("/views/{name}/federations")).await
+    }
+
+    pub async fn chain_blocks(&self, from: u32, to: Option<u32>) -> anyhow::Result<Vec<BlockTime>> {
+        let mut path = format!("/chain/blocks?from={from}");
+        if let Some(to) = to {
+            path.push_str(&format!("&to={to}"));
+        }
+        self.get(&path).await
+    }
+
+    pub async fn chain_status(&self) -> anyhow::Result<ChainSyncStatus> {
+        self.get("/chain/status").await
+    }
+
+    /// Fetches the config of any federation, observed or not
+    pub async fn config_by_invite(
+        &self,
+        invite_code: &InviteCode,
+    ) -> anyhow::Result<JsonClientConfig> {
+        self.get(&format!("/config/{invite_code}")).await
+    }
+
+    pub async fn meta_by_invite(
+        &self,
+        invite_code: &InviteCode,
+    ) -> anyhow::Result<BTreeMap<String, serde_json::Value>> {
+        self.get(&format!("/config/{invite_code}/meta")).await
+    }
+
+    /// Guardians per fedimintd version across all observed federations
+    pub async fn fedimintd_versions(&self) -> anyhow::Result<Vec<FedimintdVersionCount>> {
+        self.get("/versions").await
+    }
+
+    /// Contacts every guardian of any federation, observed or not,
+    /// individually
+    pub async fn check_invite(&self, invite_code: &InviteCode) -> anyhow::Result<InviteCheck> {
+        self.get(&format!("/config/{invite_code}/check")).await
+    }
+
+    /// Federations announced on nostr
+    /// Federations announced via Nostr with their reachability as last
+    /// probed by the server
+    pub async fn nostr_federations(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, NostrFederationStatus>> {
+        self.get("/nostr/federations").await
+    }
+
+    /// Counts of federations announced via Nostr by reachability
+    pub async fn nostr_federation_summary(&self) -> anyhow::Result<NostrFederationSummary> {
+        self.get("/nostr/federations/summary").await
+    }
+
+    /// Raw announcement and recommendation events the server stored, oldest
+    /// first, paged by `limit` and `offset`
+    pub async fn nostr_events(
+        &self,
+        filters: &NostrEventFilters,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Page<NostrArchivedEvent>> {
+        let mut query = vec![("limit", limit.to_string()), ("offset", offset.to_string())];
+        if let Some(kind) = filters.kind {
+            query.push(("kind", kind.to_string()));
+        }
+        if let Some(since) = filters.since {
+            query.push(("since", since.to_string()));
+        }
+        if let Some(federation_id) = filters.federation_id {
+            query.push(("federation_id", federation_id.to_string()));
+        }
+        self.get_with_query("/nostr/events", &query).await
+    }
+
+    /// Relays the observer syncs nostr events from
+    pub async fn nostr_relays(&self) -> anyhow::Result<Vec<String>> {
+        let response = self
+            .request(Method::GET, "/nostr/relays", true, |request| request)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn add_nostr_relay(&self, relay_url: &str) -> anyhow::Result<()> {
+        self.request(Method::PUT, "/nostr/relays", true, |request| {
+            request.query(&[("url", relay_url)])
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove_nostr_relay(&self, relay_url: &str) -> anyhow::Result<()> {
+        self.request(Method::DELETE, "/nostr/relays", true, |request| {
+            request.query(&[("url", relay_url)])
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn publish_federation_announcement(&self, event: &Event) -> anyhow::Result<()> {
+        self.send(Method::PUT, "/nostr/federations", Some(event))
+            .await
+    }
+
+    pub async fn publish_rating(&self, event: &Event) -> anyhow::Result<()> {
+        self.send(Method::PUT, "/federations/nostr/rating", Some(event))
+            .await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        self.get_with_query(path, &[]).await
+    }
+
+    /// Like [`FmoClient::get`], with URL encoded query parameters
+    async fn get_with_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> anyhow::Result<T> {
+        let response = self
+            .request(Method::GET, path, false, |request| request.query(query))
+            .await?;
+        response
+            .json()
+            .await
+            .with_context(|| format!("Invalid response from {path}"))
+    }
+
+    async fn send<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> anyhow::Result<()> {
+        self.request(method, path, false, |request| match body {
+            Some(body) => request.json(body),
+            None => request,
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn send_admin<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> anyhow::Result<()> {
+        self.request(method, path, true, |request| match body {
+            Some(body) => request.json(body),
+            None => request,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Sends a request, retrying network and server errors with exponential
+    /// backoff. Client errors are returned immediately including the error
+    /// message of the server.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        admin: bool,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> anyhow::Result<reqwest::Response> {
+        let url = format!("{}{path}", self.base_url);
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 0;
+
+        loop {
+            let mut request = build(self.http.request(method.clone(), &url));
+            if admin {
+                let admin_token = self
+                    .admin_token
+                    .as_deref()
+                    .context("Admin token required for this request")?;
+                request = request.bearer_auth(admin_token);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if response.status().is_server_error() => {
+                    anyhow::anyhow!("Server error {}", response.status())
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let message = response.text().await.unwrap_or_default();
+                    match status {
+                        StatusCode::UNAUTHORIZED => bail!("Unauthorized: {message}"),
+                        _ => bail!("Request to {path} failed with {status}: {message}"),
+                    }
+                }
+                Err(e) => e.into(),
+            };
+
+            if attempt >= self.max_retries {
+                return Err(error.context(format!("Request to {path} failed")));
+            }
+            attempt += 1;
+            debug!(%url, attempt, "Request failed, retrying in {delay:?}: {error}");
+            sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+}
+
+/// Query parameter value of a resolution
+fn resolution_name(resolution: TimeseriesResolution) -> &'static str {
+    match resolution {
+        TimeseriesResolution::Day => "day",
+        TimeseriesResolution::Week => "week",
+        TimeseriesResolution::Month => "month",
+    }
+}
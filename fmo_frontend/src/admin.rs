@@ -0,0 +1,41 @@
+use fedimint_core::config::FederationId;
+use fedimint_core::invite_code::InviteCode;
+
+use crate::util::local_storage;
+
+/// Local storage key under which the admin token of the observer instance is
+/// kept
+const ADMIN_TOKEN_KEY: &str = "fmo_admin_token";
+
+/// Returns the admin token configured in this browser, if any
+pub fn admin_token() -> Option<String> {
+    local_storage()?
+        .get_item(ADMIN_TOKEN_KEY)
+        .ok()?
+        .filter(|token| !token.is_empty())
+}
+
+/// Stores or, if `None`, removes the admin token in the browser's local
+/// storage
+pub fn set_admin_token(token: Option<&str>) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+
+    // Failing to persist the token only means the user has to enter it again
+    let _ = match token {
+        Some(token) => storage.set_item(ADMIN_TOKEN_KEY, token),
+        None => storage.remove_item(ADMIN_TOKEN_KEY),
+    };
+}
+
+/// Asks the observer to start observing the federation behind `invite_code`
+pub async fn observe_federation(
+    admin_token: &str,
+    invite_code: &InviteCode,
+) -> anyhow::Result<FederationId> {
+    crate::client()
+        .with_admin_token(admin_token)
+        .add_federation(invite_code)
+        .await
+}
@@ -0,0 +1,75 @@
+use leptos::html::Input;
+use leptos::{
+    component, create_node_ref, view, IntoView, RwSignal, SignalGet, SignalGetUntracked, SignalSet,
+};
+
+use crate::admin::set_admin_token;
+use crate::components::button::{Button, SECONDARY_BUTTON};
+
+/// Lets the operator of the observer instance store their admin token in the
+/// browser to unlock admin actions like observing new federations
+#[component]
+pub fn AdminToken(admin_token: RwSignal<Option<String>>) -> impl IntoView {
+    let token_input_ref = create_node_ref::<Input>();
+
+    let save_token = move || {
+        let token = token_input_ref
+            .get_untracked()
+            .expect("token_input_ref should be loaded by now")
+            .value();
+        let token = Some(token.trim().to_owned()).filter(|token| !token.is_empty());
+
+        set_admin_token(token.as_deref());
+        admin_token.set(token);
+    };
+
+    let clear_token = move || {
+        set_admin_token(None);
+        admin_token.set(None);
+    };
+
+    view! {
+        <div class="p-5 pt-0 dark:text-white dark:bg-gray-800">
+            { move || if admin_token.get().is_some() {
+                view! {
+                    <div class="flex gap-2 items-center">
+                        <p class="flex-1 text-sm text-gray-500 dark:text-gray-400">
+                            "Admin token configured, you can add federations to this observer instance"
+                        </p>
+                        <Button
+                            on_click=clear_token
+                            color_scheme=SECONDARY_BUTTON
+                            class="h-11"
+                        >
+                            Forget Token
+                        </Button>
+                    </div>
+                }.into_view()
+            } else {
+                view! {
+                    <form
+                        class="flex gap-2 items-center"
+                        on:submit=move |ev| {
+                            ev.prevent_default();
+                            save_token();
+                        }
+                    >
+                        <input
+                            _ref=token_input_ref
+                            placeholder="Admin token (optional)"
+                            type="password"
+                            class="flex-1 block px-2.5 h-11 text-sm text-gray-900 bg-transparent rounded-lg border border-gray-300 dark:text-white dark:border-gray-600 focus:outline-none focus:ring-0 focus:border-blue-600 dark:focus:border-blue-500"
+                        />
+                        <Button
+                            on_click=save_token
+                            color_scheme=SECONDARY_BUTTON
+                            class="h-11"
+                        >
+                            Save Token
+                        </Button>
+                    </form>
+                }.into_view()
+            }}
+        </div>
+    }
+}
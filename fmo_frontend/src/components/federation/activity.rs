@@ -1,81 +1,192 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Display;
 use std::ops::Mul;
 use std::str::FromStr;
 
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
 use fedimint_core::config::FederationId;
 use fedimint_core::Amount;
 use fmo_api_types::FederationActivity;
 use itertools::Itertools;
 use leptos::{
-    component, create_effect, create_resource, create_signal, event_target_value, view, IntoView,
-    RwSignal, Show, SignalGet, SignalSet, SignalUpdate,
+    component, create_effect, create_resource, create_rw_signal, create_signal,
+    event_target_value, view, CollectView, IntoView, RwSignal, Show, Signal, SignalGet,
+    SignalSet, SignalUpdate, SignalWith,
 };
 
-use super::chart::TimeLineChart;
+use super::chart::{ComparisonChart, TimeLineChart};
 use crate::components::alert::{Alert, AlertLevel};
-use crate::util::AsBitcoin;
+use crate::instance::use_instance_settings;
+use crate::util::format_amount;
 
 #[component]
 pub fn ActivityChart(id: FederationId) -> impl IntoView {
+    let settings = use_instance_settings();
+    let compare_with = create_rw_signal(None::<FederationId>);
+
+    // A configured chart window already limits the number of points, so only
+    // downsample the full history. Compared histories aren't downsampled
+    // either, their buckets wouldn't line up.
     let history_resource = create_resource(
+        move || {
+            (
+                settings.with(|settings| settings.chart_window_days),
+                compare_with.with(Option::is_some),
+            )
+        },
+        move |(chart_window_days, comparing)| async move {
+            let max_points = (chart_window_days.is_none() && !comparing).then(max_chart_points);
+            fetch_federation_history(id, max_points)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+    let compare_resource = create_resource(
+        move || compare_with.get(),
+        move |compare_with| async move {
+            match compare_with {
+                Some(compare_id) => fetch_federation_history(compare_id, None)
+                    .await
+                    .map(|history| Some((compare_id, history))),
+                None => Ok(None),
+            }
+        },
+    );
+    let federations_resource = create_resource(
         || (),
-        move |()| async move {
-            fetch_federation_history(id)
+        |_| async move {
+            crate::client()
+                .federations()
                 .await
                 .map_err(|e| e.to_string())
         },
     );
 
+    let federation_name = move |federation_id: FederationId| {
+        federations_resource
+            .get()
+            .and_then(Result::ok)
+            .and_then(|federations| {
+                federations
+                    .into_iter()
+                    .find(|federation| federation.id == federation_id)
+                    .and_then(|federation| federation.name)
+            })
+            .unwrap_or_else(|| federation_id.to_string())
+    };
+    let compare_options = move || {
+        federations_resource
+            .get()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|federation| federation.id != id)
+            .map(|federation| {
+                let name = federation
+                    .name
+                    .unwrap_or_else(|| federation.id.to_string());
+                view! { <option value=federation.id.to_string()>{name}</option> }
+            })
+            .collect_view()
+    };
+
     view! {
+        <div class="flex items-center justify-end gap-2 my-4">
+            <label for="compare-federation" class="text-sm font-medium text-gray-900 dark:text-gray-300">
+                "Compare with"
+            </label>
+            <select
+                id="compare-federation"
+                class="bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block p-2.5 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white dark:focus:ring-blue-500 dark:focus:border-blue-500"
+                on:change=move |ev| {
+                    compare_with.set(FederationId::from_str(&event_target_value(&ev)).ok());
+                }
+            >
+                <option value="">"No other federation"</option>
+                {compare_options}
+            </select>
+        </div>
         {move || {
-            match history_resource.get() {
-                Some(Ok(history)) => view! { <ChartInner data=history/> }.into_view(),
-                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
-                None => view! { <p>"Loading ..."</p> }.into_view(),
+            match (history_resource.get(), compare_resource.get()) {
+                (Some(Ok(history)), Some(Ok(compare_history))) => {
+                    let chart_window_days = settings.with(|settings| settings.chart_window_days);
+                    let compare = compare_history
+                        .map(|(compare_id, compare_history)| ComparedFederation {
+                            name: federation_name(compare_id),
+                            data: limit_to_window(compare_history, chart_window_days),
+                        });
+                    view! {
+                        <ChartInner
+                            data=limit_to_window(history, chart_window_days)
+                            name=federation_name(id)
+                            compare=compare
+                        />
+                    }
+                        .into_view()
+                }
+                (Some(Err(e)), _) | (_, Some(Err(e))) => view! { <p>"Error: " {e}</p> }.into_view(),
+                _ => view! { <p>"Loading ..."</p> }.into_view(),
             }
         }}
     }
 }
 
+/// Activity summed over all observed federations, shown on the home page
 #[component]
-pub fn ChartInner(data: BTreeMap<NaiveDate, FederationActivity>) -> impl IntoView {
-    let (total_volume, volumes_btc) = {
-        let total = Amount::from_msats(
-            data.values()
-                .map(|data| data.amount_transferred.msats)
-                .sum::<u64>(),
-        );
-        let volumes_btc = data
-            .iter()
-            .map(|(date, data)| {
-                (
-                    NaiveDateTime::from(*date).and_utc(),
-                    data.amount_transferred.msats as f64 / 100_000_000_000.0,
+pub fn NetworkActivityChart() -> impl IntoView {
+    let settings = use_instance_settings();
+
+    // The server already limits the history to the chart window, so only the
+    // full history needs downsampling
+    let history_resource = create_resource(
+        move || settings.with(|settings| settings.chart_window_days),
+        move |chart_window_days| async move {
+            let max_points = chart_window_days.is_none().then(max_chart_points);
+            crate::client()
+                .network_activity(
+                    chart_window_days,
+                    max_points,
+                    browser_time_zone().as_deref(),
                 )
-            })
-            .collect::<Vec<_>>();
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
 
-        (total, volumes_btc)
-    };
+    move || match history_resource.get() {
+        Some(Ok(history)) => view! {
+            <ChartInner data=history name="All federations".to_owned()/>
+        }
+        .into_view(),
+        Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+        None => view! { <p>"Loading ..."</p> }.into_view(),
+    }
+}
 
-    let (total_transactions, transactions) = {
-        let total = data.values().map(|data| data.num_transactions).sum::<u64>();
-        let transactions = data
-            .iter()
-            .map(|(date, data)| {
-                (
-                    NaiveDateTime::from(*date).and_utc(),
-                    data.num_transactions as f64,
-                )
-            })
-            .collect::<Vec<_>>();
-        (total, transactions)
-    };
+/// Federation whose activity is overlaid on the chart
+#[derive(Debug, Clone)]
+pub struct ComparedFederation {
+    name: String,
+    data: BTreeMap<NaiveDate, FederationActivity>,
+}
+
+#[component]
+pub fn ChartInner(
+    data: BTreeMap<NaiveDate, FederationActivity>,
+    /// Name of the federation, only shown when comparing
+    name: String,
+    #[prop(default = None)] compare: Option<ComparedFederation>,
+) -> impl IntoView {
+    let (total_volume, total_transactions) = totals(&data);
+    let volumes_btc = daily_values(&data, ChartType::Volume);
+    let transactions = daily_values(&data, ChartType::Transactions);
+    let compare_totals = compare.as_ref().map(|compare| totals(&compare.data));
 
+    let settings = use_instance_settings();
     let (chart_type, set_chart_type) = create_signal(ChartType::Volume);
     let (filter_outliers, set_filter_outliers) = create_signal(true);
+    let (normalize, set_normalize) = create_signal(false);
+    let comparing = compare.is_some();
 
     let chart_name_signal = RwSignal::new("".to_owned());
     create_effect(move |_| {
@@ -93,6 +204,35 @@ pub fn ChartInner(data: BTreeMap<NaiveDate, FederationActivity>) -> impl IntoVie
         ChartType::Volume => volumes_btc.clone(),
         ChartType::Transactions => transactions.clone(),
     };
+    let chart = match compare {
+        None => view! { <TimeLineChart name=chart_name_signal data=chart_data /> }.into_view(),
+        Some(compare) => {
+            let compare_name = compare.name.clone();
+            let comparison_data = move || {
+                let chart_type = chart_type.get();
+                let mut aligned = align_activity(&data, &compare.data, chart_type);
+                if normalize.get() {
+                    normalize_series(&mut aligned);
+                }
+                aligned
+            };
+            view! {
+                <ComparisonChart
+                    name=chart_name_signal
+                    first_name=name.clone()
+                    second_name=compare_name
+                    data=Signal::derive(comparison_data)
+                />
+            }
+            .into_view()
+        }
+    };
+    let format_total = move |volume: Amount, transactions: u64| match chart_type.get() {
+        ChartType::Volume => {
+            format_amount(volume, settings.with(|settings| settings.display_unit), 6)
+        }
+        ChartType::Transactions => transactions.to_string(),
+    };
 
     view! {
         <Alert
@@ -105,9 +245,15 @@ pub fn ChartInner(data: BTreeMap<NaiveDate, FederationActivity>) -> impl IntoVie
                 <div>
                     <h5 class="leading-none text-3xl font-bold text-gray-900 dark:text-white pb-2">
                         {move || {
-                            match chart_type.get() {
-                                ChartType::Volume => total_volume.as_bitcoin(6).to_string(),
-                                ChartType::Transactions => total_transactions.to_string(),
+                            let total = format_total(total_volume, total_transactions);
+                            match compare_totals {
+                                Some((compare_volume, compare_transactions)) => {
+                                    format!(
+                                        "{total} vs {}",
+                                        format_total(compare_volume, compare_transactions),
+                                    )
+                                }
+                                None => total,
                             }
                         }}
 
@@ -122,7 +268,25 @@ pub fn ChartInner(data: BTreeMap<NaiveDate, FederationActivity>) -> impl IntoVie
 
                     </p>
                 </div>
-                <Show when=move || chart_type.get() == ChartType::Volume>
+                <Show when=move || comparing>
+                    <div class="flex items-center mb-4">
+                        <input
+                            id="normalize-checkbox"
+                            type="checkbox"
+                            class="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 dark:focus:ring-blue-600 dark:ring-offset-gray-800 focus:ring-2 dark:bg-gray-700 dark:border-gray-600"
+                            checked=move || normalize.get()
+                            on:change=move |_| set_normalize.update(|v| *v = !*v)
+                        />
+                        <label
+                            for="normalize-checkbox"
+                            class="ms-2 text-sm font-medium text-gray-900 dark:text-gray-300"
+                            title="Scale both federations to their own maximum to compare trends"
+                        >
+                            Normalize
+                        </label>
+                    </div>
+                </Show>
+                <Show when=move || !comparing && chart_type.get() == ChartType::Volume>
                     <div class="flex items-center mb-4">
                         <input
                             id="default-checkbox"
@@ -158,27 +322,116 @@ pub fn ChartInner(data: BTreeMap<NaiveDate, FederationActivity>) -> impl IntoVie
                 </div>
             </div>
 
-            <TimeLineChart name=chart_name_signal data=chart_data />
+            {chart}
 
         </div>
     }
 }
 
-async fn fetch_federation_history(
+fn totals(data: &BTreeMap<NaiveDate, FederationActivity>) -> (Amount, u64) {
+    let volume = Amount::from_msats(
+        data.values()
+            .map(|data| data.amount_transferred.msats)
+            .sum::<u64>(),
+    );
+    let transactions = data.values().map(|data| data.num_transactions).sum::<u64>();
+    (volume, transactions)
+}
+
+/// Daily volume in BTC or number of transactions
+pub(super) fn daily_values(
+    data: &BTreeMap<NaiveDate, FederationActivity>,
+    chart_type: ChartType,
+) -> Vec<(DateTime<Utc>, f64)> {
+    data.iter()
+        .map(|(date, activity)| {
+            (
+                NaiveDateTime::from(*date).and_utc(),
+                chart_value(activity, chart_type),
+            )
+        })
+        .collect()
+}
+
+fn chart_value(activity: &FederationActivity, chart_type: ChartType) -> f64 {
+    match chart_type {
+        ChartType::Volume => activity.amount_transferred.msats as f64 / 100_000_000_000.0,
+        ChartType::Transactions => activity.num_transactions as f64,
+    }
+}
+
+/// Puts the daily values of two federations on the same days, days on which
+/// only one of them had activity count as zero for the other
+fn align_activity(
+    first: &BTreeMap<NaiveDate, FederationActivity>,
+    second: &BTreeMap<NaiveDate, FederationActivity>,
+    chart_type: ChartType,
+) -> Vec<(DateTime<Utc>, f64, f64)> {
+    first
+        .keys()
+        .chain(second.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|date| {
+            let value = |data: &BTreeMap<NaiveDate, FederationActivity>| {
+                data.get(date)
+                    .map(|activity| chart_value(activity, chart_type))
+                    .unwrap_or_default()
+            };
+            (NaiveDateTime::from(*date).and_utc(), value(first), value(second))
+        })
+        .collect()
+}
+
+/// Scales both series to percent of their own maximum, so federations of very
+/// different size can be compared by their trend
+fn normalize_series(data: &mut [(DateTime<Utc>, f64, f64)]) {
+    let max_first = data.iter().map(|(_, first, _)| *first).fold(0.0, f64::max);
+    let max_second = data.iter().map(|(_, _, second)| *second).fold(0.0, f64::max);
+    for (_, first, second) in data.iter_mut() {
+        if max_first > 0.0 {
+            *first = *first / max_first * 100.0;
+        }
+        if max_second > 0.0 {
+            *second = *second / max_second * 100.0;
+        }
+    }
+}
+
+pub(super) async fn fetch_federation_history(
     federation_id: FederationId,
+    max_points: Option<usize>,
 ) -> Result<BTreeMap<NaiveDate, FederationActivity>, String> {
-    let url = format!(
-        "{}/federations/{}/transactions/histogram",
-        crate::BASE_URL,
-        federation_id
-    );
-    let res = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    let json = res.json().await.map_err(|e| e.to_string())?;
-    Ok(json)
+    crate::client()
+        .transaction_histogram(federation_id, max_points, browser_time_zone().as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// IANA name of the browser's time zone, so days in the chart match the
+/// user's local calendar
+fn browser_time_zone() -> Option<String> {
+    let options = js_sys::Intl::DateTimeFormat::new(&js_sys::Array::new(), &js_sys::Object::new())
+        .resolved_options();
+    js_sys::Reflect::get(&options, &"timeZone".into())
+        .ok()?
+        .as_string()
+}
+
+/// Roughly one point per 3 pixels of screen width, more isn't visible anyway
+/// and makes rendering sluggish on mobile devices
+fn max_chart_points() -> usize {
+    let width = leptos::window()
+        .inner_width()
+        .ok()
+        .and_then(|width| width.as_f64())
+        .unwrap_or(1000.0);
+
+    ((width / 3.0) as usize).clamp(60, 365)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum ChartType {
+pub(super) enum ChartType {
     Volume,
     Transactions,
 }
@@ -204,6 +457,24 @@ impl Display for ChartType {
     }
 }
 
+/// Only keeps the entries of the last `days` days, if set
+fn limit_to_window(
+    data: BTreeMap<NaiveDate, FederationActivity>,
+    days: Option<u32>,
+) -> BTreeMap<NaiveDate, FederationActivity> {
+    let Some(first_day) = days.and_then(|days| {
+        Utc::now()
+            .date_naive()
+            .checked_sub_days(Days::new(days.into()))
+    }) else {
+        return data;
+    };
+
+    data.into_iter()
+        .filter(|(date, _)| *date > first_day)
+        .collect()
+}
+
 fn remove_outliers<T>(data: Vec<(DateTime<Utc>, T)>) -> Vec<(DateTime<Utc>, T)>
 where
     T: Copy + PartialOrd + Mul<Output = T> + From<u8>,
@@ -0,0 +1,67 @@
+use fedimint_core::config::FederationId;
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet};
+
+/// Alerts raised for a federation, most recent first
+#[component]
+pub fn AlertHistory(federation_id: FederationId) -> impl IntoView {
+    let alerts_resource = create_resource(
+        || (),
+        move |()| async move {
+            crate::client()
+                .federation_alerts(federation_id)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    view! {
+        {move || {
+            match alerts_resource.get() {
+                Some(Ok(alerts)) if alerts.is_empty() => {
+                    view! {
+                        <p class="my-4 text-gray-500 dark:text-gray-400">"No alerts were raised"</p>
+                    }
+                        .into_view()
+                }
+                Some(Ok(alerts)) => {
+                    let rows = alerts
+                        .into_iter()
+                        .map(|alert| {
+                            view! {
+                                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                                    <td class="px-6 py-4">
+                                        {alert.raised_at.format("%Y-%m-%d %H:%M").to_string()}
+                                    </td>
+                                    <td class="px-6 py-4">{format!("{:?}", alert.kind)}</td>
+                                    <td class="px-6 py-4">
+                                        {alert
+                                            .guardian_id
+                                            .map(|guardian_id| guardian_id.to_string())
+                                            .unwrap_or_else(|| "-".to_owned())}
+                                    </td>
+                                    <td class="px-6 py-4">{alert.message}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view();
+                    view! {
+                        <table class="w-full my-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                            <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Raised at (UTC)"</th>
+                                    <th scope="col" class="px-6 py-3">"Kind"</th>
+                                    <th scope="col" class="px-6 py-3">"Guardian"</th>
+                                    <th scope="col" class="px-6 py-3">"Message"</th>
+                                </tr>
+                            </thead>
+                            <tbody>{rows}</tbody>
+                        </table>
+                    }
+                        .into_view()
+                }
+                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                None => view! { <p>"Loading ..."</p> }.into_view(),
+            }
+        }}
+    }
+}
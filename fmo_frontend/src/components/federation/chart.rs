@@ -57,3 +57,63 @@ pub fn TimeLineChart(
         </div>
     }
 }
+
+/// Two series over the same timestamps, e.g. the activity of two federations
+#[component]
+pub fn ComparisonChart(
+    #[prop(into)] name: RwSignal<String>,
+    first_name: String,
+    second_name: String,
+    #[prop(into)] data: Signal<Vec<(DateTime<Utc>, f64, f64)>>,
+) -> impl IntoView {
+    let prefers_dark = use_preferred_dark();
+
+    let first_line = {
+        let mut line = Line::new(|data: &(DateTime<Utc>, f64, f64)| data.1)
+            .with_interpolation(Interpolation::Linear);
+        line.name = RwSignal::new(first_name);
+        line
+    };
+    let second_line = {
+        let mut line = Line::new(|data: &(DateTime<Utc>, f64, f64)| data.2)
+            .with_interpolation(Interpolation::Linear);
+        line.name = RwSignal::new(second_name);
+        line
+    };
+
+    let top_label = {
+        let mut label = RotatedLabel::middle("");
+        label.text = name;
+        label
+    };
+
+    view! {
+        <div style=move || {
+            if prefers_dark.get() {
+                "fill: white"
+            } else {
+                "fill: black"
+            }
+        }>
+            <Chart
+                aspect_ratio=AspectRatio::from_env_width(300.0)
+                top=top_label
+                left=TickLabels::aligned_floats().with_min_chars(6)
+                bottom=TickLabels::from_generator(Timestamps::from_period(Period::Month))
+                inner=[
+                    AxisMarker::left_edge().into_inner(),
+                    AxisMarker::bottom_edge().into_inner(),
+                    XGridLine::default().into_inner(),
+                    YGridLine::default().into_inner(),
+                    XGuideLine::over_data().into_inner(),
+                    YGuideLine::over_mouse().into_inner(),
+                ]
+                tooltip=Tooltip::left_cursor()
+                series=Series::new(|data: &(DateTime<Utc>, f64, f64)| data.0)
+                    .line(first_line)
+                    .line(second_line)
+                data=data
+            />
+        </div>
+    }
+}
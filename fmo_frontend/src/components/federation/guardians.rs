@@ -1,14 +1,11 @@
 use std::collections::BTreeMap;
 
 use fedimint_core::config::FederationId;
-use fedimint_core::util::backon::FibonacciBuilder;
-use fedimint_core::util::retry;
 use fedimint_core::{NumPeers, PeerId};
-use fmo_api_types::GuardianHealth;
+use fmo_api_types::{GuardianContribution, GuardianHealth};
 use leptos::{component, create_resource, view, IntoView, SignalGet};
 
 use crate::components::badge::{Badge, BadgeLevel};
-use crate::BASE_URL;
 
 #[component]
 pub fn Guardians(federation_id: FederationId, guardians: Vec<Guardian>) -> impl IntoView {
@@ -20,6 +17,11 @@ pub fn Guardians(federation_id: FederationId, guardians: Vec<Guardian>) -> impl
         move |()| async move { fetch_guardian_health(federation_id).await },
     );
 
+    let contribution_resource = create_resource(
+        || (),
+        move |()| async move { fetch_guardian_contribution(federation_id).await },
+    );
+
     let warn_if_true = |warn| {
         if warn {
             BadgeLevel::Warning
@@ -80,6 +82,68 @@ pub fn Guardians(federation_id: FederationId, guardians: Vec<Guardian>) -> impl
                                             }.into_view());
                                         }
 
+                                        if let Some(certificate) = health.certificate.filter(|certificate| certificate.expiring || certificate.error.is_some()) {
+                                            let (label, tooltip) = match (certificate.expires_at, certificate.error) {
+                                                (Some(expires_at), _) => (
+                                                    "Certificate expiring",
+                                                    format!("TLS certificate expires on {}", expires_at.format("%Y-%m-%d")),
+                                                ),
+                                                (None, error) => (
+                                                    "Certificate check failed",
+                                                    error.unwrap_or_default(),
+                                                ),
+                                            };
+                                            badges.push(view! {
+                                                <Badge level=BadgeLevel::Warning tooltip=Some(tooltip)>
+                                                    {label}
+                                                </Badge>
+                                            }.into_view());
+                                        }
+
+                                        if let Some(version) = health.fedimintd_version {
+                                            badges.push(view! {
+                                                <Badge level=BadgeLevel::Info>
+                                                    {format!("v{version}")}
+                                                </Badge>
+                                            }.into_view());
+                                        }
+
+                                        if let Some(signing) = health.withdrawal_signing {
+                                            let label = match signing.median_session_delay {
+                                                Some(delay) => format!("Signs peg-outs after {delay} sessions"),
+                                                None => "Never signed peg-outs".to_owned(),
+                                            };
+                                            badges.push(view! {
+                                                <Badge
+                                                    level=warn_if_true(signing.unsigned_withdrawals > 0)
+                                                    tooltip=Some(format!(
+                                                        "Signed {} of {} peg-outs, median delay since the request",
+                                                        signing.signed_withdrawals,
+                                                        signing.signed_withdrawals + signing.unsigned_withdrawals,
+                                                    ))
+                                                >
+                                                    {label}
+                                                </Badge>
+                                            }.into_view());
+                                        }
+
+                                        if let Some(Ok(contribution)) = contribution_resource.get() {
+                                            if let Some(contribution) = contribution.get(&PeerId::from(guardian_idx as u16)) {
+                                                badges.push(view! {
+                                                    <Badge
+                                                        level=BadgeLevel::Info
+                                                        tooltip=Some(format!(
+                                                            "Proposed {} consensus items, {} of them transactions",
+                                                            contribution.item_count,
+                                                            contribution.transaction_count,
+                                                        ))
+                                                    >
+                                                        {format!("{:.1}% of items", contribution.item_share * 100.0)}
+                                                    </Badge>
+                                                }.into_view());
+                                            }
+                                        }
+
                                         badges.into_view()
                                     }
                                     None => {
@@ -123,17 +187,18 @@ pub struct Guardian {
 }
 
 async fn fetch_guardian_health(id: FederationId) -> BTreeMap<PeerId, GuardianHealth> {
-    retry(
-        "fetching guardian health",
-        FibonacciBuilder::default().with_max_times(usize::MAX),
-        || async move {
-            reqwest::get(format!("{}/federations/{}/health", BASE_URL, id))
-                .await?
-                .json::<BTreeMap<PeerId, GuardianHealth>>()
-                .await
-                .map_err(Into::into)
-        },
-    )
-    .await
-    .expect("Will never return Err")
+    crate::client()
+        .with_max_retries(usize::MAX)
+        .federation_health(id)
+        .await
+        .expect("Will never return Err")
+}
+
+async fn fetch_guardian_contribution(
+    id: FederationId,
+) -> Result<BTreeMap<PeerId, GuardianContribution>, String> {
+    crate::client()
+        .federation_peer_contribution(id)
+        .await
+        .map_err(|e| e.to_string())
 }
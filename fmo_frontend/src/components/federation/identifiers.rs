@@ -0,0 +1,96 @@
+use fedimint_core::config::FederationId;
+use fmo_api_types::NostrAnnouncement;
+use leptos::{component, create_resource, view, IntoView, SignalGet};
+use qrcode::render::svg;
+use qrcode::QrCode;
+
+use crate::components::copyable::Copyable;
+
+/// Federation id as QR code and the NIP-19 identifiers of the federation's
+/// nostr announcement if it was announced
+#[component]
+pub fn Identifiers(federation_id: FederationId) -> impl IntoView {
+    let announcement_resource = create_resource(
+        || (),
+        move |()| fetch_federation_announcement(federation_id),
+    );
+
+    view! {
+        <div class="w-full p-4 bg-white border border-gray-200 rounded-lg shadow sm:p-8 dark:bg-gray-800 dark:border-gray-700">
+            <div class="flex items-center justify-between mb-4">
+                <h5 class="text-xl font-bold leading-none text-gray-900 dark:text-white">
+                    Identifiers
+                </h5>
+            </div>
+            <div class="flex flex-wrap gap-4 items-start">
+                <div
+                    class="w-40 h-40 bg-white p-1 rounded"
+                    inner_html=qr_code_svg(&federation_id.to_string())
+                ></div>
+                <div class="flex-1 min-w-[200px] text-sm text-gray-500 dark:text-gray-400">
+                    <p class="font-medium text-gray-900 dark:text-white">"Federation ID"</p>
+                    <Copyable text=federation_id.to_string()/>
+                    {move || match announcement_resource.get() {
+                        Some(Ok(Some(announcement))) => {
+                            view! { <AnnouncementIdentifiers announcement=announcement/> }.into_view()
+                        }
+                        Some(Ok(None)) => {
+                            view! { <p class="mt-4">"Not announced on nostr"</p> }.into_view()
+                        }
+                        Some(Err(e)) => view! { <p class="mt-4">"Error: " {e}</p> }.into_view(),
+                        None => view! {}.into_view(),
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn AnnouncementIdentifiers(announcement: NostrAnnouncement) -> impl IntoView {
+    let njump_url = format!("https://njump.me/{}", announcement.naddr);
+    let client_url = format!("nostr:{}", announcement.naddr);
+
+    view! {
+        <p class="mt-4 font-medium text-gray-900 dark:text-white">"Announcement (naddr)"</p>
+        <Copyable text=announcement.naddr/>
+        <p class="mt-2 font-medium text-gray-900 dark:text-white">"Announcement event (nevent)"</p>
+        <Copyable text=announcement.nevent/>
+        <p class="mt-2">
+            <a
+                href=client_url
+                class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+            >
+                "Open in nostr client"
+            </a>
+            " · "
+            <a
+                href=njump_url
+                target="_blank"
+                class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+            >
+                "View on njump"
+            </a>
+        </p>
+    }
+}
+
+fn qr_code_svg(data: &str) -> String {
+    QrCode::new(data.as_bytes())
+        .map(|code| {
+            code.render::<svg::Color>()
+                .min_dimensions(152, 152)
+                .quiet_zone(false)
+                .build()
+        })
+        .unwrap_or_default()
+}
+
+async fn fetch_federation_announcement(
+    federation_id: FederationId,
+) -> Result<Option<NostrAnnouncement>, String> {
+    crate::client()
+        .federation_announcement(federation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
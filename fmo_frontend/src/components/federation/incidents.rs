@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use fedimint_core::config::FederationId;
+use fmo_api_types::GuardianIncident;
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet};
+
+use crate::components::badge::{Badge, BadgeLevel};
+
+/// Days of incidents shown, like on a typical status page
+const INCIDENT_DAYS: u32 = 90;
+
+/// Guardian downtime incidents grouped by the day they started, most recent
+/// first
+#[component]
+pub fn Incidents(federation_id: FederationId) -> impl IntoView {
+    let incidents_resource = create_resource(
+        || (),
+        move |()| async move {
+            crate::client()
+                .federation_incidents(federation_id, INCIDENT_DAYS)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    view! {
+        {move || {
+            match incidents_resource.get() {
+                Some(Ok(incidents)) if incidents.is_empty() => {
+                    view! {
+                        <p class="my-4 text-gray-500 dark:text-gray-400">
+                            {format!("No incidents in the last {INCIDENT_DAYS} days")}
+                        </p>
+                    }
+                        .into_view()
+                }
+                Some(Ok(incidents)) => {
+                    let mut days = BTreeMap::<NaiveDate, Vec<GuardianIncident>>::new();
+                    for incident in incidents {
+                        days.entry(incident.started_at.date_naive()).or_default().push(incident);
+                    }
+                    days.into_iter()
+                        .rev()
+                        .map(|(day, incidents)| {
+                            view! {
+                                <div class="my-4">
+                                    <h3 class="pb-2 mb-2 font-semibold text-gray-900 border-b dark:text-white dark:border-gray-700">
+                                        {day.format("%b %-d, %Y").to_string()}
+                                    </h3>
+                                    <ul>
+                                        {incidents.into_iter().map(|incident| view! { <IncidentEntry incident=incident/> }).collect_view()}
+                                    </ul>
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }
+                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                None => view! { <p>"Loading ..."</p> }.into_view(),
+            }
+        }}
+    }
+}
+
+#[component]
+fn IncidentEntry(incident: GuardianIncident) -> impl IntoView {
+    let (level, status) = match incident.ended_at {
+        Some(_) => (BadgeLevel::Info, "Resolved"),
+        None => (BadgeLevel::Error, "Ongoing"),
+    };
+    let period = match incident.ended_at {
+        Some(ended_at) => format!(
+            "{} - {} UTC",
+            incident.started_at.format("%H:%M"),
+            ended_at.format("%Y-%m-%d %H:%M")
+        ),
+        None => format!("Since {} UTC", incident.started_at.format("%H:%M")),
+    };
+
+    view! {
+        <li class="flex flex-wrap items-center gap-2 py-1 text-sm text-gray-500 dark:text-gray-400">
+            <Badge level=level>{status}</Badge>
+            <span class="font-medium text-gray-900 dark:text-white">
+                {format!("{} unreachable", incident.guardian_name)}
+            </span>
+            <span>{period}</span>
+            <span>{format!("({})", format_duration(incident.duration_secs))}</span>
+        </li>
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, secs % 86_400 / 3600, secs % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{}m", minutes.max(1)),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
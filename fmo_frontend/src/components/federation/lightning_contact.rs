@@ -0,0 +1,45 @@
+use fedimint_core::config::FederationId;
+use fmo_api_types::MetaLint;
+use leptos::{component, create_resource, view, IntoView, SignalGet};
+
+use crate::components::badge::{Badge, BadgeLevel};
+
+/// Shown if the federation's meta contains a lightning contact that resolves
+/// to a working LNURL endpoint
+#[component]
+pub fn LightningContactBadge(federation_id: FederationId) -> impl IntoView {
+    let lint_resource = create_resource(|| (), move |()| fetch_meta_lint(federation_id));
+
+    view! {
+        {move || {
+            let verified_contacts = match lint_resource.get() {
+                Some(Ok(lint)) => lint
+                    .lightning_contacts
+                    .into_iter()
+                    .filter(|contact| contact.verified)
+                    .map(|contact| contact.contact)
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            };
+
+            (!verified_contacts.is_empty())
+                .then(|| {
+                    view! {
+                        <Badge
+                            level=BadgeLevel::Success
+                            tooltip=Some(format!("Resolves: {}", verified_contacts.join(", ")))
+                        >
+                            "⚡ Verified lightning contact"
+                        </Badge>
+                    }
+                })
+        }}
+    }
+}
+
+async fn fetch_meta_lint(federation_id: FederationId) -> Result<MetaLint, String> {
+    crate::client()
+        .federation_meta_lint(federation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,130 @@
+use chrono::NaiveDateTime;
+use fedimint_core::config::FederationId;
+use fmo_api_types::{TimeseriesMetric, TimeseriesResolution};
+use leptos::{
+    component, create_resource, create_rw_signal, event_target_value, view, CollectView,
+    IntoView, RwSignal, Signal, SignalGet, SignalSet, SignalUpdate,
+};
+
+use super::chart::TimeLineChart;
+
+/// Chart of user selected metrics of a federation, one line chart per metric
+/// sharing the same time buckets
+#[component]
+pub fn MetricsChart(federation_id: FederationId) -> impl IntoView {
+    let metrics = create_rw_signal(vec![TimeseriesMetric::Volume, TimeseriesMetric::Assets]);
+    let resolution = create_rw_signal(TimeseriesResolution::Week);
+
+    let timeseries_resource = create_resource(
+        move || (metrics.get(), resolution.get()),
+        move |(metrics, resolution)| async move {
+            crate::client()
+                .federation_timeseries(federation_id, &metrics, resolution)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    let metric_toggles = TimeseriesMetric::ALL
+        .into_iter()
+        .map(|metric| {
+            let id = format!("metric-{}", metric.name());
+            view! {
+                <div class="flex items-center">
+                    <input
+                        id=id.clone()
+                        type="checkbox"
+                        class="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 dark:focus:ring-blue-600 dark:ring-offset-gray-800 focus:ring-2 dark:bg-gray-700 dark:border-gray-600"
+                        checked=move || metrics.get().contains(&metric)
+                        on:change=move |_| {
+                            metrics
+                                .update(|metrics| {
+                                    if let Some(pos) = metrics.iter().position(|m| *m == metric) {
+                                        metrics.remove(pos);
+                                    } else {
+                                        metrics.push(metric);
+                                    }
+                                })
+                        }
+                    />
+                    <label for=id class="ms-2 text-sm font-medium text-gray-900 dark:text-gray-300">
+                        {metric_label(metric)}
+                    </label>
+                </div>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="flex flex-wrap items-center gap-4 my-4">
+            {metric_toggles}
+            <select
+                class="ms-auto bg-gray-50 border border-gray-300 text-gray-900 text-sm rounded-lg focus:ring-blue-500 focus:border-blue-500 block p-2.5 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white dark:focus:ring-blue-500 dark:focus:border-blue-500"
+                on:change=move |ev| {
+                    resolution
+                        .set(
+                            match event_target_value(&ev).as_str() {
+                                "day" => TimeseriesResolution::Day,
+                                "month" => TimeseriesResolution::Month,
+                                _ => TimeseriesResolution::Week,
+                            },
+                        )
+                }
+            >
+                <option value="day">"Daily"</option>
+                <option value="week" selected=true>"Weekly"</option>
+                <option value="month">"Monthly"</option>
+            </select>
+        </div>
+        {move || {
+            match timeseries_resource.get() {
+                Some(Ok(timeseries)) => {
+                    timeseries
+                        .series
+                        .into_iter()
+                        .map(|(metric, values)| {
+                            let data = timeseries
+                                .buckets
+                                .iter()
+                                .zip(values)
+                                .map(|(bucket, value)| {
+                                    (NaiveDateTime::from(*bucket).and_utc(), chart_value(metric, value))
+                                })
+                                .collect::<Vec<_>>();
+                            view! {
+                                <div class="w-full bg-white rounded-lg shadow dark:bg-gray-800 p-4 md:p-6 my-4">
+                                    <TimeLineChart
+                                        name=RwSignal::new(metric_label(metric).to_owned())
+                                        data=Signal::derive(move || data.clone())
+                                    />
+                                </div>
+                            }
+                        })
+                        .collect_view()
+                }
+                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                None => view! { <p>"Loading ..."</p> }.into_view(),
+            }
+        }}
+    }
+}
+
+fn metric_label(metric: TimeseriesMetric) -> &'static str {
+    match metric {
+        TimeseriesMetric::Volume => "Volume (BTC)",
+        TimeseriesMetric::TxCount => "Transactions",
+        TimeseriesMetric::Assets => "Assets (BTC)",
+        TimeseriesMetric::LnIn => "Lightning in (BTC)",
+        TimeseriesMetric::LnOut => "Lightning out (BTC)",
+        TimeseriesMetric::PegIns => "Peg-ins (BTC)",
+        TimeseriesMetric::PegOuts => "Peg-outs (BTC)",
+    }
+}
+
+/// Amounts are charted in BTC, counts as they are
+fn chart_value(metric: TimeseriesMetric, value: u64) -> f64 {
+    match metric {
+        TimeseriesMetric::TxCount => value as f64,
+        _ => value as f64 / 100_000_000_000.0,
+    }
+}
@@ -1,26 +1,47 @@
 mod activity;
+mod alert_history;
 mod chart;
 mod general;
 mod guardians;
+mod identifiers;
+mod incidents;
+mod lightning_contact;
+mod metrics;
 pub mod nostr_vote;
+mod previous_identities;
+mod privacy;
+mod report;
+mod sessions;
 pub mod stars_seletor;
 mod utxos;
+mod withdrawals;
 
 use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use fedimint_core::config::{FederationId, JsonClientConfig};
 use leptos::{component, create_resource, view, IntoView, Show, SignalGet, SignalWith};
-use leptos_meta::Title;
+use leptos_meta::{Meta, Title};
 use leptos_router::{use_params, Params, ParamsError, ParamsMap};
 use utxos::Utxos;
 
 use crate::components::federation::activity::ActivityChart;
+use crate::components::federation::alert_history::AlertHistory;
 use crate::components::federation::general::General;
 use crate::components::federation::guardians::{Guardian, Guardians};
+use crate::components::federation::identifiers::Identifiers;
+use crate::components::federation::incidents::Incidents;
+use crate::components::federation::lightning_contact::LightningContactBadge;
+use crate::components::federation::metrics::MetricsChart;
 use crate::components::federation::nostr_vote::NostrVote;
+use crate::components::federation::previous_identities::PreviousIdentities;
+use crate::components::federation::privacy::PrivacyScoreBadge;
+use crate::components::federation::sessions::Sessions;
+use crate::components::federation::withdrawals::Withdrawals;
 use crate::components::tabs::{Tab, Tabs};
-use crate::BASE_URL;
+
+pub use activity::NetworkActivityChart;
+pub use report::FederationReport;
 
 #[component]
 pub fn Federation() -> impl IntoView {
@@ -63,6 +84,11 @@ pub fn Federation() -> impl IntoView {
                         }
                     }
             />
+            // Only picked up by crawlers that execute JS, but it's the best we can do for a CSR app
+            <Meta
+                property="og:image"
+                content=move || crate::client().federation_card_url(id().unwrap())
+            />
             <div>
                 <h2 class="text-4xl my-8 font-extrabold dark:text-white truncate">
                     {move || {
@@ -78,6 +104,17 @@ pub fn Federation() -> impl IntoView {
                         }
                     }}
                 </h2>
+                <div class="mb-8 -mt-4">
+                    <PrivacyScoreBadge federation_id=id().unwrap()/>
+                    <LightningContactBadge federation_id=id().unwrap()/>
+                    <a
+                        href=move || format!("/federations/{}/report", id().unwrap())
+                        class="text-sm font-medium text-blue-600 dark:text-blue-500 hover:underline"
+                    >
+                        "Printable report"
+                    </a>
+                </div>
+                <PreviousIdentities federation_id=id().unwrap()/>
                 {move || {
                     match config_resource.get() {
                         Some(Ok(config)) => {
@@ -99,15 +136,32 @@ pub fn Federation() -> impl IntoView {
                                         <General config=config.clone() />
                                         <div class="h-4" />
                                         <NostrVote config=config.clone() />
+                                        <div class="h-4" />
+                                        <Identifiers federation_id=id().unwrap() />
                                     </div>
                                 </div>
                                 <Tabs default="Activity">
                                     <Tab name="Activity">
                                         <ActivityChart id=id().unwrap()/>
                                     </Tab>
+                                    <Tab name="Metrics">
+                                        <MetricsChart federation_id=id().unwrap()/>
+                                    </Tab>
+                                    <Tab name="Sessions">
+                                        <Sessions federation_id=id().unwrap()/>
+                                    </Tab>
                                     <Tab name="UTXOs">
                                         <Utxos federation_id=id().unwrap()/>
                                     </Tab>
+                                    <Tab name="Withdrawals">
+                                        <Withdrawals federation_id=id().unwrap()/>
+                                    </Tab>
+                                    <Tab name="Alerts">
+                                        <AlertHistory federation_id=id().unwrap()/>
+                                    </Tab>
+                                    <Tab name="Incidents">
+                                        <Incidents federation_id=id().unwrap()/>
+                                    </Tab>
                                     <Tab name="Config">
                                         <div class="w-full overflow-x-scroll my-4">
                                             <pre class="dark:text-white">
@@ -145,19 +199,11 @@ impl Params for FederationParams {
 }
 
 async fn fetch_federation_config(id: FederationId) -> Result<JsonClientConfig, anyhow::Error> {
-    reqwest::get(format!("{}/federations/{}/config", BASE_URL, id))
-        .await?
-        .json()
-        .await
-        .map_err(Into::into)
+    crate::client().federation_config(id).await
 }
 
 async fn fetch_federation_meta(
     id: FederationId,
 ) -> Result<BTreeMap<String, serde_json::Value>, anyhow::Error> {
-    reqwest::get(format!("{}/federations/{}/meta", BASE_URL, id))
-        .await?
-        .json()
-        .await
-        .map_err(Into::into)
+    crate::client().federation_meta(id).await
 }
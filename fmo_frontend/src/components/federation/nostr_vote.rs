@@ -1,15 +1,12 @@
-use anyhow::ensure;
 use fedimint_core::config::{FederationId, JsonClientConfig};
 use leptos::{
     component, create_action, create_signal, event_target_value, view, IntoView, SignalGet,
     SignalSet,
 };
 use nostr_sdk::{EventBuilder, Kind, SingleLetterTag, Tag, TagKind};
-use reqwest::StatusCode;
 
 use crate::components::alert::{Alert, AlertLevel};
 use crate::components::federation::stars_seletor::StarsSelector;
-use crate::BASE_URL;
 
 #[component]
 pub fn NostrVote(config: JsonClientConfig) -> impl IntoView {
@@ -145,19 +142,5 @@ async fn sign_and_publish_rating(
 
     let event = signer.sign_event(unsigned_event).await?;
 
-    let client = reqwest::Client::new();
-    let response = client
-        .put(format!("{}/federations/nostr/rating", BASE_URL))
-        .json(&event)
-        .send()
-        .await?;
-
-    let status = response.status();
-    ensure!(
-        status == StatusCode::OK,
-        "Unexpected status code {}",
-        status
-    );
-
-    Ok(())
+    crate::client().publish_rating(&event).await
 }
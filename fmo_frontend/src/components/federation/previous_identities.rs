@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fmo_api_types::MetaHistoryEntry;
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet};
+
+const NAME_FIELD: &str = "federation_name";
+const ICON_FIELD: &str = "federation_icon_url";
+
+/// Name and icon a federation used until it changed one of them
+#[derive(Debug, Clone)]
+struct PreviousIdentity {
+    name: Option<String>,
+    icon_url: Option<String>,
+    until: DateTime<Utc>,
+}
+
+/// "Previously known as" notice if the federation changed its name or icon,
+/// so users notice a federation rebranding as another one. Renders nothing
+/// otherwise.
+#[component]
+pub fn PreviousIdentities(federation_id: FederationId) -> impl IntoView {
+    let history_resource = create_resource(
+        || (),
+        move |()| async move {
+            crate::client()
+                .federation_meta_history(federation_id)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    move || {
+        let identities = match history_resource.get() {
+            Some(Ok(history)) => previous_identities(&history),
+            _ => vec![],
+        };
+        if identities.is_empty() {
+            return view! {}.into_view();
+        }
+
+        view! {
+            <div
+                class="mb-8 p-4 text-sm text-yellow-800 rounded-lg bg-yellow-50 dark:bg-gray-800 dark:text-yellow-300"
+                role="alert"
+            >
+                <p class="font-medium mb-2">"Previously known as"</p>
+                <ul class="space-y-1">
+                    {identities
+                        .into_iter()
+                        .map(|identity| {
+                            view! {
+                                <li class="flex items-center gap-2">
+                                    {identity
+                                        .icon_url
+                                        .map(|icon_url| {
+                                            view! {
+                                                <img src=icon_url alt="" class="w-5 h-5 rounded-full"/>
+                                            }
+                                        })}
+                                    <span class="font-medium">
+                                        {identity.name.unwrap_or_else(|| "Unnamed".to_owned())}
+                                    </span>
+                                    <span>{format!("until {}", identity.until.format("%Y-%m-%d"))}</span>
+                                </li>
+                            }
+                        })
+                        .collect_view()}
+                </ul>
+            </div>
+        }
+        .into_view()
+    }
+}
+
+/// Identities before each name or icon change, most recent first. `history`
+/// is ordered newest first, each entry lists the fields changed since the one
+/// after it.
+fn previous_identities(history: &[MetaHistoryEntry]) -> Vec<PreviousIdentity> {
+    let field = |entry: &MetaHistoryEntry, field: &str| {
+        entry
+            .meta
+            .get(field)
+            .and_then(|value| value.as_str())
+            .map(ToOwned::to_owned)
+    };
+
+    history
+        .windows(2)
+        .filter(|entries| {
+            entries[0]
+                .changed_fields
+                .iter()
+                .any(|changed| changed == NAME_FIELD || changed == ICON_FIELD)
+        })
+        .map(|entries| PreviousIdentity {
+            name: field(&entries[1], NAME_FIELD),
+            icon_url: field(&entries[1], ICON_FIELD),
+            until: entries[0].recorded_at,
+        })
+        .collect()
+}
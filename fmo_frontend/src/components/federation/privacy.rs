@@ -0,0 +1,128 @@
+use fedimint_core::config::FederationId;
+use fmo_api_types::{PrivacyScore, PrivacyScoreComponent};
+use leptos::{
+    component, create_resource, create_signal, view, IntoView, Show, SignalGet, SignalSet,
+};
+
+use crate::components::badge::{Badge, BadgeLevel};
+
+#[component]
+pub fn PrivacyScoreBadge(federation_id: FederationId) -> impl IntoView {
+    let score_resource = create_resource(|| (), move |()| fetch_privacy_score(federation_id));
+    let (show_explainer, set_show_explainer) = create_signal(false);
+
+    view! {
+        {move || {
+            match score_resource.get() {
+                Some(Ok(privacy_score)) => {
+                    let (level, text) = match privacy_score.score {
+                        Some(score) if score >= 70.0 => (BadgeLevel::Success, format!("Privacy {score:.0}/100")),
+                        Some(score) if score >= 40.0 => (BadgeLevel::Warning, format!("Privacy {score:.0}/100")),
+                        Some(score) => (BadgeLevel::Error, format!("Privacy {score:.0}/100")),
+                        None => (BadgeLevel::Info, "Privacy n/a".to_owned()),
+                    };
+                    view! {
+                        <button type="button" on:click=move |_| set_show_explainer.set(true)>
+                            <Badge level=level>{text}</Badge>
+                        </button>
+                        <Show when=move || show_explainer.get()>
+                            <PrivacyExplainer
+                                privacy_score=privacy_score.clone()
+                                on_close=move || set_show_explainer.set(false)
+                            />
+                        </Show>
+                    }
+                        .into_view()
+                }
+                Some(Err(e)) => view! { <span class="dark:text-white">"Error: " {e}</span> }.into_view(),
+                None => view! {}.into_view(),
+            }
+        }}
+    }
+}
+
+#[component]
+fn PrivacyExplainer(privacy_score: PrivacyScore, on_close: impl Fn() + 'static) -> impl IntoView {
+    view! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-gray-900/50">
+            <div class="w-full max-w-2xl p-6 bg-white rounded-lg shadow dark:bg-gray-800">
+                <div class="flex items-center justify-between mb-4">
+                    <h3 class="text-xl font-semibold text-gray-900 dark:text-white">
+                        Privacy Score
+                    </h3>
+                    <button
+                        type="button"
+                        class="text-gray-400 hover:text-gray-900 dark:hover:text-white"
+                        on:click=move |_| on_close()
+                    >
+                        "✕"
+                    </button>
+                </div>
+                <p class="mb-4 text-sm text-gray-500 dark:text-gray-400">
+                    "A heuristic estimate of how well the federation's activity protects its users' privacy, \
+                    derived only from public consensus history and on-chain data. Components without any \
+                    activity are left out of the overall score."
+                </p>
+                <table class="w-full text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                    <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                        <tr>
+                            <th scope="col" class="px-6 py-3">Component</th>
+                            <th scope="col" class="px-6 py-3">Score</th>
+                            <th scope="col" class="px-6 py-3">Weight</th>
+                            <th scope="col" class="px-6 py-3">Samples</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <ComponentRow
+                            name="Denomination entropy"
+                            explanation="E-cash notes spread evenly across denominations form larger anonymity sets."
+                            component=privacy_score.denomination_entropy
+                        />
+                        <ComponentRow
+                            name="Address reuse"
+                            explanation="Deposits and withdrawals to previously used addresses link users' on-chain activity."
+                            component=privacy_score.address_reuse
+                        />
+                        <ComponentRow
+                            name="Withdrawal batching"
+                            explanation="Withdrawals sharing an on-chain transaction are harder to attribute to a single user."
+                            component=privacy_score.withdrawal_batching
+                        />
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn ComponentRow(
+    name: &'static str,
+    explanation: &'static str,
+    component: PrivacyScoreComponent,
+) -> impl IntoView {
+    view! {
+        <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+            <th scope="row" class="px-6 py-4 font-medium text-gray-900 dark:text-white">
+                {name}
+                <p class="font-normal text-gray-500 dark:text-gray-400">{explanation}</p>
+            </th>
+            <td class="px-6 py-4">
+                {if component.samples > 0 {
+                    format!("{:.0}%", component.score * 100.0)
+                } else {
+                    "n/a".to_owned()
+                }}
+            </td>
+            <td class="px-6 py-4">{format!("{:.0}%", component.weight * 100.0)}</td>
+            <td class="px-6 py-4">{component.samples}</td>
+        </tr>
+    }
+}
+
+async fn fetch_privacy_score(federation_id: FederationId) -> Result<PrivacyScore, String> {
+    crate::client()
+        .privacy_score(federation_id)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,247 @@
+use chrono::Utc;
+use fedimint_core::config::FederationId;
+use fedimint_core::Amount;
+use fmo_api_types::{FederationHealth, FederationSummary};
+use leptos::{
+    component, create_resource, view, Children, IntoView, RwSignal, Show, SignalGet, SignalWith,
+};
+use leptos_meta::Title;
+use leptos_router::use_params;
+
+use super::activity::{daily_values, fetch_federation_history, ChartType};
+use super::chart::TimeLineChart;
+use super::guardians::{Guardian, Guardians};
+use super::incidents::Incidents;
+use super::{fetch_federation_config, fetch_federation_meta, FederationParams};
+use crate::components::badge::{Badge, BadgeLevel};
+use crate::components::button::Button;
+use crate::instance::use_instance_settings;
+use crate::util::{format_age, format_amount};
+
+/// Number of points in the report's activity charts, enough for a printed page
+const REPORT_CHART_POINTS: usize = 120;
+
+/// Static summary of a federation's key figures, activity and guardian health
+/// on a single page, meant to be printed or saved as PDF
+#[component]
+pub fn FederationReport() -> impl IntoView {
+    let id = move || {
+        let params = use_params::<FederationParams>();
+        params.with(|params| params.as_ref().map(|params| params.id).ok())
+    };
+
+    view! {
+        <Show
+            when=move || { id().is_some() }
+            fallback=|| {
+                view! { <p>Invalid federation id</p> }
+            }
+        >
+            <ReportInner federation_id=id().unwrap()/>
+        </Show>
+    }
+}
+
+#[component]
+fn ReportInner(federation_id: FederationId) -> impl IntoView {
+    let config_resource = create_resource(
+        || (),
+        move |()| async move {
+            fetch_federation_config(federation_id)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+    let meta_resource = create_resource(
+        || (),
+        move |()| async move {
+            fetch_federation_meta(federation_id)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+    let summary_resource = create_resource(
+        || (),
+        move |()| async move {
+            let federations = crate::client()
+                .federations()
+                .await
+                .map_err(|e| e.to_string())?;
+            federations
+                .into_iter()
+                .find(|federation| federation.id == federation_id)
+                .ok_or_else(|| "Federation not found".to_owned())
+        },
+    );
+    let history_resource = create_resource(
+        || (),
+        move |()| async move {
+            fetch_federation_history(federation_id, Some(REPORT_CHART_POINTS)).await
+        },
+    );
+
+    let name = move || {
+        meta_resource
+            .get()
+            .and_then(Result::ok)
+            .and_then(|meta| {
+                meta.get("federation_name")
+                    .and_then(|name| name.as_str())
+                    .map(|name| name.to_owned())
+            })
+            .unwrap_or_else(|| federation_id.to_string())
+    };
+
+    view! {
+        <Title text=move || format!("Report: {}", name())/>
+        <div class="my-8 text-gray-900 dark:text-white">
+            <div class="flex flex-wrap items-start justify-between gap-4 mb-8">
+                <div class="min-w-0">
+                    <h2 class="text-4xl font-extrabold truncate">{name}</h2>
+                    <p class="mt-2 text-sm text-gray-500 dark:text-gray-400 break-all">
+                        {format!("Federation {federation_id}")}
+                    </p>
+                    <p class="text-sm text-gray-500 dark:text-gray-400">
+                        {format!("Report generated {} UTC", Utc::now().format("%Y-%m-%d %H:%M"))}
+                    </p>
+                </div>
+                <div class="flex items-center gap-4 print:hidden">
+                    <a
+                        href=format!("/federations/{federation_id}")
+                        class="text-sm font-medium text-blue-600 dark:text-blue-500 hover:underline"
+                    >
+                        "Back to federation"
+                    </a>
+                    <Button on_click=move || {
+                        let _ = leptos::window().print();
+                    }>"Print"</Button>
+                </div>
+            </div>
+
+            <ReportSection title="Key figures">
+                {move || match summary_resource.get() {
+                    Some(Ok(summary)) => view! { <KeyFigures summary=summary/> }.into_view(),
+                    Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                    None => view! { <p>"Loading ..."</p> }.into_view(),
+                }}
+            </ReportSection>
+
+            <ReportSection title="Activity">
+                {move || match history_resource.get() {
+                    Some(Ok(history)) => {
+                        let volume = daily_values(&history, ChartType::Volume);
+                        let transactions = daily_values(&history, ChartType::Transactions);
+                        view! {
+                            <div class="grid grid-cols-1 gap-4 md:grid-cols-2 print:grid-cols-2">
+                                <TimeLineChart
+                                    name=RwSignal::new("Daily Volume (BTC)".to_owned())
+                                    data=move || volume.clone()
+                                />
+                                <TimeLineChart
+                                    name=RwSignal::new("Daily Transactions".to_owned())
+                                    data=move || transactions.clone()
+                                />
+                            </div>
+                        }
+                            .into_view()
+                    }
+                    Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                    None => view! { <p>"Loading ..."</p> }.into_view(),
+                }}
+            </ReportSection>
+
+            <ReportSection title="Guardians">
+                {move || match config_resource.get() {
+                    Some(Ok(config)) => {
+                        view! {
+                            <Guardians
+                                federation_id=federation_id
+                                guardians=config
+                                    .global
+                                    .api_endpoints
+                                    .values()
+                                    .map(|guardian| Guardian {
+                                        name: guardian.name.clone(),
+                                        url: guardian.url.to_string(),
+                                    })
+                                    .collect()
+                            />
+                        }
+                            .into_view()
+                    }
+                    Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                    None => view! { <p>"Loading ..."</p> }.into_view(),
+                }}
+            </ReportSection>
+
+            <ReportSection title="Downtime incidents">
+                <Incidents federation_id=federation_id/>
+            </ReportSection>
+        </div>
+    }
+}
+
+#[component]
+fn ReportSection(title: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <section class="mb-8 break-inside-avoid">
+            <h3 class="mb-4 text-2xl font-bold">{title}</h3>
+            {children()}
+        </section>
+    }
+}
+
+#[component]
+fn KeyFigures(summary: FederationSummary) -> impl IntoView {
+    let settings = use_instance_settings();
+    let unit = settings.with(|settings| settings.display_unit);
+
+    let volume_7d = Amount::from_msats(
+        summary
+            .last_7d_activity
+            .iter()
+            .map(|activity| activity.amount_transferred.msats)
+            .sum(),
+    );
+    let transactions_7d = summary
+        .last_7d_activity
+        .iter()
+        .map(|activity| activity.num_transactions)
+        .sum::<u64>();
+    let health = match summary.health {
+        FederationHealth::Online => view! { <Badge level=BadgeLevel::Success>"Online"</Badge> },
+        FederationHealth::Degraded => view! { <Badge level=BadgeLevel::Warning>"Degraded"</Badge> },
+        FederationHealth::Offline => view! { <Badge level=BadgeLevel::Error>"Offline"</Badge> },
+    };
+
+    view! {
+        <dl class="grid grid-cols-2 gap-4 md:grid-cols-4 print:grid-cols-4">
+            <KeyFigure label="Health">{health}</KeyFigure>
+            <KeyFigure label="Network">
+                {summary.network.unwrap_or_else(|| "Unknown".to_owned())}
+            </KeyFigure>
+            <KeyFigure label="Age">
+                {summary.created_at.map(format_age).unwrap_or_else(|| "Unknown".to_owned())}
+            </KeyFigure>
+            <KeyFigure label="Deposits">{format_amount(summary.deposits, unit, 8)}</KeyFigure>
+            <KeyFigure label="Volume (7 days)">{format_amount(volume_7d, unit, 8)}</KeyFigure>
+            <KeyFigure label="Transactions (7 days)">{transactions_7d}</KeyFigure>
+            <KeyFigure label="Rating">
+                {match summary.nostr_votes.avg {
+                    Some(avg) => format!("{avg:.1} / 5 ({} votes)", summary.nostr_votes.count),
+                    None => "No votes yet".to_owned(),
+                }}
+            </KeyFigure>
+        </dl>
+    }
+}
+
+#[component]
+fn KeyFigure(label: &'static str, children: Children) -> impl IntoView {
+    view! {
+        <div class="p-4 bg-white border border-gray-200 rounded-lg dark:bg-gray-800 dark:border-gray-700">
+            <dt class="text-sm text-gray-500 dark:text-gray-400">{label}</dt>
+            <dd class="mt-1 text-lg font-semibold">{children()}</dd>
+        </div>
+    }
+}
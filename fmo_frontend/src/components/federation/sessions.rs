@@ -0,0 +1,115 @@
+use fedimint_core::config::FederationId;
+use leptos::{
+    component, create_resource, create_signal, view, CollectView, IntoView, Signal, SignalGet,
+    SignalUpdate,
+};
+
+use crate::components::button::{Button, SECONDARY_BUTTON};
+
+/// Number of sessions shown per page
+const SESSION_PAGE_SIZE: u64 = 50;
+
+/// Paginated list of a federation's sessions, most recent first
+#[component]
+pub fn Sessions(federation_id: FederationId) -> impl IntoView {
+    let (offset, set_offset) = create_signal(0u64);
+    let sessions_resource = create_resource(
+        move || offset.get(),
+        move |offset| async move {
+            crate::client()
+                .federation_sessions(federation_id, SESSION_PAGE_SIZE, offset)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    let total = move || {
+        sessions_resource
+            .get()
+            .and_then(Result::ok)
+            .map(|page| page.total)
+            .unwrap_or(0)
+    };
+    let newer = move || set_offset.update(|offset| *offset = offset.saturating_sub(SESSION_PAGE_SIZE));
+    let older = move || set_offset.update(|offset| *offset += SESSION_PAGE_SIZE);
+
+    view! {
+        {move || {
+            match sessions_resource.get() {
+                Some(Ok(page)) => {
+                    let rows = page
+                        .items
+                        .into_iter()
+                        .map(|session| {
+                            view! {
+                                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                                    <td class="px-6 py-4">
+                                        <a
+                                            href=format!(
+                                                "/federations/{federation_id}/sessions/{}",
+                                                session.session_index,
+                                            )
+                                            class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                                        >
+                                            {session.session_index}
+                                        </a>
+                                    </td>
+                                    <td class="px-6 py-4">
+                                        {session
+                                            .estimated_time
+                                            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                                            .unwrap_or_else(|| "unknown".to_owned())}
+                                    </td>
+                                    <td class="px-6 py-4">{session.item_count}</td>
+                                    <td class="px-6 py-4">{session.transaction_count}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view();
+                    view! {
+                        <table class="w-full my-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                            <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Session"</th>
+                                    <th scope="col" class="px-6 py-3">"Estimated time (UTC)"</th>
+                                    <th scope="col" class="px-6 py-3">"Items"</th>
+                                    <th scope="col" class="px-6 py-3">"Transactions"</th>
+                                </tr>
+                            </thead>
+                            <tbody>{rows}</tbody>
+                        </table>
+                    }
+                        .into_view()
+                }
+                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                None => view! { <p>"Loading ..."</p> }.into_view(),
+            }
+        }}
+        <div class="flex items-center justify-between py-2">
+            <Button
+                color_scheme=SECONDARY_BUTTON
+                class="py-2"
+                on_click=newer
+                disabled=Signal::derive(move || offset.get() == 0)
+            >
+                "Newer"
+            </Button>
+            <span class="text-sm text-gray-500 dark:text-gray-400">
+                {move || {
+                    let total = total();
+                    let first = (offset.get() + 1).min(total);
+                    let last = (offset.get() + SESSION_PAGE_SIZE).min(total);
+                    format!("{first}–{last} of {total} sessions")
+                }}
+            </span>
+            <Button
+                color_scheme=SECONDARY_BUTTON
+                class="py-2"
+                on_click=older
+                disabled=Signal::derive(move || offset.get() + SESSION_PAGE_SIZE >= total())
+            >
+                "Older"
+            </Button>
+        </div>
+    }
+}
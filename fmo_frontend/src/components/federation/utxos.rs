@@ -80,8 +80,8 @@ pub fn Utxos(federation_id: FederationId) -> impl IntoView {
 async fn fetch_federation_utxos(
     federation_id: FederationId,
 ) -> Result<Vec<FederationUtxo>, String> {
-    let url = format!("{}/federations/{}/utxos", crate::BASE_URL, federation_id);
-    let res = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    let json = res.json().await.map_err(|e| e.to_string())?;
-    Ok(json)
+    crate::client()
+        .federation_utxos(federation_id)
+        .await
+        .map_err(|e| e.to_string())
 }
@@ -0,0 +1,122 @@
+use fedimint_core::config::FederationId;
+use fmo_api_types::WithdrawalStatus;
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet};
+
+use crate::util::AsBitcoin;
+
+/// Peg-out requests of a federation and the on-chain transactions paying them
+/// out
+#[component]
+pub fn Withdrawals(federation_id: FederationId) -> impl IntoView {
+    let withdrawals_resource = create_resource(
+        || (),
+        move |()| async move {
+            crate::client()
+                .withdrawals(federation_id)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    view! {
+        {move || {
+            match withdrawals_resource.get() {
+                Some(Ok(withdrawals)) if withdrawals.is_empty() => {
+                    view! {
+                        <p class="my-4 text-gray-500 dark:text-gray-400">"No withdrawals yet"</p>
+                    }
+                        .into_view()
+                }
+                Some(Ok(withdrawals)) => {
+                    let rows = withdrawals
+                        .into_iter()
+                        .map(|withdrawal| {
+                            let status = match withdrawal.status {
+                                WithdrawalStatus::Requested => "Requested".to_owned(),
+                                WithdrawalStatus::Signing => {
+                                    format!(
+                                        "Signing ({}/{})",
+                                        withdrawal.signatures,
+                                        withdrawal.signature_threshold,
+                                    )
+                                }
+                                WithdrawalStatus::Broadcast => "Unconfirmed".to_owned(),
+                                WithdrawalStatus::Confirmed => {
+                                    format!(
+                                        "Confirmed in block {}",
+                                        withdrawal.confirmation_height.unwrap_or_default(),
+                                    )
+                                }
+                            };
+                            let on_chain_tx = withdrawal
+                                .on_chain_txid
+                                .map(|txid| {
+                                    view! {
+                                        <a
+                                            href=format!("https://mempool.space/tx/{txid}")
+                                            class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                                        >
+                                            <pre class="truncate max-w-[12rem]">{txid.to_string()}</pre>
+                                        </a>
+                                    }
+                                        .into_view()
+                                })
+                                .unwrap_or_else(|| "-".into_view());
+                            view! {
+                                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                                    <td class="px-6 py-4">
+                                        {withdrawal
+                                            .estimated_time
+                                            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                                            .unwrap_or_else(|| "unknown".to_owned())}
+                                    </td>
+                                    <td class="px-6 py-4">
+                                        <a
+                                            href=format!(
+                                                "/federations/{federation_id}/transactions/{}",
+                                                withdrawal.federation_txid,
+                                            )
+                                            class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                                        >
+                                            <pre class="truncate max-w-[12rem]">
+                                                {withdrawal.federation_txid.to_string()}
+                                            </pre>
+                                        </a>
+                                    </td>
+                                    <td class="px-6 py-4">
+                                        <pre class="truncate max-w-[12rem]">
+                                            {withdrawal.address.assume_checked().to_string()}
+                                        </pre>
+                                    </td>
+                                    <td class="px-6 py-4">
+                                        {withdrawal.amount.as_bitcoin(8).to_string()}
+                                    </td>
+                                    <td class="px-6 py-4">{on_chain_tx}</td>
+                                    <td class="px-6 py-4">{status}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view();
+                    view! {
+                        <table class="w-full my-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                            <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Requested (UTC)"</th>
+                                    <th scope="col" class="px-6 py-3">"Transaction"</th>
+                                    <th scope="col" class="px-6 py-3">"Address"</th>
+                                    <th scope="col" class="px-6 py-3">"Amount"</th>
+                                    <th scope="col" class="px-6 py-3">"On-chain transaction"</th>
+                                    <th scope="col" class="px-6 py-3">"Status"</th>
+                                </tr>
+                            </thead>
+                            <tbody>{rows}</tbody>
+                        </table>
+                    }
+                        .into_view()
+                }
+                Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                None => view! { <p>"Loading ..."</p> }.into_view(),
+            }
+        }}
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+
+use leptos::{component, view, CollectView, IntoView, RwSignal, SignalGet, SignalUpdate, View};
+
+use crate::util::local_storage;
+
+/// Local storage key under which the visible columns of the federations table
+/// are kept
+const COLUMNS_KEY: &str = "fmo_federation_columns";
+
+/// Optional columns of the federations table, the federation name is always
+/// shown. The order of the variants is the order of the columns in the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Column {
+    Rating,
+    Invite,
+    Health,
+    Age,
+    Assets,
+    Activity,
+}
+
+impl Column {
+    pub const ALL: [Column; 6] = [
+        Column::Rating,
+        Column::Invite,
+        Column::Health,
+        Column::Age,
+        Column::Assets,
+        Column::Activity,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Column::Rating => "rating",
+            Column::Invite => "invite",
+            Column::Health => "health",
+            Column::Age => "age",
+            Column::Assets => "assets",
+            Column::Activity => "activity",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|column| column.key() == key)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Rating => "Recommendations",
+            Column::Invite => "Invite Code",
+            Column::Health => "Health",
+            Column::Age => "Age",
+            Column::Assets => "Total Assets",
+            Column::Activity => "Average Activity (7d)",
+        }
+    }
+
+    pub fn header(self) -> View {
+        match self {
+            Column::Rating => view! {
+                <a
+                    href="https://github.com/nostr-protocol/nips/pull/1110"
+                    class="underline hover:no-underline"
+                >
+                    {self.title()}
+                </a>
+            }
+            .into_view(),
+            _ => self.title().into_view(),
+        }
+    }
+}
+
+/// Returns the columns the user chose to show, or all columns if they never
+/// customized the table
+pub fn load_columns() -> BTreeSet<Column> {
+    let Some(columns) = local_storage().and_then(|storage| storage.get_item(COLUMNS_KEY).ok()?)
+    else {
+        return Column::ALL.into_iter().collect();
+    };
+
+    columns.split(',').filter_map(Column::from_key).collect()
+}
+
+fn store_columns(columns: &BTreeSet<Column>) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+
+    let columns = columns
+        .iter()
+        .map(|column| column.key())
+        .collect::<Vec<_>>()
+        .join(",");
+    // Failing to persist the columns only means the user has to choose them again
+    let _ = storage.set_item(COLUMNS_KEY, &columns);
+}
+
+/// Checkboxes to choose which columns of the federations table are visible
+#[component]
+pub fn ColumnToggle(columns: RwSignal<BTreeSet<Column>>) -> impl IntoView {
+    let toggle = move |column: Column| {
+        columns.update(|columns| {
+            if !columns.remove(&column) {
+                columns.insert(column);
+            }
+            store_columns(columns);
+        })
+    };
+
+    view! {
+        <div class="flex flex-wrap gap-4 mt-3">
+            {Column::ALL
+                .into_iter()
+                .map(|column| {
+                    let id = format!("column-{}", column.key());
+                    view! {
+                        <div class="flex items-center">
+                            <input
+                                id=id.clone()
+                                type="checkbox"
+                                class="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 dark:focus:ring-blue-600 dark:ring-offset-gray-800 focus:ring-2 dark:bg-gray-700 dark:border-gray-600"
+                                checked=move || columns.get().contains(&column)
+                                on:change=move |_| toggle(column)
+                            />
+                            <label
+                                for=id
+                                class="ms-2 text-sm font-normal text-gray-500 dark:text-gray-400"
+                            >
+                                {column.title()}
+                            </label>
+                        </div>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
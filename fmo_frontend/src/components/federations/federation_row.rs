@@ -1,72 +1,134 @@
+use std::collections::BTreeSet;
+
 use fedimint_core::config::FederationId;
 use fedimint_core::Amount;
-use fmo_api_types::{FederationHealth, FederationRating};
-use leptos::{component, view, IntoView};
+use fmo_api_types::{FederationHealth, FederationSummary};
+use leptos::{component, view, CollectView, IntoView, RwSignal, SignalGet, SignalWith, View};
 
 use crate::components::badge::{Badge, BadgeLevel};
+use crate::components::federations::columns::Column;
 use crate::components::federations::rating::Rating;
+use crate::components::federations::watchlist::WatchStar;
 use crate::components::Copyable;
-use crate::util::AsBitcoin;
+use crate::instance::use_instance_settings;
+use crate::util::{format_age, format_amount};
 
 #[component]
 pub fn FederationRow(
-    id: FederationId,
-    name: String,
-    rating: FederationRating,
-    invite: String,
-    total_assets: Amount,
+    summary: FederationSummary,
     avg_txs: f64,
     avg_volume: Amount,
-    health: FederationHealth,
+    columns: RwSignal<BTreeSet<Column>>,
+    watchlist: RwSignal<BTreeSet<FederationId>>,
 ) -> impl IntoView {
+    let id = summary.id;
+    let name = summary.name.clone().unwrap_or_else(|| "Unnamed".to_owned());
+    // Mainnet is the norm, only other networks are called out
+    let network_badge = summary
+        .network
+        .clone()
+        .filter(|network| network != "bitcoin")
+        .map(|network| view! { <Badge level=BadgeLevel::Info>{network}</Badge> });
+    let icon = summary.icon_url.clone().map(|icon_url| {
+        view! { <img src=icon_url alt="" class="inline-block w-5 h-5 mr-2 rounded-full"/> }
+    });
+
+    let cells = move || {
+        columns
+            .get()
+            .into_iter()
+            .map(|column| {
+                let class = match column {
+                    Column::Rating => "",
+                    Column::Age => "px-6 py-4 whitespace-nowrap",
+                    _ => "px-6 py-4",
+                };
+                view! {
+                    <td class=class>{column_cell(column, &summary, avg_txs, avg_volume)}</td>
+                }
+            })
+            .collect_view()
+    };
+
     view! {
         <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
             <th
                 scope="row"
                 class="px-6 py-4 font-medium text-gray-900 whitespace-nowrap dark:text-white"
             >
+                <WatchStar federation_id=id watchlist=watchlist/>
+                {icon}
                 <a
                     href=format!("/federations/{id}")
                     class="font-medium text-blue-600 dark:text-blue-500 hover:underline"
                 >
                     {name}
                 </a>
+                {network_badge}
             </th>
-            <td>
-                <Rating
-                    count=rating.count
-                    rating=rating.avg
-                />
-            </td>
-            <td class="px-6 py-4">
-                { match health {
-                    FederationHealth::Online => {
-                        view! { <Copyable text=invite/> }.into_view()
-                    },
-                    FederationHealth::Degraded => {
-                        view! {
-                            <Badge level=BadgeLevel::Warning>
-                                "Degraded"
-                            </Badge>
-                        }.into_view()
-                    }
-                    FederationHealth::Offline => {
-                        view! {
-                            <Badge level=BadgeLevel::Error>
-                                "Offline"
-                            </Badge>
-                        }.into_view()
-                    },
-                }}
-            </td>
-            <td class="px-6 py-4">{total_assets.as_bitcoin(6).to_string()}</td>
-            <td class="px-6 py-4">
-                <ul>
-                    <li>{format!("#tx: {:.1}", avg_txs)}</li>
-                    <li>{format!("volume: {}", avg_volume.as_bitcoin(6))}</li>
-                </ul>
-            </td>
+            {cells}
         </tr>
     }
     .into_view()
 }
+
+fn column_cell(
+    column: Column,
+    summary: &FederationSummary,
+    avg_txs: f64,
+    avg_volume: Amount,
+) -> View {
+    let settings = use_instance_settings();
+    let display_unit = move || settings.with(|settings| settings.display_unit);
+
+    match column {
+        Column::Rating => view! {
+            <Rating
+                count=summary.nostr_votes.count
+                rating=summary.nostr_votes.avg
+            />
+        }
+        .into_view(),
+        Column::Invite => view! { <Copyable text=summary.invite.clone()/> }.into_view(),
+        Column::Health => match summary.health {
+            FederationHealth::Online => view! {
+                <Badge level=BadgeLevel::Success>
+                    "Online"
+                </Badge>
+            }
+            .into_view(),
+            FederationHealth::Degraded => view! {
+                <Badge level=BadgeLevel::Warning>
+                    "Degraded"
+                </Badge>
+            }
+            .into_view(),
+            FederationHealth::Offline => view! {
+                <Badge level=BadgeLevel::Error>
+                    "Offline"
+                </Badge>
+            }
+            .into_view(),
+        },
+        Column::Age => {
+            let created_at = summary.created_at;
+            view! {
+                <span title=created_at.map(|created_at| format!("Created around {}", created_at.format("%Y-%m-%d")))>
+                    {created_at.map(format_age).unwrap_or_else(|| "Unknown".to_owned())}
+                </span>
+            }
+            .into_view()
+        }
+        Column::Assets => {
+            let total_assets = summary.deposits;
+            (move || format_amount(total_assets, display_unit(), 6)).into_view()
+        }
+        Column::Activity => view! {
+            <ul>
+                <li>{format!("#tx: {:.1}", avg_txs)}</li>
+                <li>{move || format!("volume: {}", format_amount(avg_volume, display_unit(), 6))}</li>
+            </ul>
+        }
+        .into_view(),
+    }
+}
@@ -1,15 +1,27 @@
+mod columns;
 mod federation_row;
 pub mod rating;
+mod search;
+mod shut_down;
 mod totals;
+mod watchlist;
 
-use fedimint_core::Amount;
-use fmo_api_types::{FederationHealth, FederationSummary};
-use leptos::{component, create_resource, view, IntoView, SignalGet};
+use fmo_api_types::rating::sort_by_rating_index;
+use fmo_api_types::{FederationHealth, FederationSearchMatch, FederationSearchResult};
+use fmo_client::FederationSearchFilters;
+use leptos::{
+    component, create_resource, view, CollectView, IntoView, RwSignal, Show, Signal, SignalGet,
+};
 use leptos_meta::Title;
+use leptos_use::signal_debounced;
 
+use crate::components::federation::NetworkActivityChart;
+use crate::components::federations::columns::{load_columns, ColumnToggle};
 use crate::components::federations::federation_row::FederationRow;
+use crate::components::federations::search::SearchBar;
+use crate::components::federations::shut_down::ShutDownFederations;
 use crate::components::federations::totals::Totals;
-use crate::BASE_URL;
+use crate::components::federations::watchlist::{load_watchlist, Watchlist};
 
 #[component]
 pub fn Federations() -> impl IntoView {
@@ -18,30 +30,124 @@ pub fn Federations() -> impl IntoView {
         |_| async { fetch_federations().await.map_err(|e| e.to_string()) },
     );
 
+    let query = RwSignal::new(String::new());
+    let filters = RwSignal::new(FederationSearchFilters::default());
+    let debounced_query = signal_debounced(query, 300.0);
+    // Only hits the search endpoint if a query or filter is set, otherwise the
+    // already loaded federations are shown
+    let search_res = create_resource(
+        move || (debounced_query.get(), filters.get()),
+        |(query, filters)| async move {
+            if query.trim().is_empty() && filters == FederationSearchFilters::default() {
+                return None;
+            }
+            Some(
+                search_federations(query.trim(), &filters)
+                    .await
+                    .map_err(|e| e.to_string()),
+            )
+        },
+    );
+
+    let columns = RwSignal::new(load_columns());
+    let watchlist = RwSignal::new(load_watchlist());
+
     let rows = move || {
+        let results = match search_res.get()? {
+            Some(results) => results.ok()?,
+            None => federations_res.get()?.ok()?,
+        };
+        // Federations ranked by how well they match the query keep their order
+        let ranked = results
+            .iter()
+            .any(|result| result.matched != FederationSearchMatch::Any);
+        let (observed, announced): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .partition(|result| result.summary.is_some());
+        let mut federations = observed
+            .into_iter()
+            .filter_map(|result| result.summary)
+            .filter(|summary| summary.shutdown.is_none())
+            .collect::<Vec<_>>();
+        if !ranked {
+            sort_by_rating_index(&mut federations);
+        }
+
+        if federations.is_empty() && announced.is_empty() {
+            return Some(
+                view! {
+                    <tr class="bg-white dark:bg-gray-800">
+                        <td class="px-6 py-4" colspan="100%">
+                            "No federations found"
+                        </td>
+                    </tr>
+                }
+                .into_view(),
+            );
+        }
+
         Some(
-            federations_res
-                .get()?
-                .ok()?
+            federations
                 .into_iter()
-                .map(|(summary, avg_txs, avg_volume)| {
+                .map(|summary| {
+                    let avg_txs = summary.avg_daily_transactions();
+                    let avg_volume = summary.avg_daily_volume();
                     view! {
                         <FederationRow
-                            id=summary.id
-                            name=summary.name.clone().unwrap_or_else(|| "Unnamed".to_owned())
-                            rating=summary.nostr_votes
-                            invite=summary.invite.clone()
-                            total_assets=summary.deposits
+                            summary=summary
                             avg_txs=avg_txs
                             avg_volume=avg_volume
-                            health=summary.health
+                            columns=columns
+                            watchlist=watchlist
                         />
                     }
+                    .into_view()
                 })
-                .collect::<Vec<_>>(),
+                .chain(announced.into_iter().map(|result| {
+                    let name = result.name.unwrap_or_else(|| result.id.to_string());
+                    view! {
+                        <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                            <th
+                                scope="row"
+                                class="px-6 py-4 font-medium text-gray-900 whitespace-nowrap dark:text-white"
+                            >
+                                {name}
+                            </th>
+                            <td class="px-6 py-4" colspan="100%">
+                                <a href="/nostr" class="font-medium text-blue-600 dark:text-blue-500 hover:underline">
+                                    "Announced on Nostr, not observed yet"
+                                </a>
+                            </td>
+                        </tr>
+                    }
+                    .into_view()
+                }))
+                .collect_view(),
         )
     };
 
+    let all_federations =
+        Signal::derive(move || federations_res.get().and_then(Result::ok).unwrap_or_default());
+    let watched_federations = Signal::derive(move || {
+        all_federations
+            .get()
+            .into_iter()
+            .filter_map(|result| result.summary)
+            .collect::<Vec<_>>()
+    });
+
+    let shut_down_federations = move || {
+        let federations = federations_res
+            .get()?
+            .ok()?
+            .into_iter()
+            .filter_map(|result| result.summary)
+            .filter(|summary| summary.shutdown.is_some())
+            .collect::<Vec<_>>();
+
+        (!federations.is_empty()).then(|| view! { <ShutDownFederations federations=federations/> })
+    };
+
     view! {
         <Title
             text="Fedimint Observer"
@@ -49,6 +155,13 @@ pub fn Federations() -> impl IntoView {
         <div class="my-16">
             <Totals/>
         </div>
+        <div class="my-16">
+            <h2 class="text-2xl mb-4 font-extrabold dark:text-white">"Network Activity"</h2>
+            <NetworkActivityChart/>
+        </div>
+        <Show when=move || !watchlist.get().is_empty()>
+            <Watchlist watchlist=watchlist federations=watched_federations columns=columns/>
+        </Show>
         <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
             <table class="w-full text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
                 <caption class="p-5 text-lg font-semibold text-left rtl:text-right text-gray-900 bg-white dark:text-white dark:bg-gray-800">
@@ -56,68 +169,72 @@ pub fn Federations() -> impl IntoView {
                     <p class="mt-1 text-sm font-normal text-gray-500 dark:text-gray-400">
                         "List of all federations this instance is collecting statistics on"
                     </p>
+                    <SearchBar query=query filters=filters federations=all_federations/>
+                    <ColumnToggle columns=columns/>
                 </caption>
                 <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
                     <tr>
                         <th scope="col" class="px-6 py-3">
                             "Name"
                         </th>
-                        <th scope="col" class="px-6 py-3">
-                            <a
-                                href="https://github.com/nostr-protocol/nips/pull/1110"
-                                class="underline hover:no-underline"
-                            >
-                                "Recommendations"
-                            </a>
-                        </th>
-                        <th scope="col" class="px-6 py-3">
-                            "Invite Code"
-                        </th>
-                        <th scope="col" class="px-6 py-3">
-                            "Total Assets"
-                        </th>
-                        <th scope="col" class="px-6 py-3">
-                            "Average Activity (7d)"
-                        </th>
+                        {move || {
+                            columns
+                                .get()
+                                .into_iter()
+                                .map(|column| {
+                                    view! {
+                                        <th scope="col" class="px-6 py-3">
+                                            {column.header()}
+                                        </th>
+                                    }
+                                })
+                                .collect_view()
+                        }}
                     </tr>
                 </thead>
                 <tbody>{rows}</tbody>
             </table>
         </div>
+        {shut_down_federations}
     }
 }
 
-async fn fetch_federations() -> anyhow::Result<Vec<(FederationSummary, f64, Amount)>> {
-    let url = format!("{}/federations", BASE_URL);
-    let response = reqwest::get(&url).await?;
-    let federations: Vec<FederationSummary> = response.json().await?;
-
-    let federations = federations
+async fn fetch_federations() -> anyhow::Result<Vec<FederationSearchResult>> {
+    let federations = crate::client()
+        .search_federations("", &FederationSearchFilters::default())
+        .await?
         .into_iter()
-        .filter_map(|federation_summary| {
+        .filter(|result| {
             // Don't show offline federations for now. Eventually I'd like to only not show
-            // them if they have been offline for a long time.
-            if federation_summary.health == FederationHealth::Offline {
-                return None;
-            }
+            // them if they have been offline for a long time. Shut down federations are
+            // expected to be offline and get their own section.
+            result.summary.as_ref().is_some_and(|summary| {
+                summary.health != FederationHealth::Offline || summary.shutdown.is_some()
+            })
+        })
+        .collect();
 
-            let avg_txs = federation_summary
-                .last_7d_activity
-                .iter()
-                .map(|tx| tx.num_transactions)
-                .sum::<u64>() as f64
-                / federation_summary.last_7d_activity.len() as f64;
-            let avg_volume = Amount::from_msats(
-                federation_summary
-                    .last_7d_activity
-                    .iter()
-                    .map(|tx| tx.amount_transferred.msats)
-                    .sum::<u64>()
-                    / federation_summary.last_7d_activity.len() as u64,
-            );
-            Some((federation_summary, avg_txs, avg_volume))
+    Ok(federations)
+}
+
+/// Searches all observed federations, offline ones are only hidden if the
+/// user didn't explicitly filter by health
+async fn search_federations(
+    query: &str,
+    filters: &FederationSearchFilters,
+) -> anyhow::Result<Vec<FederationSearchResult>> {
+    let federations = crate::client()
+        .search_federations(query, filters)
+        .await?
+        .into_iter()
+        .filter(|result| {
+            filters.health.is_some()
+                || result
+                    .summary
+                    .as_ref()
+                    .map_or(true, |summary| summary.health != FederationHealth::Offline)
         })
-        .collect::<Vec<_>>();
+        .collect();
 
     Ok(federations)
 }
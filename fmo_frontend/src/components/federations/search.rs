@@ -0,0 +1,138 @@
+use std::collections::BTreeSet;
+
+use fmo_api_types::{FederationHealth, FederationSearchResult};
+use fmo_client::FederationSearchFilters;
+use leptos::{
+    component, event_target_value, view, CollectView, IntoView, RwSignal, Signal,
+    SignalGet, SignalUpdate, SignalWith,
+};
+
+/// Search input and filter chips of the federations table. The chips offer the
+/// networks and modules found in `federations`, usually all observed ones.
+#[component]
+pub fn SearchBar(
+    query: RwSignal<String>,
+    filters: RwSignal<FederationSearchFilters>,
+    federations: Signal<Vec<FederationSearchResult>>,
+) -> impl IntoView {
+    let networks = move || {
+        federations.with(|federations| {
+            federations
+                .iter()
+                .filter_map(|federation| federation.summary.as_ref()?.network.clone())
+                .collect::<BTreeSet<_>>()
+        })
+    };
+    let modules = move || {
+        federations.with(|federations| {
+            federations
+                .iter()
+                .flat_map(|federation| federation.modules.iter().cloned())
+                .collect::<BTreeSet<_>>()
+        })
+    };
+
+    let network_chips = move || {
+        networks()
+            .into_iter()
+            .map(|network| {
+                let active = {
+                    let network = network.clone();
+                    Signal::derive(move || {
+                        filters.with(|filters| filters.network.as_ref() == Some(&network))
+                    })
+                };
+                let on_click = {
+                    let network = network.clone();
+                    move || {
+                        filters.update(|filters| {
+                            filters.network = if filters.network.as_ref() == Some(&network) {
+                                None
+                            } else {
+                                Some(network.clone())
+                            };
+                        })
+                    }
+                };
+                view! { <FilterChip label=network active=active on_click=on_click/> }
+            })
+            .collect_view()
+    };
+
+    let module_chips = move || {
+        modules()
+            .into_iter()
+            .map(|module| {
+                let active = {
+                    let module = module.clone();
+                    Signal::derive(move || filters.with(|filters| filters.modules.contains(&module)))
+                };
+                let on_click = {
+                    let module = module.clone();
+                    move || {
+                        filters.update(|filters| {
+                            if !filters.modules.remove(&module) {
+                                filters.modules.insert(module.clone());
+                            }
+                        })
+                    }
+                };
+                view! { <FilterChip label=module active=active on_click=on_click/> }
+            })
+            .collect_view()
+    };
+
+    let health_chips = FederationHealth::ALL
+        .into_iter()
+        .map(|health| {
+            let active = Signal::derive(move || filters.with(|filters| filters.health == Some(health)));
+            let on_click = move || {
+                filters.update(|filters| {
+                    filters.health = (filters.health != Some(health)).then_some(health);
+                })
+            };
+            view! { <FilterChip label=health.as_str().to_owned() active=active on_click=on_click/> }
+        })
+        .collect_view();
+
+    view! {
+        <div class="mt-3">
+            <input
+                type="search"
+                class="block w-full p-2.5 text-sm text-gray-900 border border-gray-300 rounded-lg bg-gray-50 focus:ring-blue-500 focus:border-blue-500 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white dark:focus:ring-blue-500 dark:focus:border-blue-500"
+                placeholder="Search by name, federation id or invite code"
+                prop:value=move || query.get()
+                on:input=move |ev| query.set(event_target_value(&ev))
+            />
+            <div class="flex flex-wrap items-center gap-2 mt-3 text-sm font-normal">
+                <span class="text-gray-500 dark:text-gray-400">"Network:"</span>
+                {network_chips}
+                <span class="ms-4 text-gray-500 dark:text-gray-400">"Modules:"</span>
+                {module_chips}
+                <span class="ms-4 text-gray-500 dark:text-gray-400">"Health:"</span>
+                {health_chips}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn FilterChip(
+    label: String,
+    #[prop(into)] active: Signal<bool>,
+    on_click: impl Fn() + 'static,
+) -> impl IntoView {
+    let class = move || {
+        if active.get() {
+            "px-2.5 py-0.5 rounded-full border border-blue-600 bg-blue-100 text-blue-800 dark:bg-blue-900 dark:text-blue-300"
+        } else {
+            "px-2.5 py-0.5 rounded-full border border-gray-300 text-gray-500 hover:bg-gray-100 dark:border-gray-600 dark:text-gray-400 dark:hover:bg-gray-700"
+        }
+    };
+
+    view! {
+        <button type="button" class=class on:click=move |_| on_click()>
+            {label}
+        </button>
+    }
+}
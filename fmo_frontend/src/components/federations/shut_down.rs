@@ -0,0 +1,82 @@
+use fmo_api_types::FederationSummary;
+use leptos::{component, view, CollectView, IntoView, SignalWith};
+
+use crate::instance::use_instance_settings;
+use crate::util::format_amount;
+
+/// Federations that were marked as shut down, showing their lifetime
+/// statistics instead of live data
+#[component]
+pub fn ShutDownFederations(federations: Vec<FederationSummary>) -> impl IntoView {
+    let settings = use_instance_settings();
+    let display_unit = move || settings.with(|settings| settings.display_unit);
+
+    let rows = federations
+        .into_iter()
+        .filter_map(|summary| {
+            let shutdown = summary.shutdown?;
+            let id = summary.id;
+            let name = summary.name.unwrap_or_else(|| "Unnamed".to_owned());
+            let lifetime = match (summary.created_at, shutdown.last_activity) {
+                (Some(created_at), Some(last_activity)) => {
+                    format!("{} days", (last_activity - created_at).num_days())
+                }
+                _ => "Unknown".to_owned(),
+            };
+            let (total_volume, peak_assets) = (shutdown.total_volume, shutdown.peak_assets);
+            let last_activity = shutdown
+                .last_activity
+                .map(|last_activity| last_activity.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "Never".to_owned());
+
+            Some(view! {
+                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                    <th
+                        scope="row"
+                        class="px-6 py-4 font-medium text-gray-900 whitespace-nowrap dark:text-white"
+                    >
+                        <a
+                            href=format!("/federations/{id}")
+                            class="font-medium text-blue-600 dark:text-blue-500 hover:underline"
+                        >
+                            {name}
+                        </a>
+                    </th>
+                    <td class="px-6 py-4">{lifetime}</td>
+                    <td class="px-6 py-4">{last_activity}</td>
+                    <td class="px-6 py-4">
+                        {move || format_amount(total_volume, display_unit(), 6)}
+                    </td>
+                    <td class="px-6 py-4">
+                        {move || format_amount(peak_assets, display_unit(), 6)}
+                    </td>
+                    <td class="px-6 py-4">{shutdown.shutdown_at.format("%Y-%m-%d").to_string()}</td>
+                </tr>
+            })
+        })
+        .collect_view();
+
+    view! {
+        <div class="relative overflow-x-auto shadow-md sm:rounded-lg mt-8">
+            <table class="w-full text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                <caption class="p-5 text-lg font-semibold text-left rtl:text-right text-gray-900 bg-white dark:text-white dark:bg-gray-800">
+                    "Shut Down Federations"
+                    <p class="mt-1 text-sm font-normal text-gray-500 dark:text-gray-400">
+                        "Federations that ceased operations, with statistics over their whole lifetime"
+                    </p>
+                </caption>
+                <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                    <tr>
+                        <th scope="col" class="px-6 py-3">"Name"</th>
+                        <th scope="col" class="px-6 py-3">"Lifetime"</th>
+                        <th scope="col" class="px-6 py-3">"Last Activity"</th>
+                        <th scope="col" class="px-6 py-3">"Total Volume"</th>
+                        <th scope="col" class="px-6 py-3">"Peak Assets"</th>
+                        <th scope="col" class="px-6 py-3">"Shut Down"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+    }
+}
@@ -1,9 +1,12 @@
 use fedimint_core::util::backon::FibonacciBuilder;
 use fedimint_core::util::retry;
 use fmo_api_types::FedimintTotals;
-use leptos::{component, create_resource, view, IntoView, SignalGet};
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet, SignalWith};
 use num_format::{Locale, ToFormattedString};
 
+use crate::instance::use_instance_settings;
+use crate::util::format_amount;
+
 #[component]
 pub fn Totals() -> impl IntoView {
     let totals_res = create_resource(
@@ -97,11 +100,61 @@ pub fn Totals() -> impl IntoView {
                 <div class="text-gray-500">Total Volume</div>
             </div>
         </div>
+        {move || {
+            totals_res
+                .get()
+                .filter(|totals| !totals.federations_at_risk.is_empty())
+                .map(|totals| view! { <AssetsAtRisk totals=totals/> })
+        }}
+    }
+}
+
+/// Red banner listing the federations that currently can't reach their signing
+/// threshold and how much user funds they hold
+#[component]
+fn AssetsAtRisk(totals: FedimintTotals) -> impl IntoView {
+    let settings = use_instance_settings();
+    let display_unit = move || settings.with(|settings| settings.display_unit);
+    let assets_at_risk = totals.assets_at_risk;
+    let federation_count = totals.federations_at_risk.len();
+
+    let federations = totals
+        .federations_at_risk
+        .into_iter()
+        .map(|federation| {
+            let id = federation.federation_id;
+            let name = federation.name.unwrap_or_else(|| id.to_string());
+            let assets = federation.assets;
+            view! {
+                <li>
+                    <a href=format!("/federations/{id}") class="font-medium underline hover:no-underline">
+                        {name}
+                    </a>
+                    " ("
+                    {move || format_amount(assets, display_unit(), 6)}
+                    ")"
+                </li>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div
+            class="p-4 mt-8 text-sm text-red-800 rounded-lg bg-red-50 dark:bg-gray-800 dark:text-red-400"
+            role="alert"
+        >
+            <span class="font-bold">
+                {move || format_amount(assets_at_risk, display_unit(), 6)}
+                " at risk: "
+            </span>
+            {format!(
+                "{federation_count} federation(s) currently have too few guardians online to sign transactions"
+            )}
+            <ul class="mt-1.5 list-disc list-inside">{federations}</ul>
+        </div>
     }
 }
 
 async fn fetch_federation_totals() -> anyhow::Result<FedimintTotals> {
-    let url = format!("{}/federations/totals", crate::BASE_URL);
-    let res = reqwest::get(&url).await?;
-    Ok(res.json().await?)
+    crate::client().federation_totals().await
 }
@@ -0,0 +1,201 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use fedimint_core::config::FederationId;
+use fmo_api_types::FederationSummary;
+use leptos::{
+    component, create_rw_signal, event_target_value, view, CollectView, IntoView, RwSignal,
+    Signal, SignalGet, SignalSet, SignalUpdate,
+};
+
+use crate::components::button::{Button, SECONDARY_BUTTON};
+use crate::components::federations::columns::Column;
+use crate::components::federations::federation_row::FederationRow;
+use crate::util::local_storage;
+
+/// Local storage key under which the ids of watched federations are kept
+const WATCHLIST_KEY: &str = "fmo_watchlist";
+
+/// Returns the federations the user starred, the list only lives in the
+/// browser
+pub fn load_watchlist() -> BTreeSet<FederationId> {
+    local_storage()
+        .and_then(|storage| storage.get_item(WATCHLIST_KEY).ok()?)
+        .map(|watchlist| parse_watchlist(&watchlist))
+        .unwrap_or_default()
+}
+
+fn store_watchlist(watchlist: &BTreeSet<FederationId>) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+
+    // Failing to persist the watchlist only means it's lost on reload
+    let _ = storage.set_item(WATCHLIST_KEY, &format_watchlist(watchlist));
+}
+
+/// Federation ids separated by commas or whitespace, invalid ones are skipped
+fn parse_watchlist(watchlist: &str) -> BTreeSet<FederationId> {
+    watchlist
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|id| FederationId::from_str(id).ok())
+        .collect()
+}
+
+fn format_watchlist(watchlist: &BTreeSet<FederationId>) -> String {
+    watchlist
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Star toggling whether a federation is on the watchlist
+#[component]
+pub fn WatchStar(
+    federation_id: FederationId,
+    watchlist: RwSignal<BTreeSet<FederationId>>,
+) -> impl IntoView {
+    let watched = move || watchlist.get().contains(&federation_id);
+    let toggle = move |_| {
+        watchlist.update(|watchlist| {
+            if !watchlist.remove(&federation_id) {
+                watchlist.insert(federation_id);
+            }
+            store_watchlist(watchlist);
+        })
+    };
+
+    view! {
+        <button
+            type="button"
+            class="me-2 text-lg leading-none"
+            class=("text-yellow-400", watched)
+            class=("text-gray-300 dark:text-gray-600 hover:text-yellow-400", move || !watched())
+            title=move || if watched() { "Remove from watchlist" } else { "Add to watchlist" }
+            on:click=toggle
+        >
+            "★"
+        </button>
+    }
+}
+
+/// Pinned table of the federations on the watchlist with export and import of
+/// the list, so it can be moved to another browser
+#[component]
+pub fn Watchlist(
+    watchlist: RwSignal<BTreeSet<FederationId>>,
+    #[prop(into)] federations: Signal<Vec<FederationSummary>>,
+    columns: RwSignal<BTreeSet<Column>>,
+) -> impl IntoView {
+    let import = create_rw_signal(String::new());
+    let import_watchlist = move || {
+        watchlist.update(|watchlist| {
+            watchlist.extend(parse_watchlist(&import.get()));
+            store_watchlist(watchlist);
+        });
+        import.set(String::new());
+    };
+
+    let export_href = move || {
+        format!(
+            "data:text/plain;charset=utf-8,{}",
+            format_watchlist(&watchlist.get()).replace('\n', "%0A")
+        )
+    };
+
+    let rows = move || {
+        let watchlist = watchlist.get();
+        let watched = federations
+            .get()
+            .into_iter()
+            .filter(|summary| watchlist.contains(&summary.id))
+            .collect::<Vec<_>>();
+
+        if watched.is_empty() {
+            return view! {
+                <tr class="bg-white dark:bg-gray-800">
+                    <td class="px-6 py-4" colspan="100%">
+                        "None of the watched federations are observed by this instance"
+                    </td>
+                </tr>
+            }
+            .into_view();
+        }
+
+        watched
+            .into_iter()
+            .map(|summary| {
+                let avg_txs = summary.avg_daily_transactions();
+                let avg_volume = summary.avg_daily_volume();
+                view! {
+                    <FederationRow
+                        summary=summary
+                        avg_txs=avg_txs
+                        avg_volume=avg_volume
+                        columns=columns
+                        watchlist=watchlist
+                    />
+                }
+            })
+            .collect_view()
+    };
+
+    view! {
+        <div class="relative overflow-x-auto shadow-md sm:rounded-lg mb-8">
+            <table class="w-full text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                <caption class="p-5 text-lg font-semibold text-left rtl:text-right text-gray-900 bg-white dark:text-white dark:bg-gray-800">
+                    "Watchlist"
+                    <p class="mt-1 text-sm font-normal text-gray-500 dark:text-gray-400">
+                        "Federations you starred, only stored in this browser"
+                    </p>
+                    <div class="flex flex-wrap items-center gap-2 mt-3">
+                        <a
+                            href=export_href
+                            download="fmo-watchlist.txt"
+                            class="text-sm font-normal text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                        >
+                            "Export"
+                        </a>
+                        <input
+                            type="text"
+                            placeholder="Federation ids to import"
+                            class="bg-gray-50 border border-gray-300 text-gray-900 text-sm font-normal rounded-lg focus:ring-blue-500 focus:border-blue-500 block p-2 dark:bg-gray-700 dark:border-gray-600 dark:placeholder-gray-400 dark:text-white"
+                            prop:value=move || import.get()
+                            on:input=move |ev| import.set(event_target_value(&ev))
+                        />
+                        <Button
+                            color_scheme=SECONDARY_BUTTON
+                            class="py-2"
+                            on_click=import_watchlist
+                            disabled=Signal::derive(move || import.get().trim().is_empty())
+                        >
+                            "Import"
+                        </Button>
+                    </div>
+                </caption>
+                <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                    <tr>
+                        <th scope="col" class="px-6 py-3">
+                            "Name"
+                        </th>
+                        {move || {
+                            columns
+                                .get()
+                                .into_iter()
+                                .map(|column| {
+                                    view! {
+                                        <th scope="col" class="px-6 py-3">
+                                            {column.header()}
+                                        </th>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+    }
+}
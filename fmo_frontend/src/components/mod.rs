@@ -1,3 +1,4 @@
+mod admin_token;
 mod alert;
 mod badge;
 pub mod button;
@@ -6,9 +7,17 @@ mod federation;
 mod federations;
 mod navbar;
 pub mod nostr;
+mod session;
 mod tabs;
+mod transaction;
+mod transaction_graph;
+mod version_check;
 
+pub use admin_token::AdminToken;
 pub use copyable::Copyable;
-pub use federation::Federation;
+pub use federation::{Federation, FederationReport};
 pub use federations::Federations;
 pub use navbar::{NavBar, NavItem};
+pub use session::Session;
+pub use transaction::Transaction;
+pub use version_check::VersionCheck;
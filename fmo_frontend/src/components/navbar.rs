@@ -1,4 +1,6 @@
-use leptos::{component, view, IntoView};
+use leptos::{component, view, IntoView, SignalGet, SignalUpdate};
+
+use crate::theme::{store_theme, use_theme, Theme};
 
 pub struct NavItem {
     pub name: String,
@@ -37,7 +39,7 @@ pub fn NavBar(items: Vec<NavItem>) -> impl IntoView {
     }).collect::<Vec<_>>();
 
     view! {
-        <nav class="bg-white border-gray-200 dark:bg-gray-900">
+        <nav class="bg-white border-gray-200 dark:bg-gray-900 print:hidden">
             <div class="max-w-screen-xl flex flex-wrap items-center justify-between mx-auto p-4">
                 <a href="/" class="flex items-center space-x-3 rtl:space-x-reverse">
                     <img src="/fedimint.png" class="h-8" alt="Fedimint Logo"/>
@@ -46,6 +48,7 @@ pub fn NavBar(items: Vec<NavItem>) -> impl IntoView {
                     </span>
                 </a>
                 <div class="flex md:order-2">
+                    <ThemeToggle/>
                     <a
                         href="https://github.com/elsirion/fedimint-observer/"
                         class="inline-flex items-center justify-center h-9 mr-3 px-3 text-xs font-medium text-gray-900 bg-white border border-gray-200 rounded-lg focus:outline-none hover:bg-gray-100 hover:text-blue-700 focus:z-10 focus:ring-2 focus:ring-gray-300 dark:focus:ring-gray-500 dark:bg-gray-800 dark:text-gray-400 dark:border-gray-600 dark:hover:text-white dark:hover:bg-gray-700"
@@ -77,3 +80,53 @@ pub fn NavBar(items: Vec<NavItem>) -> impl IntoView {
         </nav>
     }
 }
+
+/// Switches between the light and dark theme and remembers the choice
+#[component]
+fn ThemeToggle() -> impl IntoView {
+    let theme = use_theme();
+
+    view! {
+        <button
+            type="button"
+            title=move || match theme.get() {
+                Theme::Light => "Switch to dark theme",
+                Theme::Dark => "Switch to light theme",
+            }
+            class="inline-flex items-center justify-center h-9 w-9 mr-3 text-gray-500 rounded-lg hover:bg-gray-100 focus:outline-none focus:ring-2 focus:ring-gray-200 dark:text-gray-400 dark:hover:bg-gray-700 dark:focus:ring-gray-700"
+            on:click=move |_| {
+                theme.update(|theme| *theme = theme.toggled());
+                store_theme(theme.get());
+            }
+        >
+            {move || match theme.get() {
+                Theme::Light => {
+                    view! {
+                        <svg
+                            class="w-4 h-4"
+                            aria-hidden="true"
+                            xmlns="http://www.w3.org/2000/svg"
+                            fill="currentColor"
+                            viewBox="0 0 20 20"
+                        >
+                            <path d="M17.8 13.75a1 1 0 0 0-.859-.5A7.488 7.488 0 0 1 10.52 2a1 1 0 0 0 0-.969A1.035 1.035 0 0 0 9.687.5h-.113a9.5 9.5 0 1 0 8.222 14.247 1 1 0 0 0 .004-.997Z"></path>
+                        </svg>
+                    }
+                }
+                Theme::Dark => {
+                    view! {
+                        <svg
+                            class="w-4 h-4"
+                            aria-hidden="true"
+                            xmlns="http://www.w3.org/2000/svg"
+                            fill="currentColor"
+                            viewBox="0 0 20 20"
+                        >
+                            <path d="M10 15a5 5 0 1 0 0-10 5 5 0 0 0 0 10Zm0-11a1 1 0 0 0 1-1V1a1 1 0 0 0-2 0v2a1 1 0 0 0 1 1Zm0 12a1 1 0 0 0-1 1v2a1 1 0 1 0 2 0v-2a1 1 0 0 0-1-1ZM4.343 5.757a1 1 0 0 0 1.414-1.414L4.343 2.929a1 1 0 0 0-1.414 1.414l1.414 1.414Zm11.314 8.486a1 1 0 0 0-1.414 1.414l1.414 1.414a1 1 0 0 0 1.414-1.414l-1.414-1.414ZM4 10a1 1 0 0 0-1-1H1a1 1 0 0 0 0 2h2a1 1 0 0 0 1-1Zm15-1h-2a1 1 0 1 0 0 2h2a1 1 0 0 0 0-2ZM4.343 14.243l-1.414 1.414a1 1 0 1 0 1.414 1.414l1.414-1.414a1 1 0 0 0-1.414-1.414ZM14.95 6.05a1 1 0 0 0 .707-.293l1.414-1.414a1 1 0 1 0-1.414-1.414l-1.414 1.414a1 1 0 0 0 .707 1.707Z"></path>
+                        </svg>
+                    }
+                }
+            }}
+        </button>
+    }
+}
@@ -1,31 +1,36 @@
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::str::FromStr;
 
-use anyhow::{ensure, Context};
-use fedimint_core::config::JsonClientConfig;
+use anyhow::Context;
+use fedimint_core::config::{FederationId, JsonClientConfig};
 use fedimint_core::core::ModuleKind;
 use fedimint_core::invite_code::InviteCode;
 use leptos::html::Input;
 use leptos::{
-    component, create_action, create_node_ref, view, IntoView, MaybeSignal, SignalGet,
-    SignalGetUntracked,
+    component, create_action, create_node_ref, create_resource, view, IntoView, MaybeSignal,
+    RwSignal, SignalGet, SignalGetUntracked,
 };
 use nostr_sdk::{EventBuilder, Kind, SingleLetterTag, Tag, TagKind};
-use reqwest::StatusCode;
 
 use crate::components::alert::{Alert, AlertLevel};
 use crate::components::badge::{Badge, BadgeLevel};
 use crate::components::button::{Button, SUCCESS_BUTTON};
-use crate::BASE_URL;
+use crate::components::nostr::observe_button::ObserveButton;
 
 #[derive(Debug, Clone)]
 struct FederationInfo {
+    federation_id: FederationId,
+    invite_code: InviteCode,
     federation_name: String,
     federation_config: JsonClientConfig,
 }
 
 #[component]
-pub fn CheckFederation() -> impl IntoView {
+pub fn CheckFederation(
+    admin_token: RwSignal<Option<String>>,
+    observed: RwSignal<BTreeSet<FederationId>>,
+) -> impl IntoView {
     let invite_input_ref = create_node_ref::<Input>();
     let check_federation_action = create_action(move |&()| async move {
         let check_federation_inner = move || async move {
@@ -33,18 +38,14 @@ pub fn CheckFederation() -> impl IntoView {
                 .get_untracked()
                 .expect("invite_input_ref should be loaded by now")
                 .value();
+            let parsed_invite_code =
+                InviteCode::from_str(invite_code.trim()).context("Invalid invite code")?;
 
-            let federation_config = {
-                let url = format!("{}/config/{invite_code}", BASE_URL);
-                let response = reqwest::get(&url).await?;
-                let config: JsonClientConfig = response.json().await?;
-                config
-            };
+            let client = crate::client();
+            let federation_config = client.config_by_invite(&parsed_invite_code).await?;
 
             let federation_name = {
-                let url = format!("{}/config/{invite_code}/meta", BASE_URL);
-                let response = reqwest::get(&url).await?;
-                let meta: BTreeMap<String, serde_json::Value> = response.json().await?;
+                let meta = client.meta_by_invite(&parsed_invite_code).await?;
                 meta.get("federation_name")
                     .context("No name found")?
                     .as_str()
@@ -53,6 +54,8 @@ pub fn CheckFederation() -> impl IntoView {
             };
 
             Result::<_, anyhow::Error>::Ok(FederationInfo {
+                federation_id: parsed_invite_code.federation_id(),
+                invite_code: parsed_invite_code,
                 federation_name,
                 federation_config,
             })
@@ -261,6 +264,17 @@ pub fn CheckFederation() -> impl IntoView {
                                     </tbody>
                                 </table>
                             </div>
+                            { move || check_federation_action.value().get().and_then(|info| info.ok()).map(|info| view! {
+                                <GuardianChecks invite_code=info.invite_code.clone()/>
+                                <div class="mt-4">
+                                    <ObserveButton
+                                        federation_id=info.federation_id
+                                        invite_code=info.invite_code
+                                        admin_token=admin_token
+                                        observed=observed
+                                    />
+                                </div>
+                            })}
                         </div>
                     }.into_view()
                 } else if let Some(Err(e)) = check_federation_action.value().get() {
@@ -279,6 +293,93 @@ pub fn CheckFederation() -> impl IntoView {
     }
 }
 
+/// Reachability, version and config consistency of every guardian, checked
+/// individually by the server
+#[component]
+fn GuardianChecks(invite_code: InviteCode) -> impl IntoView {
+    let checks_resource = create_resource(
+        move || invite_code.clone(),
+        |invite_code| async move {
+            crate::client()
+                .check_invite(&invite_code)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    move || match checks_resource.get() {
+        Some(Ok(check)) => {
+            let rows = check
+                .guardians
+                .into_iter()
+                .map(|guardian| {
+                    let status = if guardian.config_mismatch {
+                        view! {
+                            <Badge
+                                level=BadgeLevel::Warning
+                                tooltip=guardian.config_hash.map(|hash| format!("Config hash {hash}"))
+                            >
+                                "Config mismatch"
+                            </Badge>
+                        }
+                        .into_view()
+                    } else if guardian.reachable {
+                        view! { <Badge level=BadgeLevel::Success>"Reachable"</Badge> }.into_view()
+                    } else {
+                        view! {
+                            <Badge level=BadgeLevel::Error tooltip=guardian.error>
+                                "Unreachable"
+                            </Badge>
+                        }
+                        .into_view()
+                    };
+
+                    view! {
+                        <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
+                            <td class="px-6 py-4">{guardian.guardian_id.to_usize()}</td>
+                            <td class="px-6 py-4 break-all">{guardian.url}</td>
+                            <td class="px-6 py-4">{status}</td>
+                            <td class="px-6 py-4">
+                                {guardian.consensus_version.unwrap_or_else(|| "-".to_owned())}
+                            </td>
+                            <td class="px-6 py-4">
+                                {guardian
+                                    .latency_ms
+                                    .map(|latency| format!("{latency} ms"))
+                                    .unwrap_or_else(|| "-".to_owned())}
+                            </td>
+                        </tr>
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            view! {
+                <table class="w-full mt-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+                    <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                        <tr>
+                            <th scope="col" class="px-6 py-3">"Guardian"</th>
+                            <th scope="col" class="px-6 py-3">"URL"</th>
+                            <th scope="col" class="px-6 py-3">"Status"</th>
+                            <th scope="col" class="px-6 py-3">"Consensus version"</th>
+                            <th scope="col" class="px-6 py-3">"Latency"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{rows}</tbody>
+                </table>
+            }
+            .into_view()
+        }
+        Some(Err(e)) => view! {
+            <Alert message=e level=AlertLevel::Error class="mt-4"/>
+        }
+        .into_view(),
+        None => view! {
+            <p class="mt-4 text-sm text-gray-500 dark:text-gray-400">"Checking guardians ..."</p>
+        }
+        .into_view(),
+    }
+}
+
 fn get_network(config: &JsonClientConfig) -> String {
     config
         .modules
@@ -350,19 +451,7 @@ async fn sign_and_publish_federation(config: &JsonClientConfig) -> anyhow::Resul
 
     let event = signer.sign_event(unsigned_event).await?;
 
-    let client = reqwest::Client::new();
-    let response = client
-        .put(format!("{}/nostr/federations", BASE_URL))
-        .json(&event)
-        .send()
-        .await?;
-
-    let status = response.status();
-    ensure!(
-        status == StatusCode::OK,
-        "Unexpected status code {}",
-        status
-    );
-
-    Ok(())
+    crate::client()
+        .publish_federation_announcement(&event)
+        .await
 }
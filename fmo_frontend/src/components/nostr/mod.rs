@@ -1,29 +1,43 @@
 mod check_federation;
 mod nostr_federation_row;
+mod observe_button;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use check_federation::CheckFederation;
 use fedimint_core::config::FederationId;
-use fedimint_core::invite_code::InviteCode;
-use fedimint_core::util::backon::FibonacciBuilder;
-use fedimint_core::util::retry;
-use leptos::{component, create_resource, view, IntoView, SignalGet};
+use fmo_api_types::NostrFederationStatus;
+use leptos::{
+    component, create_effect, create_resource, create_rw_signal, view, IntoView, SignalGet,
+    SignalSet,
+};
 use leptos_meta::Title;
 use nostr_federation_row::NostrFederationRow;
 
-use crate::BASE_URL;
+use crate::admin::admin_token;
+use crate::components::AdminToken;
 
 #[component]
 pub fn NostrFederations() -> impl IntoView {
     let nostr_federations_res = create_resource(|| (), |_| fetch_nostr_federations());
+    let observed_federations_res = create_resource(|| (), |_| fetch_observed_federations());
+
+    let admin_token = create_rw_signal(admin_token());
+    let observed = create_rw_signal(BTreeSet::<FederationId>::new());
+    create_effect(move |_| {
+        if let Some(observed_federations) = observed_federations_res.get() {
+            observed.set(observed_federations);
+        }
+    });
 
     view! {
         <Title
             text="Fedimint Observer"
         />
 
-        <CheckFederation />
+        <NostrSummary />
+
+        <CheckFederation admin_token=admin_token observed=observed />
 
         <div class="relative overflow-x-auto shadow-md sm:rounded-lg mt-8">
             <table class="w-full text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
@@ -41,17 +55,25 @@ pub fn NostrFederations() -> impl IntoView {
                         <th scope="col" class="px-6 py-3">
                             "Invite Code"
                         </th>
+                        <th scope="col" class="px-6 py-3">
+                            "Reachable"
+                        </th>
+                        <th scope="col" class="px-6 py-3">
+                            "Status"
+                        </th>
                     </tr>
                 </thead>
                 <tbody>
                     { move || {
                         nostr_federations_res.get().unwrap_or_default()
                             .into_iter()
-                            .map(|(federation_id, invite_code)| {
+                            .map(|(federation_id, status)| {
                                 view! {
                                     <NostrFederationRow
                                         federation_id=federation_id
-                                        invite_code=invite_code
+                                        status=status
+                                        admin_token=admin_token
+                                        observed=observed
                                     />
                                 }
                             })
@@ -59,26 +81,62 @@ pub fn NostrFederations() -> impl IntoView {
                     }}
                 </tbody>
             </table>
+            <AdminToken admin_token=admin_token />
         </div>
     }
 }
-async fn fetch_nostr_federations() -> BTreeMap<FederationId, InviteCode> {
-    let url = format!("{}/nostr/federations", BASE_URL);
+/// Banner with the number of announced federations by reachability
+#[component]
+fn NostrSummary() -> impl IntoView {
+    let summary_res = create_resource(
+        || (),
+        |_| async {
+            crate::client()
+                .nostr_federation_summary()
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
 
-    let fetch_nostr_federations_impl = || {
-        let url_inner = url.clone();
-        async move {
-            let response = reqwest::get(&url_inner).await?;
-            let federations: BTreeMap<FederationId, InviteCode> = response.json().await?;
-            Ok(federations)
+    let stat = |label: &'static str, value: u64| {
+        view! {
+            <div class="flex-1 p-4 text-center">
+                <p class="text-2xl font-bold text-gray-900 dark:text-white">{value}</p>
+                <p class="text-sm text-gray-500 dark:text-gray-400">{label}</p>
+            </div>
         }
     };
 
-    retry(
-        "Fetching Nostr federations",
-        FibonacciBuilder::default().with_max_times(usize::MAX),
-        fetch_nostr_federations_impl,
-    )
-    .await
-    .expect("Will never return Err")
+    move || {
+        summary_res.get().and_then(Result::ok).map(|summary| {
+            view! {
+                <div class="flex flex-wrap mt-8 bg-white rounded-lg shadow-md divide-x divide-gray-200 dark:bg-gray-800 dark:divide-gray-700">
+                    {stat("Announced", summary.total)}
+                    {stat("Online", summary.online)}
+                    {stat("Offline", summary.offline)}
+                    {stat("Not checked yet", summary.unchecked)}
+                    {stat("Observed", summary.observed)}
+                </div>
+            }
+        })
+    }
+}
+
+async fn fetch_nostr_federations() -> BTreeMap<FederationId, NostrFederationStatus> {
+    crate::client()
+        .with_max_retries(usize::MAX)
+        .nostr_federations()
+        .await
+        .expect("Will never return Err")
+}
+
+async fn fetch_observed_federations() -> BTreeSet<FederationId> {
+    crate::client()
+        .with_max_retries(usize::MAX)
+        .federations()
+        .await
+        .expect("Will never return Err")
+        .into_iter()
+        .map(|federation| federation.id)
+        .collect()
 }
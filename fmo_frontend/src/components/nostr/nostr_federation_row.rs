@@ -1,22 +1,48 @@
-use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 
-use anyhow::Context;
 use fedimint_core::config::FederationId;
-use fedimint_core::invite_code::InviteCode;
-use fedimint_core::util::backon::FibonacciBuilder;
-use fedimint_core::util::retry;
-use leptos::{component, create_resource, view, IntoView, SignalGet};
+use fmo_api_types::NostrFederationStatus;
+use leptos::{component, view, IntoView, RwSignal};
 
+use crate::components::badge::{Badge, BadgeLevel};
+use crate::components::nostr::observe_button::ObserveButton;
 use crate::components::Copyable;
-use crate::BASE_URL;
 
 #[component]
-pub fn NostrFederationRow(federation_id: FederationId, invite_code: InviteCode) -> impl IntoView {
-    let invite_code_inner = invite_code.clone();
-    let federation_name_res = create_resource(
-        || (),
-        move |_| fetch_federation_name(invite_code_inner.clone()),
-    );
+pub fn NostrFederationRow(
+    federation_id: FederationId,
+    status: NostrFederationStatus,
+    admin_token: RwSignal<Option<String>>,
+    observed: RwSignal<BTreeSet<FederationId>>,
+) -> impl IntoView {
+    let name = status
+        .name
+        .clone()
+        .unwrap_or_else(|| federation_id.to_string());
+    let last_seen = status
+        .last_seen
+        .map(|time| format!("Last seen {}", time.format("%Y-%m-%d %H:%M UTC")))
+        .unwrap_or_else(|| "Never seen online".to_owned());
+    let reachability = match status.online {
+        Some(true) => view! {
+            <Badge level=BadgeLevel::Success tooltip=Some(last_seen)>
+                "Online"
+            </Badge>
+        }
+        .into_view(),
+        Some(false) => view! {
+            <Badge level=BadgeLevel::Error tooltip=Some(last_seen)>
+                "Offline"
+            </Badge>
+        }
+        .into_view(),
+        None => view! {
+            <Badge level=BadgeLevel::Info>
+                "Not checked yet"
+            </Badge>
+        }
+        .into_view(),
+    };
 
     view! {
         <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700">
@@ -24,42 +50,20 @@ pub fn NostrFederationRow(federation_id: FederationId, invite_code: InviteCode)
                 scope="row"
                 class="px-6 py-4 font-medium text-gray-900 whitespace-nowrap dark:text-white"
             >
-                { move || {
-                    match federation_name_res.get() {
-                        Some(name) => name,
-                        None => federation_id.to_string(),
-                    }
-                }}
+                {name}
             </th>
             <td>
-                <Copyable text=invite_code.to_string()/>
+                <Copyable text=status.invite_code.to_string()/>
+            </td>
+            <td class="px-6 py-4">{reachability}</td>
+            <td class="px-6 py-4">
+                <ObserveButton
+                    federation_id=federation_id
+                    invite_code=status.invite_code
+                    admin_token=admin_token
+                    observed=observed
+                />
             </td>
         </tr>
     }
 }
-
-async fn fetch_federation_name(invite_code: InviteCode) -> String {
-    let url = format!("{}/config/{invite_code}/meta", BASE_URL);
-
-    let fetch_federation_name_impl = || {
-        let url_inner = url.clone();
-        async move {
-            let response = reqwest::get(&url_inner).await?;
-            let federation: BTreeMap<String, serde_json::Value> = response.json().await?;
-            Ok(federation
-                .get("federation_name")
-                .context("No name found")?
-                .as_str()
-                .context("Name isn't a string")?
-                .to_owned())
-        }
-    };
-
-    retry(
-        "Fetching federation name",
-        FibonacciBuilder::default().with_max_times(usize::MAX),
-        fetch_federation_name_impl,
-    )
-    .await
-    .expect("Won't terminate")
-}
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+
+use fedimint_core::config::FederationId;
+use fedimint_core::invite_code::InviteCode;
+use leptos::{
+    component, create_action, store_value, view, IntoView, RwSignal, SignalGet, SignalGetUntracked,
+    SignalUpdate,
+};
+
+use crate::admin::observe_federation;
+use crate::components::badge::{Badge, BadgeLevel};
+use crate::components::button::{Button, SUCCESS_BUTTON};
+
+/// Shows whether a federation is already observed by this instance and, if an
+/// admin token is configured, a button to start observing it
+#[component]
+pub fn ObserveButton(
+    federation_id: FederationId,
+    invite_code: InviteCode,
+    admin_token: RwSignal<Option<String>>,
+    observed: RwSignal<BTreeSet<FederationId>>,
+) -> impl IntoView {
+    let observe_action = create_action(move |invite_code: &InviteCode| {
+        let invite_code = invite_code.clone();
+        async move {
+            let admin_token = admin_token
+                .get_untracked()
+                .ok_or_else(|| "No admin token configured".to_owned())?;
+            let federation_id = observe_federation(&admin_token, &invite_code)
+                .await
+                .map_err(|e| e.to_string())?;
+            observed.update(|observed| {
+                observed.insert(federation_id);
+            });
+            Result::<_, String>::Ok(())
+        }
+    });
+
+    let invite_code = store_value(invite_code);
+
+    move || {
+        if observed.get().contains(&federation_id) {
+            view! {
+                <Badge level=BadgeLevel::Success>
+                    Observed
+                </Badge>
+            }
+            .into_view()
+        } else if admin_token.get().is_some() {
+            view! {
+                <div class="flex gap-2 items-center">
+                    <Button
+                        on_click=move || {
+                            observe_action.dispatch(invite_code.get_value());
+                        }
+                        disabled=observe_action.pending()
+                        color_scheme=SUCCESS_BUTTON
+                        class="h-9"
+                    >
+                        Observe
+                    </Button>
+                    { move || match observe_action.value().get() {
+                        Some(Err(e)) => view! {
+                            <span class="text-sm text-red-800 dark:text-red-400">{e}</span>
+                        }.into_view(),
+                        _ => view!().into_view(),
+                    }}
+                </div>
+            }
+            .into_view()
+        } else {
+            view!().into_view()
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use fedimint_core::config::FederationId;
+use fmo_api_types::SessionItem;
+use leptos::{
+    component, create_resource, view, CollectView, IntoView, Show, SignalGet, SignalWith,
+};
+use leptos_meta::Title;
+use leptos_router::{use_params, Params, ParamsError, ParamsMap};
+
+/// Page listing the decoded consensus items of a single session
+#[component]
+pub fn Session() -> impl IntoView {
+    let params = move || {
+        let params = use_params::<SessionParams>();
+        params.with(|params| params.as_ref().ok().cloned())
+    };
+
+    let session_resource = create_resource(params, |params| async move {
+        let params = params.ok_or_else(|| "Invalid session".to_owned())?;
+        crate::client()
+            .session_details(params.id, params.session_index)
+            .await
+            .map_err(|e| e.to_string())
+    });
+
+    view! {
+        <Show
+            when=move || params().is_some()
+            fallback=|| view! { <p>"Invalid federation id or session index"</p> }
+        >
+            <Title text=move || format!("Session {}", params().unwrap().session_index)/>
+            <h2 class="text-2xl my-8 font-extrabold dark:text-white truncate">
+                "Session " {move || params().unwrap().session_index}
+            </h2>
+            <a
+                href=move || format!("/federations/{}", params().unwrap().id)
+                class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+            >
+                "Back to federation"
+            </a>
+            {move || {
+                let federation_id = params().unwrap().id;
+                match session_resource.get() {
+                    Some(Ok(session)) => {
+                        let estimated_time = session
+                            .summary
+                            .estimated_time
+                            .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+                            .unwrap_or_else(|| "unknown".to_owned());
+                        view! {
+                            <p class="my-4 text-gray-500 dark:text-gray-400">
+                                {session.summary.item_count} " items, "
+                                {session.summary.transaction_count} " transactions, estimated time "
+                                {estimated_time}
+                            </p>
+                            <SessionItems items=session.items federation_id=federation_id/>
+                        }
+                            .into_view()
+                    }
+                    Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                    None => view! { <p>"Loading ..."</p> }.into_view(),
+                }
+            }}
+        </Show>
+    }
+}
+
+#[component]
+fn SessionItems(items: Vec<SessionItem>, federation_id: FederationId) -> impl IntoView {
+    let rows = items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let kind = match item.txid {
+                Some(txid) => view! {
+                    <a
+                        href=format!("/federations/{federation_id}/transactions/{txid}")
+                        class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                    >
+                        {item.kind}
+                    </a>
+                }
+                .into_view(),
+                None => item.kind.into_view(),
+            };
+
+            view! {
+                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700 align-top">
+                    <td class="px-6 py-4">{idx}</td>
+                    <td class="px-6 py-4">{item.peer}</td>
+                    <td class="px-6 py-4">{kind}</td>
+                    <td class="px-6 py-4">
+                        <details>
+                            <summary class="cursor-pointer">"Show"</summary>
+                            <pre class="whitespace-pre-wrap break-all">{item.decoded}</pre>
+                        </details>
+                    </td>
+                </tr>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <table class="w-full my-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+            <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                <tr>
+                    <th scope="col" class="px-6 py-3">"#"</th>
+                    <th scope="col" class="px-6 py-3">"Guardian"</th>
+                    <th scope="col" class="px-6 py-3">"Kind"</th>
+                    <th scope="col" class="px-6 py-3">"Decoded"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SessionParams {
+    id: FederationId,
+    session_index: u64,
+}
+
+impl Params for SessionParams {
+    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
+        let id = map
+            .get("id")
+            .and_then(|id| FederationId::from_str(id).ok())
+            .ok_or_else(|| ParamsError::MissingParam("id".into()))?;
+        let session_index = map
+            .get("session_index")
+            .and_then(|session_index| session_index.parse().ok())
+            .ok_or_else(|| ParamsError::MissingParam("session_index".into()))?;
+
+        Ok(SessionParams { id, session_index })
+    }
+}
@@ -1,7 +1,7 @@
 use leptos::leptos_dom::Transparent;
 use leptos::{
-    component, create_signal, view, Children, ChildrenFn, CollectView, IntoView, SignalGet,
-    SignalSet, View,
+    component, create_memo, create_signal, view, Children, ChildrenFn, CollectView, IntoView,
+    SignalGet, SignalSet, View,
 };
 use tracing::warn;
 
@@ -45,7 +45,10 @@ pub fn Tabs(#[prop(into)] default: String, children: Children) -> impl IntoView
                         }
                     }
 
-                    on:click=move |_| set_active_tab.set(tab_name_c.clone())
+                    on:click=move |ev| {
+                        ev.prevent_default();
+                        set_active_tab.set(tab_name_c.clone());
+                    }
                 >
                     {tab_name}
                 </a>
@@ -53,24 +56,31 @@ pub fn Tabs(#[prop(into)] default: String, children: Children) -> impl IntoView
         }
     }).collect_view();
 
-    let get_tab_content = move |name: String| {
-        tab_names
-            .iter()
-            .find_map(|(tab_name, children)| {
-                if *tab_name == name {
-                    Some(children.clone())
-                } else {
-                    None
-                }
-            })
-            .expect("Tab not found")
-    };
+    // Each tab is rendered when it's opened for the first time and then kept
+    // around hidden, so it neither fetches data before it's needed nor loses its
+    // state when switching back to it. The memo only changes once, re-rendering
+    // on every switch would recreate the tab's resources.
+    let tab_contents = tab_names
+        .into_iter()
+        .map(|(tab_name, children)| {
+            let tab_name_o = tab_name.clone();
+            let opened = create_memo(move |opened: Option<&bool>| {
+                opened.copied().unwrap_or(false) || active_tab.get() == tab_name_o
+            });
+
+            view! {
+                <div class:hidden=move || active_tab.get() != tab_name>
+                    {move || opened.get().then(|| children().into_view())}
+                </div>
+            }
+        })
+        .collect_view();
 
     view! {
         <div class="text-sm font-medium text-center text-gray-500 border-b border-gray-200 dark:text-gray-400 dark:border-gray-700">
             <ul class="flex flex-wrap -mb-px">{tabs}</ul>
         </div>
-        {move || (get_tab_content(active_tab.get()))()}
+        {tab_contents}
     }
 }
 
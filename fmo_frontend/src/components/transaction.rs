@@ -0,0 +1,175 @@
+use std::str::FromStr;
+
+use fedimint_core::config::FederationId;
+use fedimint_core::TransactionId;
+use fmo_api_types::{DisplayUnit, TransactionItem};
+use leptos::{
+    component, create_resource, view, CollectView, IntoView, Show, SignalGet, SignalWith,
+};
+use leptos_meta::Title;
+use leptos_router::{use_params, Params, ParamsError, ParamsMap};
+
+use crate::components::transaction_graph::TransactionFlow;
+use crate::instance::use_instance_settings;
+use crate::util::format_amount;
+
+/// Page showing the decoded inputs and outputs of a single transaction
+#[component]
+pub fn Transaction() -> impl IntoView {
+    let params = move || {
+        let params = use_params::<TransactionParams>();
+        params.with(|params| params.as_ref().ok().cloned())
+    };
+
+    let transaction_resource = create_resource(params, |params| async move {
+        let params = params.ok_or_else(|| "Invalid transaction".to_owned())?;
+        crate::client()
+            .transaction_details(params.id, params.txid)
+            .await
+            .map_err(|e| e.to_string())
+    });
+
+    let settings = use_instance_settings();
+    let display_unit = move || settings.with(|settings| settings.display_unit);
+
+    view! {
+        <Show
+            when=move || params().is_some()
+            fallback=|| view! { <p>"Invalid federation or transaction id"</p> }
+        >
+            <Title text=move || format!("Transaction {}", params().unwrap().txid)/>
+            <h2 class="text-2xl my-8 font-extrabold dark:text-white truncate">
+                "Transaction " {move || params().unwrap().txid.to_string()}
+            </h2>
+            <a
+                href=move || format!("/federations/{}", params().unwrap().id)
+                class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+            >
+                "Back to federation"
+            </a>
+            {move || {
+                let federation_id = params().unwrap().id;
+                match transaction_resource.get() {
+                    Some(Ok(transaction)) => {
+                        view! {
+                            <p class="my-4 text-gray-500 dark:text-gray-400">
+                                "Included in session " {transaction.session_index} ", item "
+                                {transaction.item_index}
+                            </p>
+                            <TransactionFlow
+                                federation_id=federation_id
+                                txid=transaction.txid
+                                display_unit=display_unit()
+                            />
+                            <TransactionItems
+                                title="Inputs"
+                                items=transaction.inputs
+                                federation_id=federation_id
+                                display_unit=display_unit()
+                            />
+                            <TransactionItems
+                                title="Outputs"
+                                items=transaction.outputs
+                                federation_id=federation_id
+                                display_unit=display_unit()
+                            />
+                        }
+                            .into_view()
+                    }
+                    Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+                    None => view! { <p>"Loading ..."</p> }.into_view(),
+                }
+            }}
+        </Show>
+    }
+}
+
+#[component]
+fn TransactionItems(
+    title: &'static str,
+    items: Vec<TransactionItem>,
+    federation_id: FederationId,
+    display_unit: DisplayUnit,
+) -> impl IntoView {
+    let rows = items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let ln_contract = item.ln_contract.map(|contract| {
+                let funding_link = contract.funding_txid.map(|funding_txid| {
+                    view! {
+                        <a
+                            href=format!("/federations/{federation_id}/transactions/{funding_txid}")
+                            class="text-blue-600 underline dark:text-blue-500 hover:no-underline"
+                        >
+                            "funded in " {funding_txid.to_string()}
+                        </a>
+                    }
+                });
+                view! {
+                    <div class="font-mono truncate max-w-xs" title=contract.contract_id.clone()>
+                        {contract.contract_id}
+                    </div>
+                    <div>{contract.interaction}</div>
+                    <div class="truncate max-w-xs">{funding_link}</div>
+                }
+            });
+
+            view! {
+                <tr class="bg-white border-b dark:bg-gray-800 dark:border-gray-700 align-top">
+                    <td class="px-6 py-4">{idx}</td>
+                    <td class="px-6 py-4">{item.kind}</td>
+                    <td class="px-6 py-4">
+                        {item.amount.map(|amount| format_amount(amount, display_unit, 8))}
+                    </td>
+                    <td class="px-6 py-4">{ln_contract}</td>
+                    <td class="px-6 py-4">
+                        <details>
+                            <summary class="cursor-pointer">"Show"</summary>
+                            <pre class="whitespace-pre-wrap break-all">{item.decoded}</pre>
+                        </details>
+                    </td>
+                </tr>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <table class="w-full my-4 text-sm text-left rtl:text-right text-gray-500 dark:text-gray-400">
+            <caption class="p-5 text-lg font-semibold text-left rtl:text-right text-gray-900 bg-white dark:text-white dark:bg-gray-800">
+                {title}
+            </caption>
+            <thead class="text-xs text-gray-700 uppercase bg-gray-50 dark:bg-gray-700 dark:text-gray-400">
+                <tr>
+                    <th scope="col" class="px-6 py-3">"#"</th>
+                    <th scope="col" class="px-6 py-3">"Module"</th>
+                    <th scope="col" class="px-6 py-3">"Amount"</th>
+                    <th scope="col" class="px-6 py-3">"LN Contract"</th>
+                    <th scope="col" class="px-6 py-3">"Decoded"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TransactionParams {
+    id: FederationId,
+    txid: TransactionId,
+}
+
+impl Params for TransactionParams {
+    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
+        let id = map
+            .get("id")
+            .and_then(|id| FederationId::from_str(id).ok())
+            .ok_or_else(|| ParamsError::MissingParam("id".into()))?;
+        let txid = map
+            .get("txid")
+            .and_then(|txid| TransactionId::from_str(txid).ok())
+            .ok_or_else(|| ParamsError::MissingParam("txid".into()))?;
+
+        Ok(TransactionParams { id, txid })
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use fedimint_core::config::FederationId;
+use fedimint_core::TransactionId;
+use fmo_api_types::{DisplayUnit, TransactionGraph, TransactionGraphNode, TransactionGraphNodeKind};
+use leptos::{component, create_resource, view, CollectView, IntoView, SignalGet};
+
+use crate::util::format_amount;
+
+const CENTER_NODE: &str = "transaction";
+const COLUMN_WIDTH: i32 = 200;
+const ROW_HEIGHT: i32 = 70;
+const NODE_WIDTH: i32 = 160;
+const NODE_HEIGHT: i32 = 48;
+
+/// Flow of funds through the transaction drawn as an SVG, with the nodes
+/// funding it on the left and the ones it funds on the right
+#[component]
+pub fn TransactionFlow(
+    federation_id: FederationId,
+    txid: TransactionId,
+    display_unit: DisplayUnit,
+) -> impl IntoView {
+    let graph_resource = create_resource(
+        || (),
+        move |()| async move {
+            crate::client()
+                .transaction_graph(federation_id, txid)
+                .await
+                .map_err(|e| e.to_string())
+        },
+    );
+
+    move || match graph_resource.get() {
+        Some(Ok(graph)) => view! {
+            <div class="my-4 overflow-x-auto bg-white dark:bg-gray-800 rounded-lg p-4">
+                <h3 class="text-lg font-semibold text-gray-900 dark:text-white mb-2">
+                    "Flow of funds"
+                </h3>
+                <GraphSvg graph=graph federation_id=federation_id display_unit=display_unit/>
+            </div>
+        }
+        .into_view(),
+        Some(Err(e)) => view! { <p>"Error: " {e}</p> }.into_view(),
+        None => view! { <p>"Loading ..."</p> }.into_view(),
+    }
+}
+
+#[component]
+fn GraphSvg(
+    graph: TransactionGraph,
+    federation_id: FederationId,
+    display_unit: DisplayUnit,
+) -> impl IntoView {
+    let positions = layout(&graph);
+    let width = positions.values().map(|(x, _)| x + NODE_WIDTH).max().unwrap_or(0);
+    let height = positions
+        .values()
+        .map(|(_, y)| y + NODE_HEIGHT)
+        .max()
+        .unwrap_or(0);
+
+    let edges = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let (from_x, from_y) = positions.get(&edge.from)?;
+            let (to_x, to_y) = positions.get(&edge.to)?;
+            Some(view! {
+                <line
+                    x1=from_x + NODE_WIDTH
+                    y1=from_y + NODE_HEIGHT / 2
+                    x2=*to_x
+                    y2=to_y + NODE_HEIGHT / 2
+                    class="stroke-gray-400"
+                    stroke-width="2"
+                />
+            })
+        })
+        .collect_view();
+
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .filter_map(|node| {
+            let (x, y) = *positions.get(&node.id)?;
+            let link = node_link(&node, federation_id);
+            let amount = node
+                .amount
+                .map(|amount| format_amount(amount, display_unit, 8))
+                .unwrap_or_default();
+            let rect = view! {
+                <rect
+                    x=x
+                    y=y
+                    width=NODE_WIDTH
+                    height=NODE_HEIGHT
+                    rx="6"
+                    class=node_class(node.kind)
+                />
+                <text x=x + 8 y=y + 20 class="fill-gray-900 text-xs font-medium">
+                    {node.label}
+                </text>
+                <text x=x + 8 y=y + 38 class="fill-gray-700 text-xs">
+                    {amount}
+                </text>
+            };
+
+            let clickable = link.is_some();
+            Some(view! {
+                <g
+                    class:cursor-pointer=clickable
+                    on:click=move |_| {
+                        if let Some(link) = &link {
+                            let _ = leptos::window().location().set_href(link);
+                        }
+                    }
+                >
+                    {rect}
+                </g>
+            })
+        })
+        .collect_view();
+
+    view! {
+        <svg
+            width=width
+            height=height
+            viewBox=format!("0 0 {width} {height}")
+            xmlns="http://www.w3.org/2000/svg"
+        >
+            {edges}
+            {nodes}
+        </svg>
+    }
+}
+
+fn node_class(kind: TransactionGraphNodeKind) -> &'static str {
+    match kind {
+        TransactionGraphNodeKind::Transaction => "fill-blue-200",
+        TransactionGraphNodeKind::Input | TransactionGraphNodeKind::Output => "fill-gray-200",
+        TransactionGraphNodeKind::LnContract => "fill-yellow-200",
+        TransactionGraphNodeKind::OnChainTransaction => "fill-orange-200",
+    }
+}
+
+/// Transactions other than the displayed one link to their own page, on-chain
+/// ones to a block explorer
+fn node_link(node: &TransactionGraphNode, federation_id: FederationId) -> Option<String> {
+    let txid = node.txid.as_ref()?;
+    match node.kind {
+        TransactionGraphNodeKind::Transaction if node.id != CENTER_NODE => Some(format!(
+            "/federations/{federation_id}/transactions/{txid}"
+        )),
+        TransactionGraphNodeKind::OnChainTransaction => {
+            Some(format!("https://mempool.space/tx/{txid}"))
+        }
+        _ => None,
+    }
+}
+
+/// Places every node in a column by its distance along the edges from the
+/// displayed transaction, nodes funding it to the left and nodes it funds to
+/// the right, in the order they appear in the graph
+fn layout(graph: &TransactionGraph) -> BTreeMap<String, (i32, i32)> {
+    let mut columns = BTreeMap::<String, i32>::new();
+    let mut queue = VecDeque::new();
+    columns.insert(CENTER_NODE.to_owned(), 0);
+    queue.push_back(CENTER_NODE.to_owned());
+
+    while let Some(id) = queue.pop_front() {
+        let column = columns[&id];
+        for edge in &graph.edges {
+            let (neighbour, neighbour_column) = if edge.from == id {
+                (&edge.to, column + 1)
+            } else if edge.to == id {
+                (&edge.from, column - 1)
+            } else {
+                continue;
+            };
+            if !columns.contains_key(neighbour) {
+                columns.insert(neighbour.clone(), neighbour_column);
+                queue.push_back(neighbour.clone());
+            }
+        }
+    }
+
+    let min_column = columns.values().copied().min().unwrap_or(0);
+    let mut rows = BTreeMap::<i32, i32>::new();
+    graph
+        .nodes
+        .iter()
+        .filter_map(|node| {
+            let column = columns.get(&node.id)? - min_column;
+            let row = rows.entry(column).or_default();
+            let position = (column * COLUMN_WIDTH, *row * ROW_HEIGHT);
+            *row += 1;
+            Some((node.id.clone(), position))
+        })
+        .collect()
+}
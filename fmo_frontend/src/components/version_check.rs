@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use fmo_api_types::VersionInfo;
+use leptos::{
+    component, create_rw_signal, set_interval, spawn_local, view, IntoView, Show, SignalGet,
+    SignalSet,
+};
+
+use crate::components::button::{Button, SECONDARY_BUTTON};
+
+const VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Periodically compares the version of the frontend with the one of the API
+/// server and asks the user to reload the page after a new deployment
+#[component]
+pub fn VersionCheck() -> impl IntoView {
+    let outdated = create_rw_signal(false);
+
+    // Builds without git revision (e.g. local development) can't be compared
+    if let Some(frontend_version) = frontend_version() {
+        let check_version = move || {
+            let frontend_version = frontend_version.clone();
+            spawn_local(async move {
+                match fetch_server_version().await {
+                    Ok(server_version) if server_version.git_rev.is_some() => {
+                        outdated.set(server_version != frontend_version);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::debug!("Failed to fetch server version: {e}");
+                    }
+                }
+            });
+        };
+
+        check_version();
+        set_interval(check_version, VERSION_CHECK_INTERVAL);
+    }
+
+    view! {
+        <Show when=move || outdated.get()>
+            <div
+                class="fixed bottom-5 right-5 z-50 flex items-center w-full max-w-sm p-4 gap-4 text-gray-500 bg-white rounded-lg shadow dark:text-gray-400 dark:bg-gray-800"
+                role="alert"
+            >
+                <div class="flex-1 text-sm font-normal">
+                    "A new version of Fedimint Observer is available."
+                </div>
+                <Button
+                    on_click=move || {
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.location().reload();
+                        }
+                    }
+                    class="h-9"
+                >
+                    Reload
+                </Button>
+                <Button
+                    on_click=move || outdated.set(false)
+                    color_scheme=SECONDARY_BUTTON
+                    class="h-9"
+                >
+                    Dismiss
+                </Button>
+            </div>
+        </Show>
+    }
+}
+
+fn frontend_version() -> Option<VersionInfo> {
+    Some(VersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        git_rev: Some(option_env!("FMO_GIT_REV")?.to_owned()),
+    })
+}
+
+async fn fetch_server_version() -> anyhow::Result<VersionInfo> {
+    crate::client().version().await
+}
@@ -0,0 +1,24 @@
+use fmo_api_types::InstanceSettings;
+use leptos::{create_rw_signal, provide_context, spawn_local, use_context, RwSignal, SignalSet};
+
+/// Fetches the instance settings from the server and makes them available to
+/// all components. Defaults are used until the request finishes or if it
+/// fails.
+pub fn provide_instance_settings() {
+    let settings = create_rw_signal(InstanceSettings::default());
+    spawn_local(async move {
+        match fetch_instance_settings().await {
+            Ok(fetched_settings) => settings.set(fetched_settings),
+            Err(e) => tracing::warn!("Failed to fetch instance settings: {e}"),
+        }
+    });
+    provide_context(settings);
+}
+
+pub fn use_instance_settings() -> RwSignal<InstanceSettings> {
+    use_context().expect("Instance settings are provided at startup")
+}
+
+async fn fetch_instance_settings() -> anyhow::Result<InstanceSettings> {
+    crate::client().instance_settings().await
+}
@@ -1,7 +1,15 @@
+mod admin;
 pub mod components;
+pub mod instance;
+pub mod theme;
 mod util;
 
 const BASE_URL: &str = match option_env!("FMO_API_SERVER") {
     Some(url) => url,
     None => "http://127.0.0.1:3000",
 };
+
+/// Client for the observer API the frontend was built against
+fn client() -> fmo_client::FmoClient {
+    fmo_client::FmoClient::new(BASE_URL)
+}
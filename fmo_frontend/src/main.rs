@@ -1,5 +1,9 @@
 use fmo_frontend::components::nostr::NostrFederations;
-use fmo_frontend::components::{Federation, Federations, NavBar, NavItem};
+use fmo_frontend::components::{
+    Federation, FederationReport, Federations, NavBar, NavItem, Session, Transaction, VersionCheck,
+};
+use fmo_frontend::instance::provide_instance_settings;
+use fmo_frontend::theme::provide_theme;
 use leptos::*;
 use leptos_meta::{provide_meta_context, Link};
 use leptos_router::{Route, Router, Routes};
@@ -11,13 +15,16 @@ fn main() {
     provide_meta_context();
 
     mount_to_body(move || {
+        provide_instance_settings();
+        provide_theme();
+
         view! {
             <Link
                 rel="icon"
                 type_="image/x-icon"
                 href="/fedimint.png"
             />
-            <body class="dark:bg-gray-900">
+            <body class="bg-white dark:bg-gray-900">
                 <Router>
                     <main class="container mx-auto max-w-6xl px-4 min-h-screen pb-4">
                         <NavBar items=vec![
@@ -36,10 +43,23 @@ fn main() {
                         <Routes>
                             <Route path="/" view=|| view! { <Federations/> }/>
                             <Route path="/federations/:id" view=|| view! { <Federation/> }/>
+                            <Route
+                                path="/federations/:id/report"
+                                view=|| view! { <FederationReport/> }
+                            />
+                            <Route
+                                path="/federations/:id/sessions/:session_index"
+                                view=|| view! { <Session/> }
+                            />
+                            <Route
+                                path="/federations/:id/transactions/:txid"
+                                view=|| view! { <Transaction/> }
+                            />
                             <Route path="/nostr" view=|| view! { <NostrFederations/> }/>
                             <Route path="/about" view=|| view! { <div>About</div> }/>
                         </Routes>
                     </main>
+                    <VersionCheck/>
                 </Router>
             </body>
         }
@@ -0,0 +1,84 @@
+use leptos::{
+    create_effect, create_rw_signal, provide_context, use_context, RwSignal, SignalGet,
+};
+
+use crate::util::local_storage;
+
+/// Local storage key under which the theme the user chose is kept
+const THEME_KEY: &str = "fmo_theme";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    fn key(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Theme> {
+        match key {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+
+    pub fn toggled(self) -> Theme {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+}
+
+/// Makes the theme available to all components and keeps the `dark` class on
+/// the document root, which all `dark:` styles depend on, in sync with it.
+/// Starts with the theme the user chose last or the system preference.
+pub fn provide_theme() {
+    let theme = create_rw_signal(stored_theme().unwrap_or_else(system_theme));
+    create_effect(move |_| {
+        let dark = theme.get() == Theme::Dark;
+        if let Some(root) = leptos::document().document_element() {
+            let _ = root.class_list().toggle_with_force("dark", dark);
+        }
+    });
+    provide_context(theme);
+}
+
+pub fn use_theme() -> RwSignal<Theme> {
+    use_context().expect("Theme is provided at startup")
+}
+
+fn stored_theme() -> Option<Theme> {
+    Theme::from_key(&local_storage()?.get_item(THEME_KEY).ok()??)
+}
+
+/// Persists the theme the user chose, from then on the system preference is
+/// ignored
+pub fn store_theme(theme: Theme) {
+    let Some(storage) = local_storage() else {
+        return;
+    };
+
+    // Failing to persist the theme only means the user has to choose it again
+    let _ = storage.set_item(THEME_KEY, theme.key());
+}
+
+fn system_theme() -> Theme {
+    let prefers_dark = leptos::window()
+        .match_media("(prefers-color-scheme: dark)")
+        .ok()
+        .flatten()
+        .is_some_and(|query| query.matches());
+    if prefers_dark {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
@@ -1,6 +1,14 @@
 use std::fmt::Display;
 
+use chrono::{DateTime, Utc};
 use fedimint_core::Amount;
+use fmo_api_types::DisplayUnit;
+use num_format::{Locale, ToFormattedString};
+
+/// The browser's local storage, if available
+pub fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
 
 pub struct FmtBitcoin {
     amount: Amount,
@@ -30,3 +38,30 @@ impl AsBitcoin for Amount {
         }
     }
 }
+
+/// Formats an amount in the display unit configured for this instance
+pub fn format_amount(amount: Amount, unit: DisplayUnit, btc_precision: usize) -> String {
+    match unit {
+        DisplayUnit::Btc => amount.as_bitcoin(btc_precision).to_string(),
+        DisplayUnit::Sats => format!(
+            "{} sats",
+            (amount.msats / 1000).to_formatted_string(&Locale::en)
+        ),
+    }
+}
+
+/// Formats the time since `since` in the largest sensible unit, e.g. "2 years"
+pub fn format_age(since: DateTime<Utc>) -> String {
+    let days = (Utc::now() - since).num_days().max(0);
+    let (count, unit) = match days {
+        0..=30 => (days, "day"),
+        31..=364 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
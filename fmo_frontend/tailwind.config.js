@@ -3,6 +3,8 @@ module.exports = {
   content: {
     files: ["*.html", "**/*.rs"],
   },
+  // Toggled by the theme switch in the navbar instead of following the system
+  darkMode: 'class',
   plugins: [
   ],
   // https://github.com/fedimint/ui/blob/master/packages/ui/src/theme.tsx
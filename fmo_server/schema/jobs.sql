@@ -0,0 +1,16 @@
+-- Created outside the versioned migrations since the backfills of migrations are tracked in it as well
+CREATE TABLE IF NOT EXISTS jobs
+(
+    id            BIGSERIAL PRIMARY KEY,
+    kind          TEXT      NOT NULL,
+    -- Not referencing federations, which don't exist yet before the first migration
+    federation_id BYTEA,
+    processed     BIGINT    NOT NULL,
+    total         BIGINT    NOT NULL,
+    started_at    TIMESTAMP NOT NULL,
+    updated_at    TIMESTAMP NOT NULL,
+    finished_at   TIMESTAMP,
+    error         TEXT
+);
+
+CREATE INDEX IF NOT EXISTS jobs_federation_id ON jobs (federation_id, started_at);
@@ -0,0 +1,21 @@
+INSERT INTO schema_version (version)
+VALUES (10);
+
+CREATE TABLE IF NOT EXISTS alert_settings
+(
+    federation_id             BYTEA            PRIMARY KEY NOT NULL REFERENCES federations (federation_id),
+    latency_regression_ratio  DOUBLE PRECISION NOT NULL,
+    latency_regression_min_ms INTEGER          NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS alert_events
+(
+    alert_id      BIGSERIAL PRIMARY KEY,
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    kind          TEXT      NOT NULL,
+    guardian_id   INTEGER,
+    message       TEXT      NOT NULL,
+    raised_at     TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS alert_events_federation_time ON alert_events (federation_id, raised_at);
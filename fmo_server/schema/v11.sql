@@ -0,0 +1,11 @@
+INSERT INTO schema_version (version)
+VALUES (11);
+
+CREATE TABLE IF NOT EXISTS observer_status
+(
+    federation_id        BYTEA     PRIMARY KEY NOT NULL REFERENCES federations (federation_id),
+    consecutive_failures INTEGER   NOT NULL,
+    last_error           TEXT      NOT NULL,
+    last_failure         TIMESTAMP NOT NULL,
+    quarantined          BOOLEAN   NOT NULL DEFAULT FALSE
+);
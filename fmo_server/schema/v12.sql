@@ -0,0 +1,11 @@
+INSERT INTO schema_version (version)
+VALUES (12);
+
+CREATE TABLE IF NOT EXISTS federation_shutdowns
+(
+    federation_id     BYTEA     PRIMARY KEY NOT NULL REFERENCES federations (federation_id),
+    shutdown_at       TIMESTAMP NOT NULL,
+    total_volume_msat BIGINT    NOT NULL,
+    peak_assets_msat  BIGINT    NOT NULL,
+    last_activity     TIMESTAMP
+);
@@ -0,0 +1,5 @@
+INSERT INTO schema_version (version)
+VALUES (13);
+
+-- Allows paging through transactions in consensus order without sorting all of them
+CREATE INDEX IF NOT EXISTS federation_transactions_ordered ON transactions (federation_id, session_index, item_index);
@@ -0,0 +1,6 @@
+INSERT INTO schema_version (version)
+VALUES (14);
+
+-- Deactivated federations are neither observed nor listed anymore, but their data is kept
+ALTER TABLE federations
+    ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,11 @@
+INSERT INTO schema_version (version)
+VALUES (15);
+
+-- Daily digest notes published by the observer, at most one per day
+CREATE TABLE IF NOT EXISTS nostr_digests
+(
+    date         DATE      PRIMARY KEY NOT NULL,
+    event_id     BYTEA     NOT NULL,
+    content      TEXT      NOT NULL,
+    published_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,40 @@
+INSERT INTO schema_version (version)
+VALUES (16);
+
+-- Fee paid by each transaction, i.e. the difference between its inputs and outputs
+CREATE TABLE IF NOT EXISTS fees
+(
+    federation_id BYTEA   NOT NULL REFERENCES federations (federation_id),
+    txid          BYTEA   NOT NULL,
+    session_index INTEGER NOT NULL,
+    fee_msat      BIGINT  NOT NULL,
+    PRIMARY KEY (federation_id, txid),
+    FOREIGN KEY (federation_id, txid) REFERENCES transactions (federation_id, txid)
+);
+CREATE INDEX IF NOT EXISTS fees_federation_session ON fees (federation_id, session_index);
+
+INSERT INTO fees (federation_id, txid, session_index, fee_msat)
+SELECT t.federation_id,
+       t.txid,
+       t.session_index,
+       COALESCE((SELECT SUM(ti.amount_msat)
+                 FROM transaction_inputs ti
+                 WHERE ti.federation_id = t.federation_id
+                   AND ti.txid = t.txid), 0) -
+       COALESCE((SELECT SUM(tout.amount_msat)
+                 FROM transaction_outputs tout
+                 WHERE tout.federation_id = t.federation_id
+                   AND tout.txid = t.txid), 0)
+FROM transactions t
+-- Items of modules we don't decode have no known amount, so their fee can't be computed
+WHERE NOT EXISTS (SELECT 1
+                  FROM transaction_inputs ti
+                  WHERE ti.federation_id = t.federation_id
+                    AND ti.txid = t.txid
+                    AND ti.amount_msat IS NULL)
+  AND NOT EXISTS (SELECT 1
+                  FROM transaction_outputs tout
+                  WHERE tout.federation_id = t.federation_id
+                    AND tout.txid = t.txid
+                    AND tout.amount_msat IS NULL)
+ON CONFLICT DO NOTHING;
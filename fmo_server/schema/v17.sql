@@ -0,0 +1,14 @@
+INSERT INTO schema_version (version)
+VALUES (17);
+
+-- Daily activity statistics group and filter sessions by date
+CREATE INDEX IF NOT EXISTS session_times_federation_id_date_idx ON session_times (federation_id, DATE(estimated_session_timestamp));
+
+-- Volume calculations sum up the amounts of a transaction's inputs/outputs, covering indices avoid heap lookups for that
+CREATE INDEX IF NOT EXISTS federation_transaction_inputs_amount ON transaction_inputs (federation_id, txid) INCLUDE (amount_msat);
+CREATE INDEX IF NOT EXISTS federation_transaction_outputs_amount ON transaction_outputs (federation_id, txid) INCLUDE (amount_msat);
+DROP INDEX IF EXISTS federation_transaction_inputs;
+DROP INDEX IF EXISTS federation_transaction_outputs;
+
+-- Health history of single guardians, e.g. for latency percentiles, filters by time first
+CREATE INDEX IF NOT EXISTS guardian_health_federation_time_guardian ON guardian_health (federation_id, time, guardian_id) INCLUDE (latency_ms);
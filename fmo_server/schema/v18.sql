@@ -0,0 +1,18 @@
+INSERT INTO schema_version (version)
+VALUES (18);
+
+-- Gateway that is the counterparty of a contract, filled by the v18 backfill for existing contracts
+ALTER TABLE ln_contracts
+    ADD COLUMN IF NOT EXISTS gateway_key BYTEA;
+CREATE INDEX IF NOT EXISTS ln_contract_gateways ON ln_contracts (federation_id, gateway_key);
+
+CREATE TABLE IF NOT EXISTS gateways
+(
+    federation_id      BYTEA   NOT NULL REFERENCES federations (federation_id),
+    gateway_key        BYTEA   NOT NULL,
+    first_seen_session INTEGER NOT NULL,
+    last_seen_session  INTEGER NOT NULL,
+    PRIMARY KEY (federation_id, gateway_key)
+);
+
+CREATE INDEX IF NOT EXISTS federation_output_ln_contracts ON transaction_outputs (federation_id, ln_contract_id);
@@ -0,0 +1,15 @@
+INSERT INTO schema_version (version)
+VALUES (19);
+
+-- Webhooks notified about new alert events. Rules without federation apply to
+-- all federations, rules without kinds to all alert kinds.
+CREATE TABLE IF NOT EXISTS alert_rules
+(
+    rule_id       BIGSERIAL PRIMARY KEY,
+    federation_id BYTEA REFERENCES federations (federation_id),
+    kinds         TEXT[]    NOT NULL,
+    webhook_url   TEXT      NOT NULL,
+    format        TEXT      NOT NULL,
+    enabled       BOOLEAN   NOT NULL DEFAULT TRUE,
+    created_at    TIMESTAMP NOT NULL
+);
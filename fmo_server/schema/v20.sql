@@ -0,0 +1,23 @@
+INSERT INTO schema_version (version)
+VALUES (20);
+
+-- Notifications are queued here in the same transaction that raises the alert
+-- and delivered by a background worker, so they survive restarts
+CREATE TABLE IF NOT EXISTS notification_outbox
+(
+    outbox_id       BIGSERIAL PRIMARY KEY,
+    rule_id         BIGINT    NOT NULL REFERENCES alert_rules (rule_id) ON DELETE CASCADE,
+    alert_id        BIGINT    NOT NULL REFERENCES alert_events (alert_id),
+    -- pending, delivered or failed
+    status          TEXT      NOT NULL DEFAULT 'pending',
+    attempts        INTEGER   NOT NULL DEFAULT 0,
+    created_at      TIMESTAMP NOT NULL,
+    next_attempt_at TIMESTAMP NOT NULL,
+    last_attempt_at TIMESTAMP,
+    last_error      TEXT,
+    delivered_at    TIMESTAMP,
+    UNIQUE (rule_id, alert_id)
+);
+
+CREATE INDEX IF NOT EXISTS notification_outbox_pending ON notification_outbox (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS notification_outbox_rule ON notification_outbox (rule_id, created_at);
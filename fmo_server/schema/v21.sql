@@ -0,0 +1,5 @@
+INSERT INTO schema_version (version)
+VALUES (21);
+
+-- Used to find the inputs spending a lightning contract
+CREATE INDEX IF NOT EXISTS federation_input_ln_contracts ON transaction_inputs (federation_id, ln_contract_id);
@@ -0,0 +1,47 @@
+INSERT INTO schema_version (version)
+VALUES (22);
+
+-- Guardians can fee-bump a peg-out by signing a transaction spending the same inputs, the replaced
+-- transaction's outputs will never exist on-chain
+ALTER TABLE wallet_withdrawal_transactions
+    ADD COLUMN IF NOT EXISTS replaced_by         BYTEA REFERENCES wallet_withdrawal_transactions (on_chain_txid),
+    ADD COLUMN IF NOT EXISTS confirmation_height INTEGER;
+CREATE INDEX IF NOT EXISTS federation_withdrawal_transactions ON wallet_withdrawal_transactions (federation_id);
+
+-- Change outputs of replaced transactions are no UTXOs
+DROP MATERIALIZED VIEW IF EXISTS utxos;
+CREATE MATERIALIZED VIEW utxos AS
+WITH unspent_deposits AS (
+  SELECT wpi.on_chain_txid, wpi.on_chain_vout, wpi.address, wpi.amount_msat, wpi.federation_id
+  FROM wallet_peg_ins wpi
+  WHERE NOT EXISTS (
+    SELECT *
+    FROM wallet_withdrawal_transaction_inputs wwti
+    WHERE wpi.on_chain_txid = wwti.previous_output_txid
+      AND wpi.on_chain_vout = wwti.previous_output_vout
+  )
+),
+unspent_change AS (
+  SELECT wwto.on_chain_txid, wwto.on_chain_vout, wwto.address, wwto.amount_msat, wwt.federation_id
+  FROM wallet_withdrawal_transaction_outputs wwto
+    JOIN wallet_withdrawal_transactions wwt ON wwto.on_chain_txid = wwt.on_chain_txid
+  WHERE wwt.replaced_by IS NULL
+  AND NOT EXISTS (
+    SELECT *
+    FROM wallet_withdrawal_transaction_inputs wwti
+    WHERE wwto.on_chain_txid = wwti.previous_output_txid
+      AND wwto.on_chain_vout = wwti.previous_output_vout
+  )
+  AND NOT EXISTS (
+    SELECT *
+    FROM wallet_withdrawal_addresses wwa
+    WHERE wwto.address = wwa.address
+  )
+)
+SELECT ud.on_chain_txid, ud.on_chain_vout, ud.address, ud.amount_msat, ud.federation_id
+FROM unspent_deposits ud
+UNION
+SELECT uc.on_chain_txid, uc.on_chain_vout, uc.address, uc.amount_msat, uc.federation_id
+FROM unspent_change uc;
+
+CREATE UNIQUE INDEX on_chain_txid_on_chain_vout ON utxos (on_chain_txid, on_chain_vout);
@@ -0,0 +1,13 @@
+INSERT INTO schema_version (version)
+VALUES (23);
+
+-- Latest TLS certificate check of each guardian endpoint, not_after is NULL if the check failed
+CREATE TABLE IF NOT EXISTS guardian_certificates
+(
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    guardian_id   INTEGER   NOT NULL,
+    checked_at    TIMESTAMP NOT NULL,
+    not_after     TIMESTAMP,
+    error         TEXT,
+    PRIMARY KEY (federation_id, guardian_id)
+);
@@ -0,0 +1,7 @@
+INSERT INTO schema_version (version)
+VALUES (24);
+
+-- Bitcoin network of the federation's wallet module (e.g. `bitcoin` or `signet`), NULL if it has none. Backfilled from
+-- the stored configs.
+ALTER TABLE federations
+    ADD COLUMN IF NOT EXISTS network TEXT;
@@ -0,0 +1,41 @@
+INSERT INTO schema_version (version)
+VALUES (25);
+
+-- Decoded transaction inputs/outputs and module consensus items as JSON, for structured searches. Only items of modules
+-- the observer knows are stored. jsonb_path_ops indices only support containment queries (`@>`), which is all we need.
+CREATE TABLE IF NOT EXISTS transaction_input_details
+(
+    federation_id BYTEA   NOT NULL,
+    txid          BYTEA   NOT NULL,
+    in_index      INTEGER NOT NULL,
+    kind          TEXT    NOT NULL,
+    details       JSONB   NOT NULL,
+    PRIMARY KEY (federation_id, txid, in_index),
+    FOREIGN KEY (federation_id, txid) REFERENCES transactions (federation_id, txid)
+);
+CREATE INDEX IF NOT EXISTS transaction_input_details_json ON transaction_input_details USING GIN (details jsonb_path_ops);
+
+CREATE TABLE IF NOT EXISTS transaction_output_details
+(
+    federation_id BYTEA   NOT NULL,
+    txid          BYTEA   NOT NULL,
+    out_index     INTEGER NOT NULL,
+    kind          TEXT    NOT NULL,
+    details       JSONB   NOT NULL,
+    PRIMARY KEY (federation_id, txid, out_index),
+    FOREIGN KEY (federation_id, txid) REFERENCES transactions (federation_id, txid)
+);
+CREATE INDEX IF NOT EXISTS transaction_output_details_json ON transaction_output_details USING GIN (details jsonb_path_ops);
+
+CREATE TABLE IF NOT EXISTS consensus_items
+(
+    federation_id BYTEA   NOT NULL,
+    session_index INTEGER NOT NULL,
+    item_index    INTEGER NOT NULL,
+    peer_id       INTEGER NOT NULL,
+    kind          TEXT    NOT NULL,
+    details       JSONB   NOT NULL,
+    PRIMARY KEY (federation_id, session_index, item_index),
+    FOREIGN KEY (federation_id, session_index) REFERENCES sessions (federation_id, session_index)
+);
+CREATE INDEX IF NOT EXISTS consensus_items_json ON consensus_items USING GIN (details jsonb_path_ops);
@@ -0,0 +1,10 @@
+INSERT INTO schema_version (version)
+VALUES (26);
+
+-- Result of the latest reachability probe of the announced invite codes, kept in sync across all announcements of a
+-- federation. online is NULL until the first probe.
+ALTER TABLE nostr_federations
+    ADD COLUMN IF NOT EXISTS name         TEXT,
+    ADD COLUMN IF NOT EXISTS online       BOOLEAN,
+    ADD COLUMN IF NOT EXISTS last_checked TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS last_seen    TIMESTAMP;
@@ -0,0 +1,22 @@
+INSERT INTO schema_version (version)
+VALUES (27);
+
+CREATE TABLE IF NOT EXISTS wallet_feerate_votes
+(
+    federation_id BYTEA   NOT NULL REFERENCES federations (federation_id),
+    session_index INTEGER NOT NULL,
+    item_index    INTEGER NOT NULL,
+    proposer      INTEGER NOT NULL,
+    sats_per_kvb  BIGINT  NOT NULL,
+    PRIMARY KEY (federation_id, session_index, item_index),
+    FOREIGN KEY (federation_id, session_index) REFERENCES sessions (federation_id, session_index)
+);
+CREATE INDEX IF NOT EXISTS wallet_feerate_vote_federation_sessions ON wallet_feerate_votes (federation_id, session_index);
+
+-- Fee rate votes of already processed sessions are taken from the decoded consensus items
+INSERT INTO wallet_feerate_votes
+SELECT federation_id, session_index, item_index, peer_id, (details -> 'Feerate' ->> 'sats_per_kvb')::bigint
+FROM consensus_items
+WHERE kind = 'wallet'
+  AND details ? 'Feerate'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,16 @@
+INSERT INTO schema_version (version)
+VALUES (28);
+
+-- Last session committed by the history observer of each federation, the observer resumes after it on restart
+CREATE TABLE IF NOT EXISTS observer_checkpoints
+(
+    federation_id BYTEA     PRIMARY KEY NOT NULL REFERENCES federations (federation_id),
+    session_index INTEGER   NOT NULL,
+    committed_at  TIMESTAMP NOT NULL
+);
+
+INSERT INTO observer_checkpoints (federation_id, session_index, committed_at)
+SELECT federation_id, MAX(session_index), NOW() AT TIME ZONE 'UTC'
+FROM sessions
+GROUP BY federation_id
+ON CONFLICT DO NOTHING;
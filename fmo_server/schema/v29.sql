@@ -0,0 +1,8 @@
+INSERT INTO schema_version (version)
+VALUES (29);
+
+-- Net peg-in/peg-out flow thresholds, NULL disables the respective alert
+ALTER TABLE alert_settings
+    ADD COLUMN IF NOT EXISTS net_outflow_threshold_msat BIGINT,
+    ADD COLUMN IF NOT EXISTS net_inflow_threshold_msat  BIGINT,
+    ADD COLUMN IF NOT EXISTS net_flow_window_hours      INTEGER NOT NULL DEFAULT 24;
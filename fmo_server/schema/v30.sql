@@ -0,0 +1,6 @@
+INSERT INTO schema_version (version)
+VALUES (30);
+
+-- Digests can be delivered to a webhook only, without publishing a note
+ALTER TABLE nostr_digests
+    ALTER COLUMN event_id DROP NOT NULL;
@@ -0,0 +1,12 @@
+INSERT INTO schema_version (version)
+VALUES (31);
+
+-- Federations seeded from a snapshot, their restored sessions are compared against the federation in the background
+CREATE TABLE IF NOT EXISTS snapshot_restores
+(
+    federation_id     BYTEA     PRIMARY KEY NOT NULL REFERENCES federations (federation_id),
+    restored_at       TIMESTAMP NOT NULL,
+    session_count     INTEGER   NOT NULL,
+    verified_sessions INTEGER   NOT NULL DEFAULT 0,
+    mismatch_session  INTEGER
+);
@@ -0,0 +1,20 @@
+INSERT INTO schema_version (version)
+VALUES (32);
+
+-- Module inputs and outputs of versions the observer can't decode, their amounts are missing until the session is
+-- re-processed by an observer that understands them
+CREATE TABLE IF NOT EXISTS quarantined_items
+(
+    federation_id BYTEA   NOT NULL REFERENCES federations (federation_id),
+    txid          BYTEA   NOT NULL,
+    direction     TEXT    NOT NULL, -- 'input' or 'output'
+    idx           INTEGER NOT NULL,
+    session_index INTEGER NOT NULL,
+    kind          TEXT    NOT NULL,
+    variant       BIGINT  NOT NULL,
+    item          BYTEA   NOT NULL,
+    PRIMARY KEY (federation_id, txid, direction, idx),
+    FOREIGN KEY (federation_id, txid) REFERENCES transactions (federation_id, txid)
+);
+
+CREATE INDEX IF NOT EXISTS quarantined_items_session ON quarantined_items (federation_id, session_index);
@@ -0,0 +1,11 @@
+INSERT INTO schema_version (version)
+VALUES (33);
+
+-- Merged consensus and override meta fields of each federation, a row is added whenever they change
+CREATE TABLE IF NOT EXISTS meta_history
+(
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    recorded_at   TIMESTAMP NOT NULL,
+    meta          JSONB     NOT NULL,
+    PRIMARY KEY (federation_id, recorded_at)
+);
@@ -0,0 +1,40 @@
+INSERT INTO schema_version (version)
+VALUES (34);
+
+-- Periods in which a guardian failed all health checks, open incidents have no end yet
+CREATE TABLE IF NOT EXISTS incidents
+(
+    incident_id   BIGSERIAL PRIMARY KEY,
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    guardian_id   INTEGER   NOT NULL,
+    started_at    TIMESTAMP NOT NULL,
+    ended_at      TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS incidents_federation_started ON incidents (federation_id, started_at);
+CREATE UNIQUE INDEX IF NOT EXISTS incidents_open ON incidents (federation_id, guardian_id) WHERE ended_at IS NULL;
+
+-- Derive incidents from the health checks recorded so far. Each run of failed checks is an incident that ends with the
+-- next successful check.
+WITH samples AS (SELECT federation_id,
+                        guardian_id,
+                        time,
+                        status IS NULL AS offline,
+                        ROW_NUMBER() OVER (PARTITION BY federation_id, guardian_id ORDER BY time) -
+                        ROW_NUMBER() OVER (PARTITION BY federation_id, guardian_id, status IS NULL ORDER BY time) AS run
+                 FROM guardian_health),
+     runs AS (SELECT federation_id, guardian_id, MIN(time) AS started_at, MAX(time) AS last_failure
+              FROM samples
+              WHERE offline
+              GROUP BY federation_id, guardian_id, run)
+INSERT
+INTO incidents (federation_id, guardian_id, started_at, ended_at)
+SELECT r.federation_id,
+       r.guardian_id,
+       r.started_at,
+       (SELECT MIN(gh.time)
+        FROM guardian_health gh
+        WHERE gh.federation_id = r.federation_id
+          AND gh.guardian_id = r.guardian_id
+          AND gh.time > r.last_failure)
+FROM runs r;
@@ -0,0 +1,12 @@
+INSERT INTO schema_version (version)
+VALUES (35);
+
+-- Transitions of a federation's confirmed health state, the latest one is the current state
+CREATE TABLE IF NOT EXISTS health_events
+(
+    federation_id   BYTEA     NOT NULL REFERENCES federations (federation_id),
+    timestamp       TIMESTAMP NOT NULL,
+    previous_health TEXT,
+    health          TEXT      NOT NULL,
+    PRIMARY KEY (federation_id, timestamp)
+);
@@ -0,0 +1,10 @@
+INSERT INTO schema_version (version)
+VALUES (36);
+
+-- Precomputed summary of each federation, refreshed periodically so listing federations doesn't query their history
+CREATE TABLE IF NOT EXISTS federation_summaries
+(
+    federation_id BYTEA     NOT NULL PRIMARY KEY REFERENCES federations (federation_id),
+    summary       JSONB     NOT NULL,
+    updated_at    TIMESTAMP NOT NULL
+);
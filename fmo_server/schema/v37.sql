@@ -0,0 +1,15 @@
+INSERT INTO schema_version (version)
+VALUES (37);
+
+-- Position up to which an API client exported a federation's transactions, clients are identified by the SHA256 hash of
+-- their API key
+CREATE TABLE IF NOT EXISTS export_cursors
+(
+    api_key_hash  BYTEA     NOT NULL,
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    name          TEXT      NOT NULL,
+    session_index INTEGER   NOT NULL,
+    item_index    INTEGER   NOT NULL,
+    updated_at    TIMESTAMP NOT NULL,
+    PRIMARY KEY (api_key_hash, federation_id, name)
+);
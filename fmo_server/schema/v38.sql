@@ -0,0 +1,31 @@
+INSERT INTO schema_version (version)
+VALUES (38);
+
+-- E-cash notes issued (mint outputs) and redeemed (mint inputs) per denomination and session, every mint input and
+-- output is a single note of the denomination given by its amount
+CREATE TABLE IF NOT EXISTS mint_denominations
+(
+    federation_id     BYTEA   NOT NULL REFERENCES federations (federation_id),
+    session_index     INTEGER NOT NULL,
+    denomination_msat BIGINT  NOT NULL,
+    issued_notes      BIGINT  NOT NULL DEFAULT 0,
+    redeemed_notes    BIGINT  NOT NULL DEFAULT 0,
+    PRIMARY KEY (federation_id, session_index, denomination_msat)
+);
+
+INSERT INTO mint_denominations (federation_id, session_index, denomination_msat, issued_notes)
+SELECT t.federation_id, t.session_index, o.amount_msat, COUNT(*)
+FROM transaction_outputs o
+         JOIN transactions t ON t.federation_id = o.federation_id AND t.txid = o.txid
+WHERE o.kind = 'mint'
+  AND o.amount_msat IS NOT NULL
+GROUP BY t.federation_id, t.session_index, o.amount_msat;
+
+INSERT INTO mint_denominations (federation_id, session_index, denomination_msat, redeemed_notes)
+SELECT t.federation_id, t.session_index, i.amount_msat, COUNT(*)
+FROM transaction_inputs i
+         JOIN transactions t ON t.federation_id = i.federation_id AND t.txid = i.txid
+WHERE i.kind = 'mint'
+  AND i.amount_msat IS NOT NULL
+GROUP BY t.federation_id, t.session_index, i.amount_msat
+ON CONFLICT (federation_id, session_index, denomination_msat) DO UPDATE SET redeemed_notes = excluded.redeemed_notes;
@@ -0,0 +1,16 @@
+INSERT INTO schema_version (version)
+VALUES (39);
+
+-- Results of the deployment specific health probes, probes are only identified by their configured name so new kinds of
+-- probes don't need schema changes
+CREATE TABLE IF NOT EXISTS health_probe_results
+(
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    checked_at    TIMESTAMP NOT NULL,
+    guardian_id   INTEGER   NOT NULL,
+    probe         TEXT      NOT NULL,
+    success       BOOLEAN   NOT NULL,
+    latency_ms    INTEGER,
+    message       TEXT,
+    PRIMARY KEY (federation_id, probe, guardian_id, checked_at)
+);
@@ -0,0 +1,9 @@
+INSERT INTO schema_version (version)
+VALUES (40);
+
+-- Status responses don't include the fedimintd version, it's requested separately about once an hour and stored with
+-- the health check of that round, so most rows don't have one
+ALTER TABLE guardian_health
+    ADD COLUMN IF NOT EXISTS fedimintd_version TEXT;
+CREATE INDEX IF NOT EXISTS guardian_health_versions ON guardian_health (federation_id, guardian_id, time)
+    WHERE fedimintd_version IS NOT NULL;
@@ -0,0 +1,13 @@
+INSERT INTO schema_version (version)
+VALUES (41);
+
+-- Only the SHA256 hash of each key is stored, the key itself is shown once when it's created
+CREATE TABLE IF NOT EXISTS api_keys
+(
+    id         BIGSERIAL PRIMARY KEY,
+    name       TEXT      NOT NULL,
+    key_hash   BYTEA     NOT NULL UNIQUE,
+    scopes     TEXT[]    NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    last_used  TIMESTAMP
+);
@@ -0,0 +1,14 @@
+INSERT INTO schema_version (version)
+VALUES (42);
+
+-- Session items the observer couldn't process, the rest of the session is stored regardless
+CREATE TABLE IF NOT EXISTS processing_errors
+(
+    federation_id BYTEA     NOT NULL REFERENCES federations (federation_id),
+    session_index INTEGER   NOT NULL,
+    item_index    INTEGER   NOT NULL,
+    txid          BYTEA,
+    error         TEXT      NOT NULL,
+    created_at    TIMESTAMP NOT NULL,
+    PRIMARY KEY (federation_id, session_index, item_index)
+);
@@ -0,0 +1,14 @@
+INSERT INTO schema_version (version)
+VALUES (43);
+
+-- Session counts reported by each guardian next to the number of sessions the observer had committed at the time
+CREATE TABLE IF NOT EXISTS session_count_samples
+(
+    federation_id          BYTEA     NOT NULL REFERENCES federations (federation_id),
+    time                   TIMESTAMP NOT NULL,
+    guardian_id            INTEGER   NOT NULL,
+    -- NULL if the guardian didn't respond
+    reported_session_count INTEGER,
+    observed_session_count INTEGER   NOT NULL,
+    PRIMARY KEY (federation_id, time, guardian_id)
+);
@@ -0,0 +1,10 @@
+INSERT INTO schema_version (version)
+VALUES (44);
+
+-- Jobs requested through the API are queued and picked up by a bounded pool of workers. Their parameters are kept so
+-- they can be resumed after a restart, `started_at` is the time they were queued until a worker picks them up.
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS params JSONB,
+    ADD COLUMN IF NOT EXISTS queued BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS jobs_queued ON jobs (id) WHERE queued;
@@ -0,0 +1,50 @@
+INSERT INTO schema_version (version)
+VALUES (45);
+
+-- Author, creation time and NIP-13 proof of work of recommendations, so ratings only count each author's latest vote
+-- and can ignore cheap spam
+ALTER TABLE nostr_votes
+    ADD COLUMN IF NOT EXISTS pubkey     BYTEA,
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMP,
+    ADD COLUMN IF NOT EXISTS pow        INTEGER;
+
+UPDATE nostr_votes
+SET pubkey     = decode(event ->> 'pubkey', 'hex'),
+    created_at = to_timestamp((event ->> 'created_at')::bigint) AT TIME ZONE 'UTC',
+    pow        = 4 * (length(encode(event_id, 'hex')) - length(ltrim(encode(event_id, 'hex'), '0'))) +
+                 CASE left(ltrim(encode(event_id, 'hex'), '0'), 1)
+                     WHEN '1' THEN 3
+                     WHEN '2' THEN 2
+                     WHEN '3' THEN 2
+                     WHEN '4' THEN 1
+                     WHEN '5' THEN 1
+                     WHEN '6' THEN 1
+                     WHEN '7' THEN 1
+                     ELSE 0
+                     END
+WHERE pubkey IS NULL;
+
+ALTER TABLE nostr_votes
+    ALTER COLUMN pubkey SET NOT NULL,
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN pow SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS nostr_votes_author ON nostr_votes (federation_id, pubkey, created_at DESC);
+
+-- Authors whose recommendations are always (allow) or never (deny) counted. Once any author is allowed, only allowed
+-- authors are counted.
+CREATE TABLE IF NOT EXISTS nostr_moderated_pubkeys
+(
+    pubkey     BYTEA     NOT NULL PRIMARY KEY,
+    allowed    BOOLEAN   NOT NULL,
+    note       TEXT,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- Individual recommendations hidden from ratings by an operator
+CREATE TABLE IF NOT EXISTS nostr_hidden_votes
+(
+    event_id  BYTEA     NOT NULL PRIMARY KEY REFERENCES nostr_votes (event_id) ON DELETE CASCADE,
+    reason    TEXT,
+    hidden_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,21 @@
+INSERT INTO schema_version (version)
+VALUES (46);
+
+-- Export cursors belong to API keys created by the operator instead of any key a client chose. Cursors whose key is
+-- also an API key are kept, the others can't be attributed and are dropped.
+ALTER TABLE export_cursors
+    ADD COLUMN IF NOT EXISTS api_key_id BIGINT REFERENCES api_keys (id) ON DELETE CASCADE;
+
+UPDATE export_cursors
+SET api_key_id = api_keys.id
+FROM api_keys
+WHERE api_keys.key_hash = export_cursors.api_key_hash;
+
+DELETE
+FROM export_cursors
+WHERE api_key_id IS NULL;
+
+ALTER TABLE export_cursors
+    DROP COLUMN api_key_hash,
+    ALTER COLUMN api_key_id SET NOT NULL,
+    ADD PRIMARY KEY (api_key_id, federation_id, name);
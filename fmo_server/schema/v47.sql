@@ -0,0 +1,17 @@
+INSERT INTO schema_version (version)
+VALUES (47);
+
+-- Fees used to be recorded for transactions with items of modules we don't decode, treating their unknown amounts as
+-- zero. Drop them, their fee isn't known.
+DELETE
+FROM fees f
+WHERE EXISTS (SELECT 1
+              FROM transaction_inputs ti
+              WHERE ti.federation_id = f.federation_id
+                AND ti.txid = f.txid
+                AND ti.amount_msat IS NULL)
+   OR EXISTS (SELECT 1
+              FROM transaction_outputs tout
+              WHERE tout.federation_id = f.federation_id
+                AND tout.txid = f.txid
+                AND tout.amount_msat IS NULL);
@@ -0,0 +1,15 @@
+INSERT INTO schema_version (version)
+VALUES (48);
+
+-- Transaction that spent the payout of a peg-out to its destination, NULL if it was unspent when last checked.
+-- Destinations whose payouts are spent together likely belong to the same wallet, e.g. an exchange sweeping deposits.
+CREATE TABLE IF NOT EXISTS wallet_withdrawal_output_spends
+(
+    on_chain_txid BYTEA     NOT NULL,
+    on_chain_vout INTEGER   NOT NULL,
+    spending_txid BYTEA,
+    checked_at    TIMESTAMP NOT NULL,
+    PRIMARY KEY (on_chain_txid, on_chain_vout),
+    FOREIGN KEY (on_chain_txid, on_chain_vout) REFERENCES wallet_withdrawal_transaction_outputs (on_chain_txid, on_chain_vout)
+);
+CREATE INDEX IF NOT EXISTS wallet_withdrawal_output_spenders ON wallet_withdrawal_output_spends (spending_txid);
@@ -0,0 +1,14 @@
+INSERT INTO schema_version (version)
+VALUES (8);
+
+CREATE TABLE IF NOT EXISTS session_peer_stats
+(
+    federation_id     BYTEA   NOT NULL REFERENCES federations (federation_id),
+    session_index     INTEGER NOT NULL,
+    peer_id           INTEGER NOT NULL,
+    item_count        INTEGER NOT NULL,
+    transaction_count INTEGER NOT NULL,
+    PRIMARY KEY (federation_id, session_index, peer_id),
+    FOREIGN KEY (federation_id, session_index) REFERENCES sessions (federation_id, session_index)
+);
+CREATE INDEX IF NOT EXISTS session_peer_stats_federation ON session_peer_stats (federation_id);
@@ -0,0 +1,14 @@
+INSERT INTO schema_version (version)
+VALUES (9);
+
+CREATE TABLE IF NOT EXISTS views
+(
+    name TEXT PRIMARY KEY NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS view_federations
+(
+    view_name     TEXT  NOT NULL REFERENCES views (name) ON DELETE CASCADE,
+    federation_id BYTEA NOT NULL REFERENCES federations (federation_id),
+    PRIMARY KEY (view_name, federation_id)
+);
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use fedimint_core::config::FederationId;
+use fmo_api_types::{
+    ApiKey, ApiKeyRequest, CatchUpProgress, CreatedApiKey, DbTableStats, JobProgress,
+    MetaFetchStats, ProcessingError, ProcessingRetry, SnapshotVerification, UsageStats,
+};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use tokio_util::io::StreamReader;
+
+use crate::federation::snapshot::snapshot_tables;
+use crate::AppState;
+
+/// Maximum time a query may run while being analyzed
+const EXPLAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn get_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/explain", get(explain_query))
+        .route("/meta_fetches", get(get_meta_fetch_stats))
+        .route("/usage", get(get_usage_stats))
+        .route("/db_stats", get(get_db_stats))
+        .route("/snapshot", post(create_snapshot))
+        .route("/restore", post(restore_snapshot))
+        .route("/restore/verification", get(get_snapshot_verifications))
+        .route("/api_keys", get(list_api_keys).post(create_api_key))
+        .route("/api_keys/:id", put(update_api_key).delete(delete_api_key))
+        .route("/tasks", get(get_catch_up_tasks))
+        .route("/jobs", get(get_jobs))
+        .route("/processing_errors", get(list_processing_errors))
+        .route(
+            "/processing_errors/:federation_id/retry",
+            post(retry_processing_errors),
+        )
+}
+
+#[derive(Debug, Deserialize)]
+struct ExplainParams {
+    query: String,
+}
+
+/// Returns the `EXPLAIN ANALYZE` output of a query in JSON format. The query
+/// is run in a read-only transaction that is rolled back afterwards.
+async fn explain_query(
+    Query(params): Query<ExplainParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<serde_json::Value>> {
+    let rows = state
+        .federation_observer
+        .read_only_query(
+            &format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", params.query),
+            &[],
+            EXPLAIN_TIMEOUT,
+        )
+        .await?;
+    let plan = rows
+        .first()
+        .context("EXPLAIN returned no plan")?
+        .try_get::<_, serde_json::Value>(0)?;
+
+    Ok(plan.into())
+}
+
+/// Fetch statistics of the meta override files of all federations requested
+/// since the server started
+async fn get_meta_fetch_stats(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<MetaFetchStats>>> {
+    Ok(state.meta_override_cache.fetch_stats().await.into())
+}
+
+/// Request counts per route and federation since the server started. Unique
+/// client counts only cover the current rotation period of the client key.
+async fn get_usage_stats(State(state): State<AppState>) -> crate::error::Result<Json<UsageStats>> {
+    Ok(state.usage_tracker.stats().into())
+}
+
+/// Catch-up progress of all history observers, the ones currently catching up
+/// first, followed by the queue in the order it will be processed
+async fn get_catch_up_tasks(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<CatchUpProgress>>> {
+    Ok(state.federation_observer.catch_up_progress().into())
+}
+
+/// Running jobs of all federations and migrations, followed by the most
+/// recently finished ones
+async fn get_jobs(State(state): State<AppState>) -> crate::error::Result<Json<Vec<JobProgress>>> {
+    Ok(state.federation_observer.list_jobs(None).await?.into())
+}
+
+/// Size, estimated bloat and last vacuum/analyze of all database tables,
+/// largest first
+async fn get_db_stats(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<DbTableStats>>> {
+    Ok(state.federation_observer.db_stats().await?.into())
+}
+
+#[derive(Debug, Deserialize)]
+struct SnapshotParams {
+    /// Comma separated tables to include, defaults to all synced data
+    tables: Option<String>,
+}
+
+/// Streams a snapshot of the synced data that can be restored on another
+/// instance to skip the initial sync
+async fn create_snapshot(
+    Query(params): Query<SnapshotParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Response> {
+    let tables = snapshot_tables(params.tables.as_deref())?;
+    Ok((
+        [
+            (CONTENT_TYPE, "application/octet-stream"),
+            (
+                CONTENT_DISPOSITION,
+                "attachment; filename=\"fmo-snapshot.bin\"",
+            ),
+        ],
+        Body::from_stream(state.federation_observer.snapshot(tables)),
+    )
+        .into_response())
+}
+
+/// Restores a snapshot sent as the request body and starts observing the
+/// restored federations. Their sessions are verified against the federation
+/// in the background.
+async fn restore_snapshot(
+    State(state): State<AppState>,
+    body: Body,
+) -> crate::error::Result<Json<Vec<FederationId>>> {
+    let reader = StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+    let federation_ids = state.federation_observer.restore_snapshot(reader).await?;
+    state
+        .federation_observer
+        .spawn_restored_observers(&federation_ids)
+        .await?;
+
+    Ok(federation_ids.into())
+}
+
+/// Progress of verifying restored sessions against their federations
+async fn get_snapshot_verifications(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<SnapshotVerification>>> {
+    Ok(state
+        .federation_observer
+        .snapshot_verifications()
+        .await?
+        .into())
+}
+
+/// API keys with their scopes, without the keys themselves
+async fn list_api_keys(State(state): State<AppState>) -> crate::error::Result<Json<Vec<ApiKey>>> {
+    Ok(state.federation_observer.list_api_keys().await?.into())
+}
+
+/// Creates an API key, the response contains the only copy of the key
+async fn create_api_key(
+    State(state): State<AppState>,
+    Json(request): Json<ApiKeyRequest>,
+) -> crate::error::Result<Json<CreatedApiKey>> {
+    Ok(state
+        .federation_observer
+        .create_api_key(&request)
+        .await?
+        .into())
+}
+
+async fn update_api_key(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+    Json(request): Json<ApiKeyRequest>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .update_api_key(id, &request)
+        .await?)
+}
+
+async fn delete_api_key(
+    Path(id): Path<u64>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state.federation_observer.delete_api_key(id).await?)
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessingErrorParams {
+    federation_id: Option<FederationId>,
+}
+
+/// Session items the observers skipped because they couldn't be processed
+async fn list_processing_errors(
+    Query(params): Query<ProcessingErrorParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<ProcessingError>>> {
+    Ok(state
+        .federation_observer
+        .list_processing_errors(params.federation_id)
+        .await?
+        .into())
+}
+
+/// Re-processes the sessions with processing errors or quarantined items of a
+/// federation, e.g. after upgrading the observer
+async fn retry_processing_errors(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<ProcessingRetry>> {
+    Ok(state
+        .federation_observer
+        .retry_processing_errors(federation_id)
+        .await?
+        .into())
+}
@@ -0,0 +1,278 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_auth::AuthBearer;
+use fmo_api_types::ApiScope;
+use tracing::error;
+
+use crate::federation::observer::FederationObserver;
+
+/// Authorization a route requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteAuth {
+    /// Anyone
+    Public,
+    /// Only the `FO_ADMIN_AUTH` token
+    Admin,
+    /// The admin token or an API key with the scope
+    Scope(ApiScope),
+}
+
+/// Routes everyone may read. Every route has to be listed here or be
+/// protected by [`route_auth`], others are rejected.
+const PUBLIC_READS: &[&str] = &[
+    "/health",
+    "/live",
+    "/ready",
+    "/version",
+    "/versions",
+    "/openapi.json",
+    "/config/:invite",
+    "/config/:invite/meta",
+    "/config/:invite/id",
+    "/config/:invite/module_kinds",
+    "/config/:invite/check",
+    "/views",
+    "/views/",
+    "/views/:name",
+    "/views/:name/federations",
+    "/queries",
+    "/queries/",
+    "/queries/:name",
+    "/instance/settings",
+    "/chain/blocks",
+    "/chain/status",
+    "/nostr/federations",
+    "/nostr/federations/summary",
+    "/nostr/events",
+    "/federations",
+    "/federations/",
+    "/federations/totals",
+    "/federations/activity",
+    "/federations/search",
+    "/federations/:federation_id",
+    "/federations/:federation_id/config",
+    "/federations/:federation_id/meta",
+    "/federations/:federation_id/meta/lint",
+    "/federations/:federation_id/meta/history",
+    "/federations/:federation_id/card.png",
+    "/federations/:federation_id/health",
+    "/federations/:federation_id/health/history",
+    "/federations/:federation_id/health/events",
+    "/federations/:federation_id/health/probes",
+    "/federations/:federation_id/incidents",
+    "/federations/:federation_id/guardians/:peer_id/latency.csv",
+    "/federations/:federation_id/shutdown",
+    "/federations/:federation_id/observer",
+    "/federations/:federation_id/backfill/status",
+    "/federations/:federation_id/alerts",
+    "/federations/:federation_id/alerts/settings",
+    "/federations/:federation_id/peers/contribution",
+    "/federations/:federation_id/transactions",
+    "/federations/:federation_id/transactions/:transaction_id",
+    "/federations/:federation_id/transactions/:transaction_id/graph",
+    "/federations/:federation_id/transactions/count",
+    "/federations/:federation_id/transactions/histogram",
+    "/federations/:federation_id/timeseries",
+    "/federations/:federation_id/export",
+    "/federations/:federation_id/utxos",
+    "/federations/:federation_id/fees",
+    "/federations/:federation_id/mint/denominations",
+    "/federations/:federation_id/gateways",
+    "/federations/:federation_id/ln/stats",
+    "/federations/:federation_id/privacy_score",
+    "/federations/:federation_id/solvency",
+    "/federations/:federation_id/completeness",
+    "/federations/:federation_id/announcement",
+    "/federations/:federation_id/deposits",
+    "/federations/:federation_id/withdrawals",
+    "/federations/:federation_id/withdrawals/concentration",
+    "/federations/:federation_id/withdrawals/concentration/history",
+    "/federations/:federation_id/withdrawals/transactions",
+    "/federations/:federation_id/sessions",
+    "/federations/:federation_id/sessions/count",
+    "/federations/:federation_id/sessions/divergence",
+    "/federations/:federation_id/sessions/:session_index",
+    "/federations/:federation_id/search/items",
+];
+
+/// Authorization required for `method` on the matched route, `None` for
+/// routes without a policy, which are rejected
+pub fn route_auth(method: &Method, route: &str) -> Option<RouteAuth> {
+    let read = method == Method::GET || method == Method::HEAD;
+    let auth = match route {
+        // Running arbitrary SQL under `EXPLAIN ANALYZE` and dumping or writing
+        // whole tables isn't covered by any scope
+        "/admin/explain" | "/admin/snapshot" | "/admin/restore" => RouteAuth::Admin,
+        _ if route.starts_with("/admin/api_keys") => RouteAuth::Admin,
+        _ if route.starts_with("/admin/") && read => RouteAuth::Scope(ApiScope::Query),
+        _ if route.starts_with("/admin/") => RouteAuth::Scope(ApiScope::Federations),
+        // Alert rules can contain secrets in their webhook URLs, so even
+        // reading them is protected
+        _ if route.starts_with("/alert_rules") => RouteAuth::Scope(ApiScope::Federations),
+        "/nostr/relays"
+        | "/nostr/moderation"
+        | "/nostr/moderation/pubkeys"
+        | "/nostr/moderation/votes" => RouteAuth::Scope(ApiScope::Nostr),
+        // Cursors belong to the API key that reads them
+        "/federations/:federation_id/cursors" | "/federations/:federation_id/cursors/:name" => {
+            RouteAuth::Scope(ApiScope::Query)
+        }
+        // Signed nostr events can be published by anyone, GraphQL only
+        // supports queries
+        "/nostr/federations" | "/federations/nostr/rating" | "/graphql" | "/graphql/" => {
+            RouteAuth::Public
+        }
+        "/federations"
+        | "/federations/"
+        | "/federations/:federation_id"
+        | "/federations/:federation_id/shutdown"
+        | "/federations/:federation_id/observer/restart"
+        | "/federations/:federation_id/alerts/settings"
+        | "/federations/:federation_id/sessions/replay"
+        | "/views/:name"
+            if !read =>
+        {
+            RouteAuth::Scope(ApiScope::Federations)
+        }
+        _ if read && PUBLIC_READS.contains(&route) => RouteAuth::Public,
+        _ => return None,
+    };
+    Some(auth)
+}
+
+/// Middleware rejecting requests to protected routes that don't carry a
+/// bearer token with the required scope, and requests to routes without an
+/// authorization policy
+pub async fn require_auth(
+    State(observer): State<FederationObserver>,
+    matched_path: Option<MatchedPath>,
+    bearer: Option<AuthBearer>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Unmatched requests end up in the fallback, which returns 404
+    let Some(matched_path) = matched_path else {
+        return next.run(request).await;
+    };
+    let required = match route_auth(request.method(), matched_path.as_str()) {
+        Some(RouteAuth::Public) => return next.run(request).await,
+        Some(required) => required,
+        None => {
+            error!(
+                route = matched_path.as_str(),
+                method = %request.method(),
+                "Route has no authorization policy"
+            );
+            return (
+                StatusCode::FORBIDDEN,
+                "Error: Route has no authorization policy",
+            )
+                .into_response();
+        }
+    };
+    let Some(AuthBearer(token)) = bearer else {
+        return (StatusCode::UNAUTHORIZED, "Error: Missing bearer token").into_response();
+    };
+
+    match observer.authorize(&token, required).await {
+        Ok(()) => next.run(request).await,
+        Err(e) => (StatusCode::UNAUTHORIZED, format!("Error: {e}")).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use fmo_api_types::ApiScope;
+    use utoipa::openapi::PathItemType;
+
+    use super::{route_auth, RouteAuth};
+
+    #[test]
+    fn test_route_auth() {
+        let public = Some(RouteAuth::Public);
+        assert_eq!(route_auth(&Method::GET, "/federations"), public);
+        assert_eq!(
+            route_auth(&Method::PUT, "/federations"),
+            Some(RouteAuth::Scope(ApiScope::Federations))
+        );
+        assert_eq!(
+            route_auth(&Method::DELETE, "/federations/:federation_id"),
+            Some(RouteAuth::Scope(ApiScope::Federations))
+        );
+        assert_eq!(
+            route_auth(&Method::PUT, "/federations/nostr/rating"),
+            public
+        );
+        assert_eq!(route_auth(&Method::PUT, "/nostr/federations"), public);
+        assert_eq!(
+            route_auth(&Method::GET, "/nostr/relays"),
+            Some(RouteAuth::Scope(ApiScope::Nostr))
+        );
+        assert_eq!(
+            route_auth(&Method::PUT, "/nostr/moderation/votes"),
+            Some(RouteAuth::Scope(ApiScope::Nostr))
+        );
+        assert_eq!(
+            route_auth(&Method::DELETE, "/federations/:federation_id/cursors/:name"),
+            Some(RouteAuth::Scope(ApiScope::Query))
+        );
+        assert_eq!(
+            route_auth(&Method::GET, "/admin/usage"),
+            Some(RouteAuth::Scope(ApiScope::Query))
+        );
+        assert_eq!(
+            route_auth(&Method::GET, "/admin/restore/verification"),
+            Some(RouteAuth::Scope(ApiScope::Query))
+        );
+        assert_eq!(
+            route_auth(&Method::GET, "/admin/explain"),
+            Some(RouteAuth::Admin)
+        );
+        assert_eq!(
+            route_auth(&Method::POST, "/admin/snapshot"),
+            Some(RouteAuth::Admin)
+        );
+        assert_eq!(
+            route_auth(&Method::POST, "/admin/restore"),
+            Some(RouteAuth::Admin)
+        );
+        assert_eq!(
+            route_auth(&Method::DELETE, "/admin/api_keys/:id"),
+            Some(RouteAuth::Admin)
+        );
+
+        // Routes without a policy are rejected, including writes to public
+        // read-only routes
+        assert_eq!(route_auth(&Method::GET, "/unknown"), None);
+        assert_eq!(
+            route_auth(&Method::POST, "/federations/:federation_id/transactions"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_documented_routes_have_policy() {
+        for (path, item) in crate::openapi::openapi().paths.paths {
+            let route = path.replace('{', ":").replace('}', "");
+            // Documented with the name of the parameter it takes, the router
+            // has to use the same name as for the other methods
+            let route = route.replace("/federations/:invite", "/federations/:federation_id");
+            for operation in item.operations.keys() {
+                let method = match operation {
+                    PathItemType::Get => Method::GET,
+                    PathItemType::Post => Method::POST,
+                    PathItemType::Put => Method::PUT,
+                    PathItemType::Delete => Method::DELETE,
+                    other => panic!("Unexpected operation {other:?} on {path}"),
+                };
+                assert!(
+                    route_auth(&method, &route).is_some(),
+                    "{method} {route} has no authorization policy"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::anyhow;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use fmo_api_types::{BlockTime, ChainSyncStatus};
+use serde::Deserialize;
+
+use crate::AppState;
+
+/// Maximum number of blocks returned per request, use `from` to page through
+/// larger ranges
+const MAX_BLOCKS: u32 = 1000;
+
+pub fn get_chain_routes() -> Router<AppState> {
+    Router::new()
+        .route("/blocks", get(get_blocks))
+        .route("/status", get(get_status))
+}
+
+#[derive(Debug, Deserialize)]
+struct BlocksParams {
+    from: u32,
+    to: Option<u32>,
+}
+
+async fn get_blocks(
+    Query(params): Query<BlocksParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<BlockTime>>> {
+    let to = params.to.unwrap_or(u32::MAX);
+    if to < params.from {
+        return Err(anyhow!("`to` has to be greater or equal to `from`").into());
+    }
+
+    Ok(state
+        .federation_observer
+        .block_times(params.from, to, MAX_BLOCKS)
+        .await?
+        .into())
+}
+
+async fn get_status(State(state): State<AppState>) -> crate::error::Result<Json<ChainSyncStatus>> {
+    Ok(state.federation_observer.chain_sync_status().await?.into())
+}
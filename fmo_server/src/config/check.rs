@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use anyhow::ensure;
+use axum::extract::Path;
+use axum::Json;
+use bitcoin::hashes::{sha256, Hash};
+use fedimint_api_client::api::{DynGlobalApi, FederationApiExt};
+use fedimint_core::config::{ClientConfig, FederationId};
+use fedimint_core::encoding::Encodable;
+use fedimint_core::endpoint_constants::{CLIENT_CONFIG_ENDPOINT, VERSION_ENDPOINT};
+use fedimint_core::invite_code::InviteCode;
+use fedimint_core::module::{ApiRequestErased, SupportedApiVersionsSummary};
+use fedimint_core::util::SafeUrl;
+use fedimint_core::PeerId;
+use fmo_api_types::{InviteCheck, InviteGuardianCheck};
+use futures::future::join_all;
+
+use crate::socks::{api_from_peers, download_config};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Contacts every guardian of the federation individually and reports which
+/// ones are reachable, their consensus version and whether they serve the same
+/// config as the others. Works for federations that aren't observed.
+#[utoipa::path(
+    get,
+    path = "/config/{invite}/check",
+    tag = "config",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    responses((status = 200, description = "Per-guardian check results", body = InviteCheck))
+)]
+pub async fn check_federation_invite(
+    Path(invite): Path<InviteCode>,
+) -> crate::error::Result<Json<InviteCheck>> {
+    let federation_id = invite.federation_id();
+
+    // Guardians missing from the invite code are only known from the config
+    let consensus_config = tokio::time::timeout(CHECK_TIMEOUT, download_config(&invite))
+        .await
+        .ok()
+        .and_then(Result::ok);
+    let endpoints = match &consensus_config {
+        Some(config) => config
+            .global
+            .api_endpoints
+            .iter()
+            .map(|(&peer_id, peer_url)| (peer_id, peer_url.url.clone()))
+            .collect::<BTreeMap<_, _>>(),
+        None => invite.peers().into_iter().collect(),
+    };
+    let consensus_config_hash = consensus_config.as_ref().map(config_hash);
+
+    let api = api_from_peers(endpoints.clone());
+    let guardians = join_all(endpoints.into_iter().map(|(peer_id, url)| {
+        let api = &api;
+        let consensus_config_hash = consensus_config_hash.as_deref();
+        async move {
+            let start_time = Instant::now();
+            let config = guardian_config(api, peer_id, federation_id)
+                .await
+                .map(|config| (config_hash(&config), start_time.elapsed()));
+            let consensus_version = guardian_consensus_version(api, peer_id).await;
+            guardian_check(
+                peer_id,
+                &url,
+                config,
+                consensus_version,
+                consensus_config_hash,
+            )
+        }
+    }))
+    .await;
+
+    Ok(Json(InviteCheck {
+        federation_id,
+        guardians,
+        consensus_config_hash,
+    }))
+}
+
+async fn guardian_config(
+    api: &DynGlobalApi,
+    peer_id: PeerId,
+    federation_id: FederationId,
+) -> anyhow::Result<ClientConfig> {
+    let response = api
+        .request_single_peer(
+            Some(CHECK_TIMEOUT),
+            CLIENT_CONFIG_ENDPOINT.to_owned(),
+            ApiRequestErased::default(),
+            peer_id,
+        )
+        .await?;
+    let config = serde_json::from_value::<ClientConfig>(response)?;
+    ensure!(
+        config.calculate_federation_id() == federation_id,
+        "Guardian returned the config of another federation"
+    );
+    Ok(config)
+}
+
+/// `None` if the guardian doesn't answer version requests, which doesn't
+/// affect its reachability
+async fn guardian_consensus_version(api: &DynGlobalApi, peer_id: PeerId) -> Option<String> {
+    let response = api
+        .request_single_peer(
+            Some(CHECK_TIMEOUT),
+            VERSION_ENDPOINT.to_owned(),
+            ApiRequestErased::default(),
+            peer_id,
+        )
+        .await
+        .ok()?;
+    let versions = serde_json::from_value::<SupportedApiVersionsSummary>(response).ok()?;
+    let version = versions.core.core_consensus;
+    Some(format!("{}.{}", version.major, version.minor))
+}
+
+fn config_hash(config: &ClientConfig) -> String {
+    sha256::Hash::hash(&config.consensus_encode_to_vec()).to_string()
+}
+
+fn guardian_check(
+    peer_id: PeerId,
+    url: &SafeUrl,
+    config: anyhow::Result<(String, Duration)>,
+    consensus_version: Option<String>,
+    consensus_config_hash: Option<&str>,
+) -> InviteGuardianCheck {
+    match config {
+        Ok((config_hash, latency)) => InviteGuardianCheck {
+            guardian_id: peer_id,
+            url: url.to_string(),
+            reachable: true,
+            latency_ms: Some(latency.as_millis() as u64),
+            consensus_version,
+            config_mismatch: consensus_config_hash.is_some_and(|hash| hash != config_hash),
+            config_hash: Some(config_hash),
+            error: None,
+        },
+        Err(e) => InviteGuardianCheck {
+            guardian_id: peer_id,
+            url: url.to_string(),
+            reachable: false,
+            latency_ms: None,
+            consensus_version,
+            config_hash: None,
+            config_mismatch: false,
+            error: Some(format!("{e:#}")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+    use fedimint_core::util::SafeUrl;
+    use fedimint_core::PeerId;
+
+    use super::guardian_check;
+
+    #[test]
+    fn test_guardian_check() {
+        let url = SafeUrl::parse("wss://guardian.example/").unwrap();
+        let config = |hash: &str| Ok((hash.to_owned(), Duration::from_millis(120)));
+
+        let check = guardian_check(PeerId::from(0), &url, config("aa"), None, Some("aa"));
+        assert!(check.reachable);
+        assert!(!check.config_mismatch);
+        assert_eq!(check.latency_ms, Some(120));
+
+        let check = guardian_check(PeerId::from(1), &url, config("bb"), None, Some("aa"));
+        assert!(check.config_mismatch);
+
+        // Without consensus there is nothing to compare against
+        let check = guardian_check(PeerId::from(2), &url, config("bb"), None, None);
+        assert!(!check.config_mismatch);
+
+        let check = guardian_check(
+            PeerId::from(3),
+            &url,
+            Err(anyhow!("Connection refused")),
+            None,
+            Some("aa"),
+        );
+        assert!(!check.reachable);
+        assert!(!check.config_mismatch);
+        assert_eq!(check.error.as_deref(), Some("Connection refused"));
+    }
+}
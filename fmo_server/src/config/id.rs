@@ -3,6 +3,13 @@ use axum::Json;
 use fedimint_core::config::FederationId;
 use fedimint_core::invite_code::InviteCode;
 
+#[utoipa::path(
+    get,
+    path = "/config/{invite}/id",
+    tag = "config",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    responses((status = 200, description = "Federation id", body = String))
+)]
 pub async fn fetch_federation_id(
     Path(invite): Path<InviteCode>,
 ) -> crate::error::Result<Json<FederationId>> {
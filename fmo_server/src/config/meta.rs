@@ -1,78 +1,522 @@
 use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure, Context};
 use axum::extract::{Path, State};
-use axum::Json;
-use fedimint_core::config::FederationId;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use bitcoin::bech32::{self, FromBase32};
+use chrono::Utc;
+use fedimint_api_client::api::FederationApiExt;
+use fedimint_core::config::{FederationId, JsonClientConfig};
+use fedimint_core::core::ModuleInstanceId;
 use fedimint_core::invite_code::InviteCode;
+use fedimint_core::module::ApiRequestErased;
+use fmo_api_types::{LightningContactKind, LightningContactVerification, MetaFetchStats};
+use reqwest::Url;
+use tracing::warn;
 
 use crate::meta::federation_meta;
+use crate::net::{is_public_ip, MetaClient};
+use crate::socks::federation_api;
 use crate::AppState;
 
 pub type MetaFields = BTreeMap<String, serde_json::Value>;
 
 const REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Minimum time between fetch attempts of an override file that failed
+const FAILURE_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_MAX_META_SIZE: usize = 1024 * 1024;
+/// Consensus meta requests wait for a threshold of guardians to agree, don't
+/// let an unreachable federation block meta requests
+const CONSENSUS_META_TIMEOUT: Duration = Duration::from_secs(10);
+/// Key under which fedimintd's meta module stores the federation's meta fields
+const CONSENSUS_META_KEY: u8 = 0;
 
+#[utoipa::path(
+    get,
+    path = "/config/{invite}/meta",
+    tag = "config",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    responses(
+        (status = 200, description = "Meta fields of the federation", body = Object, headers(
+            ("etag" = String, description = "Hash of the meta fields for conditional requests"),
+            ("x-meta-override-status" = String, description = "`fresh`, `stale` or `failed` if the federation uses a meta override file"),
+            ("x-meta-override-error" = String, description = "Why the meta override file couldn't be fetched"),
+        )),
+        (status = 304, description = "Meta fields match the `If-None-Match` header")
+    )
+)]
 pub async fn fetch_federation_meta(
     Path(invite): Path<InviteCode>,
     State(state): State<AppState>,
-) -> crate::error::Result<Json<MetaFields>> {
+    headers: HeaderMap,
+) -> crate::error::Result<Response> {
     let config = state
         .federation_config_cache
         .fetch_config_cached(&invite)
         .await?;
 
-    federation_meta(&config, &state).await
+    federation_meta(&config, &state, &headers).await
 }
 
-#[derive(Default, Debug, Clone)]
+/// Restrictions on the meta override files that are fetched, the URLs are
+/// controlled by federations
+#[derive(Debug, Clone)]
+pub struct MetaFetchPolicy {
+    allow_http: bool,
+    /// If not empty only these hosts and their subdomains are fetched from
+    allowed_hosts: Vec<String>,
+    max_size: usize,
+}
+
+impl Default for MetaFetchPolicy {
+    fn default() -> Self {
+        MetaFetchPolicy {
+            allow_http: false,
+            allowed_hosts: vec![],
+            max_size: DEFAULT_MAX_META_SIZE,
+        }
+    }
+}
+
+impl MetaFetchPolicy {
+    /// Reads the optional `FO_META_ALLOWED_HOSTS` (comma separated),
+    /// `FO_META_ALLOW_HTTP` and `FO_META_MAX_BYTES` environment variables
+    pub fn from_env() -> anyhow::Result<MetaFetchPolicy> {
+        let allowed_hosts = dotenv::var("FO_META_ALLOWED_HOSTS")
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|host| host.trim().to_lowercase())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let allow_http = match dotenv::var("FO_META_ALLOW_HTTP") {
+            Ok(allow) => allow.parse().context("Invalid FO_META_ALLOW_HTTP")?,
+            Err(_) => false,
+        };
+        let max_size = match dotenv::var("FO_META_MAX_BYTES") {
+            Ok(size) => size.parse().context("Invalid FO_META_MAX_BYTES")?,
+            Err(_) => DEFAULT_MAX_META_SIZE,
+        };
+
+        Ok(MetaFetchPolicy {
+            allow_http,
+            allowed_hosts,
+            max_size,
+        })
+    }
+
+    /// Parses the URL and checks that it may be fetched. Host names are
+    /// checked again when resolving them, see [`MetaClient`].
+    fn check_url(&self, url: &str) -> anyhow::Result<Url> {
+        let url = Url::parse(url)?;
+        match url.scheme() {
+            "https" => {}
+            "http" if self.allow_http => {}
+            scheme => bail!("Scheme {scheme} is not allowed"),
+        }
+
+        let host = url.host_str().context("URL has no host")?.to_lowercase();
+        if let Ok(ip) = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            ensure!(is_public_ip(ip), "Address {ip} is not public");
+        }
+
+        ensure!(
+            self.allowed_hosts.is_empty()
+                || self
+                    .allowed_hosts
+                    .iter()
+                    .any(|allowed| { host == *allowed || host.ends_with(&format!(".{allowed}")) }),
+            "Host {host} is not allowed"
+        );
+
+        Ok(url)
+    }
+}
+
+/// Meta fields from an override file
+#[derive(Debug, Clone)]
+pub struct MetaOverride {
+    pub fields: MetaFields,
+    /// Set if the file couldn't be refreshed and the fields are from an
+    /// outdated copy
+    pub refresh_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct MetaOverrideCache {
-    client: reqwest::Client,
+    client: MetaClient,
+    policy: MetaFetchPolicy,
     override_files: Arc<tokio::sync::RwLock<HashMap<String, (serde_json::Value, SystemTime)>>>,
+    fetch_stats: Arc<tokio::sync::RwLock<HashMap<String, MetaFetchStats>>>,
+    /// Latest verification of each lightning contact found in meta fields
+    contact_verifications: Arc<tokio::sync::RwLock<HashMap<String, LightningContactVerification>>>,
 }
 
 impl MetaOverrideCache {
+    pub fn new(client: MetaClient, policy: MetaFetchPolicy) -> MetaOverrideCache {
+        MetaOverrideCache {
+            client,
+            policy,
+            override_files: Default::default(),
+            fetch_stats: Default::default(),
+            contact_verifications: Default::default(),
+        }
+    }
+
+    /// Returns the meta fields for `federation_id` from the override file at
+    /// `url`. If refreshing the file fails an outdated copy is used if there is
+    /// one.
     pub async fn fetch_meta_cached(
         &self,
         url: &str,
         federation_id: FederationId,
-    ) -> anyhow::Result<MetaFields> {
-        let current_meta_cache_entry = self.override_files.read().await.get(url).cloned();
-        let meta = match current_meta_cache_entry {
+    ) -> anyhow::Result<MetaOverride> {
+        let cached = self.override_files.read().await.get(url).cloned();
+        let (meta, refresh_error) = match cached {
             Some((meta, last_update))
                 if SystemTime::now()
                     .duration_since(last_update)
                     .unwrap_or_default()
                     <= REFRESH_INTERVAL =>
             {
-                meta
-            }
-            _ => {
-                let meta = self.fetch_meta_inner(url).await?;
-                let mut cache = self.override_files.write().await;
-                cache.insert(url.to_owned(), (meta.clone(), SystemTime::now()));
-                meta
+                (meta, None)
             }
+            cached => match self.refresh_meta(url).await {
+                Ok(meta) => (meta, None),
+                Err(e) => match cached {
+                    Some((meta, _)) => (meta, Some(e.to_string())),
+                    None => return Err(e),
+                },
+            },
         };
 
-        let federation_meta = parse_meta_lenient(serde_json::from_value::<MetaFields>(
+        let fields = parse_meta_lenient(serde_json::from_value::<MetaFields>(
             meta.get(federation_id.to_string())
                 .ok_or_else(|| anyhow!("No entry for federation {federation_id} in {url}"))?
                 .clone(),
         )?);
-        Ok(federation_meta)
+        Ok(MetaOverride {
+            fields,
+            refresh_error,
+        })
     }
 
-    async fn fetch_meta_inner(&self, url: &str) -> anyhow::Result<serde_json::Value> {
-        Ok(self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?)
+    /// Fetch statistics of all override files, ordered by URL
+    pub async fn fetch_stats(&self) -> Vec<MetaFetchStats> {
+        let mut stats = self
+            .fetch_stats
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.url.cmp(&b.url));
+        stats
+    }
+
+    /// Checks that a lightning address or LNURL resolves to an LNURL endpoint,
+    /// results are cached for [`REFRESH_INTERVAL`]
+    pub async fn verify_lightning_contact(
+        &self,
+        field: &str,
+        contact: &str,
+        kind: LightningContactKind,
+    ) -> LightningContactVerification {
+        let cached = self
+            .contact_verifications
+            .read()
+            .await
+            .get(contact)
+            .cloned();
+        if let Some(verification) = cached {
+            let age = (Utc::now() - verification.checked_at)
+                .to_std()
+                .unwrap_or_default();
+            if verification.kind == kind && age <= REFRESH_INTERVAL {
+                return LightningContactVerification {
+                    field: field.to_owned(),
+                    ..verification
+                };
+            }
+        }
+
+        let result = self.resolve_lightning_contact(contact, kind).await;
+        let verification = LightningContactVerification {
+            field: field.to_owned(),
+            contact: contact.to_owned(),
+            kind,
+            verified: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            checked_at: Utc::now(),
+        };
+        self.contact_verifications
+            .write()
+            .await
+            .insert(contact.to_owned(), verification.clone());
+        verification
+    }
+
+    async fn resolve_lightning_contact(
+        &self,
+        contact: &str,
+        kind: LightningContactKind,
+    ) -> anyhow::Result<()> {
+        let (response, _) = self.fetch_json(&lnurl_endpoint(contact, kind)?).await?;
+
+        if response.get("status").and_then(|status| status.as_str()) == Some("ERROR") {
+            bail!(
+                "LNURL endpoint returned an error: {}",
+                response
+                    .get("reason")
+                    .and_then(|reason| reason.as_str())
+                    .unwrap_or("no reason given")
+            );
+        }
+        let tag = response
+            .get("tag")
+            .and_then(|tag| tag.as_str())
+            .context("LNURL response has no tag")?;
+        ensure!(
+            kind != LightningContactKind::LightningAddress || tag == "payRequest",
+            "Lightning address doesn't resolve to a pay request"
+        );
+
+        Ok(())
+    }
+
+    async fn refresh_meta(&self, url: &str) -> anyhow::Result<serde_json::Value> {
+        // Don't hammer servers that just failed, every meta request would
+        // otherwise trigger a fetch
+        if let Some(stats) = self.fetch_stats.read().await.get(url) {
+            if let Some(last_failure) = stats.last_failure {
+                let failed_recently =
+                    Utc::now() - last_failure < chrono::Duration::from_std(FAILURE_RETRY_INTERVAL)?;
+                if failed_recently && stats.last_success.map_or(true, |s| s < last_failure) {
+                    bail!(
+                        "Fetching failed recently: {}",
+                        stats.last_error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+            }
+        }
+
+        let start = Instant::now();
+        let result = self.fetch_json(url).await;
+        self.record_fetch(url, start.elapsed(), &result).await;
+
+        let (meta, _) = result?;
+        self.override_files
+            .write()
+            .await
+            .insert(url.to_owned(), (meta.clone(), SystemTime::now()));
+        Ok(meta)
+    }
+
+    /// Fetches a JSON document within the limits of the fetch policy
+    async fn fetch_json(&self, url: &str) -> anyhow::Result<(serde_json::Value, usize)> {
+        let url = self.policy.check_url(url)?;
+        let mut response = self.client.get(url).await?.error_for_status()?;
+
+        let max_size = self.policy.max_size;
+        if let Some(length) = response.content_length() {
+            ensure!(
+                length <= max_size as u64,
+                "Meta file of {length} bytes exceeds limit of {max_size} bytes"
+            );
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            ensure!(
+                body.len() + chunk.len() <= max_size,
+                "Meta file exceeds limit of {max_size} bytes"
+            );
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok((serde_json::from_slice(&body)?, body.len()))
+    }
+
+    async fn record_fetch(
+        &self,
+        url: &str,
+        duration: Duration,
+        result: &anyhow::Result<(serde_json::Value, usize)>,
+    ) {
+        let mut fetch_stats = self.fetch_stats.write().await;
+        let stats = fetch_stats
+            .entry(url.to_owned())
+            .or_insert_with(|| MetaFetchStats {
+                url: url.to_owned(),
+                fetches: 0,
+                failures: 0,
+                last_success: None,
+                last_failure: None,
+                last_error: None,
+                last_duration_ms: None,
+                last_size_bytes: None,
+            });
+
+        stats.fetches += 1;
+        stats.last_duration_ms = Some(duration.as_millis() as u64);
+        match result {
+            Ok((_, size)) => {
+                stats.last_success = Some(Utc::now());
+                stats.last_size_bytes = Some(*size as u64);
+            }
+            Err(e) => {
+                warn!("Failed to fetch meta override file {url}: {e:?}");
+                stats.failures += 1;
+                stats.last_failure = Some(Utc::now());
+                stats.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// Value the guardians agreed on through the meta module, the fields are a hex
+/// encoded JSON object
+#[derive(Debug, serde::Deserialize)]
+struct ConsensusMetaValue {
+    value: String,
+}
+
+/// Meta fields announced through fedimintd's meta module, by federation. Like
+/// override files they are refreshed hourly and an outdated copy is used if
+/// refreshing fails.
+#[derive(Debug, Clone, Default)]
+pub struct ConsensusMetaCache {
+    consensus_meta: Arc<tokio::sync::RwLock<HashMap<FederationId, (MetaFields, SystemTime)>>>,
+}
+
+impl ConsensusMetaCache {
+    /// Returns the consensus meta fields of the federation, `None` if it has no
+    /// meta module or its guardians didn't agree on any fields yet
+    pub async fn fetch_consensus_meta_cached(
+        &self,
+        cfg: &JsonClientConfig,
+    ) -> anyhow::Result<Option<MetaFields>> {
+        let Some(meta_module) = cfg
+            .modules
+            .iter()
+            .find_map(|(&module_instance_id, module)| {
+                (module.kind().as_str() == "meta").then_some(module_instance_id)
+            })
+        else {
+            return Ok(None);
+        };
+
+        let federation_id = cfg.global.calculate_federation_id();
+        let cached = self
+            .consensus_meta
+            .read()
+            .await
+            .get(&federation_id)
+            .cloned();
+        if let Some((fields, last_update)) = &cached {
+            if SystemTime::now()
+                .duration_since(*last_update)
+                .unwrap_or_default()
+                <= REFRESH_INTERVAL
+            {
+                return Ok(Some(fields.clone()));
+            }
+        }
+
+        match (fetch_consensus_meta(cfg, meta_module).await, cached) {
+            (Ok(fields), _) => {
+                if let Some(fields) = &fields {
+                    self.consensus_meta
+                        .write()
+                        .await
+                        .insert(federation_id, (fields.clone(), SystemTime::now()));
+                }
+                Ok(fields)
+            }
+            (Err(e), Some((fields, _))) => {
+                warn!("Failed to refresh consensus meta of {federation_id}: {e:?}");
+                Ok(Some(fields))
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+}
+
+async fn fetch_consensus_meta(
+    cfg: &JsonClientConfig,
+    meta_module: ModuleInstanceId,
+) -> anyhow::Result<Option<MetaFields>> {
+    let api = federation_api(&cfg.global.api_endpoints);
+    let response = tokio::time::timeout(
+        CONSENSUS_META_TIMEOUT,
+        api.with_module(meta_module)
+            .request_current_consensus::<serde_json::Value>(
+                "get_consensus".to_owned(),
+                ApiRequestErased::new(CONSENSUS_META_KEY),
+            ),
+    )
+    .await
+    .context("Timed out fetching consensus meta")??;
+
+    serde_json::from_value::<Option<ConsensusMetaValue>>(response)?
+        .map(|consensus| parse_consensus_meta(&consensus.value))
+        .transpose()
+}
+
+/// Decodes the hex encoded JSON object of a consensus meta value, string values
+/// are parsed leniently like config meta fields
+fn parse_consensus_meta(value: &str) -> anyhow::Result<MetaFields> {
+    let fields = serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(
+        &hex::decode(value).context("Consensus meta isn't hex encoded")?,
+    )
+    .context("Consensus meta isn't a JSON object")?;
+
+    Ok(fields
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => {
+                let value = serde_json::from_str(&value).unwrap_or_else(|_| value.into());
+                (key, value)
+            }
+            value => (key, value),
+        })
+        .collect())
+}
+
+/// URL of the LNURL endpoint a lightning address (LUD-16) or bech32 encoded
+/// LNURL (LUD-01) points to
+fn lnurl_endpoint(contact: &str, kind: LightningContactKind) -> anyhow::Result<String> {
+    match kind {
+        LightningContactKind::LightningAddress => {
+            let (user, domain) = contact
+                .split_once('@')
+                .context("Lightning address has no domain")?;
+            ensure!(
+                !user.is_empty() && !domain.is_empty(),
+                "Invalid lightning address"
+            );
+            Ok(format!(
+                "https://{}/.well-known/lnurlp/{}",
+                domain.to_lowercase(),
+                user.to_lowercase()
+            ))
+        }
+        LightningContactKind::Lnurl => {
+            let lnurl = contact.trim_start_matches("lightning:").to_lowercase();
+            let (hrp, data, _) = bech32::decode(&lnurl).context("Invalid LNURL encoding")?;
+            ensure!(hrp == "lnurl", "LNURL has unexpected prefix {hrp}");
+            Ok(String::from_utf8(Vec::<u8>::from_base32(&data)?)?)
+        }
     }
 }
 
@@ -87,3 +531,77 @@ pub fn parse_meta_lenient(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use fmo_api_types::LightningContactKind;
+
+    use serde_json::json;
+
+    use super::{lnurl_endpoint, parse_consensus_meta, MetaFetchPolicy};
+
+    #[test]
+    fn test_lnurl_endpoint() {
+        assert_eq!(
+            lnurl_endpoint("Alice@Example.com", LightningContactKind::LightningAddress).unwrap(),
+            "https://example.com/.well-known/lnurlp/alice"
+        );
+        assert!(lnurl_endpoint("alice", LightningContactKind::LightningAddress).is_err());
+
+        // Example from LUD-01
+        assert_eq!(
+            lnurl_endpoint(
+                "lightning:LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS",
+                LightningContactKind::Lnurl
+            )
+            .unwrap(),
+            "https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df"
+        );
+        assert!(lnurl_endpoint("lnbc1invalid", LightningContactKind::Lnurl).is_err());
+    }
+
+    #[test]
+    fn test_parse_consensus_meta() {
+        let value =
+            hex::encode(r#"{"federation_name":"Test","max_balance_msats":"1000","tags":["a"]}"#);
+        let fields = parse_consensus_meta(&value).unwrap();
+        assert_eq!(fields["federation_name"], json!("Test"));
+        assert_eq!(fields["max_balance_msats"], json!(1000));
+        assert_eq!(fields["tags"], json!(["a"]));
+
+        assert!(parse_consensus_meta("not hex").is_err());
+        assert!(parse_consensus_meta(&hex::encode("[]")).is_err());
+    }
+
+    #[test]
+    fn test_check_url() {
+        let policy = MetaFetchPolicy::default();
+        assert!(policy
+            .check_url("https://meta.example.com/meta.json")
+            .is_ok());
+        assert!(policy.check_url("https://1.1.1.1/meta.json").is_ok());
+        assert!(policy
+            .check_url("http://meta.example.com/meta.json")
+            .is_err());
+        assert!(policy.check_url("file:///etc/passwd").is_err());
+        assert!(policy.check_url("https://127.0.0.1/meta.json").is_err());
+        assert!(policy.check_url("https://[::1]/meta.json").is_err());
+        assert!(policy.check_url("https://169.254.169.254/latest").is_err());
+
+        let policy = MetaFetchPolicy {
+            allow_http: true,
+            allowed_hosts: vec!["example.com".to_owned()],
+            ..MetaFetchPolicy::default()
+        };
+        assert!(policy.check_url("http://example.com/meta.json").is_ok());
+        assert!(policy
+            .check_url("https://META.example.com/meta.json")
+            .is_ok());
+        assert!(policy
+            .check_url("https://notexample.com/meta.json")
+            .is_err());
+        assert!(policy
+            .check_url("https://example.com.evil.org/meta.json")
+            .is_err());
+    }
+}
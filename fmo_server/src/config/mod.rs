@@ -2,36 +2,58 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::routing::get;
-use axum::{Json, Router};
-use fedimint_api_client::download_from_invite_code;
+use axum::Router;
 use fedimint_core::config::{FederationId, JsonClientConfig};
 use fedimint_core::invite_code::InviteCode;
 use reqwest::Method;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::warn;
+use utoipa::OpenApi;
 
+use crate::config::check::check_federation_invite;
 use crate::config::id::fetch_federation_id;
 use crate::config::meta::fetch_federation_meta;
 use crate::config::modules::fetch_federation_module_kinds;
 use crate::error::Result;
+use crate::etag::json_with_etag;
+use crate::socks::download_config;
 use crate::util::config_to_json;
 use crate::AppState;
 
+/// Per-guardian reachability and config consistency checks
+pub mod check;
 /// Helper API that exposes the federation id
 pub mod id;
-/// Helper API that unifies config meta and override meta, applying lenient
+/// Helper API that unifies config, override and consensus meta, applying lenient
 /// parsing
 pub mod meta;
 
 /// Helper API that exposes the federation modules
 pub mod modules;
+/// OpenAPI description of the routes in [`get_config_routes`]
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        fetch_federation_config,
+        meta::fetch_federation_meta,
+        id::fetch_federation_id,
+        modules::fetch_federation_module_kinds,
+        check::check_federation_invite,
+    ),
+    components(schemas(fmo_api_types::InviteCheck, fmo_api_types::InviteGuardianCheck))
+)]
+pub(crate) struct ConfigApi;
+
 pub fn get_config_routes() -> Router<AppState> {
     let router = Router::new()
         .route("/:invite", get(fetch_federation_config))
         .route("/:invite/meta", get(fetch_federation_meta))
         .route("/:invite/id", get(fetch_federation_id))
-        .route("/:invite/module_kinds", get(fetch_federation_module_kinds));
+        .route("/:invite/module_kinds", get(fetch_federation_module_kinds))
+        .route("/:invite/check", get(check_federation_invite));
 
     let cors_enabled = dotenv::var("ALLOW_CONFIG_CORS").map_or(false, |v| v == "true");
 
@@ -46,15 +68,29 @@ pub fn get_config_routes() -> Router<AppState> {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/config/{invite}",
+    tag = "config",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    responses(
+        (status = 200, description = "Client config of the federation", body = Object, headers(
+            ("etag" = String, description = "Hash of the config for conditional requests")
+        )),
+        (status = 304, description = "Config matches the `If-None-Match` header")
+    )
+)]
 pub async fn fetch_federation_config(
     Path(invite): Path<InviteCode>,
     State(state): State<AppState>,
-) -> Result<Json<JsonClientConfig>> {
-    Ok(state
+    headers: HeaderMap,
+) -> Result<Response> {
+    let config = state
         .federation_config_cache
         .fetch_config_cached(&invite)
-        .await?
-        .into())
+        .await?;
+
+    Ok(json_with_etag(&headers, HeaderMap::new(), config)?)
 }
 
 #[derive(Default, Debug, Clone)]
@@ -87,6 +123,6 @@ impl FederationConfigCache {
 }
 
 async fn fetch_config_inner(invite: &InviteCode) -> anyhow::Result<JsonClientConfig> {
-    let raw_config = download_from_invite_code(invite).await?;
+    let raw_config = download_config(invite).await?;
     config_to_json(raw_config)
 }
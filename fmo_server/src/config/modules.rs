@@ -7,6 +7,13 @@ use fedimint_core::invite_code::InviteCode;
 
 use crate::AppState;
 
+#[utoipa::path(
+    get,
+    path = "/config/{invite}/module_kinds",
+    tag = "config",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    responses((status = 200, description = "Kinds of the federation's modules", body = [String]))
+)]
 pub async fn fetch_federation_module_kinds(
     Path(invite): Path<InviteCode>,
     State(state): State<AppState>,
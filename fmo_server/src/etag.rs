@@ -0,0 +1,58 @@
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use bitcoin::hashes::{sha256, Hash};
+use serde::Serialize;
+
+/// Responds with `value` as JSON and an ETag derived from it, or with `304 Not
+/// Modified` if the request's `If-None-Match` header already names that ETag.
+/// `headers` are added to both responses.
+pub fn json_with_etag<T: Serialize>(
+    request_headers: &HeaderMap,
+    mut headers: HeaderMap,
+    value: T,
+) -> anyhow::Result<Response> {
+    let etag = json_etag(&value)?;
+    headers.insert(ETAG, HeaderValue::from_str(&etag)?);
+
+    let not_modified = request_headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .any(|if_none_match| etag_matches(if_none_match, &etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    Ok((headers, Json(value)).into_response())
+}
+
+/// Strong ETag of the JSON serialization of `value`, only stable for types
+/// that serialize deterministically (e.g. use `BTreeMap`s)
+fn json_etag<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    let hash = sha256::Hash::hash(&serde_json::to_vec(value)?);
+    Ok(format!("\"{hash}\""))
+}
+
+/// Checks if `etag` is listed in an `If-None-Match` header value, using the
+/// weak comparison mandated for `If-None-Match`
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::etag_matches;
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"abc\"", "\"abc\""));
+        assert!(etag_matches("\"xyz\", W/\"abc\"", "\"abc\""));
+        assert!(etag_matches("*", "\"abc\""));
+        assert!(!etag_matches("\"abcd\"", "\"abc\""));
+        assert!(!etag_matches("abc", "\"abc\""));
+    }
+}
@@ -0,0 +1,693 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, State};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::PeerId;
+use fmo_api_types::{
+    AlertDelivery, AlertDeliveryStatus, AlertEvent, AlertKind, AlertNotification, AlertRule,
+    AlertRuleSettings, WebhookFormat,
+};
+use postgres_from_row::FromRow;
+use serde_json::json;
+use tracing::{debug, warn};
+
+use crate::federation::alerts::{alert_kind_name, parse_alert_kind};
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_opt, query_value};
+use crate::AppState;
+
+const DELIVERY_INTERVAL: Duration = Duration::from_secs(10);
+const DELIVERY_BATCH_SIZE: i64 = 50;
+const MAX_DELIVERY_ATTEMPTS: u32 = 10;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+const MAX_LISTED_DELIVERIES: i64 = 100;
+/// Webhooks that take longer to respond are treated as failed
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Claimed notifications are skipped by other workers for this long, which is
+/// more than a whole batch of timed out webhooks takes
+const DELIVERY_LEASE: Duration = Duration::from_secs(15 * 60);
+
+/// Alert rules deliver new alert events to webhooks. They may contain secrets
+/// in their URLs, so all routes require the `federations` scope.
+pub fn get_alert_rule_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_alert_rules).post(create_alert_rule))
+        .route(
+            "/:rule_id",
+            put(update_alert_rule).delete(delete_alert_rule),
+        )
+        .route("/:rule_id/test", post(test_alert_rule))
+        .route("/:rule_id/deliveries", get(list_alert_rule_deliveries))
+}
+
+async fn list_alert_rules(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<AlertRule>>> {
+    Ok(state.federation_observer.list_alert_rules().await?.into())
+}
+
+async fn create_alert_rule(
+    State(state): State<AppState>,
+    Json(settings): Json<AlertRuleSettings>,
+) -> crate::error::Result<Json<AlertRule>> {
+    Ok(state
+        .federation_observer
+        .create_alert_rule(settings)
+        .await?
+        .into())
+}
+
+async fn update_alert_rule(
+    Path(rule_id): Path<u64>,
+    State(state): State<AppState>,
+    Json(settings): Json<AlertRuleSettings>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .update_alert_rule(rule_id, &settings)
+        .await?)
+}
+
+async fn delete_alert_rule(
+    Path(rule_id): Path<u64>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state.federation_observer.delete_alert_rule(rule_id).await?)
+}
+
+/// Sends a test notification to the rule's webhook, failing if it can't be
+/// delivered
+async fn test_alert_rule(
+    Path(rule_id): Path<u64>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state.federation_observer.test_alert_rule(rule_id).await?)
+}
+
+async fn list_alert_rule_deliveries(
+    Path(rule_id): Path<u64>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<AlertDelivery>>> {
+    Ok(state
+        .federation_observer
+        .alert_rule_deliveries(rule_id)
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct AlertRuleRow {
+    rule_id: i64,
+    federation_id: Option<Vec<u8>>,
+    kinds: Vec<String>,
+    webhook_url: String,
+    format: String,
+    enabled: bool,
+}
+
+impl TryFrom<AlertRuleRow> for AlertRule {
+    type Error = anyhow::Error;
+
+    fn try_from(row: AlertRuleRow) -> anyhow::Result<Self> {
+        Ok(AlertRule {
+            rule_id: row.rule_id as u64,
+            settings: AlertRuleSettings {
+                federation_id: row
+                    .federation_id
+                    .map(|federation_id| {
+                        Ok::<_, anyhow::Error>(FederationId(
+                            bitcoin::hashes::Hash::from_byte_array(
+                                federation_id
+                                    .try_into()
+                                    .map_err(|_| anyhow::anyhow!("Invalid federation id in DB"))?,
+                            ),
+                        ))
+                    })
+                    .transpose()?,
+                kinds: row
+                    .kinds
+                    .iter()
+                    .map(|kind| parse_alert_kind(kind))
+                    .collect::<anyhow::Result<_>>()?,
+                webhook_url: row.webhook_url,
+                format: parse_webhook_format(&row.format)?,
+                enabled: row.enabled,
+            },
+        })
+    }
+}
+
+impl FederationObserver {
+    pub async fn list_alert_rules(&self) -> anyhow::Result<Vec<AlertRule>> {
+        query::<AlertRuleRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT rule_id, federation_id, kinds, webhook_url, format, enabled
+             FROM alert_rules
+             ORDER BY rule_id",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(AlertRule::try_from)
+        .collect()
+    }
+
+    pub async fn create_alert_rule(
+        &self,
+        settings: AlertRuleSettings,
+    ) -> anyhow::Result<AlertRule> {
+        self.validate_alert_rule(&settings).await?;
+
+        let rule_id = query_value::<i64>(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO alert_rules (federation_id, kinds, webhook_url, format, enabled, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING rule_id",
+            &[
+                &settings
+                    .federation_id
+                    .map(|federation_id| federation_id.consensus_encode_to_vec()),
+                &kind_names(&settings.kinds),
+                &settings.webhook_url,
+                &webhook_format_name(settings.format),
+                &settings.enabled,
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+
+        Ok(AlertRule {
+            rule_id: rule_id as u64,
+            settings,
+        })
+    }
+
+    pub async fn update_alert_rule(
+        &self,
+        rule_id: u64,
+        settings: &AlertRuleSettings,
+    ) -> anyhow::Result<()> {
+        self.validate_alert_rule(settings).await?;
+
+        let updated = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE alert_rules
+             SET federation_id = $2,
+                 kinds         = $3,
+                 webhook_url   = $4,
+                 format        = $5,
+                 enabled       = $6
+             WHERE rule_id = $1",
+            &[
+                &(rule_id as i64),
+                &settings
+                    .federation_id
+                    .map(|federation_id| federation_id.consensus_encode_to_vec()),
+                &kind_names(&settings.kinds),
+                &settings.webhook_url,
+                &webhook_format_name(settings.format),
+                &settings.enabled,
+            ],
+        )
+        .await?;
+        ensure!(updated > 0, "Alert rule doesn't exist");
+
+        Ok(())
+    }
+
+    pub async fn delete_alert_rule(&self, rule_id: u64) -> anyhow::Result<()> {
+        let deleted = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM alert_rules WHERE rule_id = $1",
+            &[&(rule_id as i64)],
+        )
+        .await?;
+        ensure!(deleted > 0, "Alert rule doesn't exist");
+
+        Ok(())
+    }
+
+    async fn test_alert_rule(&self, rule_id: u64) -> anyhow::Result<()> {
+        let rule = query_opt::<AlertRuleRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT rule_id, federation_id, kinds, webhook_url, format, enabled
+             FROM alert_rules
+             WHERE rule_id = $1",
+            &[&(rule_id as i64)],
+        )
+        .await?
+        .context("Alert rule doesn't exist")?;
+        let rule = AlertRule::try_from(rule)?;
+
+        let notification = AlertNotification {
+            federation_id: rule
+                .settings
+                .federation_id
+                .unwrap_or_else(FederationId::dummy),
+            federation_name: Some("Test Federation".to_owned()),
+            event: AlertEvent {
+                kind: rule
+                    .settings
+                    .kinds
+                    .first()
+                    .copied()
+                    .unwrap_or(AlertKind::GuardianOffline),
+                guardian_id: None,
+                message: format!("Test notification of alert rule {rule_id}"),
+                raised_at: chrono::Utc::now(),
+            },
+        };
+        self.post_webhook(
+            &rule.settings.webhook_url,
+            rule.settings.format,
+            &notification,
+        )
+        .await
+    }
+
+    async fn validate_alert_rule(&self, settings: &AlertRuleSettings) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(&settings.webhook_url).context("Invalid webhook URL")?;
+        ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "Webhook URL has to use http or https"
+        );
+
+        if let Some(federation_id) = settings.federation_id {
+            self.get_federation(federation_id)
+                .await?
+                .context("Federation doesn't exist")?;
+        }
+
+        Ok(())
+    }
+
+    /// Queues a notification of a newly raised alert for every enabled rule
+    /// matching it, they are sent by [`Self::deliver_notifications`]
+    pub(super) async fn queue_notifications(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        kind: AlertKind,
+        alert_id: i64,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        execute(
+            dbtx,
+            // language=postgresql
+            "INSERT INTO notification_outbox (rule_id, alert_id, created_at, next_attempt_at)
+             SELECT rule_id, $3, $4, $4
+             FROM alert_rules
+             WHERE enabled
+               AND (federation_id IS NULL OR federation_id = $1)
+               AND (CARDINALITY(kinds) = 0 OR $2 = ANY (kinds))
+             ON CONFLICT DO NOTHING",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &alert_kind_name(kind),
+                &alert_id,
+                &now,
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sends queued notifications until the task is cancelled
+    pub(super) async fn deliver_notifications(self) {
+        let mut interval = tokio::time::interval(DELIVERY_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.deliver_due_notifications().await {
+                warn!("Error while delivering notifications: {e:?}");
+            }
+        }
+    }
+
+    /// Sends one batch of due notifications. They are claimed by pushing their
+    /// next attempt past [`DELIVERY_LEASE`], so concurrent workers skip them
+    /// and a crash only causes them to be sent again once the lease expires.
+    /// The outcome of each delivery is stored right after it, no transaction
+    /// is held open while waiting for webhooks.
+    async fn deliver_due_notifications(&self) -> anyhow::Result<()> {
+        for notification in self.claim_due_notifications().await? {
+            let attempts = notification.attempts as u32 + 1;
+            let now = chrono::Utc::now().naive_utc();
+            let (status, next_attempt_at, error) =
+                match self.notification_payload(&notification).await {
+                    Err(e) => {
+                        // Retrying won't make the notification decodable
+                        warn!(
+                            rule_id = notification.rule_id,
+                            "Invalid alert notification: {e:?}"
+                        );
+                        ("failed", now, Some(e))
+                    }
+                    Ok((format, payload)) => match self
+                        .post_webhook(&notification.webhook_url, format, &payload)
+                        .await
+                    {
+                        Ok(()) => {
+                            debug!(
+                                rule_id = notification.rule_id,
+                                "Delivered alert notification"
+                            );
+                            ("delivered", now, None)
+                        }
+                        Err(e) => {
+                            warn!(
+                                rule_id = notification.rule_id,
+                                attempts, "Failed to deliver alert notification: {e:?}"
+                            );
+                            match retry_delay(attempts) {
+                                Some(delay) => ("pending", now + delay, Some(e)),
+                                None => ("failed", now, Some(e)),
+                            }
+                        }
+                    },
+                };
+
+            execute(
+                &self.connection().await?,
+                // language=postgresql
+                "UPDATE notification_outbox
+                 SET status          = $2,
+                     attempts        = $3,
+                     last_attempt_at = $4,
+                     next_attempt_at = $5,
+                     last_error      = $6,
+                     delivered_at    = CASE WHEN $2 = 'delivered' THEN $4 END
+                 WHERE outbox_id = $1",
+                &[
+                    &notification.outbox_id,
+                    &status,
+                    &(attempts as i32),
+                    &now,
+                    &next_attempt_at,
+                    &error.map(|e| e.to_string()),
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Claims a batch of due notifications for [`DELIVERY_LEASE`]
+    async fn claim_due_notifications(&self) -> anyhow::Result<Vec<DueNotificationRow>> {
+        let now = chrono::Utc::now().naive_utc();
+        let lease_end = now + chrono::Duration::from_std(DELIVERY_LEASE).expect("Lease is small");
+        query::<DueNotificationRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE notification_outbox o
+             SET next_attempt_at = $3
+             FROM alert_rules r,
+                  alert_events e
+             WHERE o.outbox_id IN (SELECT outbox_id
+                                   FROM notification_outbox
+                                   WHERE status = 'pending'
+                                     AND next_attempt_at <= $1
+                                   ORDER BY next_attempt_at
+                                   LIMIT $2 FOR UPDATE SKIP LOCKED)
+               AND o.rule_id = r.rule_id
+               AND o.alert_id = e.alert_id
+             RETURNING o.outbox_id,
+                       o.attempts,
+                       r.rule_id,
+                       r.webhook_url,
+                       r.format,
+                       e.federation_id,
+                       e.kind,
+                       e.guardian_id,
+                       e.message,
+                       e.raised_at",
+            &[&now, &DELIVERY_BATCH_SIZE, &lease_end],
+        )
+        .await
+    }
+
+    /// Decodes a due notification into what's sent to its webhook
+    async fn notification_payload(
+        &self,
+        notification: &DueNotificationRow,
+    ) -> anyhow::Result<(WebhookFormat, AlertNotification)> {
+        let federation_id = FederationId(bitcoin::hashes::Hash::from_byte_array(
+            notification
+                .federation_id
+                .clone()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid federation id in DB"))?,
+        ));
+        // The name is only cosmetic, so not being able to look it up shouldn't fail
+        // the delivery
+        let federation_name = match self.get_federation(federation_id).await {
+            Ok(federation) => federation.and_then(|federation| {
+                federation
+                    .config
+                    .global
+                    .federation_name()
+                    .map(ToOwned::to_owned)
+            }),
+            Err(e) => {
+                warn!(%federation_id, "Failed to look up federation name: {e:?}");
+                None
+            }
+        };
+
+        let payload = AlertNotification {
+            federation_id,
+            federation_name,
+            event: AlertEvent {
+                kind: parse_alert_kind(&notification.kind)?,
+                guardian_id: notification
+                    .guardian_id
+                    .map(|guardian_id| PeerId::from(guardian_id as u16)),
+                message: notification.message.clone(),
+                raised_at: notification.raised_at.and_utc(),
+            },
+        };
+
+        Ok((parse_webhook_format(&notification.format)?, payload))
+    }
+
+    /// Most recent notifications queued for a rule
+    pub async fn alert_rule_deliveries(&self, rule_id: u64) -> anyhow::Result<Vec<AlertDelivery>> {
+        #[derive(Debug, FromRow)]
+        struct DeliveryRow {
+            federation_id: Vec<u8>,
+            kind: String,
+            guardian_id: Option<i32>,
+            message: String,
+            raised_at: NaiveDateTime,
+            status: String,
+            attempts: i32,
+            last_attempt_at: Option<NaiveDateTime>,
+            last_error: Option<String>,
+            delivered_at: Option<NaiveDateTime>,
+        }
+
+        let conn = self.connection().await?;
+        conn.query_opt(
+            "SELECT 1 FROM alert_rules WHERE rule_id = $1",
+            &[&(rule_id as i64)],
+        )
+        .await?
+        .context("Alert rule doesn't exist")?;
+
+        query::<DeliveryRow>(
+            &conn,
+            // language=postgresql
+            "SELECT e.federation_id,
+                    e.kind,
+                    e.guardian_id,
+                    e.message,
+                    e.raised_at,
+                    o.status,
+                    o.attempts,
+                    o.last_attempt_at,
+                    o.last_error,
+                    o.delivered_at
+             FROM notification_outbox o
+                      JOIN alert_events e ON o.alert_id = e.alert_id
+             WHERE o.rule_id = $1
+             ORDER BY o.created_at DESC
+             LIMIT $2",
+            &[&(rule_id as i64), &MAX_LISTED_DELIVERIES],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(AlertDelivery {
+                federation_id: FederationId(bitcoin::hashes::Hash::from_byte_array(
+                    row.federation_id
+                        .try_into()
+                        .map_err(|_| anyhow::anyhow!("Invalid federation id in DB"))?,
+                )),
+                event: AlertEvent {
+                    kind: parse_alert_kind(&row.kind)?,
+                    guardian_id: row
+                        .guardian_id
+                        .map(|guardian_id| PeerId::from(guardian_id as u16)),
+                    message: row.message,
+                    raised_at: row.raised_at.and_utc(),
+                },
+                status: parse_delivery_status(&row.status)?,
+                attempts: row.attempts as u32,
+                last_attempt_at: row.last_attempt_at.map(|time| time.and_utc()),
+                last_error: row.last_error,
+                delivered_at: row.delivered_at.map(|time| time.and_utc()),
+            })
+        })
+        .collect()
+    }
+
+    async fn post_webhook(
+        &self,
+        webhook_url: &str,
+        format: WebhookFormat,
+        notification: &AlertNotification,
+    ) -> anyhow::Result<()> {
+        self.http_client()
+            .post(webhook_url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&webhook_payload(format, notification))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct DueNotificationRow {
+    outbox_id: i64,
+    attempts: i32,
+    rule_id: i64,
+    webhook_url: String,
+    format: String,
+    federation_id: Vec<u8>,
+    kind: String,
+    guardian_id: Option<i32>,
+    message: String,
+    raised_at: NaiveDateTime,
+}
+
+/// Formats a notification for the webhook's chat system
+fn webhook_payload(format: WebhookFormat, notification: &AlertNotification) -> serde_json::Value {
+    let federation = notification
+        .federation_name
+        .clone()
+        .unwrap_or_else(|| notification.federation_id.to_string());
+    let text = format!("[{federation}] {}", notification.event.message);
+
+    match format {
+        WebhookFormat::Slack => json!({ "text": text }),
+        WebhookFormat::Matrix => json!({ "msgtype": "m.text", "body": text }),
+        WebhookFormat::Json => {
+            serde_json::to_value(notification).expect("Notifications can be serialized")
+        }
+    }
+}
+
+/// Delay before retrying a notification that failed `attempts` times, `None`
+/// once it should be given up
+fn retry_delay(attempts: u32) -> Option<chrono::Duration> {
+    if attempts >= MAX_DELIVERY_ATTEMPTS {
+        return None;
+    }
+
+    let delay = BASE_RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY);
+    Some(chrono::Duration::from_std(delay).expect("Delay is small"))
+}
+
+fn parse_delivery_status(name: &str) -> anyhow::Result<AlertDeliveryStatus> {
+    match name {
+        "pending" => Ok(AlertDeliveryStatus::Pending),
+        "delivered" => Ok(AlertDeliveryStatus::Delivered),
+        "failed" => Ok(AlertDeliveryStatus::Failed),
+        _ => Err(anyhow::anyhow!("Unknown delivery status {name}")),
+    }
+}
+
+fn kind_names(kinds: &[AlertKind]) -> Vec<&'static str> {
+    kinds.iter().map(|&kind| alert_kind_name(kind)).collect()
+}
+
+fn webhook_format_name(format: WebhookFormat) -> &'static str {
+    match format {
+        WebhookFormat::Slack => "slack",
+        WebhookFormat::Matrix => "matrix",
+        WebhookFormat::Json => "json",
+    }
+}
+
+fn parse_webhook_format(name: &str) -> anyhow::Result<WebhookFormat> {
+    match name {
+        "slack" => Ok(WebhookFormat::Slack),
+        "matrix" => Ok(WebhookFormat::Matrix),
+        "json" => Ok(WebhookFormat::Json),
+        _ => Err(anyhow::anyhow!("Unknown webhook format {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use fedimint_core::PeerId;
+    use fmo_api_types::{AlertEvent, AlertKind, AlertNotification, WebhookFormat};
+    use serde_json::json;
+
+    use super::{retry_delay, webhook_payload, MAX_DELIVERY_ATTEMPTS};
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Some(chrono::Duration::seconds(30)));
+        assert_eq!(retry_delay(2), Some(chrono::Duration::seconds(60)));
+        assert_eq!(retry_delay(9), Some(chrono::Duration::hours(1)));
+        assert_eq!(retry_delay(MAX_DELIVERY_ATTEMPTS), None);
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let notification = AlertNotification {
+            federation_id: FederationId::dummy(),
+            federation_name: Some("Alpha".to_owned()),
+            event: AlertEvent {
+                kind: AlertKind::GuardianOffline,
+                guardian_id: Some(PeerId::from(2)),
+                message: "Guardian 2 stopped responding".to_owned(),
+                raised_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            },
+        };
+
+        assert_eq!(
+            webhook_payload(WebhookFormat::Slack, &notification),
+            json!({ "text": "[Alpha] Guardian 2 stopped responding" })
+        );
+        assert_eq!(
+            webhook_payload(WebhookFormat::Matrix, &notification),
+            json!({ "msgtype": "m.text", "body": "[Alpha] Guardian 2 stopped responding" })
+        );
+
+        let json = webhook_payload(WebhookFormat::Json, &notification);
+        assert_eq!(json["kind"], "guardian_offline");
+        assert_eq!(json["guardian_id"], 2);
+        assert_eq!(json["federation_name"], "Alpha");
+    }
+}
@@ -0,0 +1,903 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::{Amount, NumPeers, PeerId};
+use fmo_api_types::{AlertEvent, AlertKind, AlertSettings};
+use postgres_from_row::FromRow;
+use tracing::{debug, info, warn};
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_one, query_opt};
+use crate::AppState;
+
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Length of the two windows compared when looking for regressions, an alert
+/// won't be raised again for the same guardian within this window either
+const REGRESSION_WINDOW_DAYS: i32 = 7;
+const MAX_LISTED_ALERTS: i64 = 100;
+/// Number of consecutive health checks a condition has to be met for before
+/// an alert is raised
+const HEALTH_ALERT_CONFIRMATIONS: u32 = 3;
+/// Same limits as used for the outdated flags of the guardian health endpoint
+const MAX_BLOCK_LAG: u32 = 6;
+const MAX_SESSION_LAG: u64 = 1;
+/// Number of most recent sessions whose wallet votes are compared between
+/// guardians, using the median over many sessions ignores one-off outliers
+const WALLET_VOTE_WINDOW_SESSIONS: i32 = 100;
+/// Guardians with fewer votes in the window aren't compared
+const MIN_WALLET_VOTES: i64 = 10;
+/// Factor by which a guardian's median fee rate vote may differ from the
+/// federation's median in either direction
+const MAX_FEERATE_VOTE_RATIO: f64 = 2.0;
+/// Net flows are computed from the estimated session times, longer windows
+/// than a month make the hourly evaluation needlessly expensive
+const MAX_NET_FLOW_WINDOW_HOURS: u32 = 30 * 24;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/alerts",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = [AlertEvent]))
+)]
+pub(super) async fn get_federation_alerts(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<AlertEvent>>> {
+    Ok(state
+        .federation_observer
+        .list_alerts(federation_id)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/alerts/settings",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = AlertSettings))
+)]
+pub(super) async fn get_alert_settings(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<AlertSettings>> {
+    Ok(state
+        .federation_observer
+        .alert_settings(federation_id)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    put,
+    path = "/federations/{federation_id}/alerts/settings",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    request_body = AlertSettings,
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Settings updated"))
+)]
+pub(super) async fn put_alert_settings(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+    Json(settings): Json<AlertSettings>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .set_alert_settings(federation_id, &settings)
+        .await?)
+}
+
+impl FederationObserver {
+    /// Periodically checks all observed federations for conditions that should
+    /// raise an alert
+    pub(super) async fn evaluate_alerts(self) {
+        let mut interval = tokio::time::interval(EVALUATION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.evaluate_alerts_inner().await {
+                warn!("Error while evaluating alerts: {e:?}");
+            }
+        }
+    }
+
+    async fn evaluate_alerts_inner(&self) -> anyhow::Result<()> {
+        for federation in self.list_active_federations().await? {
+            let federation_id = federation.federation_id;
+            let settings = self.alert_settings(federation_id).await?;
+
+            debug!(%federation_id, "Evaluating alerts");
+            let mut conn = self.connection().await?;
+            let dbtx = conn.transaction().await?;
+            self.evaluate_latency_regressions(&dbtx, federation_id, &settings)
+                .await
+                .with_context(|| format!("Failed to evaluate latency of {federation_id}"))?;
+            self.evaluate_wallet_votes(&dbtx, federation_id)
+                .await
+                .with_context(|| format!("Failed to evaluate wallet votes of {federation_id}"))?;
+            self.evaluate_net_flow(&dbtx, federation_id, &settings)
+                .await
+                .with_context(|| format!("Failed to evaluate net flow of {federation_id}"))?;
+            dbtx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Compares each guardian's p95 latency of the last week with the week
+    /// before to catch slowly degrading guardians before they go offline
+    async fn evaluate_latency_regressions(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        settings: &AlertSettings,
+    ) -> anyhow::Result<()> {
+        #[derive(Debug, FromRow)]
+        struct GuardianLatency {
+            guardian_id: i32,
+            current_p95: Option<f64>,
+            previous_p95: Option<f64>,
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+
+        // Failed requests are recorded with the timeout as latency, we only look at
+        // successful ones (with block height) to not confuse outages with regressions
+        let latencies = query::<GuardianLatency>(
+            dbtx,
+            // language=postgresql
+            "SELECT guardian_id,
+                    PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms)
+                    FILTER (WHERE time > $2::timestamp - INTERVAL '1 day' * $3::integer) AS current_p95,
+                    PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms)
+                    FILTER (WHERE time <= $2::timestamp - INTERVAL '1 day' * $3::integer) AS previous_p95
+             FROM guardian_health
+             WHERE federation_id = $1
+               AND time > $2::timestamp - INTERVAL '1 day' * $3::integer * 2
+               AND latency_ms IS NOT NULL
+               AND block_height IS NOT NULL
+             GROUP BY guardian_id",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &now,
+                &REGRESSION_WINDOW_DAYS,
+            ],
+        )
+        .await?;
+
+        for latency in latencies {
+            let (Some(current_p95), Some(previous_p95)) =
+                (latency.current_p95, latency.previous_p95)
+            else {
+                continue;
+            };
+
+            if current_p95 < f64::from(settings.latency_regression_min_ms)
+                || current_p95 < previous_p95 * settings.latency_regression_ratio
+            {
+                continue;
+            }
+
+            let message = format!(
+                "p95 API latency increased from {previous_p95:.0}ms to {current_p95:.0}ms week-over-week"
+            );
+            let raised = self
+                .raise_alert(
+                    dbtx,
+                    federation_id,
+                    AlertKind::LatencyRegression,
+                    Some(PeerId::from(latency.guardian_id as u16)),
+                    &message,
+                    now,
+                )
+                .await?;
+            if raised {
+                info!(%federation_id, guardian_id = latency.guardian_id, "{message}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compares each guardian's recent fee rate and block height votes with the
+    /// other guardians' to catch misconfigured bitcoin backends before they
+    /// cause stuck peg-outs
+    async fn evaluate_wallet_votes(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        let now = chrono::Utc::now().naive_utc();
+
+        let feerate_votes = self
+            .median_wallet_votes(
+                dbtx,
+                federation_id,
+                // language=postgresql
+                "SELECT proposer, PERCENTILE_DISC(0.5) WITHIN GROUP (ORDER BY sats_per_kvb) AS median_vote
+                 FROM wallet_feerate_votes
+                 WHERE federation_id = $1
+                   AND session_index > (SELECT MAX(session_index) FROM sessions WHERE federation_id = $1) - $2
+                 GROUP BY proposer
+                 HAVING COUNT(*) >= $3",
+            )
+            .await?;
+        let feerate_divergences = divergent_votes(&feerate_votes, |vote, median| {
+            let (vote, median) = (vote as f64, median as f64);
+            vote > median * MAX_FEERATE_VOTE_RATIO || vote * MAX_FEERATE_VOTE_RATIO < median
+        })
+        .into_iter()
+        .map(|(peer_id, vote, median)| {
+            (
+                peer_id,
+                format!(
+                    "Guardian {peer_id} votes for a fee rate of {vote} sat/kvB, the federation median is {median} sat/kvB"
+                ),
+            )
+        });
+
+        let block_votes = self
+            .median_wallet_votes(
+                dbtx,
+                federation_id,
+                // language=postgresql
+                "SELECT proposer, PERCENTILE_DISC(0.5) WITHIN GROUP (ORDER BY height_vote)::bigint AS median_vote
+                 FROM block_height_votes
+                 WHERE federation_id = $1
+                   AND session_index > (SELECT MAX(session_index) FROM sessions WHERE federation_id = $1) - $2
+                 GROUP BY proposer
+                 HAVING COUNT(*) >= $3",
+            )
+            .await?;
+        let block_divergences = divergent_votes(&block_votes, |vote, median| {
+            vote.abs_diff(median) > u64::from(MAX_BLOCK_LAG)
+        })
+        .into_iter()
+        .map(|(peer_id, vote, median)| {
+            (
+                peer_id,
+                format!(
+                    "Guardian {peer_id} votes for block height {vote}, the federation median is {median}"
+                ),
+            )
+        });
+
+        // Both kinds of divergence share the alert kind, so only the first one per
+        // guardian is raised within the dedup window
+        for (peer_id, message) in feerate_divergences.chain(block_divergences) {
+            let raised = self
+                .raise_alert(
+                    dbtx,
+                    federation_id,
+                    AlertKind::WalletVoteDivergence,
+                    Some(peer_id),
+                    &message,
+                    now,
+                )
+                .await?;
+            if raised {
+                info!(%federation_id, "{message}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compares the federation's peg-ins and peg-outs within the configured
+    /// window against the net flow thresholds, large outflows can hint at a
+    /// bank run or a compromised federation
+    async fn evaluate_net_flow(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        settings: &AlertSettings,
+    ) -> anyhow::Result<()> {
+        #[derive(Debug, FromRow)]
+        struct NetFlowRow {
+            peg_in_msat: i64,
+            peg_out_msat: i64,
+        }
+
+        if settings.net_outflow_threshold.is_none() && settings.net_inflow_threshold.is_none() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let window_hours = i32::try_from(settings.net_flow_window_hours)?;
+
+        let flow = query_one::<NetFlowRow>(
+            dbtx,
+            // language=postgresql
+            "SELECT (SELECT COALESCE(SUM(ti.amount_msat), 0)
+                     FROM transaction_inputs ti
+                              JOIN transactions t ON t.federation_id = ti.federation_id AND t.txid = ti.txid
+                              JOIN session_times st
+                                   ON st.federation_id = t.federation_id AND st.session_index = t.session_index
+                     WHERE ti.federation_id = $1
+                       AND ti.kind = 'wallet'
+                       AND st.estimated_session_timestamp > $2::timestamp - INTERVAL '1 hour' * $3::integer)::bigint AS peg_in_msat,
+                    (SELECT COALESCE(SUM(tout.amount_msat), 0)
+                     FROM transaction_outputs tout
+                              JOIN transactions t ON t.federation_id = tout.federation_id AND t.txid = tout.txid
+                              JOIN session_times st
+                                   ON st.federation_id = t.federation_id AND st.session_index = t.session_index
+                     WHERE tout.federation_id = $1
+                       AND tout.kind = 'wallet'
+                       AND st.estimated_session_timestamp > $2::timestamp - INTERVAL '1 hour' * $3::integer)::bigint AS peg_out_msat",
+            &[&federation_id.consensus_encode_to_vec(), &now, &window_hours],
+        )
+        .await?;
+
+        let Some((kind, message)) = net_flow_alert(
+            Amount::from_msats(flow.peg_in_msat as u64),
+            Amount::from_msats(flow.peg_out_msat as u64),
+            settings,
+        ) else {
+            return Ok(());
+        };
+
+        if self
+            .raise_alert(dbtx, federation_id, kind, None, &message, now)
+            .await?
+        {
+            info!(%federation_id, "{message}");
+        }
+
+        Ok(())
+    }
+
+    /// Runs a query returning the median vote of each guardian with enough
+    /// votes in the last [`WALLET_VOTE_WINDOW_SESSIONS`]
+    async fn median_wallet_votes(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        sql: &str,
+    ) -> anyhow::Result<BTreeMap<PeerId, i64>> {
+        #[derive(Debug, FromRow)]
+        struct MedianVote {
+            proposer: i32,
+            median_vote: i64,
+        }
+
+        Ok(query::<MedianVote>(
+            dbtx,
+            sql,
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &WALLET_VOTE_WINDOW_SESSIONS,
+                &MIN_WALLET_VOTES,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|vote| (PeerId::from(vote.proposer as u16), vote.median_vote))
+        .collect())
+    }
+
+    /// Records an alert unless the same alert was already raised recently (see
+    /// [`dedup_window_hours`]) and queues notifications for all matching alert
+    /// rules in the same transaction. Returns `true` if a new alert was
+    /// recorded.
+    pub(super) async fn raise_alert(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        kind: AlertKind,
+        guardian_id: Option<PeerId>,
+        message: &str,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<bool> {
+        let Some(alert_id) = dbtx
+            .query_opt(
+                // language=postgresql
+                "INSERT INTO alert_events (federation_id, kind, guardian_id, message, raised_at)
+                 SELECT $1::bytea, $2::text, $3::integer, $4::text, $5::timestamp
+                 WHERE NOT EXISTS (SELECT 1
+                                   FROM alert_events
+                                   WHERE federation_id = $1
+                                     AND kind = $2
+                                     AND guardian_id IS NOT DISTINCT FROM $3
+                                     AND raised_at > $5::timestamp - INTERVAL '1 hour' * $6::integer)
+                 RETURNING alert_id",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &alert_kind_name(kind),
+                    &guardian_id.map(|peer_id| peer_id.to_usize() as i32),
+                    &message,
+                    &now,
+                    &dedup_window_hours(kind),
+                ],
+            )
+            .await?
+            .map(|row| row.get::<_, i64>(0))
+        else {
+            return Ok(false);
+        };
+
+        self.queue_notifications(dbtx, federation_id, kind, alert_id, now)
+            .await?;
+
+        Ok(true)
+    }
+
+    pub async fn list_alerts(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<AlertEvent>> {
+        #[derive(Debug, FromRow)]
+        struct AlertRow {
+            kind: String,
+            guardian_id: Option<i32>,
+            message: String,
+            raised_at: NaiveDateTime,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        query::<AlertRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT kind, guardian_id, message, raised_at
+             FROM alert_events
+             WHERE federation_id = $1
+             ORDER BY raised_at DESC
+             LIMIT $2",
+            &[&federation_id.consensus_encode_to_vec(), &MAX_LISTED_ALERTS],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(AlertEvent {
+                kind: parse_alert_kind(&row.kind)?,
+                guardian_id: row
+                    .guardian_id
+                    .map(|guardian_id| PeerId::from(guardian_id as u16)),
+                message: row.message,
+                raised_at: row.raised_at.and_utc(),
+            })
+        })
+        .collect()
+    }
+
+    /// Returns the alert thresholds of a federation, falling back to the
+    /// defaults if none were configured
+    pub async fn alert_settings(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<AlertSettings> {
+        #[derive(Debug, FromRow)]
+        struct AlertSettingsRow {
+            latency_regression_ratio: f64,
+            latency_regression_min_ms: i32,
+            net_outflow_threshold_msat: Option<i64>,
+            net_inflow_threshold_msat: Option<i64>,
+            net_flow_window_hours: i32,
+        }
+
+        let settings = query_opt::<AlertSettingsRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT latency_regression_ratio,
+                    latency_regression_min_ms,
+                    net_outflow_threshold_msat,
+                    net_inflow_threshold_msat,
+                    net_flow_window_hours
+             FROM alert_settings
+             WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(settings
+            .map(|settings| AlertSettings {
+                latency_regression_ratio: settings.latency_regression_ratio,
+                latency_regression_min_ms: settings.latency_regression_min_ms as u32,
+                net_outflow_threshold: settings
+                    .net_outflow_threshold_msat
+                    .map(|msat| Amount::from_msats(msat as u64)),
+                net_inflow_threshold: settings
+                    .net_inflow_threshold_msat
+                    .map(|msat| Amount::from_msats(msat as u64)),
+                net_flow_window_hours: settings.net_flow_window_hours as u32,
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn set_alert_settings(
+        &self,
+        federation_id: FederationId,
+        settings: &AlertSettings,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            settings.latency_regression_ratio > 1.0,
+            "Latency regression ratio has to be above 1"
+        );
+        ensure!(
+            (1..=MAX_NET_FLOW_WINDOW_HOURS).contains(&settings.net_flow_window_hours),
+            "Net flow window has to be between 1 and {MAX_NET_FLOW_WINDOW_HOURS} hours"
+        );
+        let threshold_msat = |threshold: Option<Amount>| {
+            threshold
+                .map(|threshold| i64::try_from(threshold.msats))
+                .transpose()
+                .context("Net flow threshold too large")
+        };
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO alert_settings (federation_id,
+                                         latency_regression_ratio,
+                                         latency_regression_min_ms,
+                                         net_outflow_threshold_msat,
+                                         net_inflow_threshold_msat,
+                                         net_flow_window_hours)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (federation_id) DO UPDATE
+                 SET latency_regression_ratio   = excluded.latency_regression_ratio,
+                     latency_regression_min_ms  = excluded.latency_regression_min_ms,
+                     net_outflow_threshold_msat = excluded.net_outflow_threshold_msat,
+                     net_inflow_threshold_msat  = excluded.net_inflow_threshold_msat,
+                     net_flow_window_hours      = excluded.net_flow_window_hours",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &settings.latency_regression_ratio,
+                &i32::try_from(settings.latency_regression_min_ms)
+                    .context("Minimum latency too large")?,
+                &threshold_msat(settings.net_outflow_threshold)?,
+                &threshold_msat(settings.net_inflow_threshold)?,
+                &(settings.net_flow_window_hours as i32),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Alerts about slow-moving conditions are only raised once per regression
+/// window, expiring certificates, diverging wallet votes and large net flows
+/// are reminded of daily. Health alerts are
+/// already limited to transitions by [`HealthAlertTracker`] and only
+/// deduplicated against restarts.
+fn dedup_window_hours(kind: AlertKind) -> i32 {
+    match kind {
+        AlertKind::LatencyRegression | AlertKind::ObserverQuarantined => {
+            REGRESSION_WINDOW_DAYS * 24
+        }
+        AlertKind::GuardianCertificateExpiry
+        | AlertKind::WalletVoteDivergence
+        | AlertKind::LargeNetOutflow
+        | AlertKind::LargeNetInflow => 24,
+        AlertKind::GuardianOffline
+        | AlertKind::GuardianBlockLag
+        | AlertKind::GuardianSessionLag
+        | AlertKind::FederationOffline => 1,
+    }
+}
+
+pub(super) fn alert_kind_name(kind: AlertKind) -> &'static str {
+    match kind {
+        AlertKind::LatencyRegression => "latency_regression",
+        AlertKind::ObserverQuarantined => "observer_quarantined",
+        AlertKind::GuardianOffline => "guardian_offline",
+        AlertKind::GuardianBlockLag => "guardian_block_lag",
+        AlertKind::GuardianSessionLag => "guardian_session_lag",
+        AlertKind::FederationOffline => "federation_offline",
+        AlertKind::GuardianCertificateExpiry => "guardian_certificate_expiry",
+        AlertKind::WalletVoteDivergence => "wallet_vote_divergence",
+        AlertKind::LargeNetOutflow => "large_net_outflow",
+        AlertKind::LargeNetInflow => "large_net_inflow",
+    }
+}
+
+pub(super) fn parse_alert_kind(name: &str) -> anyhow::Result<AlertKind> {
+    match name {
+        "latency_regression" => Ok(AlertKind::LatencyRegression),
+        "observer_quarantined" => Ok(AlertKind::ObserverQuarantined),
+        "guardian_offline" => Ok(AlertKind::GuardianOffline),
+        "guardian_block_lag" => Ok(AlertKind::GuardianBlockLag),
+        "guardian_session_lag" => Ok(AlertKind::GuardianSessionLag),
+        "federation_offline" => Ok(AlertKind::FederationOffline),
+        "guardian_certificate_expiry" => Ok(AlertKind::GuardianCertificateExpiry),
+        "wallet_vote_divergence" => Ok(AlertKind::WalletVoteDivergence),
+        "large_net_outflow" => Ok(AlertKind::LargeNetOutflow),
+        "large_net_inflow" => Ok(AlertKind::LargeNetInflow),
+        _ => Err(anyhow::anyhow!("Unknown alert kind {name}")),
+    }
+}
+
+/// Returns the alert to raise if the net flow of `peg_in` and `peg_out`
+/// exceeds one of the thresholds in `settings`
+fn net_flow_alert(
+    peg_in: Amount,
+    peg_out: Amount,
+    settings: &AlertSettings,
+) -> Option<(AlertKind, String)> {
+    let window = settings.net_flow_window_hours;
+    if let Some(threshold) = settings.net_outflow_threshold {
+        if peg_out > peg_in && peg_out - peg_in > threshold {
+            return Some((
+                AlertKind::LargeNetOutflow,
+                format!(
+                    "Net outflow of {} in the last {window}h exceeds {threshold} (peg-ins {peg_in}, peg-outs {peg_out})",
+                    peg_out - peg_in
+                ),
+            ));
+        }
+    }
+    if let Some(threshold) = settings.net_inflow_threshold {
+        if peg_in > peg_out && peg_in - peg_out > threshold {
+            return Some((
+                AlertKind::LargeNetInflow,
+                format!(
+                    "Net inflow of {} in the last {window}h exceeds {threshold} (peg-ins {peg_in}, peg-outs {peg_out})",
+                    peg_in - peg_out
+                ),
+            ));
+        }
+    }
+    None
+}
+
+/// Returns the guardians whose vote `diverges` from the median of all
+/// guardians' votes along with their vote and the median. At least three
+/// guardians have to vote for a majority to define what's normal.
+fn divergent_votes(
+    votes: &BTreeMap<PeerId, i64>,
+    diverges: impl Fn(i64, i64) -> bool,
+) -> Vec<(PeerId, i64, i64)> {
+    if votes.len() < 3 {
+        return vec![];
+    }
+
+    let mut sorted_votes = votes.values().copied().collect::<Vec<_>>();
+    sorted_votes.sort_unstable();
+    let median = sorted_votes[sorted_votes.len() / 2];
+
+    votes
+        .iter()
+        .filter(|(_, &vote)| diverges(vote, median))
+        .map(|(&peer_id, &vote)| (peer_id, vote, median))
+        .collect()
+}
+
+/// Result of checking one guardian during a health check
+#[derive(Debug, Clone)]
+pub(super) struct GuardianCheck {
+    pub(super) peer_id: PeerId,
+    pub(super) online: bool,
+    pub(super) block_height: Option<u32>,
+    pub(super) session_count: Option<u64>,
+}
+
+/// Health alert conditions met by a federation, keyed by the guardian they're
+/// about (`None` for federation-wide ones) and alert kind
+pub(super) type HealthConditions = BTreeMap<(Option<PeerId>, AlertKind), String>;
+
+/// Evaluates the guardians' latest health checks. Lag is only checked for
+/// guardians that are online.
+pub(super) fn health_conditions(checks: &[GuardianCheck], chain_tip: u32) -> HealthConditions {
+    let mut conditions = HealthConditions::new();
+    let max_session = checks
+        .iter()
+        .filter_map(|check| check.session_count)
+        .max()
+        .unwrap_or_default();
+
+    for check in checks {
+        let peer_id = check.peer_id;
+        if !check.online {
+            conditions.insert(
+                (Some(peer_id), AlertKind::GuardianOffline),
+                format!("Guardian {peer_id} stopped responding"),
+            );
+            continue;
+        }
+
+        if let Some(block_height) = check.block_height {
+            let lag = chain_tip.saturating_sub(block_height);
+            if lag > MAX_BLOCK_LAG {
+                conditions.insert(
+                    (Some(peer_id), AlertKind::GuardianBlockLag),
+                    format!("Guardian {peer_id} is {lag} blocks behind the chain tip"),
+                );
+            }
+        }
+
+        if let Some(session_count) = check.session_count {
+            let lag = max_session.saturating_sub(session_count);
+            if lag > MAX_SESSION_LAG {
+                conditions.insert(
+                    (Some(peer_id), AlertKind::GuardianSessionLag),
+                    format!("Guardian {peer_id} is {lag} sessions behind the other guardians"),
+                );
+            }
+        }
+    }
+
+    // Single guardian federations are already covered by the guardian alert
+    let online = checks.iter().filter(|check| check.online).count();
+    let threshold = NumPeers::from(checks.len()).threshold();
+    if checks.len() > 1 && online < threshold {
+        conditions.insert(
+            (None, AlertKind::FederationOffline),
+            format!(
+                "Only {online} of {} guardians are responding, the federation can't reach consensus",
+                checks.len()
+            ),
+        );
+    }
+
+    conditions
+}
+
+/// Turns the conditions of consecutive health checks into alerts. A condition
+/// has to persist for [`HEALTH_ALERT_CONFIRMATIONS`] checks before an alert is
+/// raised, so short hiccups don't wake anyone up, and it has to clear before
+/// it can raise another alert.
+#[derive(Debug, Default)]
+pub(super) struct HealthAlertTracker {
+    streaks: BTreeMap<(Option<PeerId>, AlertKind), u32>,
+}
+
+impl HealthAlertTracker {
+    /// Returns the conditions that just became alerts
+    pub(super) fn update(
+        &mut self,
+        conditions: HealthConditions,
+    ) -> Vec<((Option<PeerId>, AlertKind), String)> {
+        self.streaks.retain(|key, _| conditions.contains_key(key));
+
+        conditions
+            .into_iter()
+            .filter(|(key, _)| {
+                let streak = self.streaks.entry(*key).or_default();
+                *streak += 1;
+                *streak == HEALTH_ALERT_CONFIRMATIONS
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::{Amount, PeerId};
+    use fmo_api_types::{AlertKind, AlertSettings};
+
+    use super::{
+        divergent_votes, health_conditions, net_flow_alert, GuardianCheck, HealthAlertTracker,
+        HEALTH_ALERT_CONFIRMATIONS,
+    };
+
+    fn check(peer: u16, online: bool, block_height: u32, session_count: u64) -> GuardianCheck {
+        GuardianCheck {
+            peer_id: PeerId::from(peer),
+            online,
+            block_height: online.then_some(block_height),
+            session_count: online.then_some(session_count),
+        }
+    }
+
+    #[test]
+    fn test_health_conditions() {
+        let healthy = [
+            check(0, true, 100, 50),
+            check(1, true, 100, 50),
+            check(2, true, 95, 49),
+            check(3, true, 100, 50),
+        ];
+        assert!(health_conditions(&healthy, 100).is_empty());
+
+        let unhealthy = [
+            check(0, true, 100, 50),
+            check(1, false, 0, 0),
+            check(2, true, 90, 45),
+            check(3, true, 100, 50),
+        ];
+        let conditions = health_conditions(&unhealthy, 100);
+        assert_eq!(
+            conditions.keys().copied().collect::<Vec<_>>(),
+            vec![
+                (Some(PeerId::from(1)), AlertKind::GuardianOffline),
+                (Some(PeerId::from(2)), AlertKind::GuardianBlockLag),
+                (Some(PeerId::from(2)), AlertKind::GuardianSessionLag),
+            ]
+        );
+
+        let offline = [
+            check(0, true, 100, 50),
+            check(1, false, 0, 0),
+            check(2, false, 0, 0),
+            check(3, true, 100, 50),
+        ];
+        assert!(
+            health_conditions(&offline, 100).contains_key(&(None, AlertKind::FederationOffline))
+        );
+    }
+
+    #[test]
+    fn test_health_alert_tracker() {
+        let key = (Some(PeerId::from(1)), AlertKind::GuardianOffline);
+        let conditions = || [(key, "offline".to_owned())].into_iter().collect();
+        let mut tracker = HealthAlertTracker::default();
+
+        for _ in 1..HEALTH_ALERT_CONFIRMATIONS {
+            assert!(tracker.update(conditions()).is_empty());
+        }
+        assert_eq!(
+            tracker.update(conditions()),
+            vec![(key, "offline".to_owned())]
+        );
+        // Ongoing conditions don't raise alerts again
+        assert!(tracker.update(conditions()).is_empty());
+
+        // A hiccup resets the streak
+        tracker.update(Default::default());
+        assert!(tracker.update(conditions()).is_empty());
+    }
+
+    #[test]
+    fn test_divergent_votes() {
+        let diverges = |vote: i64, median: i64| vote.abs_diff(median) > 6;
+        let votes = |votes: &[i64]| {
+            votes
+                .iter()
+                .enumerate()
+                .map(|(peer, &vote)| (PeerId::from(peer as u16), vote))
+                .collect()
+        };
+
+        assert!(divergent_votes(&votes(&[100, 101, 99, 100]), diverges).is_empty());
+        assert_eq!(
+            divergent_votes(&votes(&[100, 101, 80, 100]), diverges),
+            vec![(PeerId::from(2), 80, 100)]
+        );
+        assert_eq!(
+            divergent_votes(&votes(&[100, 120, 100]), diverges),
+            vec![(PeerId::from(1), 120, 100)]
+        );
+        // Two guardians can't outvote each other
+        assert!(divergent_votes(&votes(&[100, 200]), diverges).is_empty());
+    }
+
+    #[test]
+    fn test_net_flow_alert() {
+        let btc = |btc: u64| Amount::from_sats(btc * 100_000_000);
+        let settings = AlertSettings {
+            net_outflow_threshold: Some(btc(1)),
+            ..AlertSettings::default()
+        };
+
+        assert!(net_flow_alert(btc(1), btc(2), &settings).is_none());
+        assert_eq!(
+            net_flow_alert(btc(1), btc(3), &settings).map(|(kind, _)| kind),
+            Some(AlertKind::LargeNetOutflow)
+        );
+        // Inflow alerts are disabled
+        assert!(net_flow_alert(btc(10), btc(0), &settings).is_none());
+
+        let settings = AlertSettings {
+            net_inflow_threshold: Some(btc(5)),
+            ..settings
+        };
+        assert_eq!(
+            net_flow_alert(btc(10), btc(0), &settings).map(|(kind, _)| kind),
+            Some(AlertKind::LargeNetInflow)
+        );
+    }
+}
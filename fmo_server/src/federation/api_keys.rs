@@ -0,0 +1,189 @@
+use anyhow::{anyhow, bail, ensure, Context};
+use bitcoin::hashes::{sha256, Hash};
+use chrono::NaiveDateTime;
+use fmo_api_types::{ApiKey, ApiKeyRequest, ApiScope, CreatedApiKey};
+use postgres_from_row::FromRow;
+use rand::RngCore;
+
+use crate::auth::RouteAuth;
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_opt, query_value};
+
+/// Prefix of generated keys so they can be recognized, e.g. by secret scanners
+const API_KEY_PREFIX: &str = "fmo_";
+
+#[derive(Debug, FromRow)]
+struct ApiKeyIdRow {
+    id: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct ApiKeyRow {
+    id: i64,
+    name: String,
+    scopes: Vec<String>,
+    created_at: NaiveDateTime,
+    last_used: Option<NaiveDateTime>,
+}
+
+impl FederationObserver {
+    /// Checks that `bearer_token` is the admin token or, unless the route is
+    /// admin-only, an API key with the required scope
+    pub async fn authorize(&self, bearer_token: &str, route_auth: RouteAuth) -> anyhow::Result<()> {
+        if self.is_admin_token(bearer_token) {
+            return Ok(());
+        }
+        let scope = match route_auth {
+            RouteAuth::Public => return Ok(()),
+            RouteAuth::Admin => bail!("Only the admin token is allowed"),
+            RouteAuth::Scope(scope) => scope,
+        };
+
+        self.api_key_id(bearer_token, scope).await?;
+
+        Ok(())
+    }
+
+    /// Id of the API key `bearer_token` if it has `scope`, e.g. to attribute
+    /// data to the client using it
+    pub async fn api_key_id(&self, bearer_token: &str, scope: ApiScope) -> anyhow::Result<u64> {
+        let id = query_opt::<ApiKeyIdRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE api_keys SET last_used = $3 WHERE key_hash = $1 AND $2 = ANY (scopes) RETURNING id",
+            &[
+                &hash_api_key(bearer_token),
+                &scope.name(),
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?
+        .with_context(|| format!("Invalid API key or missing scope {}", scope.name()))?
+        .id;
+
+        Ok(id as u64)
+    }
+
+    pub async fn list_api_keys(&self) -> anyhow::Result<Vec<ApiKey>> {
+        query::<ApiKeyRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT id, name, scopes, created_at, last_used FROM api_keys ORDER BY id",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ApiKey {
+                id: row.id as u64,
+                name: row.name,
+                scopes: parse_scopes(&row.scopes)?,
+                created_at: row.created_at.and_utc(),
+                last_used: row.last_used.map(|last_used| last_used.and_utc()),
+            })
+        })
+        .collect()
+    }
+
+    /// Generates a new key, only its hash is stored
+    pub async fn create_api_key(&self, request: &ApiKeyRequest) -> anyhow::Result<CreatedApiKey> {
+        validate_api_key_request(request)?;
+
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let key = format!("{API_KEY_PREFIX}{}", hex::encode(secret));
+
+        let id = query_value::<i64>(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO api_keys (name, key_hash, scopes, created_at)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id",
+            &[
+                &request.name,
+                &hash_api_key(&key),
+                &scope_names(&request.scopes),
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+
+        Ok(CreatedApiKey { id: id as u64, key })
+    }
+
+    /// Changes the name and scopes of a key, the key itself stays valid
+    pub async fn update_api_key(&self, id: u64, request: &ApiKeyRequest) -> anyhow::Result<()> {
+        validate_api_key_request(request)?;
+
+        let updated = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE api_keys SET name = $2, scopes = $3 WHERE id = $1",
+            &[&(id as i64), &request.name, &scope_names(&request.scopes)],
+        )
+        .await?;
+        ensure!(updated > 0, "API key doesn't exist");
+
+        Ok(())
+    }
+
+    pub async fn delete_api_key(&self, id: u64) -> anyhow::Result<()> {
+        let deleted = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM api_keys WHERE id = $1",
+            &[&(id as i64)],
+        )
+        .await?;
+        ensure!(deleted > 0, "API key doesn't exist");
+
+        Ok(())
+    }
+}
+
+fn hash_api_key(key: &str) -> Vec<u8> {
+    sha256::Hash::hash(key.as_bytes()).to_byte_array().to_vec()
+}
+
+fn validate_api_key_request(request: &ApiKeyRequest) -> anyhow::Result<()> {
+    ensure!(!request.name.trim().is_empty(), "API key name is empty");
+    ensure!(
+        !request.scopes.is_empty(),
+        "API key needs at least one scope"
+    );
+    Ok(())
+}
+
+fn scope_names(scopes: &[ApiScope]) -> Vec<&'static str> {
+    let mut names = scopes.iter().map(|scope| scope.name()).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+fn parse_scopes(names: &[String]) -> anyhow::Result<Vec<ApiScope>> {
+    names
+        .iter()
+        .map(|name| ApiScope::from_name(name).ok_or_else(|| anyhow!("Unknown API scope {name}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use fmo_api_types::ApiScope;
+
+    use super::{parse_scopes, scope_names};
+
+    #[test]
+    fn test_scope_names_roundtrip() {
+        let names = scope_names(&[ApiScope::Nostr, ApiScope::Query, ApiScope::Nostr]);
+        assert_eq!(names, vec!["nostr", "query"]);
+
+        let names = names.into_iter().map(str::to_owned).collect::<Vec<_>>();
+        assert_eq!(
+            parse_scopes(&names).unwrap(),
+            vec![ApiScope::Nostr, ApiScope::Query]
+        );
+        assert!(parse_scopes(&["admin".to_owned()]).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{anyhow, Context};
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use fedimint_core::config::FederationId;
+use fmo_api_types::{FederationActivity, FederationRating};
+use resvg::{tiny_skia, usvg};
+
+use crate::AppState;
+
+const CARD_WIDTH: u32 = 1200;
+const CARD_HEIGHT: u32 = 630;
+
+/// Renders a PNG share card for a federation, used as OpenGraph image when
+/// links to the federation page are shared
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/card.png",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Share card image", content_type = "image/png"))
+)]
+pub(super) async fn get_federation_card(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<impl IntoResponse> {
+    let observer = &state.federation_observer;
+    let federation = observer
+        .get_federation(federation_id)
+        .await?
+        .context("Federation doesn't exist")?;
+
+    let card = FederationCard {
+        name: federation
+            .config
+            .global
+            .meta
+            .get("federation_name")
+            .cloned()
+            .unwrap_or_else(|| federation_id.to_string()),
+        rating: observer.federation_rating(federation_id).await?,
+        assets_msat: observer.get_federation_assets(federation_id).await?.msats,
+        last_7d_activity: observer.federation_activity(federation_id, 7).await?,
+    };
+
+    let png = tokio::task::spawn_blocking(move || card.render_png()).await??;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, "public, max-age=3600"),
+        ],
+        png,
+    ))
+}
+
+struct FederationCard {
+    name: String,
+    rating: FederationRating,
+    assets_msat: u64,
+    last_7d_activity: Vec<FederationActivity>,
+}
+
+impl FederationCard {
+    fn render_png(&self) -> anyhow::Result<Vec<u8>> {
+        let options = usvg::Options {
+            fontdb: fonts(),
+            font_family: "DejaVu Sans".to_owned(),
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_str(&self.to_svg(), &options)?;
+
+        let mut pixmap =
+            tiny_skia::Pixmap::new(CARD_WIDTH, CARD_HEIGHT).context("Invalid card dimensions")?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+        pixmap
+            .encode_png()
+            .map_err(|e| anyhow!("Failed to encode card: {e}"))
+    }
+
+    fn to_svg(&self) -> String {
+        const CHART_X: u32 = 700;
+        const CHART_Y: u32 = 220;
+        const CHART_WIDTH: u32 = 420;
+        const CHART_HEIGHT: u32 = 260;
+
+        let rating = match self.rating.avg {
+            Some(avg) => format!("{avg:.1} / 5 ({} votes)", self.rating.count),
+            None => "No ratings yet".to_owned(),
+        };
+        let assets = format!("{:.8} BTC", self.assets_msat as f64 / 100_000_000_000f64);
+
+        let max_transactions = self
+            .last_7d_activity
+            .iter()
+            .map(|activity| activity.num_transactions)
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let bar_slot = CHART_WIDTH / self.last_7d_activity.len().max(1) as u32;
+        let mut bars = String::new();
+        for (idx, activity) in self.last_7d_activity.iter().enumerate() {
+            let height = (activity.num_transactions * u64::from(CHART_HEIGHT) / max_transactions)
+                .max(2) as u32;
+            write!(
+                bars,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" rx="4" fill="#3b82f6"/>"##,
+                CHART_X + idx as u32 * bar_slot + bar_slot / 8,
+                CHART_Y + CHART_HEIGHT - height,
+                bar_slot * 3 / 4,
+                height,
+            )
+            .expect("Writing to a string can't fail");
+        }
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{CARD_HEIGHT}">
+    <rect width="100%" height="100%" fill="#111827"/>
+    <text x="80" y="140" font-size="64" font-weight="bold" fill="#ffffff">{name}</text>
+    <text x="80" y="250" font-size="28" fill="#9ca3af">Total Assets</text>
+    <text x="80" y="300" font-size="44" fill="#ffffff">{assets}</text>
+    <text x="80" y="390" font-size="28" fill="#9ca3af">Recommendations</text>
+    <text x="80" y="440" font-size="44" fill="#ffffff">{rating}</text>
+    <text x="{CHART_X}" y="{chart_label_y}" font-size="28" fill="#9ca3af">Transactions (7d)</text>
+    {bars}
+    <text x="80" y="570" font-size="28" fill="#6b7280">Fedimint Observer</text>
+</svg>"##,
+            name = xml_escape(&truncate(&self.name, 28)),
+            assets = xml_escape(&assets),
+            rating = xml_escape(&rating),
+            chart_label_y = CHART_Y - 30,
+        )
+    }
+}
+
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+
+    FONTS
+        .get_or_init(|| {
+            let mut fontdb = usvg::fontdb::Database::new();
+            fontdb.load_system_fonts();
+            if let Ok(fonts_dir) = dotenv::var("FO_CARD_FONTS_DIR") {
+                fontdb.load_fonts_dir(fonts_dir);
+            }
+            Arc::new(fontdb)
+        })
+        .clone()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_owned()
+    } else {
+        let mut truncated = text.chars().take(max_chars - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
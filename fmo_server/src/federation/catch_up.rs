@@ -0,0 +1,317 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{CatchUpProgress, CatchUpState};
+use tokio::sync::Notify;
+
+use crate::federation::observer::FederationObserver;
+use crate::usage::UsageTracker;
+use crate::util::query_value;
+
+/// Queued observers re-check their turn at least this often, since changing
+/// usage stats can reorder the queue without any observer finishing
+const RECHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Limits how many history observers catch up at the same time, so after
+/// downtime the most viewed and most active federations are synced first
+/// instead of all of them competing for the database and network
+#[derive(Debug, Clone)]
+pub(super) struct CatchUpScheduler {
+    max_catching_up: usize,
+    usage_tracker: Option<UsageTracker>,
+    observers: Arc<Mutex<BTreeMap<FederationId, ObserverEntry>>>,
+    slot_freed: Arc<Notify>,
+}
+
+#[derive(Debug, Clone)]
+struct ObserverEntry {
+    state: CatchUpState,
+    /// Transactions of the federation's last week when the observer started
+    recent_transactions: u64,
+    next_session: u64,
+    session_count: u64,
+    since: DateTime<Utc>,
+}
+
+/// Registration of a running history observer, removed when dropped
+#[derive(Debug)]
+pub(super) struct CatchUpGuard {
+    scheduler: CatchUpScheduler,
+    federation_id: FederationId,
+}
+
+impl CatchUpScheduler {
+    pub(super) fn new(max_catching_up: usize) -> CatchUpScheduler {
+        CatchUpScheduler {
+            max_catching_up,
+            usage_tracker: None,
+            observers: Default::default(),
+            slot_freed: Default::default(),
+        }
+    }
+
+    /// Prioritizes federations by their API requests in addition to their
+    /// transaction volume
+    pub(super) fn with_usage_tracker(self, usage_tracker: UsageTracker) -> CatchUpScheduler {
+        CatchUpScheduler {
+            usage_tracker: Some(usage_tracker),
+            ..self
+        }
+    }
+
+    /// Registers an observer that continues at `next_session` of a federation
+    /// that has `session_count` sessions. Observers that are behind have to
+    /// wait for their turn before fetching sessions.
+    pub(super) fn register(
+        &self,
+        federation_id: FederationId,
+        next_session: u64,
+        session_count: u64,
+        recent_transactions: u64,
+    ) -> CatchUpGuard {
+        let state = if next_session >= session_count {
+            CatchUpState::Live
+        } else {
+            CatchUpState::Queued
+        };
+        self.observers.lock().expect("Lock poisoned").insert(
+            federation_id,
+            ObserverEntry {
+                state,
+                recent_transactions,
+                next_session,
+                session_count,
+                since: Utc::now(),
+            },
+        );
+
+        CatchUpGuard {
+            scheduler: self.clone(),
+            federation_id,
+        }
+    }
+
+    fn usage(&self) -> BTreeMap<FederationId, u64> {
+        self.usage_tracker
+            .as_ref()
+            .map(UsageTracker::federation_requests)
+            .unwrap_or_default()
+    }
+
+    /// Moves the observer out of the queue if it's next in line and a slot
+    /// is free, returns whether it may sync
+    fn try_start(&self, federation_id: FederationId) -> bool {
+        let usage = self.usage();
+        let mut observers = self.observers.lock().expect("Lock poisoned");
+        match observers.get(&federation_id).map(|entry| entry.state) {
+            Some(CatchUpState::Queued) => {}
+            _ => return true,
+        }
+        if next_in_line(&observers, &usage, self.max_catching_up) != Some(federation_id) {
+            return false;
+        }
+
+        let entry = observers
+            .get_mut(&federation_id)
+            .expect("Checked to be queued above");
+        entry.state = CatchUpState::CatchingUp;
+        entry.since = Utc::now();
+        drop(observers);
+
+        // The next queued observer may be able to start as well
+        self.slot_freed.notify_waiters();
+        true
+    }
+
+    /// All running history observers, the ones catching up first followed by
+    /// the queue in the order it will be processed
+    fn progress(&self) -> Vec<CatchUpProgress> {
+        let usage = self.usage();
+        let mut progress = self
+            .observers
+            .lock()
+            .expect("Lock poisoned")
+            .iter()
+            .map(|(federation_id, entry)| CatchUpProgress {
+                federation_id: *federation_id,
+                state: entry.state,
+                priority: priority(entry, usage.get(federation_id).copied()),
+                next_session: entry.next_session,
+                session_count: entry.session_count,
+                since: entry.since,
+            })
+            .collect::<Vec<_>>();
+        progress.sort_by_key(|progress| {
+            let state_order = match progress.state {
+                CatchUpState::CatchingUp => 0,
+                CatchUpState::Queued => 1,
+                CatchUpState::Live => 2,
+            };
+            (state_order, Reverse(progress.priority), progress.since)
+        });
+
+        progress
+    }
+}
+
+impl CatchUpGuard {
+    /// Waits until the observer is next in line and fewer than the maximum
+    /// number of observers are catching up, returns right away if it isn't
+    /// behind
+    pub(super) async fn wait_turn(&self) {
+        loop {
+            // Created before checking so a slot freed in between isn't missed
+            let slot_freed = self.scheduler.slot_freed.notified();
+            if self.scheduler.try_start(self.federation_id) {
+                return;
+            }
+            let _ = tokio::time::timeout(RECHECK_INTERVAL, slot_freed).await;
+        }
+    }
+
+    /// Records that all sessions before `next_session` were committed, frees
+    /// the observer's slot once it caught up
+    pub(super) fn record_progress(&self, next_session: u64) {
+        let mut observers = self.scheduler.observers.lock().expect("Lock poisoned");
+        let Some(entry) = observers.get_mut(&self.federation_id) else {
+            return;
+        };
+        entry.next_session = next_session;
+        if entry.state != CatchUpState::CatchingUp || next_session < entry.session_count {
+            return;
+        }
+
+        entry.state = CatchUpState::Live;
+        entry.since = Utc::now();
+        drop(observers);
+        self.scheduler.slot_freed.notify_waiters();
+    }
+}
+
+impl Drop for CatchUpGuard {
+    fn drop(&mut self) {
+        self.scheduler
+            .observers
+            .lock()
+            .expect("Lock poisoned")
+            .remove(&self.federation_id);
+        self.scheduler.slot_freed.notify_waiters();
+    }
+}
+
+/// Queue position is decided by the sum of API requests since startup and
+/// transactions of the last week
+fn priority(entry: &ObserverEntry, requests: Option<u64>) -> u64 {
+    entry
+        .recent_transactions
+        .saturating_add(requests.unwrap_or(0))
+}
+
+/// Queued observer that should start catching up next, `None` if all slots
+/// are taken or nothing is queued. Ties go to the observer queued first.
+fn next_in_line(
+    observers: &BTreeMap<FederationId, ObserverEntry>,
+    usage: &BTreeMap<FederationId, u64>,
+    max_catching_up: usize,
+) -> Option<FederationId> {
+    let catching_up = observers
+        .values()
+        .filter(|entry| entry.state == CatchUpState::CatchingUp)
+        .count();
+    if catching_up >= max_catching_up {
+        return None;
+    }
+
+    observers
+        .iter()
+        .filter(|(_, entry)| entry.state == CatchUpState::Queued)
+        .max_by_key(|(federation_id, entry)| {
+            (
+                priority(entry, usage.get(federation_id).copied()),
+                Reverse(entry.since),
+            )
+        })
+        .map(|(federation_id, _)| *federation_id)
+}
+
+impl FederationObserver {
+    pub fn catch_up_progress(&self) -> Vec<CatchUpProgress> {
+        self.catch_up.progress()
+    }
+
+    /// Transactions of the last week according to the cached summary, 0 if
+    /// the federation wasn't summarized yet
+    pub(super) async fn recent_transaction_count(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<u64> {
+        Ok(query_value::<i64>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT COALESCE(SUM((day ->> 'num_transactions')::bigint), 0)::bigint
+             FROM federation_summaries,
+                  jsonb_array_elements(summary -> 'last_7d_activity') day
+             WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await? as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bitcoin::hashes::{sha256, Hash};
+    use chrono::{TimeZone, Utc};
+    use fedimint_core::config::FederationId;
+    use fmo_api_types::CatchUpState;
+    use fmo_api_types::CatchUpState::{CatchingUp, Live, Queued};
+
+    use super::{next_in_line, ObserverEntry};
+
+    fn entry(state: CatchUpState, recent_transactions: u64, since: i64) -> ObserverEntry {
+        ObserverEntry {
+            state,
+            recent_transactions,
+            next_session: 0,
+            session_count: 100,
+            since: Utc.timestamp_opt(since, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_next_in_line() {
+        let federation_id = |seed: u8| FederationId(sha256::Hash::hash(&[seed]));
+        let mut observers = BTreeMap::from([
+            (federation_id(0), entry(Live, 1000, 0)),
+            (federation_id(1), entry(Queued, 10, 1)),
+            (federation_id(2), entry(Queued, 50, 2)),
+            (federation_id(3), entry(Queued, 50, 3)),
+        ]);
+        let no_usage = BTreeMap::new();
+
+        // Most active first, ties go to the one queued first
+        assert_eq!(
+            next_in_line(&observers, &no_usage, 1),
+            Some(federation_id(2))
+        );
+
+        // API requests count towards the priority
+        let usage = BTreeMap::from([(federation_id(1), 100)]);
+        assert_eq!(next_in_line(&observers, &usage, 1), Some(federation_id(1)));
+
+        // Nothing starts while all slots are taken
+        observers.get_mut(&federation_id(2)).unwrap().state = CatchingUp;
+        assert_eq!(next_in_line(&observers, &no_usage, 1), None);
+        assert_eq!(
+            next_in_line(&observers, &no_usage, 2),
+            Some(federation_id(3))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use deadpool_postgres::Transaction;
+use fedimint_core::config::{ClientConfig, FederationId};
+use fedimint_core::encoding::Encodable;
+use fedimint_core::util::SafeUrl;
+use fedimint_core::PeerId;
+use fmo_api_types::{AlertKind, GuardianCertificate};
+use futures::future::join_all;
+use postgres_from_row::FromRow;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{
+    ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    ClientConfig as TlsClientConfig, DigitallySignedStruct, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+use tracing::info;
+
+use crate::federation::observer::FederationObserver;
+use crate::socks::connect_tcp;
+use crate::util::query;
+
+/// Certificates change rarely, checking a few times a day is plenty
+pub(super) const CERTIFICATE_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const CERTIFICATE_CHECK_TIMEOUT: Duration = Duration::from_secs(20);
+/// Certificates expiring within this many days are reported and alerted on
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+impl FederationObserver {
+    /// Stores the certificate checks of a federation's guardians and raises
+    /// alerts for certificates that are about to expire
+    pub(super) async fn record_guardian_certificates(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        checks: Vec<(PeerId, anyhow::Result<DateTime<Utc>>)>,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        for (peer_id, check) in checks {
+            let (not_after, error) = match &check {
+                Ok(not_after) => (Some(not_after.naive_utc()), None),
+                Err(e) => (None, Some(format!("{e:#}"))),
+            };
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO guardian_certificates (federation_id, guardian_id, checked_at, not_after, error)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (federation_id, guardian_id) DO UPDATE
+                     SET checked_at = excluded.checked_at,
+                         not_after  = excluded.not_after,
+                         error      = excluded.error",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &(peer_id.to_usize() as i32),
+                    &now,
+                    &not_after,
+                    &error,
+                ],
+            )
+            .await?;
+
+            let Ok(not_after) = check else {
+                continue;
+            };
+            if !is_expiring(not_after, now.and_utc()) {
+                continue;
+            }
+
+            let date = not_after.format("%Y-%m-%d");
+            let message = if not_after <= now.and_utc() {
+                format!("TLS certificate of guardian {peer_id} expired on {date}")
+            } else {
+                format!("TLS certificate of guardian {peer_id} expires on {date}")
+            };
+            if self
+                .raise_alert(
+                    dbtx,
+                    federation_id,
+                    AlertKind::GuardianCertificateExpiry,
+                    Some(peer_id),
+                    &message,
+                    now,
+                )
+                .await?
+            {
+                info!(%federation_id, "{message}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Latest certificate check of each guardian that uses TLS
+    pub(super) async fn guardian_certificates(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<BTreeMap<PeerId, GuardianCertificate>> {
+        #[derive(Debug, FromRow)]
+        struct CertificateRow {
+            guardian_id: i32,
+            checked_at: NaiveDateTime,
+            not_after: Option<NaiveDateTime>,
+            error: Option<String>,
+        }
+
+        let now = Utc::now();
+        Ok(query::<CertificateRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT guardian_id, checked_at, not_after, error
+             FROM guardian_certificates
+             WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            let expires_at = row.not_after.map(|not_after| not_after.and_utc());
+            (
+                PeerId::from(row.guardian_id as u16),
+                GuardianCertificate {
+                    checked_at: row.checked_at.and_utc(),
+                    expires_at,
+                    expiring: expires_at.is_some_and(|expires_at| is_expiring(expires_at, now)),
+                    error: row.error,
+                },
+            )
+        })
+        .collect())
+    }
+}
+
+/// Fetches the certificate expiry of all guardian endpoints using TLS
+pub(super) async fn probe_guardian_certificates(
+    config: &ClientConfig,
+) -> Vec<(PeerId, anyhow::Result<DateTime<Utc>>)> {
+    join_all(
+        config
+            .global
+            .api_endpoints
+            .iter()
+            .filter(|(_, endpoint)| endpoint.url.scheme() == "wss")
+            .map(|(&peer_id, endpoint)| async move {
+                let expiry = tokio::time::timeout(
+                    CERTIFICATE_CHECK_TIMEOUT,
+                    certificate_expiry(&endpoint.url),
+                )
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("Timed out")));
+                (peer_id, expiry)
+            }),
+    )
+    .await
+}
+
+/// Connects to `url` and returns when the certificate presented by the server
+/// expires. The certificate isn't validated, expired or otherwise invalid
+/// certificates are exactly what we are looking for.
+async fn certificate_expiry(url: &SafeUrl) -> anyhow::Result<DateTime<Utc>> {
+    let host = url.host_str().context("URL has no host")?.to_owned();
+    let port = url.port_or_known_default().context("URL has no port")?;
+
+    let stream = connect_tcp(&host, port).await?;
+
+    let provider = Arc::new(ring::default_provider());
+    let tls_config = TlsClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(host).context("Invalid server name")?;
+    let tls_stream = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, stream)
+        .await
+        .context("TLS handshake failed")?;
+
+    let certificate = tls_stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .context("Server didn't present a certificate")?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate)
+        .map_err(|e| anyhow!("Invalid certificate: {e}"))?;
+
+    DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)
+        .context("Certificate expiry out of range")
+}
+
+fn is_expiring(not_after: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    not_after - now < chrono::Duration::days(EXPIRY_WARNING_DAYS)
+}
+
+/// Accepts any certificate while still checking handshake signatures, only
+/// used to read certificates and never to exchange data
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::is_expiring;
+
+    #[test]
+    fn test_is_expiring() {
+        let now = Utc::now();
+        assert!(!is_expiring(now + Duration::days(60), now));
+        assert!(is_expiring(now + Duration::days(13), now));
+        assert!(is_expiring(now - Duration::days(1), now));
+    }
+}
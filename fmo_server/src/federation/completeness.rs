@@ -0,0 +1,130 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::NaiveDateTime;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{FederationCompleteness, SessionGap};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{query, query_one};
+use crate::AppState;
+
+/// Only the first gaps are listed, more of them hint at a bigger problem anyway
+const MAX_LISTED_GAPS: i64 = 100;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/completeness",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = FederationCompleteness))
+)]
+pub(super) async fn get_federation_completeness(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<FederationCompleteness>> {
+    Ok(state
+        .federation_observer
+        .federation_completeness(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Reports missing sessions and data the observer couldn't derive from the
+    /// stored sessions (yet)
+    pub async fn federation_completeness(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationCompleteness> {
+        #[derive(Debug, FromRow)]
+        struct CompletenessRow {
+            session_count: i64,
+            latest_session: Option<i32>,
+            earliest_time: Option<NaiveDateTime>,
+            latest_time: Option<NaiveDateTime>,
+            timed_sessions: i64,
+            undecoded_items: i64,
+            pending_on_chain_lookups: i64,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct GapRow {
+            first: i32,
+            last: i32,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+
+        let row = query_one::<CompletenessRow>(
+            &conn,
+            // language=postgresql
+            "SELECT (SELECT COUNT(*) FROM sessions WHERE federation_id = $1)           AS session_count,
+                    (SELECT MAX(session_index) FROM sessions WHERE federation_id = $1) AS latest_session,
+                    (SELECT MIN(estimated_session_timestamp)
+                     FROM session_times
+                     WHERE federation_id = $1)                                         AS earliest_time,
+                    (SELECT MAX(estimated_session_timestamp)
+                     FROM session_times
+                     WHERE federation_id = $1)                                         AS latest_time,
+                    (SELECT COUNT(*) FROM session_times WHERE federation_id = $1)      AS timed_sessions,
+                    (SELECT COUNT(*)
+                     FROM transaction_inputs
+                     WHERE federation_id = $1
+                       AND kind NOT IN ('ln', 'mint', 'wallet')) +
+                    (SELECT COUNT(*)
+                     FROM transaction_outputs
+                     WHERE federation_id = $1
+                       AND kind NOT IN ('ln', 'mint', 'wallet'))                       AS undecoded_items,
+                    (SELECT COUNT(*)
+                     FROM wallet_withdrawal_transactions wwt
+                     WHERE wwt.federation_id = $1
+                       AND NOT EXISTS (SELECT 1
+                                       FROM wallet_withdrawal_transaction_outputs wwto
+                                       WHERE wwto.on_chain_txid = wwt.on_chain_txid)) AS pending_on_chain_lookups",
+            &[&federation_id_bytes],
+        )
+        .await?;
+
+        // The virtual session -1 makes missing sessions at the start show up as gap
+        let gaps = query::<GapRow>(
+            &conn,
+            // language=postgresql
+            "SELECT session_index + 1 AS first, next_session_index - 1 AS last
+             FROM (SELECT session_index,
+                          LEAD(session_index) OVER (ORDER BY session_index) AS next_session_index
+                   FROM (SELECT session_index FROM sessions WHERE federation_id = $1
+                         UNION ALL
+                         SELECT -1) s) indices
+             WHERE next_session_index > session_index + 1
+             ORDER BY session_index
+             LIMIT $2",
+            &[&federation_id_bytes, &MAX_LISTED_GAPS],
+        )
+        .await?
+        .into_iter()
+        .map(|gap| SessionGap {
+            first: gap.first as u64,
+            last: gap.last as u64,
+        })
+        .collect();
+
+        Ok(FederationCompleteness {
+            session_count: row.session_count as u64,
+            latest_session: row.latest_session.map(|session| session as u64),
+            gaps,
+            earliest_time: row.earliest_time.map(|time| time.and_utc()),
+            latest_time: row.latest_time.map(|time| time.and_utc()),
+            untimed_sessions: (row.session_count - row.timed_sessions).max(0) as u64,
+            undecoded_items: row.undecoded_items as u64,
+            pending_on_chain_lookups: row.pending_on_chain_lookups as u64,
+        })
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{ensure, Context};
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_auth::AuthBearer;
+use chrono::{NaiveDateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{ApiScope, ExportCursor};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_opt, query_value};
+use crate::AppState;
+
+const MAX_CURSOR_NAME_LEN: usize = 64;
+/// Cursors an API key can have across all federations
+const MAX_CURSORS_PER_API_KEY: i64 = 100;
+
+/// Cursors belong to the API key with the `query` scope passed as bearer
+/// token, cursors of different keys are independent
+pub(super) async fn cursor_owner(
+    observer: &FederationObserver,
+    bearer: Option<AuthBearer>,
+) -> anyhow::Result<u64> {
+    let AuthBearer(token) = bearer.context("Cursors require an API key as bearer token")?;
+    ensure!(
+        !observer.is_admin_token(&token),
+        "The admin token can't own cursors, use an API key"
+    );
+    observer.api_key_id(&token, ApiScope::Query).await
+}
+
+fn check_cursor_name(name: &str) -> anyhow::Result<()> {
+    ensure!(
+        !name.is_empty() && name.len() <= MAX_CURSOR_NAME_LEN,
+        "Cursor names have to be between 1 and {MAX_CURSOR_NAME_LEN} characters long"
+    );
+    Ok(())
+}
+
+/// Export cursors of the API key
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/cursors",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    security(("api_key" = [])),
+    responses((status = 200, description = "Export cursors of the API key", body = Vec<ExportCursor>))
+)]
+pub(super) async fn list_export_cursors(
+    Path(federation_id): Path<FederationId>,
+    bearer: Option<AuthBearer>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<ExportCursor>>> {
+    let observer = state.federation_observer;
+    let api_key_id = cursor_owner(&observer, bearer).await?;
+    Ok(observer
+        .export_cursors(api_key_id, federation_id)
+        .await?
+        .into())
+}
+
+/// Deletes a cursor so the next export with it starts from the beginning
+#[utoipa::path(
+    delete,
+    path = "/federations/{federation_id}/cursors/{name}",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ("name" = String, Path, description = "Cursor name")),
+    security(("api_key" = [])),
+    responses((status = 200, description = "Cursor deleted"))
+)]
+pub(super) async fn delete_export_cursor(
+    Path((federation_id, name)): Path<(FederationId, String)>,
+    bearer: Option<AuthBearer>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    let observer = state.federation_observer;
+    let api_key_id = cursor_owner(&observer, bearer).await?;
+    observer
+        .delete_export_cursor(api_key_id, federation_id, &name)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, FromRow)]
+struct ExportCursorRow {
+    name: String,
+    session_index: i32,
+    item_index: i32,
+    updated_at: NaiveDateTime,
+}
+
+impl From<ExportCursorRow> for ExportCursor {
+    fn from(row: ExportCursorRow) -> Self {
+        ExportCursor {
+            name: row.name,
+            session_index: row.session_index as u64,
+            item_index: row.item_index as u64,
+            updated_at: row.updated_at.and_utc(),
+        }
+    }
+}
+
+impl FederationObserver {
+    pub async fn export_cursors(
+        &self,
+        api_key_id: u64,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<ExportCursor>> {
+        Ok(query::<ExportCursorRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT name, session_index, item_index, updated_at
+             FROM export_cursors
+             WHERE api_key_id = $1 AND federation_id = $2
+             ORDER BY name",
+            &[
+                &(api_key_id as i64),
+                &federation_id.consensus_encode_to_vec(),
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(ExportCursor::from)
+        .collect())
+    }
+
+    /// Position of the last transaction exported with the cursor as session
+    /// and item index, `None` if nothing was exported with it yet
+    pub async fn export_cursor_position(
+        &self,
+        api_key_id: u64,
+        federation_id: FederationId,
+        name: &str,
+    ) -> anyhow::Result<Option<(i32, i32)>> {
+        check_cursor_name(name)?;
+        Ok(query_opt::<ExportCursorRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT name, session_index, item_index, updated_at
+             FROM export_cursors
+             WHERE api_key_id = $1 AND federation_id = $2 AND name = $3",
+            &[
+                &(api_key_id as i64),
+                &federation_id.consensus_encode_to_vec(),
+                &name,
+            ],
+        )
+        .await?
+        .map(|row| (row.session_index, row.item_index)))
+    }
+
+    pub async fn advance_export_cursor(
+        &self,
+        api_key_id: u64,
+        federation_id: FederationId,
+        name: &str,
+        (session_index, item_index): (i32, i32),
+    ) -> anyhow::Result<()> {
+        check_cursor_name(name)?;
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO export_cursors (api_key_id, federation_id, name, session_index, item_index, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (api_key_id, federation_id, name) DO UPDATE
+                 SET session_index = excluded.session_index,
+                     item_index    = excluded.item_index,
+                     updated_at    = excluded.updated_at",
+            &[
+                &(api_key_id as i64),
+                &federation_id.consensus_encode_to_vec(),
+                &name,
+                &session_index,
+                &item_index,
+                &Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Fails if the API key can't create another cursor, checked before an
+    /// export with a new cursor starts
+    pub async fn check_cursor_limit(&self, api_key_id: u64) -> anyhow::Result<()> {
+        let cursors = query_value::<i64>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint FROM export_cursors WHERE api_key_id = $1",
+            &[&(api_key_id as i64)],
+        )
+        .await?;
+        ensure!(
+            cursors < MAX_CURSORS_PER_API_KEY,
+            "API key already has {MAX_CURSORS_PER_API_KEY} cursors, delete unused ones first"
+        );
+        Ok(())
+    }
+
+    pub async fn delete_export_cursor(
+        &self,
+        api_key_id: u64,
+        federation_id: FederationId,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        let deleted = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM export_cursors WHERE api_key_id = $1 AND federation_id = $2 AND name = $3",
+            &[
+                &(api_key_id as i64),
+                &federation_id.consensus_encode_to_vec(),
+                &name,
+            ],
+        )
+        .await?;
+        ensure!(deleted > 0, "Cursor doesn't exist");
+        Ok(())
+    }
+}
@@ -43,6 +43,14 @@ pub struct Federation {
     pub config: ClientConfig,
 }
 
+impl Federation {
+    /// Bitcoin network the federation operates on, derived from the config of
+    /// its wallet module. Also stored in the `network` column for queries.
+    pub fn network(&self) -> anyhow::Result<Option<bitcoin::Network>> {
+        crate::util::config_network(&self.config)
+    }
+}
+
 impl FromRow for Federation {
     fn from_row(row: &Row) -> Self {
         Self::try_from_row(row).expect("Decoding row failed")
@@ -67,9 +75,7 @@ impl FromRow for Federation {
 
 pub struct Transaction {
     pub txid: TransactionId,
-    #[allow(dead_code)]
     pub session_index: i32,
-    #[allow(dead_code)]
     pub item_index: i32,
     pub data: fedimint_core::transaction::Transaction,
 }
@@ -104,6 +110,37 @@ impl FromRow for crate::federation::db::Transaction {
     }
 }
 
+impl Transaction {
+    pub fn from_row_with_decoders(row: &Row, decoders: &ModuleDecoderRegistry) -> Self {
+        Self::try_from_row_with_decoders(row, decoders).expect("Decoding row failed")
+    }
+
+    pub fn try_from_row_with_decoders(
+        row: &Row,
+        decoders: &ModuleDecoderRegistry,
+    ) -> Result<Self, Error> {
+        let txid_bytes: Vec<u8> = row.try_get("txid")?;
+        let txid =
+            TransactionId::consensus_decode_vec(txid_bytes, decoders).expect("Invalid data in DB");
+
+        let session_index = row.try_get::<_, i32>("session_index")?;
+
+        let item_index = row.try_get::<_, i32>("item_index")?;
+
+        let data_bytes: Vec<u8> = row.try_get("data")?;
+        let data =
+            fedimint_core::transaction::Transaction::consensus_decode_vec(data_bytes, decoders)
+                .expect("Invalid data in DB");
+
+        Ok(crate::federation::db::Transaction {
+            txid,
+            session_index,
+            item_index,
+            data,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct SessionOutcome {
     pub session_index: i32,
@@ -0,0 +1,177 @@
+use anyhow::{ensure, Context};
+use axum::extract::{Path, Query, State};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query_value};
+use crate::AppState;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct DeleteFederationParams {
+    /// Also delete the federation's consensus history and guardian health data
+    #[serde(default)]
+    purge: bool,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/federations/{federation_id}",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), DeleteFederationParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Federation deactivated"))
+)]
+pub(super) async fn delete_observed_federation(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<DeleteFederationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .deactivate_federation(federation_id, params.purge)
+        .await?)
+}
+
+impl FederationObserver {
+    /// Stops observing a federation and hides it from federation listings. If
+    /// `purge` is set all data derived from its consensus history is deleted,
+    /// otherwise it's kept so the federation can be re-added later without
+    /// syncing from scratch.
+    pub async fn deactivate_federation(
+        &self,
+        federation_id: FederationId,
+        purge: bool,
+    ) -> anyhow::Result<()> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE federations SET active = FALSE WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        self.stop_federation_tasks(federation_id).await?;
+        info!(%federation_id, purge, "Deactivated federation");
+
+        if purge {
+            self.purge_federation_data(federation_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Marks a deactivated federation as active again, returns `false` if it
+    /// already was active
+    pub(super) async fn reactivate_federation(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<bool> {
+        let updated = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE federations SET active = TRUE WHERE federation_id = $1 AND NOT active",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(updated > 0)
+    }
+
+    pub(super) async fn is_federation_active(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<bool> {
+        query_value::<bool>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT EXISTS(SELECT 1 FROM federations WHERE federation_id = $1 AND active)",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await
+    }
+
+    /// Deletes the consensus history of a deactivated federation and all data
+    /// derived from it. Ratings, alerts and view memberships are kept. The
+    /// materialized views are cleaned up on their next refresh.
+    async fn purge_federation_data(&self, federation_id: FederationId) -> anyhow::Result<()> {
+        ensure!(
+            !self.is_federation_active(federation_id).await?,
+            "Can't purge data of an active federation"
+        );
+
+        let mut conn = self.connection().await?;
+        let dbtx = conn.transaction().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+
+        // Withdrawal details are only linked to the federation through the
+        // withdrawal transactions, so they have to go first
+        for table in [
+            "wallet_withdrawal_signatures",
+            "wallet_withdrawal_transaction_inputs",
+            "wallet_withdrawal_output_spends",
+            "wallet_withdrawal_transaction_outputs",
+        ] {
+            dbtx.execute(
+                &format!(
+                    "DELETE FROM {table}
+                     WHERE on_chain_txid IN (SELECT on_chain_txid
+                                             FROM wallet_withdrawal_transactions
+                                             WHERE federation_id = $1)"
+                ),
+                &[&federation_id_bytes],
+            )
+            .await?;
+        }
+
+        // Ordered so rows are deleted before the ones they reference
+        for table in [
+            "wallet_withdrawal_transactions",
+            "wallet_peg_ins",
+            "wallet_withdrawal_addresses",
+            "transaction_input_details",
+            "transaction_output_details",
+            "quarantined_items",
+            "processing_errors",
+            "transaction_inputs",
+            "transaction_outputs",
+            "ln_contracts",
+            "fees",
+            "mint_denominations",
+            "transactions",
+            "block_height_votes",
+            "wallet_feerate_votes",
+            "consensus_items",
+            "session_peer_stats",
+            "sessions",
+            "guardian_health",
+            "session_count_samples",
+            "incidents",
+            "health_events",
+            "health_probe_results",
+            "federation_summaries",
+            "export_cursors",
+            "observer_status",
+            "observer_checkpoints",
+            "jobs",
+        ] {
+            dbtx.execute(
+                &format!("DELETE FROM {table} WHERE federation_id = $1"),
+                &[&federation_id_bytes],
+            )
+            .await?;
+        }
+
+        dbtx.commit().await?;
+        info!(%federation_id, "Purged federation data");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::Amount;
+use fmo_api_types::MintDenomination;
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+/// Whether a mint output issued a note or a mint input redeemed one
+#[derive(Debug, Clone, Copy)]
+pub(super) enum NoteDirection {
+    Issued,
+    Redeemed,
+}
+
+/// E-cash notes issued and redeemed per denomination, smallest denomination
+/// first. Only denominations that were used at least once are included.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/mint/denominations",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Notes per denomination", body = Vec<MintDenomination>))
+)]
+pub(super) async fn get_mint_denominations(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<MintDenomination>>> {
+    Ok(state
+        .federation_observer
+        .mint_denominations(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn mint_denominations(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<MintDenomination>> {
+        #[derive(Debug, FromRow)]
+        struct DenominationRow {
+            denomination_msat: i64,
+            issued_notes: i64,
+            redeemed_notes: i64,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        Ok(query::<DenominationRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT denomination_msat,
+                    SUM(issued_notes)::bigint   AS issued_notes,
+                    SUM(redeemed_notes)::bigint AS redeemed_notes
+             FROM mint_denominations
+             WHERE federation_id = $1
+             GROUP BY denomination_msat
+             ORDER BY denomination_msat",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            mint_denomination(
+                Amount::from_msats(row.denomination_msat as u64),
+                row.issued_notes as u64,
+                row.redeemed_notes as u64,
+            )
+        })
+        .collect())
+    }
+
+    /// Counts a note of the given denomination issued or redeemed in the
+    /// session
+    pub(super) async fn record_mint_note(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        denomination: Amount,
+        direction: NoteDirection,
+    ) -> Result<(), tokio_postgres::Error> {
+        let (issued, redeemed): (i64, i64) = match direction {
+            NoteDirection::Issued => (1, 0),
+            NoteDirection::Redeemed => (0, 1),
+        };
+
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO mint_denominations VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (federation_id, session_index, denomination_msat) DO UPDATE
+                 SET issued_notes   = mint_denominations.issued_notes + excluded.issued_notes,
+                     redeemed_notes = mint_denominations.redeemed_notes + excluded.redeemed_notes",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(session_index as i32),
+                &(denomination.msats as i64),
+                &issued,
+                &redeemed,
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Outstanding notes bottom out at zero in case redemptions were counted
+/// whose issuance wasn't, e.g. because the output was quarantined
+fn mint_denomination(denomination: Amount, issued: u64, redeemed: u64) -> MintDenomination {
+    let outstanding_notes = issued.saturating_sub(redeemed);
+    MintDenomination {
+        denomination,
+        issued_notes: issued,
+        redeemed_notes: redeemed,
+        outstanding_notes,
+        outstanding_amount: denomination * outstanding_notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::Amount;
+
+    use super::mint_denomination;
+
+    #[test]
+    fn test_mint_denomination() {
+        let denomination = mint_denomination(Amount::from_msats(1024), 10, 4);
+        assert_eq!(denomination.outstanding_notes, 6);
+        assert_eq!(
+            denomination.outstanding_amount,
+            Amount::from_msats(6 * 1024)
+        );
+
+        let denomination = mint_denomination(Amount::from_msats(1024), 1, 3);
+        assert_eq!(denomination.outstanding_notes, 0);
+        assert_eq!(denomination.outstanding_amount, Amount::ZERO);
+    }
+}
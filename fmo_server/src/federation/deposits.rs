@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Txid};
+use chrono::{NaiveDate, NaiveDateTime};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fedimint_core::{Amount, TransactionId};
+use fmo_api_types::{Deposit, Page};
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{query, query_value};
+use crate::AppState;
+
+/// Number of deposits returned if the client doesn't specify a limit
+const DEFAULT_DEPOSIT_PAGE_SIZE: u64 = 100;
+const MAX_DEPOSIT_PAGE_SIZE: u64 = 1_000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct DepositListParams {
+    limit: Option<u64>,
+    /// Number of deposits to skip, counted from the most recent one
+    offset: Option<u64>,
+    /// First day (UTC) to include deposits of
+    from: Option<NaiveDate>,
+    /// Last day (UTC) to include deposits of
+    to: Option<NaiveDate>,
+}
+
+/// On-chain deposits claimed by a federation's transactions, most recent
+/// first. Days are matched against the estimated session time of the claiming
+/// transaction.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/deposits",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), DepositListParams),
+    responses((status = 200, description = "Deposits, most recent first", body = DepositPage))
+)]
+pub(super) async fn list_deposits(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<DepositListParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Page<Deposit>>> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_DEPOSIT_PAGE_SIZE)
+        .min(MAX_DEPOSIT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    Ok(state
+        .federation_observer
+        .federation_deposits(federation_id, params.from, params.to, limit, offset)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn federation_deposits(
+        &self,
+        federation_id: FederationId,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Page<Deposit>> {
+        #[derive(Debug, FromRow)]
+        struct DepositRow {
+            on_chain_txid: Vec<u8>,
+            on_chain_vout: i32,
+            address: String,
+            amount_msat: i64,
+            txid: Vec<u8>,
+            session_index: i32,
+            estimated_session_timestamp: Option<NaiveDateTime>,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+
+        // language=postgresql
+        let filter = "FROM wallet_peg_ins wpi
+                          JOIN transactions t ON t.federation_id = wpi.federation_id AND t.txid = wpi.txid
+                          LEFT JOIN session_times st
+                                    ON st.federation_id = t.federation_id AND st.session_index = t.session_index
+                      WHERE wpi.federation_id = $1
+                        AND ($2::date IS NULL OR DATE(st.estimated_session_timestamp) >= $2)
+                        AND ($3::date IS NULL OR DATE(st.estimated_session_timestamp) <= $3)";
+
+        let total = query_value::<i64>(
+            &conn,
+            &format!("SELECT COUNT(*) {filter}"),
+            &[&federation_id_bytes, &from, &to],
+        )
+        .await?;
+
+        let rows = query::<DepositRow>(
+            &conn,
+            &format!(
+                "SELECT wpi.on_chain_txid,
+                        wpi.on_chain_vout,
+                        wpi.address,
+                        wpi.amount_msat,
+                        wpi.txid,
+                        t.session_index,
+                        st.estimated_session_timestamp
+                 {filter}
+                 ORDER BY t.session_index DESC, t.item_index DESC, wpi.in_index DESC
+                 LIMIT $4 OFFSET $5"
+            ),
+            &[
+                &federation_id_bytes,
+                &from,
+                &to,
+                &(limit as i64),
+                &(offset as i64),
+            ],
+        )
+        .await?;
+
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                Ok(Deposit {
+                    on_chain_txid: Txid::from_slice(&row.on_chain_txid)?,
+                    on_chain_vout: row.on_chain_vout as u32,
+                    address: Address::from_str(&row.address)?,
+                    amount: Amount::from_msats(row.amount_msat as u64),
+                    federation_txid: TransactionId::consensus_decode_vec(
+                        row.txid,
+                        &Default::default(),
+                    )
+                    .context("Invalid transaction id in DB")?,
+                    session_index: row.session_index as u64,
+                    estimated_time: row.estimated_session_timestamp.map(|time| time.and_utc()),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Page {
+            items,
+            total: total as u64,
+            offset,
+            limit,
+        })
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::task::sleep;
+use fedimint_core::Amount;
+use fmo_api_types::{FederationHealth, FederationSummary};
+use nostr_sdk::{EventBuilder, Keys, RelaySendOptions, Tag, TagStandard};
+use postgres_from_row::FromRow;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_one, query_value};
+
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of federations listed with their volume
+const TOP_FEDERATIONS_BY_VOLUME: i64 = 10;
+
+const DEFAULT_DIGEST_TEMPLATE: &str = "Fedimint Observer daily digest for {date}
+
+Observed federations: {federations}
+New federations: {new_federations}
+Transactions: {tx_count}
+Volume: {tx_volume}
+Volume by federation: {federation_volumes}
+Health changes: {health_changes}
+Outages: {outages}";
+
+/// Operator settings for the daily digest, publishing is disabled if neither a
+/// nostr key nor a webhook is configured
+pub(super) struct DigestConfig {
+    keys: Option<Keys>,
+    webhook_url: Option<reqwest::Url>,
+    template: String,
+}
+
+impl DigestConfig {
+    /// Reads the signing key from `FO_DIGEST_NOSTR_KEY` (hex or bech32), the
+    /// webhook from `FO_DIGEST_WEBHOOK_URL` and an optional template file from
+    /// `FO_DIGEST_TEMPLATE`
+    pub(super) fn from_env() -> anyhow::Result<Option<DigestConfig>> {
+        let keys = dotenv::var("FO_DIGEST_NOSTR_KEY")
+            .ok()
+            .map(|secret_key| Keys::parse(secret_key.trim()).context("Invalid FO_DIGEST_NOSTR_KEY"))
+            .transpose()?;
+        let webhook_url = dotenv::var("FO_DIGEST_WEBHOOK_URL")
+            .ok()
+            .map(|url| reqwest::Url::parse(url.trim()).context("Invalid FO_DIGEST_WEBHOOK_URL"))
+            .transpose()?;
+        if keys.is_none() && webhook_url.is_none() {
+            return Ok(None);
+        }
+
+        let template = match dotenv::var("FO_DIGEST_TEMPLATE") {
+            Ok(path) => std::fs::read_to_string(&path)
+                .with_context(|| format!("Could not read digest template from {path}"))?,
+            Err(_) => DEFAULT_DIGEST_TEMPLATE.to_owned(),
+        };
+
+        Ok(Some(DigestConfig {
+            keys,
+            webhook_url,
+            template,
+        }))
+    }
+}
+
+/// Network statistics of a single day
+#[derive(Debug, Clone)]
+struct DigestStats {
+    date: NaiveDate,
+    federations: usize,
+    new_federations: Vec<String>,
+    tx_count: u64,
+    tx_volume: Amount,
+    /// Federations with the highest volume of the day, in descending order
+    federation_volumes: Vec<(String, Amount)>,
+    /// Federation wide health alerts raised during the day
+    health_changes: Vec<String>,
+    /// Federations that are currently offline or degraded
+    outages: Vec<String>,
+}
+
+impl FederationObserver {
+    /// Publishes a summary of the previous day once per day
+    pub(super) async fn publish_daily_digests(self, config: DigestConfig) {
+        info!(
+            public_key = ?config.keys.as_ref().map(|keys| keys.public_key()),
+            webhook = config.webhook_url.is_some(),
+            "Publishing daily digests"
+        );
+
+        loop {
+            if let Err(e) = self.publish_digest_if_due(&config).await {
+                warn!("Error while publishing daily digest: {e:?}");
+            }
+            sleep(DIGEST_CHECK_INTERVAL).await;
+        }
+    }
+
+    async fn publish_digest_if_due(&self, config: &DigestConfig) -> anyhow::Result<()> {
+        let date = chrono::Utc::now()
+            .date_naive()
+            .pred_opt()
+            .expect("We are far from the beginning of time");
+
+        let already_published = query_value::<bool>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT EXISTS(SELECT 1 FROM nostr_digests WHERE date = $1)",
+            &[&date],
+        )
+        .await?;
+        if already_published {
+            return Ok(());
+        }
+
+        let content = render_digest(&config.template, &self.digest_stats(date).await?);
+
+        // The note is replaceable, publishing it again after the webhook
+        // failed doesn't create duplicates
+        let event_id = match &config.keys {
+            Some(keys) => {
+                let tags = [
+                    Tag::identifier(format!("fmo-digest-{date}")),
+                    Tag::from_standardized(TagStandard::Title(format!(
+                        "Fedimint Observer daily digest for {date}"
+                    ))),
+                ];
+                let event =
+                    EventBuilder::long_form_text_note(content.clone(), tags).to_event(keys)?;
+                self.nostr_relay_client()
+                    .await?
+                    .send_event(
+                        event.clone(),
+                        RelaySendOptions::default().timeout(Some(Duration::from_secs(5))),
+                    )
+                    .await?;
+                Some(event.id)
+            }
+            None => None,
+        };
+
+        if let Some(webhook_url) = &config.webhook_url {
+            self.http_client()
+                .post(webhook_url.clone())
+                .json(&json!({ "date": date, "text": content }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO nostr_digests (date, event_id, content, published_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT DO NOTHING",
+            &[
+                &date,
+                &event_id.map(|event_id| event_id.to_bytes().to_vec()),
+                &content,
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+        info!(%date, event_id = ?event_id, "Published daily digest");
+
+        Ok(())
+    }
+
+    async fn digest_stats(&self, date: NaiveDate) -> anyhow::Result<DigestStats> {
+        #[derive(Debug, FromRow)]
+        struct DailyActivityRow {
+            tx_count: i64,
+            tx_volume: i64,
+        }
+
+        let activity = query_one::<DailyActivityRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT COUNT(DISTINCT (t.federation_id, t.txid))::bigint AS tx_count,
+                    COALESCE(SUM(ti.amount_msat), 0)::bigint        AS tx_volume
+             FROM transactions t
+                      JOIN session_times st
+                           ON t.federation_id = st.federation_id AND t.session_index = st.session_index
+                      LEFT JOIN transaction_inputs ti
+                                ON t.federation_id = ti.federation_id AND t.txid = ti.txid
+             WHERE DATE(st.estimated_session_timestamp) = $1",
+            &[&date],
+        )
+        .await?;
+
+        #[derive(Debug, FromRow)]
+        struct FederationVolumeRow {
+            federation_id: Vec<u8>,
+            tx_volume: i64,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct HealthChangeRow {
+            federation_id: Vec<u8>,
+            message: String,
+        }
+
+        let federation_volumes = query::<FederationVolumeRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT t.federation_id,
+                    COALESCE(SUM(ti.amount_msat), 0)::bigint AS tx_volume
+             FROM transactions t
+                      JOIN session_times st
+                           ON t.federation_id = st.federation_id AND t.session_index = st.session_index
+                      JOIN transaction_inputs ti
+                           ON t.federation_id = ti.federation_id AND t.txid = ti.txid
+             WHERE DATE(st.estimated_session_timestamp) = $1
+             GROUP BY t.federation_id
+             ORDER BY tx_volume DESC
+             LIMIT $2",
+            &[&date, &TOP_FEDERATIONS_BY_VOLUME],
+        )
+        .await?;
+
+        let health_changes = query::<HealthChangeRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT federation_id, message
+             FROM alert_events
+             WHERE DATE(raised_at) = $1
+               AND kind IN ('federation_offline', 'guardian_offline')
+             ORDER BY raised_at",
+            &[&date],
+        )
+        .await?;
+
+        let federations = self
+            .list_federation_summaries()
+            .await?
+            .into_iter()
+            .filter(|summary| summary.shutdown.is_none())
+            .collect::<Vec<_>>();
+        let name = |summary: &FederationSummary| {
+            summary
+                .name
+                .clone()
+                .unwrap_or_else(|| summary.id.to_string())
+        };
+
+        let names = federations
+            .iter()
+            .map(|summary| (summary.id.consensus_encode_to_vec(), name(summary)))
+            .collect::<BTreeMap<_, _>>();
+        let name_by_id = |federation_id: &Vec<u8>| {
+            names
+                .get(federation_id)
+                .cloned()
+                .unwrap_or_else(|| hex::encode(federation_id))
+        };
+
+        Ok(DigestStats {
+            date,
+            federations: federations.len(),
+            new_federations: federations
+                .iter()
+                .filter(|summary| {
+                    summary
+                        .created_at
+                        .is_some_and(|created_at| created_at.date_naive() == date)
+                })
+                .map(name)
+                .collect(),
+            tx_count: activity.tx_count as u64,
+            tx_volume: Amount::from_msats(activity.tx_volume as u64),
+            federation_volumes: federation_volumes
+                .iter()
+                .map(|row| {
+                    (
+                        name_by_id(&row.federation_id),
+                        Amount::from_msats(row.tx_volume as u64),
+                    )
+                })
+                .collect(),
+            health_changes: health_changes
+                .iter()
+                .map(|row| format!("{}: {}", name_by_id(&row.federation_id), row.message))
+                .collect(),
+            outages: federations
+                .iter()
+                .filter(|summary| summary.health != FederationHealth::Online)
+                .map(name)
+                .collect(),
+        })
+    }
+}
+
+/// Fills the placeholders `{date}`, `{federations}`, `{new_federations}`,
+/// `{tx_count}`, `{tx_volume}`, `{federation_volumes}`, `{health_changes}` and
+/// `{outages}` of the operator's template
+fn render_digest(template: &str, stats: &DigestStats) -> String {
+    let list = |names: &[String]| {
+        if names.is_empty() {
+            "none".to_owned()
+        } else {
+            names.join(", ")
+        }
+    };
+
+    [
+        ("{date}", stats.date.to_string()),
+        ("{federations}", stats.federations.to_string()),
+        ("{new_federations}", list(&stats.new_federations)),
+        ("{tx_count}", stats.tx_count.to_string()),
+        ("{tx_volume}", format_sats(stats.tx_volume)),
+        (
+            "{federation_volumes}",
+            list(
+                &stats
+                    .federation_volumes
+                    .iter()
+                    .map(|(name, volume)| format!("{name} ({})", format_sats(*volume)))
+                    .collect::<Vec<_>>(),
+            ),
+        ),
+        ("{health_changes}", list(&stats.health_changes)),
+        ("{outages}", list(&stats.outages)),
+    ]
+    .into_iter()
+    .fold(template.to_owned(), |content, (placeholder, value)| {
+        content.replace(placeholder, &value)
+    })
+}
+
+fn format_sats(amount: Amount) -> String {
+    format!("{} sat", amount.sats_round_down())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use fedimint_core::Amount;
+
+    use super::{render_digest, DigestStats, DEFAULT_DIGEST_TEMPLATE};
+
+    #[test]
+    fn test_render_digest() {
+        let stats = DigestStats {
+            date: NaiveDate::from_ymd_opt(2024, 9, 1).unwrap(),
+            federations: 12,
+            new_federations: vec!["Alpha".to_owned(), "Beta".to_owned()],
+            tx_count: 345,
+            tx_volume: Amount::from_sats(1_234_567),
+            federation_volumes: vec![
+                ("Alpha".to_owned(), Amount::from_sats(1_000_000)),
+                ("Gamma".to_owned(), Amount::from_sats(234_567)),
+            ],
+            health_changes: vec![],
+            outages: vec![],
+        };
+
+        assert_eq!(
+            render_digest(DEFAULT_DIGEST_TEMPLATE, &stats),
+            "Fedimint Observer daily digest for 2024-09-01
+
+Observed federations: 12
+New federations: Alpha, Beta
+Transactions: 345
+Volume: 1234567 sat
+Volume by federation: Alpha (1000000 sat), Gamma (234567 sat)
+Health changes: none
+Outages: none"
+        );
+        assert_eq!(
+            render_digest("{tx_count} txs, {tx_count} again, {unknown}", &stats),
+            "345 txs, 345 again, {unknown}"
+        );
+    }
+}
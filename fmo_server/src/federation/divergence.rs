@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::PeerId;
+use fmo_api_types::{GuardianSessionDivergence, SessionDivergence, SessionDivergenceBucket};
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+const DEFAULT_DIVERGENCE_HISTORY_DAYS: u32 = 1;
+const MAX_DIVERGENCE_HISTORY_DAYS: u32 = 30;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct SessionDivergenceParams {
+    /// Number of days of hourly history to return, defaults to 1
+    days: Option<u32>,
+}
+
+/// Session counts reported by the guardians compared to each other and to the
+/// sessions the observer committed. Detects both the observer lagging behind
+/// and guardians serving a stale or forked history.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/sessions/divergence",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), SessionDivergenceParams),
+    responses((status = 200, description = "Success", body = SessionDivergence))
+)]
+pub(super) async fn get_session_divergence(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<SessionDivergenceParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<SessionDivergence>> {
+    Ok(state
+        .federation_observer
+        .session_divergence(
+            federation_id,
+            params.days.unwrap_or(DEFAULT_DIVERGENCE_HISTORY_DAYS),
+        )
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Stores the session counts of one health check together with the number
+    /// of sessions the observer committed so far
+    pub(super) async fn record_session_counts(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_counts: &BTreeMap<PeerId, Option<u64>>,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        let observed_session_count = self
+            .observer_checkpoint(federation_id)
+            .await?
+            .map_or(0, |last_session| last_session + 1);
+
+        for (peer_id, session_count) in session_counts {
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO session_count_samples VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &timestamp,
+                    &(peer_id.to_usize() as i32),
+                    &session_count.map(|count| count as i32),
+                    &(observed_session_count as i32),
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn session_divergence(
+        &self,
+        federation_id: FederationId,
+        days: u32,
+    ) -> anyhow::Result<SessionDivergence> {
+        #[derive(Debug, FromRow)]
+        struct LatestSampleRow {
+            time: NaiveDateTime,
+            guardian_id: i32,
+            reported_session_count: Option<i32>,
+            observed_session_count: i32,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct DivergenceBucketRow {
+            start: NaiveDateTime,
+            max_observer_lag: i64,
+            max_guardian_lag: i64,
+            max_stale_sessions: i64,
+        }
+
+        ensure!(
+            (1..=MAX_DIVERGENCE_HISTORY_DAYS).contains(&days),
+            "Days have to be between 1 and {MAX_DIVERGENCE_HISTORY_DAYS}"
+        );
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let latest = query::<LatestSampleRow>(
+            &conn,
+            // language=postgresql
+            "SELECT time, guardian_id, reported_session_count, observed_session_count
+             FROM session_count_samples
+             WHERE federation_id = $1
+               AND time = (SELECT MAX(time) FROM session_count_samples WHERE federation_id = $1)
+             ORDER BY guardian_id",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        let history = query::<DivergenceBucketRow>(
+            &conn,
+            // language=postgresql
+            "WITH checks AS (SELECT time,
+                                    MAX(observed_session_count) AS observed,
+                                    MAX(reported_session_count) AS highest,
+                                    MIN(reported_session_count) AS lowest
+                             FROM session_count_samples
+                             WHERE federation_id = $1
+                               AND time > NOW() - INTERVAL '1 day' * $2::integer
+                             GROUP BY time)
+             SELECT DATE_TRUNC('hour', time)                                 AS start,
+                    COALESCE(MAX(GREATEST(highest - observed, 0)), 0)::bigint AS max_observer_lag,
+                    COALESCE(MAX(highest - lowest), 0)::bigint                AS max_guardian_lag,
+                    COALESCE(MAX(GREATEST(observed - lowest, 0)), 0)::bigint  AS max_stale_sessions
+             FROM checks
+             GROUP BY start
+             ORDER BY start",
+            &[&federation_id.consensus_encode_to_vec(), &(days as i32)],
+        )
+        .await?
+        .into_iter()
+        .map(|row| SessionDivergenceBucket {
+            start: row.start.and_utc(),
+            max_observer_lag: row.max_observer_lag as u64,
+            max_guardian_lag: row.max_guardian_lag as u64,
+            max_stale_sessions: row.max_stale_sessions as u64,
+        })
+        .collect();
+
+        let checked_at = latest.first().map(|row| row.time.and_utc());
+        let observed_session_count = latest
+            .first()
+            .map_or(0, |row| row.observed_session_count as u64);
+        let reported = latest
+            .iter()
+            .map(|row| {
+                (
+                    PeerId::from(row.guardian_id as u16),
+                    row.reported_session_count.map(|count| count as u64),
+                )
+            })
+            .collect();
+
+        Ok(divergence(
+            checked_at,
+            observed_session_count,
+            &reported,
+            history,
+        ))
+    }
+}
+
+fn divergence(
+    checked_at: Option<DateTime<Utc>>,
+    observed_session_count: u64,
+    reported: &BTreeMap<PeerId, Option<u64>>,
+    history: Vec<SessionDivergenceBucket>,
+) -> SessionDivergence {
+    let federation_session_count = reported.values().flatten().copied().max();
+    let guardians = reported
+        .iter()
+        .map(
+            |(&guardian_id, &reported_session_count)| GuardianSessionDivergence {
+                guardian_id,
+                reported_session_count,
+                behind_federation: reported_session_count
+                    .zip(federation_session_count)
+                    .map(|(reported, highest)| highest - reported),
+                behind_observer: reported_session_count
+                    .filter(|&reported| reported < observed_session_count)
+                    .map(|reported| observed_session_count - reported),
+            },
+        )
+        .collect();
+
+    SessionDivergence {
+        checked_at,
+        observed_session_count,
+        federation_session_count,
+        observer_lag: federation_session_count
+            .map(|highest| highest.saturating_sub(observed_session_count)),
+        guardians,
+        history,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fedimint_core::PeerId;
+    use fmo_api_types::GuardianSessionDivergence;
+
+    use super::divergence;
+
+    #[test]
+    fn test_divergence() {
+        let reported = BTreeMap::from([
+            (PeerId::from(0), Some(1000)),
+            (PeerId::from(1), Some(998)),
+            (PeerId::from(2), Some(900)),
+            (PeerId::from(3), None),
+        ]);
+        let divergence = divergence(None, 995, &reported, vec![]);
+
+        assert_eq!(divergence.federation_session_count, Some(1000));
+        assert_eq!(divergence.observer_lag, Some(5));
+        assert_eq!(
+            divergence.guardians,
+            vec![
+                GuardianSessionDivergence {
+                    guardian_id: PeerId::from(0),
+                    reported_session_count: Some(1000),
+                    behind_federation: Some(0),
+                    behind_observer: None,
+                },
+                GuardianSessionDivergence {
+                    guardian_id: PeerId::from(1),
+                    reported_session_count: Some(998),
+                    behind_federation: Some(2),
+                    behind_observer: None,
+                },
+                GuardianSessionDivergence {
+                    guardian_id: PeerId::from(2),
+                    reported_session_count: Some(900),
+                    behind_federation: Some(100),
+                    behind_observer: Some(95),
+                },
+                GuardianSessionDivergence {
+                    guardian_id: PeerId::from(3),
+                    reported_session_count: None,
+                    behind_federation: None,
+                    behind_observer: None,
+                },
+            ]
+        );
+
+        let no_responses = BTreeMap::from([(PeerId::from(0), None)]);
+        assert_eq!(
+            divergence(None, 10, &no_responses, vec![]).observer_lag,
+            None
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fedimint_core::session_outcome::SessionOutcome;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tracing::{info, warn};
+
+use crate::federation::db::Federation;
+use crate::federation::decoders_from_config;
+use crate::federation::jobs::Job;
+use crate::federation::observer::FederationObserver;
+
+/// Suffix of session dump files, the file name before it is the session index
+const DUMP_FILE_SUFFIX: &str = ".bin.gz";
+
+impl FederationObserver {
+    /// Processes the session dumps in `dir` as if they were fetched from the
+    /// federation, starting at the first session that wasn't processed yet.
+    /// Each file `<session_index>.bin.gz` contains a gzipped consensus encoded
+    /// `SessionOutcome`. The federation has to be added already and the API
+    /// server must not run at the same time.
+    pub async fn import_session_dumps(
+        &self,
+        federation_id: FederationId,
+        dir: &Path,
+    ) -> anyhow::Result<u64> {
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist, add it before importing sessions")?;
+
+        let dumps = list_session_dumps(dir)?;
+        let first_session = match self.observer_checkpoint(federation_id).await? {
+            Some(last_session) => last_session + 1,
+            None => self.federation_session_count(federation_id).await?,
+        };
+
+        let mut job = self
+            .start_job(
+                "import",
+                Some(federation_id),
+                dumps.range(first_session..).count() as u64,
+            )
+            .await?;
+        let result = self
+            .import_sessions(federation_id, &federation, &dumps, first_session, &mut job)
+            .await;
+        job.finish(&result).await?;
+
+        result
+    }
+
+    async fn import_sessions(
+        &self,
+        federation_id: FederationId,
+        federation: &Federation,
+        dumps: &BTreeMap<u64, PathBuf>,
+        first_session: u64,
+        job: &mut Job,
+    ) -> anyhow::Result<u64> {
+        let decoders = decoders_from_config(&federation.config);
+        let mut imported = 0;
+        for (session_index, path) in dumps.range(first_session..) {
+            if *session_index != first_session + imported {
+                warn!(
+                    "Session {} is missing from the dump, stopping import",
+                    first_session + imported
+                );
+                break;
+            }
+
+            let session_outcome =
+                SessionOutcome::consensus_decode_vec(read_gzipped(path)?, &decoders)
+                    .with_context(|| format!("Invalid session dump {}", path.display()))?;
+
+            let mut connection = self.connection().await?;
+            let dbtx = connection.transaction().await?;
+            self.process_session(
+                federation_id,
+                federation.config.clone(),
+                *session_index,
+                session_outcome,
+                &dbtx,
+            )
+            .await?;
+            Self::record_observer_checkpoint(&dbtx, federation_id, *session_index).await?;
+            dbtx.commit().await?;
+
+            imported += 1;
+            job.progress(imported).await?;
+            if imported % 1000 == 0 {
+                info!("Imported up to session {session_index} of {federation_id}");
+            }
+        }
+
+        Ok(imported)
+    }
+
+    /// Writes all stored sessions of a federation to `dir` in the format read
+    /// by [`FederationObserver::import_session_dumps`]
+    pub async fn dump_sessions(
+        &self,
+        federation_id: FederationId,
+        dir: &Path,
+    ) -> anyhow::Result<u64> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let rows = self
+            .connection()
+            .await?
+            .query(
+                // language=postgresql
+                "SELECT session_index, session FROM sessions WHERE federation_id = $1 ORDER BY session_index",
+                &[&federation_id.consensus_encode_to_vec()],
+            )
+            .await?;
+
+        for row in &rows {
+            let session_index = row.get::<_, i32>("session_index");
+            let path = dir.join(format!("{session_index}{DUMP_FILE_SUFFIX}"));
+            let mut encoder = GzEncoder::new(
+                std::fs::File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?,
+                Compression::default(),
+            );
+            encoder.write_all(&row.get::<_, Vec<u8>>("session"))?;
+            encoder.finish()?;
+        }
+
+        Ok(rows.len() as u64)
+    }
+}
+
+/// Session dump files in `dir` by session index, other files are ignored
+fn list_session_dumps(dir: &Path) -> anyhow::Result<BTreeMap<u64, PathBuf>> {
+    let mut dumps = BTreeMap::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let Some(session_index) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(dump_session_index)
+        else {
+            continue;
+        };
+        dumps.insert(session_index, path);
+    }
+    ensure!(
+        !dumps.is_empty(),
+        "No session dumps found in {}",
+        dir.display()
+    );
+    Ok(dumps)
+}
+
+fn dump_session_index(file_name: &str) -> Option<u64> {
+    file_name.strip_suffix(DUMP_FILE_SUFFIX)?.parse().ok()
+}
+
+fn read_gzipped(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut bytes = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to decompress {}", path.display()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dump_session_index;
+
+    #[test]
+    fn test_dump_session_index() {
+        assert_eq!(dump_session_index("0.bin.gz"), Some(0));
+        assert_eq!(dump_session_index("1234.bin.gz"), Some(1234));
+        assert_eq!(dump_session_index("1234.bin"), None);
+        assert_eq!(dump_session_index("session.bin.gz"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::Context;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use axum_auth::AuthBearer;
+use chrono::{NaiveDate, NaiveDateTime};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::federation::cursors::cursor_owner;
+use crate::AppState;
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum ExportFormat {
+    #[default]
+    Csv,
+    /// A JSON array of transaction objects
+    Json,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ExportParams {
+    /// Defaults to CSV
+    #[serde(default)]
+    format: ExportFormat,
+    /// First day (UTC) to include transactions of
+    from: Option<NaiveDate>,
+    /// Last day (UTC) to include transactions of
+    to: Option<NaiveDate>,
+    /// Maximum number of transactions to export
+    limit: Option<u32>,
+    /// Name of a cursor of the API key passed as bearer token, which needs
+    /// the `query` scope. The export starts after the last transaction
+    /// exported with the cursor and advances it once the response was sent
+    /// completely.
+    cursor: Option<String>,
+}
+
+/// Transaction as exported, with module kinds and amounts summed up over the
+/// transaction's inputs and outputs
+#[derive(Debug, Serialize)]
+struct ExportedTransaction {
+    txid: String,
+    session_index: i32,
+    item_index: i32,
+    /// RFC 3339, empty if the session time isn't known yet
+    estimated_time: Option<String>,
+    input_amount_msat: i64,
+    output_amount_msat: i64,
+    input_kinds: Vec<String>,
+    output_kinds: Vec<String>,
+}
+
+impl ExportedTransaction {
+    fn try_from_row(row: &Row) -> anyhow::Result<Self> {
+        Ok(ExportedTransaction {
+            txid: hex::encode(row.try_get::<_, Vec<u8>>("txid")?),
+            session_index: row.try_get("session_index")?,
+            item_index: row.try_get("item_index")?,
+            estimated_time: row
+                .try_get::<_, Option<NaiveDateTime>>("estimated_time")?
+                .map(|time| time.and_utc().to_rfc3339()),
+            input_amount_msat: row.try_get("input_amount_msat")?,
+            output_amount_msat: row.try_get("output_amount_msat")?,
+            input_kinds: row.try_get("input_kinds")?,
+            output_kinds: row.try_get("output_kinds")?,
+        })
+    }
+
+    const CSV_HEADER: [&'static str; 8] = [
+        "txid",
+        "session_index",
+        "item_index",
+        "estimated_time",
+        "input_amount_msat",
+        "output_amount_msat",
+        "input_kinds",
+        "output_kinds",
+    ];
+
+    /// Module kinds are joined with `;` to keep one column per field
+    fn to_csv_record(&self) -> anyhow::Result<Vec<u8>> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record([
+            self.txid.clone(),
+            self.session_index.to_string(),
+            self.item_index.to_string(),
+            self.estimated_time.clone().unwrap_or_default(),
+            self.input_amount_msat.to_string(),
+            self.output_amount_msat.to_string(),
+            self.input_kinds.join(";"),
+            self.output_kinds.join(";"),
+        ])?;
+        Ok(writer.into_inner()?)
+    }
+}
+
+/// Bulk export of a federation's transactions in consensus order. The response
+/// is streamed, so large exports don't have to fit into memory. Days are
+/// matched against the estimated session time, transactions in sessions
+/// without a time estimate are only included if neither `from` nor `to` is
+/// set. With a `cursor` an interrupted export is repeated from the cursor's
+/// previous position, so a client keeps the cursor's name instead of its
+/// position to resume syncing.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/export",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ExportParams),
+    security((), ("api_key" = [])),
+    responses(
+        (status = 200, description = "Transactions as CSV", content_type = "text/csv"),
+        (status = 200, description = "Transactions as JSON array", content_type = "application/json")
+    )
+)]
+pub(super) async fn export_transactions(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<ExportParams>,
+    bearer: Option<AuthBearer>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Response> {
+    let observer = state.federation_observer;
+    observer
+        .get_federation(federation_id)
+        .await?
+        .context("Federation doesn't exist")?;
+
+    let cursor = match params.cursor {
+        Some(name) => {
+            let api_key_id = cursor_owner(&observer, bearer).await?;
+            let position = observer
+                .export_cursor_position(api_key_id, federation_id, &name)
+                .await?;
+            if position.is_none() {
+                observer.check_cursor_limit(api_key_id).await?;
+            }
+            Some((api_key_id, name, position))
+        }
+        None => None,
+    };
+    let (after_session, after_item) = cursor
+        .as_ref()
+        .and_then(|(_, _, position)| *position)
+        .unzip();
+    let limit = params.limit.map(i64::from);
+
+    let conn = observer.connection().await?;
+    let federation_id_bytes = federation_id.consensus_encode_to_vec();
+    let params_sql: Vec<&(dyn ToSql + Sync)> = vec![
+        &federation_id_bytes,
+        &params.from,
+        &params.to,
+        &after_session,
+        &after_item,
+        &limit,
+    ];
+    let rows = conn
+        .query_raw(
+            // language=postgresql
+            "SELECT t.txid,
+                    t.session_index,
+                    t.item_index,
+                    st.estimated_session_timestamp                         AS estimated_time,
+                    COALESCE((SELECT SUM(ti.amount_msat)
+                              FROM transaction_inputs ti
+                              WHERE ti.federation_id = t.federation_id
+                                AND ti.txid = t.txid), 0)::bigint          AS input_amount_msat,
+                    COALESCE((SELECT SUM(tout.amount_msat)
+                              FROM transaction_outputs tout
+                              WHERE tout.federation_id = t.federation_id
+                                AND tout.txid = t.txid), 0)::bigint        AS output_amount_msat,
+                    ARRAY(SELECT DISTINCT ti.kind
+                          FROM transaction_inputs ti
+                          WHERE ti.federation_id = t.federation_id
+                            AND ti.txid = t.txid
+                          ORDER BY ti.kind)                                AS input_kinds,
+                    ARRAY(SELECT DISTINCT tout.kind
+                          FROM transaction_outputs tout
+                          WHERE tout.federation_id = t.federation_id
+                            AND tout.txid = t.txid
+                          ORDER BY tout.kind)                              AS output_kinds
+             FROM transactions t
+                      LEFT JOIN session_times st
+                                ON st.federation_id = t.federation_id AND st.session_index = t.session_index
+             WHERE t.federation_id = $1
+               AND ($2::date IS NULL OR DATE(st.estimated_session_timestamp) >= $2)
+               AND ($3::date IS NULL OR DATE(st.estimated_session_timestamp) <= $3)
+               AND ($4::integer IS NULL OR (t.session_index, t.item_index) > ($4, $5::integer))
+             ORDER BY t.session_index, t.item_index
+             LIMIT $6::bigint",
+            params_sql,
+        )
+        .await?;
+
+    let format = params.format;
+    let body = async_stream::stream! {
+        // Keep the connection alive while streaming
+        let _conn = conn;
+        match format {
+            ExportFormat::Csv => yield csv_header(),
+            ExportFormat::Json => yield Ok(b"[".to_vec()),
+        }
+
+        let mut rows = std::pin::pin!(rows);
+        let mut first = true;
+        let mut last_position = None;
+        while let Some(row) = rows.next().await {
+            let chunk = row
+                .map_err(anyhow::Error::from)
+                .and_then(|row| ExportedTransaction::try_from_row(&row))
+                .and_then(|transaction| {
+                    last_position = Some((transaction.session_index, transaction.item_index));
+                    match format {
+                        ExportFormat::Csv => transaction.to_csv_record(),
+                        ExportFormat::Json => {
+                            let separator = if first { "\n" } else { ",\n" };
+                            Ok([separator.as_bytes(), &serde_json::to_vec(&transaction)?].concat())
+                        }
+                    }
+                });
+            first = false;
+            let failed = chunk.is_err();
+            yield chunk;
+            if failed {
+                return;
+            }
+        }
+
+        if let ExportFormat::Json = format {
+            yield Ok(b"\n]\n".to_vec());
+        }
+
+        // Only reached once the whole response was sent, an interrupted
+        // response drops the stream before
+        if let (Some((api_key_id, name, _)), Some(position)) = (cursor, last_position) {
+            if let Err(e) = observer
+                .advance_export_cursor(api_key_id, federation_id, &name, position)
+                .await
+            {
+                yield Err(e);
+            }
+        }
+    };
+
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv", "csv"),
+        ExportFormat::Json => ("application/json", "json"),
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, content_type.to_owned()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{federation_id}-transactions.{extension}\""),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+fn csv_header() -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(ExportedTransaction::CSV_HEADER)?;
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExportedTransaction;
+
+    #[test]
+    fn test_csv_record() {
+        let transaction = ExportedTransaction {
+            txid: "00ff".to_owned(),
+            session_index: 3,
+            item_index: 1,
+            estimated_time: None,
+            input_amount_msat: 2_000,
+            output_amount_msat: 1_000,
+            input_kinds: vec!["mint".to_owned()],
+            output_kinds: vec!["ln".to_owned(), "mint".to_owned()],
+        };
+        assert_eq!(
+            transaction.to_csv_record().unwrap(),
+            b"00ff,3,1,,2000,1000,mint,ln;mint\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::NaiveDate;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::Amount;
+use fmo_api_types::{FederationFees, FeeRevenue};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{config_to_json, query, query_value};
+use crate::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/fees",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = FederationFees))
+)]
+pub(super) async fn get_federation_fees(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<FederationFees>> {
+    Ok(state
+        .federation_observer
+        .federation_fees(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Fee configuration and fee revenue of a federation. The fee of a
+    /// transaction is the difference between its inputs and outputs.
+    pub async fn federation_fees(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationFees> {
+        #[derive(Debug, FromRow)]
+        struct DailyFeesRow {
+            date: NaiveDate,
+            fees: i64,
+        }
+
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let fee_config = config_to_json(federation.config)?
+            .modules
+            .into_values()
+            .filter_map(|module| {
+                let fee_consensus = module.value().get("fee_consensus")?.clone();
+                Some((module.kind().as_str().to_owned(), fee_consensus))
+            })
+            .collect();
+
+        let conn = self.connection().await?;
+        let total_fees = query_value::<i64>(
+            &conn,
+            // language=postgresql
+            "SELECT COALESCE(SUM(fee_msat), 0)::bigint FROM fees WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let daily_fees = query::<DailyFeesRow>(
+            &conn,
+            // language=postgresql
+            "SELECT DATE(st.estimated_session_timestamp) AS date,
+                    SUM(f.fee_msat)::bigint             AS fees
+             FROM fees f
+                      JOIN session_times st
+                           ON f.federation_id = st.federation_id AND f.session_index = st.session_index
+             WHERE f.federation_id = $1
+             GROUP BY date
+             ORDER BY date",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let history = fee_history(daily_fees.into_iter().map(|row| (row.date, row.fees)))?;
+
+        Ok(FederationFees {
+            fee_config,
+            total_fees: Amount::from_msats(
+                u64::try_from(total_fees).context("Negative total fees")?,
+            ),
+            history,
+        })
+    }
+}
+
+/// Daily and cumulative fee revenue from the fees collected per day. Fees of
+/// transactions with unknown amounts aren't recorded, so a negative day means
+/// the recorded amounts are inconsistent.
+fn fee_history(
+    daily_fees: impl IntoIterator<Item = (NaiveDate, i64)>,
+) -> anyhow::Result<BTreeMap<NaiveDate, FeeRevenue>> {
+    let mut cumulative_fees = Amount::ZERO;
+    daily_fees
+        .into_iter()
+        .map(|(date, fees)| {
+            let fees = Amount::from_msats(
+                u64::try_from(fees).with_context(|| format!("Negative fees on {date}"))?,
+            );
+            cumulative_fees += fees;
+            Ok((
+                date,
+                FeeRevenue {
+                    fees,
+                    cumulative_fees,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use fedimint_core::Amount;
+
+    use super::fee_history;
+
+    #[test]
+    fn test_fee_history() {
+        let day = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+
+        let history = fee_history([(day(1), 100), (day(2), 0), (day(3), 50)]).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[&day(2)].fees, Amount::ZERO);
+        assert_eq!(history[&day(2)].cumulative_fees, Amount::from_msats(100));
+        assert_eq!(history[&day(3)].cumulative_fees, Amount::from_msats(150));
+
+        // Outputs exceeding the known inputs would show up as negative fees
+        assert!(fee_history([(day(1), 100), (day(2), -4_000)]).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::secp256k1::PublicKey;
+use fedimint_core::Amount;
+use fedimint_ln_common::contracts::{Contract, IdentifiableContract};
+use fedimint_ln_common::{LightningOutput, LightningOutputV0};
+use fmo_api_types::FederationGateway;
+use postgres_from_row::FromRow;
+use tracing::info;
+
+use crate::federation::jobs::Job;
+use crate::federation::observer::FederationObserver;
+use crate::federation::{db, decoders_from_config, instance_to_kind};
+use crate::util::query;
+use crate::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/gateways",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = [FederationGateway]))
+)]
+pub(super) async fn get_federation_gateways(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<FederationGateway>>> {
+    Ok(state
+        .federation_observer
+        .federation_gateways(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Lightning gateways that were the counterparty of at least one contract
+    /// of the federation, ordered by routed volume
+    pub async fn federation_gateways(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<FederationGateway>> {
+        #[derive(Debug, FromRow)]
+        struct GatewayRow {
+            gateway_key: Vec<u8>,
+            contracts: i64,
+            routed_volume: i64,
+            first_seen_session: i32,
+            last_seen_session: i32,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let gateways = query::<GatewayRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT g.gateway_key,
+                    g.first_seen_session,
+                    g.last_seen_session,
+                    COUNT(DISTINCT lc.contract_id)::bigint     AS contracts,
+                    COALESCE(SUM(tout.amount_msat), 0)::bigint AS routed_volume
+             FROM gateways g
+                      LEFT JOIN ln_contracts lc
+                                ON lc.federation_id = g.federation_id AND lc.gateway_key = g.gateway_key
+                      LEFT JOIN transaction_outputs tout
+                                ON tout.federation_id = lc.federation_id AND
+                                   tout.ln_contract_id = lc.contract_id AND
+                                   tout.ln_contract_interaction_kind = 'fund'
+             WHERE g.federation_id = $1
+             GROUP BY g.gateway_key, g.first_seen_session, g.last_seen_session
+             ORDER BY routed_volume DESC",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        gateways
+            .into_iter()
+            .map(|row| {
+                Ok(FederationGateway {
+                    gateway_key: PublicKey::from_slice(&row.gateway_key)
+                        .context("Invalid gateway key in DB")?,
+                    contracts: row.contracts as u64,
+                    routed_volume: Amount::from_msats(row.routed_volume as u64),
+                    first_seen_session: row.first_seen_session as u64,
+                    last_seen_session: row.last_seen_session as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Records that a contract with `gateway_key` as counterparty was funded in
+    /// `session_index`
+    pub(super) async fn record_gateway_activity(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        gateway_key: PublicKey,
+    ) -> Result<(), tokio_postgres::Error> {
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO gateways (federation_id, gateway_key, first_seen_session, last_seen_session)
+             VALUES ($1, $2, $3, $3)
+             ON CONFLICT (federation_id, gateway_key) DO UPDATE
+                 SET first_seen_session = LEAST(gateways.first_seen_session, excluded.first_seen_session),
+                     last_seen_session  = GREATEST(gateways.last_seen_session, excluded.last_seen_session)",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &gateway_key.consensus_encode_to_vec(),
+                &(session_index as i32),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fills in the gateways of contracts that were funded before gateways
+    /// were tracked
+    pub(super) async fn backfill_v18_gateways(
+        &self,
+        dbtx: &Transaction<'_>,
+        job: &mut Job,
+    ) -> anyhow::Result<()> {
+        info!("Beginning backfill of lightning gateways");
+
+        for fed in self.list_federations().await? {
+            let decoders = decoders_from_config(&fed.config);
+            let transaction_rows = dbtx
+                .query(
+                    // language=postgresql
+                    "SELECT t.txid, t.session_index, t.item_index, t.data
+                     FROM transactions t
+                     WHERE t.federation_id = $1
+                       AND EXISTS (SELECT 1
+                                   FROM transaction_outputs tout
+                                   WHERE tout.federation_id = t.federation_id
+                                     AND tout.txid = t.txid
+                                     AND tout.ln_contract_interaction_kind = 'fund')",
+                    &[&fed.federation_id.consensus_encode_to_vec()],
+                )
+                .await?;
+
+            info!(
+                "Extracting gateways from {} transactions for fed: {}",
+                transaction_rows.len(),
+                fed.federation_id
+            );
+            for row in transaction_rows {
+                let transaction = db::Transaction::from_row_with_decoders(&row, &decoders);
+
+                for output in transaction.data.outputs {
+                    if instance_to_kind(&fed.config, output.module_instance_id()) != "ln" {
+                        continue;
+                    }
+
+                    let Some(LightningOutputV0::Contract(contract)) = output
+                        .as_any()
+                        .downcast_ref::<LightningOutput>()
+                        .expect("Not LN output")
+                        .maybe_v0_ref()
+                    else {
+                        continue;
+                    };
+                    let gateway_key = match &contract.contract {
+                        Contract::Incoming(c) => c.gateway_key,
+                        Contract::Outgoing(c) => c.gateway_key,
+                    };
+
+                    dbtx.execute(
+                        // language=postgresql
+                        "UPDATE ln_contracts SET gateway_key = $3 WHERE federation_id = $1 AND contract_id = $2",
+                        &[
+                            &fed.federation_id.consensus_encode_to_vec(),
+                            &contract.contract.contract_id().consensus_encode_to_vec(),
+                            &gateway_key.consensus_encode_to_vec(),
+                        ],
+                    )
+                    .await?;
+                    Self::record_gateway_activity(
+                        dbtx,
+                        fed.federation_id,
+                        transaction.session_index as u64,
+                        gateway_key,
+                    )
+                    .await?;
+                }
+            }
+            job.advance().await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use axum::extract::{Path, State};
+use axum::Json;
+use bitcoin::hashes::Hash;
+use bitcoin::Txid;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::{Amount, TransactionId};
+use fmo_api_types::{
+    TransactionDetails, TransactionGraph, TransactionGraphEdge, TransactionGraphNode,
+    TransactionGraphNodeKind, TransactionItem,
+};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+const TRANSACTION_NODE: &str = "transaction";
+
+/// Graph of the transaction's inputs and outputs and what they reference:
+/// lightning contracts, the transactions funding them and peg-in/peg-out
+/// transactions on the bitcoin chain
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/transactions/{transaction_id}/graph",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ("transaction_id" = String, Path, description = "Hex encoded transaction id")),
+    responses((status = 200, description = "Flow of funds through the transaction", body = TransactionGraph))
+)]
+pub(super) async fn get_transaction_graph(
+    Path((federation_id, transaction_id)): Path<(FederationId, TransactionId)>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<TransactionGraph>> {
+    Ok(state
+        .federation_observer
+        .transaction_graph(federation_id, transaction_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn transaction_graph(
+        &self,
+        federation_id: FederationId,
+        transaction_id: TransactionId,
+    ) -> anyhow::Result<TransactionGraph> {
+        #[derive(Debug, FromRow)]
+        struct PegInRow {
+            in_index: i32,
+            on_chain_txid: Vec<u8>,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct PegOutRow {
+            on_chain_txid: Vec<u8>,
+        }
+
+        let details = self
+            .transaction_details(federation_id, transaction_id)
+            .await?;
+
+        let conn = self.connection().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+        let txid_bytes = transaction_id.consensus_encode_to_vec();
+
+        let peg_ins = query::<PegInRow>(
+            &conn,
+            // language=postgresql
+            "SELECT in_index, on_chain_txid FROM wallet_peg_ins WHERE federation_id = $1 AND txid = $2",
+            &[&federation_id_bytes, &txid_bytes],
+        )
+        .await?
+        .into_iter()
+        .map(|row| Ok((row.in_index as usize, Txid::from_slice(&row.on_chain_txid)?)))
+        .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+        let peg_outs = query::<PegOutRow>(
+            &conn,
+            // language=postgresql
+            "SELECT on_chain_txid
+             FROM wallet_withdrawal_transactions
+             WHERE federation_id = $1 AND federation_txid = $2",
+            &[&federation_id_bytes, &txid_bytes],
+        )
+        .await?
+        .into_iter()
+        .map(|row| Ok(Txid::from_slice(&row.on_chain_txid)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(transaction_graph(&details, &peg_ins, &peg_outs))
+    }
+}
+
+#[derive(Debug, Default)]
+struct GraphBuilder {
+    nodes: Vec<TransactionGraphNode>,
+    node_ids: BTreeSet<String>,
+    edges: Vec<TransactionGraphEdge>,
+}
+
+impl GraphBuilder {
+    /// Adds the node unless one with the same id exists, returns the id
+    fn node(
+        &mut self,
+        id: String,
+        kind: TransactionGraphNodeKind,
+        label: String,
+        amount: Option<Amount>,
+        txid: Option<String>,
+    ) -> String {
+        if self.node_ids.insert(id.clone()) {
+            self.nodes.push(TransactionGraphNode {
+                id: id.clone(),
+                kind,
+                label,
+                amount,
+                txid,
+            });
+        }
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str) {
+        self.edges.push(TransactionGraphEdge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        });
+    }
+
+    /// Lightning contract node and the node of the transaction that funded
+    /// it, if that isn't the graph's transaction
+    fn ln_contract(&mut self, item: &TransactionItem) -> Option<String> {
+        let contract = item.ln_contract.as_ref()?;
+        let contract_node = self.node(
+            format!("contract:{}", contract.contract_id),
+            TransactionGraphNodeKind::LnContract,
+            format!(
+                "LN contract {}",
+                contract
+                    .contract_id
+                    .get(..8)
+                    .unwrap_or(&contract.contract_id)
+            ),
+            None,
+            None,
+        );
+
+        if let Some(funding_txid) = contract.funding_txid {
+            let funding_node = self.node(
+                format!("transaction:{funding_txid}"),
+                TransactionGraphNodeKind::Transaction,
+                "Funding transaction".to_owned(),
+                None,
+                Some(funding_txid.to_string()),
+            );
+            self.edge(&funding_node, &contract_node);
+        }
+
+        Some(contract_node)
+    }
+
+    fn on_chain_transaction(&mut self, txid: Txid, label: &str) -> String {
+        self.node(
+            format!("onchain:{txid}"),
+            TransactionGraphNodeKind::OnChainTransaction,
+            label.to_owned(),
+            None,
+            Some(txid.to_string()),
+        )
+    }
+}
+
+/// Builds the graph with funds flowing from the referenced contracts and
+/// peg-in transactions through the inputs into the transaction, and out of
+/// it through the outputs into contracts and peg-out transactions
+fn transaction_graph(
+    details: &TransactionDetails,
+    peg_ins: &BTreeMap<usize, Txid>,
+    peg_outs: &[Txid],
+) -> TransactionGraph {
+    let mut graph = GraphBuilder::default();
+    let transaction_node = graph.node(
+        TRANSACTION_NODE.to_owned(),
+        TransactionGraphNodeKind::Transaction,
+        "Transaction".to_owned(),
+        None,
+        Some(details.txid.to_string()),
+    );
+
+    for (in_index, input) in details.inputs.iter().enumerate() {
+        let input_node = graph.node(
+            format!("input:{in_index}"),
+            TransactionGraphNodeKind::Input,
+            format!("{} input", input.kind),
+            input.amount,
+            None,
+        );
+        if let Some(contract_node) = graph.ln_contract(input) {
+            graph.edge(&contract_node, &input_node);
+        }
+        if let Some(on_chain_txid) = peg_ins.get(&in_index) {
+            let on_chain_node = graph.on_chain_transaction(*on_chain_txid, "Peg-in");
+            graph.edge(&on_chain_node, &input_node);
+        }
+        graph.edge(&input_node, &transaction_node);
+    }
+
+    for (out_index, output) in details.outputs.iter().enumerate() {
+        let output_node = graph.node(
+            format!("output:{out_index}"),
+            TransactionGraphNodeKind::Output,
+            format!("{} output", output.kind),
+            output.amount,
+            None,
+        );
+        graph.edge(&transaction_node, &output_node);
+        if let Some(contract_node) = graph.ln_contract(output) {
+            graph.edge(&output_node, &contract_node);
+        }
+        if output.kind == "wallet" {
+            for on_chain_txid in peg_outs {
+                let on_chain_node = graph.on_chain_transaction(*on_chain_txid, "Peg-out");
+                graph.edge(&output_node, &on_chain_node);
+            }
+        }
+    }
+
+    TransactionGraph {
+        nodes: graph.nodes,
+        edges: graph.edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bitcoin::hashes::Hash;
+    use bitcoin::Txid;
+    use fedimint_core::{Amount, TransactionId};
+    use fmo_api_types::{
+        LnContractReference, TransactionDetails, TransactionGraphEdge, TransactionGraphNodeKind,
+        TransactionItem,
+    };
+
+    use super::transaction_graph;
+
+    fn item(kind: &str, msat: u64, ln_contract: Option<LnContractReference>) -> TransactionItem {
+        TransactionItem {
+            kind: kind.to_owned(),
+            amount: Some(Amount::from_msats(msat)),
+            ln_contract,
+            decoded: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_transaction_graph() {
+        let funding_txid = TransactionId::from_byte_array([1; 32]);
+        let contract = LnContractReference {
+            contract_id: "abcdef0123456789".to_owned(),
+            interaction: None,
+            funding_txid: Some(funding_txid),
+        };
+        let details = TransactionDetails {
+            txid: TransactionId::from_byte_array([2; 32]),
+            session_index: 0,
+            item_index: 0,
+            inputs: vec![
+                item("ln", 1_000, Some(contract.clone())),
+                item("wallet", 2_000, None),
+            ],
+            outputs: vec![item("mint", 2_500, None), item("wallet", 500, None)],
+        };
+        let peg_in = Txid::from_byte_array([3; 32]);
+        let peg_out = Txid::from_byte_array([4; 32]);
+
+        let graph = transaction_graph(&details, &BTreeMap::from([(1, peg_in)]), &[peg_out]);
+
+        let ids = graph
+            .nodes
+            .iter()
+            .map(|node| node.id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                "transaction".to_owned(),
+                "input:0".to_owned(),
+                "contract:abcdef0123456789".to_owned(),
+                format!("transaction:{funding_txid}"),
+                "input:1".to_owned(),
+                format!("onchain:{peg_in}"),
+                "output:0".to_owned(),
+                "output:1".to_owned(),
+                format!("onchain:{peg_out}"),
+            ]
+        );
+        assert_eq!(graph.nodes[2].kind, TransactionGraphNodeKind::LnContract);
+        assert_eq!(graph.nodes[2].label, "LN contract abcdef01");
+
+        let edge = |from: &str, to: &str| TransactionGraphEdge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        };
+        assert!(graph.edges.contains(&edge(
+            &format!("transaction:{funding_txid}"),
+            "contract:abcdef0123456789"
+        )));
+        assert!(graph
+            .edges
+            .contains(&edge("contract:abcdef0123456789", "input:0")));
+        assert!(graph
+            .edges
+            .contains(&edge(&format!("onchain:{peg_in}"), "input:1")));
+        assert!(graph
+            .edges
+            .contains(&edge("output:1", &format!("onchain:{peg_out}"))));
+        assert!(!graph
+            .edges
+            .contains(&edge("output:0", &format!("onchain:{peg_out}"))));
+    }
+}
@@ -1,22 +1,37 @@
 use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Context};
-use axum::extract::{Path, State};
+use anyhow::{anyhow, ensure, Context};
+use axum::extract::{Path, Query, State};
 use axum::Json;
-use fedimint_api_client::api::{DynGlobalApi, FederationApiExt, StatusResponse};
+use chrono::NaiveDateTime;
+use fedimint_api_client::api::{FederationApiExt, StatusResponse};
 use fedimint_core::config::{ClientConfig, FederationId};
 use fedimint_core::encoding::Encodable;
 use fedimint_core::endpoint_constants::STATUS_ENDPOINT;
 use fedimint_core::module::ApiRequestErased;
-use fedimint_core::{NumPeers, PeerId};
+use fedimint_core::PeerId;
 use fedimint_wallet_common::endpoint_constants::BLOCK_COUNT_LOCAL_ENDPOINT;
-use fmo_api_types::{FederationHealth, GuardianHealth, GuardianHealthLatest};
+use fmo_api_types::{
+    FederationHealth, GuardianContribution, GuardianHealth, GuardianHealthBucket,
+    GuardianHealthLatest,
+};
 use futures::future::join_all;
 use postgres_from_row::FromRow;
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
 
+use crate::federation::alerts::{health_conditions, GuardianCheck, HealthAlertTracker};
+use crate::federation::certificates::{probe_guardian_certificates, CERTIFICATE_CHECK_INTERVAL};
+use crate::federation::health::{observed_health, HealthStateMachine};
 use crate::federation::observer::FederationObserver;
-use crate::util::query;
+use crate::federation::versions::{fetch_fedimintd_versions, VERSION_CHECK_INTERVAL};
+use crate::socks::federation_api;
+use crate::util::{config_network, query};
+
+const DEFAULT_HEALTH_HISTORY_DAYS: u32 = 7;
+const MAX_HEALTH_HISTORY_DAYS: u32 = 90;
 
 impl FederationObserver {
     pub async fn monitor_health(
@@ -27,15 +42,9 @@ impl FederationObserver {
         const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
         const REQUEST_INTERVAL: Duration = Duration::from_secs(60);
 
+        let network = config_network(&config)?.unwrap_or(bitcoin::Network::Bitcoin);
         let mut interval = tokio::time::interval(REQUEST_INTERVAL);
-        let api = DynGlobalApi::from_endpoints(
-            config
-                .global
-                .api_endpoints
-                .iter()
-                .map(|(&peer_id, peer_url)| (peer_id, peer_url.url.clone())),
-            &None,
-        );
+        let api = federation_api(&config.global.api_endpoints);
         let wallet_module = config
             .modules
             .iter()
@@ -43,10 +52,35 @@ impl FederationObserver {
                 (module.kind.as_str() == "wallet").then_some(module_instance_id)
             })
             .context("Wallet module not found")?;
+        let mut alert_tracker = HealthAlertTracker::default();
+        let mut health_state = HealthStateMachine::new(
+            self.health_thresholds(),
+            self.confirmed_health(federation_id).await?,
+        );
+        let mut last_certificate_check: Option<Instant> = None;
+        let mut last_version_check: Option<Instant> = None;
 
         loop {
             interval.tick().await;
 
+            let certificate_checks = if last_certificate_check.map_or(true, |checked| {
+                checked.elapsed() >= CERTIFICATE_CHECK_INTERVAL
+            }) {
+                last_certificate_check = Some(Instant::now());
+                Some(probe_guardian_certificates(&config).await)
+            } else {
+                None
+            };
+
+            let mut versions = if last_version_check
+                .map_or(true, |checked| checked.elapsed() >= VERSION_CHECK_INTERVAL)
+            {
+                last_version_check = Some(Instant::now());
+                fetch_fedimintd_versions(&api, config.global.api_endpoints.keys().copied()).await
+            } else {
+                BTreeMap::new()
+            };
+
             let peer_status_responses =
                 join_all(config.global.api_endpoints.keys().map(|&peer_id| {
                     let api = api.clone();
@@ -92,23 +126,90 @@ impl FederationObserver {
                 }))
                 .await;
 
+            let session_counts = peer_status_responses
+                .iter()
+                .map(|(peer_id, status, _, _)| {
+                    let session_count = status
+                        .as_ref()
+                        .and_then(|status| status.federation.as_ref())
+                        .map(|federation| federation.session_count);
+                    (*peer_id, session_count)
+                })
+                .collect();
+            let probe_results = self
+                .health_probes()
+                .run(self.http_client(), &api, &config, &session_counts)
+                .await;
+
             let mut conn = self.connection().await?;
             let dbtx = conn.transaction().await?;
             let timestamp = chrono::Utc::now().naive_utc();
-            for (peer_id, status, block_height, api_latency) in peer_status_responses {
+            for (peer_id, status, block_height, api_latency) in &peer_status_responses {
                 dbtx.execute(
-                    "INSERT INTO guardian_health VALUES ($1, $2, $3, $4, $5, $6)",
+                    "INSERT INTO guardian_health VALUES ($1, $2, $3, $4, $5, $6, $7)",
                     &[
                         &federation_id.consensus_encode_to_vec(),
                         &timestamp,
                         &(peer_id.to_usize() as i32),
-                        &status.map(|s| serde_json::to_value(s).expect("Can be serialized")),
+                        &status
+                            .as_ref()
+                            .map(|s| serde_json::to_value(s).expect("Can be serialized")),
                         &block_height.map(|bh| bh as i32),
                         &(api_latency.as_millis() as i32),
+                        &versions.remove(peer_id),
                     ],
                 )
                 .await?;
             }
+
+            let checks = peer_status_responses
+                .into_iter()
+                .map(|(peer_id, status, block_height, _)| GuardianCheck {
+                    peer_id,
+                    online: status.is_some(),
+                    block_height,
+                    session_count: status
+                        .and_then(|status| status.federation)
+                        .map(|federation| federation.session_count),
+                })
+                .collect::<Vec<_>>();
+            Self::record_incidents(
+                &dbtx,
+                federation_id,
+                checks.iter().map(|check| (check.peer_id, check.online)),
+                timestamp,
+            )
+            .await?;
+            let previous_health = health_state.state();
+            let online = checks
+                .iter()
+                .filter(|check| check.session_count.is_some())
+                .count();
+            if let Some(health) = health_state.update(observed_health(checks.len(), online)) {
+                Self::record_health_event(&dbtx, federation_id, previous_health, health, timestamp)
+                    .await?;
+            }
+            let conditions = health_conditions(&checks, self.chain_tip(network).await?);
+            for ((guardian_id, kind), message) in alert_tracker.update(conditions) {
+                if self
+                    .raise_alert(&dbtx, federation_id, kind, guardian_id, &message, timestamp)
+                    .await?
+                {
+                    info!(%federation_id, "{message}");
+                }
+            }
+            Self::record_probe_results(&dbtx, federation_id, probe_results, timestamp).await?;
+            self.record_session_counts(&dbtx, federation_id, &session_counts, timestamp)
+                .await?;
+            if let Some(certificate_checks) = certificate_checks {
+                self.record_guardian_certificates(
+                    &dbtx,
+                    federation_id,
+                    certificate_checks,
+                    timestamp,
+                )
+                .await?;
+            }
             dbtx.commit().await?;
         }
     }
@@ -117,9 +218,9 @@ impl FederationObserver {
         &self,
         federation_id: FederationId,
     ) -> anyhow::Result<BTreeMap<PeerId, GuardianHealth>> {
-        let _federation = self
+        let federation = self
             .get_federation(federation_id)
-            .await
+            .await?
             .context("Unknown federation")?;
 
         let health_rows = query::<GuardianHealthRow>(
@@ -152,7 +253,14 @@ impl FederationObserver {
                     RankedRows.block_height,
                     (RankedRows.status -> 'federation'  ->> 'session_count')::integer AS session_count,
                     Last30d.uptime,
-                    Last30d.latency_ms
+                    Last30d.latency_ms,
+                    (SELECT fedimintd_version
+                     FROM guardian_health gh
+                     WHERE gh.federation_id = $1
+                       AND gh.guardian_id = RankedRows.guardian_id
+                       AND gh.fedimintd_version IS NOT NULL
+                     ORDER BY gh.time DESC
+                     LIMIT 1) AS fedimintd_version
                 FROM
                     RankedRows join Last30d on RankedRows.guardian_id = Last30d.guardian_id
                 WHERE
@@ -162,7 +270,11 @@ impl FederationObserver {
         )
         .await?;
 
-        let our_block_height = self.get_block_height().await?;
+        let our_block_height = self
+            .chain_tip(federation.network()?.unwrap_or(bitcoin::Network::Bitcoin))
+            .await?;
+        let mut certificates = self.guardian_certificates(federation_id).await?;
+        let mut signing_latency = self.withdrawal_signing_latency(&federation).await?;
         let max_session = health_rows
             .iter()
             .filter_map(|row| row.session_count)
@@ -185,20 +297,46 @@ impl FederationObserver {
                     None
                 };
 
+                let peer_id = PeerId::new(row.guardian_id as u16);
                 let health = GuardianHealth {
                     avg_uptime: row.uptime,
                     avg_latency: row.latency_ms,
                     latest,
+                    certificate: certificates.remove(&peer_id),
+                    withdrawal_signing: signing_latency.remove(&peer_id),
+                    fedimintd_version: row.fedimintd_version,
                 };
 
-                (PeerId::new(row.guardian_id as u16), health)
+                (peer_id, health)
             })
             .collect())
     }
 
+    /// Confirmed health state of each federation, federations without recorded
+    /// transitions are judged by their latest health checks
     pub async fn get_guardian_health_summary(
         &self,
     ) -> anyhow::Result<BTreeMap<FederationId, FederationHealth>> {
+        let federations = self.latest_guardian_counts().await?;
+        let confirmed_health = self.confirmed_health_states().await?;
+
+        Ok(federations
+            .into_iter()
+            .map(|(federation_id, (guardians, online_guardians))| {
+                let health = confirmed_health
+                    .get(&federation_id)
+                    .copied()
+                    .unwrap_or_else(|| observed_health(guardians, online_guardians));
+                (federation_id, health)
+            })
+            .collect())
+    }
+
+    /// Number of guardians and how many of them were online in the latest
+    /// health check of each federation
+    pub(super) async fn latest_guardian_counts(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, (usize, usize)>> {
         #[derive(FromRow)]
         struct FederationHealthRow {
             federation_id: Vec<u8>,
@@ -247,25 +385,135 @@ impl FederationObserver {
                         .try_into()
                         .map_err(|_| anyhow!("Invalid federation id in DB"))?,
                 ));
+                Ok((
+                    federation_id,
+                    (
+                        federation.guardians as usize,
+                        federation.online_guardians as usize,
+                    ),
+                ))
+            })
+            .collect()
+    }
 
-                // Special case single guardian federations to not show them as degraded
-                if federation.guardians == 1 {
-                    return Ok((federation_id, FederationHealth::Online));
-                }
+    /// Uptime and latency of a federation's guardians in hourly buckets for up
+    /// to a week and daily buckets for longer periods
+    pub async fn get_guardian_health_history(
+        &self,
+        federation_id: FederationId,
+        guardian: Option<PeerId>,
+        days: u32,
+    ) -> anyhow::Result<BTreeMap<PeerId, Vec<GuardianHealthBucket>>> {
+        #[derive(FromRow)]
+        struct HealthBucketRow {
+            guardian_id: i32,
+            start: NaiveDateTime,
+            uptime: f32,
+            avg_latency_ms: Option<f32>,
+            samples: i64,
+        }
+
+        ensure!(
+            (1..=MAX_HEALTH_HISTORY_DAYS).contains(&days),
+            "Days have to be between 1 and {MAX_HEALTH_HISTORY_DAYS}"
+        );
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let bucket = if days <= 7 { "hour" } else { "day" };
+        let rows = query::<HealthBucketRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT guardian_id,
+                    DATE_TRUNC($3, time)                                                  AS start,
+                    (COUNT(status)::decimal / COUNT(*)::decimal * 100)::real               AS uptime,
+                    (AVG(latency_ms) FILTER (WHERE block_height IS NOT NULL))::real        AS avg_latency_ms,
+                    COUNT(*)::bigint                                                       AS samples
+             FROM guardian_health
+             WHERE federation_id = $1
+               AND ($2::integer IS NULL OR guardian_id = $2)
+               AND time > NOW() - INTERVAL '1 day' * $4::integer
+             GROUP BY guardian_id, start
+             ORDER BY guardian_id, start",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &guardian.map(|peer_id| peer_id.to_usize() as i32),
+                &bucket,
+                &(days as i32),
+            ],
+        )
+        .await?;
 
-                let threshold = NumPeers::from(federation.guardians as usize).threshold();
-                let online = federation.online_guardians as usize;
+        let mut history = BTreeMap::<PeerId, Vec<GuardianHealthBucket>>::new();
+        for row in rows {
+            history
+                .entry(PeerId::from(row.guardian_id as u16))
+                .or_default()
+                .push(GuardianHealthBucket {
+                    start: row.start.and_utc(),
+                    uptime: row.uptime,
+                    avg_latency_ms: row.avg_latency_ms,
+                    samples: row.samples as u64,
+                });
+        }
+
+        Ok(history)
+    }
+
+    pub async fn get_guardian_contribution(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<BTreeMap<PeerId, GuardianContribution>> {
+        #[derive(FromRow)]
+        struct GuardianContributionRow {
+            peer_id: i32,
+            item_count: i64,
+            transaction_count: i64,
+        }
+
+        let _federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
 
-                #[allow(clippy::comparison_chain)]
-                if online > threshold {
-                    Ok((federation_id, FederationHealth::Online))
-                } else if online == threshold {
-                    Ok((federation_id, FederationHealth::Degraded))
+        let rows = query::<GuardianContributionRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT
+                    peer_id,
+                    SUM(item_count)::bigint AS item_count,
+                    SUM(transaction_count)::bigint AS transaction_count
+                FROM session_peer_stats
+                WHERE federation_id = $1
+                GROUP BY peer_id
+                ORDER BY peer_id
+            ",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let total_items = rows.iter().map(|row| row.item_count).sum::<i64>();
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let item_share = if total_items == 0 {
+                    0.0
                 } else {
-                    Ok((federation_id, FederationHealth::Offline))
-                }
+                    row.item_count as f64 / total_items as f64
+                };
+
+                (
+                    PeerId::new(row.peer_id as u16),
+                    GuardianContribution {
+                        item_count: row.item_count as u64,
+                        transaction_count: row.transaction_count as u64,
+                        item_share,
+                    },
+                )
             })
-            .collect()
+            .collect())
     }
 }
 
@@ -276,8 +524,16 @@ struct GuardianHealthRow {
     session_count: Option<i32>,
     uptime: f32,
     latency_ms: f32,
+    fedimintd_version: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/health",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Health per guardian id", body = BTreeMap<String, GuardianHealth>))
+)]
 pub(super) async fn get_federation_health(
     Path(federation_id): Path<FederationId>,
     State(state): State<crate::AppState>,
@@ -289,3 +545,56 @@ pub(super) async fn get_federation_health(
 
     Ok(Json(guardian_health))
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct HealthHistoryParams {
+    /// Only return the history of this guardian
+    guardian: Option<u16>,
+    /// Number of days to return, defaults to 7. Buckets are hourly for up to 7
+    /// days and daily for longer periods.
+    days: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/health/history",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), HealthHistoryParams),
+    responses((status = 200, description = "Health history per guardian id", body = BTreeMap<String, Vec<GuardianHealthBucket>>))
+)]
+pub(super) async fn get_federation_health_history(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<HealthHistoryParams>,
+    State(state): State<crate::AppState>,
+) -> crate::error::Result<Json<BTreeMap<PeerId, Vec<GuardianHealthBucket>>>> {
+    let history = state
+        .federation_observer
+        .get_guardian_health_history(
+            federation_id,
+            params.guardian.map(PeerId::from),
+            params.days.unwrap_or(DEFAULT_HEALTH_HISTORY_DAYS),
+        )
+        .await?;
+
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/peers/contribution",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Contribution per guardian id", body = BTreeMap<String, GuardianContribution>))
+)]
+pub(super) async fn get_federation_peer_contribution(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<crate::AppState>,
+) -> crate::error::Result<Json<BTreeMap<PeerId, GuardianContribution>>> {
+    let contribution = state
+        .federation_observer
+        .get_guardian_contribution(federation_id)
+        .await?;
+
+    Ok(Json(contribution))
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, ensure, Context};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::NumPeers;
+use fmo_api_types::{FederationHealth, HealthEvent};
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{query, query_opt};
+use crate::AppState;
+
+const DEFAULT_DEGRADED_ROUNDS: u32 = 2;
+const DEFAULT_OFFLINE_ROUNDS: u32 = 3;
+const DEFAULT_RECOVERY_ROUNDS: u32 = 2;
+const DEFAULT_HEALTH_EVENT_DAYS: u32 = 30;
+const MAX_HEALTH_EVENT_DAYS: u32 = 365;
+
+/// Number of consecutive health check rounds (one per minute) required before
+/// a federation's health state changes, so a single missed probe doesn't make
+/// it flap
+#[derive(Debug, Clone, Copy)]
+pub(super) struct HealthThresholds {
+    /// Rounds with too few guardians online before an online federation is
+    /// considered degraded
+    degraded_rounds: u32,
+    /// Rounds below the threshold of guardians before it's considered offline
+    offline_rounds: u32,
+    /// Rounds with a better observed health before the state improves
+    recovery_rounds: u32,
+}
+
+impl HealthThresholds {
+    pub(super) fn from_env() -> anyhow::Result<HealthThresholds> {
+        let rounds = |name: &str, default: u32| -> anyhow::Result<u32> {
+            let rounds = match dotenv::var(name) {
+                Ok(rounds) => rounds.parse().with_context(|| format!("Invalid {name}"))?,
+                Err(_) => default,
+            };
+            ensure!(rounds > 0, "{name} has to be positive");
+            Ok(rounds)
+        };
+
+        Ok(HealthThresholds {
+            degraded_rounds: rounds("FO_HEALTH_DEGRADED_ROUNDS", DEFAULT_DEGRADED_ROUNDS)?,
+            offline_rounds: rounds("FO_HEALTH_OFFLINE_ROUNDS", DEFAULT_OFFLINE_ROUNDS)?,
+            recovery_rounds: rounds("FO_HEALTH_RECOVERY_ROUNDS", DEFAULT_RECOVERY_ROUNDS)?,
+        })
+    }
+}
+
+/// Health of a federation judged by a single round of checks, a federation is
+/// only fully online if it could lose a guardian without stalling
+pub(super) fn observed_health(guardians: usize, online: usize) -> FederationHealth {
+    // Special case single guardian federations to not show them as degraded
+    if guardians == 1 {
+        return if online == 1 {
+            FederationHealth::Online
+        } else {
+            FederationHealth::Offline
+        };
+    }
+
+    let threshold = NumPeers::from(guardians).threshold();
+    #[allow(clippy::comparison_chain)]
+    if online > threshold {
+        FederationHealth::Online
+    } else if online == threshold {
+        FederationHealth::Degraded
+    } else {
+        FederationHealth::Offline
+    }
+}
+
+fn severity(health: FederationHealth) -> u8 {
+    match health {
+        FederationHealth::Online => 0,
+        FederationHealth::Degraded => 1,
+        FederationHealth::Offline => 2,
+    }
+}
+
+fn parse_health(health: &str) -> anyhow::Result<FederationHealth> {
+    FederationHealth::ALL
+        .into_iter()
+        .find(|candidate| candidate.as_str() == health)
+        .ok_or_else(|| anyhow!("Invalid health state {health} in DB"))
+}
+
+/// Confirmed health state of a federation, only changing once the observed
+/// health deviated for the configured number of rounds
+#[derive(Debug)]
+pub(super) struct HealthStateMachine {
+    thresholds: HealthThresholds,
+    state: Option<FederationHealth>,
+    /// Consecutive rounds observed as degraded or offline
+    failing_rounds: u32,
+    /// Consecutive rounds observed as offline
+    offline_rounds: u32,
+    /// Consecutive rounds observed healthier than the current state
+    recovering_rounds: u32,
+}
+
+impl HealthStateMachine {
+    pub(super) fn new(
+        thresholds: HealthThresholds,
+        state: Option<FederationHealth>,
+    ) -> HealthStateMachine {
+        HealthStateMachine {
+            thresholds,
+            state,
+            failing_rounds: 0,
+            offline_rounds: 0,
+            recovering_rounds: 0,
+        }
+    }
+
+    pub(super) fn state(&self) -> Option<FederationHealth> {
+        self.state
+    }
+
+    /// Feeds the health observed in one round of checks and returns the new
+    /// state if it changed. Without a previous state the observation is taken
+    /// as is.
+    pub(super) fn update(&mut self, observed: FederationHealth) -> Option<FederationHealth> {
+        let count = |rounds: &mut u32, condition: bool| {
+            *rounds = if condition { *rounds + 1 } else { 0 };
+        };
+        count(
+            &mut self.failing_rounds,
+            observed != FederationHealth::Online,
+        );
+        count(
+            &mut self.offline_rounds,
+            observed == FederationHealth::Offline,
+        );
+
+        let Some(state) = self.state else {
+            self.state = Some(observed);
+            return Some(observed);
+        };
+        count(
+            &mut self.recovering_rounds,
+            severity(observed) < severity(state),
+        );
+
+        let next = if state != FederationHealth::Offline
+            && self.offline_rounds >= self.thresholds.offline_rounds
+        {
+            FederationHealth::Offline
+        } else if state == FederationHealth::Online
+            && self.failing_rounds >= self.thresholds.degraded_rounds
+        {
+            FederationHealth::Degraded
+        } else if severity(observed) < severity(state)
+            && self.recovering_rounds >= self.thresholds.recovery_rounds
+        {
+            observed
+        } else {
+            return None;
+        };
+
+        self.state = Some(next);
+        self.recovering_rounds = 0;
+        Some(next)
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct HealthEventParams {
+    /// Number of days to return transitions of, defaults to 30
+    days: Option<u32>,
+}
+
+/// Transitions of the federation's health state, most recent first. The state
+/// only changes after the configured number of consecutive health check rounds
+/// agree on it.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/health/events",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), HealthEventParams),
+    responses((status = 200, description = "Health transitions, most recent first", body = Vec<HealthEvent>))
+)]
+pub(super) async fn get_federation_health_events(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<HealthEventParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<HealthEvent>>> {
+    Ok(state
+        .federation_observer
+        .health_events(
+            federation_id,
+            params.days.unwrap_or(DEFAULT_HEALTH_EVENT_DAYS),
+        )
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct HealthEventRow {
+    timestamp: NaiveDateTime,
+    previous_health: Option<String>,
+    health: String,
+}
+
+impl FederationObserver {
+    pub async fn health_events(
+        &self,
+        federation_id: FederationId,
+        days: u32,
+    ) -> anyhow::Result<Vec<HealthEvent>> {
+        ensure!(
+            (1..=MAX_HEALTH_EVENT_DAYS).contains(&days),
+            "Days have to be between 1 and {MAX_HEALTH_EVENT_DAYS}"
+        );
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        query::<HealthEventRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT timestamp, previous_health, health
+             FROM health_events
+             WHERE federation_id = $1
+               AND timestamp > NOW() AT TIME ZONE 'UTC' - INTERVAL '1 day' * $2::integer
+             ORDER BY timestamp DESC",
+            &[&federation_id.consensus_encode_to_vec(), &(days as i32)],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(HealthEvent {
+                timestamp: row.timestamp.and_utc(),
+                previous_health: row
+                    .previous_health
+                    .as_deref()
+                    .map(parse_health)
+                    .transpose()?,
+                health: parse_health(&row.health)?,
+            })
+        })
+        .collect()
+    }
+
+    /// Latest confirmed health state of a federation, `None` if it was never
+    /// checked
+    pub(super) async fn confirmed_health(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<FederationHealth>> {
+        query_opt::<HealthEventRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT timestamp, previous_health, health
+             FROM health_events
+             WHERE federation_id = $1
+             ORDER BY timestamp DESC
+             LIMIT 1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .map(|row| parse_health(&row.health))
+        .transpose()
+    }
+
+    /// Latest confirmed health state of all federations that were checked
+    pub(super) async fn confirmed_health_states(
+        &self,
+    ) -> anyhow::Result<BTreeMap<FederationId, FederationHealth>> {
+        #[derive(Debug, FromRow)]
+        struct LatestHealthRow {
+            federation_id: Vec<u8>,
+            health: String,
+        }
+
+        query::<LatestHealthRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT DISTINCT ON (federation_id) federation_id, health
+             FROM health_events
+             ORDER BY federation_id, timestamp DESC",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            let federation_id = FederationId(bitcoin::hashes::Hash::from_byte_array(
+                row.federation_id
+                    .try_into()
+                    .map_err(|_| anyhow!("Invalid federation id in DB"))?,
+            ));
+            Ok((federation_id, parse_health(&row.health)?))
+        })
+        .collect()
+    }
+
+    pub(super) async fn record_health_event(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        previous_health: Option<FederationHealth>,
+        health: FederationHealth,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO health_events VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &timestamp,
+                &previous_health.map(FederationHealth::as_str),
+                &health.as_str(),
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fmo_api_types::FederationHealth::{Degraded, Offline, Online};
+
+    use super::{observed_health, HealthStateMachine, HealthThresholds};
+
+    const THRESHOLDS: HealthThresholds = HealthThresholds {
+        degraded_rounds: 2,
+        offline_rounds: 3,
+        recovery_rounds: 2,
+    };
+
+    #[test]
+    fn test_observed_health() {
+        assert_eq!(observed_health(4, 4), Online);
+        assert_eq!(observed_health(4, 3), Degraded);
+        assert_eq!(observed_health(4, 2), Offline);
+        assert_eq!(observed_health(1, 1), Online);
+        assert_eq!(observed_health(1, 0), Offline);
+    }
+
+    #[test]
+    fn test_health_state_machine() {
+        let mut machine = HealthStateMachine::new(THRESHOLDS, None);
+        assert_eq!(machine.update(Online), Some(Online));
+
+        // A single missed round doesn't change anything
+        assert_eq!(machine.update(Offline), None);
+        assert_eq!(machine.update(Online), None);
+
+        // Failing rounds degrade first, then go offline
+        assert_eq!(machine.update(Offline), None);
+        assert_eq!(machine.update(Offline), Some(Degraded));
+        assert_eq!(machine.update(Offline), Some(Offline));
+
+        // Recovery needs consecutive better rounds and goes to the observed
+        // state directly
+        assert_eq!(machine.update(Online), None);
+        assert_eq!(machine.update(Offline), None);
+        assert_eq!(machine.update(Online), None);
+        assert_eq!(machine.update(Online), Some(Online));
+        assert_eq!(machine.state(), Some(Online));
+    }
+
+    #[test]
+    fn test_health_state_machine_resumes_state() {
+        let mut machine = HealthStateMachine::new(THRESHOLDS, Some(Offline));
+        assert_eq!(machine.update(Offline), None);
+        assert_eq!(machine.update(Degraded), None);
+        assert_eq!(machine.update(Degraded), Some(Degraded));
+    }
+}
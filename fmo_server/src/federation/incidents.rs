@@ -0,0 +1,147 @@
+use anyhow::{ensure, Context};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{NaiveDateTime, Utc};
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::PeerId;
+use fmo_api_types::GuardianIncident;
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+const DEFAULT_INCIDENT_DAYS: u32 = 90;
+const MAX_INCIDENT_DAYS: u32 = 365;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct IncidentParams {
+    /// Number of days to return incidents of, defaults to 90. Incidents that
+    /// started earlier but were still ongoing are included.
+    days: Option<u32>,
+}
+
+/// Downtime incidents of the federation's guardians, most recent first. An
+/// incident starts with the first failed health check of a guardian and ends
+/// with the next successful one.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/incidents",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), IncidentParams),
+    responses((status = 200, description = "Incidents, most recent first", body = Vec<GuardianIncident>))
+)]
+pub(super) async fn get_federation_incidents(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<IncidentParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<GuardianIncident>>> {
+    Ok(state
+        .federation_observer
+        .incidents(federation_id, params.days.unwrap_or(DEFAULT_INCIDENT_DAYS))
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn incidents(
+        &self,
+        federation_id: FederationId,
+        days: u32,
+    ) -> anyhow::Result<Vec<GuardianIncident>> {
+        #[derive(Debug, FromRow)]
+        struct IncidentRow {
+            guardian_id: i32,
+            started_at: NaiveDateTime,
+            ended_at: Option<NaiveDateTime>,
+        }
+
+        ensure!(
+            (1..=MAX_INCIDENT_DAYS).contains(&days),
+            "Days have to be between 1 and {MAX_INCIDENT_DAYS}"
+        );
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let now = Utc::now().naive_utc();
+        Ok(query::<IncidentRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT guardian_id, started_at, ended_at
+             FROM incidents
+             WHERE federation_id = $1
+               AND (ended_at IS NULL OR ended_at > NOW() AT TIME ZONE 'UTC' - INTERVAL '1 day' * $2::integer)
+             ORDER BY started_at DESC",
+            &[&federation_id.consensus_encode_to_vec(), &(days as i32)],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            let guardian_id = PeerId::from(row.guardian_id as u16);
+            GuardianIncident {
+                guardian_id,
+                guardian_name: federation
+                    .config
+                    .global
+                    .api_endpoints
+                    .get(&guardian_id)
+                    .map(|endpoint| endpoint.name.clone())
+                    .unwrap_or_else(|| format!("Guardian {guardian_id}")),
+                started_at: row.started_at.and_utc(),
+                ended_at: row.ended_at.map(|ended_at| ended_at.and_utc()),
+                duration_secs: (row.ended_at.unwrap_or(now) - row.started_at)
+                    .num_seconds()
+                    .max(0) as u64,
+            }
+        })
+        .collect())
+    }
+
+    /// Opens an incident for each guardian that failed its health check and
+    /// doesn't have an open one yet, and closes the open incidents of
+    /// guardians that are reachable again
+    pub(super) async fn record_incidents(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        checks: impl IntoIterator<Item = (PeerId, bool)>,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        for (peer_id, online) in checks {
+            let params: [&(dyn tokio_postgres::types::ToSql + Sync); 3] = [
+                &federation_id.consensus_encode_to_vec(),
+                &(peer_id.to_usize() as i32),
+                &timestamp,
+            ];
+            if online {
+                dbtx.execute(
+                    // language=postgresql
+                    "UPDATE incidents
+                     SET ended_at = $3
+                     WHERE federation_id = $1
+                       AND guardian_id = $2
+                       AND ended_at IS NULL",
+                    &params,
+                )
+                .await?;
+            } else {
+                dbtx.execute(
+                    // language=postgresql
+                    "INSERT INTO incidents (federation_id, guardian_id, started_at)
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (federation_id, guardian_id) WHERE ended_at IS NULL DO NOTHING",
+                    &params,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
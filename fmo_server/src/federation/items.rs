@@ -0,0 +1,373 @@
+use std::any::Any;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::{ClientConfig, FederationId};
+use fedimint_core::core::DynModuleConsensusItem;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fedimint_core::epoch::ConsensusItem;
+use fedimint_core::{PeerId, TransactionId};
+use fedimint_ln_common::{LightningConsensusItem, LightningInput, LightningOutput};
+use fedimint_mint_common::{MintConsensusItem, MintInput, MintOutput};
+use fedimint_wallet_common::{WalletConsensusItem, WalletInput, WalletOutput};
+use fmo_api_types::{ItemSearchMatch, ItemSearchSource};
+use postgres_from_row::FromRow;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use utoipa::IntoParams;
+
+use crate::federation::jobs::Job;
+use crate::federation::observer::FederationObserver;
+use crate::federation::{db, decoders_from_config, instance_to_kind};
+use crate::util::query;
+use crate::AppState;
+
+const DEFAULT_ITEM_SEARCH_LIMIT: u32 = 100;
+const MAX_ITEM_SEARCH_LIMIT: u32 = 1000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ItemSearchParams {
+    /// Dot separated object keys leading to the value in the decoded item,
+    /// e.g. `V0.PegOut.recipient`
+    path: String,
+    /// Value to look for, parsed as JSON if possible and used as a string
+    /// otherwise
+    value: String,
+    /// Defaults to 100, at most 1000
+    limit: Option<u32>,
+}
+
+/// Finds transaction inputs/outputs and module consensus items of a
+/// federation whose decoded JSON contains `value` at `path`, e.g. all peg-outs
+/// to an address. Only items of the lightning, mint and wallet modules are
+/// searchable.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/search/items",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ItemSearchParams),
+    responses((status = 200, description = "Matching items in consensus order", body = Vec<ItemSearchMatch>))
+)]
+pub(super) async fn search_items(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<ItemSearchParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<ItemSearchMatch>>> {
+    let filter = containment_filter(&params.path, &params.value)?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_ITEM_SEARCH_LIMIT)
+        .min(MAX_ITEM_SEARCH_LIMIT);
+
+    Ok(state
+        .federation_observer
+        .search_items(federation_id, filter, limit)
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct ItemSearchRow {
+    source: String,
+    session_index: i32,
+    item_index: i32,
+    txid: Option<Vec<u8>>,
+    idx: Option<i32>,
+    peer_id: Option<i32>,
+    kind: String,
+    details: serde_json::Value,
+}
+
+impl FederationObserver {
+    async fn search_items(
+        &self,
+        federation_id: FederationId,
+        filter: serde_json::Value,
+        limit: u32,
+    ) -> anyhow::Result<Vec<ItemSearchMatch>> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let rows = query::<ItemSearchRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT 'input'             AS source,
+                    t.session_index,
+                    t.item_index,
+                    d.txid,
+                    d.in_index          AS idx,
+                    NULL::integer       AS peer_id,
+                    d.kind,
+                    d.details
+             FROM transaction_input_details d
+                      JOIN transactions t ON t.federation_id = d.federation_id AND t.txid = d.txid
+             WHERE d.federation_id = $1
+               AND d.details @> $2
+             UNION ALL
+             SELECT 'output'            AS source,
+                    t.session_index,
+                    t.item_index,
+                    d.txid,
+                    d.out_index         AS idx,
+                    NULL::integer       AS peer_id,
+                    d.kind,
+                    d.details
+             FROM transaction_output_details d
+                      JOIN transactions t ON t.federation_id = d.federation_id AND t.txid = d.txid
+             WHERE d.federation_id = $1
+               AND d.details @> $2
+             UNION ALL
+             SELECT 'consensus_item'    AS source,
+                    c.session_index,
+                    c.item_index,
+                    NULL::bytea         AS txid,
+                    NULL::integer       AS idx,
+                    c.peer_id,
+                    c.kind,
+                    c.details
+             FROM consensus_items c
+             WHERE c.federation_id = $1
+               AND c.details @> $2
+             ORDER BY session_index, item_index, idx
+             LIMIT $3",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &filter,
+                &(limit as i64),
+            ],
+        )
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let source = match row.source.as_str() {
+                    "input" => ItemSearchSource::Input,
+                    "output" => ItemSearchSource::Output,
+                    _ => ItemSearchSource::ConsensusItem,
+                };
+                let txid = row
+                    .txid
+                    .map(|txid| {
+                        TransactionId::consensus_decode_vec(txid, &Default::default())
+                            .context("Invalid transaction id in DB")
+                    })
+                    .transpose()?;
+
+                Ok(ItemSearchMatch {
+                    source,
+                    session_index: row.session_index as u64,
+                    item_index: row.item_index as u64,
+                    txid,
+                    index: row.idx.map(|idx| idx as u64),
+                    peer: row.peer_id.map(|peer_id| peer_id as u16),
+                    kind: row.kind,
+                    details: row.details,
+                })
+            })
+            .collect()
+    }
+
+    /// Stores the decoded inputs and outputs of a transaction for structured
+    /// searches
+    pub(super) async fn insert_transaction_details(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        config: &ClientConfig,
+        transaction: &fedimint_core::transaction::Transaction,
+    ) -> Result<(), tokio_postgres::Error> {
+        let txid = transaction.tx_hash().consensus_encode_to_vec();
+
+        for (in_idx, input) in transaction.inputs.iter().enumerate() {
+            let Some(details) = input_details(input.as_any()) else {
+                continue;
+            };
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO transaction_input_details VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &txid,
+                    &(in_idx as i32),
+                    &instance_to_kind(config, input.module_instance_id()),
+                    &details,
+                ],
+            )
+            .await?;
+        }
+
+        for (out_idx, output) in transaction.outputs.iter().enumerate() {
+            let Some(details) = output_details(output.as_any()) else {
+                continue;
+            };
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO transaction_output_details VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &txid,
+                    &(out_idx as i32),
+                    &instance_to_kind(config, output.module_instance_id()),
+                    &details,
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores a decoded module consensus item for structured searches
+    pub(super) async fn insert_consensus_item_details(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        item_index: u64,
+        peer_id: PeerId,
+        kind: &str,
+        ci: &DynModuleConsensusItem,
+    ) -> Result<(), tokio_postgres::Error> {
+        let Some(details) = consensus_item_details(ci.as_any()) else {
+            return Ok(());
+        };
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO consensus_items VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(session_index as i32),
+                &(item_index as i32),
+                &(peer_id.to_usize() as i32),
+                &kind,
+                &details,
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Decodes all stored sessions once to fill the item detail tables
+    pub(super) async fn backfill_v25_item_details(
+        &self,
+        dbtx: &Transaction<'_>,
+        job: &mut Job,
+    ) -> anyhow::Result<()> {
+        info!("Beginning backfill of decoded item details, this may take a while");
+
+        for fed in self.list_federations().await? {
+            let decoders = decoders_from_config(&fed.config);
+            let session_rows = dbtx
+                .query(
+                    // language=postgresql
+                    "SELECT session_index, session FROM sessions WHERE federation_id = $1",
+                    &[&fed.federation_id.consensus_encode_to_vec()],
+                )
+                .await?;
+
+            info!(
+                "Storing item details of {} sessions for fed: {}",
+                session_rows.len(),
+                fed.federation_id
+            );
+            for row in session_rows {
+                let session = db::SessionOutcome::from_row_with_decoders(&row, &decoders);
+                for (item_idx, item) in session.data.items.into_iter().enumerate() {
+                    match item.item {
+                        ConsensusItem::Transaction(transaction) => {
+                            Self::insert_transaction_details(
+                                dbtx,
+                                fed.federation_id,
+                                &fed.config,
+                                &transaction,
+                            )
+                            .await?;
+                        }
+                        ConsensusItem::Module(ci) => {
+                            Self::insert_consensus_item_details(
+                                dbtx,
+                                fed.federation_id,
+                                session.session_index as u64,
+                                item_idx as u64,
+                                item.peer,
+                                &instance_to_kind(&fed.config, ci.module_instance_id()),
+                                &ci,
+                            )
+                            .await?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            job.advance().await?;
+        }
+        Ok(())
+    }
+}
+
+fn to_json<T: Serialize + 'static>(item: &(dyn Any + Send + Sync)) -> Option<serde_json::Value> {
+    serde_json::to_value(item.downcast_ref::<T>()?).ok()
+}
+
+fn input_details(input: &(dyn Any + Send + Sync)) -> Option<serde_json::Value> {
+    to_json::<LightningInput>(input)
+        .or_else(|| to_json::<MintInput>(input))
+        .or_else(|| to_json::<WalletInput>(input))
+}
+
+fn output_details(output: &(dyn Any + Send + Sync)) -> Option<serde_json::Value> {
+    to_json::<LightningOutput>(output)
+        .or_else(|| to_json::<MintOutput>(output))
+        .or_else(|| to_json::<WalletOutput>(output))
+}
+
+fn consensus_item_details(ci: &(dyn Any + Send + Sync)) -> Option<serde_json::Value> {
+    to_json::<LightningConsensusItem>(ci)
+        .or_else(|| to_json::<MintConsensusItem>(ci))
+        .or_else(|| to_json::<WalletConsensusItem>(ci))
+}
+
+/// Builds the JSON object that contains `value` nested under the keys of
+/// `path`, matching items with `details @> filter`
+fn containment_filter(path: &str, value: &str) -> anyhow::Result<serde_json::Value> {
+    let keys = path.split('.').collect::<Vec<_>>();
+    ensure!(
+        keys.iter().all(|key| !key.is_empty()),
+        "Invalid path, expected dot separated keys"
+    );
+
+    let value = serde_json::from_str(value).unwrap_or_else(|_| json!(value));
+    Ok(keys
+        .into_iter()
+        .rev()
+        .fold(value, |value, key| json!({ key: value })))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::containment_filter;
+
+    #[test]
+    fn test_containment_filter() {
+        assert_eq!(
+            containment_filter("V0.PegOut.recipient", "bc1qxyz").unwrap(),
+            json!({ "V0": { "PegOut": { "recipient": "bc1qxyz" } } })
+        );
+        assert_eq!(
+            containment_filter("amount", "1000").unwrap(),
+            json!({ "amount": 1000 })
+        );
+        assert_eq!(
+            containment_filter("key", "\"1000\"").unwrap(),
+            json!({ "key": "1000" })
+        );
+        assert!(containment_filter("", "x").is_err());
+        assert!(containment_filter("a..b", "x").is_err());
+    }
+}
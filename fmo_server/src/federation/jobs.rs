@@ -0,0 +1,453 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fmo_api_types::JobProgress;
+use postgres_from_row::FromRow;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query, query_one, query_opt, query_value};
+use crate::AppState;
+
+/// Progress of running jobs is written at most this often
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// Number of finished jobs listed in addition to the running ones
+const FINISHED_JOBS_LIMIT: i64 = 100;
+/// Idle job workers check for queued jobs at least this often, in case a job
+/// was queued by another instance or a notification was missed
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Advisory lock held while claiming a queued job, see [`FederationObserver::claim_job`]
+const CLAIM_JOB_LOCK: i64 = 0x666d_6f5f_6a6f_6273;
+
+/// Jobs of the federation, e.g. its initial sync or session replays, most
+/// recent first
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/backfill/status",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = Vec<JobProgress>))
+)]
+pub(super) async fn get_backfill_status(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<JobProgress>>> {
+    Ok(state
+        .federation_observer
+        .list_jobs(Some(federation_id))
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct JobRow {
+    id: i64,
+    kind: String,
+    federation_id: Option<Vec<u8>>,
+    queued: bool,
+    processed: i64,
+    total: i64,
+    started_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    finished_at: Option<NaiveDateTime>,
+    error: Option<String>,
+}
+
+/// Running job whose progress is tracked in the `jobs` table. Dropping it
+/// without calling [`Job::finish`] leaves the job unfinished until another one
+/// of the same kind and federation is started.
+#[derive(Debug)]
+pub(super) struct Job {
+    observer: FederationObserver,
+    id: i64,
+    processed: u64,
+    last_write: Instant,
+}
+
+/// Work that runs on the job workers, stored with the job so it survives
+/// restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(super) enum QueuedJob {
+    /// Re-processes the stored sessions `from..=to`
+    Replay { from: u64, to: u64 },
+}
+
+impl QueuedJob {
+    fn kind(&self) -> &'static str {
+        match self {
+            QueuedJob::Replay { .. } => "replay",
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct ClaimedJobRow {
+    id: i64,
+    federation_id: Option<Vec<u8>>,
+    processed: i64,
+    params: serde_json::Value,
+}
+
+impl FederationObserver {
+    /// Starts tracking a job processing `total` items, previous unfinished jobs
+    /// of the same kind and federation are marked as interrupted
+    pub(super) async fn start_job(
+        &self,
+        kind: &str,
+        federation_id: Option<FederationId>,
+        total: u64,
+    ) -> anyhow::Result<Job> {
+        let federation_id = federation_id.map(|id| id.consensus_encode_to_vec());
+        let now = Utc::now().naive_utc();
+
+        let conn = self.connection().await?;
+        execute(
+            &conn,
+            // language=postgresql
+            "UPDATE jobs
+             SET finished_at = $3, error = 'Interrupted'
+             WHERE kind = $1
+               AND federation_id IS NOT DISTINCT FROM $2
+               AND finished_at IS NULL",
+            &[&kind, &federation_id, &now],
+        )
+        .await?;
+        let id = query_value::<i64>(
+            &conn,
+            // language=postgresql
+            "INSERT INTO jobs (kind, federation_id, processed, total, started_at, updated_at)
+             VALUES ($1, $2, 0, $3, $4, $4)
+             RETURNING id",
+            &[&kind, &federation_id, &(total as i64), &now],
+        )
+        .await?;
+
+        Ok(Job {
+            observer: self.clone(),
+            id,
+            processed: 0,
+            last_write: Instant::now(),
+        })
+    }
+
+    /// Queues `job` processing `total` items, it's run by the next free job
+    /// worker. Jobs of the same federation run one after another.
+    pub(super) async fn enqueue_job(
+        &self,
+        federation_id: Option<FederationId>,
+        job: QueuedJob,
+        total: u64,
+    ) -> anyhow::Result<JobProgress> {
+        let now = Utc::now().naive_utc();
+        let row = query_one::<JobRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO jobs (kind, federation_id, processed, total, started_at, updated_at, params, queued)
+             VALUES ($1, $2, 0, $3, $4, $4, $5, TRUE)
+             RETURNING *",
+            &[
+                &job.kind(),
+                &federation_id.map(|id| id.consensus_encode_to_vec()),
+                &(total as i64),
+                &now,
+                &serde_json::to_value(&job)?,
+            ],
+        )
+        .await?;
+        self.job_queued.notify_one();
+
+        job_progress(row, Utc::now())
+    }
+
+    /// Puts jobs that were running when the observer was stopped back into
+    /// the queue, they continue where they left off
+    pub(super) async fn requeue_interrupted_jobs(&self) -> anyhow::Result<()> {
+        let requeued = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "UPDATE jobs SET queued = TRUE WHERE params IS NOT NULL AND finished_at IS NULL",
+            &[],
+        )
+        .await?;
+        if requeued > 0 {
+            info!(requeued, "Resuming interrupted jobs");
+        }
+        Ok(())
+    }
+
+    /// Runs queued jobs one at a time until the observer shuts down
+    pub(super) async fn run_job_worker(self) {
+        loop {
+            // Created before checking so a job queued in between isn't missed
+            let job_queued = self.job_queued.notified();
+            match self.claim_job().await {
+                Ok(Some(row)) => self.run_queued_job(row).await,
+                Ok(None) => {
+                    let _ = tokio::time::timeout(QUEUE_POLL_INTERVAL, job_queued).await;
+                }
+                Err(e) => {
+                    warn!("Failed to fetch queued job: {e:?}");
+                    sleep(QUEUE_POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Takes the oldest queued job whose federation has no other queued job
+    /// running. Claims are serialized across workers and instances, otherwise
+    /// two of them could both see a federation as idle and start jobs of it
+    /// at the same time.
+    async fn claim_job(&self) -> anyhow::Result<Option<ClaimedJobRow>> {
+        let now = Utc::now().naive_utc();
+        let mut conn = self.connection().await?;
+        let dbtx = conn.transaction().await?;
+        // Taken before the claim query so its snapshot includes all previous
+        // claims
+        execute(
+            &dbtx,
+            // language=postgresql
+            "SELECT pg_advisory_xact_lock($1)",
+            &[&CLAIM_JOB_LOCK],
+        )
+        .await?;
+        let row = query_opt::<ClaimedJobRow>(
+            &dbtx,
+            // language=postgresql
+            "UPDATE jobs
+             SET queued     = FALSE,
+                 started_at = CASE WHEN processed = 0 THEN $1 ELSE started_at END,
+                 updated_at = $1
+             WHERE id = (SELECT id
+                         FROM jobs
+                         WHERE queued
+                           AND (federation_id IS NULL OR federation_id NOT IN (
+                               SELECT federation_id
+                               FROM jobs
+                               WHERE params IS NOT NULL
+                                 AND NOT queued
+                                 AND finished_at IS NULL
+                                 AND federation_id IS NOT NULL))
+                         ORDER BY id
+                         LIMIT 1 FOR UPDATE SKIP LOCKED)
+             RETURNING id, federation_id, processed, params",
+            &[&now],
+        )
+        .await?;
+        dbtx.commit().await?;
+
+        Ok(row)
+    }
+
+    async fn run_queued_job(&self, row: ClaimedJobRow) {
+        let mut job = Job {
+            observer: self.clone(),
+            id: row.id,
+            processed: row.processed as u64,
+            last_write: Instant::now(),
+        };
+        let result = async {
+            let federation_id = row
+                .federation_id
+                .map(|id| FederationId::consensus_decode_vec(id, &Default::default()))
+                .transpose()?;
+            match serde_json::from_value::<QueuedJob>(row.params)? {
+                QueuedJob::Replay { from, to } => {
+                    let federation_id = federation_id.context("Replay job without federation")?;
+                    self.run_replay_job(federation_id, from, to, &mut job).await
+                }
+            }
+        }
+        .await;
+
+        if let Err(e) = &result {
+            warn!(job_id = row.id, "Queued job failed: {e:?}");
+        }
+        if let Err(e) = job.finish(&result).await {
+            warn!(job_id = row.id, "Failed to record end of job: {e:?}");
+        }
+    }
+
+    /// Running jobs and the most recently finished ones, optionally of only
+    /// one federation
+    pub async fn list_jobs(
+        &self,
+        federation_id: Option<FederationId>,
+    ) -> anyhow::Result<Vec<JobProgress>> {
+        let now = Utc::now();
+        query::<JobRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "(SELECT * FROM jobs
+              WHERE finished_at IS NULL
+                AND ($1::bytea IS NULL OR federation_id = $1))
+             UNION ALL
+             (SELECT * FROM jobs
+              WHERE finished_at IS NOT NULL
+                AND ($1::bytea IS NULL OR federation_id = $1)
+              ORDER BY started_at DESC
+              LIMIT $2)
+             ORDER BY started_at DESC",
+            &[
+                &federation_id.map(|id| id.consensus_encode_to_vec()),
+                &FINISHED_JOBS_LIMIT,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| job_progress(row, now))
+        .collect()
+    }
+}
+
+impl Job {
+    /// Items processed so far, more than 0 if the job was resumed after a
+    /// restart
+    pub(super) fn processed(&self) -> u64 {
+        self.processed
+    }
+
+    /// Records that `processed` items are done, only written to the database
+    /// every few seconds
+    pub(super) async fn progress(&mut self, processed: u64) -> anyhow::Result<()> {
+        self.processed = processed;
+        if self.last_write.elapsed() < PROGRESS_INTERVAL {
+            return Ok(());
+        }
+
+        self.write(None).await?;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+
+    pub(super) async fn advance(&mut self) -> anyhow::Result<()> {
+        self.progress(self.processed + 1).await
+    }
+
+    /// Marks the job as finished, failed if `result` is an error
+    pub(super) async fn finish<T>(self, result: &anyhow::Result<T>) -> anyhow::Result<()> {
+        let error = result.as_ref().err().map(|e| format!("{e:#}"));
+        self.write(Some(error)).await
+    }
+
+    async fn write(&self, finished: Option<Option<String>>) -> anyhow::Result<()> {
+        let now = Utc::now().naive_utc();
+        let (finished_at, error) = match finished {
+            Some(error) => (Some(now), error),
+            None => (None, None),
+        };
+        execute(
+            &self.observer.connection().await?,
+            // language=postgresql
+            "UPDATE jobs SET processed = $2, updated_at = $3, finished_at = $4, error = $5 WHERE id = $1",
+            &[
+                &self.id,
+                &(self.processed as i64),
+                &now,
+                &finished_at,
+                &error,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn job_progress(row: JobRow, now: DateTime<Utc>) -> anyhow::Result<JobProgress> {
+    let started_at = row.started_at.and_utc();
+    let updated_at = row.updated_at.and_utc();
+    let finished_at = row.finished_at.map(|finished_at| finished_at.and_utc());
+    let processed = row.processed as u64;
+    let total = row.total as u64;
+    let (percent_complete, rate, eta) = estimate(processed, total, started_at, updated_at);
+
+    Ok(JobProgress {
+        id: row.id as u64,
+        kind: row.kind,
+        federation_id: row
+            .federation_id
+            .map(|id| FederationId::consensus_decode_vec(id, &Default::default()))
+            .transpose()?,
+        queued: row.queued,
+        processed,
+        total,
+        percent_complete,
+        rate,
+        // An ETA in the past means the job is slower than it used to be, but
+        // it's still the best guess
+        eta: eta
+            .filter(|_| finished_at.is_none())
+            .map(|eta| eta.max(now)),
+        started_at,
+        updated_at,
+        finished_at,
+        error: row.error,
+    })
+}
+
+/// Percentage done, items per second and estimated completion time assuming
+/// the average rate so far stays the same
+fn estimate(
+    processed: u64,
+    total: u64,
+    started_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> (f64, Option<f64>, Option<DateTime<Utc>>) {
+    let percent_complete = if total == 0 {
+        100.0
+    } else {
+        (processed as f64 / total as f64 * 100.0).min(100.0)
+    };
+
+    let elapsed = (updated_at - started_at).num_milliseconds() as f64 / 1000.0;
+    if elapsed <= 0.0 || processed == 0 {
+        return (percent_complete, None, None);
+    }
+    let rate = processed as f64 / elapsed;
+    let remaining_secs = total.saturating_sub(processed) as f64 / rate;
+    let eta = updated_at + chrono::Duration::milliseconds((remaining_secs * 1000.0) as i64);
+
+    (percent_complete, Some(rate), Some(eta))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{estimate, QueuedJob};
+
+    #[test]
+    fn test_estimate() {
+        let start = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let after = |secs: i64| start + chrono::Duration::seconds(secs);
+
+        assert_eq!(estimate(0, 100, start, start), (0.0, None, None));
+        assert_eq!(estimate(0, 0, start, start), (100.0, None, None));
+        assert_eq!(
+            estimate(25, 100, start, after(50)),
+            (25.0, Some(0.5), Some(after(200)))
+        );
+        assert_eq!(
+            estimate(100, 100, start, after(10)),
+            (100.0, Some(10.0), Some(after(10)))
+        );
+    }
+
+    #[test]
+    fn test_queued_job_params() {
+        // Stored with the job, changing the format breaks resuming jobs
+        // queued by an older version
+        let job = QueuedJob::Replay { from: 10, to: 20 };
+        let params = serde_json::json!({ "kind": "replay", "from": 10, "to": 20 });
+
+        assert_eq!(serde_json::to_value(&job).unwrap(), params);
+        assert_eq!(serde_json::from_value::<QueuedJob>(params).unwrap(), job);
+    }
+}
@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDateTime;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use futures::StreamExt;
+use serde::Deserialize;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::Row;
+use utoipa::IntoParams;
+
+use crate::AppState;
+
+const DEFAULT_WINDOW: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const MAX_WINDOW: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+/// Health checks run once per minute, smaller buckets would be mostly empty
+const MIN_BUCKET: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct LatencyCsvParams {
+    /// How far to look back, e.g. `30d`, `12h` or `90m`, defaults to 30 days
+    window: Option<String>,
+    /// Aggregate samples into buckets of this size, e.g. `1h`. Raw samples are
+    /// returned if not set.
+    bucket: Option<String>,
+}
+
+/// Latency samples of one guardian as measured by the health monitor. Failed
+/// health checks are recorded with the request timeout as latency and have no
+/// block height, bucketed latency statistics only include successful checks.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/guardians/{peer_id}/latency.csv",
+    tag = "federations",
+    params(
+        ("federation_id" = String, Path, description = "Hex encoded federation id"),
+        ("peer_id" = u16, Path, description = "Guardian id"),
+        LatencyCsvParams
+    ),
+    responses((status = 200, description = "Latency samples as CSV", content_type = "text/csv"))
+)]
+pub(super) async fn get_guardian_latency_csv(
+    Path((federation_id, peer_id)): Path<(FederationId, u16)>,
+    Query(params): Query<LatencyCsvParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Response> {
+    let window = params
+        .window
+        .as_deref()
+        .map(parse_duration)
+        .transpose()?
+        .unwrap_or(DEFAULT_WINDOW);
+    ensure!(window <= MAX_WINDOW, "Window can be at most 365 days");
+    let bucket = params.bucket.as_deref().map(parse_duration).transpose()?;
+    if let Some(bucket) = bucket {
+        ensure!(bucket >= MIN_BUCKET, "Buckets have to be at least 1 minute");
+    }
+
+    let federation = state
+        .federation_observer
+        .get_federation(federation_id)
+        .await?
+        .context("Federation doesn't exist")?;
+    ensure!(
+        federation
+            .config
+            .global
+            .api_endpoints
+            .keys()
+            .any(|peer| peer.to_usize() == usize::from(peer_id)),
+        "Guardian doesn't exist"
+    );
+
+    let since = chrono::Utc::now().naive_utc() - chrono::Duration::from_std(window)?;
+    let conn = state.federation_observer.connection().await?;
+
+    let (header, sql): (&[&str], &str) = match bucket {
+        None => (
+            &["time", "latency_ms", "online", "block_height"],
+            // language=postgresql
+            "SELECT time,
+                    latency_ms::bigint   AS latency_ms,
+                    status IS NOT NULL   AS online,
+                    block_height::bigint AS block_height
+             FROM guardian_health
+             WHERE federation_id = $1
+               AND guardian_id = $2
+               AND time > $3
+             ORDER BY time",
+        ),
+        Some(_) => (
+            &[
+                "bucket_start",
+                "samples",
+                "online_samples",
+                "min_ms",
+                "avg_ms",
+                "p50_ms",
+                "p95_ms",
+                "max_ms",
+            ],
+            // language=postgresql
+            "SELECT (TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM time)::double precision / $4::double precision)
+                                  * $4::double precision) AT TIME ZONE 'UTC')                   AS bucket_start,
+                    COUNT(*)::bigint                                                          AS samples,
+                    COUNT(status)::bigint                                                     AS online_samples,
+                    (MIN(latency_ms) FILTER (WHERE block_height IS NOT NULL))::bigint         AS min_ms,
+                    (AVG(latency_ms) FILTER (WHERE block_height IS NOT NULL))::double precision AS avg_ms,
+                    PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY latency_ms)
+                    FILTER (WHERE block_height IS NOT NULL)                                   AS p50_ms,
+                    PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms)
+                    FILTER (WHERE block_height IS NOT NULL)                                   AS p95_ms,
+                    (MAX(latency_ms) FILTER (WHERE block_height IS NOT NULL))::bigint         AS max_ms
+             FROM guardian_health
+             WHERE federation_id = $1
+               AND guardian_id = $2
+               AND time > $3
+             GROUP BY bucket_start
+             ORDER BY bucket_start",
+        ),
+    };
+
+    let federation_id_bytes = federation_id.consensus_encode_to_vec();
+    let guardian_id = i32::from(peer_id);
+    let bucket_secs = bucket.map(|bucket| bucket.as_secs_f64());
+    let mut params: Vec<&(dyn ToSql + Sync)> = vec![&federation_id_bytes, &guardian_id, &since];
+    if let Some(bucket_secs) = &bucket_secs {
+        params.push(bucket_secs);
+    }
+    let rows = conn.query_raw(sql, params).await?;
+
+    let header = csv_record(header.iter().map(|column| column.to_string()))?;
+    let csv = async_stream::stream! {
+        // Keep the connection alive while streaming
+        let _conn = conn;
+        yield Ok::<_, anyhow::Error>(header);
+
+        let mut rows = std::pin::pin!(rows);
+        while let Some(row) = rows.next().await {
+            let record = row.map_err(anyhow::Error::from).and_then(|row| {
+                let time = row.try_get::<_, NaiveDateTime>(0)?.and_utc().to_rfc3339();
+                let fields = (1..row.len()).map(|idx| csv_field(&row, idx)).collect::<anyhow::Result<Vec<_>>>()?;
+                csv_record(std::iter::once(time).chain(fields))
+            });
+            let failed = record.is_err();
+            yield record;
+            if failed {
+                break;
+            }
+        }
+    };
+
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv".to_owned()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{federation_id}-guardian-{peer_id}-latency.csv\""),
+            ),
+        ],
+        Body::from_stream(csv),
+    )
+        .into_response())
+}
+
+/// Formats a numeric or boolean column, `NULL`s become empty fields
+fn csv_field(row: &Row, idx: usize) -> anyhow::Result<String> {
+    let column_type = row.columns()[idx].type_();
+    let value = if *column_type == Type::INT8 {
+        row.try_get::<_, Option<i64>>(idx)?
+            .map(|value| value.to_string())
+    } else if *column_type == Type::FLOAT8 {
+        row.try_get::<_, Option<f64>>(idx)?
+            .map(|value| format!("{value:.1}"))
+    } else if *column_type == Type::BOOL {
+        row.try_get::<_, Option<bool>>(idx)?
+            .map(|value| value.to_string())
+    } else {
+        bail!("Unexpected column type {column_type}")
+    };
+
+    Ok(value.unwrap_or_default())
+}
+
+fn csv_record(fields: impl IntoIterator<Item = String>) -> anyhow::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(fields)?;
+    Ok(writer.into_inner()?)
+}
+
+/// Parses durations like `30d`, `12h`, `90m` or `600s`
+fn parse_duration(duration: &str) -> anyhow::Result<Duration> {
+    let duration = duration.trim();
+    let unit_start = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .context("Duration needs a unit (d, h, m or s)")?;
+    let (value, unit) = duration.split_at(unit_start);
+    let value = value
+        .parse::<u64>()
+        .with_context(|| format!("Invalid duration {duration}"))?;
+
+    let unit_secs = match unit {
+        "d" => 24 * 60 * 60,
+        "h" => 60 * 60,
+        "m" => 60,
+        "s" => 1,
+        _ => bail!("Unknown duration unit {unit}, expected d, h, m or s"),
+    };
+    ensure!(value > 0, "Duration has to be positive");
+
+    Ok(Duration::from_secs(value.saturating_mul(unit_secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{csv_record, parse_duration};
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(30 * 24 * 60 * 60)
+        );
+        assert_eq!(
+            parse_duration("12h").unwrap(),
+            Duration::from_secs(12 * 60 * 60)
+        );
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(parse_duration("600s").unwrap(), Duration::from_secs(600));
+
+        for invalid in ["", "30", "d", "0d", "1w", "-1d", "1.5h"] {
+            assert!(parse_duration(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_csv_record() {
+        assert_eq!(
+            csv_record([
+                "2024-01-01T00:00:00+00:00".to_owned(),
+                "".to_owned(),
+                "a,b".to_owned()
+            ])
+            .unwrap(),
+            b"2024-01-01T00:00:00+00:00,,\"a,b\"\n".to_vec()
+        );
+    }
+}
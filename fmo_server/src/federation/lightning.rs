@@ -0,0 +1,151 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::Amount;
+use fmo_api_types::{LightningContractStats, LightningStats};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/ln/stats",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = LightningStats))
+)]
+pub(super) async fn get_federation_ln_stats(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<LightningStats>> {
+    Ok(state
+        .federation_observer
+        .federation_ln_stats(federation_id)
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct ContractStatsRow {
+    contract_type: String,
+    funded: i64,
+    settled: i64,
+    cancelled: i64,
+    total_amount_msat: i64,
+}
+
+impl FederationObserver {
+    /// Correlates funded lightning contracts with the inputs spending them
+    /// and the gateway's cancellations. Spent contracts that weren't cancelled
+    /// count as settled. The claim preimage isn't stored, so refunds after a
+    /// timeout or of a failed incoming payment count as settled too.
+    pub async fn federation_ln_stats(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<LightningStats> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let rows = query::<ContractStatsRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT c.type                                                   AS contract_type,
+                    COUNT(*)::bigint                                         AS funded,
+                    (COUNT(*) FILTER (WHERE c.spent AND NOT c.cancelled))::bigint AS settled,
+                    (COUNT(*) FILTER (WHERE c.cancelled))::bigint            AS cancelled,
+                    COALESCE(SUM(c.amount_msat), 0)::bigint                  AS total_amount_msat
+             FROM (SELECT lc.type,
+                          fund.amount_msat,
+                          EXISTS(SELECT 1
+                                 FROM transaction_inputs ti
+                                 WHERE ti.federation_id = lc.federation_id
+                                   AND ti.ln_contract_id = lc.contract_id) AS spent,
+                          EXISTS(SELECT 1
+                                 FROM transaction_outputs cancel
+                                 WHERE cancel.federation_id = lc.federation_id
+                                   AND cancel.ln_contract_id = lc.contract_id
+                                   AND cancel.ln_contract_interaction_kind = 'cancel') AS cancelled
+                   FROM ln_contracts lc
+                            JOIN transaction_outputs fund
+                                 ON fund.federation_id = lc.federation_id AND
+                                    fund.ln_contract_id = lc.contract_id AND
+                                    fund.ln_contract_interaction_kind = 'fund'
+                   WHERE lc.federation_id = $1) c
+             GROUP BY c.type",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let stats_of = |contract_type: &str| {
+            contract_stats(rows.iter().find(|row| row.contract_type == contract_type))
+        };
+
+        Ok(LightningStats {
+            incoming: stats_of("incoming"),
+            outgoing: stats_of("outgoing"),
+        })
+    }
+}
+
+fn contract_stats(row: Option<&ContractStatsRow>) -> LightningContractStats {
+    let Some(row) = row else {
+        return LightningContractStats {
+            funded: 0,
+            settled: 0,
+            cancelled: 0,
+            pending: 0,
+            total_amount: Amount::ZERO,
+            avg_amount: Amount::ZERO,
+            success_rate: None,
+        };
+    };
+
+    let funded = row.funded as u64;
+    let settled = row.settled as u64;
+    let cancelled = row.cancelled as u64;
+    let pending = funded.saturating_sub(settled + cancelled);
+    let total_amount = Amount::from_msats(row.total_amount_msat as u64);
+    let resolved = settled + cancelled;
+
+    LightningContractStats {
+        funded,
+        settled,
+        cancelled,
+        pending,
+        total_amount,
+        avg_amount: Amount::from_msats(total_amount.msats.checked_div(funded).unwrap_or(0)),
+        success_rate: (resolved > 0).then(|| settled as f64 / resolved as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::Amount;
+
+    use super::{contract_stats, ContractStatsRow};
+
+    #[test]
+    fn test_contract_stats() {
+        let empty = contract_stats(None);
+        assert_eq!(empty.funded, 0);
+        assert_eq!(empty.avg_amount, Amount::ZERO);
+        assert_eq!(empty.success_rate, None);
+
+        let row = ContractStatsRow {
+            contract_type: "outgoing".to_owned(),
+            funded: 10,
+            settled: 6,
+            cancelled: 2,
+            total_amount_msat: 25_000,
+        };
+        let stats = contract_stats(Some(&row));
+        assert_eq!(stats.pending, 2);
+        assert_eq!(stats.avg_amount, Amount::from_msats(2_500));
+        assert_eq!(stats.success_rate, Some(0.75));
+    }
+}
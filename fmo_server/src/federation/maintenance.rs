@@ -0,0 +1,234 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Context};
+use chrono::{NaiveDateTime, NaiveTime, Utc};
+use fmo_api_types::DbTableStats;
+use postgres_from_row::FromRow;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Tables with more rows modified since they were last analyzed are analyzed
+/// right away instead of waiting for autovacuum, e.g. after a backfill
+const ANALYZE_MODIFIED_ROWS: i64 = 100_000;
+/// Minimum time between two vacuum runs, so a window spanning midnight doesn't
+/// trigger two runs
+const MIN_VACUUM_INTERVAL: Duration = Duration::from_secs(20 * 60 * 60);
+
+/// Daily time window in UTC in which `VACUUM (ANALYZE)` runs once, configured
+/// as `FO_VACUUM_WINDOW=02:00-04:00`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct VacuumWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl VacuumWindow {
+    pub(super) fn from_env() -> anyhow::Result<Option<VacuumWindow>> {
+        dotenv::var("FO_VACUUM_WINDOW")
+            .ok()
+            .map(|window| parse_vacuum_window(&window).context("Invalid FO_VACUUM_WINDOW"))
+            .transpose()
+    }
+
+    /// Windows may span midnight, e.g. `23:00-01:00`
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+fn parse_vacuum_window(window: &str) -> anyhow::Result<VacuumWindow> {
+    let (start, end) = window
+        .split_once('-')
+        .context("Expected <start>-<end>, e.g. 02:00-04:00")?;
+    let parse = |time: &str| {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .with_context(|| format!("Invalid time {time}, expected HH:MM"))
+    };
+    let window = VacuumWindow {
+        start: parse(start)?,
+        end: parse(end)?,
+    };
+    ensure!(window.start != window.end, "The window must not be empty");
+    Ok(window)
+}
+
+/// Bytes of `bytes` taken up by dead rows, assuming they are the same size as
+/// live ones
+fn estimate_bloat(bytes: i64, live_rows: i64, dead_rows: i64) -> u64 {
+    let rows = live_rows + dead_rows;
+    if rows <= 0 || bytes <= 0 {
+        return 0;
+    }
+    (bytes as f64 * dead_rows.max(0) as f64 / rows as f64) as u64
+}
+
+impl FederationObserver {
+    /// Analyzes tables after large changes and vacuums the database in the
+    /// configured window, so query plans stay good on long-lived instances
+    pub(super) async fn maintain_database(self, vacuum_window: Option<VacuumWindow>) {
+        let mut last_vacuum: Option<Instant> = None;
+        loop {
+            if let Err(e) = self
+                .maintain_database_inner(vacuum_window, &mut last_vacuum)
+                .await
+            {
+                warn!("Error during database maintenance: {e:?}");
+            }
+            sleep(MAINTENANCE_INTERVAL).await;
+        }
+    }
+
+    async fn maintain_database_inner(
+        &self,
+        vacuum_window: Option<VacuumWindow>,
+        last_vacuum: &mut Option<Instant>,
+    ) -> anyhow::Result<()> {
+        let vacuum_due = vacuum_window.is_some_and(|window| {
+            window.contains(Utc::now().time())
+                && last_vacuum.map_or(true, |last| last.elapsed() >= MIN_VACUUM_INTERVAL)
+        });
+        if vacuum_due {
+            info!("Running VACUUM (ANALYZE) in the maintenance window");
+            let start = Instant::now();
+            self.connection()
+                .await?
+                .batch_execute("VACUUM (ANALYZE)")
+                .await?;
+            *last_vacuum = Some(Instant::now());
+            info!(
+                "VACUUM (ANALYZE) completed in {:.2}s",
+                start.elapsed().as_secs_f64()
+            );
+            return Ok(());
+        }
+
+        #[derive(Debug, FromRow)]
+        struct ModifiedTableRow {
+            table_name: String,
+            modified_rows: i64,
+        }
+
+        let connection = self.connection().await?;
+        let tables = query::<ModifiedTableRow>(
+            &connection,
+            // language=postgresql
+            "SELECT relname::text AS table_name, n_mod_since_analyze AS modified_rows
+             FROM pg_stat_user_tables
+             WHERE n_mod_since_analyze > $1",
+            &[&ANALYZE_MODIFIED_ROWS],
+        )
+        .await?;
+        for table in tables {
+            info!(
+                "Analyzing {} after {} modified rows",
+                table.table_name, table.modified_rows
+            );
+            connection
+                .batch_execute(&format!(
+                    "ANALYZE \"{}\"",
+                    table.table_name.replace('"', "\"\"")
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Size, estimated bloat and maintenance state of all tables, largest
+    /// first. Bloat is estimated from the share of dead rows since exact
+    /// numbers need a full table scan.
+    pub async fn db_stats(&self) -> anyhow::Result<Vec<DbTableStats>> {
+        #[derive(Debug, FromRow)]
+        struct TableStatsRow {
+            table_name: String,
+            live_rows: i64,
+            dead_rows: i64,
+            modified_rows: i64,
+            table_bytes: i64,
+            index_bytes: i64,
+            last_vacuum: Option<NaiveDateTime>,
+            last_analyze: Option<NaiveDateTime>,
+        }
+
+        Ok(query::<TableStatsRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT relname::text                                                        AS table_name,
+                    n_live_tup                                                           AS live_rows,
+                    n_dead_tup                                                           AS dead_rows,
+                    n_mod_since_analyze                                                  AS modified_rows,
+                    pg_table_size(relid)                                                 AS table_bytes,
+                    pg_indexes_size(relid)                                               AS index_bytes,
+                    (GREATEST(last_vacuum, last_autovacuum) AT TIME ZONE 'UTC')::timestamp   AS last_vacuum,
+                    (GREATEST(last_analyze, last_autoanalyze) AT TIME ZONE 'UTC')::timestamp AS last_analyze
+             FROM pg_stat_user_tables
+             ORDER BY pg_total_relation_size(relid) DESC",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| DbTableStats {
+            estimated_table_bloat_bytes: estimate_bloat(
+                row.table_bytes,
+                row.live_rows,
+                row.dead_rows,
+            ),
+            estimated_index_bloat_bytes: estimate_bloat(
+                row.index_bytes,
+                row.live_rows,
+                row.dead_rows,
+            ),
+            table: row.table_name,
+            live_rows: row.live_rows.max(0) as u64,
+            dead_rows: row.dead_rows.max(0) as u64,
+            modified_since_analyze: row.modified_rows.max(0) as u64,
+            table_bytes: row.table_bytes.max(0) as u64,
+            index_bytes: row.index_bytes.max(0) as u64,
+            last_vacuum: row.last_vacuum.map(|time| time.and_utc()),
+            last_analyze: row.last_analyze.map(|time| time.and_utc()),
+        })
+        .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::{estimate_bloat, parse_vacuum_window};
+
+    #[test]
+    fn test_vacuum_window() {
+        let time = |hour, minute| NaiveTime::from_hms_opt(hour, minute, 0).unwrap();
+
+        let window = parse_vacuum_window("02:00-04:30").unwrap();
+        assert!(window.contains(time(2, 0)));
+        assert!(window.contains(time(4, 29)));
+        assert!(!window.contains(time(4, 30)));
+        assert!(!window.contains(time(1, 59)));
+
+        let overnight = parse_vacuum_window("23:00 - 01:00").unwrap();
+        assert!(overnight.contains(time(23, 30)));
+        assert!(overnight.contains(time(0, 30)));
+        assert!(!overnight.contains(time(12, 0)));
+
+        assert!(parse_vacuum_window("02:00").is_err());
+        assert!(parse_vacuum_window("02:00-02:00").is_err());
+        assert!(parse_vacuum_window("2am-4am").is_err());
+    }
+
+    #[test]
+    fn test_estimate_bloat() {
+        assert_eq!(estimate_bloat(1000, 75, 25), 250);
+        assert_eq!(estimate_bloat(1000, 100, 0), 0);
+        assert_eq!(estimate_bloat(1000, 0, 0), 0);
+    }
+}
@@ -1,17 +1,47 @@
-use anyhow::Context;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
 use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::Json;
+use chrono::{NaiveDateTime, Utc};
 use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{MetaHistoryEntry, MetaLint};
+use postgres_from_row::FromRow;
+use tracing::{info, warn};
+
+use crate::config::meta::{ConsensusMetaCache, MetaFields, MetaOverrideCache};
+use crate::federation::observer::FederationObserver;
+use crate::meta::{federation_meta, lint_federation_meta, meta_for_history};
+use crate::util::{config_to_json, execute, query};
 
-use crate::config::meta::MetaFields;
-use crate::meta::federation_meta;
-use crate::util::config_to_json;
+/// Override files are refreshed hourly, checking more often wouldn't find more
+/// changes
+const META_HISTORY_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 // FIXME: cache meta in DB
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/meta",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses(
+        (status = 200, description = "Meta fields of the federation", body = Object, headers(
+            ("etag" = String, description = "Hash of the meta fields for conditional requests"),
+            ("x-meta-override-status" = String, description = "`fresh`, `stale` or `failed` if the federation uses a meta override file"),
+            ("x-meta-override-error" = String, description = "Why the meta override file couldn't be fetched"),
+        )),
+        (status = 304, description = "Meta fields match the `If-None-Match` header")
+    )
+)]
 pub(super) async fn get_federation_meta(
     Path(federation_id): Path<FederationId>,
     State(state): State<crate::AppState>,
-) -> crate::error::Result<Json<MetaFields>> {
+    headers: HeaderMap,
+) -> crate::error::Result<Response> {
     let config = state
         .federation_observer
         .get_federation(federation_id)
@@ -19,5 +49,218 @@ pub(super) async fn get_federation_meta(
         .context("Federation not observed, you might want to try /config/:federation_invite")?
         .config;
 
-    federation_meta(&config_to_json(config)?, &state).await
+    federation_meta(&config_to_json(config)?, &state, &headers).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/meta/lint",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Problems found in the federation's meta fields", body = MetaLint))
+)]
+pub(super) async fn get_federation_meta_lint(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<crate::AppState>,
+) -> crate::error::Result<Json<MetaLint>> {
+    let config = state
+        .federation_observer
+        .get_federation(federation_id)
+        .await?
+        .context("Federation doesn't exist")?
+        .config;
+
+    Ok(lint_federation_meta(&config_to_json(config)?, &state)
+        .await
+        .into())
+}
+
+/// All recorded versions of the federation's meta fields, newest first. The
+/// fields are checked hourly, so short-lived changes may be missing.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/meta/history",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Meta field changes, newest first", body = Vec<MetaHistoryEntry>))
+)]
+pub(super) async fn get_federation_meta_history(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<crate::AppState>,
+) -> crate::error::Result<Json<Vec<MetaHistoryEntry>>> {
+    Ok(state
+        .federation_observer
+        .meta_history(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn meta_history(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<MetaHistoryEntry>> {
+        #[derive(Debug, FromRow)]
+        struct MetaHistoryRow {
+            recorded_at: NaiveDateTime,
+            meta: serde_json::Value,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let rows = query::<MetaHistoryRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT recorded_at, meta FROM meta_history WHERE federation_id = $1 ORDER BY recorded_at",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let mut previous: Option<MetaFields> = None;
+        let mut history = Vec::with_capacity(rows.len());
+        for row in rows {
+            let meta = serde_json::from_value::<MetaFields>(row.meta)?;
+            history.push(MetaHistoryEntry {
+                recorded_at: row.recorded_at.and_utc(),
+                changed_fields: previous
+                    .as_ref()
+                    .map(|previous| changed_fields(previous, &meta))
+                    .unwrap_or_default(),
+                meta: meta.clone(),
+            });
+            previous = Some(meta);
+        }
+        history.reverse();
+
+        Ok(history)
+    }
+
+    /// Latest recorded meta fields of all federations, used for summaries so
+    /// they don't have to fetch override files or consensus meta per request
+    pub(super) async fn latest_meta(&self) -> anyhow::Result<BTreeMap<FederationId, MetaFields>> {
+        #[derive(Debug, FromRow)]
+        struct LatestMetaRow {
+            federation_id: Vec<u8>,
+            meta: serde_json::Value,
+        }
+
+        query::<LatestMetaRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT DISTINCT ON (federation_id) federation_id, meta
+             FROM meta_history
+             ORDER BY federation_id, recorded_at DESC",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            let federation_id = FederationId(bitcoin::hashes::Hash::from_byte_array(
+                row.federation_id
+                    .try_into()
+                    .map_err(|_| anyhow!("Invalid federation id in DB"))?,
+            ));
+            Ok((federation_id, serde_json::from_value(row.meta)?))
+        })
+        .collect()
+    }
+
+    pub(super) async fn record_meta_history(
+        self,
+        meta_override_cache: MetaOverrideCache,
+        consensus_meta_cache: ConsensusMetaCache,
+    ) {
+        loop {
+            if let Err(e) = self
+                .record_meta_history_inner(&meta_override_cache, &consensus_meta_cache)
+                .await
+            {
+                warn!("Error while recording meta history: {e:?}");
+            }
+            tokio::time::sleep(META_HISTORY_INTERVAL).await;
+        }
+    }
+
+    async fn record_meta_history_inner(
+        &self,
+        meta_override_cache: &MetaOverrideCache,
+        consensus_meta_cache: &ConsensusMetaCache,
+    ) -> anyhow::Result<()> {
+        for federation in self.list_active_federations().await? {
+            let Some(meta) = meta_for_history(
+                &config_to_json(federation.config)?,
+                meta_override_cache,
+                consensus_meta_cache,
+            )
+            .await
+            else {
+                continue;
+            };
+
+            let inserted = execute(
+                &self.connection().await?,
+                // language=postgresql
+                "INSERT INTO meta_history (federation_id, recorded_at, meta)
+                 SELECT $1, $2, $3::jsonb
+                 WHERE $3::jsonb IS DISTINCT FROM (SELECT meta
+                                                   FROM meta_history
+                                                   WHERE federation_id = $1
+                                                   ORDER BY recorded_at DESC
+                                                   LIMIT 1)",
+                &[
+                    &federation.federation_id.consensus_encode_to_vec(),
+                    &Utc::now().naive_utc(),
+                    &serde_json::to_value(&meta)?,
+                ],
+            )
+            .await?;
+            if inserted > 0 {
+                info!(federation_id = %federation.federation_id, "Recorded changed meta fields");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fields that were added, removed or changed, sorted by name
+fn changed_fields(previous: &MetaFields, current: &MetaFields) -> Vec<String> {
+    previous
+        .keys()
+        .chain(current.keys())
+        .filter(|field| previous.get(*field) != current.get(*field))
+        .cloned()
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::changed_fields;
+    use crate::config::meta::MetaFields;
+
+    #[test]
+    fn test_changed_fields() {
+        let previous = MetaFields::from([
+            ("federation_name".to_owned(), json!("Old")),
+            ("welcome_message".to_owned(), json!("Hi")),
+            ("vetted_gateways".to_owned(), json!(["a"])),
+        ]);
+        let current = MetaFields::from([
+            ("federation_name".to_owned(), json!("New")),
+            ("vetted_gateways".to_owned(), json!(["a"])),
+            ("fee_schedule".to_owned(), json!({})),
+        ]);
+
+        assert_eq!(
+            changed_fields(&previous, &current),
+            vec!["federation_name", "fee_schedule", "welcome_message"]
+        );
+        assert!(changed_fields(&current, &current).is_empty());
+    }
 }
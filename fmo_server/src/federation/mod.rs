@@ -1,51 +1,329 @@
+pub(crate) mod alert_rules;
+mod alerts;
+mod api_keys;
+mod card;
+mod catch_up;
+mod certificates;
+mod completeness;
+mod cursors;
 pub mod db;
+mod deactivation;
+mod denominations;
+mod deposits;
+mod digest;
+mod divergence;
+mod dump;
+mod export;
+mod fees;
+mod gateways;
+mod graph;
 mod guardians;
+mod health;
+mod incidents;
+mod items;
+mod jobs;
+mod latency;
+mod lightning;
+mod maintenance;
 mod meta;
 pub(crate) mod nostr;
 pub mod observer;
+mod observer_status;
+mod privacy;
+mod probes;
+mod processing_errors;
+mod quarantine;
+pub(crate) mod readiness;
+mod replay;
+mod risk;
+mod search;
 mod session;
+mod shutdown;
+mod signing_latency;
+pub(crate) mod snapshot;
+mod solvency;
+mod summary_cache;
+mod timeseries;
 mod transaction;
+pub(crate) mod versions;
+pub(crate) mod views;
+mod withdrawals;
+
+use std::str::FromStr;
 
 use anyhow::Context;
-use axum::extract::{Path, State};
-use axum::routing::{get, put};
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
-use axum_auth::AuthBearer;
-use fedimint_core::config::{ClientConfig, FederationId, JsonClientConfig};
+use fedimint_core::config::{ClientConfig, FederationId};
 use fedimint_core::core::ModuleInstanceId;
 use fedimint_core::invite_code::InviteCode;
 use fedimint_core::module::registry::ModuleDecoderRegistry;
-use fmo_api_types::{FederationSummary, FedimintTotals};
+use fmo_api_types::rating::{assign_rating_indices, sort_by_rating_index};
+use fmo_api_types::{FederationSummary, FedimintTotals, RatingAlgorithm};
+use serde::Deserialize;
 use serde_json::json;
+use utoipa::{IntoParams, OpenApi};
 
-use crate::federation::guardians::get_federation_health;
-use crate::federation::meta::get_federation_meta;
-use crate::federation::session::{count_sessions, list_sessions};
+use crate::etag::json_with_etag;
+use crate::federation::alerts::{get_alert_settings, get_federation_alerts, put_alert_settings};
+use crate::federation::card::get_federation_card;
+use crate::federation::completeness::get_federation_completeness;
+use crate::federation::cursors::{delete_export_cursor, list_export_cursors};
+use crate::federation::deactivation::delete_observed_federation;
+use crate::federation::denominations::get_mint_denominations;
+use crate::federation::deposits::list_deposits;
+use crate::federation::divergence::get_session_divergence;
+use crate::federation::export::export_transactions;
+use crate::federation::fees::get_federation_fees;
+use crate::federation::gateways::get_federation_gateways;
+use crate::federation::graph::get_transaction_graph;
+use crate::federation::guardians::{
+    get_federation_health, get_federation_health_history, get_federation_peer_contribution,
+};
+use crate::federation::health::get_federation_health_events;
+use crate::federation::incidents::get_federation_incidents;
+use crate::federation::items::search_items;
+use crate::federation::jobs::get_backfill_status;
+use crate::federation::latency::get_guardian_latency_csv;
+use crate::federation::lightning::get_federation_ln_stats;
+use crate::federation::meta::{
+    get_federation_meta, get_federation_meta_history, get_federation_meta_lint,
+};
+use crate::federation::nostr::get_federation_announcement;
+use crate::federation::observer_status::{get_observer_status, restart_observer};
+use crate::federation::privacy::get_privacy_score;
+use crate::federation::probes::get_health_probe_results;
+use crate::federation::replay::replay_sessions;
+use crate::federation::search::search_federations;
+use crate::federation::session::{count_sessions, get_session, list_sessions};
+use crate::federation::shutdown::{delete_federation_shutdown, put_federation_shutdown};
+use crate::federation::solvency::get_federation_solvency;
+use crate::federation::timeseries::get_federation_timeseries;
 use crate::federation::transaction::{
-    count_transactions, list_transactions, transaction, transaction_histogram,
+    count_transactions, list_transactions, network_activity, transaction, transaction_histogram,
+};
+use crate::federation::withdrawals::{
+    get_withdrawal_concentration, get_withdrawal_concentration_history,
+    get_withdrawal_transactions, get_withdrawals,
 };
 use crate::util::{config_to_json, get_decoders};
 use crate::{federation, AppState};
 
+/// OpenAPI description of the routes in [`get_federations_routes`]
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_observed_federations,
+        add_observed_federation,
+        get_federation_totals,
+        search::search_federations,
+        items::search_items,
+        publish_rating_event,
+        get_federation_overview,
+        add_observed_federation_by_invite,
+        deactivation::delete_observed_federation,
+        get_federation_config,
+        meta::get_federation_meta,
+        meta::get_federation_meta_lint,
+        meta::get_federation_meta_history,
+        card::get_federation_card,
+        guardians::get_federation_health,
+        guardians::get_federation_health_history,
+        health::get_federation_health_events,
+        probes::get_health_probe_results,
+        incidents::get_federation_incidents,
+        shutdown::put_federation_shutdown,
+        shutdown::delete_federation_shutdown,
+        observer_status::get_observer_status,
+        observer_status::restart_observer,
+        jobs::get_backfill_status,
+        divergence::get_session_divergence,
+        alerts::get_federation_alerts,
+        alerts::get_alert_settings,
+        alerts::put_alert_settings,
+        guardians::get_federation_peer_contribution,
+        latency::get_guardian_latency_csv,
+        transaction::list_transactions,
+        transaction::transaction,
+        graph::get_transaction_graph,
+        transaction::count_transactions,
+        transaction::transaction_histogram,
+        transaction::network_activity,
+        timeseries::get_federation_timeseries,
+        export::export_transactions,
+        cursors::list_export_cursors,
+        cursors::delete_export_cursor,
+        get_federation_utxos,
+        fees::get_federation_fees,
+        denominations::get_mint_denominations,
+        gateways::get_federation_gateways,
+        lightning::get_federation_ln_stats,
+        privacy::get_privacy_score,
+        solvency::get_federation_solvency,
+        nostr::get_federation_announcement,
+        completeness::get_federation_completeness,
+        deposits::list_deposits,
+        withdrawals::get_withdrawals,
+        withdrawals::get_withdrawal_concentration,
+        withdrawals::get_withdrawal_concentration_history,
+        withdrawals::get_withdrawal_transactions,
+        session::list_sessions,
+        session::count_sessions,
+        session::get_session,
+        replay::replay_sessions,
+    ),
+    components(schemas(
+        fmo_api_types::FederationSummary,
+        fmo_api_types::FederationSearchResult,
+        fmo_api_types::FederationSearchMatch,
+        fmo_api_types::FederationActivity,
+        fmo_api_types::FederationRating,
+        fmo_api_types::RatingAlgorithm,
+        fmo_api_types::FederationHealth,
+        fmo_api_types::HealthEvent,
+        fmo_api_types::HealthProbeResult,
+        fmo_api_types::FederationShutdown,
+        fmo_api_types::FedimintTotals,
+        fmo_api_types::FederationAtRisk,
+        fmo_api_types::GuardianHealth,
+        fmo_api_types::GuardianHealthLatest,
+        fmo_api_types::GuardianSigningLatency,
+        fmo_api_types::GuardianCertificate,
+        fmo_api_types::GuardianHealthBucket,
+        fmo_api_types::GuardianIncident,
+        fmo_api_types::GuardianContribution,
+        fmo_api_types::ObserverStatus,
+        fmo_api_types::JobProgress,
+        fmo_api_types::SessionDivergence,
+        fmo_api_types::GuardianSessionDivergence,
+        fmo_api_types::SessionDivergenceBucket,
+        fmo_api_types::AlertEvent,
+        fmo_api_types::AlertKind,
+        fmo_api_types::AlertSettings,
+        fmo_api_types::TransactionIdPage,
+        fmo_api_types::TransactionDetails,
+        fmo_api_types::TransactionGraph,
+        fmo_api_types::TransactionGraphNode,
+        fmo_api_types::TransactionGraphNodeKind,
+        fmo_api_types::TransactionGraphEdge,
+        export::ExportFormat,
+        fmo_api_types::ExportCursor,
+        fmo_api_types::SessionPage,
+        fmo_api_types::SessionSummary,
+        fmo_api_types::SessionDetails,
+        fmo_api_types::SessionItem,
+        fmo_api_types::ItemSearchSource,
+        fmo_api_types::ItemSearchMatch,
+        fmo_api_types::TransactionItem,
+        fmo_api_types::LnContractReference,
+        fmo_api_types::FederationUtxo,
+        fmo_api_types::FederationFees,
+        fmo_api_types::FeeRevenue,
+        fmo_api_types::MintDenomination,
+        fmo_api_types::FederationGateway,
+        fmo_api_types::LightningStats,
+        fmo_api_types::LightningContractStats,
+        fmo_api_types::PrivacyScore,
+        fmo_api_types::PrivacyScoreComponent,
+        fmo_api_types::FederationSolvency,
+        fmo_api_types::NostrAnnouncement,
+        fmo_api_types::FederationCompleteness,
+        fmo_api_types::SessionGap,
+        fmo_api_types::MetaLint,
+        fmo_api_types::MetaHistoryEntry,
+        fmo_api_types::MetaLintIssue,
+        fmo_api_types::MetaLintSeverity,
+        fmo_api_types::LightningContactKind,
+        fmo_api_types::LightningContactVerification,
+        fmo_api_types::WithdrawalConcentration,
+        fmo_api_types::WithdrawalConcentrationPeriod,
+        fmo_api_types::WithdrawalCluster,
+        fmo_api_types::WithdrawalDestination,
+        fmo_api_types::WithdrawalTransaction,
+        fmo_api_types::Timeseries,
+        fmo_api_types::TimeseriesMetric,
+        fmo_api_types::TimeseriesResolution,
+        fmo_api_types::Deposit,
+        fmo_api_types::DepositPage,
+        fmo_api_types::Withdrawal,
+        fmo_api_types::WithdrawalStatus,
+    ))
+)]
+pub(crate) struct FederationsApi;
+
 pub fn get_federations_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_observed_federations))
         .route("/", put(add_observed_federation))
         .route("/totals", get(get_federation_totals))
+        .route("/activity", get(network_activity))
+        .route("/search", get(search_federations))
         // TODO: move to nostr module
         .route("/nostr/rating", put(publish_rating_event))
-        .route("/:federation_id", get(get_federation_overview))
+        // PUT takes an invite code instead of a federation id, the parameter names have to match
+        .route(
+            "/:federation_id",
+            get(get_federation_overview)
+                .put(add_observed_federation_by_invite)
+                .delete(delete_observed_federation),
+        )
         .route(
             "/:federation_id/config",
             get(federation::get_federation_config),
         )
         .route("/:federation_id/meta", get(get_federation_meta))
+        .route("/:federation_id/meta/lint", get(get_federation_meta_lint))
+        .route(
+            "/:federation_id/meta/history",
+            get(get_federation_meta_history),
+        )
+        .route("/:federation_id/card.png", get(get_federation_card))
         .route("/:federation_id/health", get(get_federation_health))
+        .route(
+            "/:federation_id/health/history",
+            get(get_federation_health_history),
+        )
+        .route(
+            "/:federation_id/health/events",
+            get(get_federation_health_events),
+        )
+        .route(
+            "/:federation_id/health/probes",
+            get(get_health_probe_results),
+        )
+        .route("/:federation_id/incidents", get(get_federation_incidents))
+        .route(
+            "/:federation_id/guardians/:peer_id/latency.csv",
+            get(get_guardian_latency_csv),
+        )
+        .route(
+            "/:federation_id/shutdown",
+            put(put_federation_shutdown).delete(delete_federation_shutdown),
+        )
+        .route("/:federation_id/observer", get(get_observer_status))
+        .route("/:federation_id/observer/restart", post(restart_observer))
+        .route("/:federation_id/backfill/status", get(get_backfill_status))
+        .route("/:federation_id/alerts", get(get_federation_alerts))
+        .route(
+            "/:federation_id/alerts/settings",
+            get(get_alert_settings).put(put_alert_settings),
+        )
+        .route(
+            "/:federation_id/peers/contribution",
+            get(get_federation_peer_contribution),
+        )
         .route("/:federation_id/transactions", get(list_transactions))
         .route(
             "/:federation_id/transactions/:transaction_id",
             get(transaction),
         )
+        .route(
+            "/:federation_id/transactions/:transaction_id/graph",
+            get(get_transaction_graph),
+        )
         .route(
             "/:federation_id/transactions/count",
             get(count_transactions),
@@ -54,28 +332,110 @@ pub fn get_federations_routes() -> Router<AppState> {
             "/:federation_id/transactions/histogram",
             get(transaction_histogram),
         )
+        .route("/:federation_id/timeseries", get(get_federation_timeseries))
+        .route("/:federation_id/export", get(export_transactions))
+        .route("/:federation_id/cursors", get(list_export_cursors))
+        .route(
+            "/:federation_id/cursors/:name",
+            delete(delete_export_cursor),
+        )
         .route("/:federation_id/utxos", get(get_federation_utxos))
+        .route("/:federation_id/fees", get(get_federation_fees))
+        .route(
+            "/:federation_id/mint/denominations",
+            get(get_mint_denominations),
+        )
+        .route("/:federation_id/gateways", get(get_federation_gateways))
+        .route("/:federation_id/ln/stats", get(get_federation_ln_stats))
+        .route("/:federation_id/privacy_score", get(get_privacy_score))
+        .route("/:federation_id/solvency", get(get_federation_solvency))
+        .route(
+            "/:federation_id/completeness",
+            get(get_federation_completeness),
+        )
+        .route(
+            "/:federation_id/announcement",
+            get(get_federation_announcement),
+        )
+        .route("/:federation_id/deposits", get(list_deposits))
+        .route("/:federation_id/withdrawals", get(get_withdrawals))
+        .route(
+            "/:federation_id/withdrawals/concentration",
+            get(get_withdrawal_concentration),
+        )
+        .route(
+            "/:federation_id/withdrawals/concentration/history",
+            get(get_withdrawal_concentration_history),
+        )
+        .route(
+            "/:federation_id/withdrawals/transactions",
+            get(get_withdrawal_transactions),
+        )
         .route("/:federation_id/sessions", get(list_sessions))
         .route("/:federation_id/sessions/count", get(count_sessions))
+        .route("/:federation_id/sessions/replay", post(replay_sessions))
+        .route(
+            "/:federation_id/sessions/divergence",
+            get(get_session_divergence),
+        )
+        .route("/:federation_id/sessions/:session_index", get(get_session))
+        .route("/:federation_id/search/items", get(search_items))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct RatingParams {
+    /// Algorithm scoring nostr votes, defaults to the instance's configured one
+    rating: Option<RatingAlgorithm>,
+}
+
+/// Sets the rating index of each federation using the instance's weights and
+/// the requested or configured rating algorithm
+pub(super) fn assign_ratings(
+    state: &AppState,
+    federations: &mut [FederationSummary],
+    algorithm: Option<RatingAlgorithm>,
+) {
+    assign_rating_indices(
+        federations,
+        state.instance_settings.rating_weights,
+        algorithm.unwrap_or(state.instance_settings.rating_algorithm),
+    );
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations",
+    tag = "federations",
+    params(RatingParams),
+    responses((status = 200, description = "Observed federations, highest rating index first", body = [FederationSummary]))
+)]
 pub async fn list_observed_federations(
+    Query(params): Query<RatingParams>,
     State(state): State<AppState>,
 ) -> crate::error::Result<Json<Vec<FederationSummary>>> {
-    Ok(state
+    let mut federations = state
         .federation_observer
         .list_federation_summaries()
-        .await?
-        .into())
+        .await?;
+    assign_ratings(&state, &mut federations, params.rating);
+    sort_by_rating_index(&mut federations);
+
+    Ok(federations.into())
 }
 
+#[utoipa::path(
+    put,
+    path = "/federations",
+    tag = "federations",
+    request_body = Object,
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Id of the added federation", body = String))
+)]
 pub async fn add_observed_federation(
-    AuthBearer(auth): AuthBearer,
     State(state): State<AppState>,
     Json(body): Json<serde_json::Value>,
 ) -> crate::error::Result<Json<FederationId>> {
-    state.federation_observer.check_auth(&auth)?;
-
     let invite: InviteCode = serde_json::from_value(
         body.get("invite")
             .context("Request did not contain invite field")?
@@ -89,21 +449,64 @@ pub async fn add_observed_federation(
         .into())
 }
 
+/// Alternative to [`add_observed_federation`] taking the invite code as path
+/// parameter, which is easier to use from shell scripts
+#[utoipa::path(
+    put,
+    path = "/federations/{invite}",
+    tag = "federations",
+    params(("invite" = String, Path, description = "Invite code of the federation")),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Id of the added federation", body = String))
+)]
+pub async fn add_observed_federation_by_invite(
+    Path(invite): Path<String>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<FederationId>> {
+    let invite = InviteCode::from_str(invite.trim()).context("Invalid invite code")?;
+    Ok(state
+        .federation_observer
+        .add_federation(&invite)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/config",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses(
+        (status = 200, description = "Client config of the federation", body = Object, headers(
+            ("etag" = String, description = "Hash of the config for conditional requests")
+        )),
+        (status = 304, description = "Config matches the `If-None-Match` header")
+    )
+)]
 pub(crate) async fn get_federation_config(
     Path(federation_id): Path<FederationId>,
     State(state): State<AppState>,
-) -> crate::error::Result<Json<JsonClientConfig>> {
-    Ok(config_to_json(
+    headers: HeaderMap,
+) -> crate::error::Result<Response> {
+    let config = config_to_json(
         state
             .federation_observer
             .get_federation(federation_id)
             .await?
             .context("Federation not observed, you might want to try /config/:federation_invite")?
             .config,
-    )?
-    .into())
+    )?;
+
+    Ok(json_with_etag(&headers, HeaderMap::new(), config)?)
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = Object))
+)]
 async fn get_federation_overview(
     Path(federation_id): Path<FederationId>,
     State(state): State<AppState>,
@@ -124,6 +527,13 @@ async fn get_federation_overview(
     .into())
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/utxos",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = [fmo_api_types::FederationUtxo]))
+)]
 async fn get_federation_utxos(
     Path(federation_id): Path<FederationId>,
     State(state): State<AppState>,
@@ -135,12 +545,25 @@ async fn get_federation_utxos(
     Ok(utxos.into())
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/totals",
+    tag = "federations",
+    responses((status = 200, description = "Success", body = FedimintTotals))
+)]
 async fn get_federation_totals(
     State(state): State<AppState>,
 ) -> crate::error::Result<Json<FedimintTotals>> {
     Ok(state.federation_observer.totals().await?.into())
 }
 
+#[utoipa::path(
+    put,
+    path = "/federations/nostr/rating",
+    tag = "nostr",
+    request_body = Object,
+    responses((status = 200, description = "Rating published"))
+)]
 async fn publish_rating_event(
     State(state): State<AppState>,
     Json(event): Json<nostr_sdk::Event>,
@@ -1,34 +1,50 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::{anyhow, ensure, Context};
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
 use axum::Json;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use deadpool_postgres::GenericClient;
 use fedimint_core::config::FederationId;
-use fedimint_core::encoding::Encodable;
+use fedimint_core::encoding::{Decodable, Encodable};
 use fedimint_core::invite_code::InviteCode;
 use fedimint_core::task::sleep;
 use fedimint_core::BitcoinHash;
-use fmo_api_types::FederationRating;
+use fmo_api_types::{
+    FederationRating, NostrAnnouncement, NostrArchivedEvent, NostrFederationStatus,
+    NostrFederationSummary, NostrHiddenVote, NostrModeratedPubkey, NostrModeration, Page,
+};
+use futures::StreamExt;
+use nostr_sdk::nips::nip01::Coordinate;
+use nostr_sdk::nips::nip19::{Nip19Event, ToBech32};
 use nostr_sdk::{
-    Event, Filter, FilterOptions, Kind, RelayOptions, RelayPool, RelayPoolOptions,
-    RelaySendOptions, SingleLetterTag,
+    Event, EventId, Filter, FilterOptions, Kind, PublicKey, RelayOptions, RelayPool,
+    RelayPoolOptions, RelaySendOptions, SingleLetterTag, Url,
 };
 use postgres_from_row::FromRow;
 use regex::Regex;
+use serde::Deserialize;
 use tokio::time::interval;
 use tracing::{debug, info, warn};
+use utoipa::IntoParams;
 
 use crate::federation::observer::FederationObserver;
-use crate::util::{query, query_one};
+use crate::socks::{download_config, socks_proxy_addr};
+use crate::util::{execute, query, query_one, query_value};
 use crate::AppState;
 
 // TODO: move to common crate
 const FEDERATION_ANNOUNCEMENT_EVENT_KIND: Kind = Kind::Custom(38173);
 const RECOMMENDATION_EVENT_KIND: Kind = Kind::Custom(38000);
 
+const NOSTR_FEDERATION_PROBE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const NOSTR_FEDERATION_PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+const NOSTR_FEDERATION_PROBE_CONCURRENCY: usize = 8;
+/// Number of relays included in NIP-19 identifiers, more make them unwieldy
+const MAX_RELAY_HINTS: usize = 3;
+
 #[derive(Debug, Clone, FromRow)]
 struct NostrRelay {
     relay_url: String,
@@ -49,19 +65,11 @@ impl FederationObserver {
         }
     }
 
-    async fn nostr_relay_client(&self) -> anyhow::Result<RelayPool> {
-        let relays = query::<NostrRelay>(
-            &self.connection().await?,
-            "SELECT relay_url FROM nostr_relays",
-            &[],
-        )
-        .await?
-        .into_iter()
-        .map(|relay| relay.relay_url)
-        .collect::<Vec<_>>();
+    pub(super) async fn nostr_relay_client(&self) -> anyhow::Result<RelayPool> {
+        let relays = self.list_nostr_relays().await?;
         let client = RelayPool::new(RelayPoolOptions::default());
         for relay_url in &relays {
-            client.add_relay(relay_url, RelayOptions::default()).await?;
+            client.add_relay(relay_url, relay_options().await?).await?;
         }
         client.connect(Some(Duration::from_secs(5))).await;
 
@@ -70,6 +78,121 @@ impl FederationObserver {
         Ok(client)
     }
 
+    pub async fn list_nostr_relays(&self) -> anyhow::Result<Vec<String>> {
+        Ok(query::<NostrRelay>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT relay_url FROM nostr_relays ORDER BY relay_url",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|relay| relay.relay_url)
+        .collect())
+    }
+
+    /// Most recent announcement of the federation, with a few of our relays as
+    /// hints since we don't know where the author published it
+    pub async fn federation_announcement(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<NostrAnnouncement>> {
+        let Some(row) = self
+            .connection()
+            .await?
+            .query_opt(
+                // language=postgresql
+                "SELECT event
+                 FROM nostr_federations
+                 WHERE federation_id = $1
+                 ORDER BY (event ->> 'created_at')::bigint DESC
+                 LIMIT 1",
+                &[&federation_id.consensus_encode_to_vec()],
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let event = serde_json::from_value::<Event>(row.get::<_, serde_json::Value>("event"))
+            .context("Invalid announcement in DB")?;
+        let relays = self
+            .list_nostr_relays()
+            .await?
+            .into_iter()
+            .take(MAX_RELAY_HINTS)
+            .collect();
+
+        announcement_identifiers(&event, relays).map(Some)
+    }
+
+    pub async fn add_nostr_relay(&self, relay_url: &str) -> anyhow::Result<()> {
+        let relay_url = parse_relay_url(relay_url)?;
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO nostr_relays (relay_url) VALUES ($1) ON CONFLICT DO NOTHING",
+            &[&relay_url.to_string()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes a relay, URLs are compared after normalization so e.g. a
+    /// missing trailing slash doesn't matter
+    pub async fn remove_nostr_relay(&self, relay_url: &str) -> anyhow::Result<()> {
+        let relay_url = parse_relay_url(relay_url)?;
+        let stored_urls = self
+            .list_nostr_relays()
+            .await?
+            .into_iter()
+            .filter(|stored| parse_relay_url(stored).is_ok_and(|stored| stored == relay_url))
+            .collect::<Vec<_>>();
+        ensure!(
+            !stored_urls.is_empty(),
+            "Relay {relay_url} isn't configured"
+        );
+
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM nostr_relays WHERE relay_url = ANY($1)",
+            &[&stored_urls],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Connects to relays added to the `nostr_relays` table since `client` was
+    /// created and disconnects from removed ones
+    async fn reload_nostr_relays(&self, client: &RelayPool) -> anyhow::Result<()> {
+        let configured = self
+            .list_nostr_relays()
+            .await?
+            .iter()
+            .filter_map(|relay_url| parse_relay_url(relay_url).ok())
+            .collect::<HashSet<_>>();
+        let connected = client.relays().await.into_keys().collect::<HashSet<_>>();
+
+        for relay_url in connected.difference(&configured) {
+            info!(%relay_url, "Removing nostr relay");
+            client.remove_relay(relay_url.clone()).await?;
+        }
+        for relay_url in configured.difference(&connected) {
+            info!(%relay_url, "Adding nostr relay");
+            client
+                .add_relay(relay_url.clone(), relay_options().await?)
+                .await?;
+            client
+                .connect_relay(relay_url.clone(), Some(Duration::from_secs(5)))
+                .await?;
+        }
+
+        Ok(())
+    }
+
     async fn sync_nostr_events_inner(&self) -> anyhow::Result<()> {
         let mut interval = interval(Duration::from_secs(60));
 
@@ -78,6 +201,7 @@ impl FederationObserver {
         loop {
             interval.tick().await;
 
+            self.reload_nostr_relays(&client).await?;
             self.sync_federation_announcements(&client).await?;
 
             let federations = {
@@ -143,17 +267,94 @@ impl FederationObserver {
         Ok(())
     }
 
+    /// Periodically tries to fetch the config of each federation announced via
+    /// Nostr, so clients don't have to probe them themselves
+    pub(super) async fn probe_nostr_federations(self) {
+        loop {
+            if let Err(e) = self.probe_nostr_federations_inner().await {
+                warn!("Error while probing nostr federations: {e:?}");
+            }
+            sleep(NOSTR_FEDERATION_PROBE_INTERVAL).await;
+        }
+    }
+
+    async fn probe_nostr_federations_inner(&self) -> anyhow::Result<()> {
+        let probes = futures::stream::iter(self.list_nostr_federations().await?)
+            .map(|federation| async move {
+                let config = match tokio::time::timeout(
+                    NOSTR_FEDERATION_PROBE_TIMEOUT,
+                    download_config(&federation.invite_code),
+                )
+                .await
+                {
+                    Ok(Ok(config)) => Some(config),
+                    Ok(Err(e)) => {
+                        debug!(federation_id = %federation.federation_id, "Nostr federation unreachable: {e:?}");
+                        None
+                    }
+                    Err(_) => {
+                        debug!(federation_id = %federation.federation_id, "Nostr federation probe timed out");
+                        None
+                    }
+                };
+                (federation.federation_id, config)
+            })
+            .buffer_unordered(NOSTR_FEDERATION_PROBE_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        let now = Utc::now().naive_utc();
+        let conn = self.connection().await?;
+        let online = probes.iter().filter(|(_, config)| config.is_some()).count();
+        for (federation_id, config) in probes {
+            let name = config
+                .as_ref()
+                .and_then(|config| config.global.meta.get("federation_name").cloned());
+            execute(
+                &conn,
+                // language=postgresql
+                "UPDATE nostr_federations
+                 SET online       = $2,
+                     last_checked = $3,
+                     last_seen    = CASE WHEN $2 THEN $3 ELSE last_seen END,
+                     name         = COALESCE($4, name)
+                 WHERE federation_id = $1",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &config.is_some(),
+                    &now,
+                    &name,
+                ],
+            )
+            .await?;
+        }
+        info!(online, "Probed nostr federations");
+
+        Ok(())
+    }
+
     pub async fn list_nostr_federations(&self) -> anyhow::Result<Vec<NostrFederation>> {
         #[derive(Debug, Clone, FromRow)]
         pub struct RawNostrFederation {
             pub federation_id: Vec<u8>,
             pub invite_code: String,
+            pub name: Option<String>,
+            pub online: Option<bool>,
+            pub last_checked: Option<NaiveDateTime>,
+            pub last_seen: Option<NaiveDateTime>,
         }
 
         query::<RawNostrFederation>(
             &self.connection().await.expect("db connection"),
             // language=postgresql
-            "select federation_id, MIN(invite_code) as invite_code from nostr_federations group by federation_id",
+            "SELECT federation_id,
+                    MIN(invite_code)  AS invite_code,
+                    MAX(name)         AS name,
+                    BOOL_OR(online)   AS online,
+                    MAX(last_checked) AS last_checked,
+                    MAX(last_seen)    AS last_seen
+             FROM nostr_federations
+             GROUP BY federation_id",
             &[],
         )
         .await?
@@ -168,33 +369,250 @@ impl FederationObserver {
                     federation_id_bytes,
                 )),
                 invite_code: InviteCode::from_str(&federation.invite_code)?,
+                name: federation.name,
+                online: federation.online,
+                last_checked: federation.last_checked.map(|time| time.and_utc()),
+                last_seen: federation.last_seen.map(|time| time.and_utc()),
             })
         })
         .collect()
     }
 
+    /// Stored announcements and recommendations matching the filters, paged
+    /// by creation time, and the total number of matching events
+    pub async fn archived_nostr_events(
+        &self,
+        kind: Option<u16>,
+        since: Option<i64>,
+        federation_id: Option<FederationId>,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<NostrArchivedEvent>, u64)> {
+        #[derive(Debug, FromRow)]
+        struct ArchivedEventRow {
+            federation_id: Vec<u8>,
+            fetch_time: NaiveDateTime,
+            event: serde_json::Value,
+        }
+
+        // Both tables store the full event, so they only differ in kind
+        // language=postgresql
+        const EVENTS: &str = "
+            WITH events AS (SELECT event_id, federation_id, event, fetch_time
+                            FROM nostr_federations
+                            UNION ALL
+                            SELECT event_id, federation_id, event, fetch_time
+                            FROM nostr_votes)
+            SELECT federation_id, fetch_time, event
+            FROM events
+            WHERE ($1::integer IS NULL OR (event ->> 'kind')::integer = $1)
+              AND ($2::bigint IS NULL OR (event ->> 'created_at')::bigint >= $2)
+              AND ($3::bytea IS NULL OR federation_id = $3)
+            ORDER BY (event ->> 'created_at')::bigint, event_id
+            LIMIT $4 OFFSET $5";
+        // language=postgresql
+        const COUNT: &str = "
+            WITH events AS (SELECT federation_id, event
+                            FROM nostr_federations
+                            UNION ALL
+                            SELECT federation_id, event
+                            FROM nostr_votes)
+            SELECT COUNT(*)
+            FROM events
+            WHERE ($1::integer IS NULL OR (event ->> 'kind')::integer = $1)
+              AND ($2::bigint IS NULL OR (event ->> 'created_at')::bigint >= $2)
+              AND ($3::bytea IS NULL OR federation_id = $3)";
+
+        let kind = kind.map(i32::from);
+        let federation_id = federation_id.map(|id| id.consensus_encode_to_vec());
+        let conn = self.connection().await?;
+
+        let events = query::<ArchivedEventRow>(
+            &conn,
+            EVENTS,
+            &[
+                &kind,
+                &since,
+                &federation_id,
+                &(limit as i64),
+                &(offset as i64),
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(NostrArchivedEvent {
+                federation_id: FederationId::consensus_decode_vec(
+                    row.federation_id,
+                    &Default::default(),
+                )?,
+                fetch_time: row.fetch_time.and_utc(),
+                event: row.event,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let total = query_value::<i64>(&conn, COUNT, &[&kind, &since, &federation_id]).await?;
+
+        Ok((events, total as u64))
+    }
+
     pub async fn federation_rating(
         &self,
         federation_id: FederationId,
     ) -> anyhow::Result<FederationRating> {
-        #[derive(Debug, Clone, FromRow)]
-        struct FederationRatingRow {
-            count: i64,
-            avg: Option<f64>,
+        query_federation_rating(&self.connection().await?, federation_id, self.nostr_min_pow).await
+    }
+
+    pub async fn nostr_moderation(&self) -> anyhow::Result<NostrModeration> {
+        #[derive(Debug, FromRow)]
+        struct ModeratedPubkeyRow {
+            pubkey: Vec<u8>,
+            allowed: bool,
+            note: Option<String>,
+            created_at: NaiveDateTime,
         }
 
-        let query_res = query_one::<FederationRatingRow>(
+        #[derive(Debug, FromRow)]
+        struct HiddenVoteRow {
+            event_id: Vec<u8>,
+            federation_id: Vec<u8>,
+            pubkey: Vec<u8>,
+            reason: Option<String>,
+            hidden_at: NaiveDateTime,
+        }
+
+        let conn = self.connection().await?;
+        let pubkeys = query::<ModeratedPubkeyRow>(
+            &conn,
+            // language=postgresql
+            "SELECT pubkey, allowed, note, created_at FROM nostr_moderated_pubkeys ORDER BY created_at",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| NostrModeratedPubkey {
+            pubkey: hex::encode(row.pubkey),
+            allowed: row.allowed,
+            note: row.note,
+            created_at: row.created_at.and_utc(),
+        })
+        .collect();
+        let hidden_votes = query::<HiddenVoteRow>(
+            &conn,
+            // language=postgresql
+            "SELECT hidden.event_id, votes.federation_id, votes.pubkey, hidden.reason, hidden.hidden_at
+             FROM nostr_hidden_votes hidden
+                      JOIN nostr_votes votes ON votes.event_id = hidden.event_id
+             ORDER BY hidden.hidden_at",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(NostrHiddenVote {
+                event_id: hex::encode(row.event_id),
+                federation_id: FederationId::consensus_decode_vec(
+                    row.federation_id,
+                    &Default::default(),
+                )?,
+                author: hex::encode(row.pubkey),
+                reason: row.reason,
+                hidden_at: row.hidden_at.and_utc(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+        Ok(NostrModeration {
+            min_pow: self.nostr_min_pow,
+            pubkeys,
+            hidden_votes,
+        })
+    }
+
+    /// Always (`allowed`) or never counts recommendations of `pubkey`,
+    /// replacing a previous decision about the same author
+    pub async fn moderate_nostr_pubkey(
+        &self,
+        pubkey: PublicKey,
+        allowed: bool,
+        note: Option<String>,
+    ) -> anyhow::Result<()> {
+        execute(
             &self.connection().await?,
             // language=postgresql
-            "SELECT COUNT(star_vote)::bigint as count, AVG(star_vote)::DOUBLE PRECISION as avg from nostr_votes WHERE federation_id = $1;",
-            &[&federation_id.consensus_encode_to_vec()],
+            "INSERT INTO nostr_moderated_pubkeys (pubkey, allowed, note, created_at)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (pubkey) DO UPDATE
+                 SET allowed    = excluded.allowed,
+                     note       = excluded.note,
+                     created_at = excluded.created_at",
+            &[
+                &pubkey.to_bytes().to_vec(),
+                &allowed,
+                &note,
+                &Utc::now().naive_utc(),
+            ],
         )
         .await?;
 
-        Ok(FederationRating {
-            count: query_res.count as u64,
-            avg: query_res.avg,
-        })
+        Ok(())
+    }
+
+    pub async fn remove_nostr_pubkey_moderation(&self, pubkey: PublicKey) -> anyhow::Result<()> {
+        let removed = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM nostr_moderated_pubkeys WHERE pubkey = $1",
+            &[&pubkey.to_bytes().to_vec()],
+        )
+        .await?;
+        ensure!(removed > 0, "Public key {pubkey} isn't moderated");
+
+        Ok(())
+    }
+
+    /// Excludes a stored recommendation from the federation's rating
+    pub async fn hide_nostr_vote(
+        &self,
+        event_id: EventId,
+        reason: Option<String>,
+    ) -> anyhow::Result<()> {
+        let event_id = event_id.to_bytes().to_vec();
+        let conn = self.connection().await?;
+        ensure!(
+            query_value::<bool>(
+                &conn,
+                // language=postgresql
+                "SELECT EXISTS (SELECT FROM nostr_votes WHERE event_id = $1)",
+                &[&event_id],
+            )
+            .await?,
+            "Recommendation doesn't exist"
+        );
+        execute(
+            &conn,
+            // language=postgresql
+            "INSERT INTO nostr_hidden_votes (event_id, reason, hidden_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (event_id) DO UPDATE SET reason = excluded.reason",
+            &[&event_id, &reason, &Utc::now().naive_utc()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unhide_nostr_vote(&self, event_id: EventId) -> anyhow::Result<()> {
+        let removed = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM nostr_hidden_votes WHERE event_id = $1",
+            &[&event_id.to_bytes().to_vec()],
+        )
+        .await?;
+        ensure!(removed > 0, "Recommendation isn't hidden");
+
+        Ok(())
     }
 
     pub async fn submit_rating(&self, nostr_event: Event) -> anyhow::Result<()> {
@@ -239,10 +657,26 @@ impl FederationObserver {
     }
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone)]
 pub struct NostrFederation {
     pub federation_id: FederationId,
     pub invite_code: InviteCode,
+    pub name: Option<String>,
+    pub online: Option<bool>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl From<NostrFederation> for NostrFederationStatus {
+    fn from(federation: NostrFederation) -> Self {
+        NostrFederationStatus {
+            invite_code: federation.invite_code,
+            name: federation.name,
+            online: federation.online,
+            last_checked: federation.last_checked,
+            last_seen: federation.last_seen,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -308,6 +742,9 @@ struct ParsedRecommendationEvent {
     event_id: [u8; 32],
     federation_id: FederationId,
     star_vote: Option<u8>,
+    pubkey: [u8; 32],
+    created_at: NaiveDateTime,
+    pow: u32,
 }
 
 impl TryFrom<Event> for ParsedRecommendationEvent {
@@ -342,10 +779,17 @@ impl TryFrom<Event> for ParsedRecommendationEvent {
             ensure!(star_vote <= 5, "Vote above 5 is invalid");
         }
 
+        let created_at = DateTime::from_timestamp(event.created_at.as_u64() as i64, 0)
+            .context("Invalid recommendation timestamp")?
+            .naive_utc();
+
         Ok(ParsedRecommendationEvent {
             event_id,
             federation_id,
             star_vote,
+            pubkey: event.pubkey.to_bytes(),
+            created_at,
+            pow: pow_difficulty(&event_id),
         })
     }
 }
@@ -436,19 +880,87 @@ async fn insert_federation_votes(
     let now = chrono::Utc::now().naive_utc();
     dbtx.execute(
         // language=postgresql
-        "INSERT INTO nostr_votes (event_id, federation_id, star_vote, event, fetch_time) VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+        "INSERT INTO nostr_votes (event_id, federation_id, star_vote, event, fetch_time, pubkey, created_at, pow) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT DO NOTHING",
         &[
             &parsed_event.event_id.to_vec(),
             &parsed_event.federation_id.consensus_encode_to_vec(),
             &parsed_event.star_vote.map(|vote| vote as i32),
             &serde_json::to_value(event).expect("can be serialized"),
-            &now
+            &now,
+            &parsed_event.pubkey.to_vec(),
+            &parsed_event.created_at,
+            &(parsed_event.pow as i32),
         ],
     ).await?;
 
     Ok(())
 }
 
+/// Relays are connected to through the SOCKS proxy if one is configured, like
+/// guardians
+async fn relay_options() -> anyhow::Result<RelayOptions> {
+    Ok(RelayOptions::default().proxy(socks_proxy_addr().await?))
+}
+
+/// Rating from the latest recommendation of each author, ignoring it if it has
+/// less than `min_pow` bits of proof of work or is moderated. A hidden latest
+/// vote doesn't bring back the author's older ones.
+async fn query_federation_rating(
+    conn: &impl GenericClient,
+    federation_id: FederationId,
+    min_pow: u8,
+) -> anyhow::Result<FederationRating> {
+    #[derive(Debug, Clone, FromRow)]
+    struct FederationRatingRow {
+        count: i64,
+        avg: Option<f64>,
+    }
+
+    let query_res = query_one::<FederationRatingRow>(
+        conn,
+        // language=postgresql
+        "WITH latest_votes AS (SELECT DISTINCT ON (pubkey) event_id, pubkey, star_vote, pow
+                              FROM nostr_votes
+                              WHERE federation_id = $1
+                              ORDER BY pubkey, created_at DESC, event_id)
+         SELECT COUNT(star_vote)::bigint AS count, AVG(star_vote)::DOUBLE PRECISION AS avg
+         FROM latest_votes
+         WHERE pow >= $2
+           AND event_id NOT IN (SELECT event_id FROM nostr_hidden_votes)
+           AND pubkey NOT IN (SELECT pubkey FROM nostr_moderated_pubkeys WHERE NOT allowed)
+           AND (pubkey IN (SELECT pubkey FROM nostr_moderated_pubkeys WHERE allowed)
+               OR NOT EXISTS (SELECT FROM nostr_moderated_pubkeys WHERE allowed))",
+        &[&federation_id.consensus_encode_to_vec(), &(min_pow as i32)],
+    )
+    .await?;
+
+    Ok(FederationRating {
+        count: query_res.count as u64,
+        avg: query_res.avg,
+    })
+}
+
+/// NIP-13 proof of work of an event, the number of leading zero bits of its id
+fn pow_difficulty(event_id: &[u8]) -> u32 {
+    let mut difficulty = 0;
+    for byte in event_id {
+        difficulty += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    difficulty
+}
+
+/// Minimum proof of work of recommendations counted in ratings, none by
+/// default
+pub(super) fn min_pow_from_env() -> anyhow::Result<u8> {
+    match dotenv::var("FO_NOSTR_MIN_POW") {
+        Ok(min_pow) => min_pow.trim().parse().context("Invalid FO_NOSTR_MIN_POW"),
+        Err(_) => Ok(0),
+    }
+}
+
 fn extract_star_rating(comment: &str) -> Option<u8> {
     let re = Regex::new(r"^\[([0-9]+)/5]").expect("valid regex");
     let rating = re.captures(comment)?.get(1)?.as_str().parse::<u8>().ok()?;
@@ -460,23 +972,725 @@ fn extract_star_rating(comment: &str) -> Option<u8> {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/nostr/federations",
+    tag = "nostr",
+    responses((status = 200, description = "Invite codes and reachability by federation id", body = BTreeMap<String, NostrFederationStatus>))
+)]
 pub(crate) async fn get_nostr_federations(
     State(state): State<crate::AppState>,
-) -> crate::error::Result<Json<BTreeMap<FederationId, InviteCode>>> {
+) -> crate::error::Result<Json<BTreeMap<FederationId, NostrFederationStatus>>> {
     let federation_map = state
         .federation_observer
         .list_nostr_federations()
         .await?
         .into_iter()
-        .map(|federation| (federation.federation_id, federation.invite_code))
+        .map(|federation| (federation.federation_id, federation.into()))
         .collect();
 
     Ok(Json(federation_map))
 }
 
+/// Counts of announced federations by reachability, as of the latest probes
+#[utoipa::path(
+    get,
+    path = "/nostr/federations/summary",
+    tag = "nostr",
+    responses((status = 200, description = "Announced federations by status", body = NostrFederationSummary))
+)]
+pub(crate) async fn get_nostr_federation_summary(
+    State(state): State<crate::AppState>,
+) -> crate::error::Result<Json<NostrFederationSummary>> {
+    let observed = state
+        .federation_observer
+        .list_active_federations()
+        .await?
+        .into_iter()
+        .map(|federation| federation.federation_id)
+        .collect::<BTreeSet<_>>();
+    let federations = state.federation_observer.list_nostr_federations().await?;
+
+    Ok(Json(nostr_federation_summary(&federations, &observed)))
+}
+
+fn nostr_federation_summary(
+    federations: &[NostrFederation],
+    observed: &BTreeSet<FederationId>,
+) -> NostrFederationSummary {
+    let mut summary = NostrFederationSummary::default();
+    for federation in federations {
+        summary.total += 1;
+        match federation.online {
+            Some(true) => summary.online += 1,
+            Some(false) => summary.offline += 1,
+            None => summary.unchecked += 1,
+        }
+        if observed.contains(&federation.federation_id) {
+            summary.observed += 1;
+        }
+    }
+    summary
+}
+
+#[utoipa::path(
+    put,
+    path = "/nostr/federations",
+    tag = "nostr",
+    request_body = Object,
+    responses((status = 200, description = "Announcement published"))
+)]
 pub(crate) async fn publish_federation_event(
     State(state): State<AppState>,
     Json(event): Json<nostr_sdk::Event>,
 ) -> crate::error::Result<()> {
     Ok(state.federation_observer.submit_federation(event).await?)
 }
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/announcement",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Latest announcement, `null` if the federation wasn't announced on nostr", body = Option<NostrAnnouncement>))
+)]
+pub(crate) async fn get_federation_announcement(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Option<NostrAnnouncement>>> {
+    Ok(state
+        .federation_observer
+        .federation_announcement(federation_id)
+        .await?
+        .into())
+}
+
+/// Number of events returned if the client doesn't specify a limit
+const DEFAULT_NOSTR_EVENT_PAGE_SIZE: u64 = 500;
+const MAX_NOSTR_EVENT_PAGE_SIZE: u64 = 5_000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct NostrEventParams {
+    /// Only events of this kind, 38173 for announcements or 38000 for
+    /// recommendations
+    kind: Option<u16>,
+    /// Only events created at or after this unix timestamp
+    since: Option<i64>,
+    /// Only events referencing this hex encoded federation id
+    #[param(value_type = Option<String>)]
+    federation_id: Option<FederationId>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+/// Archive of all announcements and recommendations fetched from relays,
+/// oldest first by creation time
+#[utoipa::path(
+    get,
+    path = "/nostr/events",
+    tag = "nostr",
+    params(NostrEventParams),
+    responses((status = 200, description = "Stored nostr events", body = NostrEventPage))
+)]
+pub(crate) async fn get_nostr_events(
+    Query(params): Query<NostrEventParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Page<NostrArchivedEvent>>> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_NOSTR_EVENT_PAGE_SIZE)
+        .min(MAX_NOSTR_EVENT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    let (items, total) = state
+        .federation_observer
+        .archived_nostr_events(
+            params.kind,
+            params.since,
+            params.federation_id,
+            limit,
+            offset,
+        )
+        .await?;
+
+    Ok(Page {
+        items,
+        total,
+        offset,
+        limit,
+    }
+    .into())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct NostrRelayParams {
+    /// `ws://` or `wss://` URL of the relay
+    url: String,
+}
+
+/// Relays used to sync and publish nostr events
+#[utoipa::path(
+    get,
+    path = "/nostr/relays",
+    tag = "nostr",
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Configured relay URLs", body = Vec<String>))
+)]
+pub(crate) async fn get_nostr_relays(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<String>>> {
+    Ok(state.federation_observer.list_nostr_relays().await?.into())
+}
+
+/// Adds a relay, the event sync connects to it within a minute
+#[utoipa::path(
+    put,
+    path = "/nostr/relays",
+    tag = "nostr",
+    params(NostrRelayParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Relay added"))
+)]
+pub(crate) async fn put_nostr_relay(
+    Query(params): Query<NostrRelayParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .add_nostr_relay(&params.url)
+        .await?)
+}
+
+/// Removes a relay, the event sync disconnects from it within a minute
+#[utoipa::path(
+    delete,
+    path = "/nostr/relays",
+    tag = "nostr",
+    params(NostrRelayParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Relay removed"))
+)]
+pub(crate) async fn delete_nostr_relay(
+    Query(params): Query<NostrRelayParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .remove_nostr_relay(&params.url)
+        .await?)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct NostrPubkeyModerationParams {
+    /// Hex or npub encoded public key of the author
+    pubkey: String,
+    /// Only count allowed authors (`true`) or never count this one (`false`,
+    /// default), ignored when removing
+    #[serde(default)]
+    allowed: bool,
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct NostrVoteModerationParams {
+    /// Hex or note encoded event id of the recommendation
+    event_id: String,
+    /// Why the recommendation is hidden, ignored when unhiding
+    reason: Option<String>,
+}
+
+/// Proof of work requirement, moderated authors and hidden recommendations
+#[utoipa::path(
+    get,
+    path = "/nostr/moderation",
+    tag = "nostr",
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Moderation settings", body = NostrModeration))
+)]
+pub(crate) async fn get_nostr_moderation(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<NostrModeration>> {
+    Ok(state.federation_observer.nostr_moderation().await?.into())
+}
+
+/// Allows or denies an author's recommendations, ratings change once the
+/// federation summaries are refreshed
+#[utoipa::path(
+    put,
+    path = "/nostr/moderation/pubkeys",
+    tag = "nostr",
+    params(NostrPubkeyModerationParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Author moderated"))
+)]
+pub(crate) async fn put_nostr_pubkey_moderation(
+    Query(params): Query<NostrPubkeyModerationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    let pubkey = PublicKey::parse(params.pubkey.trim()).context("Invalid public key")?;
+    Ok(state
+        .federation_observer
+        .moderate_nostr_pubkey(pubkey, params.allowed, params.note)
+        .await?)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/nostr/moderation/pubkeys",
+    tag = "nostr",
+    params(NostrPubkeyModerationParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Author no longer moderated"))
+)]
+pub(crate) async fn delete_nostr_pubkey_moderation(
+    Query(params): Query<NostrPubkeyModerationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    let pubkey = PublicKey::parse(params.pubkey.trim()).context("Invalid public key")?;
+    Ok(state
+        .federation_observer
+        .remove_nostr_pubkey_moderation(pubkey)
+        .await?)
+}
+
+/// Hides a spam recommendation from the federation's rating
+#[utoipa::path(
+    put,
+    path = "/nostr/moderation/votes",
+    tag = "nostr",
+    params(NostrVoteModerationParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Recommendation hidden"))
+)]
+pub(crate) async fn put_nostr_hidden_vote(
+    Query(params): Query<NostrVoteModerationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    let event_id = EventId::parse(params.event_id.trim()).context("Invalid event id")?;
+    Ok(state
+        .federation_observer
+        .hide_nostr_vote(event_id, params.reason)
+        .await?)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/nostr/moderation/votes",
+    tag = "nostr",
+    params(NostrVoteModerationParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Recommendation counted again"))
+)]
+pub(crate) async fn delete_nostr_hidden_vote(
+    Query(params): Query<NostrVoteModerationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    let event_id = EventId::parse(params.event_id.trim()).context("Invalid event id")?;
+    Ok(state
+        .federation_observer
+        .unhide_nostr_vote(event_id)
+        .await?)
+}
+
+/// Builds the `naddr` and `nevent` identifiers of an announcement, `relays`
+/// are included as hints where to find it
+fn announcement_identifiers(
+    event: &Event,
+    relays: Vec<String>,
+) -> anyhow::Result<NostrAnnouncement> {
+    let identifier = event
+        .identifier()
+        .context("Announcement has no d tag")?
+        .to_owned();
+    let naddr = Coordinate {
+        kind: event.kind,
+        public_key: event.pubkey,
+        identifier,
+        relays: relays.clone(),
+    }
+    .to_bech32()?;
+
+    let mut nevent = Nip19Event::new(event.id, relays);
+    nevent.author = Some(event.pubkey);
+    nevent.kind = Some(event.kind);
+
+    Ok(NostrAnnouncement {
+        event_id: event.id.to_hex(),
+        author: event.pubkey.to_hex(),
+        created_at: DateTime::from_timestamp(event.created_at.as_u64() as i64, 0)
+            .context("Invalid announcement timestamp")?,
+        naddr,
+        nevent: nevent.to_bech32()?,
+    })
+}
+
+fn parse_relay_url(relay_url: &str) -> anyhow::Result<Url> {
+    let url = Url::parse(relay_url.trim()).context("Invalid relay URL")?;
+    ensure!(
+        matches!(url.scheme(), "ws" | "wss"),
+        "Relay URL has to use ws:// or wss://"
+    );
+    ensure!(url.host_str().is_some(), "Relay URL has no host");
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use deadpool_postgres::Runtime;
+    use fedimint_core::util::SafeUrl;
+    use fedimint_core::PeerId;
+    use nostr_sdk::{EventBuilder, Keys, Tag, TagKind, Timestamp};
+    use tokio_postgres::NoTls;
+
+    use super::*;
+    use crate::federation::observer::migrate_test_schema;
+
+    fn federation_id(seed: &str) -> FederationId {
+        FederationId(bitcoin::hashes::sha256::Hash::hash(seed.as_bytes()))
+    }
+
+    fn invite_code(federation_id: FederationId) -> InviteCode {
+        let url: SafeUrl = "wss://fedimint.example.com/".parse().expect("valid url");
+        InviteCode::new(url, PeerId::from(0), federation_id, None)
+    }
+
+    fn event(keys: &Keys, kind: Kind, content: &str, tags: &[(char, &str)]) -> Event {
+        event_at(keys, kind, content, tags, Timestamp::now())
+    }
+
+    fn event_at(
+        keys: &Keys,
+        kind: Kind,
+        content: &str,
+        tags: &[(char, &str)],
+        created_at: Timestamp,
+    ) -> Event {
+        let tags = tags
+            .iter()
+            .map(|(tag, value)| {
+                Tag::custom(
+                    TagKind::SingleLetter(SingleLetterTag::from_char(*tag).unwrap()),
+                    [*value],
+                )
+            })
+            .collect::<Vec<_>>();
+        EventBuilder::new(kind, content, tags)
+            .custom_created_at(created_at)
+            .to_event(keys)
+            .expect("can sign event")
+    }
+
+    fn announcement(federation_id: FederationId, invite_code: &str) -> Event {
+        event(
+            &Keys::generate(),
+            FEDERATION_ANNOUNCEMENT_EVENT_KIND,
+            "",
+            &[('d', &federation_id.to_string()), ('u', invite_code)],
+        )
+    }
+
+    fn recommendation(keys: &Keys, federation_id: FederationId, content: &str) -> Event {
+        event(
+            keys,
+            RECOMMENDATION_EVENT_KIND,
+            content,
+            &[('d', &federation_id.to_string())],
+        )
+    }
+
+    #[test]
+    fn test_nostr_federation_summary() {
+        let federation = |seed: &str, online: Option<bool>| {
+            let federation_id = federation_id(seed);
+            NostrFederation {
+                federation_id,
+                invite_code: invite_code(federation_id),
+                name: None,
+                online,
+                last_checked: None,
+                last_seen: None,
+            }
+        };
+        let federations = [
+            federation("a", Some(true)),
+            federation("b", Some(true)),
+            federation("c", Some(false)),
+            federation("d", None),
+        ];
+        let observed = [federation_id("a"), federation_id("observed only")]
+            .into_iter()
+            .collect();
+
+        assert_eq!(
+            nostr_federation_summary(&federations, &observed),
+            NostrFederationSummary {
+                total: 4,
+                online: 2,
+                offline: 1,
+                unchecked: 1,
+                observed: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_federation_event() {
+        let id = federation_id("federation");
+        let invite = invite_code(id);
+
+        let parsed = ParsedFederationEvent::try_from(announcement(id, &invite.to_string()))
+            .expect("valid announcement");
+        assert_eq!(parsed.federation_id, id);
+        assert_eq!(parsed.invite_code, invite);
+
+        // Invite code belongs to another federation
+        let other_invite = invite_code(federation_id("other")).to_string();
+        assert!(ParsedFederationEvent::try_from(announcement(id, &other_invite)).is_err());
+
+        // Garbage invite code
+        assert!(ParsedFederationEvent::try_from(announcement(id, "fed1notaninvite")).is_err());
+
+        // Missing invite tag
+        let no_invite = event(
+            &Keys::generate(),
+            FEDERATION_ANNOUNCEMENT_EVENT_KIND,
+            "",
+            &[('d', &id.to_string())],
+        );
+        assert!(ParsedFederationEvent::try_from(no_invite).is_err());
+
+        // Wrong kind
+        let wrong_kind = event(
+            &Keys::generate(),
+            RECOMMENDATION_EVENT_KIND,
+            "",
+            &[('d', &id.to_string()), ('u', &invite.to_string())],
+        );
+        assert!(ParsedFederationEvent::try_from(wrong_kind).is_err());
+    }
+
+    #[test]
+    fn test_parse_recommendation_event() {
+        let keys = Keys::generate();
+        let id = federation_id("federation");
+
+        let parsed = ParsedRecommendationEvent::try_from(recommendation(&keys, id, "[4/5] Nice"))
+            .expect("valid recommendation");
+        assert_eq!(parsed.federation_id, id);
+        assert_eq!(parsed.star_vote, Some(4));
+
+        let parsed = ParsedRecommendationEvent::try_from(recommendation(&keys, id, "Nice"))
+            .expect("recommendations without rating are valid");
+        assert_eq!(parsed.star_vote, None);
+
+        // Out of range ratings are kept as recommendations without a vote
+        for invalid_rating in ["[0/5] Bad", "[6/5] Great", "[300/5] Great"] {
+            let parsed =
+                ParsedRecommendationEvent::try_from(recommendation(&keys, id, invalid_rating))
+                    .expect("recommendation is still valid");
+            assert_eq!(parsed.star_vote, None, "{invalid_rating}");
+        }
+
+        // The first federation id tag that parses is used
+        let second_tag_valid = event(
+            &keys,
+            RECOMMENDATION_EVENT_KIND,
+            "[5/5]",
+            &[('d', "not a federation id"), ('d', &id.to_string())],
+        );
+        let parsed =
+            ParsedRecommendationEvent::try_from(second_tag_valid).expect("valid federation id tag");
+        assert_eq!(parsed.federation_id, id);
+
+        let invalid_federation_id = event(
+            &keys,
+            RECOMMENDATION_EVENT_KIND,
+            "[5/5]",
+            &[('d', "not a federation id")],
+        );
+        assert!(ParsedRecommendationEvent::try_from(invalid_federation_id).is_err());
+
+        let no_federation_id = event(&keys, RECOMMENDATION_EVENT_KIND, "[5/5]", &[]);
+        assert!(ParsedRecommendationEvent::try_from(no_federation_id).is_err());
+    }
+
+    #[test]
+    fn test_pow_difficulty() {
+        assert_eq!(pow_difficulty(&[0xff; 32]), 0);
+        assert_eq!(pow_difficulty(&[0x00, 0x0f, 0xff]), 12);
+        assert_eq!(pow_difficulty(&[0x00, 0x00, 0x01, 0x00]), 23);
+        assert_eq!(pow_difficulty(&[0x00; 32]), 256);
+    }
+
+    #[test]
+    fn test_extract_star_rating() {
+        assert_eq!(extract_star_rating("[1/5]"), Some(1));
+        assert_eq!(extract_star_rating("[5/5] Great"), Some(5));
+        assert_eq!(extract_star_rating("Great [5/5]"), None);
+        assert_eq!(extract_star_rating(""), None);
+        assert_eq!(extract_star_rating("[0/5]"), None);
+        assert_eq!(extract_star_rating("[6/5]"), None);
+    }
+
+    /// Runs against the database in `FO_TEST_DATABASE`. The schema is migrated
+    /// into a throwaway namespace and the transaction is never committed, so
+    /// it's safe to point at a database with real data.
+    #[tokio::test]
+    #[ignore = "needs a database in FO_TEST_DATABASE"]
+    async fn test_federation_rating() {
+        let database = dotenv::var("FO_TEST_DATABASE").expect("FO_TEST_DATABASE is set");
+        let pool = deadpool_postgres::Config {
+            url: Some(database),
+            ..Default::default()
+        }
+        .create_pool(Some(Runtime::Tokio1), NoTls)
+        .expect("valid database config");
+        let mut conn = pool.get().await.expect("can connect to database");
+        let dbtx = conn.transaction().await.expect("can open transaction");
+        migrate_test_schema(&dbtx)
+            .await
+            .expect("can migrate test schema");
+
+        let id = federation_id("federation");
+        let other_id = federation_id("other");
+        for federation in [id, other_id] {
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO federations (federation_id, config) VALUES ($1, $2)",
+                &[&federation.consensus_encode_to_vec(), &Vec::<u8>::new()],
+            )
+            .await
+            .expect("can insert federation");
+        }
+        let changed_mind = Keys::generate();
+        let votes = [
+            recommendation(&Keys::generate(), id, "[5/5] Great"),
+            recommendation(&Keys::generate(), id, "[2/5] Meh"),
+            recommendation(&Keys::generate(), id, "No stars"),
+            recommendation(&Keys::generate(), other_id, "[1/5] Bad"),
+            event_at(
+                &changed_mind,
+                RECOMMENDATION_EVENT_KIND,
+                "[1/5] Bad",
+                &[('d', &id.to_string())],
+                Timestamp::from(1_700_000_000),
+            ),
+            event_at(
+                &changed_mind,
+                RECOMMENDATION_EVENT_KIND,
+                "[5/5] Fixed now",
+                &[('d', &id.to_string())],
+                Timestamp::from(1_700_000_100),
+            ),
+        ];
+        for vote in &votes {
+            insert_federation_votes(&dbtx, vote.clone())
+                .await
+                .expect("valid vote");
+        }
+        // The same event being fetched from multiple relays is only counted once
+        insert_federation_votes(&dbtx, votes[0].clone())
+            .await
+            .expect("duplicates are ignored");
+
+        // Only the latest vote of each author counts
+        let rating = query_federation_rating(&dbtx, id, 0).await.unwrap();
+        assert_eq!(rating.count, 3);
+        assert_eq!(rating.avg, Some(4.0));
+
+        let rating = query_federation_rating(&dbtx, federation_id("unknown"), 0)
+            .await
+            .unwrap();
+        assert_eq!(rating.count, 0);
+        assert_eq!(rating.avg, None);
+
+        // Random event ids never have that much proof of work
+        let rating = query_federation_rating(&dbtx, id, 200).await.unwrap();
+        assert_eq!(rating.count, 0);
+
+        let now = Utc::now().naive_utc();
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO nostr_hidden_votes VALUES ($1, NULL, $2)",
+            &[&votes[1].id.to_bytes().to_vec(), &now],
+        )
+        .await
+        .unwrap();
+        let rating = query_federation_rating(&dbtx, id, 0).await.unwrap();
+        assert_eq!(rating.count, 2);
+        assert_eq!(rating.avg, Some(5.0));
+
+        // Hiding an author's latest vote doesn't count their superseded one
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO nostr_hidden_votes VALUES ($1, NULL, $2)",
+            &[&votes[5].id.to_bytes().to_vec(), &now],
+        )
+        .await
+        .unwrap();
+        let rating = query_federation_rating(&dbtx, id, 0).await.unwrap();
+        assert_eq!(rating.count, 1);
+        assert_eq!(rating.avg, Some(5.0));
+
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO nostr_moderated_pubkeys VALUES ($1, FALSE, NULL, $2)",
+            &[&changed_mind.public_key().to_bytes().to_vec(), &now],
+        )
+        .await
+        .unwrap();
+        let rating = query_federation_rating(&dbtx, id, 0).await.unwrap();
+        assert_eq!(rating.count, 1);
+
+        // Once an author is allowed everyone else is ignored
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO nostr_moderated_pubkeys VALUES ($1, TRUE, NULL, $2)",
+            &[&votes[2].pubkey.to_bytes().to_vec(), &now],
+        )
+        .await
+        .unwrap();
+        let rating = query_federation_rating(&dbtx, id, 0).await.unwrap();
+        assert_eq!(rating.count, 0);
+        assert_eq!(rating.avg, None);
+
+        dbtx.rollback().await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_relay_url() {
+        assert_eq!(
+            parse_relay_url("wss://relay.damus.io").unwrap(),
+            parse_relay_url("wss://relay.damus.io/").unwrap()
+        );
+        assert!(parse_relay_url(" ws://localhost:7000 ").is_ok());
+        assert!(parse_relay_url("https://relay.damus.io").is_err());
+        assert!(parse_relay_url("relay.damus.io").is_err());
+    }
+
+    #[test]
+    fn test_announcement_identifiers() {
+        use nostr_sdk::nips::nip19::FromBech32;
+
+        let id = federation_id("federation");
+        let event = announcement(id, &invite_code(id).to_string());
+        let relays = vec!["wss://relay.damus.io".to_owned()];
+
+        let announcement = announcement_identifiers(&event, relays.clone()).unwrap();
+        assert_eq!(announcement.event_id, event.id.to_hex());
+
+        let coordinate = Coordinate::from_bech32(&announcement.naddr).unwrap();
+        assert_eq!(coordinate.kind, FEDERATION_ANNOUNCEMENT_EVENT_KIND);
+        assert_eq!(coordinate.public_key, event.pubkey);
+        assert_eq!(coordinate.identifier, id.to_string());
+        assert_eq!(coordinate.relays, relays);
+
+        let nevent = Nip19Event::from_bech32(&announcement.nevent).unwrap();
+        assert_eq!(nevent.event_id, event.id);
+        assert_eq!(nevent.author, Some(event.pubkey));
+    }
+}
@@ -1,51 +1,436 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::ensure;
+use anyhow::{ensure, Context};
 use bitcoin::hashes::Hash;
 use bitcoin::{Address, OutPoint, Txid};
-use chrono::{DateTime, NaiveDate};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use deadpool_postgres::{GenericClient, Runtime, Transaction};
-use fedimint_api_client::api::DynGlobalApi;
-use fedimint_api_client::download_from_invite_code;
 use fedimint_core::config::{ClientConfig, FederationId};
 use fedimint_core::core::DynModuleConsensusItem;
 use fedimint_core::encoding::Encodable;
 use fedimint_core::epoch::ConsensusItem;
 use fedimint_core::invite_code::InviteCode;
 use fedimint_core::session_outcome::SessionOutcome;
-use fedimint_core::task::TaskGroup;
+use fedimint_core::task::{TaskGroup, TaskHandle};
 use fedimint_core::util::backon::{ConstantBuilder, FibonacciBuilder};
 use fedimint_core::util::retry;
-use fedimint_core::{Amount, PeerId};
+use fedimint_core::{Amount, PeerId, TransactionId};
 use fedimint_ln_common::contracts::{Contract, IdentifiableContract};
 use fedimint_ln_common::{LightningInput, LightningOutput, LightningOutputV0};
 use fedimint_mint_common::{MintInput, MintOutput};
 use fedimint_wallet_common::{WalletConsensusItem, WalletInput, WalletOutput, WalletOutputV0};
 use fmo_api_types::{
-    FederationActivity, FederationHealth, FederationSummary, FederationUtxo, FedimintTotals,
+    BlockTime, ChainSyncStatus, FederationActivity, FederationHealth, FederationSummary,
+    FederationUtxo, FedimintTotals,
 };
 use futures::future::join_all;
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use postgres_from_row::FromRow;
+use tokio::sync::Notify;
 use tokio::time::sleep;
 use tokio_postgres::NoTls;
 use tracing::log::info;
 use tracing::{debug, error, warn};
 
+use crate::config::meta::{ConsensusMetaCache, MetaOverrideCache};
+use crate::federation::catch_up::CatchUpScheduler;
 use crate::federation::db::{Federation, FederationV0};
+use crate::federation::denominations::NoteDirection;
+use crate::federation::digest::DigestConfig;
+use crate::federation::health::HealthThresholds;
+use crate::federation::jobs::Job;
+use crate::federation::maintenance::VacuumWindow;
+use crate::federation::nostr::min_pow_from_env;
+use crate::federation::probes::HealthProbes;
+use crate::federation::processing_errors::{in_savepoint, ProcessingErrorPolicy, UnsupportedItem};
+use crate::federation::quarantine::{
+    unknown_input_variant, unknown_output_variant, QuarantinedItem,
+};
+use crate::federation::summary_cache::SummaryCache;
 use crate::federation::{db, decoders_from_config, instance_to_kind};
-use crate::util::{execute, query, query_one, query_opt, query_value};
+use crate::net::{EsploraClients, HttpClients};
+use crate::socks::{download_config, federation_api};
+use crate::usage::UsageTracker;
+use crate::util::{config_network, execute, query, query_one, query_opt, query_value};
+
+/// How long to wait for background tasks to finish when shutting down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_FETCH_PARALLELISM: usize = 32;
+const DEFAULT_COMMIT_BATCH_SIZE: usize = 100;
+const DEFAULT_MAX_CATCHING_UP: usize = 4;
+const DEFAULT_JOB_WORKERS: usize = 2;
+
+/// Schema migrations by the version they migrate to, applied in order
+const MIGRATIONS: &[(i32, &str)] = &[
+    (
+        0,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v0.sql")),
+    ),
+    (
+        1,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v1.sql")),
+    ),
+    (
+        2,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v2.sql")),
+    ),
+    (
+        3,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v3.sql")),
+    ),
+    (
+        4,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v4.sql")),
+    ),
+    (
+        5,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v5.sql")),
+    ),
+    (
+        6,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v6.sql")),
+    ),
+    (
+        7,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v7.sql")),
+    ),
+    (
+        8,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v8.sql")),
+    ),
+    (
+        9,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v9.sql")),
+    ),
+    (
+        10,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v10.sql")),
+    ),
+    (
+        11,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v11.sql")),
+    ),
+    (
+        12,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v12.sql")),
+    ),
+    (
+        13,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v13.sql")),
+    ),
+    (
+        14,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v14.sql")),
+    ),
+    (
+        15,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v15.sql")),
+    ),
+    (
+        16,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v16.sql")),
+    ),
+    (
+        17,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v17.sql")),
+    ),
+    (
+        18,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v18.sql")),
+    ),
+    (
+        19,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v19.sql")),
+    ),
+    (
+        20,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v20.sql")),
+    ),
+    (
+        21,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v21.sql")),
+    ),
+    (
+        22,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v22.sql")),
+    ),
+    (
+        23,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v23.sql")),
+    ),
+    (
+        24,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v24.sql")),
+    ),
+    (
+        25,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v25.sql")),
+    ),
+    (
+        26,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v26.sql")),
+    ),
+    (
+        27,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v27.sql")),
+    ),
+    (
+        28,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v28.sql")),
+    ),
+    (
+        29,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v29.sql")),
+    ),
+    (
+        30,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v30.sql")),
+    ),
+    (
+        31,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v31.sql")),
+    ),
+    (
+        32,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v32.sql")),
+    ),
+    (
+        33,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v33.sql")),
+    ),
+    (
+        34,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v34.sql")),
+    ),
+    (
+        35,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v35.sql")),
+    ),
+    (
+        36,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v36.sql")),
+    ),
+    (
+        37,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v37.sql")),
+    ),
+    (
+        38,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v38.sql")),
+    ),
+    (
+        39,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v39.sql")),
+    ),
+    (
+        40,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v40.sql")),
+    ),
+    (
+        41,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v41.sql")),
+    ),
+    (
+        42,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v42.sql")),
+    ),
+    (
+        43,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v43.sql")),
+    ),
+    (
+        44,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v44.sql")),
+    ),
+    (
+        45,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v45.sql")),
+    ),
+    (
+        46,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v46.sql")),
+    ),
+    (
+        47,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v47.sql")),
+    ),
+    (
+        48,
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v48.sql")),
+    ),
+];
+
+/// Schema version of a fully migrated database
+pub(super) const LATEST_SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].0;
+
+/// Tuning of how the history observers fetch and store sessions
+#[derive(Debug, Clone, Copy)]
+struct SyncConfig {
+    /// Number of sessions fetched from the federation concurrently
+    fetch_parallelism: usize,
+    /// Maximum number of sessions committed in one DB transaction. Sessions
+    /// are only batched while catching up, once the observer waits for new
+    /// sessions each is committed right away.
+    commit_batch_size: usize,
+    /// Number of observers that may catch up at the same time, the others
+    /// wait in a queue ordered by usage and activity
+    max_catching_up: usize,
+    /// Number of queued jobs like session replays that run at the same time
+    job_workers: usize,
+}
+
+impl SyncConfig {
+    fn from_env() -> anyhow::Result<SyncConfig> {
+        let fetch_parallelism = match dotenv::var("FO_SYNC_FETCH_PARALLELISM") {
+            Ok(parallelism) => parallelism
+                .parse()
+                .context("Invalid FO_SYNC_FETCH_PARALLELISM")?,
+            Err(_) => DEFAULT_FETCH_PARALLELISM,
+        };
+        let commit_batch_size = match dotenv::var("FO_SYNC_BATCH_SIZE") {
+            Ok(batch_size) => batch_size.parse().context("Invalid FO_SYNC_BATCH_SIZE")?,
+            Err(_) => DEFAULT_COMMIT_BATCH_SIZE,
+        };
+        let max_catching_up = match dotenv::var("FO_SYNC_MAX_CATCHING_UP") {
+            Ok(max) => max.parse().context("Invalid FO_SYNC_MAX_CATCHING_UP")?,
+            Err(_) => DEFAULT_MAX_CATCHING_UP,
+        };
+        let job_workers = match dotenv::var("FO_JOB_WORKERS") {
+            Ok(workers) => workers.parse().context("Invalid FO_JOB_WORKERS")?,
+            Err(_) => DEFAULT_JOB_WORKERS,
+        };
+        ensure!(
+            fetch_parallelism > 0 && commit_batch_size > 0 && max_catching_up > 0 && job_workers > 0,
+            "FO_SYNC_FETCH_PARALLELISM, FO_SYNC_BATCH_SIZE, FO_SYNC_MAX_CATCHING_UP and FO_JOB_WORKERS have to be positive"
+        );
+
+        Ok(SyncConfig {
+            fetch_parallelism,
+            commit_batch_size,
+            max_catching_up,
+            job_workers,
+        })
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct FederationObserver {
     connection_pool: deadpool_postgres::Pool,
     admin_auth: String,
     task_group: TaskGroup,
+    /// Subgroups of `task_group` running the observer and health monitor of
+    /// each federation, so they can be stopped individually
+    federation_task_groups: Arc<Mutex<BTreeMap<FederationId, TaskGroup>>>,
+    /// Last chain tip height reported by esplora, 0 if unknown
+    chain_tip_height: Arc<AtomicU32>,
+    esplora_clients: EsploraClients,
+    /// Client for operator-configured endpoints like alert webhooks
+    http_client: reqwest::Client,
+    sync_config: SyncConfig,
+    pub(super) catch_up: CatchUpScheduler,
+    health_thresholds: HealthThresholds,
+    summary_cache: SummaryCache,
+    health_probes: HealthProbes,
+    pub(super) processing_error_policy: ProcessingErrorPolicy,
+    /// Recommendations with less proof of work aren't counted in ratings
+    pub(super) nostr_min_pow: u8,
+    /// Wakes up job workers when a job is queued
+    pub(super) job_queued: Arc<Notify>,
 }
 
 impl FederationObserver {
-    pub async fn new(database: &str, admin_auth: &str) -> anyhow::Result<FederationObserver> {
+    pub async fn new(
+        database: &str,
+        admin_auth: &str,
+        http_clients: HttpClients,
+        usage_tracker: UsageTracker,
+    ) -> anyhow::Result<FederationObserver> {
+        let mut slf = Self::connect(database, admin_auth, http_clients).await?;
+        slf.catch_up = slf.catch_up.with_usage_tracker(usage_tracker);
+
+        for federation in slf.list_active_federations().await? {
+            slf.spawn_observer(federation).await;
+        }
+
+        slf.task_group
+            .spawn_cancellable("fetch block times", Self::fetch_block_times(slf.clone()));
+        slf.task_group
+            .spawn_cancellable("sync nostr events", Self::sync_nostr_events(slf.clone()));
+        slf.task_group.spawn_cancellable(
+            "probe nostr federations",
+            Self::probe_nostr_federations(slf.clone()),
+        );
+        slf.task_group
+            .spawn_cancellable("refresh views", Self::refresh_views(slf.clone()));
+        slf.task_group
+            .spawn_cancellable("evaluate alerts", Self::evaluate_alerts(slf.clone()));
+        slf.task_group.spawn_cancellable(
+            "check withdrawal replacements",
+            Self::check_withdrawal_replacements(slf.clone()),
+        );
+        slf.task_group.spawn_cancellable(
+            "check withdrawal spends",
+            Self::check_withdrawal_spends(slf.clone()),
+        );
+        slf.task_group.spawn_cancellable(
+            "deliver notifications",
+            Self::deliver_notifications(slf.clone()),
+        );
+        slf.task_group.spawn_cancellable(
+            "replay quarantined sessions",
+            Self::replay_quarantined_sessions(slf.clone()),
+        );
+        slf.task_group.spawn_cancellable(
+            "verify restored sessions",
+            Self::verify_restored_sessions(slf.clone()),
+        );
+        slf.task_group.spawn_cancellable(
+            "maintain database",
+            Self::maintain_database(slf.clone(), VacuumWindow::from_env()?),
+        );
+        // Jobs interrupted by the last shutdown are picked up again
+        slf.requeue_interrupted_jobs().await?;
+        for worker in 0..slf.sync_config.job_workers {
+            slf.task_group.spawn_cancellable(
+                format!("job worker {worker}"),
+                Self::run_job_worker(slf.clone()),
+            );
+        }
+        if let Some(digest_config) = DigestConfig::from_env()? {
+            slf.task_group.spawn_cancellable(
+                "publish daily digest",
+                Self::publish_daily_digests(slf.clone(), digest_config),
+            );
+        }
+
+        Ok(slf)
+    }
+
+    /// Periodically records changes of the federations' meta fields, which
+    /// needs the meta caches shared with the API
+    pub fn spawn_meta_history_recorder(
+        &self,
+        meta_override_cache: MetaOverrideCache,
+        consensus_meta_cache: ConsensusMetaCache,
+    ) {
+        self.task_group.spawn_cancellable(
+            "record meta history",
+            Self::record_meta_history(self.clone(), meta_override_cache, consensus_meta_cache),
+        );
+    }
+
+    /// Connects to the database and migrates its schema without starting any
+    /// background tasks, used by one-off commands
+    pub async fn connect(
+        database: &str,
+        admin_auth: &str,
+        http_clients: HttpClients,
+    ) -> anyhow::Result<FederationObserver> {
+        let sync_config = SyncConfig::from_env()?;
         let connection_pool = {
             let pool_config = deadpool_postgres::Config {
                 url: Some(database.to_owned()),
@@ -58,61 +443,161 @@ impl FederationObserver {
             connection_pool,
             admin_auth: admin_auth.to_owned(),
             task_group: Default::default(),
+            federation_task_groups: Default::default(),
+            chain_tip_height: Default::default(),
+            esplora_clients: http_clients.esplora,
+            http_client: http_clients.http,
+            sync_config,
+            catch_up: CatchUpScheduler::new(sync_config.max_catching_up),
+            health_thresholds: HealthThresholds::from_env()?,
+            summary_cache: SummaryCache::from_env()?,
+            health_probes: HealthProbes::from_env()?,
+            processing_error_policy: ProcessingErrorPolicy::from_env()?,
+            nostr_min_pow: min_pow_from_env()?,
+            job_queued: Default::default(),
         };
 
         slf.setup_schema().await?;
 
-        for federation in slf.list_federations().await? {
-            slf.spawn_observer(federation).await;
-        }
-
-        slf.task_group
-            .spawn_cancellable("fetch block times", Self::fetch_block_times(slf.clone()));
-        slf.task_group
-            .spawn_cancellable("sync nostr events", Self::sync_nostr_events(slf.clone()));
-        slf.task_group
-            .spawn_cancellable("refresh views", Self::refresh_views(slf.clone()));
-
         Ok(slf)
     }
 
-    async fn spawn_observer(&self, federation: Federation) {
-        let slf = self.clone();
+    pub(super) async fn spawn_observer(&self, federation: Federation) {
+        match self.is_observer_quarantined(federation.federation_id).await {
+            Ok(false) => self.spawn_history_observer(federation.clone()),
+            Ok(true) => warn!(
+                "Observer for {} is quarantined, not starting it",
+                federation.federation_id
+            ),
+            Err(e) => error!(
+                "Failed to check quarantine status of {}, not starting observer: {e:?}",
+                federation.federation_id
+            ),
+        }
 
-        let federation_inner = federation.clone();
-        self.task_group.spawn_cancellable(
-            format!("Observer for {}", federation_inner.federation_id),
+        let slf = self.clone();
+        let task_group = self.federation_task_group(federation.federation_id);
+        task_group.spawn_cancellable(
+            format!("Health Monitor for {}", federation.federation_id),
             async move {
                 loop {
                     let e = slf
-                        .observe_federation_history(
-                            federation_inner.federation_id,
-                            federation_inner.config.clone(),
-                        )
+                        .monitor_health(federation.federation_id, federation.config.clone())
                         .await
-                        .expect_err("observer task exited unexpectedly");
-                    error!("Observer errored, restarting in 30s: {e}");
+                        .expect_err("health monitor task exited unexpectedly");
+                    error!("Health Monitor errored, restarting in 30s: {e}");
                     tokio::time::sleep(Duration::from_secs(30)).await;
                 }
             },
         );
+    }
 
+    /// Spawns the task following the federation's consensus history. If it
+    /// keeps failing it's restarted with exponential backoff until it gets
+    /// quarantined. Unlike the other tasks it isn't cancelled on shutdown but
+    /// stops after committing the session it's processing.
+    pub(super) fn spawn_history_observer(&self, federation: Federation) {
         let slf = self.clone();
-        self.task_group.spawn_cancellable(
-            format!("Health Monitor for {}", federation.federation_id),
-            async move {
+        let task_group = self.federation_task_group(federation.federation_id);
+        task_group.spawn(
+            format!("Observer for {}", federation.federation_id),
+            move |task_handle| async move {
                 loop {
-                    let e = slf
-                        .monitor_health(federation.federation_id, federation.config.clone())
+                    let start = Instant::now();
+                    let Err(e) = slf
+                        .observe_federation_history(
+                            federation.federation_id,
+                            federation.config.clone(),
+                            &task_handle,
+                        )
                         .await
-                        .expect_err("health monitor task exited unexpectedly");
-                    error!("Health Monitor errored, restarting in 30s: {e}");
-                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    else {
+                        break;
+                    };
+
+                    let delay = match slf
+                        .record_observer_failure(federation.federation_id, &e, start.elapsed())
+                        .await
+                    {
+                        Ok(Some(delay)) => {
+                            error!(
+                                "Observer errored, restarting in {}s: {e}",
+                                delay.as_secs()
+                            );
+                            delay
+                        }
+                        Ok(None) => {
+                            error!("Observer errored too often, quarantining it: {e}");
+                            break;
+                        }
+                        Err(status_error) => {
+                            error!("Observer errored, restarting in 30s: {e} (failed to record failure: {status_error:?})");
+                            Duration::from_secs(30)
+                        }
+                    };
+
+                    tokio::select! {
+                        () = tokio::time::sleep(delay) => {}
+                        _ = task_handle.make_shutdown_rx() => break,
+                    }
                 }
             },
         );
     }
 
+    fn federation_task_group(&self, federation_id: FederationId) -> TaskGroup {
+        self.federation_task_groups
+            .lock()
+            .expect("Lock poisoned")
+            .entry(federation_id)
+            .or_insert_with(|| self.task_group.make_subgroup())
+            .clone()
+    }
+
+    /// Number of federations whose observer and health monitor tasks are
+    /// running
+    pub(super) fn running_observers(&self) -> usize {
+        self.federation_task_groups
+            .lock()
+            .expect("Lock poisoned")
+            .len()
+    }
+
+    /// Stops all tasks belonging to the federation, waiting for them to exit
+    pub(super) async fn stop_federation_tasks(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        let task_group = self
+            .federation_task_groups
+            .lock()
+            .expect("Lock poisoned")
+            .remove(&federation_id);
+
+        if let Some(task_group) = task_group {
+            task_group
+                .shutdown_join_all(Some(Duration::from_secs(30)))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops all background tasks, letting the history observers commit the
+    /// sessions they are processing, and refreshes the views one last time so
+    /// they include all committed sessions
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        info!("Stopping background tasks");
+        self.task_group
+            .clone()
+            .shutdown_join_all(Some(SHUTDOWN_TIMEOUT))
+            .await?;
+
+        self.refresh_views_inner().await?;
+
+        Ok(())
+    }
+
     async fn setup_schema(&self) -> anyhow::Result<()> {
         execute(
             &self.connection().await?,
@@ -151,42 +636,16 @@ impl FederationObserver {
         let schema_version =
             query_value::<i32>(&self.connection().await?, "SELECT get_max_version();", &[]).await?;
 
-        let migration_map = [
-            (
-                0,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v0.sql")),
-            ),
-            (
-                1,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v1.sql")),
-            ),
-            (
-                2,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v2.sql")),
-            ),
-            (
-                3,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v3.sql")),
-            ),
-            (
-                4,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v4.sql")),
-            ),
-            (
-                5,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v5.sql")),
-            ),
-            (
-                6,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v6.sql")),
-            ),
-            (
-                7,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/schema/v7.sql")),
-            ),
-        ];
+        // Has to exist before migrating so the migrations' backfills can be tracked
+        self.connection()
+            .await?
+            .batch_execute(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/schema/jobs.sql"
+            )))
+            .await?;
 
-        for (version, migration) in migration_map.iter() {
+        for (version, migration) in MIGRATIONS {
             if *version > schema_version {
                 let mut conn = self.connection().await?;
                 let transaction = conn.transaction().await?;
@@ -220,6 +679,7 @@ impl FederationObserver {
     async fn backfill_v2_migration_wallet_data(
         &self,
         dbtx: &Transaction<'_>,
+        job: &mut Job,
     ) -> anyhow::Result<()> {
         info!("Beginning backfill for v2 wallet migration data, this may take a long time");
 
@@ -275,6 +735,7 @@ impl FederationObserver {
                 )
                 .await?;
             }
+            job.advance().await?;
         }
         Ok(())
     }
@@ -301,40 +762,200 @@ impl FederationObserver {
         Ok(())
     }
 
+    async fn backfill_v8_session_peer_stats(
+        &self,
+        dbtx: &Transaction<'_>,
+        job: &mut Job,
+    ) -> anyhow::Result<()> {
+        info!("Beginning backfill of per-peer session statistics, this may take a while");
+
+        for fed in self.list_federations().await? {
+            let sessions = query::<db::SessionOutcome>(
+                dbtx,
+                "SELECT session_index, session FROM sessions WHERE federation_id = $1",
+                &[&fed.federation_id.consensus_encode_to_vec()],
+            )
+            .await?;
+
+            info!(
+                "Counting items of {} sessions for fed: {}",
+                sessions.len(),
+                fed.federation_id
+            );
+            for session in sessions {
+                Self::insert_session_peer_stats(
+                    dbtx,
+                    fed.federation_id,
+                    session.session_index as u64,
+                    &session.data,
+                )
+                .await?;
+            }
+            job.advance().await?;
+        }
+        Ok(())
+    }
+
+    async fn backfill_v24_federation_networks(&self, dbtx: &Transaction<'_>) -> anyhow::Result<()> {
+        for fed in self.list_federations().await? {
+            dbtx.execute(
+                // language=postgresql
+                "UPDATE federations SET network = $1 WHERE federation_id = $2",
+                &[
+                    &fed.network()?.map(|network| network.to_string()),
+                    &fed.federation_id.consensus_encode_to_vec(),
+                ],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Runs the backfill belonging to a migration, tracking its progress in
+    /// federations processed
     async fn handle_backfill(&self, version: i32, dbtx: &Transaction<'_>) -> anyhow::Result<()> {
-        match version {
-            2 => Ok(self.backfill_v2_migration_wallet_data(dbtx).await?),
-            6 => Ok(self.backfill_v6_migrate_configs(dbtx).await?),
-            _ => Ok(()),
+        if !matches!(version, 2 | 6 | 8 | 18 | 24 | 25) {
+            return Ok(());
+        }
+
+        let federations = self.list_federations().await?.len() as u64;
+        let mut job = self
+            .start_job(&format!("migration_v{version}"), None, federations)
+            .await?;
+        let result = match version {
+            2 => self.backfill_v2_migration_wallet_data(dbtx, &mut job).await,
+            6 => self.backfill_v6_migrate_configs(dbtx).await,
+            8 => self.backfill_v8_session_peer_stats(dbtx, &mut job).await,
+            18 => self.backfill_v18_gateways(dbtx, &mut job).await,
+            24 => self.backfill_v24_federation_networks(dbtx).await,
+            25 => self.backfill_v25_item_details(dbtx, &mut job).await,
+            _ => unreachable!("Checked above"),
+        };
+        if result.is_ok() {
+            // The backfills without progress reporting are quick
+            job.progress(federations).await?;
         }
+        job.finish(&result).await?;
+
+        result
     }
 
     pub(super) async fn connection(&self) -> anyhow::Result<deadpool_postgres::Object> {
         Ok(self.connection_pool.get().await?)
     }
 
+    pub(super) fn http_client(&self) -> &reqwest::Client {
+        &self.http_client
+    }
+
+    pub(super) fn health_thresholds(&self) -> HealthThresholds {
+        self.health_thresholds
+    }
+
+    pub(super) fn summary_cache(&self) -> &SummaryCache {
+        &self.summary_cache
+    }
+
+    pub(super) fn health_probes(&self) -> &HealthProbes {
+        &self.health_probes
+    }
+
+    /// Block explorer for `network`, fails if none is configured for it
+    pub(super) fn esplora_client(
+        &self,
+        network: bitcoin::Network,
+    ) -> anyhow::Result<&esplora_client::AsyncClient> {
+        self.esplora_clients.get(network)
+    }
+
+    /// Lists all federations, including deactivated ones
     pub async fn list_federations(&self) -> anyhow::Result<Vec<db::Federation>> {
         query(&self.connection().await?, "SELECT * FROM federations", &[]).await
     }
 
+    /// Lists the federations that are currently being observed
+    pub async fn list_active_federations(&self) -> anyhow::Result<Vec<db::Federation>> {
+        query(
+            &self.connection().await?,
+            "SELECT * FROM federations WHERE active",
+            &[],
+        )
+        .await
+    }
+
     pub async fn list_federation_summaries(&self) -> anyhow::Result<Vec<FederationSummary>> {
         // TODO: possibly combine list and health query
-        let federations =
-            query::<Federation>(&self.connection().await?, "SELECT * FROM federations", &[])
-                .await?;
+        let federations = self.list_active_federations().await?;
+
+        self.federation_summaries(federations).await
+    }
+
+    /// Summary of a single federation, `None` if it isn't observed
+    pub async fn federation_summary(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<FederationSummary>> {
+        let Some(federation) = self.get_federation(federation_id).await? else {
+            return Ok(None);
+        };
+
+        Ok(self.federation_summaries(vec![federation]).await?.pop())
+    }
+
+    /// Summaries of several federations at once, ones that aren't observed are
+    /// left out
+    pub async fn federation_summaries_by_id(
+        &self,
+        federation_ids: &[FederationId],
+    ) -> anyhow::Result<Vec<FederationSummary>> {
+        let federations = self
+            .list_federations()
+            .await?
+            .into_iter()
+            .filter(|federation| federation_ids.contains(&federation.federation_id))
+            .collect();
 
+        self.federation_summaries(federations).await
+    }
+
+    /// Computes the summaries from the federations' history, which takes
+    /// several queries per federation. Use the cached
+    /// [`FederationObserver::federation_summaries`] to answer requests.
+    pub(super) async fn compute_federation_summaries(
+        &self,
+        federations: Vec<Federation>,
+    ) -> anyhow::Result<Vec<FederationSummary>> {
         let federation_health = self.get_guardian_health_summary().await?;
+        let latest_meta = self.latest_meta().await?;
 
         join_all(federations.into_iter().map(|federation| {
             let federation_health_ref = &federation_health;
+            let meta = latest_meta.get(&federation.federation_id);
             async move {
                 let deposits = self.get_federation_assets(federation.federation_id).await?;
-                let name = federation
-                    .config
-                    .global
-                    .meta
-                    .get("federation_name")
-                    .cloned();
+                // Prefer the recorded meta fields, which include override files and
+                // consensus meta, until they are recorded fall back to the config
+                let meta_field = |field: &str| {
+                    meta.and_then(|meta| meta.get(field))
+                        .and_then(|value| value.as_str())
+                        .map(ToOwned::to_owned)
+                };
+                let name = meta_field("federation_name").or_else(|| {
+                    federation
+                        .config
+                        .global
+                        .meta
+                        .get("federation_name")
+                        .cloned()
+                });
+                let icon_url = meta_field("federation_icon_url").or_else(|| {
+                    federation
+                        .config
+                        .global
+                        .meta
+                        .get("federation_icon_url")
+                        .cloned()
+                });
 
                 let health = federation_health_ref
                     .get(&federation.federation_id)
@@ -362,11 +983,16 @@ impl FederationObserver {
                 Ok(FederationSummary {
                     id: federation.federation_id,
                     name,
+                    icon_url,
                     last_7d_activity,
                     deposits,
                     invite,
+                    network: federation.network()?.map(|network| network.to_string()),
                     nostr_votes: self.federation_rating(federation.federation_id).await?,
                     health,
+                    created_at: self.federation_created_at(federation.federation_id).await?,
+                    shutdown: self.federation_shutdown(federation.federation_id).await?,
+                    rating_index: 0.0,
                 })
             }
         }))
@@ -375,7 +1001,24 @@ impl FederationObserver {
         .collect()
     }
 
-    async fn federation_activity(
+    /// Estimates when the federation was created from the timestamp of its
+    /// earliest session with a known block height
+    pub async fn federation_created_at(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<DateTime<Utc>>> {
+        let created_at = query_value::<Option<NaiveDateTime>>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT MIN(estimated_session_timestamp) FROM session_times WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(created_at.map(|created_at| created_at.and_utc()))
+    }
+
+    pub(super) async fn federation_activity(
         &self,
         federation_id: FederationId,
         days: u32,
@@ -434,19 +1077,25 @@ impl FederationObserver {
     pub async fn add_federation(&self, invite: &InviteCode) -> anyhow::Result<FederationId> {
         let federation_id = invite.federation_id();
 
-        if self.get_federation(federation_id).await?.is_some() {
+        if let Some(federation) = self.get_federation(federation_id).await? {
+            if self.reactivate_federation(federation_id).await? {
+                self.spawn_observer(federation).await;
+            }
             return Ok(federation_id);
         }
 
-        let config = download_from_invite_code(invite).await?;
+        let config = download_config(invite).await?;
+        let network = config_network(&config)?;
 
         self.connection()
             .await?
             .execute(
-                "INSERT INTO federations VALUES ($1, $2)",
+                // language=postgresql
+                "INSERT INTO federations (federation_id, config, network) VALUES ($1, $2, $3)",
                 &[
                     &federation_id.consensus_encode_to_vec(),
                     &config.consensus_encode_to_vec(),
+                    &network.map(|network| network.to_string()),
                 ],
             )
             .await?;
@@ -460,10 +1109,10 @@ impl FederationObserver {
         Ok(federation_id)
     }
 
-    // FIXME: use middleware for auth and get it out of here
-    pub fn check_auth(&self, bearer_token: &str) -> anyhow::Result<()> {
-        ensure!(self.admin_auth == bearer_token, "Invalid bearer token");
-        Ok(())
+    /// Whether `bearer_token` is the `FO_ADMIN_AUTH` token, which has all
+    /// scopes
+    pub(super) fn is_admin_token(&self, bearer_token: &str) -> bool {
+        self.admin_auth == bearer_token
     }
 
     async fn fetch_block_times(self) {
@@ -478,14 +1127,17 @@ impl FederationObserver {
     }
 
     async fn fetch_block_times_inner(&self) -> anyhow::Result<()> {
-        let builder = esplora_client::Builder::new("https://mempool.space/api");
-        let esplora_client = builder.build_async()?;
+        // Block times are only needed to estimate session times, which we only
+        // do for mainnet federations
+        let esplora_client = self.esplora_client(bitcoin::Network::Bitcoin)?.clone();
 
         // TODO: find a better way to pre-seed the DB so we don't have to bother
         // blockstream.info Block 820k was mined Dec 2023, afaik there are no
         // compatible federations older than that
         let next_block_height = self.last_fetched_block_height().await?.unwrap_or(820_000) + 1;
         let current_block_height = esplora_client.get_height().await?;
+        self.chain_tip_height
+            .store(current_block_height, Ordering::Relaxed);
 
         info!("Fetching block times for block {next_block_height} to {current_block_height}");
 
@@ -542,24 +1194,103 @@ impl FederationObserver {
         Ok(max_height.map(|max_height| max_height as u32))
     }
 
+    /// Returns the synced block times with heights in `from..=to`, at most
+    /// `limit` of them
+    pub async fn block_times(
+        &self,
+        from: u32,
+        to: u32,
+        limit: u32,
+    ) -> anyhow::Result<Vec<BlockTime>> {
+        #[derive(Debug, FromRow)]
+        struct BlockTimeRow {
+            block_height: i32,
+            timestamp: NaiveDateTime,
+        }
+
+        Ok(query::<BlockTimeRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT block_height, timestamp
+             FROM block_times
+             WHERE block_height >= $1 AND block_height <= $2
+             ORDER BY block_height
+             LIMIT $3",
+            &[
+                &i32::try_from(from)?,
+                &i32::try_from(to).unwrap_or(i32::MAX),
+                &i64::from(limit),
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| BlockTime {
+            block_height: row.block_height as u32,
+            timestamp: row.timestamp.and_utc(),
+        })
+        .collect())
+    }
+
+    pub async fn chain_sync_status(&self) -> anyhow::Result<ChainSyncStatus> {
+        #[derive(Debug, FromRow)]
+        struct LatestBlockRow {
+            block_height: i32,
+            timestamp: NaiveDateTime,
+        }
+
+        let latest_block = query_opt::<LatestBlockRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT block_height, timestamp FROM block_times ORDER BY block_height DESC LIMIT 1",
+            &[],
+        )
+        .await?;
+        let synced_height = latest_block.as_ref().map(|block| block.block_height as u32);
+        let chain_tip_height =
+            Some(self.chain_tip_height.load(Ordering::Relaxed)).filter(|height| *height != 0);
+
+        Ok(ChainSyncStatus {
+            synced_height,
+            synced_block_time: latest_block.map(|block| block.timestamp.and_utc()),
+            chain_tip_height,
+            blocks_behind: chain_tip_height
+                .map(|tip| tip.saturating_sub(synced_height.unwrap_or_default())),
+        })
+    }
+
+    /// Follows the federation's consensus history until `task_handle` signals
+    /// a shutdown, only ever returns `Ok` in that case
     async fn observe_federation_history(
         &self,
         federation_id: FederationId,
         config: ClientConfig,
+        task_handle: &TaskHandle,
     ) -> anyhow::Result<()> {
-        let api = DynGlobalApi::from_endpoints(
-            config
-                .global
-                .api_endpoints
-                .iter()
-                .map(|(&peer_id, peer_url)| (peer_id, peer_url.url.clone())),
-            &None,
-        );
+        let api = federation_api(&config.global.api_endpoints);
         let decoders = decoders_from_config(&config);
 
         info!("Starting background job for {federation_id}");
-        let next_session = self.federation_session_count(federation_id).await?;
+        let next_session = match self.observer_checkpoint(federation_id).await? {
+            Some(last_session) => last_session + 1,
+            None => self.federation_session_count(federation_id).await?,
+        };
         debug!("Next session {next_session}");
+
+        let session_count = match api.session_count().await {
+            Ok(session_count) => session_count,
+            Err(e) => {
+                warn!(%federation_id, "Failed to fetch session count, not queueing catch-up: {e}");
+                next_session
+            }
+        };
+        let recent_transactions = self.recent_transaction_count(federation_id).await?;
+        let catch_up = self.catch_up.register(
+            federation_id,
+            next_session,
+            session_count,
+            recent_transactions,
+        );
+
         let api_fetch = api.clone();
         let mut session_stream = futures::stream::iter(next_session..)
             .map(move |session_index| {
@@ -584,11 +1315,40 @@ impl FederationObserver {
                     (session_index, signed_session_outcome)
                 }
             })
-            .buffered(32);
+            .buffered(self.sync_config.fetch_parallelism);
 
         let mut timer = SystemTime::now();
         let mut last_session = next_session;
-        while let Some((session_index, signed_session_outcome)) = session_stream.next().await {
+        let mut shutdown = std::pin::pin!(task_handle.make_shutdown_rx());
+        tokio::select! {
+            () = catch_up.wait_turn() => {}
+            _ = &mut shutdown => {
+                info!("Stopping background job for {federation_id} while waiting to catch up");
+                return Ok(());
+            }
+        }
+        let mut sync_job = if next_session < session_count {
+            Some(
+                self.start_job("sync", Some(federation_id), session_count - next_session)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        loop {
+            // Only waiting for the next session is interrupted by a shutdown, a
+            // session that was fetched already is still committed
+            let next = tokio::select! {
+                next = session_stream.next() => next,
+                _ = &mut shutdown => {
+                    info!("Stopping background job for {federation_id}");
+                    return Ok(());
+                }
+            };
+            let Some((mut session_index, signed_session_outcome)) = next else {
+                break;
+            };
+
             let mut connection = self.connection().await?;
             let dbtx = connection.transaction().await?;
             self.process_session(
@@ -599,7 +1359,37 @@ impl FederationObserver {
                 &dbtx,
             )
             .await?;
+
+            // Sessions that were fetched already are added to the same DB
+            // transaction, which speeds up the initial sync considerably
+            for _ in 1..self.sync_config.commit_batch_size {
+                let Some(Some((next_index, signed_session_outcome))) =
+                    session_stream.next().now_or_never()
+                else {
+                    break;
+                };
+                self.process_session(
+                    federation_id,
+                    config.clone(),
+                    next_index,
+                    signed_session_outcome,
+                    &dbtx,
+                )
+                .await?;
+                session_index = next_index;
+            }
+
+            Self::record_observer_checkpoint(&dbtx, federation_id, session_index).await?;
             dbtx.commit().await?;
+            catch_up.record_progress(session_index + 1);
+            if let Some(job) = sync_job.as_mut() {
+                job.progress(session_index + 1 - next_session).await?;
+            }
+            if session_index + 1 >= session_count {
+                if let Some(job) = sync_job.take() {
+                    job.finish(&Ok(())).await?;
+                }
+            }
 
             let elapsed = timer.elapsed().unwrap_or_default();
             if elapsed >= Duration::from_secs(5) {
@@ -614,7 +1404,7 @@ impl FederationObserver {
         unreachable!("Session stream should never end")
     }
 
-    async fn process_session(
+    pub(super) async fn process_session(
         &self,
         federation_id: FederationId,
         config: ClientConfig,
@@ -632,34 +1422,69 @@ impl FederationObserver {
         )
         .await?;
 
+        Self::insert_session_peer_stats(
+            dbtx,
+            federation_id,
+            session_index,
+            &signed_session_outcome,
+        )
+        .await?;
+
+        let network = config_network(&config)?.unwrap_or(bitcoin::Network::Bitcoin);
         for (item_idx, item) in signed_session_outcome.items.into_iter().enumerate() {
-            match item.item {
+            // Rows of an item that fails to process are rolled back, so sessions
+            // never contain half-processed transactions
+            let (txid, result) = match item.item {
                 ConsensusItem::Transaction(transaction) => {
-                    Self::process_transaction(
+                    let txid = transaction.tx_hash();
+                    let result = in_savepoint(
                         dbtx,
-                        federation_id,
-                        &config,
-                        session_index,
-                        item_idx as u64,
-                        transaction,
+                        Self::process_transaction(
+                            dbtx,
+                            federation_id,
+                            &config,
+                            network,
+                            session_index,
+                            item_idx as u64,
+                            transaction,
+                        ),
                     )
                     .await?;
+                    (Some(txid), result)
                 }
                 ConsensusItem::Module(module_ci) => {
-                    Self::process_ci(
+                    let result = in_savepoint(
                         dbtx,
-                        federation_id,
-                        &config,
-                        session_index,
-                        item_idx as u64,
-                        item.peer,
-                        module_ci,
+                        self.process_ci(
+                            dbtx,
+                            federation_id,
+                            &config,
+                            network,
+                            session_index,
+                            item_idx as u64,
+                            item.peer,
+                            module_ci,
+                        ),
                     )
                     .await?;
+                    (None, result)
                 }
                 _ => {
                     // Ignore unknown CIs
+                    continue;
                 }
+            };
+
+            if let Err(error) = result {
+                self.handle_processing_error(
+                    dbtx,
+                    federation_id,
+                    session_index,
+                    item_idx as u64,
+                    txid,
+                    error,
+                )
+                .await?;
             }
         }
 
@@ -667,14 +1492,38 @@ impl FederationObserver {
         Ok(())
     }
 
+    async fn insert_session_peer_stats(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        session_outcome: &SessionOutcome,
+    ) -> Result<(), tokio_postgres::Error> {
+        for (peer_id, stats) in peer_item_stats(session_outcome) {
+            dbtx.execute(
+                "INSERT INTO session_peer_stats VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &(session_index as i32),
+                    &(peer_id.to_usize() as i32),
+                    &(stats.item_count as i32),
+                    &(stats.transaction_count as i32),
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
     async fn process_transaction(
         dbtx: &Transaction<'_>,
         federation_id: FederationId,
         config: &ClientConfig,
+        network: bitcoin::Network,
         session_index: u64,
         item_index: u64,
         transaction: fedimint_core::transaction::Transaction,
-    ) -> Result<(), tokio_postgres::Error> {
+    ) -> anyhow::Result<()> {
         let fedimint_txid = transaction.tx_hash();
 
         dbtx.execute(
@@ -689,38 +1538,69 @@ impl FederationObserver {
         )
         .await?;
 
+        Self::insert_transaction_details(dbtx, federation_id, config, &transaction).await?;
+
+        // Fees can't be calculated if amounts are missing
+        let mut has_quarantined_items = false;
+
         for (in_idx, input) in transaction.inputs.into_iter().enumerate() {
             let kind = instance_to_kind(config, input.module_instance_id());
+            let unknown_variant = unknown_input_variant(&kind, &input);
+            if let Some(variant) = unknown_variant {
+                has_quarantined_items = true;
+                Self::quarantine_item(
+                    dbtx,
+                    federation_id,
+                    session_index,
+                    fedimint_txid,
+                    QuarantinedItem {
+                        direction: "input",
+                        idx: in_idx,
+                        kind: &kind,
+                        variant,
+                        item: input.consensus_encode_to_vec(),
+                    },
+                )
+                .await?;
+            }
             let (maybe_amount_msat, maybe_ln_contract_id) = match kind.as_str() {
+                _ if unknown_variant.is_some() => (None, None),
                 "ln" => {
                     let input = input
                         .as_any()
                         .downcast_ref::<LightningInput>()
-                        .expect("Not LN input")
+                        .ok_or_else(|| UnsupportedItem::new("Not LN input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0");
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?;
 
                     (Some(input.amount.msats), Some(input.contract_id))
                 }
                 "mint" => {
-                    let amount_msat = input
+                    let amount = input
                         .as_any()
                         .downcast_ref::<MintInput>()
-                        .expect("Not Mint input")
+                        .ok_or_else(|| UnsupportedItem::new("Not Mint input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0")
-                        .amount
-                        .msats;
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?
+                        .amount;
+                    Self::record_mint_note(
+                        dbtx,
+                        federation_id,
+                        session_index,
+                        amount,
+                        NoteDirection::Redeemed,
+                    )
+                    .await?;
 
-                    (Some(amount_msat), None)
+                    (Some(amount.msats), None)
                 }
                 "wallet" => {
                     let amount_msat = input
                         .as_any()
                         .downcast_ref::<WalletInput>()
-                        .expect("Not Wallet input")
+                        .ok_or_else(|| UnsupportedItem::new("Not Wallet input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0")
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?
                         .0
                         .tx_output()
                         .value
@@ -743,22 +1623,22 @@ impl FederationObserver {
             )
             .await?;
 
-            if kind.as_str() == "wallet" {
+            if kind.as_str() == "wallet" && unknown_variant.is_none() {
                 let peg_in_proof = &input
                     .as_any()
                     .downcast_ref::<WalletInput>()
-                    .expect("Not Wallet input")
+                    .ok_or_else(|| UnsupportedItem::new("Not Wallet input"))?
                     .maybe_v0_ref()
-                    .expect("Not v0")
+                    .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?
                     .0;
 
                 let outpoint = peg_in_proof.outpoint();
 
                 let address = bitcoin::Address::from_script(
                     bitcoin::Script::from_bytes(peg_in_proof.tx_output().script_pubkey.as_bytes()),
-                    bitcoin::Network::Bitcoin,
+                    network,
                 )
-                .expect("Invalid output address");
+                .map_err(|e| UnsupportedItem::new(format!("Invalid peg-in address: {e}")))?;
 
                 dbtx.execute(
                         "INSERT INTO wallet_peg_ins VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING",
@@ -766,7 +1646,7 @@ impl FederationObserver {
                             &outpoint.txid[..].to_owned(),
                             &(outpoint.vout as i32),
                             &address.to_string(),
-                            &maybe_amount_msat.map(|amt| amt as i64).expect("Wallet input must have amount"),
+                            &maybe_amount_msat.map(|amt| amt as i64).ok_or_else(|| UnsupportedItem::new("Wallet input without amount"))?,
                             &federation_id.consensus_encode_to_vec(),
                             &fedimint_txid.consensus_encode_to_vec(),
                             &(in_idx as i32),
@@ -777,33 +1657,62 @@ impl FederationObserver {
 
         for (out_idx, output) in transaction.outputs.into_iter().enumerate() {
             let kind = instance_to_kind(config, output.module_instance_id());
+            let unknown_variant = unknown_output_variant(&kind, &output);
+            if let Some(variant) = unknown_variant {
+                has_quarantined_items = true;
+                Self::quarantine_item(
+                    dbtx,
+                    federation_id,
+                    session_index,
+                    fedimint_txid,
+                    QuarantinedItem {
+                        direction: "output",
+                        idx: out_idx,
+                        kind: &kind,
+                        variant,
+                        item: output.consensus_encode_to_vec(),
+                    },
+                )
+                .await?;
+            }
             let (maybe_amount_msat, maybe_ln_contract) = match kind.as_str() {
+                _ if unknown_variant.is_some() => (None, None),
                 "ln" => {
                     let ln_output = output
                         .as_any()
                         .downcast_ref::<LightningOutput>()
-                        .expect("Not LN input")
+                        .ok_or_else(|| UnsupportedItem::new("Not LN input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0");
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?;
                     let (maybe_amount_msat, ln_contract_interaction_kind, contract_id) =
                         match ln_output {
                             LightningOutputV0::Contract(contract) => {
                                 let contract_id = contract.contract.contract_id();
-                                let (contract_type, payment_hash) = match &contract.contract {
-                                    Contract::Incoming(c) => ("incoming", c.hash),
-                                    Contract::Outgoing(c) => ("outgoing", c.hash),
+                                let (contract_type, payment_hash, gateway_key) = match &contract
+                                    .contract
+                                {
+                                    Contract::Incoming(c) => ("incoming", c.hash, c.gateway_key),
+                                    Contract::Outgoing(c) => ("outgoing", c.hash, c.gateway_key),
                                 };
 
                                 dbtx.execute(
-                                    "INSERT INTO ln_contracts VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                                    "INSERT INTO ln_contracts VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
                                     &[
                                         &federation_id.consensus_encode_to_vec(),
                                         &contract_id.consensus_encode_to_vec(),
                                         &contract_type,
                                         &payment_hash.consensus_encode_to_vec(),
+                                        &gateway_key.consensus_encode_to_vec(),
                                     ],
                                 )
                                 .await?;
+                                Self::record_gateway_activity(
+                                    dbtx,
+                                    federation_id,
+                                    session_index,
+                                    gateway_key,
+                                )
+                                .await?;
 
                                 (Some(contract.amount.msats), "fund", contract_id)
                             }
@@ -822,23 +1731,30 @@ impl FederationObserver {
                     )
                 }
                 "mint" => {
-                    let amount_msat = output
+                    let amount = output
                         .as_any()
                         .downcast_ref::<MintOutput>()
-                        .expect("Not Mint input")
+                        .ok_or_else(|| UnsupportedItem::new("Not Mint input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0")
-                        .amount
-                        .msats;
-                    (Some(amount_msat), None)
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?
+                        .amount;
+                    Self::record_mint_note(
+                        dbtx,
+                        federation_id,
+                        session_index,
+                        amount,
+                        NoteDirection::Issued,
+                    )
+                    .await?;
+                    (Some(amount.msats), None)
                 }
                 "wallet" => {
                     let amount_msat = output
                         .as_any()
                         .downcast_ref::<WalletOutput>()
-                        .expect("Not Wallet input")
+                        .ok_or_else(|| UnsupportedItem::new("Not Wallet input"))?
                         .maybe_v0_ref()
-                        .expect("Not v0")
+                        .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?
                         .amount()
                         .to_sat()
                         * 1000;
@@ -861,13 +1777,13 @@ impl FederationObserver {
             )
             .await?;
 
-            if kind.as_str() == "wallet" {
+            if kind.as_str() == "wallet" && unknown_variant.is_none() {
                 let wallet_v0_output = output
                     .as_any()
                     .downcast_ref::<WalletOutput>()
-                    .expect("Not Wallet input")
+                    .ok_or_else(|| UnsupportedItem::new("Not Wallet input"))?
                     .maybe_v0_ref()
-                    .expect("Not v0");
+                    .ok_or_else(|| UnsupportedItem::new("Unknown module version"))?;
 
                 match wallet_v0_output {
                     WalletOutputV0::PegOut(peg_out) => {
@@ -885,42 +1801,54 @@ impl FederationObserver {
                         ).await?;
                     }
                     WalletOutputV0::Rbf(_) => {
-                        // panic, since the benefits may outweigh the annoyance of removing and
-                        // restarting
-                        panic!(
-                            r#"
-                            You've discovered a terribly unfortunate situation: an RBF wallet output
-
-                            Federation ID: {}
-                            Name: {}
-
-                            If you know any of the guardians of the federation, please give them a heads up
-                            that they should expect failures re-syncing, or worse. They can reach out to the
-                            core dev team on Discord (chat.fedimint.org).
-
-                            For more context, see: https://github.com/fedimint/fedimint/pull/5496
-                        "#,
-                            federation_id,
-                            config.global.federation_name().unwrap_or("no name defined"),
+                        // If you know any of the guardians of the federation, please give them a
+                        // heads up that they should expect failures re-syncing, or worse. They can
+                        // reach out to the core dev team on Discord (chat.fedimint.org).
+                        error!(
+                            %federation_id,
+                            name = config.global.federation_name().unwrap_or("no name defined"),
+                            "Federation contains an RBF wallet output, see https://github.com/fedimint/fedimint/pull/5496"
                         );
+                        return Err(UnsupportedItem::new(format!(
+                            "RBF wallet output {out_idx} in transaction {fedimint_txid}"
+                        ))
+                        .into());
                     }
                 }
             }
         }
 
+        if has_quarantined_items {
+            return Ok(());
+        }
+
+        insert_transaction_fee(dbtx, federation_id, fedimint_txid, session_index).await?;
+
         Ok(())
     }
 
     async fn process_ci(
+        &self,
         dbtx: &Transaction<'_>,
         federation_id: FederationId,
         config: &ClientConfig,
+        network: bitcoin::Network,
         session_index: u64,
         item_index: u64,
         peer_id: PeerId,
         ci: DynModuleConsensusItem,
-    ) -> Result<(), tokio_postgres::Error> {
+    ) -> anyhow::Result<()> {
         let kind = instance_to_kind(config, ci.module_instance_id());
+        Self::insert_consensus_item_details(
+            dbtx,
+            federation_id,
+            session_index,
+            item_index,
+            peer_id,
+            &kind,
+            &ci,
+        )
+        .await?;
 
         if kind != "wallet" {
             return Ok(());
@@ -929,7 +1857,7 @@ impl FederationObserver {
         let wallet_ci = ci
             .as_any()
             .downcast_ref::<WalletConsensusItem>()
-            .expect("config says this should be a wallet CI");
+            .ok_or_else(|| UnsupportedItem::new("config says this should be a wallet CI"))?;
         match wallet_ci {
             WalletConsensusItem::BlockCount(height_vote) => {
                 dbtx.execute(
@@ -944,6 +1872,19 @@ impl FederationObserver {
                 )
                 .await?;
             }
+            WalletConsensusItem::Feerate(feerate) => {
+                dbtx.execute(
+                    "INSERT INTO wallet_feerate_votes VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                    &[
+                        &federation_id.consensus_encode_to_vec(),
+                        &(session_index as i32),
+                        &(item_index as i32),
+                        &(peer_id.to_usize() as i32),
+                        &(feerate.sats_per_kvb as i64),
+                    ],
+                )
+                .await?;
+            }
             WalletConsensusItem::PegOutSignature(peg_out_sig) => {
                 let peg_out_txid = peg_out_sig.txid.to_string();
                 let peg_out_txid_encoded =
@@ -1001,10 +1942,10 @@ impl FederationObserver {
                 let esplora_txid = esplora_client::Txid::from_str(peg_out_txid.as_str())
                     .expect("Couldn't create esplora txid");
 
-                let builder = esplora_client::Builder::new("https://mempool.space/api");
-                let client = builder
-                    .build_async()
-                    .expect("Failed to build esplora client");
+                let Ok(client) = self.esplora_client(network) else {
+                    warn!(%federation_id, "No esplora URL configured for {network}, not fetching peg-out transaction {peg_out_txid}");
+                    return Ok(());
+                };
 
                 let fetched_tx = retry(
                     "fetching tx from esplora".to_string(),
@@ -1022,67 +1963,13 @@ impl FederationObserver {
                 .await
                 .expect("Reached usize::MAX retries");
 
-                for input in fetched_tx.input {
-                    let prev_out_txid = fedimint_core::TransactionId::from_str(
-                        input.previous_output.txid.to_string().as_str(),
-                    )
-                    .expect("Invalid txid")
-                    .consensus_encode_to_vec();
-
-                    dbtx.execute(
-                        "INSERT INTO wallet_withdrawal_transaction_inputs VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
-                        &[
-                            &prev_out_txid,
-                            &(input.previous_output.vout as i32),
-                            &peg_out_txid_encoded,
-                        ],
-                    )
-                    .await?;
-                }
-
-                for (out_idx, output) in fetched_tx.output.iter().enumerate() {
-                    let address = bitcoin::Address::from_script(
-                        bitcoin::Script::from_bytes(output.script_pubkey.as_bytes()),
-                        bitcoin::Network::Bitcoin,
-                    )
-                    .expect("Invalid bitcoin address");
-
-                    dbtx.execute(
-                        "INSERT INTO wallet_withdrawal_transaction_outputs VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
-                        &[
-                            &peg_out_txid_encoded,
-                            &(out_idx as i32),
-                            &address.to_string(),
-                            &((output.value.to_sat() as i64) * 1000),
-
-                        ],
-                    )
-                    .await?;
-
-                    // update federation_txid if we found a matching withdrawal address
-                    dbtx.execute(
-                        "
-                        UPDATE wallet_withdrawal_transactions
-                        SET federation_txid = (
-                            SELECT txid
-                            FROM wallet_withdrawal_addresses wwa
-                            WHERE address = $1
-                              AND NOT EXISTS (
-                                SELECT *
-                                FROM wallet_withdrawal_transactions wwt
-                                WHERE wwa.txid = wwt.federation_txid
-                              )
-                            -- if address reuse, assume earliest withdrawal request first
-                            ORDER BY session_index, item_index
-                            LIMIT 1
-                        )
-                        WHERE on_chain_txid = $2
-                          AND federation_txid IS NULL
-                        ",
-                        &[&address.to_string(), &peg_out_txid_encoded],
-                    )
-                    .await?;
-                }
+                Self::record_withdrawal_transaction(
+                    dbtx,
+                    &peg_out_txid_encoded,
+                    &fetched_tx,
+                    network,
+                )
+                .await?;
             }
             _ => {
                 // other WalletConsesnsusItems are not needed yet
@@ -1116,7 +2003,8 @@ impl FederationObserver {
             )
             .await?;
 
-        Ok(())
+        // Summaries are derived from the views, so they are refreshed after them
+        self.refresh_federation_summaries().await
     }
 
     pub async fn get_federation_assets(
@@ -1205,11 +2093,19 @@ impl FederationObserver {
             &[],
         )
         .await?;
+        let federations_at_risk = self.federations_at_risk().await?;
 
         Ok(FedimintTotals {
             federations: (totals.federations as u64) - offline_federations,
             tx_count: totals.tx_count as u64,
             tx_volume: Amount::from_msats(totals.tx_volume as u64),
+            assets_at_risk: Amount::from_msats(
+                federations_at_risk
+                    .iter()
+                    .map(|federation| federation.assets.msats)
+                    .sum(),
+            ),
+            federations_at_risk,
         })
     }
 
@@ -1221,6 +2117,61 @@ impl FederationObserver {
         )
         .await? as u32)
     }
+
+    /// Current block height of `network`. Mainnet's is taken from the synced
+    /// block times, 0 if no esplora instance is configured for the network.
+    pub async fn chain_tip(&self, network: bitcoin::Network) -> anyhow::Result<u32> {
+        if network == bitcoin::Network::Bitcoin {
+            return self.get_block_height().await;
+        }
+
+        match self.esplora_client(network) {
+            Ok(esplora) => Ok(esplora.get_height().await?),
+            Err(_) => Ok(0),
+        }
+    }
+
+    /// Runs `sql` in a read-only transaction with a statement timeout, so it can
+    /// be used for queries that aren't fully under our control
+    pub async fn read_only_query(
+        &self,
+        sql: &str,
+        params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
+        timeout: Duration,
+    ) -> anyhow::Result<Vec<tokio_postgres::Row>> {
+        let mut conn = self.connection().await?;
+        let dbtx = conn.build_transaction().read_only(true).start().await?;
+        dbtx.batch_execute(&format!(
+            "SET LOCAL statement_timeout = {}",
+            timeout.as_millis()
+        ))
+        .await?;
+
+        let rows = dbtx.query(sql, params).await?;
+        dbtx.rollback().await?;
+
+        Ok(rows)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct PeerItemStats {
+    item_count: u32,
+    transaction_count: u32,
+}
+
+/// Counts how many consensus items (and how many of them transactions) each
+/// peer contributed to a session
+fn peer_item_stats(session_outcome: &SessionOutcome) -> BTreeMap<PeerId, PeerItemStats> {
+    let mut stats = BTreeMap::<PeerId, PeerItemStats>::new();
+    for item in &session_outcome.items {
+        let peer_stats = stats.entry(item.peer).or_default();
+        peer_stats.item_count += 1;
+        if matches!(item.item, ConsensusItem::Transaction(_)) {
+            peer_stats.transaction_count += 1;
+        }
+    }
+    stats
 }
 
 fn last_n_day_iter(now: NaiveDate, days: u32) -> impl Iterator<Item = NaiveDate> {
@@ -1229,9 +2180,84 @@ fn last_n_day_iter(now: NaiveDate, days: u32) -> impl Iterator<Item = NaiveDate>
         .map(move |day| now - chrono::Duration::days(day as i64))
 }
 
+/// Records the fee of a transaction. Transactions have to balance, so whatever
+/// inputs exceed outputs by is the fee collected by the guardians. Items of
+/// modules we don't decode have no known amount, so no fee is recorded for
+/// transactions containing them.
+async fn insert_transaction_fee(
+    dbtx: &Transaction<'_>,
+    federation_id: FederationId,
+    txid: TransactionId,
+    session_index: u64,
+) -> anyhow::Result<()> {
+    dbtx.execute(
+        // language=postgresql
+        "INSERT INTO fees (federation_id, txid, session_index, fee_msat)
+         SELECT $1,
+                $2,
+                $3,
+                (SELECT COALESCE(SUM(amount_msat), 0)
+                 FROM transaction_inputs
+                 WHERE federation_id = $1
+                   AND txid = $2) -
+                (SELECT COALESCE(SUM(amount_msat), 0)
+                 FROM transaction_outputs
+                 WHERE federation_id = $1
+                   AND txid = $2)
+         WHERE NOT EXISTS (SELECT 1
+                           FROM transaction_inputs
+                           WHERE federation_id = $1
+                             AND txid = $2
+                             AND amount_msat IS NULL)
+           AND NOT EXISTS (SELECT 1
+                           FROM transaction_outputs
+                           WHERE federation_id = $1
+                             AND txid = $2
+                             AND amount_msat IS NULL)
+         ON CONFLICT DO NOTHING",
+        &[
+            &federation_id.consensus_encode_to_vec(),
+            &txid.consensus_encode_to_vec(),
+            &(session_index as i32),
+        ],
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Applies all migrations to a fresh, randomly named schema that only lives as
+/// long as `dbtx`, so tests run against the real tables without touching data
+#[cfg(test)]
+pub(crate) async fn migrate_test_schema(dbtx: &Transaction<'_>) -> anyhow::Result<()> {
+    let schema = format!("fo_test_{:016x}", rand::random::<u64>());
+    dbtx.batch_execute(&format!(
+        "CREATE SCHEMA {schema}; SET LOCAL search_path TO {schema};"
+    ))
+    .await?;
+    dbtx.batch_execute(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/schema/jobs.sql"
+    )))
+    .await?;
+    for (_, migration) in MIGRATIONS {
+        dbtx.batch_execute(migration).await?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::federation::observer::last_n_day_iter;
+    use bitcoin::hashes::Hash;
+    use deadpool_postgres::{Runtime, Transaction};
+    use fedimint_core::config::FederationId;
+    use fedimint_core::encoding::Encodable;
+    use fedimint_core::TransactionId;
+    use tokio_postgres::NoTls;
+
+    use crate::federation::observer::{
+        insert_transaction_fee, last_n_day_iter, migrate_test_schema,
+    };
 
     #[test]
     fn test_day_iter() {
@@ -1242,4 +2268,119 @@ mod tests {
         assert_eq!(last_7_days[6], now);
         assert_eq!(last_7_days[0], now - chrono::Duration::days(6));
     }
+
+    async fn insert_transaction(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        txid: TransactionId,
+        input_amounts: &[Option<i64>],
+        output_amounts: &[Option<i64>],
+    ) {
+        let federation_id = federation_id.consensus_encode_to_vec();
+        let txid = txid.consensus_encode_to_vec();
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO transactions (txid, federation_id, session_index, item_index, data)
+             VALUES ($1, $2, 0, 0, $3)",
+            &[&txid, &federation_id, &Vec::<u8>::new()],
+        )
+        .await
+        .expect("can insert transaction");
+        for (in_index, amount_msat) in input_amounts.iter().enumerate() {
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO transaction_inputs (federation_id, txid, in_index, kind, amount_msat)
+                 VALUES ($1, $2, $3, 'mint', $4)",
+                &[&federation_id, &txid, &(in_index as i32), amount_msat],
+            )
+            .await
+            .expect("can insert input");
+        }
+        for (out_index, amount_msat) in output_amounts.iter().enumerate() {
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO transaction_outputs (federation_id, txid, out_index, kind, amount_msat)
+                 VALUES ($1, $2, $3, 'mint', $4)",
+                &[&federation_id, &txid, &(out_index as i32), amount_msat],
+            )
+            .await
+            .expect("can insert output");
+        }
+    }
+
+    /// Runs against the database in `FO_TEST_DATABASE`. It only uses a
+    /// temporary schema and the transaction is never committed.
+    #[tokio::test]
+    #[ignore = "needs a database in FO_TEST_DATABASE"]
+    async fn test_transaction_fees() {
+        let database = dotenv::var("FO_TEST_DATABASE").expect("FO_TEST_DATABASE is set");
+        let pool = deadpool_postgres::Config {
+            url: Some(database),
+            ..Default::default()
+        }
+        .create_pool(Some(Runtime::Tokio1), NoTls)
+        .expect("valid database config");
+        let mut conn = pool.get().await.expect("can connect to database");
+        let dbtx = conn.transaction().await.expect("can open transaction");
+        migrate_test_schema(&dbtx)
+            .await
+            .expect("can migrate test schema");
+
+        let federation_id = FederationId::dummy();
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO federations (federation_id, config) VALUES ($1, $2)",
+            &[&federation_id.consensus_encode_to_vec(), &Vec::<u8>::new()],
+        )
+        .await
+        .expect("can insert federation");
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO sessions (federation_id, session_index, session) VALUES ($1, 0, $2)",
+            &[&federation_id.consensus_encode_to_vec(), &Vec::<u8>::new()],
+        )
+        .await
+        .expect("can insert session");
+
+        let known = TransactionId::from_byte_array([1; 32]);
+        // The unknown input funds an output exceeding the known inputs
+        let unknown_input = TransactionId::from_byte_array([2; 32]);
+        let unknown_output = TransactionId::from_byte_array([3; 32]);
+        insert_transaction(&dbtx, federation_id, known, &[Some(1_000)], &[Some(900)]).await;
+        insert_transaction(
+            &dbtx,
+            federation_id,
+            unknown_input,
+            &[Some(1_000), None],
+            &[Some(5_000)],
+        )
+        .await;
+        insert_transaction(
+            &dbtx,
+            federation_id,
+            unknown_output,
+            &[Some(1_000)],
+            &[Some(900), None],
+        )
+        .await;
+
+        for txid in [known, unknown_input, unknown_output] {
+            insert_transaction_fee(&dbtx, federation_id, txid, 0)
+                .await
+                .expect("can insert fee");
+        }
+
+        let fees = dbtx
+            .query(
+                // language=postgresql
+                "SELECT txid, fee_msat FROM fees",
+                &[],
+            )
+            .await
+            .expect("can query fees")
+            .into_iter()
+            .map(|row| (row.get::<_, Vec<u8>>(0), row.get::<_, i64>(1)))
+            .collect::<Vec<_>>();
+        assert_eq!(fees, vec![(known.consensus_encode_to_vec(), 100)]);
+    }
 }
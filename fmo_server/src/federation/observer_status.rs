@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{AlertKind, ObserverStatus};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query_opt, query_value};
+use crate::AppState;
+
+/// Number of consecutive failures after which an observer is quarantined
+const MAX_CONSECUTIVE_FAILURES: u32 = 10;
+/// If an observer ran this long before failing it's considered to have made
+/// progress and the failure counter is reset
+const HEALTHY_RUNTIME: Duration = Duration::from_secs(10 * 60);
+const BASE_RESTART_DELAY: Duration = Duration::from_secs(30);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60 * 60);
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/observer",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = ObserverStatus))
+)]
+pub(super) async fn get_observer_status(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<ObserverStatus>> {
+    Ok(state
+        .federation_observer
+        .observer_status(federation_id)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    post,
+    path = "/federations/{federation_id}/observer/restart",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Observer restarted"))
+)]
+pub(super) async fn restart_observer(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .restart_quarantined_observer(federation_id)
+        .await?)
+}
+
+impl FederationObserver {
+    pub async fn observer_status(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<ObserverStatus> {
+        #[derive(Debug, FromRow)]
+        struct ObserverStatusRow {
+            consecutive_failures: i32,
+            last_error: String,
+            last_failure: NaiveDateTime,
+            quarantined: bool,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct CheckpointRow {
+            session_index: i32,
+            committed_at: NaiveDateTime,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let status = query_opt::<ObserverStatusRow>(
+            &conn,
+            // language=postgresql
+            "SELECT consecutive_failures, last_error, last_failure, quarantined
+             FROM observer_status
+             WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        let checkpoint = query_opt::<CheckpointRow>(
+            &conn,
+            // language=postgresql
+            "SELECT session_index, committed_at FROM observer_checkpoints WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let quarantined_items = query_value::<i64>(
+            &conn,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint FROM quarantined_items WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await? as u64;
+        let processing_errors = self.processing_error_count(federation_id).await?;
+        let degraded = processing_errors > 0;
+
+        let last_committed_session = checkpoint
+            .as_ref()
+            .map(|checkpoint| checkpoint.session_index as u64);
+        let last_commit = checkpoint.map(|checkpoint| checkpoint.committed_at.and_utc());
+        Ok(match status {
+            Some(status) => ObserverStatus {
+                consecutive_failures: status.consecutive_failures as u32,
+                last_error: Some(status.last_error),
+                last_failure: Some(status.last_failure.and_utc()),
+                quarantined: status.quarantined,
+                last_committed_session,
+                last_commit,
+                quarantined_items,
+                processing_errors,
+                degraded,
+            },
+            None => ObserverStatus {
+                consecutive_failures: 0,
+                last_error: None,
+                last_failure: None,
+                quarantined: false,
+                last_committed_session,
+                last_commit,
+                quarantined_items,
+                processing_errors,
+                degraded,
+            },
+        })
+    }
+
+    /// Last session committed by the federation's history observer
+    pub(super) async fn observer_checkpoint(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<u64>> {
+        #[derive(Debug, FromRow)]
+        struct CheckpointSession {
+            session_index: i32,
+        }
+
+        Ok(query_opt::<CheckpointSession>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT session_index FROM observer_checkpoints WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .map(|checkpoint| checkpoint.session_index as u64))
+    }
+
+    /// Moves the federation's checkpoint to `session_index`, has to happen in
+    /// the transaction committing the session
+    pub(super) async fn record_observer_checkpoint(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+    ) -> anyhow::Result<()> {
+        execute(
+            dbtx,
+            // language=postgresql
+            "INSERT INTO observer_checkpoints (federation_id, session_index, committed_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (federation_id) DO UPDATE
+                 SET session_index = GREATEST(observer_checkpoints.session_index, excluded.session_index),
+                     committed_at  = excluded.committed_at",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(session_index as i32),
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub(super) async fn is_observer_quarantined(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<bool> {
+        query_value::<bool>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT EXISTS(SELECT 1 FROM observer_status WHERE federation_id = $1 AND quarantined)",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await
+    }
+
+    /// Records a failure of the federation's observer and returns how long to
+    /// wait before restarting it, or `None` if it got quarantined
+    pub(super) async fn record_observer_failure(
+        &self,
+        federation_id: FederationId,
+        error: &anyhow::Error,
+        runtime: Duration,
+    ) -> anyhow::Result<Option<Duration>> {
+        let now = chrono::Utc::now().naive_utc();
+        let consecutive_failures = query_value::<i32>(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO observer_status (federation_id, consecutive_failures, last_error, last_failure)
+             VALUES ($1, 1, $2, $3)
+             ON CONFLICT (federation_id) DO UPDATE
+                 SET consecutive_failures = CASE
+                                                WHEN $4 THEN 1
+                                                ELSE observer_status.consecutive_failures + 1
+                                            END,
+                     last_error           = excluded.last_error,
+                     last_failure         = excluded.last_failure
+             RETURNING consecutive_failures",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &format!("{error:?}"),
+                &now,
+                &(runtime >= HEALTHY_RUNTIME),
+            ],
+        )
+        .await? as u32;
+
+        if consecutive_failures < MAX_CONSECUTIVE_FAILURES {
+            let delay = BASE_RESTART_DELAY
+                .saturating_mul(2u32.saturating_pow(consecutive_failures - 1))
+                .min(MAX_RESTART_DELAY);
+            return Ok(Some(delay));
+        }
+
+        let mut conn = self.connection().await?;
+        let dbtx = conn.transaction().await?;
+        execute(
+            &dbtx,
+            // language=postgresql
+            "UPDATE observer_status SET quarantined = TRUE WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        self.raise_alert(
+            &dbtx,
+            federation_id,
+            AlertKind::ObserverQuarantined,
+            None,
+            &format!("Observer quarantined after {consecutive_failures} consecutive failures, last error: {error}"),
+            now,
+        )
+        .await?;
+        dbtx.commit().await?;
+
+        Ok(None)
+    }
+
+    /// Clears the failure history of a quarantined observer and starts it again
+    pub async fn restart_quarantined_observer(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        ensure!(
+            self.is_federation_active(federation_id).await?,
+            "Federation is deactivated"
+        );
+
+        // Deleting only quarantined entries makes sure we never spawn a second
+        // observer for the same federation
+        let deleted = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM observer_status WHERE federation_id = $1 AND quarantined",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        ensure!(deleted > 0, "Observer isn't quarantined");
+        self.spawn_history_observer(federation);
+
+        Ok(())
+    }
+}
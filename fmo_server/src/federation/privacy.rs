@@ -0,0 +1,258 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{PrivacyScore, PrivacyScoreComponent};
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{query, query_one};
+use crate::AppState;
+
+const DENOMINATION_ENTROPY_WEIGHT: f64 = 0.5;
+const ADDRESS_REUSE_WEIGHT: f64 = 0.3;
+const WITHDRAWAL_BATCHING_WEIGHT: f64 = 0.2;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/privacy_score",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = PrivacyScore))
+)]
+pub(super) async fn get_privacy_score(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<PrivacyScore>> {
+    Ok(state
+        .federation_observer
+        .privacy_score(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn privacy_score(&self, federation_id: FederationId) -> anyhow::Result<PrivacyScore> {
+        #[derive(Debug, FromRow)]
+        struct DenominationRow {
+            notes: i64,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct AddressUseRow {
+            uses: i64,
+            addresses: i64,
+        }
+
+        #[derive(Debug, FromRow)]
+        struct BatchingRow {
+            withdrawal_transactions: i64,
+            batched: i64,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+
+        let denominations = query::<DenominationRow>(
+            &conn,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint AS notes
+             FROM transaction_outputs
+             WHERE federation_id = $1
+               AND kind = 'mint'
+               AND amount_msat IS NOT NULL
+             GROUP BY amount_msat",
+            &[&federation_id_bytes],
+        )
+        .await?;
+
+        let address_uses = query_one::<AddressUseRow>(
+            &conn,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint                AS uses,
+                    COUNT(DISTINCT address)::bigint AS addresses
+             FROM (SELECT address
+                   FROM wallet_peg_ins
+                   WHERE federation_id = $1
+                   UNION ALL
+                   SELECT address
+                   FROM wallet_withdrawal_addresses
+                   WHERE federation_id = $1) a",
+            &[&federation_id_bytes],
+        )
+        .await?;
+
+        // Outputs that aren't paying to a withdrawal address are change
+        let batching = query_one::<BatchingRow>(
+            &conn,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint                        AS withdrawal_transactions,
+                    COUNT(*) FILTER (WHERE payouts > 1)::bigint AS batched
+             FROM (SELECT wwto.on_chain_txid,
+                          SUM(CASE
+                                  WHEN EXISTS (SELECT 1
+                                               FROM wallet_withdrawal_addresses wwa
+                                               WHERE wwa.federation_id = wwt.federation_id
+                                                 AND wwa.address = wwto.address) THEN 1
+                                  ELSE 0 END) AS payouts
+                   FROM wallet_withdrawal_transactions wwt
+                            JOIN wallet_withdrawal_transaction_outputs wwto
+                                 ON wwt.on_chain_txid = wwto.on_chain_txid
+                   WHERE wwt.federation_id = $1
+                   GROUP BY wwto.on_chain_txid) t",
+            &[&federation_id_bytes],
+        )
+        .await?;
+
+        Ok(combine_components(
+            denomination_entropy(
+                &denominations
+                    .into_iter()
+                    .map(|row| row.notes as u64)
+                    .collect::<Vec<_>>(),
+            ),
+            address_reuse(address_uses.uses as u64, address_uses.addresses as u64),
+            withdrawal_batching(
+                batching.withdrawal_transactions as u64,
+                batching.batched as u64,
+            ),
+        ))
+    }
+}
+
+/// Shannon entropy of the note count per denomination, normalized to the
+/// maximum entropy possible with the number of denominations in use
+fn denomination_entropy(notes_per_denomination: &[u64]) -> PrivacyScoreComponent {
+    let notes = notes_per_denomination.iter().sum::<u64>();
+    let entropy = notes_per_denomination
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / notes as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>();
+    let max_entropy = (notes_per_denomination.len() as f64).log2();
+
+    PrivacyScoreComponent {
+        // With a single denomination all notes look the same
+        score: if max_entropy > 0.0 {
+            entropy / max_entropy
+        } else if notes > 0 {
+            1.0
+        } else {
+            0.0
+        },
+        weight: DENOMINATION_ENTROPY_WEIGHT,
+        value: entropy,
+        samples: notes,
+    }
+}
+
+fn address_reuse(uses: u64, distinct_addresses: u64) -> PrivacyScoreComponent {
+    let reuse_rate = if uses == 0 {
+        0.0
+    } else {
+        uses.saturating_sub(distinct_addresses) as f64 / uses as f64
+    };
+
+    PrivacyScoreComponent {
+        score: 1.0 - reuse_rate,
+        weight: ADDRESS_REUSE_WEIGHT,
+        value: reuse_rate,
+        samples: uses,
+    }
+}
+
+/// Batched withdrawals make it harder to tell which output belongs to which
+/// user
+fn withdrawal_batching(withdrawal_transactions: u64, batched: u64) -> PrivacyScoreComponent {
+    let batching_rate = if withdrawal_transactions == 0 {
+        0.0
+    } else {
+        batched as f64 / withdrawal_transactions as f64
+    };
+
+    PrivacyScoreComponent {
+        score: batching_rate,
+        weight: WITHDRAWAL_BATCHING_WEIGHT,
+        value: batching_rate,
+        samples: withdrawal_transactions,
+    }
+}
+
+/// Weighted average of all components that have samples, scaled to 0-100
+fn combine_components(
+    denomination_entropy: PrivacyScoreComponent,
+    address_reuse: PrivacyScoreComponent,
+    withdrawal_batching: PrivacyScoreComponent,
+) -> PrivacyScore {
+    let (weighted_sum, total_weight) = [denomination_entropy, address_reuse, withdrawal_batching]
+        .iter()
+        .filter(|component| component.samples > 0)
+        .fold((0.0, 0.0), |(sum, weights), component| {
+            (
+                sum + component.score * component.weight,
+                weights + component.weight,
+            )
+        });
+
+    PrivacyScore {
+        score: (total_weight > 0.0).then(|| 100.0 * weighted_sum / total_weight),
+        denomination_entropy,
+        address_reuse,
+        withdrawal_batching,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{address_reuse, combine_components, denomination_entropy, withdrawal_batching};
+
+    #[test]
+    fn test_denomination_entropy() {
+        let uniform = denomination_entropy(&[10, 10, 10, 10]);
+        assert_eq!(uniform.value, 2.0);
+        assert_eq!(uniform.score, 1.0);
+        assert_eq!(uniform.samples, 40);
+
+        let skewed = denomination_entropy(&[97, 1, 1, 1]);
+        assert!(skewed.score < 0.15);
+
+        assert_eq!(denomination_entropy(&[5]).score, 1.0);
+        assert_eq!(denomination_entropy(&[]).score, 0.0);
+    }
+
+    #[test]
+    fn test_address_reuse() {
+        let reuse = address_reuse(10, 8);
+        assert!((reuse.value - 0.2).abs() < 1e-9);
+        assert!((reuse.score - 0.8).abs() < 1e-9);
+
+        assert_eq!(address_reuse(0, 0).score, 1.0);
+    }
+
+    #[test]
+    fn test_combine_components() {
+        let score = combine_components(
+            denomination_entropy(&[10, 10]),
+            address_reuse(10, 5),
+            withdrawal_batching(0, 0),
+        );
+        // Batching has no samples and is ignored
+        let expected = 100.0 * (0.5 * 1.0 + 0.3 * 0.5) / 0.8;
+        assert!((score.score.unwrap() - expected).abs() < 1e-9);
+
+        let no_activity = combine_components(
+            denomination_entropy(&[]),
+            address_reuse(0, 0),
+            withdrawal_batching(0, 0),
+        );
+        assert_eq!(no_activity.score, None);
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_api_client::api::{DynGlobalApi, FederationApiExt};
+use fedimint_core::config::{ClientConfig, FederationId};
+use fedimint_core::encoding::Encodable;
+use fedimint_core::module::ApiRequestErased;
+use fedimint_core::util::SafeUrl;
+use fedimint_core::PeerId;
+use fmo_api_types::HealthProbeResult;
+use futures::future::join_all;
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Checks run against every guardian in each health check round in addition
+/// to the built-in status and block count requests, loaded from the TOML file
+/// `FO_HEALTH_PROBES` points to
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(super) struct HealthProbes {
+    #[serde(default, rename = "probe")]
+    probes: Vec<HealthProbe>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HealthProbe {
+    /// Results are stored and returned under this name
+    name: String,
+    #[serde(flatten)]
+    kind: ProbeKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ProbeKind {
+    /// Succeeds if the guardian answers a request to the API endpoint,
+    /// optionally of the first module of kind `module`
+    Api {
+        endpoint: String,
+        module: Option<String>,
+        params: Option<serde_json::Value>,
+    },
+    /// Succeeds if a GET request to the URL returns a success status. `{host}`
+    /// is replaced with the host of the guardian's API URL.
+    Http { url: String },
+    /// Succeeds if the guardian's session count is at most `max_lag` sessions
+    /// behind the most advanced guardian
+    SessionCountConsistency {
+        #[serde(default)]
+        max_lag: u64,
+    },
+}
+
+/// Outcome of one probe of one guardian
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ProbeOutcome {
+    success: bool,
+    latency: Option<Duration>,
+    message: Option<String>,
+}
+
+impl ProbeOutcome {
+    fn failed(message: impl ToString) -> ProbeOutcome {
+        ProbeOutcome {
+            success: false,
+            latency: None,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+impl HealthProbes {
+    pub(super) fn from_env() -> anyhow::Result<HealthProbes> {
+        let Ok(path) = dotenv::var("FO_HEALTH_PROBES") else {
+            return Ok(HealthProbes::default());
+        };
+
+        let probes = std::fs::read_to_string(&path)
+            .with_context(|| format!("Could not read health probes from {path}"))?;
+        toml::from_str(&probes).with_context(|| format!("Invalid health probes in {path}"))
+    }
+
+    /// Runs all probes against all guardians concurrently. `session_counts`
+    /// are the ones reported in the same round, `None` for guardians that
+    /// didn't respond.
+    pub(super) async fn run(
+        &self,
+        http_client: &reqwest::Client,
+        api: &DynGlobalApi,
+        config: &ClientConfig,
+        session_counts: &BTreeMap<PeerId, Option<u64>>,
+    ) -> Vec<(String, PeerId, ProbeOutcome)> {
+        let max_session_count = session_counts.values().flatten().copied().max();
+
+        let probes = self.probes.iter().flat_map(move |probe| {
+            config
+                .global
+                .api_endpoints
+                .iter()
+                .map(move |(&peer_id, peer_url)| async move {
+                    let outcome = match &probe.kind {
+                        ProbeKind::Api {
+                            endpoint,
+                            module,
+                            params,
+                        } => {
+                            probe_api(api, config, peer_id, endpoint, module.as_deref(), params)
+                                .await
+                        }
+                        ProbeKind::Http { url } => {
+                            probe_http(http_client, url, &peer_url.url).await
+                        }
+                        ProbeKind::SessionCountConsistency { max_lag } => {
+                            session_count_consistency(
+                                session_counts.get(&peer_id).copied().flatten(),
+                                max_session_count,
+                                *max_lag,
+                            )
+                        }
+                    };
+                    (probe.name.clone(), peer_id, outcome)
+                })
+        });
+
+        join_all(probes).await
+    }
+}
+
+async fn probe_api(
+    api: &DynGlobalApi,
+    config: &ClientConfig,
+    peer_id: PeerId,
+    endpoint: &str,
+    module: Option<&str>,
+    params: &Option<serde_json::Value>,
+) -> ProbeOutcome {
+    let params = params
+        .clone()
+        .map(ApiRequestErased::new)
+        .unwrap_or_default();
+
+    let start_time = Instant::now();
+    let response = match module {
+        Some(kind) => {
+            let Some(module_instance_id) =
+                config
+                    .modules
+                    .iter()
+                    .find_map(|(&module_instance_id, module)| {
+                        (module.kind.as_str() == kind).then_some(module_instance_id)
+                    })
+            else {
+                return ProbeOutcome::failed(format!("Module {kind} not found"));
+            };
+            api.with_module(module_instance_id)
+                .request_single_peer(Some(PROBE_TIMEOUT), endpoint.to_owned(), params, peer_id)
+                .await
+        }
+        None => {
+            api.request_single_peer(Some(PROBE_TIMEOUT), endpoint.to_owned(), params, peer_id)
+                .await
+        }
+    };
+
+    match response {
+        Ok(_) => ProbeOutcome {
+            success: true,
+            latency: Some(start_time.elapsed()),
+            message: None,
+        },
+        Err(e) => ProbeOutcome::failed(e),
+    }
+}
+
+async fn probe_http(
+    http_client: &reqwest::Client,
+    url_template: &str,
+    guardian_url: &SafeUrl,
+) -> ProbeOutcome {
+    let url = match probe_url(url_template, guardian_url) {
+        Ok(url) => url,
+        Err(e) => return ProbeOutcome::failed(e),
+    };
+
+    let start_time = Instant::now();
+    match http_client.get(&url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => ProbeOutcome {
+            success: true,
+            latency: Some(start_time.elapsed()),
+            message: None,
+        },
+        Ok(response) => ProbeOutcome {
+            success: false,
+            latency: Some(start_time.elapsed()),
+            message: Some(format!("{url} returned {}", response.status())),
+        },
+        Err(e) => ProbeOutcome::failed(e),
+    }
+}
+
+fn probe_url(url_template: &str, guardian_url: &SafeUrl) -> anyhow::Result<String> {
+    let host = guardian_url
+        .host_str()
+        .ok_or_else(|| anyhow!("Guardian URL has no host"))?;
+    Ok(url_template.replace("{host}", host))
+}
+
+fn session_count_consistency(
+    session_count: Option<u64>,
+    max_session_count: Option<u64>,
+    max_lag: u64,
+) -> ProbeOutcome {
+    let (Some(session_count), Some(max_session_count)) = (session_count, max_session_count) else {
+        return ProbeOutcome::failed("Session count unknown");
+    };
+
+    let lag = max_session_count.saturating_sub(session_count);
+    ProbeOutcome {
+        success: lag <= max_lag,
+        latency: None,
+        message: (lag > 0).then(|| format!("{lag} sessions behind")),
+    }
+}
+
+/// Latest result of each configured probe for each guardian of the federation
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/health/probes",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Latest probe results", body = Vec<HealthProbeResult>))
+)]
+pub(super) async fn get_health_probe_results(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<HealthProbeResult>>> {
+    Ok(state
+        .federation_observer
+        .health_probe_results(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn health_probe_results(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<HealthProbeResult>> {
+        #[derive(Debug, FromRow)]
+        struct ProbeResultRow {
+            probe: String,
+            guardian_id: i32,
+            checked_at: NaiveDateTime,
+            success: bool,
+            latency_ms: Option<i32>,
+            message: Option<String>,
+        }
+
+        Ok(query::<ProbeResultRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT DISTINCT ON (probe, guardian_id) probe, guardian_id, checked_at, success, latency_ms, message
+             FROM health_probe_results
+             WHERE federation_id = $1
+             ORDER BY probe, guardian_id, checked_at DESC",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .into_iter()
+        .map(|row| HealthProbeResult {
+            probe: row.probe,
+            guardian_id: PeerId::from(row.guardian_id as u16),
+            checked_at: row.checked_at.and_utc(),
+            success: row.success,
+            latency_ms: row.latency_ms.map(|latency| latency as u64),
+            message: row.message,
+        })
+        .collect())
+    }
+
+    pub(super) async fn record_probe_results(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        results: Vec<(String, PeerId, ProbeOutcome)>,
+        timestamp: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        for (probe, peer_id, outcome) in results {
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO health_probe_results VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &timestamp,
+                    &(peer_id.to_usize() as i32),
+                    &probe,
+                    &outcome.success,
+                    &outcome.latency.map(|latency| latency.as_millis() as i32),
+                    &outcome.message,
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::util::SafeUrl;
+
+    use super::{probe_url, session_count_consistency, HealthProbes, ProbeKind};
+
+    #[test]
+    fn test_parse_probes() {
+        let probes: HealthProbes = toml::from_str(
+            r#"
+            [[probe]]
+            name = "guardian_ui"
+            kind = "http"
+            url = "https://{host}/"
+
+            [[probe]]
+            name = "session_count"
+            kind = "session_count_consistency"
+            max_lag = 2
+
+            [[probe]]
+            name = "backup"
+            kind = "api"
+            endpoint = "recover"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(probes.probes.len(), 3);
+        assert!(matches!(probes.probes[0].kind, ProbeKind::Http { .. }));
+        assert!(matches!(
+            probes.probes[1].kind,
+            ProbeKind::SessionCountConsistency { max_lag: 2 }
+        ));
+        assert!(matches!(
+            &probes.probes[2].kind,
+            ProbeKind::Api { endpoint, module: None, params: None } if endpoint == "recover"
+        ));
+    }
+
+    #[test]
+    fn test_probe_url() {
+        let guardian_url = SafeUrl::parse("wss://api.guardian.example:443/").unwrap();
+        assert_eq!(
+            probe_url("https://{host}/status", &guardian_url).unwrap(),
+            "https://api.guardian.example/status"
+        );
+    }
+
+    #[test]
+    fn test_session_count_consistency() {
+        assert!(session_count_consistency(Some(10), Some(10), 0).success);
+        assert!(session_count_consistency(Some(8), Some(10), 2).success);
+
+        let behind = session_count_consistency(Some(7), Some(10), 2);
+        assert!(!behind.success);
+        assert_eq!(behind.message.as_deref(), Some("3 sessions behind"));
+
+        assert!(!session_count_consistency(None, Some(10), 2).success);
+    }
+}
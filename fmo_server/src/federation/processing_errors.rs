@@ -0,0 +1,299 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+
+use anyhow::{bail, Context};
+use chrono::NaiveDateTime;
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fedimint_core::TransactionId;
+use fmo_api_types::{ProcessingError, ProcessingRetry};
+use postgres_from_row::FromRow;
+use tracing::warn;
+
+use crate::federation::decoders_from_config;
+use crate::federation::observer::FederationObserver;
+use crate::util::{query, query_value};
+
+/// What to do with session items the observer can't process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum ProcessingErrorPolicy {
+    /// Record the error, skip the item and continue with the session
+    #[default]
+    Record,
+    /// Fail the session, the observer is restarted and eventually quarantined
+    Fail,
+}
+
+impl ProcessingErrorPolicy {
+    pub(super) fn from_env() -> anyhow::Result<ProcessingErrorPolicy> {
+        match dotenv::var("FO_PROCESSING_ERROR_POLICY") {
+            Ok(policy) => ProcessingErrorPolicy::parse(&policy),
+            Err(_) => Ok(ProcessingErrorPolicy::default()),
+        }
+    }
+
+    fn parse(policy: &str) -> anyhow::Result<ProcessingErrorPolicy> {
+        match policy.trim() {
+            "record" => Ok(ProcessingErrorPolicy::Record),
+            "fail" => Ok(ProcessingErrorPolicy::Fail),
+            _ => bail!("Invalid FO_PROCESSING_ERROR_POLICY {policy}, expected record or fail"),
+        }
+    }
+}
+
+/// Session content the observer doesn't know how to process, as opposed to
+/// e.g. database errors that are always fatal for the session
+#[derive(Debug)]
+pub(super) struct UnsupportedItem(String);
+
+impl UnsupportedItem {
+    pub(super) fn new(reason: impl Into<String>) -> UnsupportedItem {
+        UnsupportedItem(reason.into())
+    }
+}
+
+impl Display for UnsupportedItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedItem {}
+
+/// Runs `item` inside a savepoint of `dbtx` and rolls back everything it
+/// wrote if it fails. The outer error is a failure of the savepoint itself,
+/// the inner one the result of `item`.
+pub(super) async fn in_savepoint<T>(
+    dbtx: &Transaction<'_>,
+    item: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<anyhow::Result<T>> {
+    dbtx.batch_execute("SAVEPOINT process_item").await?;
+    let result = item.await;
+    match &result {
+        Ok(_) => dbtx.batch_execute("RELEASE SAVEPOINT process_item").await?,
+        Err(_) => {
+            dbtx.batch_execute("ROLLBACK TO SAVEPOINT process_item")
+                .await?
+        }
+    }
+    Ok(result)
+}
+
+#[derive(Debug, FromRow)]
+struct ProcessingErrorRow {
+    federation_id: Vec<u8>,
+    session_index: i32,
+    item_index: i32,
+    txid: Option<Vec<u8>>,
+    error: String,
+    created_at: NaiveDateTime,
+}
+
+impl FederationObserver {
+    /// Records `error` of processing an item and lets the session continue
+    /// if the policy allows it, otherwise returns the error
+    pub(super) async fn handle_processing_error(
+        &self,
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        item_index: u64,
+        txid: Option<TransactionId>,
+        error: anyhow::Error,
+    ) -> anyhow::Result<()> {
+        if self.processing_error_policy == ProcessingErrorPolicy::Fail
+            || error.downcast_ref::<UnsupportedItem>().is_none()
+        {
+            return Err(error);
+        }
+
+        warn!(
+            %federation_id,
+            session_index,
+            item_index,
+            "Skipping item that couldn't be processed: {error:#}"
+        );
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO processing_errors VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (federation_id, session_index, item_index) DO UPDATE
+                 SET error      = excluded.error,
+                     created_at = excluded.created_at",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(session_index as i32),
+                &(item_index as i32),
+                &txid.map(|txid| txid.consensus_encode_to_vec()),
+                &format!("{error:#}"),
+                &chrono::Utc::now().naive_utc(),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Processing errors of one or all federations, newest sessions first
+    pub async fn list_processing_errors(
+        &self,
+        federation_id: Option<FederationId>,
+    ) -> anyhow::Result<Vec<ProcessingError>> {
+        query::<ProcessingErrorRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT federation_id, session_index, item_index, txid, error, created_at
+             FROM processing_errors
+             WHERE $1::bytea IS NULL OR federation_id = $1
+             ORDER BY session_index DESC, item_index",
+            &[&federation_id.map(|id| id.consensus_encode_to_vec())],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(ProcessingError {
+                federation_id: FederationId::consensus_decode_vec(
+                    row.federation_id,
+                    &Default::default(),
+                )?,
+                session_index: row.session_index as u64,
+                item_index: row.item_index as u64,
+                txid: row
+                    .txid
+                    .map(|txid| TransactionId::consensus_decode_vec(txid, &Default::default()))
+                    .transpose()?,
+                error: row.error,
+                created_at: row.created_at.and_utc(),
+            })
+        })
+        .collect()
+    }
+
+    pub(super) async fn processing_error_count(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<u64> {
+        Ok(query_value::<i64>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint FROM processing_errors WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await? as u64)
+    }
+
+    /// Re-processes all sessions of the federation with processing errors or
+    /// quarantined items, e.g. after upgrading the observer
+    pub async fn retry_processing_errors(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<ProcessingRetry> {
+        #[derive(Debug, FromRow)]
+        struct SessionIndexRow {
+            session_index: i32,
+        }
+
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        let sessions = query::<SessionIndexRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT session_index FROM processing_errors WHERE federation_id = $1
+             UNION
+             SELECT session_index FROM quarantined_items WHERE federation_id = $1
+             ORDER BY session_index",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let decoders = decoders_from_config(&federation.config);
+        let mut failed_sessions = vec![];
+        for row in &sessions {
+            let session_index = row.session_index as u64;
+            if let Err(e) = self
+                .replay_session(&federation, &decoders, session_index)
+                .await
+            {
+                warn!(%federation_id, session_index, "Retrying session failed: {e:?}");
+                failed_sessions.push(session_index);
+            }
+        }
+
+        Ok(ProcessingRetry {
+            replayed_sessions: (sessions.len() - failed_sessions.len()) as u64,
+            failed_sessions,
+            remaining_errors: self.processing_error_count(federation_id).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use deadpool_postgres::Runtime;
+    use tokio_postgres::NoTls;
+
+    use super::{in_savepoint, ProcessingErrorPolicy, UnsupportedItem};
+
+    #[test]
+    fn test_parse_processing_error_policy() {
+        assert_eq!(
+            ProcessingErrorPolicy::parse("record").unwrap(),
+            ProcessingErrorPolicy::Record
+        );
+        assert_eq!(
+            ProcessingErrorPolicy::parse(" fail\n").unwrap(),
+            ProcessingErrorPolicy::Fail
+        );
+        assert!(ProcessingErrorPolicy::parse("ignore").is_err());
+    }
+
+    /// Runs against the database in `FO_TEST_DATABASE` inside a transaction
+    /// that is never committed
+    #[tokio::test]
+    #[ignore = "needs a database in FO_TEST_DATABASE"]
+    async fn test_in_savepoint_rolls_back_failed_item() {
+        let database = dotenv::var("FO_TEST_DATABASE").expect("FO_TEST_DATABASE is set");
+        let pool = deadpool_postgres::Config {
+            url: Some(database),
+            ..Default::default()
+        }
+        .create_pool(Some(Runtime::Tokio1), NoTls)
+        .expect("valid database config");
+        let mut conn = pool.get().await.expect("can connect to database");
+        let dbtx = conn.transaction().await.expect("can open transaction");
+        dbtx.batch_execute(
+            // language=postgresql
+            "CREATE TEMPORARY TABLE items (item_index INTEGER NOT NULL) ON COMMIT DROP",
+        )
+        .await
+        .unwrap();
+
+        let insert = |item_index: i32, fail: bool| {
+            let dbtx = &dbtx;
+            async move {
+                dbtx.execute("INSERT INTO items VALUES ($1)", &[&item_index])
+                    .await?;
+                if fail {
+                    return Err(UnsupportedItem::new("unsupported").into());
+                }
+                anyhow::Ok(())
+            }
+        };
+        assert!(in_savepoint(&dbtx, insert(0, false)).await.unwrap().is_ok());
+        assert!(in_savepoint(&dbtx, insert(1, true)).await.unwrap().is_err());
+        assert!(in_savepoint(&dbtx, insert(2, false)).await.unwrap().is_ok());
+
+        let item_indices = dbtx
+            .query("SELECT item_index FROM items ORDER BY item_index", &[])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.get::<_, i32>(0))
+            .collect::<Vec<_>>();
+        assert_eq!(item_indices, vec![0, 2]);
+
+        dbtx.rollback().await.unwrap();
+    }
+}
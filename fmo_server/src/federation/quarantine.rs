@@ -0,0 +1,154 @@
+use deadpool_postgres::Transaction;
+use fedimint_core::config::FederationId;
+use fedimint_core::core::{DynInput, DynOutput};
+use fedimint_core::encoding::Encodable;
+use fedimint_core::TransactionId;
+use fedimint_ln_common::{LightningInput, LightningOutput};
+use fedimint_mint_common::{MintInput, MintOutput};
+use fedimint_wallet_common::{WalletInput, WalletOutput};
+use postgres_from_row::FromRow;
+use tracing::{info, warn};
+
+use crate::federation::decoders_from_config;
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+
+/// Variant of an extensible module item if it isn't the v0 one we can decode
+macro_rules! unknown_variant {
+    ($item:expr, $ty:ident) => {
+        match $item.as_any().downcast_ref::<$ty>() {
+            Some($ty::Default { variant, .. }) => Some(*variant),
+            _ => None,
+        }
+    };
+}
+
+/// Version of an input of a known module kind that the observer can't decode
+pub(super) fn unknown_input_variant(kind: &str, input: &DynInput) -> Option<u64> {
+    match kind {
+        "ln" => unknown_variant!(input, LightningInput),
+        "mint" => unknown_variant!(input, MintInput),
+        "wallet" => unknown_variant!(input, WalletInput),
+        _ => None,
+    }
+}
+
+/// Version of an output of a known module kind that the observer can't decode
+pub(super) fn unknown_output_variant(kind: &str, output: &DynOutput) -> Option<u64> {
+    match kind {
+        "ln" => unknown_variant!(output, LightningOutput),
+        "mint" => unknown_variant!(output, MintOutput),
+        "wallet" => unknown_variant!(output, WalletOutput),
+        _ => None,
+    }
+}
+
+/// Transaction input or output that couldn't be decoded
+pub(super) struct QuarantinedItem<'a> {
+    /// `input` or `output`
+    pub direction: &'static str,
+    pub idx: usize,
+    pub kind: &'a str,
+    pub variant: u64,
+    /// Consensus encoding of the whole item
+    pub item: Vec<u8>,
+}
+
+impl FederationObserver {
+    pub(super) async fn quarantine_item(
+        dbtx: &Transaction<'_>,
+        federation_id: FederationId,
+        session_index: u64,
+        txid: TransactionId,
+        item: QuarantinedItem<'_>,
+    ) -> Result<(), tokio_postgres::Error> {
+        warn!(
+            %federation_id,
+            %txid,
+            "Quarantining {} {} of unknown {} version {}",
+            item.direction,
+            item.idx,
+            item.kind,
+            item.variant
+        );
+        dbtx.execute(
+            // language=postgresql
+            "INSERT INTO quarantined_items VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT DO NOTHING",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &txid.consensus_encode_to_vec(),
+                &item.direction,
+                &(item.idx as i32),
+                &(session_index as i32),
+                &item.kind,
+                &(item.variant as i64),
+                &item.item,
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Re-processes all sessions containing quarantined items once on startup.
+    /// After upgrading to a fedimint version that understands the new item
+    /// versions this fills in the missing data, items that still can't be
+    /// decoded are simply quarantined again.
+    pub(super) async fn replay_quarantined_sessions(self) {
+        let federations = match self.list_active_federations().await {
+            Ok(federations) => federations,
+            Err(e) => {
+                warn!("Failed to list federations for replaying quarantined items: {e:?}");
+                return;
+            }
+        };
+
+        for federation in federations {
+            let federation_id = federation.federation_id;
+            let sessions = match self.quarantined_sessions(federation_id).await {
+                Ok(sessions) if sessions.is_empty() => continue,
+                Ok(sessions) => sessions,
+                Err(e) => {
+                    warn!(%federation_id, "Failed to list quarantined sessions: {e:?}");
+                    continue;
+                }
+            };
+
+            let decoders = decoders_from_config(&federation.config);
+            for &session_index in &sessions {
+                if let Err(e) = self
+                    .replay_session(&federation, &decoders, session_index)
+                    .await
+                {
+                    warn!(%federation_id, session_index, "Replaying quarantined session failed: {e:?}");
+                }
+            }
+            info!(
+                %federation_id,
+                "Replayed {} sessions with quarantined items",
+                sessions.len()
+            );
+        }
+    }
+
+    async fn quarantined_sessions(&self, federation_id: FederationId) -> anyhow::Result<Vec<u64>> {
+        #[derive(Debug, FromRow)]
+        struct QuarantinedSessionRow {
+            session_index: i32,
+        }
+
+        Ok(query::<QuarantinedSessionRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT DISTINCT session_index
+             FROM quarantined_items
+             WHERE federation_id = $1
+             ORDER BY session_index",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.session_index as u64)
+        .collect())
+    }
+}
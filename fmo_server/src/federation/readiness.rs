@@ -0,0 +1,91 @@
+use anyhow::ensure;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+
+use crate::federation::observer::{FederationObserver, LATEST_SCHEMA_VERSION};
+use crate::util::query_value;
+use crate::AppState;
+
+/// Liveness probe, succeeds as long as the process serves requests, including
+/// while the database is being migrated
+pub(crate) async fn get_live() -> &'static str {
+    "Server is up and running!"
+}
+
+/// Readiness probe, fails with `503 Service Unavailable` unless the database
+/// is reachable and fully migrated and the observers were started
+pub(crate) async fn get_ready(State(state): State<AppState>) -> Response {
+    match state.federation_observer.check_ready().await {
+        Ok(()) => "Ready".into_response(),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, format!("Not ready: {e:#}")).into_response(),
+    }
+}
+
+/// Routes served while the observer starts up and migrates the database, so
+/// orchestrators see the process as alive but don't send it traffic yet
+pub(crate) fn get_startup_routes() -> Router {
+    Router::new()
+        .route("/live", get(get_live))
+        .route("/health", get(get_live))
+        .fallback(|| async {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Not ready: starting up and migrating the database",
+            )
+        })
+}
+
+impl FederationObserver {
+    pub async fn check_ready(&self) -> anyhow::Result<()> {
+        let conn = self.connection().await?;
+        let schema_version = query_value::<i32>(&conn, "SELECT get_max_version();", &[]).await?;
+        let active_federations = query_value::<i64>(
+            &conn,
+            // language=postgresql
+            "SELECT COUNT(*)::bigint FROM federations WHERE active",
+            &[],
+        )
+        .await?;
+
+        readiness(
+            schema_version,
+            active_federations as usize,
+            self.running_observers(),
+        )
+    }
+}
+
+/// An instance without federations is ready once migrated, otherwise at least
+/// one observer has to run
+fn readiness(
+    schema_version: i32,
+    active_federations: usize,
+    running_observers: usize,
+) -> anyhow::Result<()> {
+    ensure!(
+        schema_version >= LATEST_SCHEMA_VERSION,
+        "database schema is at version {schema_version}, expected {LATEST_SCHEMA_VERSION}"
+    );
+    ensure!(
+        active_federations == 0 || running_observers > 0,
+        "no observer is running for {active_federations} active federations"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::readiness;
+    use crate::federation::observer::LATEST_SCHEMA_VERSION;
+
+    #[test]
+    fn test_readiness() {
+        assert!(readiness(LATEST_SCHEMA_VERSION, 3, 3).is_ok());
+        assert!(readiness(LATEST_SCHEMA_VERSION, 0, 0).is_ok());
+        assert!(readiness(LATEST_SCHEMA_VERSION - 1, 3, 3).is_err());
+        assert!(readiness(LATEST_SCHEMA_VERSION, 3, 0).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::module::registry::ModuleDecoderRegistry;
+use fmo_api_types::JobProgress;
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+
+use crate::federation::db::Federation;
+use crate::federation::jobs::{Job, QueuedJob};
+use crate::federation::observer::FederationObserver;
+use crate::federation::{db, decoders_from_config};
+use crate::util::query;
+use crate::AppState;
+
+/// Rows derived from a session's content, ordered so rows are deleted before
+/// the ones they reference. Lightning contracts and peg-out signatures are
+/// only ever added, re-processing a session inserts missing ones.
+const DERIVED_SESSION_DATA: &[&str] = &[
+    // language=postgresql
+    "DELETE FROM fees WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM mint_denominations WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM wallet_peg_ins
+     WHERE federation_id = $1
+       AND txid IN (SELECT txid FROM transactions WHERE federation_id = $1 AND session_index = $2)",
+    // language=postgresql
+    "DELETE FROM wallet_withdrawal_addresses WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM transaction_input_details
+     WHERE federation_id = $1
+       AND txid IN (SELECT txid FROM transactions WHERE federation_id = $1 AND session_index = $2)",
+    // language=postgresql
+    "DELETE FROM transaction_output_details
+     WHERE federation_id = $1
+       AND txid IN (SELECT txid FROM transactions WHERE federation_id = $1 AND session_index = $2)",
+    // language=postgresql
+    "DELETE FROM quarantined_items WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM processing_errors WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM transaction_inputs
+     WHERE federation_id = $1
+       AND txid IN (SELECT txid FROM transactions WHERE federation_id = $1 AND session_index = $2)",
+    // language=postgresql
+    "DELETE FROM transaction_outputs
+     WHERE federation_id = $1
+       AND txid IN (SELECT txid FROM transactions WHERE federation_id = $1 AND session_index = $2)",
+    // language=postgresql
+    "DELETE FROM transactions WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM block_height_votes WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM wallet_feerate_votes WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM consensus_items WHERE federation_id = $1 AND session_index = $2",
+    // language=postgresql
+    "DELETE FROM session_peer_stats WHERE federation_id = $1 AND session_index = $2",
+];
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ReplaySessionsParams {
+    /// First session to re-process
+    from: u64,
+    /// Last session to re-process (inclusive), defaults to the latest stored
+    /// session
+    to: Option<u64>,
+}
+
+/// Queues re-processing of stored sessions, e.g. after fixing a parser bug.
+/// The replay runs on a job worker, its progress is reported by the
+/// federation's backfill status. It stops at the first session that fails and
+/// continues after a restart of the observer.
+#[utoipa::path(
+    post,
+    path = "/federations/{federation_id}/sessions/replay",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ReplaySessionsParams),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Queued replay job", body = JobProgress))
+)]
+pub(super) async fn replay_sessions(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<ReplaySessionsParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<JobProgress>> {
+    let observer = state.federation_observer;
+    observer
+        .get_federation(federation_id)
+        .await?
+        .context("Federation doesn't exist")?;
+    let session_indices = observer
+        .stored_session_indices(federation_id, params.from, params.to)
+        .await?;
+    // The range is fixed when queueing so a resumed replay doesn't grow
+    let &to = session_indices
+        .last()
+        .context("No stored sessions in range")?;
+    info!(
+        %federation_id,
+        sessions = session_indices.len(),
+        "Queueing session replay"
+    );
+
+    Ok(observer
+        .enqueue_job(
+            Some(federation_id),
+            QueuedJob::Replay {
+                from: params.from,
+                to,
+            },
+            session_indices.len() as u64,
+        )
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Replays the sessions `from..=to`, skipping the ones an interrupted run
+    /// of the job already replayed
+    pub(super) async fn run_replay_job(
+        &self,
+        federation_id: FederationId,
+        from: u64,
+        to: u64,
+        job: &mut Job,
+    ) -> anyhow::Result<()> {
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        let decoders = decoders_from_config(&federation.config);
+        let session_indices = self
+            .stored_session_indices(federation_id, from, Some(to))
+            .await?;
+
+        let already_processed = job.processed() as usize;
+        for (processed, session_index) in session_indices
+            .into_iter()
+            .enumerate()
+            .skip(already_processed)
+        {
+            self.replay_session(&federation, &decoders, session_index)
+                .await
+                .with_context(|| format!("Replaying session {session_index} failed"))?;
+            job.progress(processed as u64 + 1).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn stored_session_indices(
+        &self,
+        federation_id: FederationId,
+        from: u64,
+        to: Option<u64>,
+    ) -> anyhow::Result<Vec<u64>> {
+        #[derive(Debug, postgres_from_row::FromRow)]
+        struct SessionIndexRow {
+            session_index: i32,
+        }
+
+        Ok(query::<SessionIndexRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT session_index
+             FROM sessions
+             WHERE federation_id = $1
+               AND session_index >= $2
+               AND ($3::integer IS NULL OR session_index <= $3)
+             ORDER BY session_index",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(from as i32),
+                &to.map(|to| to as i32),
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.session_index as u64)
+        .collect())
+    }
+
+    /// Deletes everything derived from a stored session and processes it again
+    /// within one DB transaction
+    pub(super) async fn replay_session(
+        &self,
+        federation: &Federation,
+        decoders: &ModuleDecoderRegistry,
+        session_index: u64,
+    ) -> anyhow::Result<()> {
+        let federation_id_bytes = federation.federation_id.consensus_encode_to_vec();
+
+        let mut conn = self.connection().await?;
+        let dbtx = conn.transaction().await?;
+
+        let row = dbtx
+            .query_one(
+                // language=postgresql
+                "SELECT session_index, session FROM sessions WHERE federation_id = $1 AND session_index = $2",
+                &[&federation_id_bytes, &(session_index as i32)],
+            )
+            .await?;
+        let session = db::SessionOutcome::try_from_row_with_decoders(&row, decoders)?;
+
+        for statement in DERIVED_SESSION_DATA {
+            dbtx.execute(*statement, &[&federation_id_bytes, &(session_index as i32)])
+                .await?;
+        }
+
+        self.process_session(
+            federation.federation_id,
+            federation.config.clone(),
+            session_index,
+            session.data,
+            &dbtx,
+        )
+        .await?;
+        dbtx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeSet;
+
+use fedimint_core::config::FederationId;
+use fedimint_core::Amount;
+use fmo_api_types::{FederationAtRisk, FederationHealth};
+
+use crate::federation::health::observed_health;
+use crate::federation::observer::FederationObserver;
+
+impl FederationObserver {
+    /// Observed federations whose online guardians were below the signing
+    /// threshold in the latest health check, largest assets first. Unlike the
+    /// confirmed health this reacts to outages immediately.
+    pub(super) async fn federations_at_risk(&self) -> anyhow::Result<Vec<FederationAtRisk>> {
+        let below_threshold = self
+            .latest_guardian_counts()
+            .await?
+            .into_iter()
+            .filter(|(_, (guardians, online_guardians))| {
+                observed_health(*guardians, *online_guardians) == FederationHealth::Offline
+            })
+            .map(|(federation_id, _)| federation_id)
+            .collect::<BTreeSet<_>>();
+        if below_threshold.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let federations = self
+            .list_federation_summaries()
+            .await?
+            .into_iter()
+            .filter(|summary| summary.shutdown.is_none())
+            .map(|summary| (summary.id, summary.name, summary.deposits));
+        Ok(federations_at_risk(federations, &below_threshold))
+    }
+}
+
+fn federations_at_risk(
+    federations: impl IntoIterator<Item = (FederationId, Option<String>, Amount)>,
+    below_threshold: &BTreeSet<FederationId>,
+) -> Vec<FederationAtRisk> {
+    let mut at_risk = federations
+        .into_iter()
+        .filter(|(federation_id, _, _)| below_threshold.contains(federation_id))
+        .map(|(federation_id, name, assets)| FederationAtRisk {
+            federation_id,
+            name,
+            assets,
+        })
+        .collect::<Vec<_>>();
+    at_risk.sort_by(|a, b| b.assets.cmp(&a.assets));
+    at_risk
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use bitcoin::hashes::{sha256, Hash};
+    use fedimint_core::config::FederationId;
+    use fedimint_core::Amount;
+
+    use super::federations_at_risk;
+
+    #[test]
+    fn test_federations_at_risk() {
+        let federation_id = |seed: u8| FederationId(sha256::Hash::hash(&[seed]));
+        let federations = vec![
+            (
+                federation_id(0),
+                Some("Healthy".to_owned()),
+                Amount::from_sats(5_000),
+            ),
+            (
+                federation_id(1),
+                Some("Small".to_owned()),
+                Amount::from_sats(1_000),
+            ),
+            (federation_id(2), None, Amount::from_sats(3_000)),
+        ];
+        let below_threshold =
+            BTreeSet::from([federation_id(1), federation_id(2), federation_id(3)]);
+
+        let at_risk = federations_at_risk(federations, &below_threshold);
+        assert_eq!(
+            at_risk
+                .iter()
+                .map(|federation| federation.federation_id)
+                .collect::<Vec<_>>(),
+            vec![federation_id(2), federation_id(1)]
+        );
+        assert_eq!(
+            at_risk
+                .iter()
+                .map(|federation| federation.assets.msats)
+                .sum::<u64>(),
+            4_000_000
+        );
+    }
+}
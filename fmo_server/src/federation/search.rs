@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use axum::extract::{Query, State};
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::invite_code::InviteCode;
+use fmo_api_types::rating::assign_rating_indices;
+use fmo_api_types::{
+    FederationHealth, FederationSearchMatch, FederationSearchResult, RatingAlgorithm, RatingWeights,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::db::Federation;
+use crate::federation::observer::FederationObserver;
+use crate::AppState;
+
+/// Shortest query that is matched against federation id prefixes, shorter hex
+/// strings are too likely to be part of a name
+const MIN_ID_PREFIX_LEN: usize = 4;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct SearchParams {
+    /// Federation name, federation id prefix or invite code
+    #[serde(default)]
+    q: String,
+    /// Only return federations on this bitcoin network, e.g. `bitcoin`
+    network: Option<String>,
+    /// Comma separated module kinds the federations need to have
+    modules: Option<String>,
+    /// Only return federations with this health
+    health: Option<FederationHealth>,
+    /// Algorithm scoring nostr votes, defaults to the instance's configured one
+    rating: Option<RatingAlgorithm>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/search",
+    tag = "federations",
+    params(SearchParams),
+    responses((status = 200, description = "Matching federations, best match first and by rating index otherwise", body = [FederationSearchResult]))
+)]
+pub(super) async fn search_federations(
+    Query(params): Query<SearchParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<FederationSearchResult>>> {
+    Ok(state
+        .federation_observer
+        .search_federations(
+            &params,
+            state.instance_settings.rating_weights,
+            params
+                .rating
+                .unwrap_or(state.instance_settings.rating_algorithm),
+        )
+        .await?
+        .into())
+}
+
+/// A federation that matched the query and filters that only need its config
+struct Candidate {
+    federation: Federation,
+    modules: Vec<String>,
+    matched: FederationSearchMatch,
+    score: u32,
+}
+
+impl FederationObserver {
+    /// Searches active federations and federations only announced via Nostr
+    /// by name, id prefix or invite code. Filters that only need the config
+    /// are applied first so summaries are only computed for candidates.
+    /// Equally good matches are ordered by their rating index, announced
+    /// federations after observed ones. Announced federations are only
+    /// returned for non-empty queries without filters, since their modules,
+    /// network and health are unknown.
+    async fn search_federations(
+        &self,
+        params: &SearchParams,
+        rating_weights: RatingWeights,
+        rating_algorithm: RatingAlgorithm,
+    ) -> anyhow::Result<Vec<FederationSearchResult>> {
+        let query = params.q.trim();
+        let invite_federation_id = InviteCode::from_str(query)
+            .ok()
+            .map(|invite| invite.federation_id());
+        let required_modules = params
+            .modules
+            .iter()
+            .flat_map(|modules| modules.split(','))
+            .map(|kind| kind.trim().to_lowercase())
+            .filter(|kind| !kind.is_empty())
+            .collect::<Vec<_>>();
+        let mut announcements = self
+            .list_nostr_federations()
+            .await?
+            .into_iter()
+            .map(|federation| (federation.federation_id, federation))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut candidates = Vec::new();
+        let mut observed = BTreeSet::new();
+        for federation in self.list_active_federations().await? {
+            observed.insert(federation.federation_id);
+            let name = federation.config.global.meta.get("federation_name");
+            let Some((matched, score)) = match_query(
+                query,
+                invite_federation_id,
+                federation.federation_id,
+                name.map(String::as_str),
+            ) else {
+                continue;
+            };
+
+            let modules = federation
+                .config
+                .modules
+                .values()
+                .map(|module| module.kind.to_string())
+                .collect::<Vec<_>>();
+            if !required_modules.iter().all(|kind| modules.contains(kind)) {
+                continue;
+            }
+
+            if let Some(required_network) = &params.network {
+                if !federation.network()?.is_some_and(|network| {
+                    network.to_string().eq_ignore_ascii_case(required_network)
+                }) {
+                    continue;
+                }
+            }
+
+            candidates.push(Candidate {
+                federation,
+                modules,
+                matched,
+                score,
+            });
+        }
+
+        let mut summaries = self
+            .federation_summaries(
+                candidates
+                    .iter()
+                    .map(|candidate| candidate.federation.clone())
+                    .collect(),
+            )
+            .await?;
+        assign_rating_indices(&mut summaries, rating_weights, rating_algorithm);
+
+        let mut results = candidates
+            .into_iter()
+            .zip(summaries)
+            .filter(|(_, summary)| {
+                params
+                    .health
+                    .map_or(true, |health| summary.health == health)
+            })
+            .map(|(candidate, summary)| {
+                let result = FederationSearchResult {
+                    id: summary.id,
+                    name: summary.name.clone(),
+                    announcement: announcements.get(&summary.id).cloned().map(Into::into),
+                    summary: Some(summary),
+                    modules: candidate.modules,
+                    matched: candidate.matched,
+                };
+                (candidate.score, result)
+            })
+            .collect::<Vec<_>>();
+
+        let filtered =
+            params.network.is_some() || !required_modules.is_empty() || params.health.is_some();
+        if !query.is_empty() && !filtered {
+            announcements.retain(|federation_id, _| !observed.contains(federation_id));
+            for (federation_id, announcement) in announcements {
+                let Some((matched, score)) = match_query(
+                    query,
+                    invite_federation_id,
+                    federation_id,
+                    announcement.name.as_deref(),
+                ) else {
+                    continue;
+                };
+                results.push((
+                    score,
+                    FederationSearchResult {
+                        id: federation_id,
+                        name: announcement.name.clone(),
+                        summary: None,
+                        announcement: Some(announcement.into()),
+                        modules: vec![],
+                        matched,
+                    },
+                ));
+            }
+        }
+
+        let rating_index = |result: &FederationSearchResult| {
+            result
+                .summary
+                .as_ref()
+                .map_or(f64::NEG_INFINITY, |summary| summary.rating_index)
+        };
+        results.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(rating_index(b).total_cmp(&rating_index(a)))
+        });
+
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+}
+
+/// How a federation matches the query and how well, `None` if it doesn't match
+fn match_query(
+    query: &str,
+    invite_federation_id: Option<FederationId>,
+    federation_id: FederationId,
+    name: Option<&str>,
+) -> Option<(FederationSearchMatch, u32)> {
+    if query.is_empty() {
+        Some((FederationSearchMatch::Any, 0))
+    } else if invite_federation_id == Some(federation_id) {
+        Some((FederationSearchMatch::Invite, u32::MAX))
+    } else if is_id_prefix(query, &federation_id.to_string()) {
+        Some((FederationSearchMatch::IdPrefix, u32::MAX - 1))
+    } else {
+        let score = fuzzy_score(query, name.unwrap_or_default())?;
+        Some((FederationSearchMatch::Name, score))
+    }
+}
+
+fn is_id_prefix(query: &str, federation_id: &str) -> bool {
+    query.len() >= MIN_ID_PREFIX_LEN
+        && query.chars().all(|c| c.is_ascii_hexdigit())
+        && federation_id.starts_with(&query.to_ascii_lowercase())
+}
+
+/// Scores how well `query` matches `name` if all its characters appear in
+/// `name` in order, ignoring case and whitespace. Consecutive characters and
+/// characters at the start of a word score higher.
+fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let name = name.to_lowercase().chars().collect::<Vec<_>>();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for query_char in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = (next..name.len()).find(|&idx| name[idx] == query_char)?;
+
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == idx) {
+            score += 4;
+        }
+        if idx == 0 || !name[idx - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        last_match = Some(idx);
+        next = idx + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fedimint_core::config::FederationId;
+    use fmo_api_types::FederationSearchMatch;
+
+    use super::{fuzzy_score, is_id_prefix, match_query};
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("xyz", "Bitcoin Principles"), None);
+        assert_eq!(fuzzy_score("nicb", "Bitcoin"), None);
+
+        let exact = fuzzy_score("bitcoin", "Bitcoin Principles").unwrap();
+        let scattered = fuzzy_score("bitcoin", "Big Tent Coin").unwrap();
+        assert!(exact > scattered);
+
+        let word_start = fuzzy_score("bp", "Bitcoin Principles").unwrap();
+        let mid_word = fuzzy_score("bp", "Bumpy").unwrap();
+        assert!(word_start > mid_word);
+
+        assert_eq!(
+            fuzzy_score("bitcoin principles", "Bitcoin Principles"),
+            fuzzy_score("BitcoinPrinciples", "Bitcoin Principles"),
+        );
+    }
+
+    #[test]
+    fn test_is_id_prefix() {
+        let id = "15db8cb4f1ec8e484d73b889372bec94812580f929e8148b7437d359af422cd3";
+        assert!(is_id_prefix("15db", id));
+        assert!(is_id_prefix("15DB8CB4", id));
+        assert!(!is_id_prefix("15d", id));
+        assert!(!is_id_prefix("15dc", id));
+        assert!(!is_id_prefix("15db-8c", id));
+    }
+
+    #[test]
+    fn test_match_query() {
+        let id = FederationId::from_str(
+            "15db8cb4f1ec8e484d73b889372bec94812580f929e8148b7437d359af422cd3",
+        )
+        .unwrap();
+
+        assert_eq!(
+            match_query("", None, id, None),
+            Some((FederationSearchMatch::Any, 0))
+        );
+        assert_eq!(
+            match_query("invite", Some(id), id, None).map(|(matched, _)| matched),
+            Some(FederationSearchMatch::Invite)
+        );
+        assert_eq!(
+            match_query("15db8c", None, id, Some("Other")).map(|(matched, _)| matched),
+            Some(FederationSearchMatch::IdPrefix)
+        );
+        assert_eq!(
+            match_query("bp", None, id, Some("Bitcoin Principles")).map(|(matched, _)| matched),
+            Some(FederationSearchMatch::Name)
+        );
+        assert_eq!(match_query("bp", None, id, None), None);
+    }
+}
@@ -1,36 +1,75 @@
-use std::collections::BTreeMap;
-
 use anyhow::Context;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
+use chrono::NaiveDateTime;
 use fedimint_core::config::FederationId;
 use fedimint_core::encoding::Encodable;
+use fedimint_core::epoch::ConsensusItem;
+use fmo_api_types::{Page, SessionDetails, SessionItem, SessionSummary};
 use postgres_from_row::FromRow;
-use serde_json::json;
+use serde::Deserialize;
+use utoipa::IntoParams;
 
 use crate::federation::observer::FederationObserver;
-use crate::util::{query, query_value};
+use crate::federation::{db, decoders_from_config, instance_to_kind};
+use crate::util::{query, query_opt, query_value};
 use crate::AppState;
 
+/// Number of sessions returned if the client doesn't specify a limit
+const DEFAULT_SESSION_PAGE_SIZE: u64 = 100;
+const MAX_SESSION_PAGE_SIZE: u64 = 1_000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct SessionListParams {
+    limit: Option<u64>,
+    /// Number of sessions to skip, counted from the most recent one
+    offset: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/sessions",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), SessionListParams),
+    responses((status = 200, description = "Sessions, most recent first", body = SessionPage))
+)]
 pub(super) async fn list_sessions(
     Path(federation_id): Path<FederationId>,
+    Query(params): Query<SessionListParams>,
     State(state): State<AppState>,
-) -> crate::error::Result<Json<BTreeMap<i64, serde_json::Value>>> {
-    Ok(state
+) -> crate::error::Result<Json<Page<SessionSummary>>> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SESSION_PAGE_SIZE)
+        .min(MAX_SESSION_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    let sessions = state
         .federation_observer
-        .federation_session_list(federation_id)
-        .await?
-        .into_iter()
-        .map(|session| {
-            (
-                session.session_index,
-                json!({ "transactions": session.transaction_count }),
-            )
-        })
-        .collect::<BTreeMap<_, _>>()
-        .into())
+        .federation_session_list(federation_id, limit, offset)
+        .await?;
+    let total = state
+        .federation_observer
+        .federation_session_count(federation_id)
+        .await?;
+
+    Ok(Page {
+        items: sessions,
+        total,
+        offset,
+        limit,
+    }
+    .into())
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/sessions/count",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = u64))
+)]
 pub(super) async fn count_sessions(
     Path(federation_id): Path<FederationId>,
     State(state): State<AppState>,
@@ -42,30 +81,155 @@ pub(super) async fn count_sessions(
         .into())
 }
 
-#[derive(FromRow)]
-pub struct SessionData {
-    pub session_index: i64,
-    pub transaction_count: i64,
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/sessions/{session_index}",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ("session_index" = u64, Path, description = "Index of the session")),
+    responses((status = 200, description = "Session with its decoded consensus items", body = SessionDetails))
+)]
+pub(super) async fn get_session(
+    Path((federation_id, session_index)): Path<(FederationId, u64)>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<SessionDetails>> {
+    Ok(state
+        .federation_observer
+        .federation_session_details(federation_id, session_index)
+        .await?
+        .into())
+}
+
+#[derive(Debug, FromRow)]
+struct SessionSummaryRow {
+    session_index: i32,
+    item_count: i64,
+    transaction_count: i64,
+    estimated_time: Option<NaiveDateTime>,
+}
+
+/// Selects the columns of [`SessionSummaryRow`], callers append the filter
+// language=postgresql
+const SESSION_SUMMARY_SELECT: &str = "
+    SELECT s.session_index,
+           COALESCE((SELECT SUM(sps.item_count)
+                     FROM session_peer_stats sps
+                     WHERE sps.federation_id = s.federation_id
+                       AND sps.session_index = s.session_index), 0)::bigint AS item_count,
+           (SELECT COUNT(*)
+            FROM transactions t
+            WHERE t.federation_id = s.federation_id
+              AND t.session_index = s.session_index)::bigint                 AS transaction_count,
+           st.estimated_session_timestamp                                     AS estimated_time
+    FROM sessions s
+             LEFT JOIN session_times st
+                       ON st.federation_id = s.federation_id AND st.session_index = s.session_index
+";
+
+impl From<SessionSummaryRow> for SessionSummary {
+    fn from(row: SessionSummaryRow) -> Self {
+        SessionSummary {
+            session_index: row.session_index as u64,
+            item_count: row.item_count as u64,
+            transaction_count: row.transaction_count as u64,
+            estimated_time: row.estimated_time.map(|time| time.and_utc()),
+        }
+    }
 }
 
 impl FederationObserver {
     pub async fn federation_session_list(
         &self,
         federation_id: FederationId,
-    ) -> anyhow::Result<Vec<SessionData>> {
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<Vec<SessionSummary>> {
         self.get_federation(federation_id)
-            .await
+            .await?
             .context("Federation doesn't exist")?;
 
-        query::<SessionData>(&self.connection().await?, "
-            SELECT s.session_index, COUNT(t.txid) AS transaction_count
-            FROM sessions AS s
-            LEFT JOIN transactions AS t ON s.federation_id = t.federation_id AND s.session_index = t.session_index
-            WHERE s.federation_id = $1
-            GROUP BY s.session_index
-            ORDER BY s.session_index ASC
-        ", &[&federation_id.consensus_encode_to_vec()])
-        .await
+        let sessions = query::<SessionSummaryRow>(
+            &self.connection().await?,
+            &format!(
+                "{SESSION_SUMMARY_SELECT}
+                WHERE s.federation_id = $1
+                ORDER BY s.session_index DESC
+                LIMIT $2 OFFSET $3"
+            ),
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(limit as i64),
+                &(offset as i64),
+            ],
+        )
+        .await?;
+
+        Ok(sessions.into_iter().map(Into::into).collect())
+    }
+
+    /// Decodes all consensus items of a session using the module decoders of
+    /// the federation's config
+    pub async fn federation_session_details(
+        &self,
+        federation_id: FederationId,
+        session_index: u64,
+    ) -> anyhow::Result<SessionDetails> {
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+        let conn = self.connection().await?;
+
+        let summary = query_opt::<SessionSummaryRow>(
+            &conn,
+            &format!(
+                "{SESSION_SUMMARY_SELECT} WHERE s.federation_id = $1 AND s.session_index = $2"
+            ),
+            &[&federation_id_bytes, &(session_index as i32)],
+        )
+        .await?
+        .context("Session doesn't exist")?;
+
+        let row = conn
+            .query_one(
+                // language=postgresql
+                "SELECT session_index, session FROM sessions WHERE federation_id = $1 AND session_index = $2",
+                &[&federation_id_bytes, &(session_index as i32)],
+            )
+            .await?;
+        let session = db::SessionOutcome::try_from_row_with_decoders(
+            &row,
+            &decoders_from_config(&federation.config),
+        )?;
+
+        let items = session
+            .data
+            .items
+            .into_iter()
+            .map(|item| {
+                let (kind, txid) = match &item.item {
+                    ConsensusItem::Transaction(transaction) => {
+                        ("transaction".to_owned(), Some(transaction.tx_hash()))
+                    }
+                    ConsensusItem::Module(module_ci) => (
+                        instance_to_kind(&federation.config, module_ci.module_instance_id()),
+                        None,
+                    ),
+                    _ => ("unknown".to_owned(), None),
+                };
+                SessionItem {
+                    peer: item.peer.to_usize() as u16,
+                    kind,
+                    txid,
+                    decoded: format!("{:?}", item.item),
+                }
+            })
+            .collect();
+
+        Ok(SessionDetails {
+            summary: summary.into(),
+            items,
+        })
     }
 
     pub async fn federation_session_count(
@@ -0,0 +1,152 @@
+use anyhow::{ensure, Context};
+use axum::extract::{Path, State};
+use chrono::NaiveDateTime;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::Amount;
+use fmo_api_types::FederationShutdown;
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query_opt};
+use crate::AppState;
+
+#[utoipa::path(
+    put,
+    path = "/federations/{federation_id}/shutdown",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Federation marked as shut down"))
+)]
+pub(super) async fn put_federation_shutdown(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .mark_federation_shutdown(federation_id)
+        .await?)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/federations/{federation_id}/shutdown",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    security(("admin_auth" = [])),
+    responses((status = 200, description = "Shutdown mark removed"))
+)]
+pub(super) async fn delete_federation_shutdown(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .unmark_federation_shutdown(federation_id)
+        .await?)
+}
+
+impl FederationObserver {
+    /// Marks a federation as shut down and records its lifetime statistics, so
+    /// they can be shown instead of the (now meaningless) live ones
+    pub async fn mark_federation_shutdown(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let now = chrono::Utc::now().naive_utc();
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "WITH wallet_deltas AS (SELECT t.session_index,
+                                           t.item_index,
+                                           COALESCE((SELECT SUM(ti.amount_msat)
+                                                     FROM transaction_inputs ti
+                                                     WHERE ti.federation_id = t.federation_id
+                                                       AND ti.txid = t.txid
+                                                       AND ti.kind = 'wallet'), 0) -
+                                           COALESCE((SELECT SUM(tout.amount_msat)
+                                                     FROM transaction_outputs tout
+                                                     WHERE tout.federation_id = t.federation_id
+                                                       AND tout.txid = t.txid
+                                                       AND tout.kind = 'wallet'), 0) AS delta
+                                    FROM transactions t
+                                    WHERE t.federation_id = $1),
+                  assets AS (SELECT SUM(delta) OVER (ORDER BY session_index, item_index) AS assets
+                             FROM wallet_deltas)
+             INSERT INTO federation_shutdowns (federation_id, shutdown_at, total_volume_msat, peak_assets_msat, last_activity)
+             VALUES ($1,
+                     $2,
+                     (SELECT COALESCE(SUM(amount_msat), 0)::bigint
+                      FROM transaction_inputs
+                      WHERE federation_id = $1),
+                     (SELECT COALESCE(MAX(assets), 0)::bigint FROM assets),
+                     (SELECT MAX(st.estimated_session_timestamp)
+                      FROM transactions t
+                               JOIN session_times st
+                                    ON t.federation_id = st.federation_id AND t.session_index = st.session_index
+                      WHERE t.federation_id = $1))
+             ON CONFLICT (federation_id) DO UPDATE
+                 SET shutdown_at       = excluded.shutdown_at,
+                     total_volume_msat = excluded.total_volume_msat,
+                     peak_assets_msat  = excluded.peak_assets_msat,
+                     last_activity     = excluded.last_activity",
+            &[&federation_id.consensus_encode_to_vec(), &now],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unmark_federation_shutdown(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<()> {
+        let deleted = execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM federation_shutdowns WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+        ensure!(deleted > 0, "Federation isn't marked as shut down");
+
+        Ok(())
+    }
+
+    pub async fn federation_shutdown(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Option<FederationShutdown>> {
+        #[derive(Debug, FromRow)]
+        struct FederationShutdownRow {
+            shutdown_at: NaiveDateTime,
+            total_volume_msat: i64,
+            peak_assets_msat: i64,
+            last_activity: Option<NaiveDateTime>,
+        }
+
+        let shutdown = query_opt::<FederationShutdownRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT shutdown_at, total_volume_msat, peak_assets_msat, last_activity
+             FROM federation_shutdowns
+             WHERE federation_id = $1",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(shutdown.map(|shutdown| FederationShutdown {
+            shutdown_at: shutdown.shutdown_at.and_utc(),
+            total_volume: Amount::from_msats(shutdown.total_volume_msat as u64),
+            peak_assets: Amount::from_msats(shutdown.peak_assets_msat as u64),
+            last_activity: shutdown
+                .last_activity
+                .map(|last_activity| last_activity.and_utc()),
+        }))
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use fedimint_core::encoding::Encodable;
+use fedimint_core::PeerId;
+use fmo_api_types::GuardianSigningLatency;
+use postgres_from_row::FromRow;
+
+use crate::federation::db::Federation;
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+
+/// Delay between a withdrawal request and one guardian's signature of the
+/// transaction paying it out
+#[derive(Debug, FromRow)]
+struct SignatureDelayRow {
+    txid: Vec<u8>,
+    peer_id: i32,
+    session_delay: i32,
+    delay_secs: Option<f64>,
+}
+
+impl FederationObserver {
+    /// Signing latency per guardian over all withdrawals whose on-chain
+    /// transaction is known. If a withdrawal was paid out by several
+    /// transactions, e.g. because of fee bumps, a guardian's first signature of
+    /// any of them counts.
+    pub(super) async fn withdrawal_signing_latency(
+        &self,
+        federation: &Federation,
+    ) -> anyhow::Result<BTreeMap<PeerId, GuardianSigningLatency>> {
+        let delays = query::<SignatureDelayRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT wwa.txid,
+                    wws.peer_id,
+                    (MIN(wws.session_index) - wwa.session_index)::integer AS session_delay,
+                    EXTRACT(EPOCH FROM MIN(sst.estimated_session_timestamp) -
+                                       rst.estimated_session_timestamp)::float8 AS delay_secs
+             FROM wallet_withdrawal_addresses wwa
+                      JOIN wallet_withdrawal_transactions wwt ON wwt.federation_id = wwa.federation_id AND
+                                                                 wwt.federation_txid = wwa.txid
+                      JOIN wallet_withdrawal_signatures wws ON wws.on_chain_txid = wwt.on_chain_txid
+                      LEFT JOIN session_times rst ON rst.federation_id = wwa.federation_id AND
+                                                     rst.session_index = wwa.session_index
+                      LEFT JOIN session_times sst ON sst.federation_id = wwa.federation_id AND
+                                                     sst.session_index = wws.session_index
+             WHERE wwa.federation_id = $1
+             GROUP BY wwa.txid, wwa.session_index, wws.peer_id, rst.estimated_session_timestamp",
+            &[&federation.federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(signing_latency(
+            federation.config.global.api_endpoints.keys().copied(),
+            delays,
+        ))
+    }
+}
+
+/// Aggregates signature delays per guardian. Every guardian that didn't sign a
+/// withdrawal others signed counts it as unsigned. Returns an empty map if
+/// there are no withdrawals.
+fn signing_latency(
+    peers: impl IntoIterator<Item = PeerId>,
+    delays: Vec<SignatureDelayRow>,
+) -> BTreeMap<PeerId, GuardianSigningLatency> {
+    let withdrawals = delays
+        .iter()
+        .map(|delay| delay.txid.as_slice())
+        .collect::<BTreeSet<_>>()
+        .len() as u64;
+    if withdrawals == 0 {
+        return BTreeMap::new();
+    }
+
+    let mut delays_by_peer = BTreeMap::<PeerId, Vec<&SignatureDelayRow>>::new();
+    for delay in &delays {
+        delays_by_peer
+            .entry(PeerId::from(delay.peer_id as u16))
+            .or_default()
+            .push(delay);
+    }
+
+    peers
+        .into_iter()
+        .map(|peer_id| {
+            let peer_delays = delays_by_peer.remove(&peer_id).unwrap_or_default();
+            let mut session_delays = peer_delays
+                .iter()
+                .map(|delay| delay.session_delay.max(0) as u64)
+                .collect::<Vec<_>>();
+            session_delays.sort_unstable();
+            let delays_secs = peer_delays
+                .iter()
+                .filter_map(|delay| delay.delay_secs)
+                .collect::<Vec<_>>();
+
+            let signed_withdrawals = session_delays.len() as u64;
+            let latency = GuardianSigningLatency {
+                signed_withdrawals,
+                unsigned_withdrawals: withdrawals.saturating_sub(signed_withdrawals),
+                avg_session_delay: (!session_delays.is_empty()).then(|| {
+                    session_delays.iter().sum::<u64>() as f64 / session_delays.len() as f64
+                }),
+                median_session_delay: session_delays
+                    .get(session_delays.len().saturating_sub(1) / 2)
+                    .copied(),
+                max_session_delay: session_delays.last().copied(),
+                avg_delay_secs: (!delays_secs.is_empty())
+                    .then(|| delays_secs.iter().sum::<f64>() / delays_secs.len() as f64),
+            };
+            (peer_id, latency)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::PeerId;
+
+    use super::{signing_latency, SignatureDelayRow};
+
+    fn delay(txid: u8, peer_id: i32, session_delay: i32) -> SignatureDelayRow {
+        SignatureDelayRow {
+            txid: vec![txid],
+            peer_id,
+            session_delay,
+            delay_secs: Some(session_delay as f64 * 600.0),
+        }
+    }
+
+    #[test]
+    fn test_signing_latency() {
+        let peers = (0..3).map(PeerId::from);
+        assert!(signing_latency(peers.clone(), vec![]).is_empty());
+
+        let latency = signing_latency(
+            peers,
+            vec![
+                delay(1, 0, 0),
+                delay(1, 1, 2),
+                delay(2, 0, 1),
+                delay(2, 1, 6),
+                delay(3, 0, 1),
+                delay(3, 1, 4),
+            ],
+        );
+
+        let fast = &latency[&PeerId::from(0)];
+        assert_eq!(fast.signed_withdrawals, 3);
+        assert_eq!(fast.unsigned_withdrawals, 0);
+        assert_eq!(fast.median_session_delay, Some(1));
+        assert_eq!(fast.max_session_delay, Some(1));
+
+        let slow = &latency[&PeerId::from(1)];
+        assert_eq!(slow.avg_session_delay, Some(4.0));
+        assert_eq!(slow.median_session_delay, Some(4));
+        assert_eq!(slow.avg_delay_secs, Some(2400.0));
+
+        let absent = &latency[&PeerId::from(2)];
+        assert_eq!(absent.signed_withdrawals, 0);
+        assert_eq!(absent.unsigned_withdrawals, 3);
+        assert_eq!(absent.avg_session_delay, None);
+    }
+}
@@ -0,0 +1,562 @@
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use bytes::Bytes;
+use chrono::{NaiveDateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fmo_api_types::SnapshotVerification;
+use futures::{SinkExt, Stream, StreamExt};
+use postgres_from_row::FromRow;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::IsolationLevel;
+use tracing::{error, info, warn};
+
+use crate::federation::decoders_from_config;
+use crate::federation::observer::FederationObserver;
+use crate::socks::federation_api;
+use crate::util::{execute, query, query_value};
+
+/// Identifies snapshot files, the number is bumped on format changes
+const SNAPSHOT_MAGIC: &[u8; 8] = b"FMOSNAP1";
+
+/// Tables filled by syncing federations in an order that satisfies their
+/// foreign keys. Operator data like alert rules, views and nostr state isn't
+/// part of snapshots.
+pub const SNAPSHOT_TABLES: &[&str] = &[
+    "federations",
+    "block_times",
+    "sessions",
+    "transactions",
+    "ln_contracts",
+    "transaction_inputs",
+    "transaction_outputs",
+    "block_height_votes",
+    "wallet_peg_ins",
+    "wallet_withdrawal_addresses",
+    "wallet_withdrawal_transactions",
+    "wallet_withdrawal_signatures",
+    "wallet_withdrawal_transaction_inputs",
+    "wallet_withdrawal_transaction_outputs",
+    "session_peer_stats",
+    "fees",
+    "transaction_input_details",
+    "transaction_output_details",
+    "consensus_items",
+    "wallet_feerate_votes",
+    "quarantined_items",
+    "processing_errors",
+    "observer_checkpoints",
+];
+
+/// Tables that reference their federation only through
+/// `wallet_withdrawal_transactions`
+const WITHDRAWAL_TRANSACTION_TABLES: &[&str] = &[
+    "wallet_withdrawal_signatures",
+    "wallet_withdrawal_transaction_inputs",
+    "wallet_withdrawal_transaction_outputs",
+];
+/// Upper bound for a single `COPY` chunk, which holds one row. Lengths come
+/// from the uploaded file, so they can't be trusted.
+const MAX_CHUNK_LEN: u32 = 64 * 1024 * 1024;
+
+const VERIFICATION_INTERVAL: Duration = Duration::from_secs(10);
+/// Sessions verified per federation and interval, keeps the load on the
+/// guardians low
+const VERIFICATION_BATCH_SIZE: i32 = 10;
+const VERIFICATION_FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+impl FederationObserver {
+    /// Streams a snapshot of `tables` taken in a single read-only transaction.
+    /// The format is the magic bytes and schema version followed by each
+    /// table's name and its binary `COPY` data split into length-prefixed
+    /// chunks.
+    pub fn snapshot(
+        &self,
+        tables: Vec<&'static str>,
+    ) -> impl Stream<Item = anyhow::Result<Vec<u8>>> + Send + 'static {
+        let slf = self.clone();
+        async_stream::try_stream! {
+            let mut conn = slf.connection().await?;
+            let dbtx = conn
+                .build_transaction()
+                .isolation_level(IsolationLevel::RepeatableRead)
+                .read_only(true)
+                .start()
+                .await?;
+
+            let schema_version =
+                query_value::<i32>(&dbtx, "SELECT get_max_version();", &[]).await?;
+            yield snapshot_header(schema_version);
+
+            for table in tables {
+                yield table_header(table);
+                let copy = dbtx
+                    .copy_out(&format!("COPY {table} TO STDOUT (FORMAT binary)"))
+                    .await?;
+                let mut copy = std::pin::pin!(copy);
+                while let Some(chunk) = copy.next().await {
+                    yield data_chunk(&chunk?);
+                }
+                yield data_chunk(&[]);
+            }
+            yield table_header("");
+        }
+    }
+
+    /// Adds the data of a snapshot created by [`FederationObserver::snapshot`]
+    /// to the database in one transaction. Only federations that aren't
+    /// observed yet can be restored and the snapshot has to be taken at the
+    /// same schema version. Returns the restored federations.
+    pub async fn restore_snapshot(
+        &self,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> anyhow::Result<Vec<FederationId>> {
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .await
+            .context("Snapshot is empty")?;
+        ensure!(&magic == SNAPSHOT_MAGIC, "Not a snapshot file");
+        let snapshot_version = reader.read_i32().await?;
+        let mut conn = self.connection().await?;
+        let schema_version = query_value::<i32>(&conn, "SELECT get_max_version();", &[]).await?;
+        ensure!(
+            snapshot_version == schema_version,
+            "Snapshot has schema version {snapshot_version}, but the database has {schema_version}"
+        );
+
+        let dbtx = conn.transaction().await?;
+        let mut federation_ids = None;
+        let mut last_table_idx = None;
+        loop {
+            let table = read_table_name(&mut reader).await?;
+            if table.is_empty() {
+                break;
+            }
+            // Tables have to be in the order snapshots are written in, which
+            // starts with the federations all other rows are checked against
+            let table_idx = SNAPSHOT_TABLES
+                .iter()
+                .position(|known| *known == table)
+                .expect("Checked when reading the name");
+            ensure!(
+                last_table_idx.map_or(true, |last_idx| table_idx > last_idx),
+                "Table {table} is out of order in snapshot"
+            );
+            last_table_idx = Some(table_idx);
+            ensure!(
+                table == "federations" || federation_ids.is_some(),
+                "Snapshot doesn't contain the federations table"
+            );
+
+            let staging = format!("snapshot_{table}");
+            dbtx.batch_execute(&format!(
+                "CREATE TEMPORARY TABLE {staging} (LIKE {table}) ON COMMIT DROP"
+            ))
+            .await?;
+            let sink = dbtx
+                .copy_in::<_, Bytes>(&format!("COPY {staging} FROM STDIN (FORMAT binary)"))
+                .await?;
+            let mut sink = std::pin::pin!(sink);
+            loop {
+                let chunk = read_chunk(&mut reader).await?;
+                if chunk.is_empty() {
+                    break;
+                }
+                sink.send(Bytes::from(chunk)).await?;
+            }
+            sink.as_mut().finish().await?;
+
+            if table == "federations" {
+                federation_ids = Some(self.new_snapshot_federations(&dbtx).await?);
+            }
+            let encoded_ids = federation_ids
+                .iter()
+                .flatten()
+                .map(Encodable::consensus_encode_to_vec)
+                .collect::<Vec<_>>();
+
+            // Rows of other federations are dropped, they could overwrite data the
+            // instance observed itself. Block times are seeded on startup, so they
+            // may exist already.
+            let filter = restore_filter(&table);
+            let params: Vec<&(dyn ToSql + Sync)> = match filter {
+                Some(_) => vec![&encoded_ids],
+                None => vec![],
+            };
+            let inserted = execute(
+                &dbtx,
+                &format!(
+                    "INSERT INTO {table} SELECT * FROM {staging} {} ON CONFLICT DO NOTHING",
+                    filter.unwrap_or_default()
+                ),
+                &params,
+            )
+            .await?;
+            info!("Restored {inserted} rows of {table}");
+        }
+        let federation_ids =
+            federation_ids.context("Snapshot doesn't contain the federations table")?;
+
+        execute(
+            &dbtx,
+            // language=postgresql
+            "INSERT INTO snapshot_restores (federation_id, restored_at, session_count)
+             SELECT f.federation_id, $1, COALESCE(MAX(s.session_index) + 1, 0)
+             FROM federations f
+                      LEFT JOIN sessions s ON s.federation_id = f.federation_id
+             WHERE f.federation_id = ANY ($2)
+             GROUP BY f.federation_id",
+            &[
+                &Utc::now().naive_utc(),
+                &federation_ids
+                    .iter()
+                    .map(Encodable::consensus_encode_to_vec)
+                    .collect::<Vec<_>>(),
+            ],
+        )
+        .await?;
+        dbtx.commit().await?;
+
+        Ok(federation_ids)
+    }
+
+    /// Federations in the staged snapshot, failing if any is observed already
+    async fn new_snapshot_federations(
+        &self,
+        dbtx: &deadpool_postgres::Transaction<'_>,
+    ) -> anyhow::Result<Vec<FederationId>> {
+        let rows = dbtx
+            .query(
+                // language=postgresql
+                "SELECT s.federation_id, f.federation_id IS NOT NULL AS observed
+                 FROM snapshot_federations s
+                          LEFT JOIN federations f ON f.federation_id = s.federation_id",
+                &[],
+            )
+            .await?;
+
+        let mut federation_ids = Vec::new();
+        for row in rows {
+            let federation_id = FederationId::consensus_decode_vec(
+                row.get::<_, Vec<u8>>("federation_id"),
+                &Default::default(),
+            )?;
+            if row.get::<_, bool>("observed") {
+                bail!("Federation {federation_id} is observed already, remove it before restoring");
+            }
+            federation_ids.push(federation_id);
+        }
+        Ok(federation_ids)
+    }
+
+    /// Starts observing federations added by a restore, so they continue
+    /// syncing after the last restored session
+    pub async fn spawn_restored_observers(
+        &self,
+        federation_ids: &[FederationId],
+    ) -> anyhow::Result<()> {
+        for federation_id in federation_ids {
+            let federation = self
+                .get_federation(*federation_id)
+                .await?
+                .context("Restored federation doesn't exist")?;
+            self.spawn_observer(federation).await;
+        }
+        Ok(())
+    }
+
+    pub async fn snapshot_verifications(&self) -> anyhow::Result<Vec<SnapshotVerification>> {
+        self.snapshot_restores(false)
+            .await?
+            .into_iter()
+            .map(|restore| {
+                Ok(SnapshotVerification {
+                    federation_id: restore.federation_id()?,
+                    restored_at: restore.restored_at.and_utc(),
+                    session_count: restore.session_count as u64,
+                    verified_sessions: restore.verified_sessions as u64,
+                    mismatch_session: restore.mismatch_session.map(|session| session as u64),
+                })
+            })
+            .collect()
+    }
+
+    pub(super) async fn verify_restored_sessions(self) {
+        loop {
+            if let Err(e) = self.verify_restored_sessions_inner().await {
+                warn!("Error while verifying restored sessions: {e:?}");
+            }
+            tokio::time::sleep(VERIFICATION_INTERVAL).await;
+        }
+    }
+
+    /// Fetches the next batch of restored sessions from each federation and
+    /// compares them to the stored ones, stopping at the first mismatch
+    async fn verify_restored_sessions_inner(&self) -> anyhow::Result<()> {
+        for restore in self.snapshot_restores(true).await? {
+            let federation_id = restore.federation_id()?;
+            let federation = self
+                .get_federation(federation_id)
+                .await?
+                .context("Restored federation doesn't exist")?;
+            let api = federation_api(&federation.config.global.api_endpoints);
+            let decoders = decoders_from_config(&federation.config);
+
+            let batch_end =
+                (restore.verified_sessions + VERIFICATION_BATCH_SIZE).min(restore.session_count);
+            let stored_sessions = self
+                .connection()
+                .await?
+                .query(
+                    // language=postgresql
+                    "SELECT session_index, session
+                     FROM sessions
+                     WHERE federation_id = $1
+                       AND session_index >= $2
+                       AND session_index < $3
+                     ORDER BY session_index",
+                    &[
+                        &federation_id.consensus_encode_to_vec(),
+                        &restore.verified_sessions,
+                        &batch_end,
+                    ],
+                )
+                .await?;
+
+            let mut verified_sessions = restore.verified_sessions;
+            let mut mismatch_session = None;
+            for row in stored_sessions {
+                let session_index = row.get::<_, i32>("session_index");
+                if session_index != verified_sessions {
+                    // A session missing from the snapshot can't be verified
+                    mismatch_session = Some(verified_sessions);
+                    break;
+                }
+
+                let session_outcome = tokio::time::timeout(
+                    VERIFICATION_FETCH_TIMEOUT,
+                    api.await_block(session_index as u64, &decoders),
+                )
+                .await
+                .context("Timed out fetching session")??;
+                if session_outcome.consensus_encode_to_vec() != row.get::<_, Vec<u8>>("session") {
+                    mismatch_session = Some(session_index);
+                    break;
+                }
+                verified_sessions += 1;
+            }
+            if mismatch_session.is_none() && verified_sessions < batch_end {
+                mismatch_session = Some(verified_sessions);
+            }
+
+            if let Some(session_index) = mismatch_session {
+                error!("Restored session {session_index} of {federation_id} doesn't match the federation's");
+            } else if verified_sessions == restore.session_count {
+                info!("Verified all {verified_sessions} restored sessions of {federation_id}");
+            }
+            execute(
+                &self.connection().await?,
+                // language=postgresql
+                "UPDATE snapshot_restores
+                 SET verified_sessions = $2,
+                     mismatch_session  = $3
+                 WHERE federation_id = $1",
+                &[
+                    &federation_id.consensus_encode_to_vec(),
+                    &verified_sessions,
+                    &mismatch_session,
+                ],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn snapshot_restores(&self, pending_only: bool) -> anyhow::Result<Vec<SnapshotRestore>> {
+        query::<SnapshotRestore>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT federation_id, restored_at, session_count, verified_sessions, mismatch_session
+             FROM snapshot_restores
+             WHERE NOT $1
+                OR (verified_sessions < session_count AND mismatch_session IS NULL)
+             ORDER BY restored_at",
+            &[&pending_only],
+        )
+        .await
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct SnapshotRestore {
+    federation_id: Vec<u8>,
+    restored_at: NaiveDateTime,
+    session_count: i32,
+    verified_sessions: i32,
+    mismatch_session: Option<i32>,
+}
+
+impl SnapshotRestore {
+    fn federation_id(&self) -> anyhow::Result<FederationId> {
+        Ok(FederationId::consensus_decode_vec(
+            self.federation_id.clone(),
+            &Default::default(),
+        )?)
+    }
+}
+
+/// Condition on staged rows of `table` that only restores rows of the
+/// federations in `$1`, `None` for tables not tied to a federation
+fn restore_filter(table: &str) -> Option<&'static str> {
+    if table == "block_times" {
+        None
+    } else if WITHDRAWAL_TRANSACTION_TABLES.contains(&table) {
+        Some(
+            "WHERE on_chain_txid IN (SELECT on_chain_txid
+                                     FROM wallet_withdrawal_transactions
+                                     WHERE federation_id = ANY ($1))",
+        )
+    } else {
+        Some("WHERE federation_id = ANY ($1)")
+    }
+}
+
+/// Picks the snapshot tables from a comma separated selection, all tables if
+/// none is given. Tables are always returned in restorable order.
+pub fn snapshot_tables(selection: Option<&str>) -> anyhow::Result<Vec<&'static str>> {
+    let Some(selection) = selection else {
+        return Ok(SNAPSHOT_TABLES.to_vec());
+    };
+
+    let selected = selection
+        .split(',')
+        .map(str::trim)
+        .filter(|table| !table.is_empty())
+        .collect::<Vec<_>>();
+    if let Some(unknown) = selected
+        .iter()
+        .find(|table| !SNAPSHOT_TABLES.contains(table))
+    {
+        bail!("Table {unknown} can't be included in snapshots");
+    }
+
+    Ok(SNAPSHOT_TABLES
+        .iter()
+        .copied()
+        .filter(|table| selected.contains(table))
+        .collect())
+}
+
+fn snapshot_header(schema_version: i32) -> Vec<u8> {
+    [SNAPSHOT_MAGIC.as_slice(), &schema_version.to_be_bytes()].concat()
+}
+
+/// An empty name marks the end of the snapshot
+fn table_header(table: &str) -> Vec<u8> {
+    [&[table.len() as u8], table.as_bytes()].concat()
+}
+
+/// An empty chunk marks the end of a table's data
+fn data_chunk(data: &[u8]) -> Vec<u8> {
+    [&(data.len() as u32).to_be_bytes(), data].concat()
+}
+
+/// Reads a length-prefixed chunk without allocating more than was actually
+/// received
+async fn read_chunk(reader: &mut (impl AsyncRead + Unpin)) -> anyhow::Result<Vec<u8>> {
+    let len = reader.read_u32().await?;
+    ensure!(
+        len <= MAX_CHUNK_LEN,
+        "Snapshot chunk of {len} bytes is too large"
+    );
+    let mut chunk = Vec::new();
+    (&mut *reader)
+        .take(len.into())
+        .read_to_end(&mut chunk)
+        .await?;
+    ensure!(
+        chunk.len() == len as usize,
+        "Snapshot ends in the middle of a chunk"
+    );
+    Ok(chunk)
+}
+
+async fn read_table_name(reader: &mut (impl AsyncRead + Unpin)) -> anyhow::Result<String> {
+    let len = reader.read_u8().await?;
+    let mut name = vec![0u8; len as usize];
+    reader.read_exact(&mut name).await?;
+    let name = String::from_utf8(name).context("Invalid table name")?;
+    // Table names end up in SQL statements, so only known ones are accepted
+    ensure!(
+        name.is_empty() || SNAPSHOT_TABLES.contains(&name.as_str()),
+        "Unexpected table {name} in snapshot"
+    );
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        data_chunk, read_chunk, read_table_name, restore_filter, snapshot_tables, table_header,
+        MAX_CHUNK_LEN, SNAPSHOT_TABLES,
+    };
+
+    #[test]
+    fn test_snapshot_tables() {
+        assert_eq!(snapshot_tables(None).unwrap(), SNAPSHOT_TABLES);
+        assert_eq!(
+            snapshot_tables(Some("sessions, federations")).unwrap(),
+            vec!["federations", "sessions"]
+        );
+        assert!(snapshot_tables(Some("sessions,alert_rules")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_framing() {
+        let snapshot = [
+            table_header("sessions"),
+            data_chunk(b"data"),
+            data_chunk(&[]),
+            table_header("views"),
+        ]
+        .concat();
+        let mut reader = snapshot.as_slice();
+
+        assert_eq!(read_table_name(&mut reader).await.unwrap(), "sessions");
+        assert_eq!(&reader[..8], &[0, 0, 0, 4, b'd', b'a', b't', b'a']);
+        reader = &reader[12..];
+        assert!(read_table_name(&mut reader).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_chunk() {
+        let snapshot = [data_chunk(b"data"), data_chunk(&[])].concat();
+        let mut reader = snapshot.as_slice();
+        assert_eq!(read_chunk(&mut reader).await.unwrap(), b"data");
+        assert!(read_chunk(&mut reader).await.unwrap().is_empty());
+
+        let oversized = (MAX_CHUNK_LEN + 1).to_be_bytes();
+        assert!(read_chunk(&mut oversized.as_slice()).await.is_err());
+
+        let truncated = [&10u32.to_be_bytes()[..], b"data"].concat();
+        assert!(read_chunk(&mut truncated.as_slice()).await.is_err());
+    }
+
+    #[test]
+    fn test_restore_filter() {
+        assert_eq!(restore_filter("block_times"), None);
+        for table in SNAPSHOT_TABLES
+            .iter()
+            .filter(|table| **table != "block_times")
+        {
+            assert!(
+                restore_filter(table).unwrap().contains("ANY ($1)"),
+                "{table}"
+            );
+        }
+    }
+}
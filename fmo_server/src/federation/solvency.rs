@@ -0,0 +1,119 @@
+use anyhow::Context;
+use axum::extract::{Path, State};
+use axum::Json;
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fedimint_core::Amount;
+use fmo_api_types::FederationSolvency;
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query_one;
+use crate::AppState;
+
+/// Unconfirmed change outputs are missing from the reconstructed UTXO set, so
+/// small shortfalls are expected while peg-outs are pending
+const INSOLVENCY_THRESHOLD: f64 = 0.99;
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/solvency",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = FederationSolvency))
+)]
+pub(super) async fn get_federation_solvency(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<FederationSolvency>> {
+    Ok(state
+        .federation_observer
+        .federation_solvency(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    /// Compares the federation's on-chain UTXOs against its outstanding e-cash
+    /// and lightning contract balances
+    pub async fn federation_solvency(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<FederationSolvency> {
+        #[derive(Debug, FromRow)]
+        struct SolvencyRow {
+            assets: i64,
+            ecash_liabilities: i64,
+            ln_contract_liabilities: i64,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let row = query_one::<SolvencyRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT (SELECT COALESCE(SUM(amount_msat), 0)
+                     FROM utxos
+                     WHERE federation_id = $1)::bigint AS assets,
+                    ((SELECT COALESCE(SUM(amount_msat), 0)
+                      FROM transaction_outputs
+                      WHERE federation_id = $1 AND kind = 'mint') -
+                     (SELECT COALESCE(SUM(amount_msat), 0)
+                      FROM transaction_inputs
+                      WHERE federation_id = $1 AND kind = 'mint'))::bigint AS ecash_liabilities,
+                    ((SELECT COALESCE(SUM(amount_msat), 0)
+                      FROM transaction_outputs
+                      WHERE federation_id = $1 AND kind = 'ln') -
+                     (SELECT COALESCE(SUM(amount_msat), 0)
+                      FROM transaction_inputs
+                      WHERE federation_id = $1 AND kind = 'ln'))::bigint AS ln_contract_liabilities",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        Ok(solvency(
+            row.assets.max(0) as u64,
+            row.ecash_liabilities.max(0) as u64,
+            row.ln_contract_liabilities.max(0) as u64,
+        ))
+    }
+}
+
+fn solvency(
+    assets_msat: u64,
+    ecash_liabilities_msat: u64,
+    ln_contract_liabilities_msat: u64,
+) -> FederationSolvency {
+    let liabilities_msat = ecash_liabilities_msat + ln_contract_liabilities_msat;
+    let ratio = (liabilities_msat > 0).then(|| assets_msat as f64 / liabilities_msat as f64);
+
+    FederationSolvency {
+        assets: Amount::from_msats(assets_msat),
+        ecash_liabilities: Amount::from_msats(ecash_liabilities_msat),
+        ln_contract_liabilities: Amount::from_msats(ln_contract_liabilities_msat),
+        ratio,
+        appears_insolvent: ratio.is_some_and(|ratio| ratio < INSOLVENCY_THRESHOLD),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solvency;
+
+    #[test]
+    fn test_solvency() {
+        let healthy = solvency(1_000_000, 900_000, 50_000);
+        assert!(!healthy.appears_insolvent);
+        assert!(healthy.ratio.unwrap() > 1.0);
+
+        // Tolerate small shortfalls from unconfirmed change
+        assert!(!solvency(995_000, 1_000_000, 0).appears_insolvent);
+        assert!(solvency(500_000, 900_000, 100_000).appears_insolvent);
+
+        let empty = solvency(0, 0, 0);
+        assert_eq!(empty.ratio, None);
+        assert!(!empty.appears_insolvent);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::FederationSummary;
+use postgres_from_row::FromRow;
+use tokio::sync::RwLock;
+
+use crate::federation::db::Federation;
+use crate::federation::observer::FederationObserver;
+use crate::util::{execute, query};
+
+const DEFAULT_SUMMARY_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Federation summaries kept in memory and in the `federation_summaries` table,
+/// so listing federations doesn't run the summary queries of every federation
+/// per request. They are refreshed together with the materialized views.
+#[derive(Debug, Clone)]
+pub(super) struct SummaryCache {
+    /// Summaries older than this are recomputed on request, which only happens
+    /// if refreshing them in the background fails
+    ttl: Duration,
+    summaries: Arc<RwLock<BTreeMap<FederationId, CachedSummary>>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedSummary {
+    summary: FederationSummary,
+    updated_at: DateTime<Utc>,
+}
+
+impl SummaryCache {
+    pub(super) fn from_env() -> anyhow::Result<SummaryCache> {
+        let ttl = match dotenv::var("FO_SUMMARY_CACHE_TTL_SECS") {
+            Ok(ttl) => {
+                Duration::from_secs(ttl.parse().context("Invalid FO_SUMMARY_CACHE_TTL_SECS")?)
+            }
+            Err(_) => DEFAULT_SUMMARY_TTL,
+        };
+
+        Ok(SummaryCache {
+            ttl,
+            summaries: Default::default(),
+        })
+    }
+
+    fn is_fresh(&self, cached: &CachedSummary, now: DateTime<Utc>) -> bool {
+        // Negative ages can only be caused by clock adjustments
+        (now - cached.updated_at)
+            .to_std()
+            .map_or(true, |age| age <= self.ttl)
+    }
+}
+
+impl FederationObserver {
+    /// Summaries of `federations` in the same order. Cached summaries are used
+    /// if they are younger than the configured TTL, the others are computed
+    /// and cached, e.g. for federations that were just added.
+    pub(super) async fn federation_summaries(
+        &self,
+        federations: Vec<Federation>,
+    ) -> anyhow::Result<Vec<FederationSummary>> {
+        let now = Utc::now();
+        let mut summaries = {
+            let cache = self.summary_cache().summaries.read().await;
+            federations
+                .iter()
+                .filter_map(|federation| cache.get(&federation.federation_id))
+                .filter(|cached| self.summary_cache().is_fresh(cached, now))
+                .map(|cached| (cached.summary.id, cached.summary.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        // The memory cache is empty after a restart, the table isn't
+        let missing = federations
+            .iter()
+            .map(|federation| federation.federation_id)
+            .filter(|federation_id| !summaries.contains_key(federation_id))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let loaded = self.load_cached_summaries(&missing).await?;
+            let mut cache = self.summary_cache().summaries.write().await;
+            for cached in loaded {
+                if self.summary_cache().is_fresh(&cached, now) {
+                    summaries.insert(cached.summary.id, cached.summary.clone());
+                    cache.insert(cached.summary.id, cached);
+                }
+            }
+        }
+
+        let uncached = federations
+            .iter()
+            .filter(|federation| !summaries.contains_key(&federation.federation_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !uncached.is_empty() {
+            for summary in self.compute_federation_summaries(uncached).await? {
+                self.store_summary(summary.clone()).await?;
+                summaries.insert(summary.id, summary);
+            }
+        }
+
+        Ok(federations
+            .iter()
+            .filter_map(|federation| summaries.remove(&federation.federation_id))
+            .collect())
+    }
+
+    /// Recomputes the summaries of all active federations and drops the ones
+    /// of deactivated federations
+    pub(super) async fn refresh_federation_summaries(&self) -> anyhow::Result<()> {
+        let federations = self.list_active_federations().await?;
+        let active = federations
+            .iter()
+            .map(|federation| federation.federation_id)
+            .collect::<BTreeSet<_>>();
+
+        for summary in self.compute_federation_summaries(federations).await? {
+            self.store_summary(summary).await?;
+        }
+
+        self.summary_cache()
+            .summaries
+            .write()
+            .await
+            .retain(|federation_id, _| active.contains(federation_id));
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "DELETE FROM federation_summaries WHERE NOT (federation_id = ANY($1))",
+            &[&active
+                .iter()
+                .map(|federation_id| federation_id.consensus_encode_to_vec())
+                .collect::<Vec<_>>()],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn load_cached_summaries(
+        &self,
+        federation_ids: &[FederationId],
+    ) -> anyhow::Result<Vec<CachedSummary>> {
+        #[derive(Debug, FromRow)]
+        struct SummaryRow {
+            summary: serde_json::Value,
+            updated_at: NaiveDateTime,
+        }
+
+        query::<SummaryRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT summary, updated_at FROM federation_summaries WHERE federation_id = ANY($1)",
+            &[&federation_ids
+                .iter()
+                .map(|federation_id| federation_id.consensus_encode_to_vec())
+                .collect::<Vec<_>>()],
+        )
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(CachedSummary {
+                summary: serde_json::from_value(row.summary)?,
+                updated_at: row.updated_at.and_utc(),
+            })
+        })
+        .collect()
+    }
+
+    async fn store_summary(&self, summary: FederationSummary) -> anyhow::Result<()> {
+        let updated_at = Utc::now();
+        execute(
+            &self.connection().await?,
+            // language=postgresql
+            "INSERT INTO federation_summaries (federation_id, summary, updated_at)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (federation_id) DO UPDATE SET summary    = excluded.summary,
+                                                       updated_at = excluded.updated_at",
+            &[
+                &summary.id.consensus_encode_to_vec(),
+                &serde_json::to_value(&summary)?,
+                &updated_at.naive_utc(),
+            ],
+        )
+        .await?;
+
+        self.summary_cache().summaries.write().await.insert(
+            summary.id,
+            CachedSummary {
+                summary,
+                updated_at,
+            },
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use chrono::{Days, Months, NaiveDate};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::{Timeseries, TimeseriesMetric, TimeseriesResolution};
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct TimeseriesParams {
+    /// Comma separated metrics, any of `volume`, `tx_count`, `assets`,
+    /// `ln_in`, `ln_out`, `peg_ins` and `peg_outs`. Defaults to `volume` and
+    /// `tx_count`.
+    metrics: Option<String>,
+    /// Defaults to `day`, weeks start on Monday
+    #[serde(default)]
+    resolution: TimeseriesResolution,
+}
+
+/// Multiple metrics of a federation aligned to the same time buckets, based on
+/// the estimated session times. Buckets without activity are included with
+/// zero values.
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/timeseries",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), TimeseriesParams),
+    responses((status = 200, description = "Aligned metrics", body = Timeseries))
+)]
+pub(super) async fn get_federation_timeseries(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<TimeseriesParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Timeseries>> {
+    let metrics = match params.metrics.as_deref() {
+        Some(metrics) => parse_metrics(metrics)?,
+        None => vec![TimeseriesMetric::Volume, TimeseriesMetric::TxCount],
+    };
+
+    Ok(state
+        .federation_observer
+        .federation_timeseries(federation_id, &metrics, params.resolution)
+        .await?
+        .into())
+}
+
+/// Totals of all supported metrics within one bucket, amounts in msat
+#[derive(Debug, Clone, FromRow)]
+struct BucketTotals {
+    bucket: NaiveDate,
+    tx_count: i64,
+    volume: i64,
+    ln_in: i64,
+    ln_out: i64,
+    peg_ins: i64,
+    peg_outs: i64,
+}
+
+impl FederationObserver {
+    pub async fn federation_timeseries(
+        &self,
+        federation_id: FederationId,
+        metrics: &[TimeseriesMetric],
+        resolution: TimeseriesResolution,
+    ) -> anyhow::Result<Timeseries> {
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let totals = query::<BucketTotals>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT DATE_TRUNC($2, st.estimated_session_timestamp)::date AS bucket,
+                    COUNT(*)::bigint                                      AS tx_count,
+                    COALESCE(SUM(ti.total), 0)::bigint                    AS volume,
+                    COALESCE(SUM(ti.ln), 0)::bigint                       AS ln_in,
+                    COALESCE(SUM(tout.ln), 0)::bigint                     AS ln_out,
+                    COALESCE(SUM(ti.wallet), 0)::bigint                   AS peg_ins,
+                    COALESCE(SUM(tout.wallet), 0)::bigint                 AS peg_outs
+             FROM transactions t
+                      JOIN session_times st
+                           ON st.federation_id = t.federation_id AND st.session_index = t.session_index
+                      LEFT JOIN LATERAL (SELECT SUM(amount_msat)                               AS total,
+                                                SUM(amount_msat) FILTER (WHERE kind = 'ln')     AS ln,
+                                                SUM(amount_msat) FILTER (WHERE kind = 'wallet') AS wallet
+                                         FROM transaction_inputs
+                                         WHERE federation_id = t.federation_id
+                                           AND txid = t.txid) ti ON TRUE
+                      LEFT JOIN LATERAL (SELECT SUM(amount_msat) FILTER (WHERE kind = 'ln')     AS ln,
+                                                SUM(amount_msat) FILTER (WHERE kind = 'wallet') AS wallet
+                                         FROM transaction_outputs
+                                         WHERE federation_id = t.federation_id
+                                           AND txid = t.txid) tout ON TRUE
+             WHERE t.federation_id = $1
+             GROUP BY bucket
+             ORDER BY bucket",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &resolution_unit(resolution),
+            ],
+        )
+        .await?;
+
+        Ok(assemble_timeseries(&totals, metrics, resolution))
+    }
+}
+
+fn parse_metrics(metrics: &str) -> anyhow::Result<Vec<TimeseriesMetric>> {
+    let mut parsed = Vec::new();
+    for name in metrics
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        let Some(metric) = TimeseriesMetric::from_name(name) else {
+            bail!("Unknown metric {name}");
+        };
+        if !parsed.contains(&metric) {
+            parsed.push(metric);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Unit understood by postgres' `DATE_TRUNC`
+pub(super) fn resolution_unit(resolution: TimeseriesResolution) -> &'static str {
+    match resolution {
+        TimeseriesResolution::Day => "day",
+        TimeseriesResolution::Week => "week",
+        TimeseriesResolution::Month => "month",
+    }
+}
+
+fn next_bucket(bucket: NaiveDate, resolution: TimeseriesResolution) -> NaiveDate {
+    match resolution {
+        TimeseriesResolution::Day => bucket + Days::new(1),
+        TimeseriesResolution::Week => bucket + Days::new(7),
+        TimeseriesResolution::Month => bucket + Months::new(1),
+    }
+}
+
+/// Fills in empty buckets between the first and last one with activity and
+/// picks the requested metrics. Assets are the running sum of peg-ins minus
+/// peg-outs.
+fn assemble_timeseries(
+    totals: &[BucketTotals],
+    metrics: &[TimeseriesMetric],
+    resolution: TimeseriesResolution,
+) -> Timeseries {
+    let by_bucket = totals
+        .iter()
+        .map(|totals| (totals.bucket, totals))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut buckets = Vec::new();
+    if let (Some(first), Some(last)) = (by_bucket.keys().next(), by_bucket.keys().next_back()) {
+        let mut bucket = *first;
+        while bucket <= *last {
+            buckets.push(bucket);
+            bucket = next_bucket(bucket, resolution);
+        }
+    }
+
+    let mut series = metrics
+        .iter()
+        .map(|metric| (*metric, Vec::with_capacity(buckets.len())))
+        .collect::<BTreeMap<_, _>>();
+    let mut assets = 0i64;
+    for bucket in &buckets {
+        let totals = by_bucket.get(bucket);
+        let total =
+            |field: fn(&BucketTotals) -> i64| totals.map(|totals| field(totals)).unwrap_or(0);
+        assets += total(|totals| totals.peg_ins) - total(|totals| totals.peg_outs);
+
+        for (metric, values) in &mut series {
+            let value = match metric {
+                TimeseriesMetric::Volume => total(|totals| totals.volume),
+                TimeseriesMetric::TxCount => total(|totals| totals.tx_count),
+                TimeseriesMetric::Assets => assets,
+                TimeseriesMetric::LnIn => total(|totals| totals.ln_in),
+                TimeseriesMetric::LnOut => total(|totals| totals.ln_out),
+                TimeseriesMetric::PegIns => total(|totals| totals.peg_ins),
+                TimeseriesMetric::PegOuts => total(|totals| totals.peg_outs),
+            };
+            values.push(value.max(0) as u64);
+        }
+    }
+
+    Timeseries {
+        resolution,
+        buckets,
+        series,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use fmo_api_types::{TimeseriesMetric, TimeseriesResolution};
+
+    use super::{assemble_timeseries, parse_metrics, BucketTotals};
+
+    fn totals(bucket: NaiveDate, peg_ins: i64, peg_outs: i64) -> BucketTotals {
+        BucketTotals {
+            bucket,
+            tx_count: 1,
+            volume: peg_ins + peg_outs,
+            ln_in: 0,
+            ln_out: 0,
+            peg_ins,
+            peg_outs,
+        }
+    }
+
+    #[test]
+    fn test_assemble_timeseries() {
+        let day = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let timeseries = assemble_timeseries(
+            &[totals(day(1), 1_000, 0), totals(day(4), 0, 400)],
+            &[TimeseriesMetric::TxCount, TimeseriesMetric::Assets],
+            TimeseriesResolution::Day,
+        );
+
+        assert_eq!(timeseries.buckets, vec![day(1), day(2), day(3), day(4)]);
+        assert_eq!(
+            timeseries.series[&TimeseriesMetric::TxCount],
+            vec![1, 0, 0, 1]
+        );
+        assert_eq!(
+            timeseries.series[&TimeseriesMetric::Assets],
+            vec![1_000, 1_000, 1_000, 600]
+        );
+        assert!(!timeseries.series.contains_key(&TimeseriesMetric::Volume));
+
+        let month = |month| NaiveDate::from_ymd_opt(2024, month, 1).unwrap();
+        let timeseries = assemble_timeseries(
+            &[totals(month(1), 0, 0), totals(month(3), 0, 0)],
+            &[TimeseriesMetric::TxCount],
+            TimeseriesResolution::Month,
+        );
+        assert_eq!(timeseries.buckets, vec![month(1), month(2), month(3)]);
+    }
+
+    #[test]
+    fn test_parse_metrics() {
+        assert_eq!(
+            parse_metrics("volume, ln_out,volume").unwrap(),
+            vec![TimeseriesMetric::Volume, TimeseriesMetric::LnOut]
+        );
+        assert!(parse_metrics("volume,unknown").is_err());
+    }
+}
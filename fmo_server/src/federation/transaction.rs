@@ -1,37 +1,81 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Cursor;
 
 use anyhow::Context;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use chrono::NaiveDate;
 use fedimint_core::config::FederationId;
 use fedimint_core::core::{DynInput, DynOutput, DynUnknown};
-use fedimint_core::encoding::Encodable;
+use fedimint_core::encoding::{Decodable, Encodable};
 use fedimint_core::{Amount, TransactionId};
-use fmo_api_types::FederationActivity;
+use fmo_api_types::{
+    FederationActivity, LnContractReference, Page, TransactionDetails, TransactionIdPage,
+    TransactionItem,
+};
 use postgres_from_row::FromRow;
-use serde::Serialize;
+use serde::Deserialize;
+use utoipa::IntoParams;
 
 use crate::federation::db;
 use crate::federation::observer::FederationObserver;
-use crate::util::{get_decoders, query, query_one, query_value};
+use crate::util::{get_decoders, query, query_value};
 use crate::AppState;
 
+/// Number of transactions returned if the client doesn't specify a limit
+const DEFAULT_TRANSACTION_PAGE_SIZE: u64 = 1_000;
+const MAX_TRANSACTION_PAGE_SIZE: u64 = 10_000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct TransactionListParams {
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/transactions",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), TransactionListParams),
+    responses((status = 200, description = "Transaction ids in consensus order", body = TransactionIdPage))
+)]
 pub(super) async fn list_transactions(
     Path(federation_id): Path<FederationId>,
+    Query(params): Query<TransactionListParams>,
     State(state): State<AppState>,
-) -> crate::error::Result<Json<Vec<TransactionId>>> {
-    Ok(state
+) -> crate::error::Result<Json<Page<TransactionId>>> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_TRANSACTION_PAGE_SIZE)
+        .min(MAX_TRANSACTION_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+
+    let transactions = state
         .federation_observer
-        .federation_transaction_list(federation_id)
-        .await?
-        .into_iter()
-        .map(|tx| tx.txid)
-        .collect::<Vec<_>>()
-        .into())
+        .federation_transaction_list(federation_id, limit, offset)
+        .await?;
+    let total = state
+        .federation_observer
+        .federation_transaction_count(federation_id)
+        .await?;
+
+    Ok(Page {
+        items: transactions.into_iter().map(|tx| tx.txid).collect(),
+        total,
+        offset,
+        limit,
+    }
+    .into())
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/transactions/count",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = u64))
+)]
 pub(super) async fn count_transactions(
     Path(federation_id): Path<FederationId>,
     State(state): State<AppState>,
@@ -43,10 +87,17 @@ pub(super) async fn count_transactions(
         .into())
 }
 
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/transactions/{transaction_id}",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), ("transaction_id" = String, Path, description = "Hex encoded transaction id")),
+    responses((status = 200, description = "Decoded transaction", body = TransactionDetails))
+)]
 pub(super) async fn transaction(
     Path((federation_id, transaction_id)): Path<(FederationId, TransactionId)>,
     State(state): State<AppState>,
-) -> crate::error::Result<Json<DebugTransaction>> {
+) -> crate::error::Result<Json<TransactionDetails>> {
     Ok(state
         .federation_observer
         .transaction_details(federation_id, transaction_id)
@@ -54,14 +105,82 @@ pub(super) async fn transaction(
         .into())
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct TransactionHistogramParams {
+    /// Merge adjacent days so that at most `max_points` entries are returned
+    max_points: Option<usize>,
+    /// IANA time zone name (e.g. `Europe/Berlin`) days are bucketed in,
+    /// defaults to UTC
+    tz: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/transactions/histogram",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), TransactionHistogramParams),
+    responses((status = 200, description = "Activity per day", body = BTreeMap<String, FederationActivity>))
+)]
 pub(super) async fn transaction_histogram(
     Path(federation_id): Path<FederationId>,
+    Query(params): Query<TransactionHistogramParams>,
     State(state): State<AppState>,
 ) -> crate::error::Result<Json<BTreeMap<NaiveDate, FederationActivity>>> {
-    Ok(state
-        .federation_observer
-        .transaction_histogram(federation_id)
-        .await?
+    let histogram = activity_by_date(
+        state
+            .federation_observer
+            .transaction_histogram(federation_id, params.tz.as_deref().unwrap_or("UTC"))
+            .await?,
+    );
+
+    Ok(match params.max_points {
+        Some(max_points) => downsample_histogram(histogram, max_points),
+        None => histogram,
+    }
+    .into())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct NetworkActivityParams {
+    /// Only include the last `days` days, defaults to all history
+    days: Option<u32>,
+    /// Merge adjacent days so that at most `max_points` entries are returned
+    max_points: Option<usize>,
+    /// IANA time zone name (e.g. `Europe/Berlin`) days are bucketed in,
+    /// defaults to UTC
+    tz: Option<String>,
+}
+
+/// Daily transaction count and volume summed over all observed federations
+#[utoipa::path(
+    get,
+    path = "/federations/activity",
+    tag = "federations",
+    params(NetworkActivityParams),
+    responses((status = 200, description = "Network-wide activity per day", body = BTreeMap<String, FederationActivity>))
+)]
+pub(super) async fn network_activity(
+    Query(params): Query<NetworkActivityParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<BTreeMap<NaiveDate, FederationActivity>>> {
+    let histogram = activity_by_date(
+        state
+            .federation_observer
+            .network_histogram(params.days, params.tz.as_deref().unwrap_or("UTC"))
+            .await?,
+    );
+
+    Ok(match params.max_points {
+        Some(max_points) => downsample_histogram(histogram, max_points),
+        None => histogram,
+    }
+    .into())
+}
+
+fn activity_by_date(histogram: Vec<HistogramEntry>) -> BTreeMap<NaiveDate, FederationActivity> {
+    histogram
         .into_iter()
         .map(|histogram_entry| {
             (
@@ -72,14 +191,55 @@ pub(super) async fn transaction_histogram(
                 },
             )
         })
-        .collect::<BTreeMap<_, _>>()
-        .into())
+        .collect()
+}
+
+/// Sums up activity into buckets of equally many days so that at most
+/// `max_points` buckets remain. Each bucket is keyed by its first day, totals
+/// are preserved.
+fn downsample_histogram(
+    histogram: BTreeMap<NaiveDate, FederationActivity>,
+    max_points: usize,
+) -> BTreeMap<NaiveDate, FederationActivity> {
+    let (Some(first_day), Some(last_day)) = (
+        histogram.keys().next().copied(),
+        histogram.keys().next_back().copied(),
+    ) else {
+        return histogram;
+    };
+
+    let days = (last_day - first_day).num_days() as u64 + 1;
+    let max_points = max_points.max(1) as u64;
+    if days <= max_points {
+        return histogram;
+    }
+    let bucket_days = days.div_ceil(max_points);
+
+    let mut downsampled = BTreeMap::<NaiveDate, FederationActivity>::new();
+    for (date, activity) in histogram {
+        let bucket = (date - first_day).num_days() as u64 / bucket_days;
+        let bucket_start = first_day + chrono::Days::new(bucket * bucket_days);
+
+        let entry = downsampled
+            .entry(bucket_start)
+            .or_insert(FederationActivity {
+                num_transactions: 0,
+                amount_transferred: Amount::ZERO,
+            });
+        entry.num_transactions += activity.num_transactions;
+        entry.amount_transferred += activity.amount_transferred;
+    }
+
+    downsampled
 }
 
 impl FederationObserver {
+    /// Lists transactions in consensus order, skipping the first `offset` ones
     pub async fn federation_transaction_list(
         &self,
         federation_id: FederationId,
+        limit: u64,
+        offset: u64,
     ) -> anyhow::Result<Vec<db::Transaction>> {
         self.get_federation(federation_id)
             .await
@@ -87,9 +247,19 @@ impl FederationObserver {
 
         query::<db::Transaction>(
             &self.connection().await?,
-            "SELECT txid, session_index, item_index, data FROM transactions WHERE federation_id = $1",
-            &[&federation_id.consensus_encode_to_vec()]
-        ).await
+            // language=postgresql
+            "SELECT txid, session_index, item_index, data
+             FROM transactions
+             WHERE federation_id = $1
+             ORDER BY session_index, item_index
+             LIMIT $2 OFFSET $3",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &(limit as i64),
+                &(offset as i64),
+            ],
+        )
+        .await
     }
 
     pub async fn federation_transaction_count(
@@ -108,18 +278,96 @@ impl FederationObserver {
         .await? as u64)
     }
 
+    /// Decodes a transaction and enriches its inputs and outputs with the
+    /// amounts and lightning contracts recorded when it was processed
     pub async fn transaction_details(
         &self,
         federation_id: FederationId,
         transaction_id: TransactionId,
-    ) -> anyhow::Result<DebugTransaction> {
+    ) -> anyhow::Result<TransactionDetails> {
+        self.transactions_details(federation_id, &[transaction_id])
+            .await?
+            .remove(&transaction_id)
+            .context("Transaction doesn't exist")
+    }
+
+    /// Like [`FederationObserver::transaction_details`] for several
+    /// transactions of a federation at once, with a fixed number of queries.
+    /// Unknown transactions are missing from the result.
+    pub async fn transactions_details(
+        &self,
+        federation_id: FederationId,
+        transaction_ids: &[TransactionId],
+    ) -> anyhow::Result<HashMap<TransactionId, TransactionDetails>> {
         let cfg = self
             .get_federation(federation_id)
             .await?
             .context("Federation doesn't exist")?
             .config;
 
-        let tx = query_one::<db::Transaction>(&self.connection().await?, "SELECT txid, session_index, item_index, data FROM transactions WHERE federation_id = $1 AND txid = $2", &[&federation_id.consensus_encode_to_vec(), &transaction_id.consensus_encode_to_vec()]).await?;
+        let conn = self.connection().await?;
+        let federation_id_bytes = federation_id.consensus_encode_to_vec();
+        let txids_bytes = transaction_ids
+            .iter()
+            .map(Encodable::consensus_encode_to_vec)
+            .collect::<Vec<_>>();
+
+        let transactions = query::<db::Transaction>(
+            &conn,
+            // language=postgresql
+            "SELECT txid, session_index, item_index, data
+             FROM transactions
+             WHERE federation_id = $1 AND txid = ANY ($2)",
+            &[&federation_id_bytes, &txids_bytes],
+        )
+        .await?;
+
+        let mut input_rows = rows_by_txid(
+            query::<TransactionItemRow>(
+                &conn,
+                // language=postgresql
+                "SELECT ti.txid,
+                        ti.in_index                 AS idx,
+                        ti.kind,
+                        ti.amount_msat,
+                        ti.ln_contract_id,
+                        NULL::text                  AS ln_contract_interaction_kind,
+                        (SELECT fund.txid
+                         FROM transaction_outputs fund
+                         WHERE fund.federation_id = ti.federation_id
+                           AND fund.ln_contract_id = ti.ln_contract_id
+                           AND fund.ln_contract_interaction_kind = 'fund'
+                         LIMIT 1)                   AS funding_txid
+                 FROM transaction_inputs ti
+                 WHERE ti.federation_id = $1 AND ti.txid = ANY ($2)",
+                &[&federation_id_bytes, &txids_bytes],
+            )
+            .await?,
+        );
+
+        let mut output_rows = rows_by_txid(
+            query::<TransactionItemRow>(
+                &conn,
+                // language=postgresql
+                "SELECT tout.txid,
+                        tout.out_index AS idx,
+                        tout.kind,
+                        tout.amount_msat,
+                        tout.ln_contract_id,
+                        tout.ln_contract_interaction_kind,
+                        (SELECT fund.txid
+                         FROM transaction_outputs fund
+                         WHERE fund.federation_id = tout.federation_id
+                           AND fund.ln_contract_id = tout.ln_contract_id
+                           AND fund.ln_contract_interaction_kind = 'fund'
+                           AND fund.txid <> tout.txid
+                         LIMIT 1)      AS funding_txid
+                 FROM transaction_outputs tout
+                 WHERE tout.federation_id = $1 AND tout.txid = ANY ($2)",
+                &[&federation_id_bytes, &txids_bytes],
+            )
+            .await?,
+        );
 
         let decoders = get_decoders(
             cfg.modules
@@ -127,70 +375,98 @@ impl FederationObserver {
                 .map(|(module_instance_id, module_cfg)| (module_instance_id, module_cfg.kind)),
         );
 
-        let inputs = tx
-            .data
-            .inputs
+        transactions
             .into_iter()
-            .map(|input| {
-                let module_instance_id = input.module_instance_id();
-                let undecoded = input
-                    .as_any()
-                    .downcast_ref::<DynUnknown>()
-                    .expect("Shouldn't be decoded yet");
-                decoders
-                    .get(module_instance_id)
-                    .map(|decoder| {
-                        decoder
-                            .decode_complete::<DynInput>(
-                                &mut Cursor::new(&undecoded.0),
-                                undecoded.0.len() as u64,
-                                module_instance_id,
-                                &Default::default(),
-                            )
-                            .expect("decoding failed")
+            .map(|tx| {
+                let inputs = tx
+                    .data
+                    .inputs
+                    .into_iter()
+                    .map(|input| {
+                        let module_instance_id = input.module_instance_id();
+                        let undecoded = input
+                            .as_any()
+                            .downcast_ref::<DynUnknown>()
+                            .expect("Shouldn't be decoded yet");
+                        decoders
+                            .get(module_instance_id)
+                            .map(|decoder| {
+                                decoder
+                                    .decode_complete::<DynInput>(
+                                        &mut Cursor::new(&undecoded.0),
+                                        undecoded.0.len() as u64,
+                                        module_instance_id,
+                                        &Default::default(),
+                                    )
+                                    .expect("decoding failed")
+                            })
+                            .map(|input| format!("{input:?}"))
+                            .unwrap_or_else(|| {
+                                format!("Unknown module, instance id={module_instance_id}")
+                            })
                     })
-                    .map(|input| format!("{input:?}"))
-                    .unwrap_or_else(|| format!("Unknown module, instance id={module_instance_id}"))
-            })
-            .collect::<Vec<_>>();
+                    .collect::<Vec<_>>();
 
-        let outputs = tx
-            .data
-            .outputs
-            .into_iter()
-            .map(|output| {
-                let module_instance_id = output.module_instance_id();
-                let undecoded = output
-                    .as_any()
-                    .downcast_ref::<DynUnknown>()
-                    .expect("Shouldn't be decoded yet");
-                decoders
-                    .get(module_instance_id)
-                    .map(|decoder| {
-                        decoder
-                            .decode_complete::<DynOutput>(
-                                &mut Cursor::new(&undecoded.0),
-                                undecoded.0.len() as u64,
-                                module_instance_id,
-                                &Default::default(),
-                            )
-                            .expect("decoding failed")
+                let outputs = tx
+                    .data
+                    .outputs
+                    .into_iter()
+                    .map(|output| {
+                        let module_instance_id = output.module_instance_id();
+                        let undecoded = output
+                            .as_any()
+                            .downcast_ref::<DynUnknown>()
+                            .expect("Shouldn't be decoded yet");
+                        decoders
+                            .get(module_instance_id)
+                            .map(|decoder| {
+                                decoder
+                                    .decode_complete::<DynOutput>(
+                                        &mut Cursor::new(&undecoded.0),
+                                        undecoded.0.len() as u64,
+                                        module_instance_id,
+                                        &Default::default(),
+                                    )
+                                    .expect("decoding failed")
+                            })
+                            .map(|output| format!("{output:?}"))
+                            .unwrap_or_else(|| {
+                                format!("Unknown module, instance id={module_instance_id}")
+                            })
                     })
-                    .map(|output| format!("{output:?}"))
-                    .unwrap_or_else(|| format!("Unknown module, instance id={module_instance_id}"))
-            })
-            .collect::<Vec<_>>();
+                    .collect::<Vec<_>>();
 
-        Ok(DebugTransaction { inputs, outputs })
+                let txid_bytes = tx.txid.consensus_encode_to_vec();
+                let details = TransactionDetails {
+                    txid: tx.txid,
+                    session_index: tx.session_index as u64,
+                    item_index: tx.item_index as u64,
+                    inputs: transaction_items(
+                        inputs,
+                        input_rows.remove(&txid_bytes).unwrap_or_default(),
+                    )?,
+                    outputs: transaction_items(
+                        outputs,
+                        output_rows.remove(&txid_bytes).unwrap_or_default(),
+                    )?,
+                };
+                Ok((tx.txid, details))
+            })
+            .collect()
     }
 
+    /// Daily transaction counts and volume, with days starting at midnight in
+    /// the time zone `tz`
     pub async fn transaction_histogram(
         &self,
         federation_id: FederationId,
+        tz: &str,
     ) -> anyhow::Result<Vec<HistogramEntry>> {
+        // Session timestamps are stored as UTC without time zone, so they have
+        // to be marked as UTC before converting them to the requested one
         // language=postgresql
         const QUERY: &str = "
-            SELECT DATE(st.estimated_session_timestamp)            AS date,
+            SELECT DATE(st.estimated_session_timestamp AT TIME ZONE 'UTC' AT TIME ZONE $2) AS date,
                    COUNT(DISTINCT t.txid)::bigint                  AS count,
                    COALESCE(SUM(ti.total_input_amount), 0)::bigint AS amount
             FROM transactions t
@@ -216,18 +492,127 @@ impl FederationObserver {
         let histogram = query::<HistogramEntry>(
             &self.connection().await?,
             QUERY,
-            &[&federation_id.consensus_encode_to_vec()],
+            &[&federation_id.consensus_encode_to_vec(), &tz],
         )
-        .await?;
+        .await
+        .with_context(|| format!("Could not bucket transactions by time zone {tz}"))?;
 
         Ok(histogram)
     }
+
+    /// Like [`Self::transaction_histogram`], but summed over all active
+    /// federations in a single query, optionally limited to the last `days`
+    /// days
+    pub async fn network_histogram(
+        &self,
+        days: Option<u32>,
+        tz: &str,
+    ) -> anyhow::Result<Vec<HistogramEntry>> {
+        // language=postgresql
+        const QUERY: &str = "
+            SELECT DATE(st.estimated_session_timestamp AT TIME ZONE 'UTC' AT TIME ZONE $2) AS date,
+                   COUNT(*)::bigint                                AS count,
+                   COALESCE(SUM(ti.total_input_amount), 0)::bigint AS amount
+            FROM transactions t
+                     JOIN
+                 federations f ON t.federation_id = f.federation_id
+                     JOIN
+                 session_times st ON t.session_index = st.session_index AND t.federation_id = st.federation_id
+                     JOIN
+                 (SELECT federation_id,
+                         txid,
+                         SUM(amount_msat) AS total_input_amount
+                  FROM transaction_inputs
+                  GROUP BY txid, federation_id) ti ON t.txid = ti.txid AND t.federation_id = ti.federation_id
+            WHERE f.active
+              AND ($1::integer IS NULL OR
+                   st.estimated_session_timestamp > (NOW() AT TIME ZONE 'UTC') - make_interval(days => $1))
+            GROUP BY date
+            ORDER BY date;
+        ";
+
+        let days = days.map(|days| days.min(i32::MAX as u32) as i32);
+        let histogram = query::<HistogramEntry>(&self.connection().await?, QUERY, &[&days, &tz])
+            .await
+            .with_context(|| format!("Could not bucket transactions by time zone {tz}"))?;
+
+        Ok(histogram)
+    }
+}
+
+/// Input or output as recorded in `transaction_inputs` or
+/// `transaction_outputs`
+#[derive(Debug, FromRow)]
+struct TransactionItemRow {
+    txid: Vec<u8>,
+    idx: i32,
+    kind: String,
+    amount_msat: Option<i64>,
+    ln_contract_id: Option<Vec<u8>>,
+    ln_contract_interaction_kind: Option<String>,
+    funding_txid: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct DebugTransaction {
-    inputs: Vec<String>,
-    outputs: Vec<String>,
+fn rows_by_txid(rows: Vec<TransactionItemRow>) -> HashMap<Vec<u8>, Vec<TransactionItemRow>> {
+    let mut rows_by_txid = HashMap::<_, Vec<_>>::new();
+    for row in rows {
+        rows_by_txid.entry(row.txid.clone()).or_default().push(row);
+    }
+    rows_by_txid
+}
+
+/// Combines the decoded inputs or outputs of a transaction with the data
+/// recorded for them, matched by their index
+fn transaction_items(
+    decoded: Vec<String>,
+    rows: Vec<TransactionItemRow>,
+) -> anyhow::Result<Vec<TransactionItem>> {
+    let mut rows = rows
+        .into_iter()
+        .map(|row| (row.idx as usize, row))
+        .collect::<BTreeMap<_, _>>();
+
+    decoded
+        .into_iter()
+        .enumerate()
+        .map(|(idx, decoded)| {
+            let Some(row) = rows.remove(&idx) else {
+                return Ok(TransactionItem {
+                    kind: "unknown".to_owned(),
+                    amount: None,
+                    ln_contract: None,
+                    decoded,
+                });
+            };
+
+            let ln_contract = row
+                .ln_contract_id
+                .map(|contract_id| {
+                    let funding_txid = row
+                        .funding_txid
+                        .map(|txid| {
+                            TransactionId::consensus_decode_vec(txid, &Default::default())
+                                .context("Invalid transaction id in DB")
+                        })
+                        .transpose()?;
+                    anyhow::Ok(LnContractReference {
+                        contract_id: hex::encode(contract_id),
+                        interaction: row.ln_contract_interaction_kind,
+                        funding_txid,
+                    })
+                })
+                .transpose()?;
+
+            Ok(TransactionItem {
+                kind: row.kind,
+                amount: row
+                    .amount_msat
+                    .map(|amount_msat| Amount::from_msats(amount_msat as u64)),
+                ln_contract,
+                decoded,
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -236,3 +621,65 @@ pub struct HistogramEntry {
     count: i64,
     amount: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::NaiveDate;
+    use fedimint_core::Amount;
+    use fmo_api_types::FederationActivity;
+
+    use super::downsample_histogram;
+
+    fn activity(num_transactions: u64) -> FederationActivity {
+        FederationActivity {
+            num_transactions,
+            amount_transferred: Amount::from_sats(num_transactions * 1000),
+        }
+    }
+
+    #[test]
+    fn test_downsample_histogram() {
+        let first_day = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        // 100 days with a one day gap
+        let histogram = (0..100)
+            .filter(|day| *day != 42)
+            .map(|day| (first_day + chrono::Days::new(day), activity(day + 1)))
+            .collect::<BTreeMap<_, _>>();
+
+        assert_eq!(downsample_histogram(histogram.clone(), 100), histogram);
+
+        let downsampled = downsample_histogram(histogram.clone(), 30);
+        // 4 days per bucket
+        assert_eq!(downsampled.len(), 25);
+        assert_eq!(downsampled.keys().next(), Some(&first_day));
+        assert_eq!(
+            downsampled[&(first_day + chrono::Days::new(4))].num_transactions,
+            5 + 6 + 7 + 8
+        );
+        assert_eq!(
+            downsampled
+                .values()
+                .map(|activity| activity.num_transactions)
+                .sum::<u64>(),
+            histogram
+                .values()
+                .map(|activity| activity.num_transactions)
+                .sum::<u64>()
+        );
+        assert_eq!(
+            downsampled
+                .values()
+                .map(|activity| activity.amount_transferred.msats)
+                .sum::<u64>(),
+            histogram
+                .values()
+                .map(|activity| activity.amount_transferred.msats)
+                .sum::<u64>()
+        );
+
+        assert_eq!(downsample_histogram(histogram, 0).len(), 1);
+        assert!(downsample_histogram(BTreeMap::new(), 10).is_empty());
+    }
+}
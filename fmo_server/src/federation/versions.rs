@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::Json;
+use fedimint_api_client::api::{DynGlobalApi, FederationApiExt};
+use fedimint_core::endpoint_constants::FEDIMINTD_VERSION_ENDPOINT;
+use fedimint_core::module::ApiRequestErased;
+use fedimint_core::PeerId;
+use fmo_api_types::FedimintdVersionCount;
+use futures::future::join_all;
+use postgres_from_row::FromRow;
+
+use crate::federation::observer::FederationObserver;
+use crate::util::query;
+use crate::AppState;
+
+/// Guardians rarely upgrade, asking for their version once an hour is enough
+pub(super) const VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const VERSION_REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Requests the fedimintd version of each guardian, guardians that don't
+/// answer are left out
+pub(super) async fn fetch_fedimintd_versions(
+    api: &DynGlobalApi,
+    peers: impl IntoIterator<Item = PeerId>,
+) -> BTreeMap<PeerId, String> {
+    join_all(peers.into_iter().map(|peer_id| async move {
+        let version = api
+            .request_single_peer(
+                Some(VERSION_REQUEST_TIMEOUT),
+                FEDIMINTD_VERSION_ENDPOINT.to_owned(),
+                ApiRequestErased::default(),
+                peer_id,
+            )
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_value::<String>(json).ok());
+        (peer_id, version)
+    }))
+    .await
+    .into_iter()
+    .filter_map(|(peer_id, version)| Some((peer_id, version?)))
+    .collect()
+}
+
+/// Distribution of fedimintd versions across the guardians of all observed
+/// federations, based on the latest version each guardian reported
+#[utoipa::path(
+    get,
+    path = "/versions",
+    tag = "federations",
+    responses((status = 200, description = "Guardians per fedimintd version, most common first", body = Vec<FedimintdVersionCount>))
+)]
+pub(crate) async fn get_fedimintd_versions(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<FedimintdVersionCount>>> {
+    Ok(state.federation_observer.fedimintd_versions().await?.into())
+}
+
+impl FederationObserver {
+    pub async fn fedimintd_versions(&self) -> anyhow::Result<Vec<FedimintdVersionCount>> {
+        #[derive(Debug, FromRow)]
+        struct VersionCountRow {
+            version: String,
+            guardians: i64,
+            federations: i64,
+        }
+
+        Ok(query::<VersionCountRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "WITH latest_versions AS (SELECT DISTINCT ON (gh.federation_id, gh.guardian_id) gh.federation_id,
+                                                                                            gh.fedimintd_version
+                                     FROM guardian_health gh
+                                              JOIN federations f ON f.federation_id = gh.federation_id
+                                     WHERE f.active
+                                       AND gh.fedimintd_version IS NOT NULL
+                                     ORDER BY gh.federation_id, gh.guardian_id, gh.time DESC)
+             SELECT fedimintd_version                     AS version,
+                    COUNT(*)::bigint                      AS guardians,
+                    COUNT(DISTINCT federation_id)::bigint AS federations
+             FROM latest_versions
+             GROUP BY fedimintd_version
+             ORDER BY guardians DESC, version",
+            &[],
+        )
+        .await?
+        .into_iter()
+        .map(|row| FedimintdVersionCount {
+            version: row.version,
+            guardians: row.guardians as u64,
+            federations: row.federations as u64,
+        })
+        .collect())
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context};
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use fmo_api_types::rating::sort_by_rating_index;
+use fmo_api_types::FederationSummary;
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+
+use crate::federation::db::Federation;
+use crate::federation::observer::FederationObserver;
+use crate::federation::{assign_ratings, RatingParams};
+use crate::util::{execute, query};
+use crate::AppState;
+
+/// Views are named subsets of the observed federations, so one observer
+/// instance can power multiple community frontends that only want to list
+/// their own federations.
+pub fn get_view_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_views))
+        .route("/:name", get(get_view).put(put_view).delete(delete_view))
+        .route("/:name/federations", get(list_view_federations))
+}
+
+#[derive(Debug, Deserialize)]
+struct ViewRequest {
+    federations: Vec<FederationId>,
+}
+
+async fn list_views(
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<BTreeMap<String, Vec<FederationId>>>> {
+    Ok(state.federation_observer.list_views().await?.into())
+}
+
+async fn get_view(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<FederationId>>> {
+    Ok(state
+        .federation_observer
+        .view_federations(&name)
+        .await?
+        .into_iter()
+        .map(|federation| federation.federation_id)
+        .collect::<Vec<_>>()
+        .into())
+}
+
+async fn put_view(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    Json(request): Json<ViewRequest>,
+) -> crate::error::Result<()> {
+    Ok(state
+        .federation_observer
+        .set_view(&name, &request.federations)
+        .await?)
+}
+
+async fn delete_view(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> crate::error::Result<()> {
+    Ok(state.federation_observer.delete_view(&name).await?)
+}
+
+async fn list_view_federations(
+    Path(name): Path<String>,
+    Query(params): Query<RatingParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<FederationSummary>>> {
+    let federations = state.federation_observer.view_federations(&name).await?;
+    let mut federations = state
+        .federation_observer
+        .federation_summaries(federations)
+        .await?;
+    assign_ratings(&state, &mut federations, params.rating);
+    sort_by_rating_index(&mut federations);
+
+    Ok(federations.into())
+}
+
+impl FederationObserver {
+    pub async fn list_views(&self) -> anyhow::Result<BTreeMap<String, Vec<FederationId>>> {
+        #[derive(FromRow)]
+        struct ViewRow {
+            name: String,
+            federation_id: Option<Vec<u8>>,
+        }
+
+        let rows = query::<ViewRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT v.name, vf.federation_id
+             FROM views v
+                      LEFT JOIN view_federations vf ON v.name = vf.view_name
+             ORDER BY v.name",
+            &[],
+        )
+        .await?;
+
+        let mut views = BTreeMap::<String, Vec<FederationId>>::new();
+        for row in rows {
+            let federations = views.entry(row.name).or_default();
+            if let Some(federation_id) = row.federation_id {
+                federations.push(FederationId(bitcoin::hashes::Hash::from_byte_array(
+                    federation_id
+                        .try_into()
+                        .map_err(|_| anyhow::anyhow!("Invalid federation id in DB"))?,
+                )));
+            }
+        }
+
+        Ok(views)
+    }
+
+    pub async fn view_federations(&self, name: &str) -> anyhow::Result<Vec<Federation>> {
+        let conn = self.connection().await?;
+
+        conn.query_opt("SELECT 1 FROM views WHERE name = $1", &[&name])
+            .await?
+            .context("View doesn't exist")?;
+
+        query::<Federation>(
+            &conn,
+            // language=postgresql
+            "SELECT f.*
+             FROM federations f
+                      JOIN view_federations vf ON f.federation_id = vf.federation_id
+             WHERE vf.view_name = $1
+               AND f.active",
+            &[&name],
+        )
+        .await
+    }
+
+    /// Creates the view `name` or replaces its federations if it already
+    /// exists
+    pub async fn set_view(&self, name: &str, federations: &[FederationId]) -> anyhow::Result<()> {
+        ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "View names may only contain alphanumeric characters, '-' and '_'"
+        );
+
+        for &federation_id in federations {
+            self.get_federation(federation_id)
+                .await?
+                .with_context(|| format!("Federation {federation_id} isn't observed"))?;
+        }
+
+        let mut conn = self.connection().await?;
+        let dbtx = conn.transaction().await?;
+
+        execute(
+            &dbtx,
+            "INSERT INTO views VALUES ($1) ON CONFLICT DO NOTHING",
+            &[&name],
+        )
+        .await?;
+        execute(
+            &dbtx,
+            "DELETE FROM view_federations WHERE view_name = $1",
+            &[&name],
+        )
+        .await?;
+        for federation_id in federations {
+            execute(
+                &dbtx,
+                "INSERT INTO view_federations VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                &[&name, &federation_id.consensus_encode_to_vec()],
+            )
+            .await?;
+        }
+
+        dbtx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn delete_view(&self, name: &str) -> anyhow::Result<()> {
+        let deleted = execute(
+            &self.connection().await?,
+            "DELETE FROM views WHERE name = $1",
+            &[&name],
+        )
+        .await?;
+        ensure!(deleted > 0, "View doesn't exist");
+
+        Ok(())
+    }
+}
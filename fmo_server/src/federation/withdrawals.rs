@@ -0,0 +1,1108 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use bitcoin::hashes::Hash;
+use bitcoin::{Address, Txid};
+use chrono::{NaiveDate, NaiveDateTime};
+use deadpool_postgres::{GenericClient, Transaction};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::{Decodable, Encodable};
+use fedimint_core::task::sleep;
+use fedimint_core::{Amount, NumPeers, TransactionId};
+use fmo_api_types::{
+    TimeseriesResolution, Withdrawal, WithdrawalCluster, WithdrawalConcentration,
+    WithdrawalConcentrationPeriod, WithdrawalDestination, WithdrawalStatus, WithdrawalTransaction,
+};
+use postgres_from_row::FromRow;
+use serde::Deserialize;
+use tracing::{info, warn};
+use utoipa::IntoParams;
+
+use crate::federation::observer::FederationObserver;
+use crate::federation::timeseries::resolution_unit;
+use crate::util::{execute, query};
+use crate::AppState;
+
+/// Share of the withdrawn amount a single destination needs to receive for the
+/// federation's withdrawals to be considered concentrated
+const CONCENTRATION_THRESHOLD: f64 = 0.5;
+/// Below this number of withdrawals concentration isn't meaningful
+const MIN_WITHDRAWALS_FOR_CONCENTRATION: u64 = 5;
+const TOP_DESTINATIONS: usize = 10;
+/// How often unconfirmed withdrawal transactions are checked for replacements
+const REPLACEMENT_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// How often payouts to withdrawal destinations are checked for being spent
+const SPEND_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Payouts checked per interval, keeps the load on the chain source low
+const SPEND_CHECK_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct WithdrawalConcentrationParams {
+    /// Only take withdrawals of the last `days` days into account
+    days: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct WithdrawalConcentrationHistoryParams {
+    /// Defaults to `day`, weeks start on Monday
+    #[serde(default)]
+    resolution: TimeseriesResolution,
+}
+
+/// Peg-out requests of a federation, most recent first, with the on-chain
+/// transaction paying them out and its signing and confirmation progress
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/withdrawals",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = [Withdrawal]))
+)]
+pub(super) async fn get_withdrawals(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<Withdrawal>>> {
+    Ok(state
+        .federation_observer
+        .withdrawals(federation_id)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/withdrawals/concentration",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), WithdrawalConcentrationParams),
+    responses((status = 200, description = "Success", body = WithdrawalConcentration))
+)]
+pub(super) async fn get_withdrawal_concentration(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<WithdrawalConcentrationParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<WithdrawalConcentration>> {
+    Ok(state
+        .federation_observer
+        .withdrawal_concentration(federation_id, params.days)
+        .await?
+        .into())
+}
+
+/// Withdrawal concentration of each period with peg-out requests, oldest first
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/withdrawals/concentration/history",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id"), WithdrawalConcentrationHistoryParams),
+    responses((status = 200, description = "Success", body = [WithdrawalConcentrationPeriod]))
+)]
+pub(super) async fn get_withdrawal_concentration_history(
+    Path(federation_id): Path<FederationId>,
+    Query(params): Query<WithdrawalConcentrationHistoryParams>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<WithdrawalConcentrationPeriod>>> {
+    Ok(state
+        .federation_observer
+        .withdrawal_concentration_history(federation_id, params.resolution)
+        .await?
+        .into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/federations/{federation_id}/withdrawals/transactions",
+    tag = "federations",
+    params(("federation_id" = String, Path, description = "Hex encoded federation id")),
+    responses((status = 200, description = "Success", body = [WithdrawalTransaction]))
+)]
+pub(super) async fn get_withdrawal_transactions(
+    Path(federation_id): Path<FederationId>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<WithdrawalTransaction>>> {
+    Ok(state
+        .federation_observer
+        .withdrawal_transactions(federation_id)
+        .await?
+        .into())
+}
+
+impl FederationObserver {
+    pub async fn withdrawals(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<Withdrawal>> {
+        #[derive(Debug, FromRow)]
+        struct WithdrawalRow {
+            txid: Vec<u8>,
+            session_index: i32,
+            estimated_session_timestamp: Option<NaiveDateTime>,
+            address: String,
+            amount_msat: i64,
+            on_chain_txid: Option<Vec<u8>>,
+            signatures: i64,
+            confirmation_height: Option<i32>,
+        }
+
+        let federation = self
+            .get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+        let signature_threshold =
+            NumPeers::from(federation.config.global.api_endpoints.len()).threshold() as u64;
+
+        let rows = query::<WithdrawalRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT wwa.txid,
+                    wwa.session_index,
+                    st.estimated_session_timestamp,
+                    wwa.address,
+                    tout.amount_msat,
+                    wwt.on_chain_txid,
+                    wwt.confirmation_height,
+                    (SELECT COUNT(*)
+                     FROM wallet_withdrawal_signatures wws
+                     WHERE wws.on_chain_txid = wwt.on_chain_txid)::bigint AS signatures
+             FROM wallet_withdrawal_addresses wwa
+                      JOIN transaction_outputs tout ON wwa.federation_id = tout.federation_id AND
+                                                       wwa.txid = tout.txid AND
+                                                       wwa.out_index = tout.out_index
+                      LEFT JOIN session_times st ON wwa.federation_id = st.federation_id AND
+                                                    wwa.session_index = st.session_index
+                      LEFT JOIN LATERAL (SELECT on_chain_txid, confirmation_height
+                                         FROM wallet_withdrawal_transactions
+                                         WHERE federation_id = wwa.federation_id
+                                           AND federation_txid = wwa.txid
+                                         ORDER BY replaced_by IS NULL DESC
+                                         LIMIT 1) wwt ON TRUE
+             WHERE wwa.federation_id = $1
+             ORDER BY wwa.session_index DESC, wwa.item_index DESC",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let signatures = row.signatures as u64;
+                let confirmation_height = row.confirmation_height.map(|height| height as u32);
+                Ok(Withdrawal {
+                    federation_txid: TransactionId::consensus_decode_vec(
+                        row.txid,
+                        &Default::default(),
+                    )
+                    .context("Invalid transaction id in DB")?,
+                    session_index: row.session_index as u64,
+                    estimated_time: row.estimated_session_timestamp.map(|time| time.and_utc()),
+                    address: Address::from_str(&row.address)?,
+                    amount: Amount::from_msats(row.amount_msat as u64),
+                    status: withdrawal_status(
+                        row.on_chain_txid.is_some(),
+                        signatures,
+                        signature_threshold,
+                        confirmation_height,
+                    ),
+                    on_chain_txid: row
+                        .on_chain_txid
+                        .map(|txid| Txid::from_slice(&txid))
+                        .transpose()?,
+                    signatures,
+                    signature_threshold,
+                    confirmation_height,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn withdrawal_concentration(
+        &self,
+        federation_id: FederationId,
+        days: Option<u32>,
+    ) -> anyhow::Result<WithdrawalConcentration> {
+        #[derive(Debug, FromRow)]
+        struct WithdrawalDestinationRow {
+            address: String,
+            withdrawal_count: i64,
+            amount_msat: i64,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let destinations = query::<WithdrawalDestinationRow>(
+            &conn,
+            // language=postgresql
+            "SELECT wwa.address,
+                    COUNT(*)::bigint                            AS withdrawal_count,
+                    COALESCE(SUM(tout.amount_msat), 0)::bigint  AS amount_msat
+             FROM wallet_withdrawal_addresses wwa
+                      JOIN transaction_outputs tout ON wwa.federation_id = tout.federation_id AND
+                                                       wwa.txid = tout.txid AND
+                                                       wwa.out_index = tout.out_index
+                      LEFT JOIN session_times st ON wwa.federation_id = st.federation_id AND
+                                                    wwa.session_index = st.session_index
+             WHERE wwa.federation_id = $1
+               AND ($2::bigint IS NULL OR
+                    st.estimated_session_timestamp > NOW() - INTERVAL '1 day' * $2::bigint)
+             GROUP BY wwa.address
+             ORDER BY amount_msat DESC",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &days.map(i64::from),
+            ],
+        )
+        .await?;
+        let spends = withdrawal_spends(&conn, federation_id).await?;
+
+        let destination_amounts = destinations
+            .iter()
+            .map(|destination| {
+                (
+                    destination.address.clone(),
+                    destination.withdrawal_count as u64,
+                    destination.amount_msat as u64,
+                )
+            })
+            .collect::<Vec<_>>();
+        let stats = concentration_stats(
+            &destination_amounts
+                .iter()
+                .map(|(_, withdrawal_count, amount_msat)| (*withdrawal_count, *amount_msat))
+                .collect::<Vec<_>>(),
+        );
+        let clusters = cluster_destinations(&destination_amounts, &spends);
+        let cluster_stats = concentration_stats(
+            &clusters
+                .iter()
+                .map(|cluster| (cluster.withdrawal_count, cluster.amount_msat))
+                .collect::<Vec<_>>(),
+        );
+
+        let top_destinations = destinations
+            .into_iter()
+            .take(TOP_DESTINATIONS)
+            .map(|destination| {
+                Ok(WithdrawalDestination {
+                    address: Address::from_str(&destination.address)?,
+                    withdrawal_count: destination.withdrawal_count as u64,
+                    amount: Amount::from_msats(destination.amount_msat as u64),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let top_clusters = clusters
+            .into_iter()
+            .take(TOP_DESTINATIONS)
+            .map(|cluster| {
+                Ok(WithdrawalCluster {
+                    addresses: cluster
+                        .addresses
+                        .iter()
+                        .map(|address| Address::from_str(address))
+                        .collect::<Result<_, _>>()?,
+                    withdrawal_count: cluster.withdrawal_count,
+                    amount: Amount::from_msats(cluster.amount_msat),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(WithdrawalConcentration {
+            withdrawal_count: stats.withdrawal_count,
+            total_amount: Amount::from_msats(stats.total_amount_msat),
+            destination_count: stats.destination_count,
+            top_destination_share: stats.top_destination_share,
+            hhi: stats.hhi,
+            effective_destinations: stats.effective_destinations,
+            cluster_count: cluster_stats.destination_count,
+            top_cluster_share: cluster_stats.top_destination_share,
+            cluster_hhi: cluster_stats.hhi,
+            effective_clusters: cluster_stats.effective_destinations,
+            concentrated: cluster_stats.concentrated,
+            top_destinations,
+            top_clusters,
+        })
+    }
+
+    pub async fn withdrawal_concentration_history(
+        &self,
+        federation_id: FederationId,
+        resolution: TimeseriesResolution,
+    ) -> anyhow::Result<Vec<WithdrawalConcentrationPeriod>> {
+        #[derive(Debug, FromRow)]
+        struct PeriodDestinationRow {
+            bucket: NaiveDate,
+            address: String,
+            withdrawal_count: i64,
+            amount_msat: i64,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let conn = self.connection().await?;
+        let rows = query::<PeriodDestinationRow>(
+            &conn,
+            // language=postgresql
+            "SELECT DATE_TRUNC($2, st.estimated_session_timestamp)::date AS bucket,
+                    wwa.address,
+                    COUNT(*)::bigint                                      AS withdrawal_count,
+                    COALESCE(SUM(tout.amount_msat), 0)::bigint            AS amount_msat
+             FROM wallet_withdrawal_addresses wwa
+                      JOIN transaction_outputs tout ON wwa.federation_id = tout.federation_id AND
+                                                       wwa.txid = tout.txid AND
+                                                       wwa.out_index = tout.out_index
+                      JOIN session_times st ON wwa.federation_id = st.federation_id AND
+                                               wwa.session_index = st.session_index
+             WHERE wwa.federation_id = $1
+             GROUP BY bucket, wwa.address
+             ORDER BY bucket",
+            &[
+                &federation_id.consensus_encode_to_vec(),
+                &resolution_unit(resolution),
+            ],
+        )
+        .await?;
+        let spends = withdrawal_spends(&conn, federation_id).await?;
+
+        let mut periods = BTreeMap::<NaiveDate, Vec<(String, u64, u64)>>::new();
+        for row in rows {
+            periods.entry(row.bucket).or_default().push((
+                row.address,
+                row.withdrawal_count as u64,
+                row.amount_msat as u64,
+            ));
+        }
+
+        Ok(periods
+            .into_iter()
+            .map(|(start, destinations)| {
+                let clusters = cluster_destinations(&destinations, &spends);
+                let stats = concentration_stats(
+                    &clusters
+                        .iter()
+                        .map(|cluster| (cluster.withdrawal_count, cluster.amount_msat))
+                        .collect::<Vec<_>>(),
+                );
+                WithdrawalConcentrationPeriod {
+                    start,
+                    withdrawal_count: stats.withdrawal_count,
+                    total_amount: Amount::from_msats(stats.total_amount_msat),
+                    destination_count: destinations.len() as u64,
+                    cluster_count: stats.destination_count,
+                    top_cluster_share: stats.top_destination_share,
+                    cluster_hhi: stats.hhi,
+                    effective_clusters: stats.effective_destinations,
+                    concentrated: stats.concentrated,
+                }
+            })
+            .collect())
+    }
+
+    /// On-chain peg-out transactions of a federation with their replacements,
+    /// ordered by when the guardians started signing them
+    pub async fn withdrawal_transactions(
+        &self,
+        federation_id: FederationId,
+    ) -> anyhow::Result<Vec<WithdrawalTransaction>> {
+        #[derive(Debug, FromRow)]
+        struct WithdrawalTransactionRow {
+            on_chain_txid: Vec<u8>,
+            federation_txid: Option<Vec<u8>>,
+            signatures: i64,
+            confirmation_height: Option<i32>,
+            replaced_by: Option<Vec<u8>>,
+        }
+
+        self.get_federation(federation_id)
+            .await?
+            .context("Federation doesn't exist")?;
+
+        let rows = query::<WithdrawalTransactionRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT wwt.on_chain_txid,
+                    wwt.federation_txid,
+                    wwt.confirmation_height,
+                    wwt.replaced_by,
+                    COUNT(wws.peer_id)::bigint AS signatures
+             FROM wallet_withdrawal_transactions wwt
+                      LEFT JOIN wallet_withdrawal_signatures wws ON wwt.on_chain_txid = wws.on_chain_txid
+             WHERE wwt.federation_id = $1
+             GROUP BY wwt.on_chain_txid
+             ORDER BY MIN(wws.session_index), MIN(wws.item_index)",
+            &[&federation_id.consensus_encode_to_vec()],
+        )
+        .await?;
+
+        let replacements = rows
+            .iter()
+            .filter_map(|row| Some((row.on_chain_txid.clone(), row.replaced_by.clone()?)))
+            .collect::<BTreeMap<_, _>>();
+
+        rows.into_iter()
+            .map(|row| {
+                let replacement_chain = replacement_chain(&replacements, &row.on_chain_txid)
+                    .into_iter()
+                    .map(|txid| Ok(Txid::from_slice(&txid)?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+
+                Ok(WithdrawalTransaction {
+                    on_chain_txid: Txid::from_slice(&row.on_chain_txid)?,
+                    federation_txid: row
+                        .federation_txid
+                        .map(|txid| {
+                            TransactionId::consensus_decode_vec(txid, &Default::default())
+                                .context("Invalid transaction id in DB")
+                        })
+                        .transpose()?,
+                    signatures: row.signatures as u64,
+                    confirmation_height: row.confirmation_height.map(|height| height as u32),
+                    replaced_by: replacement_chain.first().copied(),
+                    replacement_chain,
+                })
+            })
+            .collect()
+    }
+
+    /// Records the inputs and outputs of a peg-out transaction fetched from
+    /// the chain source. If it spends inputs of another withdrawal transaction
+    /// it replaced that one, e.g. because the guardians bumped its fee.
+    pub(super) async fn record_withdrawal_transaction(
+        dbtx: &Transaction<'_>,
+        on_chain_txid: &[u8],
+        tx: &esplora_client::Transaction,
+        network: bitcoin::Network,
+    ) -> Result<(), tokio_postgres::Error> {
+        // A replaced transaction can be recorded again when its session gets
+        // replayed, its inputs have to stay with the replacement
+        let replaced = dbtx
+            .query_one(
+                // language=postgresql
+                "SELECT replaced_by IS NOT NULL AS replaced
+                 FROM wallet_withdrawal_transactions
+                 WHERE on_chain_txid = $1",
+                &[&on_chain_txid],
+            )
+            .await?
+            .get::<_, bool>("replaced");
+
+        if !replaced {
+            for input in &tx.input {
+                let prev_out_txid =
+                    TransactionId::from_str(input.previous_output.txid.to_string().as_str())
+                        .expect("Invalid txid")
+                        .consensus_encode_to_vec();
+                let prev_out_vout = input.previous_output.vout as i32;
+
+                let conflicting = dbtx
+                    .query_opt(
+                        // language=postgresql
+                        "SELECT on_chain_txid
+                         FROM wallet_withdrawal_transaction_inputs
+                         WHERE previous_output_txid = $1
+                           AND previous_output_vout = $2
+                           AND on_chain_txid <> $3",
+                        &[&prev_out_txid, &prev_out_vout, &on_chain_txid],
+                    )
+                    .await?;
+                if let Some(conflicting) = conflicting {
+                    let replaced_txid = conflicting.get::<_, Vec<u8>>("on_chain_txid");
+                    Self::record_withdrawal_replacement(dbtx, &replaced_txid, on_chain_txid)
+                        .await?;
+                }
+
+                dbtx.execute(
+                    // language=postgresql
+                    "INSERT INTO wallet_withdrawal_transaction_inputs
+                     VALUES ($1, $2, $3)
+                     ON CONFLICT (previous_output_txid, previous_output_vout) DO UPDATE
+                         SET on_chain_txid = excluded.on_chain_txid",
+                    &[&prev_out_txid, &prev_out_vout, &on_chain_txid],
+                )
+                .await?;
+            }
+        }
+
+        for (out_idx, output) in tx.output.iter().enumerate() {
+            let address = bitcoin::Address::from_script(
+                bitcoin::Script::from_bytes(output.script_pubkey.as_bytes()),
+                network,
+            )
+            .expect("Invalid bitcoin address");
+
+            dbtx.execute(
+                "INSERT INTO wallet_withdrawal_transaction_outputs VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+                &[
+                    &on_chain_txid,
+                    &(out_idx as i32),
+                    &address.to_string(),
+                    &((output.value.to_sat() as i64) * 1000),
+                ],
+            )
+            .await?;
+
+            // update federation_txid if we found a matching withdrawal address
+            dbtx.execute(
+                "
+                UPDATE wallet_withdrawal_transactions
+                SET federation_txid = (
+                    SELECT txid
+                    FROM wallet_withdrawal_addresses wwa
+                    WHERE address = $1
+                      AND NOT EXISTS (
+                        SELECT *
+                        FROM wallet_withdrawal_transactions wwt
+                        WHERE wwa.txid = wwt.federation_txid
+                      )
+                    -- if address reuse, assume earliest withdrawal request first
+                    ORDER BY session_index, item_index
+                    LIMIT 1
+                )
+                WHERE on_chain_txid = $2
+                  AND federation_txid IS NULL
+                ",
+                &[&address.to_string(), &on_chain_txid],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Marks `replaced` as replaced by `replacement`, which pays out the same
+    /// withdrawal
+    async fn record_withdrawal_replacement(
+        dbtx: &Transaction<'_>,
+        replaced: &[u8],
+        replacement: &[u8],
+    ) -> Result<(), tokio_postgres::Error> {
+        dbtx.execute(
+            // language=postgresql
+            "UPDATE wallet_withdrawal_transactions SET replaced_by = $2 WHERE on_chain_txid = $1",
+            &[&replaced, &replacement],
+        )
+        .await?;
+        dbtx.execute(
+            // language=postgresql
+            "UPDATE wallet_withdrawal_transactions replacement
+             SET federation_txid = replaced.federation_txid
+             FROM wallet_withdrawal_transactions replaced
+             WHERE replacement.on_chain_txid = $2
+               AND replaced.on_chain_txid = $1
+               AND replacement.federation_txid IS NULL",
+            &[&replaced, &replacement],
+        )
+        .await?;
+        info!(
+            replaced = hex::encode(replaced),
+            replacement = hex::encode(replacement),
+            "Withdrawal transaction was replaced"
+        );
+
+        Ok(())
+    }
+
+    /// Periodically asks the chain source about unconfirmed withdrawal
+    /// transactions, recording confirmations and replacements the guardians
+    /// didn't sign through consensus
+    pub(super) async fn check_withdrawal_replacements(self) {
+        loop {
+            if let Err(e) = self.check_withdrawal_replacements_inner().await {
+                warn!("Error while checking withdrawal replacements: {e:?}");
+            }
+            sleep(REPLACEMENT_CHECK_INTERVAL).await;
+        }
+    }
+
+    async fn check_withdrawal_replacements_inner(&self) -> anyhow::Result<()> {
+        #[derive(Debug, FromRow)]
+        struct UnconfirmedWithdrawalRow {
+            on_chain_txid: Vec<u8>,
+            federation_id: Vec<u8>,
+            previous_output_txid: Option<Vec<u8>>,
+            previous_output_vout: Option<i32>,
+            network: Option<String>,
+        }
+
+        let unconfirmed = query::<UnconfirmedWithdrawalRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT wwt.on_chain_txid, wwt.federation_id, wwti.previous_output_txid, wwti.previous_output_vout, f.network
+             FROM wallet_withdrawal_transactions wwt
+                      JOIN federations f ON f.federation_id = wwt.federation_id
+                      LEFT JOIN LATERAL (SELECT previous_output_txid, previous_output_vout
+                                         FROM wallet_withdrawal_transaction_inputs
+                                         WHERE on_chain_txid = wwt.on_chain_txid
+                                         LIMIT 1) wwti ON TRUE
+             WHERE wwt.confirmation_height IS NULL
+               AND wwt.replaced_by IS NULL",
+            &[],
+        )
+        .await?;
+
+        for withdrawal in unconfirmed {
+            let network = withdrawal
+                .network
+                .as_deref()
+                .map(bitcoin::Network::from_str)
+                .transpose()?
+                .unwrap_or(bitcoin::Network::Bitcoin);
+            let Ok(esplora) = self.esplora_client(network) else {
+                continue;
+            };
+            let txid = esplora_txid(&withdrawal.on_chain_txid)?;
+
+            if esplora.get_tx(&txid).await?.is_some() {
+                let status = esplora.get_tx_status(&txid).await?;
+                if let (true, Some(block_height)) = (status.confirmed, status.block_height) {
+                    execute(
+                        &self.connection().await?,
+                        // language=postgresql
+                        "UPDATE wallet_withdrawal_transactions
+                         SET confirmation_height = $2
+                         WHERE on_chain_txid = $1",
+                        &[&withdrawal.on_chain_txid, &(block_height as i32)],
+                    )
+                    .await?;
+                }
+                continue;
+            }
+
+            // The transaction isn't known (anymore), if one of its inputs was
+            // spent by another transaction that one replaced it
+            let (Some(prev_txid), Some(prev_vout)) = (
+                withdrawal.previous_output_txid,
+                withdrawal.previous_output_vout,
+            ) else {
+                continue;
+            };
+            let Some(spender) = esplora
+                .get_output_status(&esplora_txid(&prev_txid)?, prev_vout as u64)
+                .await?
+                .and_then(|output_status| output_status.txid)
+                .filter(|spender| *spender != txid)
+            else {
+                continue;
+            };
+
+            let replacement = esplora.get_tx_no_opt(&spender).await?;
+            let replacement_txid = TransactionId::from_str(spender.to_string().as_str())
+                .expect("Invalid txid")
+                .consensus_encode_to_vec();
+
+            let mut conn = self.connection().await?;
+            let dbtx = conn.transaction().await?;
+            dbtx.execute(
+                // language=postgresql
+                "INSERT INTO wallet_withdrawal_transactions (on_chain_txid, federation_id)
+                 VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                &[&replacement_txid, &withdrawal.federation_id],
+            )
+            .await?;
+            Self::record_withdrawal_transaction(&dbtx, &replacement_txid, &replacement, network)
+                .await?;
+            dbtx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Periodically asks the chain source which transactions spent the
+    /// payouts to withdrawal destinations, used to cluster destinations
+    /// belonging to the same wallet. Unspent payouts are checked again daily.
+    pub(super) async fn check_withdrawal_spends(self) {
+        loop {
+            if let Err(e) = self.check_withdrawal_spends_inner().await {
+                warn!("Error while checking withdrawal spends: {e:?}");
+            }
+            sleep(SPEND_CHECK_INTERVAL).await;
+        }
+    }
+
+    async fn check_withdrawal_spends_inner(&self) -> anyhow::Result<()> {
+        #[derive(Debug, FromRow)]
+        struct UncheckedPayoutRow {
+            on_chain_txid: Vec<u8>,
+            on_chain_vout: i32,
+            network: Option<String>,
+        }
+
+        let unchecked = query::<UncheckedPayoutRow>(
+            &self.connection().await?,
+            // language=postgresql
+            "SELECT wwto.on_chain_txid, wwto.on_chain_vout, f.network
+             FROM wallet_withdrawal_transaction_outputs wwto
+                      JOIN wallet_withdrawal_transactions wwt ON wwt.on_chain_txid = wwto.on_chain_txid
+                      JOIN federations f ON f.federation_id = wwt.federation_id
+                      LEFT JOIN wallet_withdrawal_output_spends wwos
+                                ON wwos.on_chain_txid = wwto.on_chain_txid AND
+                                   wwos.on_chain_vout = wwto.on_chain_vout
+             WHERE wwt.confirmation_height IS NOT NULL
+               AND EXISTS (SELECT 1
+                           FROM wallet_withdrawal_addresses wwa
+                           WHERE wwa.federation_id = wwt.federation_id
+                             AND wwa.address = wwto.address)
+               AND (wwos.on_chain_txid IS NULL OR
+                    (wwos.spending_txid IS NULL AND wwos.checked_at < NOW() - INTERVAL '1 day'))
+             ORDER BY wwos.checked_at NULLS FIRST
+             LIMIT $1",
+            &[&SPEND_CHECK_BATCH_SIZE],
+        )
+        .await?;
+
+        for payout in unchecked {
+            let network = payout
+                .network
+                .as_deref()
+                .map(bitcoin::Network::from_str)
+                .transpose()?
+                .unwrap_or(bitcoin::Network::Bitcoin);
+            let Ok(esplora) = self.esplora_client(network) else {
+                continue;
+            };
+
+            let spending_txid = esplora
+                .get_output_status(
+                    &esplora_txid(&payout.on_chain_txid)?,
+                    payout.on_chain_vout as u64,
+                )
+                .await?
+                .and_then(|output_status| output_status.txid)
+                .map(|spender| {
+                    TransactionId::from_str(spender.to_string().as_str())
+                        .expect("Invalid txid")
+                        .consensus_encode_to_vec()
+                });
+
+            execute(
+                &self.connection().await?,
+                // language=postgresql
+                "INSERT INTO wallet_withdrawal_output_spends
+                 VALUES ($1, $2, $3, NOW())
+                 ON CONFLICT (on_chain_txid, on_chain_vout) DO UPDATE
+                     SET spending_txid = excluded.spending_txid,
+                         checked_at    = excluded.checked_at",
+                &[&payout.on_chain_txid, &payout.on_chain_vout, &spending_txid],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Destination addresses of a federation's peg-outs with the transactions
+/// that spent the payouts to them
+async fn withdrawal_spends(
+    conn: &impl GenericClient,
+    federation_id: FederationId,
+) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    #[derive(Debug, FromRow)]
+    struct SpendRow {
+        address: String,
+        spending_txid: Vec<u8>,
+    }
+
+    Ok(query::<SpendRow>(
+        conn,
+        // language=postgresql
+        "SELECT DISTINCT wwto.address, wwos.spending_txid
+         FROM wallet_withdrawal_output_spends wwos
+                  JOIN wallet_withdrawal_transaction_outputs wwto
+                       ON wwto.on_chain_txid = wwos.on_chain_txid AND
+                          wwto.on_chain_vout = wwos.on_chain_vout
+                  JOIN wallet_withdrawal_transactions wwt ON wwt.on_chain_txid = wwos.on_chain_txid
+         WHERE wwt.federation_id = $1
+           AND wwos.spending_txid IS NOT NULL",
+        &[&federation_id.consensus_encode_to_vec()],
+    )
+    .await?
+    .into_iter()
+    .map(|row| (row.address, row.spending_txid))
+    .collect())
+}
+
+/// Converts a transaction id the way it's encoded in withdrawal tables to the
+/// one used by the chain source
+fn esplora_txid(txid: &[u8]) -> anyhow::Result<esplora_client::Txid> {
+    let txid = TransactionId::consensus_decode_vec(txid.to_vec(), &Default::default())
+        .context("Invalid transaction id in DB")?;
+    Ok(esplora_client::Txid::from_str(&txid.to_string())?)
+}
+
+fn withdrawal_status(
+    has_on_chain_transaction: bool,
+    signatures: u64,
+    signature_threshold: u64,
+    confirmation_height: Option<u32>,
+) -> WithdrawalStatus {
+    if confirmation_height.is_some() {
+        WithdrawalStatus::Confirmed
+    } else if !has_on_chain_transaction {
+        WithdrawalStatus::Requested
+    } else if signatures < signature_threshold {
+        WithdrawalStatus::Signing
+    } else {
+        WithdrawalStatus::Broadcast
+    }
+}
+
+/// Concentration metrics of [`WithdrawalConcentration`] without the listed
+/// destinations
+#[derive(Debug, PartialEq)]
+struct ConcentrationStats {
+    withdrawal_count: u64,
+    total_amount_msat: u64,
+    destination_count: u64,
+    top_destination_share: f64,
+    hhi: f64,
+    effective_destinations: f64,
+    concentrated: bool,
+}
+
+/// Computes the concentration metrics from the number of withdrawals and the
+/// amount withdrawn per destination, given as `(withdrawal_count,
+/// amount_msat)` in any order
+fn concentration_stats(destinations: &[(u64, u64)]) -> ConcentrationStats {
+    let withdrawal_count = destinations.iter().map(|(count, _)| count).sum::<u64>();
+    let total_amount_msat = destinations.iter().map(|(_, amount)| amount).sum::<u64>();
+
+    let shares = destinations
+        .iter()
+        .map(|(_, amount_msat)| {
+            if total_amount_msat == 0 {
+                0.0
+            } else {
+                *amount_msat as f64 / total_amount_msat as f64
+            }
+        })
+        .collect::<Vec<_>>();
+    let top_destination_share = shares.iter().copied().fold(0.0, f64::max);
+    let hhi = shares.iter().map(|share| share * share).sum::<f64>();
+
+    ConcentrationStats {
+        withdrawal_count,
+        total_amount_msat,
+        destination_count: destinations.len() as u64,
+        top_destination_share,
+        hhi,
+        effective_destinations: if hhi == 0.0 { 0.0 } else { 1.0 / hhi },
+        concentrated: withdrawal_count >= MIN_WITHDRAWALS_FOR_CONCENTRATION
+            && top_destination_share > CONCENTRATION_THRESHOLD,
+    }
+}
+
+/// Destinations that likely belong to the same wallet
+#[derive(Debug, PartialEq)]
+struct DestinationCluster<A> {
+    addresses: Vec<A>,
+    withdrawal_count: u64,
+    amount_msat: u64,
+}
+
+/// Groups destinations, given as `(address, withdrawal_count, amount_msat)`,
+/// whose payouts were spent by the same transaction, given as `(address,
+/// spending_txid)`. Spending several outputs together requires controlling all
+/// of them (common-input-ownership heuristic). Clusters are ordered by
+/// withdrawn amount, largest first.
+fn cluster_destinations<A: Ord + Clone, S: Ord>(
+    destinations: &[(A, u64, u64)],
+    spends: &[(A, S)],
+) -> Vec<DestinationCluster<A>> {
+    fn root<A: Ord + Clone>(parents: &BTreeMap<A, A>, address: &A) -> A {
+        let mut root = address;
+        while let Some(parent) = parents.get(root) {
+            root = parent;
+        }
+        root.clone()
+    }
+
+    let mut spent_together = BTreeMap::<&S, Vec<&A>>::new();
+    for (address, spending_txid) in spends {
+        spent_together
+            .entry(spending_txid)
+            .or_default()
+            .push(address);
+    }
+
+    let mut parents = BTreeMap::new();
+    for addresses in spent_together.values() {
+        let first = root(&parents, addresses[0]);
+        for address in &addresses[1..] {
+            let other = root(&parents, address);
+            if other != first {
+                parents.insert(other, first.clone());
+            }
+        }
+    }
+
+    let mut clusters = BTreeMap::<A, DestinationCluster<A>>::new();
+    for (address, withdrawal_count, amount_msat) in destinations {
+        let cluster =
+            clusters
+                .entry(root(&parents, address))
+                .or_insert_with(|| DestinationCluster {
+                    addresses: vec![],
+                    withdrawal_count: 0,
+                    amount_msat: 0,
+                });
+        cluster.addresses.push(address.clone());
+        cluster.withdrawal_count += withdrawal_count;
+        cluster.amount_msat += amount_msat;
+    }
+
+    let mut clusters = clusters.into_values().collect::<Vec<_>>();
+    clusters.sort_by(|a, b| b.amount_msat.cmp(&a.amount_msat));
+    clusters
+}
+
+/// Follows `replaced_by` links starting at `txid`, returning the replacements
+/// in order. Stops at cycles, which shouldn't exist but would otherwise loop
+/// forever.
+fn replacement_chain<T: Ord + Clone>(replaced_by: &BTreeMap<T, T>, txid: &T) -> Vec<T> {
+    let mut visited = BTreeSet::from([txid.clone()]);
+    let mut chain = Vec::new();
+    let mut current = txid;
+    while let Some(replacement) = replaced_by.get(current) {
+        if !visited.insert(replacement.clone()) {
+            break;
+        }
+        chain.push(replacement.clone());
+        current = replacement;
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fmo_api_types::WithdrawalStatus;
+
+    use super::{
+        cluster_destinations, concentration_stats, replacement_chain, withdrawal_status,
+        ConcentrationStats, DestinationCluster,
+    };
+
+    #[test]
+    fn test_replacement_chain() {
+        let replaced_by = BTreeMap::from([("a", "b"), ("b", "c"), ("x", "y"), ("y", "x")]);
+
+        assert_eq!(replacement_chain(&replaced_by, &"a"), vec!["b", "c"]);
+        assert_eq!(replacement_chain(&replaced_by, &"b"), vec!["c"]);
+        assert!(replacement_chain(&replaced_by, &"c").is_empty());
+        assert_eq!(replacement_chain(&replaced_by, &"x"), vec!["y"]);
+    }
+
+    #[test]
+    fn test_withdrawal_status() {
+        assert_eq!(
+            withdrawal_status(false, 0, 3, None),
+            WithdrawalStatus::Requested
+        );
+        assert_eq!(
+            withdrawal_status(true, 2, 3, None),
+            WithdrawalStatus::Signing
+        );
+        assert_eq!(
+            withdrawal_status(true, 3, 3, None),
+            WithdrawalStatus::Broadcast
+        );
+        assert_eq!(
+            withdrawal_status(true, 3, 3, Some(850_000)),
+            WithdrawalStatus::Confirmed
+        );
+    }
+
+    #[test]
+    fn test_concentration_stats() {
+        assert_eq!(
+            concentration_stats(&[]),
+            ConcentrationStats {
+                withdrawal_count: 0,
+                total_amount_msat: 0,
+                destination_count: 0,
+                top_destination_share: 0.0,
+                hhi: 0.0,
+                effective_destinations: 0.0,
+                concentrated: false,
+            }
+        );
+
+        // Evenly spread over four destinations
+        let stats = concentration_stats(&[(2, 1_000), (2, 1_000), (2, 1_000), (2, 1_000)]);
+        assert_eq!(stats.withdrawal_count, 8);
+        assert_eq!(stats.total_amount_msat, 4_000);
+        assert_eq!(stats.top_destination_share, 0.25);
+        assert_eq!(stats.hhi, 0.25);
+        assert_eq!(stats.effective_destinations, 4.0);
+        assert!(!stats.concentrated);
+
+        // The largest destination doesn't have to come first
+        let stats = concentration_stats(&[(1, 1_000), (4, 3_000)]);
+        assert_eq!(stats.top_destination_share, 0.75);
+        assert_eq!(stats.hhi, 0.625);
+        assert_eq!(stats.effective_destinations, 1.6);
+        assert!(stats.concentrated);
+
+        // A single destination with too few withdrawals isn't flagged
+        let stats = concentration_stats(&[(4, 5_000)]);
+        assert_eq!(stats.hhi, 1.0);
+        assert!(!stats.concentrated);
+
+        // Exactly half isn't dominating yet
+        assert!(!concentration_stats(&[(3, 1_000), (3, 1_000)]).concentrated);
+    }
+
+    #[test]
+    fn test_cluster_destinations() {
+        let destinations = [
+            ("a", 1, 1_000),
+            ("b", 2, 2_000),
+            ("c", 1, 4_000),
+            ("d", 3, 500),
+            ("e", 1, 100),
+        ];
+        // a and b are swept together, then b and d, which links a and d too. x
+        // isn't a destination within the period, but still links c and e.
+        let spends = [
+            ("a", 1),
+            ("b", 1),
+            ("b", 2),
+            ("d", 2),
+            ("c", 3),
+            ("x", 3),
+            ("x", 4),
+            ("e", 4),
+        ];
+
+        assert_eq!(
+            cluster_destinations(&destinations, &spends),
+            vec![
+                DestinationCluster {
+                    addresses: vec!["c", "e"],
+                    withdrawal_count: 2,
+                    amount_msat: 4_100,
+                },
+                DestinationCluster {
+                    addresses: vec!["a", "b", "d"],
+                    withdrawal_count: 6,
+                    amount_msat: 3_500,
+                },
+            ]
+        );
+
+        // Without spends every destination is its own cluster
+        let clusters = cluster_destinations(&destinations, &[] as &[(&str, u8)]);
+        assert_eq!(clusters.len(), 5);
+        assert_eq!(clusters[0].addresses, vec!["c"]);
+    }
+}
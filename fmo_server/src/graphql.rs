@@ -0,0 +1,694 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, HashMapCache, Loader};
+use async_graphql::extensions::{
+    Extension as GraphQLExtension, ExtensionContext, ExtensionFactory, NextParseQuery,
+};
+use async_graphql::parser::types::{ExecutableDocument, Selection, SelectionSet};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Schema, ServerError, ServerResult,
+    SimpleObject, Variables,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Extension, Router};
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fedimint_core::TransactionId;
+use fmo_api_types::{
+    FederationHealth, FederationSummary, SessionSummary, TransactionDetails, TransactionItem,
+};
+use tokio::sync::OnceCell;
+
+use crate::federation::observer::FederationObserver;
+use crate::AppState;
+
+/// Nesting deeper than this isn't needed to navigate between the types and
+/// only serves to multiply the work per request
+const MAX_QUERY_DEPTH: usize = 8;
+/// Every field counts as one, lists count once per requested item
+const MAX_QUERY_COMPLEXITY: usize = 1_000;
+/// Aliases let a single query request the same expensive field many times
+const MAX_QUERY_ALIASES: usize = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+
+type ObserverSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn get_graphql_routes() -> Router<AppState> {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .extension(AliasLimit)
+        .finish();
+
+    Router::new()
+        .route("/", post(graphql_handler))
+        .layer(Extension(schema))
+}
+
+async fn graphql_handler(
+    Extension(schema): Extension<ObserverSchema>,
+    State(state): State<AppState>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let observer = state.federation_observer;
+    // Loaders are per request, so their caches never serve stale data
+    let request = request
+        .into_inner()
+        .data(DataLoader::with_cache(
+            SummaryLoader(observer.clone()),
+            tokio::spawn,
+            HashMapCache::default(),
+        ))
+        .data(DataLoader::with_cache(
+            TransactionLoader(observer.clone()),
+            tokio::spawn,
+            HashMapCache::default(),
+        ))
+        .data(observer);
+
+    schema.execute(request).await.into()
+}
+
+/// Rejects queries with more than [`MAX_QUERY_ALIASES`] aliased fields
+struct AliasLimit;
+
+impl ExtensionFactory for AliasLimit {
+    fn create(&self) -> Arc<dyn GraphQLExtension> {
+        Arc::new(AliasLimit)
+    }
+}
+
+#[async_trait]
+impl GraphQLExtension for AliasLimit {
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        let aliases = document_aliases(&document);
+        if aliases > MAX_QUERY_ALIASES {
+            return Err(ServerError::new(
+                format!("Query has {aliases} aliases, at most {MAX_QUERY_ALIASES} are allowed"),
+                None,
+            ));
+        }
+        Ok(document)
+    }
+}
+
+fn document_aliases(document: &ExecutableDocument) -> usize {
+    document
+        .operations
+        .iter()
+        .map(|(_, operation)| selection_aliases(&operation.node.selection_set.node))
+        .chain(
+            document
+                .fragments
+                .values()
+                .map(|fragment| selection_aliases(&fragment.node.selection_set.node)),
+        )
+        .sum()
+}
+
+fn selection_aliases(selection_set: &SelectionSet) -> usize {
+    selection_set
+        .items
+        .iter()
+        .map(|selection| match &selection.node {
+            Selection::Field(field) => {
+                usize::from(field.node.alias.is_some())
+                    + selection_aliases(&field.node.selection_set.node)
+            }
+            Selection::InlineFragment(fragment) => {
+                selection_aliases(&fragment.node.selection_set.node)
+            }
+            Selection::FragmentSpread(_) => 0,
+        })
+        .sum()
+}
+
+/// Loads the summaries of all federations referenced by nodes of a query at
+/// once
+struct SummaryLoader(FederationObserver);
+
+impl Loader<FederationId> for SummaryLoader {
+    type Value = FederationSummary;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(
+        &self,
+        keys: &[FederationId],
+    ) -> Result<HashMap<FederationId, FederationSummary>, Self::Error> {
+        Ok(self
+            .0
+            .federation_summaries_by_id(keys)
+            .await?
+            .into_iter()
+            .map(|summary| (summary.id, summary))
+            .collect())
+    }
+}
+
+/// Loads the details of all transactions referenced by nodes of a query with
+/// one batch of queries per federation
+struct TransactionLoader(FederationObserver);
+
+impl Loader<(FederationId, TransactionId)> for TransactionLoader {
+    type Value = TransactionDetails;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(
+        &self,
+        keys: &[(FederationId, TransactionId)],
+    ) -> Result<HashMap<(FederationId, TransactionId), TransactionDetails>, Self::Error> {
+        let mut txids_by_federation = HashMap::<_, Vec<_>>::new();
+        for (federation_id, txid) in keys {
+            txids_by_federation
+                .entry(*federation_id)
+                .or_default()
+                .push(*txid);
+        }
+
+        let mut details = HashMap::new();
+        for (federation_id, txids) in txids_by_federation {
+            details.extend(
+                self.0
+                    .transactions_details(federation_id, &txids)
+                    .await?
+                    .into_iter()
+                    .map(|(txid, details)| ((federation_id, txid), details)),
+            );
+        }
+        Ok(details)
+    }
+}
+
+async fn federation_node(
+    ctx: &Context<'_>,
+    federation_id: FederationId,
+) -> async_graphql::Result<Option<FederationNode>> {
+    Ok(ctx
+        .data::<DataLoader<SummaryLoader, HashMapCache>>()?
+        .load_one(federation_id)
+        .await?
+        .map(FederationNode))
+}
+
+fn observer<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a FederationObserver> {
+    ctx.data::<FederationObserver>()
+}
+
+fn check_page_size(limit: u64) -> async_graphql::Result<u64> {
+    if limit > MAX_PAGE_SIZE {
+        return Err(format!("Limit can be at most {MAX_PAGE_SIZE}").into());
+    }
+    Ok(limit)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Enum)]
+#[graphql(name = "FederationHealth")]
+enum Health {
+    Online,
+    Degraded,
+    Offline,
+}
+
+impl From<FederationHealth> for Health {
+    fn from(health: FederationHealth) -> Self {
+        match health {
+            FederationHealth::Online => Health::Online,
+            FederationHealth::Degraded => Health::Degraded,
+            FederationHealth::Offline => Health::Offline,
+        }
+    }
+}
+
+/// Criteria federations have to match, unset ones match all federations
+#[derive(Debug, Default)]
+struct FederationFilter {
+    network: Option<String>,
+    health: Option<Health>,
+    name_contains: Option<String>,
+}
+
+impl FederationFilter {
+    fn matches(&self, summary: &FederationSummary) -> bool {
+        let network = summary.network.as_deref().unwrap_or("bitcoin");
+        self.network
+            .as_deref()
+            .map_or(true, |filter| filter.eq_ignore_ascii_case(network))
+            && self
+                .health
+                .map_or(true, |health| health == Health::from(summary.health))
+            && self.name_contains.as_deref().map_or(true, |filter| {
+                summary
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| name.to_lowercase().contains(&filter.to_lowercase()))
+            })
+    }
+}
+
+struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Active federations matching all given filters
+    async fn federations(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Bitcoin network, e.g. `bitcoin` or `signet`")] network: Option<String>,
+        health: Option<Health>,
+        #[graphql(desc = "Case insensitive substring of the federation name")]
+        name_contains: Option<String>,
+    ) -> async_graphql::Result<Vec<FederationNode>> {
+        let filter = FederationFilter {
+            network,
+            health,
+            name_contains,
+        };
+
+        Ok(observer(ctx)?
+            .list_federation_summaries()
+            .await?
+            .into_iter()
+            .filter(|summary| filter.matches(summary))
+            .map(FederationNode)
+            .collect())
+    }
+
+    async fn federation(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Hex encoded federation id")] id: String,
+    ) -> async_graphql::Result<Option<FederationNode>> {
+        federation_node(ctx, FederationId::from_str(&id)?).await
+    }
+}
+
+struct FederationNode(FederationSummary);
+
+#[Object(name = "Federation")]
+impl FederationNode {
+    async fn id(&self) -> String {
+        self.0.id.to_string()
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn icon_url(&self) -> Option<&str> {
+        self.0.icon_url.as_deref()
+    }
+
+    async fn network(&self) -> Option<&str> {
+        self.0.network.as_deref()
+    }
+
+    async fn invite(&self) -> &str {
+        &self.0.invite
+    }
+
+    async fn health(&self) -> Health {
+        self.0.health.into()
+    }
+
+    async fn deposits_msat(&self) -> u64 {
+        self.0.deposits.msats
+    }
+
+    async fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.0.created_at
+    }
+
+    async fn shut_down(&self) -> bool {
+        self.0.shutdown.is_some()
+    }
+
+    /// Ratings published on Nostr
+    async fn rating(&self) -> Rating {
+        Rating {
+            count: self.0.nostr_votes.count,
+            avg: self.0.nostr_votes.avg,
+        }
+    }
+
+    async fn session_count(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(observer(ctx)?.federation_session_count(self.0.id).await?)
+    }
+
+    /// Sessions, most recent first
+    #[graphql(complexity = "limit as usize * child_complexity")]
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u64,
+        #[graphql(default)] offset: u64,
+    ) -> async_graphql::Result<Vec<SessionNode>> {
+        Ok(observer(ctx)?
+            .federation_session_list(self.0.id, check_page_size(limit)?, offset)
+            .await?
+            .into_iter()
+            .map(|summary| SessionNode {
+                federation_id: self.0.id,
+                summary,
+            })
+            .collect())
+    }
+
+    async fn session(
+        &self,
+        ctx: &Context<'_>,
+        index: u64,
+    ) -> async_graphql::Result<Option<SessionNode>> {
+        session_node(observer(ctx)?, self.0.id, index).await
+    }
+
+    /// Transactions in consensus order
+    #[graphql(complexity = "limit as usize * child_complexity")]
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u64,
+        #[graphql(default)] offset: u64,
+    ) -> async_graphql::Result<Vec<TransactionNode>> {
+        Ok(observer(ctx)?
+            .federation_transaction_list(self.0.id, check_page_size(limit)?, offset)
+            .await?
+            .into_iter()
+            .map(|transaction| TransactionNode::new(self.0.id, transaction.txid))
+            .collect())
+    }
+
+    async fn transaction(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Hex encoded transaction id")] txid: String,
+    ) -> async_graphql::Result<TransactionNode> {
+        let node = TransactionNode::new(self.0.id, TransactionId::from_str(&txid)?);
+        // Fail early for unknown transactions instead of in every field
+        node.details(ctx).await?;
+        Ok(node)
+    }
+
+    /// Unspent outputs of the federation's wallet
+    async fn utxos(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Utxo>> {
+        Ok(observer(ctx)?
+            .federation_utxos(self.0.id)
+            .await?
+            .into_iter()
+            .map(|utxo| Utxo {
+                address: utxo.address.assume_checked().to_string(),
+                out_point: utxo.out_point.to_string(),
+                amount_msat: utxo.amount.msats,
+            })
+            .collect())
+    }
+
+    async fn guardians(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Guardian>> {
+        let observer = observer(ctx)?;
+        let federation = observer
+            .get_federation(self.0.id)
+            .await?
+            .ok_or("Federation doesn't exist")?;
+        let health = observer.get_guardian_health(self.0.id).await?;
+
+        Ok(federation
+            .config
+            .global
+            .api_endpoints
+            .iter()
+            .map(|(peer_id, endpoint)| {
+                let health = health.get(peer_id);
+                let latest = health.and_then(|health| health.latest.as_ref());
+                Guardian {
+                    peer_id: peer_id.to_usize() as u16,
+                    name: endpoint.name.clone(),
+                    url: endpoint.url.to_string(),
+                    avg_uptime: health.map(|health| health.avg_uptime),
+                    avg_latency_ms: health.map(|health| health.avg_latency),
+                    online: latest.is_some(),
+                    block_height: latest.map(|latest| latest.block_height),
+                    session_count: latest.map(|latest| latest.session_count),
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+struct Rating {
+    count: u64,
+    avg: Option<f64>,
+}
+
+#[derive(Debug, SimpleObject)]
+struct Utxo {
+    address: String,
+    out_point: String,
+    amount_msat: u64,
+}
+
+#[derive(Debug, SimpleObject)]
+struct Guardian {
+    peer_id: u16,
+    name: String,
+    url: String,
+    /// Share of successful health checks, `None` if never checked
+    avg_uptime: Option<f32>,
+    avg_latency_ms: Option<f32>,
+    /// Responded to the latest health check
+    online: bool,
+    block_height: Option<u32>,
+    session_count: Option<u32>,
+}
+
+async fn session_node(
+    observer: &FederationObserver,
+    federation_id: FederationId,
+    session_index: u64,
+) -> async_graphql::Result<Option<SessionNode>> {
+    if session_index >= observer.federation_session_count(federation_id).await? {
+        return Ok(None);
+    }
+
+    let details = observer
+        .federation_session_details(federation_id, session_index)
+        .await?;
+    Ok(Some(SessionNode {
+        federation_id,
+        summary: details.summary,
+    }))
+}
+
+struct SessionNode {
+    federation_id: FederationId,
+    summary: SessionSummary,
+}
+
+#[Object(name = "Session")]
+impl SessionNode {
+    async fn index(&self) -> u64 {
+        self.summary.session_index
+    }
+
+    async fn item_count(&self) -> u64 {
+        self.summary.item_count
+    }
+
+    async fn transaction_count(&self) -> u64 {
+        self.summary.transaction_count
+    }
+
+    /// Derived from the block height votes up to the session
+    async fn estimated_time(&self) -> Option<DateTime<Utc>> {
+        self.summary.estimated_time
+    }
+
+    /// Transactions accepted in this session, in consensus order
+    async fn transactions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TransactionNode>> {
+        Ok(observer(ctx)?
+            .federation_session_details(self.federation_id, self.summary.session_index)
+            .await?
+            .items
+            .into_iter()
+            .filter_map(|item| item.txid)
+            .map(|txid| TransactionNode::new(self.federation_id, txid))
+            .collect())
+    }
+
+    async fn federation(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<FederationNode>> {
+        federation_node(ctx, self.federation_id).await
+    }
+}
+
+/// Transaction whose details are only loaded if a field needs them
+struct TransactionNode {
+    federation_id: FederationId,
+    txid: TransactionId,
+    details: OnceCell<TransactionDetails>,
+}
+
+impl TransactionNode {
+    fn new(federation_id: FederationId, txid: TransactionId) -> TransactionNode {
+        TransactionNode {
+            federation_id,
+            txid,
+            details: OnceCell::new(),
+        }
+    }
+
+    async fn details(&self, ctx: &Context<'_>) -> async_graphql::Result<&TransactionDetails> {
+        let loader = ctx.data::<DataLoader<TransactionLoader, HashMapCache>>()?;
+        self.details
+            .get_or_try_init(|| async {
+                loader
+                    .load_one((self.federation_id, self.txid))
+                    .await?
+                    .ok_or_else(|| async_graphql::Error::new("Transaction doesn't exist"))
+            })
+            .await
+    }
+}
+
+#[Object(name = "Transaction")]
+impl TransactionNode {
+    async fn txid(&self) -> String {
+        self.txid.to_string()
+    }
+
+    async fn session_index(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(self.details(ctx).await?.session_index)
+    }
+
+    async fn item_index(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(self.details(ctx).await?.item_index)
+    }
+
+    async fn inputs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TransactionItemNode>> {
+        Ok(self
+            .details(ctx)
+            .await?
+            .inputs
+            .iter()
+            .map(TransactionItemNode::from)
+            .collect())
+    }
+
+    async fn outputs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TransactionItemNode>> {
+        Ok(self
+            .details(ctx)
+            .await?
+            .outputs
+            .iter()
+            .map(TransactionItemNode::from)
+            .collect())
+    }
+
+    async fn session(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<SessionNode>> {
+        let session_index = self.details(ctx).await?.session_index;
+        session_node(observer(ctx)?, self.federation_id, session_index).await
+    }
+
+    async fn federation(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<FederationNode>> {
+        federation_node(ctx, self.federation_id).await
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+#[graphql(name = "TransactionItem")]
+struct TransactionItemNode {
+    /// Kind of the module the item belongs to, e.g. `mint`
+    kind: String,
+    amount_msat: Option<u64>,
+    /// Hex encoded id of the lightning contract the item interacts with
+    ln_contract_id: Option<String>,
+}
+
+impl From<&TransactionItem> for TransactionItemNode {
+    fn from(item: &TransactionItem) -> Self {
+        TransactionItemNode {
+            kind: item.kind.clone(),
+            amount_msat: item.amount.map(|amount| amount.msats),
+            ln_contract_id: item
+                .ln_contract
+                .as_ref()
+                .map(|contract| contract.contract_id.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fedimint_core::config::FederationId;
+    use fedimint_core::Amount;
+    use fmo_api_types::{FederationHealth, FederationRating, FederationSummary};
+
+    use super::{document_aliases, FederationFilter, Health};
+
+    fn summary(name: &str, network: Option<&str>, health: FederationHealth) -> FederationSummary {
+        FederationSummary {
+            id: FederationId::dummy(),
+            name: Some(name.to_owned()),
+            icon_url: None,
+            last_7d_activity: vec![],
+            deposits: Amount::ZERO,
+            invite: String::new(),
+            network: network.map(ToOwned::to_owned),
+            nostr_votes: FederationRating {
+                count: 0,
+                avg: None,
+            },
+            health,
+            created_at: None,
+            shutdown: None,
+            rating_index: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_federation_filter() {
+        let mainnet = summary("Bitcoin Circle", Some("bitcoin"), FederationHealth::Online);
+        let signet = summary("Test Fed", Some("signet"), FederationHealth::Degraded);
+
+        assert!(FederationFilter::default().matches(&mainnet));
+
+        let filter = FederationFilter {
+            network: Some("Signet".to_owned()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&mainnet));
+        assert!(filter.matches(&signet));
+
+        let filter = FederationFilter {
+            health: Some(Health::Online),
+            name_contains: Some("circle".to_owned()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&mainnet));
+        assert!(!filter.matches(&signet));
+    }
+
+    #[test]
+    fn test_document_aliases() {
+        let document = async_graphql::parser::parse_query(
+            "query {
+                a: federations { id }
+                b: federations { theName: name ...Sessions }
+                federation(id: \"00\") { ... on Federation { sessionCount } }
+            }
+            fragment Sessions on Federation { latest: sessions(limit: 1) { index } }",
+        )
+        .unwrap();
+        assert_eq!(document_aliases(&document), 4);
+    }
+}
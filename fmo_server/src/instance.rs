@@ -0,0 +1,27 @@
+use anyhow::Context;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use fmo_api_types::InstanceSettings;
+
+use crate::AppState;
+
+pub fn get_instance_routes() -> Router<AppState> {
+    Router::new().route("/settings", get(get_instance_settings))
+}
+
+/// Loads the instance settings from the TOML file `FO_INSTANCE_SETTINGS`
+/// points to, falling back to defaults if it isn't set
+pub fn load_instance_settings() -> anyhow::Result<InstanceSettings> {
+    let Ok(path) = dotenv::var("FO_INSTANCE_SETTINGS") else {
+        return Ok(InstanceSettings::default());
+    };
+
+    let settings = std::fs::read_to_string(&path)
+        .with_context(|| format!("Could not read instance settings from {path}"))?;
+    toml::from_str(&settings).with_context(|| format!("Invalid instance settings in {path}"))
+}
+
+async fn get_instance_settings(State(state): State<AppState>) -> Json<InstanceSettings> {
+    Json(state.instance_settings.clone())
+}
@@ -1,31 +1,84 @@
-use anyhow::Context;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
 use axum::routing::{get, put};
-use axum::Router;
+use axum::{Json, Router};
+use fedimint_core::config::FederationId;
+use fmo_api_types::{InstanceSettings, VersionInfo};
+use futures::StreamExt;
+use tokio::io::AsyncWriteExt;
 use tower_http::cors::CorsLayer;
 use tracing::info;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
-use crate::config::meta::MetaOverrideCache;
+use crate::admin::get_admin_routes;
+use crate::auth::require_auth;
+use crate::chain::get_chain_routes;
+use crate::config::meta::{ConsensusMetaCache, MetaFetchPolicy, MetaOverrideCache};
 use crate::config::{get_config_routes, FederationConfigCache};
+use crate::federation::alert_rules::get_alert_rule_routes;
 use crate::federation::get_federations_routes;
-use crate::federation::nostr::{get_nostr_federations, publish_federation_event};
+use crate::federation::nostr::{
+    delete_nostr_hidden_vote, delete_nostr_pubkey_moderation, delete_nostr_relay, get_nostr_events,
+    get_nostr_federation_summary, get_nostr_federations, get_nostr_moderation, get_nostr_relays,
+    publish_federation_event, put_nostr_hidden_vote, put_nostr_pubkey_moderation, put_nostr_relay,
+};
 use crate::federation::observer::FederationObserver;
+use crate::federation::readiness::{get_live, get_ready, get_startup_routes};
+use crate::federation::snapshot::snapshot_tables;
+use crate::federation::versions::get_fedimintd_versions;
+use crate::federation::views::get_view_routes;
+use crate::instance::{get_instance_routes, load_instance_settings};
+use crate::net::HttpClients;
+use crate::openapi::get_openapi_spec;
+use crate::queries::get_query_routes;
+use crate::socks::{init_socks_proxy, take_socks_proxy_arg};
+use crate::usage::{track_usage, UsageTracker};
 
+/// Operator-only endpoints for maintenance and debugging
+mod admin;
+/// Bearer token and API key checks of protected routes
+mod auth;
+/// Block times of the bitcoin chain used for session time estimation
+mod chain;
 /// Fedimint config fetching service implementation
 mod config;
 /// `anyhow`-based error handling for axum
 mod error;
+/// Conditional request handling for large, rarely changing responses
+mod etag;
 mod federation;
+/// Optional GraphQL API over the observed data, built with the `graphql`
+/// feature
+#[cfg(feature = "graphql")]
+mod graphql;
+/// Operator provided settings of this deployment
+mod instance;
 mod meta;
+/// Shared HTTP clients for outgoing requests
+mod net;
+/// OpenAPI document describing the public API
+mod openapi;
+/// Predefined analytics queries with typed parameters
+mod queries;
+/// Connections to guardians through a SOCKS5 proxy, e.g. Tor
+mod socks;
+/// Per-route API usage counters
+mod usage;
 mod util;
 
 #[derive(Debug, Clone)]
 struct AppState {
     federation_config_cache: FederationConfigCache,
     meta_override_cache: MetaOverrideCache,
+    consensus_meta_cache: ConsensusMetaCache,
     federation_observer: FederationObserver,
+    instance_settings: InstanceSettings,
+    usage_tracker: UsageTracker,
 }
 
 #[tokio::main]
@@ -40,34 +93,221 @@ async fn main() -> anyhow::Result<()> {
         )
         .init();
 
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    init_socks_proxy(take_socks_proxy_arg(&mut args)?)?;
+    if let Some((command, args)) = args.split_first() {
+        return run_command(command, args).await;
+    }
+
     let bind_address = dotenv::var("FO_BIND").unwrap_or_else(|_| "127.0.0.1:3000".to_owned());
     info!("Starting API server on {bind_address}");
 
-    let app = Router::new()
-        .route("/health", get(|| async { "Server is up and running!" }))
+    // Connecting may run long migrations, until they are done only the
+    // liveness probe succeeds. The full API takes over the same socket after.
+    let listener = std::net::TcpListener::bind(&bind_address).context("Binding to port")?;
+    listener.set_nonblocking(true)?;
+    let (startup_done_tx, startup_done_rx) = tokio::sync::oneshot::channel::<()>();
+    let startup_listener = tokio::net::TcpListener::from_std(listener.try_clone()?)?;
+    let startup_server = tokio::spawn(async move {
+        axum::serve(startup_listener, get_startup_routes())
+            .with_graceful_shutdown(async {
+                let _ = startup_done_rx.await;
+            })
+            .await
+    });
+
+    let http_clients = HttpClients::from_env()?;
+    let usage_tracker = UsageTracker::from_env();
+    let federation_observer = FederationObserver::new(
+        &dotenv::var("FO_DATABASE").context("No FO_DATABASE provided")?,
+        &dotenv::var("FO_ADMIN_AUTH").context("No FO_ADMIN_AUTH provided")?,
+        http_clients.clone(),
+        usage_tracker.clone(),
+    )
+    .await?;
+
+    let _ = startup_done_tx.send(());
+    startup_server
+        .await
+        .context("Startup server panicked")?
+        .context("Startup server failed")?;
+
+    let meta_override_cache =
+        MetaOverrideCache::new(http_clients.meta.clone(), MetaFetchPolicy::from_env()?);
+    let consensus_meta_cache = ConsensusMetaCache::default();
+    federation_observer
+        .spawn_meta_history_recorder(meta_override_cache.clone(), consensus_meta_cache.clone());
+
+    let routes = Router::new()
+        // Kept for existing deployments, same as `/live`
+        .route("/health", get(get_live))
+        .route("/live", get(get_live))
+        .route("/ready", get(get_ready))
+        .route("/version", get(get_version))
+        .route("/versions", get(get_fedimintd_versions))
+        .route("/openapi.json", get(get_openapi_spec))
         .nest("/config", get_config_routes())
         .nest("/federations", get_federations_routes())
+        .nest("/views", get_view_routes())
+        .nest("/alert_rules", get_alert_rule_routes())
+        .nest("/queries", get_query_routes())
+        .nest("/instance", get_instance_routes())
+        .nest("/chain", get_chain_routes())
+        .nest("/admin", get_admin_routes())
         // TODO: move into nostr service/module
         .route("/nostr/federations", get(get_nostr_federations))
         .route("/nostr/federations", put(publish_federation_event))
+        .route(
+            "/nostr/federations/summary",
+            get(get_nostr_federation_summary),
+        )
+        .route("/nostr/events", get(get_nostr_events))
+        .route(
+            "/nostr/relays",
+            get(get_nostr_relays)
+                .put(put_nostr_relay)
+                .delete(delete_nostr_relay),
+        )
+        .route("/nostr/moderation", get(get_nostr_moderation))
+        .route(
+            "/nostr/moderation/pubkeys",
+            put(put_nostr_pubkey_moderation).delete(delete_nostr_pubkey_moderation),
+        )
+        .route(
+            "/nostr/moderation/votes",
+            put(put_nostr_hidden_vote).delete(delete_nostr_hidden_vote),
+        );
+    #[cfg(feature = "graphql")]
+    let routes = routes.nest("/graphql", graphql::get_graphql_routes());
+
+    let app = routes
+        .route_layer(axum::middleware::from_fn_with_state(
+            federation_observer.clone(),
+            require_auth,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            usage_tracker.clone(),
+            track_usage,
+        ))
         .layer(CorsLayer::permissive())
         .with_state(AppState {
             federation_config_cache: Default::default(),
-            meta_override_cache: Default::default(),
-            federation_observer: FederationObserver::new(
-                &dotenv::var("FO_DATABASE").context("No FO_DATABASE provided")?,
-                &dotenv::var("FO_ADMIN_AUTH").context("No FO_ADMIN_AUTH provided")?,
-            )
-            .await?,
+            meta_override_cache,
+            consensus_meta_cache,
+            federation_observer: federation_observer.clone(),
+            instance_settings: load_instance_settings()?,
+            usage_tracker,
         });
 
-    let listener = tokio::net::TcpListener::bind(bind_address)
-        .await
-        .context("Binding to port")?;
+    axum::serve(
+        tokio::net::TcpListener::from_std(listener)?,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .context("Starting axum server")?;
 
-    axum::serve(listener, app)
-        .await
-        .context("Starting axum server")?;
+    federation_observer.shutdown().await?;
+    info!("Shut down cleanly");
+
+    Ok(())
+}
+
+/// Runs a one-off maintenance command instead of the API server:
+/// * `import-sessions <federation_id> <dir>` processes session dumps from
+///   `dir`, e.g. for the initial sync of air-gapped deployments
+/// * `dump-sessions <federation_id> <dir>` writes all stored sessions to `dir`
+/// * `snapshot <file> [tables]` writes a snapshot of the synced data, tables
+///   are comma separated and default to all
+/// * `restore <file>` seeds the database from a snapshot
+async fn run_command(command: &str, args: &[String]) -> anyhow::Result<()> {
+    let federation_observer = FederationObserver::connect(
+        &dotenv::var("FO_DATABASE").context("No FO_DATABASE provided")?,
+        &dotenv::var("FO_ADMIN_AUTH").context("No FO_ADMIN_AUTH provided")?,
+        HttpClients::from_env()?,
+    )
+    .await?;
+
+    match (command, args) {
+        ("import-sessions", [federation_id, dir]) => {
+            let federation_id =
+                FederationId::from_str(federation_id).context("Invalid federation id")?;
+            let imported = federation_observer
+                .import_session_dumps(federation_id, Path::new(dir))
+                .await?;
+            info!("Imported {imported} sessions of {federation_id}");
+        }
+        ("dump-sessions", [federation_id, dir]) => {
+            let federation_id =
+                FederationId::from_str(federation_id).context("Invalid federation id")?;
+            let dumped = federation_observer
+                .dump_sessions(federation_id, Path::new(dir))
+                .await?;
+            info!("Dumped {dumped} sessions of {federation_id}");
+        }
+        ("snapshot", [file, tables @ ..]) if tables.len() <= 1 => {
+            let tables = snapshot_tables(tables.first().map(String::as_str))?;
+            let mut output = tokio::io::BufWriter::new(
+                tokio::fs::File::create(file)
+                    .await
+                    .with_context(|| format!("Failed to create {file}"))?,
+            );
+            let mut snapshot = std::pin::pin!(federation_observer.snapshot(tables));
+            while let Some(chunk) = snapshot.next().await {
+                output.write_all(&chunk?).await?;
+            }
+            output.flush().await?;
+            info!("Wrote snapshot to {file}");
+        }
+        ("restore", [file]) => {
+            let input = tokio::io::BufReader::new(
+                tokio::fs::File::open(file)
+                    .await
+                    .with_context(|| format!("Failed to open {file}"))?,
+            );
+            let federation_ids = federation_observer.restore_snapshot(input).await?;
+            info!("Restored {} federations from {file}", federation_ids.len());
+        }
+        (
+            "import-sessions" | "dump-sessions" | "snapshot" | "restore",
+            _,
+        ) => bail!("Invalid arguments for {command}, see the README for usage"),
+        _ => bail!(
+            "Unknown command {command}, expected import-sessions, dump-sessions, snapshot or restore"
+        ),
+    }
 
     Ok(())
 }
+
+/// Resolves once the process receives Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+    info!("Shutdown signal received, finishing in-flight work");
+}
+
+async fn get_version() -> Json<VersionInfo> {
+    Json(VersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        git_rev: option_env!("FMO_GIT_REV").map(ToOwned::to_owned),
+    })
+}
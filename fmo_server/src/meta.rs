@@ -1,15 +1,152 @@
-use axum::Json;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::Response;
 use fedimint_core::config::{JsonClientConfig, META_OVERRIDE_URL_KEY};
+use fmo_api_types::{LightningContactKind, MetaLint, MetaLintIssue, MetaLintSeverity};
+use futures::future::join_all;
 use tracing::debug;
 use tracing::log::warn;
 
-use crate::config::meta::{parse_meta_lenient, MetaFields};
+use crate::config::meta::{parse_meta_lenient, ConsensusMetaCache, MetaFields, MetaOverrideCache};
+use crate::etag::json_with_etag;
 use crate::AppState;
 
+/// `fresh`, `stale` or `failed` if the federation uses a meta override file,
+/// `stale` meaning fields from an outdated copy of the file were used
+const META_OVERRIDE_STATUS_HEADER: &str = "x-meta-override-status";
+/// Why the meta override file couldn't be (re)fetched
+const META_OVERRIDE_ERROR_HEADER: &str = "x-meta-override-error";
+
+/// Result of applying a federation's meta override file
+struct OverrideStatus {
+    status: &'static str,
+    error: Option<String>,
+}
+
+/// Meta fields of a federation, fields from its override file take precedence
+/// over config fields and fields agreed on through the meta module over both.
+/// Problems fetching the override file are reported in the response headers.
+/// Answers with `304 Not Modified` if the client already has the current
+/// fields according to `request_headers`.
 pub async fn federation_meta(
     cfg: &JsonClientConfig,
     state: &AppState,
-) -> crate::error::Result<Json<MetaFields>> {
+    request_headers: &HeaderMap,
+) -> crate::error::Result<Response> {
+    let (meta_fields, override_status) =
+        merged_meta(cfg, &state.meta_override_cache, &state.consensus_meta_cache).await;
+
+    let mut headers = HeaderMap::new();
+    if let Some(OverrideStatus { status, error }) = override_status {
+        headers.insert(
+            META_OVERRIDE_STATUS_HEADER,
+            HeaderValue::from_static(status),
+        );
+        if let Some(error) =
+            error.and_then(|error| HeaderValue::from_str(&error.replace(['\r', '\n'], " ")).ok())
+        {
+            headers.insert(META_OVERRIDE_ERROR_HEADER, error);
+        }
+    }
+
+    Ok(json_with_etag(request_headers, headers, meta_fields)?)
+}
+
+/// Checks a federation's meta fields for common mistakes and verifies the
+/// lightning contacts they contain
+pub async fn lint_federation_meta(cfg: &JsonClientConfig, state: &AppState) -> MetaLint {
+    let (meta_fields, override_status) =
+        merged_meta(cfg, &state.meta_override_cache, &state.consensus_meta_cache).await;
+
+    let mut issues = lint_meta_fields(&meta_fields);
+    if let Some(OverrideStatus {
+        status,
+        error: Some(error),
+    }) = override_status
+    {
+        issues.push(MetaLintIssue {
+            field: META_OVERRIDE_URL_KEY.to_owned(),
+            severity: if status == "failed" {
+                MetaLintSeverity::Error
+            } else {
+                MetaLintSeverity::Warning
+            },
+            message: format!("Meta override file couldn't be fetched: {error}"),
+        });
+    }
+
+    let lightning_contacts = join_all(lightning_contacts(&meta_fields).into_iter().map(
+        |(field, contact, kind)| async move {
+            state
+                .meta_override_cache
+                .verify_lightning_contact(&field, &contact, kind)
+                .await
+        },
+    ))
+    .await;
+    for verification in &lightning_contacts {
+        if let Some(error) = &verification.error {
+            issues.push(MetaLintIssue {
+                field: verification.field.clone(),
+                severity: MetaLintSeverity::Warning,
+                message: format!("Lightning contact doesn't resolve: {error}"),
+            });
+        }
+    }
+
+    MetaLint {
+        issues,
+        lightning_contacts,
+    }
+}
+
+/// Meta fields to record in the federation's meta history, `None` if the
+/// override file couldn't be fetched so its missing fields aren't mistaken for
+/// a change
+pub async fn meta_for_history(
+    cfg: &JsonClientConfig,
+    meta_override_cache: &MetaOverrideCache,
+    consensus_meta_cache: &ConsensusMetaCache,
+) -> Option<MetaFields> {
+    match merged_meta(cfg, meta_override_cache, consensus_meta_cache).await {
+        (
+            _,
+            Some(OverrideStatus {
+                status: "failed", ..
+            }),
+        ) => None,
+        (meta_fields, _) => Some(meta_fields),
+    }
+}
+
+async fn merged_meta(
+    cfg: &JsonClientConfig,
+    meta_override_cache: &MetaOverrideCache,
+    consensus_meta_cache: &ConsensusMetaCache,
+) -> (MetaFields, Option<OverrideStatus>) {
+    let (meta_fields, override_status) = config_and_override_meta(cfg, meta_override_cache).await;
+
+    let consensus_meta = match consensus_meta_cache.fetch_consensus_meta_cached(cfg).await {
+        Ok(consensus_meta) => consensus_meta.unwrap_or_default(),
+        Err(e) => {
+            warn!(
+                "Failed to fetch consensus meta of {}: {e:?}",
+                cfg.global.calculate_federation_id()
+            );
+            MetaFields::new()
+        }
+    };
+
+    let meta_fields = meta_fields
+        .into_iter()
+        .chain(consensus_meta)
+        .collect::<MetaFields>();
+    (meta_fields, override_status)
+}
+
+async fn config_and_override_meta(
+    cfg: &JsonClientConfig,
+    meta_override_cache: &MetaOverrideCache,
+) -> (MetaFields, Option<OverrideStatus>) {
     let meta_fields_config = parse_meta_lenient(
         cfg.global
             .meta
@@ -17,31 +154,168 @@ pub async fn federation_meta(
             .map(|(key, value)| (key.to_owned(), value.to_owned().into())),
     );
 
-    let meta_fields = if let Some(override_url) = meta_fields_config
+    let Some(override_url) = meta_fields_config
         .get(META_OVERRIDE_URL_KEY)
         .or_else(|| meta_fields_config.get("meta_external_url")) // Fedi legacy field
         .and_then(|url| url.as_str().map(ToOwned::to_owned))
+    else {
+        return (meta_fields_config, None);
+    };
+
+    debug!("fetching {override_url}");
+    let (status, meta_override) = match meta_override_cache
+        .fetch_meta_cached(&override_url, cfg.global.calculate_federation_id())
+        .await
     {
-        debug!("fetching {override_url}");
-        let meta_override = match state
-            .meta_override_cache
-            .fetch_meta_cached(&override_url, cfg.global.calculate_federation_id())
-            .await
-        {
-            Ok(meta) => meta,
-            Err(e) => {
-                warn!("Failed to fetch meta fields from {override_url}: {e:?}");
-                return Ok(meta_fields_config.into());
-            }
-        };
-
-        meta_fields_config
-            .into_iter()
-            .chain(meta_override)
-            .collect::<MetaFields>()
-    } else {
-        meta_fields_config
+        Ok(meta) => match meta.refresh_error {
+            Some(error) => (
+                OverrideStatus {
+                    status: "stale",
+                    error: Some(error),
+                },
+                meta.fields,
+            ),
+            None => (
+                OverrideStatus {
+                    status: "fresh",
+                    error: None,
+                },
+                meta.fields,
+            ),
+        },
+        Err(e) => {
+            warn!("Failed to fetch meta fields from {override_url}: {e:?}");
+            (
+                OverrideStatus {
+                    status: "failed",
+                    error: Some(e.to_string()),
+                },
+                MetaFields::new(),
+            )
+        }
     };
 
-    Ok(meta_fields.into())
+    let meta_fields = meta_fields_config
+        .into_iter()
+        .chain(meta_override)
+        .collect::<MetaFields>();
+    (meta_fields, Some(status))
+}
+
+/// Checks that don't need network access
+fn lint_meta_fields(meta_fields: &MetaFields) -> Vec<MetaLintIssue> {
+    let mut issues = vec![];
+
+    if !meta_fields
+        .get("federation_name")
+        .and_then(|name| name.as_str())
+        .is_some_and(|name| !name.trim().is_empty())
+    {
+        issues.push(MetaLintIssue {
+            field: "federation_name".to_owned(),
+            severity: MetaLintSeverity::Warning,
+            message: "Federation has no name, clients will show its id instead".to_owned(),
+        });
+    }
+
+    if let Some(icon_url) = meta_fields.get("federation_icon_url") {
+        if !icon_url
+            .as_str()
+            .is_some_and(|url| url.starts_with("https://"))
+        {
+            issues.push(MetaLintIssue {
+                field: "federation_icon_url".to_owned(),
+                severity: MetaLintSeverity::Error,
+                message: "Icon URL has to be an https URL".to_owned(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Finds lightning addresses in fields named like one and bech32 encoded LNURLs
+/// in any field
+fn lightning_contacts(meta_fields: &MetaFields) -> Vec<(String, String, LightningContactKind)> {
+    meta_fields
+        .iter()
+        .filter_map(|(field, value)| {
+            let value = value.as_str()?.trim();
+            let lowercase = value.to_lowercase();
+            let kind =
+                if lowercase.starts_with("lnurl1") || lowercase.starts_with("lightning:lnurl1") {
+                    LightningContactKind::Lnurl
+                } else if ["lightning", "lnaddress", "ln_address", "lud16"]
+                    .iter()
+                    .any(|name| field.contains(name))
+                    && value.contains('@')
+                {
+                    LightningContactKind::LightningAddress
+                } else {
+                    return None;
+                };
+
+            Some((field.clone(), value.to_owned(), kind))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use fmo_api_types::LightningContactKind;
+    use serde_json::json;
+
+    use super::{lightning_contacts, lint_meta_fields};
+    use crate::config::meta::MetaFields;
+
+    #[test]
+    fn test_lint_meta_fields() {
+        let fields =
+            |value: serde_json::Value| serde_json::from_value::<MetaFields>(value).unwrap();
+
+        assert!(lint_meta_fields(&fields(json!({
+            "federation_name": "Alpha",
+            "federation_icon_url": "https://example.com/icon.png",
+        })))
+        .is_empty());
+
+        let issues = lint_meta_fields(&fields(json!({
+            "federation_name": " ",
+            "federation_icon_url": "http://example.com/icon.png",
+        })));
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| issue.field.as_str())
+                .collect::<Vec<_>>(),
+            vec!["federation_name", "federation_icon_url"]
+        );
+    }
+
+    #[test]
+    fn test_lightning_contacts() {
+        let fields = serde_json::from_value::<MetaFields>(json!({
+            "federation_name": "Alpha",
+            "lightning_address": "support@example.com",
+            "contact_email": "support@example.com",
+            "donations": "lightning:LNURL1DP68GURN8GHJ7",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            lightning_contacts(&fields),
+            vec![
+                (
+                    "donations".to_owned(),
+                    "lightning:LNURL1DP68GURN8GHJ7".to_owned(),
+                    LightningContactKind::Lnurl
+                ),
+                (
+                    "lightning_address".to_owned(),
+                    "support@example.com".to_owned(),
+                    LightningContactKind::LightningAddress
+                ),
+            ]
+        );
+    }
 }
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use bitcoin::Network;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+
+use crate::socks::socks_proxy;
+
+/// Esplora instances used for networks without a configured URL, regtest
+/// federations need `FO_ESPLORA_URL_REGTEST` to be set
+const DEFAULT_ESPLORA_URLS: &[(Network, &str)] = &[
+    (Network::Bitcoin, "https://mempool.space/api"),
+    (Network::Testnet, "https://mempool.space/testnet/api"),
+    (Network::Signet, "https://mempool.space/signet/api"),
+];
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_META_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Clients for all outgoing HTTP requests. They are created once and cloned
+/// where needed so connections are pooled and network settings apply
+/// everywhere.
+#[derive(Debug, Clone)]
+pub struct HttpClients {
+    /// General purpose client for endpoints configured by the operator, e.g.
+    /// alert webhooks
+    pub http: reqwest::Client,
+    /// Client for fetching meta override files and LNURLs. Their URLs are
+    /// controlled by federations, so it only connects to public addresses and
+    /// doesn't follow redirects.
+    pub meta: MetaClient,
+    /// Block explorers used for block times and peg-out transactions
+    pub esplora: EsploraClients,
+}
+
+/// One esplora client per bitcoin network
+#[derive(Debug, Clone)]
+pub struct EsploraClients {
+    clients: HashMap<Network, esplora_client::AsyncClient>,
+}
+
+impl EsploraClients {
+    pub fn get(&self, network: Network) -> anyhow::Result<&esplora_client::AsyncClient> {
+        self.clients.get(&network).with_context(|| {
+            format!(
+                "No esplora URL configured for {network}, set {}",
+                esplora_url_var(network)
+            )
+        })
+    }
+}
+
+/// `FO_ESPLORA_URL` for mainnet, `FO_ESPLORA_URL_<NETWORK>` for the others
+fn esplora_url_var(network: Network) -> String {
+    match network {
+        Network::Bitcoin => "FO_ESPLORA_URL".to_owned(),
+        network => format!("FO_ESPLORA_URL_{}", network.to_string().to_uppercase()),
+    }
+}
+
+impl HttpClients {
+    /// Configures the clients from the optional `FO_HTTP_TIMEOUT_SECS`,
+    /// `FO_META_FETCH_TIMEOUT_SECS`, `FO_HTTP_PROXY`, `FO_ESPLORA_URL` and
+    /// `FO_ESPLORA_URL_<NETWORK>` environment variables and the SOCKS proxy
+    pub fn from_env() -> anyhow::Result<HttpClients> {
+        let timeout = match dotenv::var("FO_HTTP_TIMEOUT_SECS") {
+            Ok(secs) => Duration::from_secs(secs.parse().context("Invalid FO_HTTP_TIMEOUT_SECS")?),
+            Err(_) => DEFAULT_TIMEOUT,
+        };
+        let meta_timeout = match dotenv::var("FO_META_FETCH_TIMEOUT_SECS") {
+            Ok(secs) => {
+                Duration::from_secs(secs.parse().context("Invalid FO_META_FETCH_TIMEOUT_SECS")?)
+            }
+            Err(_) => DEFAULT_META_TIMEOUT,
+        };
+        // Without an explicit HTTP proxy requests go through the SOCKS proxy
+        // too. `socks5h` makes the proxy resolve host names.
+        let proxy = dotenv::var("FO_HTTP_PROXY")
+            .ok()
+            .or_else(|| socks_proxy().map(|proxy| format!("socks5h://{proxy}")));
+
+        let mut http = reqwest::Client::builder().timeout(timeout);
+        if let Some(proxy) = &proxy {
+            http = http.proxy(reqwest::Proxy::all(proxy).context("Invalid proxy URL")?);
+        }
+        // A proxy resolves host names itself, so the resolver can only check
+        // addresses of direct connections. Proxied requests are checked by
+        // `MetaClient` before they are sent.
+        let mut meta = reqwest::Client::builder()
+            .timeout(meta_timeout)
+            .redirect(reqwest::redirect::Policy::none());
+        meta = match &proxy {
+            Some(proxy) => meta.proxy(reqwest::Proxy::all(proxy).context("Invalid proxy URL")?),
+            None => meta
+                .dns_resolver(Arc::new(PublicAddressResolver))
+                .no_proxy(),
+        };
+
+        let mut esplora = HashMap::new();
+        for network in [
+            Network::Bitcoin,
+            Network::Testnet,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            let default_url = DEFAULT_ESPLORA_URLS
+                .iter()
+                .find(|(default_network, _)| *default_network == network)
+                .map(|(_, url)| (*url).to_owned());
+            let Some(url) = dotenv::var(esplora_url_var(network)).ok().or(default_url) else {
+                continue;
+            };
+
+            let mut builder = esplora_client::Builder::new(&url).timeout(timeout.as_secs());
+            if let Some(proxy) = &proxy {
+                builder = builder.proxy(proxy);
+            }
+            esplora.insert(
+                network,
+                builder
+                    .build_async()
+                    .with_context(|| format!("Failed to build esplora client for {network}"))?,
+            );
+        }
+
+        Ok(HttpClients {
+            http: http.build().context("Failed to build HTTP client")?,
+            meta: MetaClient {
+                client: meta.build().context("Failed to build meta HTTP client")?,
+                proxied: proxy.is_some(),
+            },
+            esplora: EsploraClients { clients: esplora },
+        })
+    }
+}
+
+/// HTTP client for URLs controlled by federations
+#[derive(Debug, Clone)]
+pub struct MetaClient {
+    client: reqwest::Client,
+    /// Set if requests go through a proxy, which resolves host names itself
+    proxied: bool,
+}
+
+impl MetaClient {
+    pub async fn get(&self, url: Url) -> anyhow::Result<reqwest::Response> {
+        if self.proxied {
+            ensure_public_host(&url).await?;
+        }
+
+        Ok(self.client.get(url).send().await?)
+    }
+}
+
+/// Checks the host of a URL fetched through the proxy. Host names are resolved
+/// locally to check their addresses, except for `.onion` ones which only the
+/// proxy can resolve.
+async fn ensure_public_host(url: &Url) -> anyhow::Result<()> {
+    let host = url.host_str().context("URL has no host")?.to_lowercase();
+    if host.ends_with(".onion") {
+        return Ok(());
+    }
+
+    if let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        ensure!(is_public_ip(ip), "Address {ip} is not public");
+    } else {
+        lookup_public_host(&host).await?;
+    }
+
+    Ok(())
+}
+
+/// Resolves a host name using the system resolver, but fails if any of the
+/// addresses isn't publicly routable
+async fn lookup_public_host(host: &str) -> anyhow::Result<Vec<SocketAddr>> {
+    let addrs = tokio::net::lookup_host((host, 0))
+        .await?
+        .collect::<Vec<_>>();
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        bail!("{host} resolves to non-public address {}", addr.ip());
+    }
+
+    Ok(addrs)
+}
+
+/// Only lets direct connections use public addresses, see
+/// [`lookup_public_host`]
+struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = lookup_public_host(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Returns `false` for loopback, private, link-local and other addresses that
+/// aren't reachable from the internet
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            let shared_address_space = a == 100 && (b & 0xc0) == 64;
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || shared_address_space
+                || a == 0
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(ip.into());
+            }
+
+            let first_segment = ip.segments()[0];
+            let unique_local = (first_segment & 0xfe00) == 0xfc00;
+            let link_local = (first_segment & 0xffc0) == 0xfe80;
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || unique_local
+                || link_local)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use reqwest::Url;
+
+    use super::{ensure_public_host, esplora_url_var, is_public_ip};
+
+    #[test]
+    fn test_esplora_url_var() {
+        assert_eq!(esplora_url_var(Network::Bitcoin), "FO_ESPLORA_URL");
+        assert_eq!(esplora_url_var(Network::Signet), "FO_ESPLORA_URL_SIGNET");
+        assert_eq!(esplora_url_var(Network::Regtest), "FO_ESPLORA_URL_REGTEST");
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["1.1.1.1", "95.217.1.2", "2a01:4f9::1", "::ffff:8.8.8.8"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_public_host() {
+        for url in [
+            "http://2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion/meta.json",
+            "https://1.1.1.1/meta.json",
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert!(ensure_public_host(&url).await.is_ok(), "{url}");
+        }
+
+        for url in [
+            "https://localhost/meta.json",
+            "https://127.0.0.1/meta.json",
+            "https://[fd00::1]/meta.json",
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert!(ensure_public_host(&url).await.is_err(), "{url}");
+        }
+    }
+}
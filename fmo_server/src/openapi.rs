@@ -0,0 +1,108 @@
+use axum::Json;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::config::ConfigApi;
+use crate::federation::{nostr, versions, FederationsApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Fedimint Observer API",
+        description = "Statistics and health data of Fedimint federations. Amounts are given in msat."
+    ),
+    paths(
+        nostr::get_nostr_federations,
+        nostr::get_nostr_federation_summary,
+        nostr::publish_federation_event,
+        nostr::get_nostr_events,
+        nostr::get_nostr_relays,
+        nostr::put_nostr_relay,
+        nostr::delete_nostr_relay,
+        nostr::get_nostr_moderation,
+        nostr::put_nostr_pubkey_moderation,
+        nostr::delete_nostr_pubkey_moderation,
+        nostr::put_nostr_hidden_vote,
+        nostr::delete_nostr_hidden_vote,
+        versions::get_fedimintd_versions,
+    ),
+    components(schemas(
+        fmo_api_types::NostrFederationStatus,
+        fmo_api_types::NostrFederationSummary,
+        fmo_api_types::NostrArchivedEvent,
+        fmo_api_types::NostrEventPage,
+        fmo_api_types::NostrModeration,
+        fmo_api_types::NostrModeratedPubkey,
+        fmo_api_types::NostrHiddenVote,
+        fmo_api_types::FedimintdVersionCount
+    )),
+    modifiers(&SecuritySchemes)
+)]
+struct ApiDoc;
+
+/// Registers the bearer token schemes: `admin_auth` for the `FO_ADMIN_AUTH`
+/// token, which also passes scope checks, and `api_key` for endpoints that
+/// only accept API keys
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("API key created with `/admin/api_keys`"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Combined OpenAPI document of all public routes
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    openapi.merge(FederationsApi::openapi());
+    openapi.merge(ConfigApi::openapi());
+    openapi
+}
+
+pub async fn get_openapi_spec() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_openapi_covers_routes() {
+        let openapi = super::openapi();
+
+        for path in [
+            "/federations",
+            "/federations/activity",
+            "/federations/{federation_id}/fees",
+            "/federations/{federation_id}/backfill/status",
+            "/config/{invite}/meta",
+            "/config/{invite}/check",
+            "/nostr/federations",
+            "/nostr/events",
+            "/nostr/moderation",
+            "/versions",
+        ] {
+            assert!(openapi.paths.paths.contains_key(path), "{path} missing");
+        }
+        let components = openapi.components.expect("components exist");
+        assert!(components.schemas.contains_key("FederationSummary"));
+        for scheme in ["admin_auth", "api_key"] {
+            assert!(
+                components.security_schemes.contains_key(scheme),
+                "{scheme} missing"
+            );
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use fedimint_core::config::FederationId;
+use fedimint_core::encoding::Encodable;
+use serde::Serialize;
+use tokio_postgres::types::ToSql;
+
+use crate::util::row_to_json;
+use crate::AppState;
+
+/// Maximum time a named query may run before being canceled by postgres
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Analytics queries that can be run by anyone. Only the parameters are user
+/// controlled, so these can't be abused to run arbitrary SQL.
+const QUERY_TEMPLATES: &[QueryTemplate] = &[
+    QueryTemplate {
+        name: "activity_by_kind",
+        description: "Number and volume of transaction inputs and outputs per module kind over the last `days` days",
+        params: &[
+            ("federation_id", QueryParamType::FederationId),
+            ("days", QueryParamType::Integer),
+        ],
+        // language=postgresql
+        sql: "WITH recent_transactions AS (SELECT t.federation_id, t.txid
+                                           FROM transactions t
+                                                    JOIN session_times st
+                                                         ON t.federation_id = st.federation_id AND
+                                                            t.session_index = st.session_index
+                                           WHERE t.federation_id = $1
+                                             AND st.estimated_session_timestamp > NOW() - INTERVAL '1 day' * $2::bigint),
+                   items AS (SELECT ti.kind, 'input' AS direction, ti.amount_msat
+                             FROM transaction_inputs ti
+                                      JOIN recent_transactions rt
+                                           ON ti.federation_id = rt.federation_id AND ti.txid = rt.txid
+                             UNION ALL
+                             SELECT tout.kind, 'output' AS direction, tout.amount_msat
+                             FROM transaction_outputs tout
+                                      JOIN recent_transactions rt
+                                           ON tout.federation_id = rt.federation_id AND tout.txid = rt.txid)
+              SELECT kind,
+                     direction,
+                     COUNT(*)::bigint                         AS count,
+                     COALESCE(SUM(amount_msat), 0)::bigint    AS amount_msat
+              FROM items
+              GROUP BY kind, direction
+              ORDER BY kind, direction",
+    },
+    QueryTemplate {
+        name: "daily_transactions",
+        description: "Number of transactions per day over the last `days` days",
+        params: &[
+            ("federation_id", QueryParamType::FederationId),
+            ("days", QueryParamType::Integer),
+        ],
+        // language=postgresql
+        sql: "SELECT DATE(st.estimated_session_timestamp) AS date,
+                     COUNT(t.txid)::bigint                 AS transactions
+              FROM transactions t
+                       JOIN session_times st
+                            ON t.federation_id = st.federation_id AND t.session_index = st.session_index
+              WHERE t.federation_id = $1
+                AND st.estimated_session_timestamp > NOW() - INTERVAL '1 day' * $2::bigint
+              GROUP BY date
+              ORDER BY date",
+    },
+    QueryTemplate {
+        name: "ln_contracts_by_type",
+        description: "Number of incoming and outgoing lightning contracts",
+        params: &[("federation_id", QueryParamType::FederationId)],
+        // language=postgresql
+        sql: "SELECT type, COUNT(*)::bigint AS count
+              FROM ln_contracts
+              WHERE federation_id = $1
+              GROUP BY type
+              ORDER BY type",
+    },
+    QueryTemplate {
+        name: "largest_peg_ins",
+        description: "The `limit` largest on-chain deposits into the federation",
+        params: &[
+            ("federation_id", QueryParamType::FederationId),
+            ("limit", QueryParamType::Integer),
+        ],
+        // language=postgresql
+        sql: "SELECT on_chain_txid, on_chain_vout, amount_msat
+              FROM wallet_peg_ins
+              WHERE federation_id = $1
+              ORDER BY amount_msat DESC
+              LIMIT LEAST($2::bigint, 1000)",
+    },
+];
+
+pub fn get_query_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_queries))
+        .route("/:name", get(run_query))
+}
+
+struct QueryTemplate {
+    name: &'static str,
+    description: &'static str,
+    params: &'static [(&'static str, QueryParamType)],
+    sql: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryParamType {
+    FederationId,
+    Integer,
+}
+
+enum QueryParam {
+    Bytes(Vec<u8>),
+    Integer(i64),
+}
+
+impl QueryParamType {
+    fn parse(self, value: &str) -> anyhow::Result<QueryParam> {
+        Ok(match self {
+            QueryParamType::FederationId => QueryParam::Bytes(
+                FederationId::from_str(value)
+                    .context("Invalid federation id")?
+                    .consensus_encode_to_vec(),
+            ),
+            QueryParamType::Integer => {
+                QueryParam::Integer(value.parse().context("Invalid integer")?)
+            }
+        })
+    }
+}
+
+impl QueryParam {
+    fn as_sql(&self) -> &(dyn ToSql + Sync) {
+        match self {
+            QueryParam::Bytes(bytes) => bytes,
+            QueryParam::Integer(integer) => integer,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct QueryDescription {
+    name: &'static str,
+    description: &'static str,
+    params: BTreeMap<&'static str, QueryParamType>,
+}
+
+async fn list_queries() -> Json<Vec<QueryDescription>> {
+    QUERY_TEMPLATES
+        .iter()
+        .map(|template| QueryDescription {
+            name: template.name,
+            description: template.description,
+            params: template.params.iter().copied().collect(),
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+async fn run_query(
+    Path(name): Path<String>,
+    Query(args): Query<BTreeMap<String, String>>,
+    State(state): State<AppState>,
+) -> crate::error::Result<Json<Vec<serde_json::Map<String, serde_json::Value>>>> {
+    let template = QUERY_TEMPLATES
+        .iter()
+        .find(|template| template.name == name)
+        .with_context(|| format!("Unknown query {name}"))?;
+
+    if let Some(unknown_arg) = args
+        .keys()
+        .find(|arg| !template.params.iter().any(|(param, _)| param == arg))
+    {
+        return Err(anyhow!("Unknown parameter {unknown_arg} for query {name}").into());
+    }
+
+    let params = template
+        .params
+        .iter()
+        .map(|(param, param_type)| {
+            let value = args
+                .get(*param)
+                .with_context(|| format!("Missing parameter {param}"))?;
+            param_type
+                .parse(value)
+                .with_context(|| format!("Invalid parameter {param}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let sql_params = params.iter().map(QueryParam::as_sql).collect::<Vec<_>>();
+
+    let rows = state
+        .federation_observer
+        .read_only_query(template.sql, &sql_params, QUERY_TIMEOUT)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(row_to_json)
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into())
+}
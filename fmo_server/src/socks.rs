@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use async_trait::async_trait;
+use fedimint_api_client::api::{
+    DynGlobalApi, FederationApiExt, GlobalFederationApiWithCache, JsonRpcClient,
+    JsonRpcClientError, WsFederationApi,
+};
+use fedimint_api_client::download_from_invite_code;
+use fedimint_core::config::{ClientConfig, FederationId, PeerUrl};
+use fedimint_core::endpoint_constants::CLIENT_CONFIG_ENDPOINT;
+use fedimint_core::invite_code::InviteCode;
+use fedimint_core::module::ApiRequestErased;
+use fedimint_core::util::SafeUrl;
+use fedimint_core::PeerId;
+use jsonrpsee_core::client::{BatchResponse, ClientT};
+use jsonrpsee_core::params::BatchRequestBuilder;
+use jsonrpsee_core::traits::ToRpcParams;
+use jsonrpsee_ws_client::{WsClient, WsClientBuilder};
+use serde::de::DeserializeOwned;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig as TlsClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+/// Address of the SOCKS5 proxy all connections to guardians go through, set
+/// once at startup. The API client connects from a static constructor, so it
+/// can't be passed around like the HTTP clients.
+static SOCKS_PROXY: OnceLock<Option<String>> = OnceLock::new();
+
+/// Sets the SOCKS5 proxy from the `--socks-proxy` CLI option or, if not given,
+/// `FO_SOCKS_PROXY`, e.g. `127.0.0.1:9050` for a local Tor daemon
+pub fn init_socks_proxy(cli_proxy: Option<String>) -> anyhow::Result<()> {
+    let proxy = cli_proxy.or_else(|| dotenv::var("FO_SOCKS_PROXY").ok());
+    if let Some(proxy) = &proxy {
+        ensure!(!proxy.contains("://"), "SOCKS proxy has to be host:port");
+    }
+    SOCKS_PROXY
+        .set(proxy)
+        .map_err(|_| anyhow!("SOCKS proxy was already set"))
+}
+
+pub fn socks_proxy() -> Option<&'static str> {
+    SOCKS_PROXY.get().and_then(Option::as_deref)
+}
+
+/// Resolved address of the SOCKS5 proxy for clients that only take a socket
+/// address, like the nostr relay pool
+pub async fn socks_proxy_addr() -> anyhow::Result<Option<SocketAddr>> {
+    let Some(proxy) = socks_proxy() else {
+        return Ok(None);
+    };
+    let addr = tokio::net::lookup_host(proxy)
+        .await
+        .with_context(|| format!("Failed to resolve SOCKS proxy {proxy}"))?
+        .next()
+        .with_context(|| format!("SOCKS proxy {proxy} has no address"))?;
+    Ok(Some(addr))
+}
+
+/// Removes `--socks-proxy <address>` from the command line arguments and
+/// returns the address
+pub fn take_socks_proxy_arg(args: &mut Vec<String>) -> anyhow::Result<Option<String>> {
+    let Some(idx) = args.iter().position(|arg| arg == "--socks-proxy") else {
+        return Ok(None);
+    };
+    ensure!(idx + 1 < args.len(), "--socks-proxy requires an address");
+    let proxy = args.remove(idx + 1);
+    args.remove(idx);
+    Ok(Some(proxy))
+}
+
+/// API client for a federation's guardians, connecting through the SOCKS
+/// proxy if one is configured
+pub fn federation_api(api_endpoints: &BTreeMap<PeerId, PeerUrl>) -> DynGlobalApi {
+    let peers = api_endpoints
+        .iter()
+        .map(|(&peer_id, peer_url)| (peer_id, peer_url.url.clone()));
+    api_from_peers(peers)
+}
+
+/// Like [`federation_api`], but for the peers of an invite code
+pub fn api_from_peers(peers: impl IntoIterator<Item = (PeerId, SafeUrl)>) -> DynGlobalApi {
+    match socks_proxy() {
+        Some(_) => GlobalFederationApiWithCache::new(
+            WsFederationApi::<SocksWsClient>::new_with_client(peers, None, &None),
+        )
+        .into(),
+        None => DynGlobalApi::from_endpoints(peers, &None),
+    }
+}
+
+/// Downloads the client config of the federation behind `invite`
+pub async fn download_config(invite: &InviteCode) -> anyhow::Result<ClientConfig> {
+    if socks_proxy().is_none() {
+        return download_from_invite_code(invite).await;
+    }
+
+    let config = api_from_peers(invite.peers())
+        .request_current_consensus::<ClientConfig>(
+            CLIENT_CONFIG_ENDPOINT.to_owned(),
+            ApiRequestErased::default(),
+        )
+        .await?;
+    ensure_federation_id(&config, invite.federation_id())?;
+    Ok(config)
+}
+
+fn ensure_federation_id(config: &ClientConfig, federation_id: FederationId) -> anyhow::Result<()> {
+    ensure!(
+        config.calculate_federation_id() == federation_id,
+        "Guardians returned the config of another federation"
+    );
+    Ok(())
+}
+
+/// Opens a TCP connection to `host`, through the SOCKS proxy if one is
+/// configured. The proxy resolves the host name, which is required for onion
+/// addresses.
+pub async fn connect_tcp(host: &str, port: u16) -> anyhow::Result<TcpStream> {
+    match socks_proxy() {
+        Some(proxy) => Ok(Socks5Stream::connect(proxy, (host, port))
+            .await
+            .with_context(|| format!("Connecting to {host}:{port} through {proxy} failed"))?
+            .into_inner()),
+        None => TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("Connecting to {host}:{port} failed")),
+    }
+}
+
+/// Websocket JSON-RPC client whose connections go through the SOCKS proxy
+#[derive(Debug)]
+struct SocksWsClient(WsClient);
+
+impl SocksWsClient {
+    async fn connect_via_proxy(url: &SafeUrl) -> anyhow::Result<WsClient> {
+        let host = url.host_str().context("API URL has no host")?.to_owned();
+        let port = url.port_or_known_default().context("API URL has no port")?;
+        let stream = connect_tcp(&host, port).await?;
+        let builder = WsClientBuilder::default().max_concurrent_requests(u16::MAX as usize);
+
+        let client = match url.scheme() {
+            "wss" => {
+                let mut roots = RootCertStore::empty();
+                roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+                let tls_config = TlsClientConfig::builder()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth();
+                let server_name = ServerName::try_from(host).context("Invalid server name")?;
+                let stream = TlsConnector::from(Arc::new(tls_config))
+                    .connect(server_name, stream)
+                    .await
+                    .context("TLS handshake failed")?;
+                builder
+                    .build_with_stream(url.as_str(), stream.compat())
+                    .await?
+            }
+            "ws" => {
+                builder
+                    .build_with_stream(url.as_str(), stream.compat())
+                    .await?
+            }
+            scheme => bail!("Unsupported API URL scheme {scheme}"),
+        };
+        Ok(client)
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for SocksWsClient {
+    /// The observer doesn't use API secrets, so `_api_secret` is ignored
+    async fn connect(
+        url: &SafeUrl,
+        _api_secret: Option<String>,
+    ) -> Result<Self, JsonRpcClientError> {
+        Self::connect_via_proxy(url)
+            .await
+            .map(SocksWsClient)
+            .map_err(JsonRpcClientError::Transport)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.0.is_connected()
+    }
+}
+
+#[async_trait]
+impl ClientT for SocksWsClient {
+    async fn notification<Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<(), JsonRpcClientError>
+    where
+        Params: ToRpcParams + Send,
+    {
+        self.0.notification(method, params).await
+    }
+
+    async fn request<R, Params>(
+        &self,
+        method: &str,
+        params: Params,
+    ) -> Result<R, JsonRpcClientError>
+    where
+        R: DeserializeOwned,
+        Params: ToRpcParams + Send,
+    {
+        self.0.request(method, params).await
+    }
+
+    async fn batch_request<'a, R>(
+        &self,
+        batch: BatchRequestBuilder<'a>,
+    ) -> Result<BatchResponse<'a, R>, JsonRpcClientError>
+    where
+        R: DeserializeOwned + std::fmt::Debug + 'a,
+    {
+        self.0.batch_request(batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::take_socks_proxy_arg;
+
+    #[test]
+    fn test_take_socks_proxy_arg() {
+        let mut args = vec![
+            "--socks-proxy".to_owned(),
+            "127.0.0.1:9050".to_owned(),
+            "dump-sessions".to_owned(),
+        ];
+        assert_eq!(
+            take_socks_proxy_arg(&mut args).unwrap().as_deref(),
+            Some("127.0.0.1:9050")
+        );
+        assert_eq!(args, vec!["dump-sessions".to_owned()]);
+
+        let mut args = vec!["restore".to_owned()];
+        assert_eq!(take_socks_proxy_arg(&mut args).unwrap(), None);
+        assert_eq!(args, vec!["restore".to_owned()]);
+
+        assert!(take_socks_proxy_arg(&mut vec!["--socks-proxy".to_owned()]).is_err());
+    }
+}
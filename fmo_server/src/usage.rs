@@ -0,0 +1,266 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashSet};
+use std::hash::BuildHasher;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{ConnectInfo, MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use fedimint_core::config::FederationId;
+use fmo_api_types::{FederationUsage, RouteUsage, UsageStats};
+
+/// Clients are identified by a keyed hash of their IP address, the key is
+/// replaced after this period so identifiers can't be linked across periods
+const CLIENT_KEY_ROTATION: chrono::Duration = chrono::Duration::days(1);
+
+/// Counts requests per route and federation. Only keyed hashes of client IP
+/// addresses are kept and only for the current rotation period.
+#[derive(Debug, Clone)]
+pub struct UsageTracker {
+    counters: Arc<Mutex<UsageCounters>>,
+    /// Take the client address from `X-Forwarded-For`, only safe behind a
+    /// reverse proxy that sets it
+    trust_forwarded_for: bool,
+}
+
+#[derive(Debug)]
+struct UsageCounters {
+    since: DateTime<Utc>,
+    period_start: DateTime<Utc>,
+    client_key: RandomState,
+    routes: BTreeMap<(String, String), Counter>,
+    federations: BTreeMap<FederationId, Counter>,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    requests: u64,
+    clients: HashSet<u64>,
+}
+
+impl Counter {
+    fn record(&mut self, client: Option<u64>) {
+        self.requests += 1;
+        self.clients.extend(client);
+    }
+}
+
+impl UsageTracker {
+    /// Reads `FO_USAGE_TRUST_FORWARDED_FOR` to decide where client addresses
+    /// come from
+    pub fn from_env() -> UsageTracker {
+        let trust_forwarded_for = dotenv::var("FO_USAGE_TRUST_FORWARDED_FOR")
+            .is_ok_and(|value| value == "true" || value == "1");
+
+        UsageTracker {
+            counters: Arc::new(Mutex::new(UsageCounters::new(Utc::now()))),
+            trust_forwarded_for,
+        }
+    }
+
+    pub fn stats(&self) -> UsageStats {
+        self.counters.lock().expect("Lock poisoned").stats()
+    }
+
+    /// Requests per federation since the server started
+    pub fn federation_requests(&self) -> BTreeMap<FederationId, u64> {
+        self.counters
+            .lock()
+            .expect("Lock poisoned")
+            .federations
+            .iter()
+            .map(|(federation_id, counter)| (*federation_id, counter.requests))
+            .collect()
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded_for = request
+                .headers()
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|ip| IpAddr::from_str(ip.trim()).ok());
+            if forwarded_for.is_some() {
+                return forwarded_for;
+            }
+        }
+
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+impl UsageCounters {
+    fn new(now: DateTime<Utc>) -> UsageCounters {
+        UsageCounters {
+            since: now,
+            period_start: now,
+            client_key: RandomState::new(),
+            routes: BTreeMap::new(),
+            federations: BTreeMap::new(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        now: DateTime<Utc>,
+        method: &str,
+        route: &str,
+        federation_id: Option<FederationId>,
+        client_ip: Option<IpAddr>,
+    ) {
+        if now - self.period_start >= CLIENT_KEY_ROTATION {
+            self.client_key = RandomState::new();
+            self.period_start = now;
+            for counter in self
+                .routes
+                .values_mut()
+                .chain(self.federations.values_mut())
+            {
+                counter.clients.clear();
+            }
+        }
+
+        let client = client_ip.map(|ip| self.client_key.hash_one(ip));
+        self.routes
+            .entry((method.to_owned(), route.to_owned()))
+            .or_default()
+            .record(client);
+        if let Some(federation_id) = federation_id {
+            self.federations
+                .entry(federation_id)
+                .or_default()
+                .record(client);
+        }
+    }
+
+    fn stats(&self) -> UsageStats {
+        let mut routes = self
+            .routes
+            .iter()
+            .map(|((method, route), counter)| RouteUsage {
+                method: method.clone(),
+                route: route.clone(),
+                requests: counter.requests,
+                unique_clients: counter.clients.len() as u64,
+            })
+            .collect::<Vec<_>>();
+        routes.sort_by(|a, b| b.requests.cmp(&a.requests));
+
+        let mut federations = self
+            .federations
+            .iter()
+            .map(|(federation_id, counter)| FederationUsage {
+                federation_id: *federation_id,
+                requests: counter.requests,
+                unique_clients: counter.clients.len() as u64,
+            })
+            .collect::<Vec<_>>();
+        federations.sort_by(|a, b| b.requests.cmp(&a.requests));
+
+        UsageStats {
+            since: self.since,
+            period_start: self.period_start,
+            routes,
+            federations,
+        }
+    }
+}
+
+/// Middleware recording every request that matched a route
+pub async fn track_usage(
+    State(tracker): State<UsageTracker>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(matched_path) = matched_path {
+        let federation_id = federation_id_from_path(matched_path.as_str(), request.uri().path());
+        let client_ip = tracker.client_ip(&request);
+        tracker.counters.lock().expect("Lock poisoned").record(
+            Utc::now(),
+            request.method().as_str(),
+            matched_path.as_str(),
+            federation_id,
+            client_ip,
+        );
+    }
+
+    next.run(request).await
+}
+
+/// Extracts the `:federation_id` segment of `path` if the route has one
+fn federation_id_from_path(route: &str, path: &str) -> Option<FederationId> {
+    route
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(route_segment, _)| *route_segment == ":federation_id")
+        .and_then(|(_, segment)| FederationId::from_str(segment).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use chrono::{Duration, Utc};
+    use fedimint_core::config::FederationId;
+
+    use super::{federation_id_from_path, UsageCounters};
+
+    #[test]
+    fn test_federation_id_from_path() {
+        let federation_id = FederationId::dummy();
+        assert_eq!(
+            federation_id_from_path(
+                "/federations/:federation_id/health",
+                &format!("/federations/{federation_id}/health")
+            ),
+            Some(federation_id)
+        );
+        assert_eq!(
+            federation_id_from_path("/federations/:federation_id", "/federations/not-an-id"),
+            None
+        );
+        assert_eq!(
+            federation_id_from_path("/federations/totals", "/federations/totals"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_usage_counters() {
+        let start = Utc::now();
+        let federation_id = FederationId::dummy();
+        let alice = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let bob = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let route = "/federations/:federation_id";
+
+        let mut counters = UsageCounters::new(start);
+        counters.record(start, "GET", route, Some(federation_id), alice);
+        counters.record(start, "GET", route, Some(federation_id), alice);
+        counters.record(start, "GET", route, Some(federation_id), bob);
+        counters.record(start, "GET", "/federations", None, None);
+
+        let stats = counters.stats();
+        assert_eq!(stats.routes[0].route, route);
+        assert_eq!(stats.routes[0].requests, 3);
+        assert_eq!(stats.routes[0].unique_clients, 2);
+        assert_eq!(stats.routes[1].unique_clients, 0);
+        assert_eq!(stats.federations[0].requests, 3);
+
+        // Clients are forgotten once the key rotates, requests are kept
+        let next_day = start + Duration::days(1);
+        counters.record(next_day, "GET", route, Some(federation_id), alice);
+        let stats = counters.stats();
+        assert_eq!(stats.period_start, next_day);
+        assert_eq!(stats.routes[0].requests, 4);
+        assert_eq!(stats.routes[0].unique_clients, 1);
+        assert_eq!(stats.federations[0].unique_clients, 1);
+    }
+}
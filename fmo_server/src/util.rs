@@ -11,6 +11,19 @@ use hex::ToHex;
 use postgres_from_row::FromRow;
 use serde_json::json;
 
+/// Bitcoin network of the federation's wallet module, `None` if it has none
+pub fn config_network(cfg: &ClientConfig) -> anyhow::Result<Option<bitcoin::Network>> {
+    let Some(network) = config_to_json(cfg.clone())?
+        .modules
+        .into_values()
+        .find_map(|module| Some(module.value().get("network")?.as_str()?.to_owned()))
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(network.parse()?))
+}
+
 pub fn config_to_json(cfg: ClientConfig) -> anyhow::Result<JsonClientConfig> {
     let decoders = get_decoders(
         cfg.modules
@@ -133,3 +146,48 @@ where
         .map(T::try_from_row)
         .collect::<Result<_, _>>()?)
 }
+
+/// Converts a row of arbitrary shape into a JSON object keyed by column name.
+/// Byte arrays are hex-encoded, types without a sensible JSON representation
+/// result in an error.
+pub fn row_to_json(
+    row: &tokio_postgres::Row,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    use tokio_postgres::types::Type;
+
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let value = match *column.type_() {
+                Type::BOOL => json!(row.try_get::<_, Option<bool>>(idx)?),
+                Type::INT2 => json!(row.try_get::<_, Option<i16>>(idx)?),
+                Type::INT4 => json!(row.try_get::<_, Option<i32>>(idx)?),
+                Type::INT8 => json!(row.try_get::<_, Option<i64>>(idx)?),
+                Type::FLOAT4 => json!(row.try_get::<_, Option<f32>>(idx)?),
+                Type::FLOAT8 => json!(row.try_get::<_, Option<f64>>(idx)?),
+                Type::TEXT | Type::VARCHAR | Type::NAME => {
+                    json!(row.try_get::<_, Option<String>>(idx)?)
+                }
+                Type::BYTEA => json!(row
+                    .try_get::<_, Option<Vec<u8>>>(idx)?
+                    .map(|bytes| bytes.encode_hex::<String>())),
+                Type::DATE => json!(row.try_get::<_, Option<chrono::NaiveDate>>(idx)?),
+                Type::TIMESTAMP => json!(row.try_get::<_, Option<chrono::NaiveDateTime>>(idx)?),
+                Type::TIMESTAMPTZ => {
+                    json!(row.try_get::<_, Option<chrono::DateTime<chrono::Utc>>>(idx)?)
+                }
+                Type::JSON | Type::JSONB => row
+                    .try_get::<_, Option<serde_json::Value>>(idx)?
+                    .unwrap_or_default(),
+                ref other => anyhow::bail!(
+                    "Column {} has unsupported type {}, cast it in the query",
+                    column.name(),
+                    other
+                ),
+            };
+
+            Ok((column.name().to_owned(), value))
+        })
+        .collect()
+}